@@ -0,0 +1,149 @@
+//! Actuation_Leader.rs - Lease-based leader election for the actuation role (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::time::Duration;
+
+use crate::decision::now_ms;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lease {
+    pub holder: String,
+    pub term: u64,
+    pub expires_ms: u64,
+}
+
+// Compare-and-set lease store (etcd, Consul, a SQL row, the config Raft log).
+pub trait LeaseStore {
+    fn current(&mut self) -> Option<Lease>;
+    // Succeeds only if `expected` still matches the stored lease.
+    fn compare_and_swap(&mut self, expected: Option<&Lease>, new: Lease) -> bool;
+}
+
+#[derive(Clone, Debug)]
+pub struct LeadershipChange {
+    pub term: u64,
+    pub from: Option<String>,
+    pub to: String,
+    pub at_ms: u64,
+}
+
+pub struct ActuationElector {
+    node_id: String,
+    ttl: Duration,
+    // Local safety margin so a paused node stops actuating before others may take over.
+    margin: Duration,
+    held: Option<Lease>,
+    audit: Vec<LeadershipChange>,
+}
+
+impl ActuationElector {
+    pub fn new(node_id: &str, ttl: Duration) -> Self {
+        ActuationElector { node_id: node_id.to_string(), ttl, margin: ttl / 4, held: None, audit: Vec::new() }
+    }
+
+    // Called every cycle on every node; all nodes keep evaluating regardless of the outcome.
+    pub fn tick<S: LeaseStore>(&mut self, store: &mut S) {
+        let now = now_ms();
+        let current = store.current();
+        let ours = matches!(&current, Some(l) if l.holder == self.node_id);
+        let expired = current.as_ref().is_none_or(|l| l.expires_ms <= now);
+        if !ours && !expired {
+            self.held = None;
+            return;
+        }
+        let term = match &current {
+            Some(l) if ours => l.term,
+            Some(l) => l.term + 1,
+            None => 1,
+        };
+        let lease = Lease { holder: self.node_id.clone(), term, expires_ms: now + self.ttl.as_millis() as u64 };
+        if store.compare_and_swap(current.as_ref(), lease.clone()) {
+            if !ours {
+                let change = LeadershipChange {
+                    term,
+                    from: current.map(|l| l.holder),
+                    to: self.node_id.clone(),
+                    at_ms: now,
+                };
+                println!("Leader: term {} {:?} -> {}", change.term, change.from, change.to);
+                self.audit.push(change);
+            }
+            self.held = Some(lease);
+        } else {
+            self.held = None;
+        }
+    }
+
+    pub fn is_leader(&self) -> bool {
+        let margin = self.margin.as_millis() as u64;
+        matches!(&self.held, Some(l) if now_ms() + margin < l.expires_ms)
+    }
+
+    // Fencing token to attach to actuator commands so a deposed leader's writes are refused.
+    pub fn fencing_term(&self) -> Option<u64> {
+        if self.is_leader() { self.held.as_ref().map(|l| l.term) } else { None }
+    }
+
+    pub fn audit(&self) -> &[LeadershipChange] {
+        &self.audit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Store(Option<Lease>);
+
+    impl LeaseStore for Store {
+        fn current(&mut self) -> Option<Lease> {
+            self.0.clone()
+        }
+
+        fn compare_and_swap(&mut self, expected: Option<&Lease>, new: Lease) -> bool {
+            if self.0.as_ref() != expected {
+                return false;
+            }
+            self.0 = Some(new);
+            true
+        }
+    }
+
+    #[test]
+    fn one_leader_at_a_time_and_renewal_keeps_the_term() {
+        let mut store = Store::default();
+        let (mut a, mut b) = (ActuationElector::new("a", Duration::from_secs(60)), ActuationElector::new("b", Duration::from_secs(60)));
+        a.tick(&mut store);
+        b.tick(&mut store);
+        assert!(a.is_leader() && !b.is_leader());
+        a.tick(&mut store);
+        assert_eq!((a.fencing_term(), b.fencing_term()), (Some(1), None));
+        assert_eq!(a.audit().len(), 1);
+    }
+
+    #[test]
+    fn an_expired_lease_is_taken_over_in_the_next_term_and_audited() {
+        let mut store = Store(Some(Lease { holder: "a".into(), term: 4, expires_ms: now_ms() - 1 }));
+        let mut b = ActuationElector::new("b", Duration::from_secs(60));
+        b.tick(&mut store);
+        assert_eq!(b.fencing_term(), Some(5));
+        let change = &b.audit()[0];
+        assert_eq!((change.term, change.from.as_deref(), change.to.as_str()), (5, Some("a"), "b"));
+    }
+
+    #[test]
+    fn a_lost_compare_and_swap_or_the_margin_stops_actuation() {
+        let mut store = Store::default();
+        let mut a = ActuationElector::new("a", Duration::from_secs(60));
+        a.tick(&mut store);
+        store.0 = Some(Lease { holder: "b".into(), term: 2, expires_ms: now_ms() + 60_000 });
+        a.tick(&mut store);
+        assert_eq!(a.fencing_term(), None);
+        // Inside the last quarter of its ttl a holder no longer actuates.
+        let mut short = ActuationElector::new("c", Duration::from_millis(400));
+        short.tick(&mut Store::default());
+        assert!(short.is_leader());
+        std::thread::sleep(Duration::from_millis(320));
+        assert!(!short.is_leader());
+    }
+}
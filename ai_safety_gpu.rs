@@ -1,65 +1,393 @@
 //! AI_Safety_GPU.rs - NIST AI RMF / EU AI Act GPU shim (forbid unsafe)
 #![forbid(unsafe_code)]
+use std::path::Path;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-const HARMONY_THRESHOLD: f64 = 0.9995;
-const MIN_SCORE: f64 = 1e-12;
 
-pub struct AISafetyContext {
-    pub scores: Vec<f64>,
-    pub weights: Vec<f64>,
+use ed25519_dalek::VerifyingKey;
+
+mod attestation_store;
+mod catalog;
+mod clock_sync;
+mod core;
+mod crypto_policy;
+mod decision;
+mod decision_kernel;
+mod decision_stream;
+mod diagnostics;
+mod domain_dependencies;
+mod explain;
+mod health_probes;
+mod ingest;
+mod killswitch;
+mod plugin;
+mod rbac;
+mod report;
+mod report_sink;
+mod sealed_config;
+mod units;
+use crate::core::anomaly::Anomalies;
+use crate::core::checks::{CheckRegistry, FnCheck};
+use crate::core::fusion::{Fusion, FusionMode, Likelihood};
+use crate::core::harmony::{Conditions, Evaluation, Severity};
+use crate::core::monitor::HarmonyMonitor;
+use crate::core::self_harmony::{SelfHarmony, DEFAULT_SELF_HEALTH_LIMITS};
+use crate::core::source::{FnSource, SourceError, SourceSet};
+use crate::core::trend::{MuTrend, Trends};
+use crate::core::validity::Validity;
+use attestation_store::AttestationStore;
+use clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+use decision::{lease_for, now_ms, Decision, DecisionRecord};
+use decision_stream::{RecordSigner, RecordVerifier};
+use diagnostics::Diagnostics;
+use domain_dependencies::{Dependency, DependencyGraph};
+use explain::Channel;
+use health_probes::ProbeState;
+use killswitch::{KillSwitch, KillSwitchCheck};
+use plugin::Domain;
+use report::{EvaluationReport, HaltCode, HaltReason};
+use report_sink::ReportSinks;
+
+
+// Alignment audits, red-team reports and the regulatory sandbox approval are signed documents
+// dropped into HARMONY_ATTESTATION_DIR, verified against HARMONY_ATTESTATION_KEYS (one
+// `<id> <hex ed25519 public key>` per line); their validity windows live in the condition
+// framework. Without the keys file every kind stays never-attested.
+// Re-read on every rescan, so a revoked signer stops counting without a restart. An unreadable
+// key file trusts no one, and every attestation condition fails.
+fn attestation_keys() -> Vec<(String, VerifyingKey)> {
+    let keys_path = std::env::var("HARMONY_ATTESTATION_KEYS").unwrap_or_else(|_| "/etc/harmony/attestation_keys".into());
+    match std::fs::read_to_string(&keys_path).map_err(|e| e.to_string()).and_then(|t| sealed_config::parse_trusted_keys(&t)) {
+        Ok(keys) => keys,
+        Err(e) => {
+            eprintln!("AI: attestation keys {}: {}", keys_path, e);
+            Vec::new()
+        }
+    }
+}
+
+fn attestation_store() -> AttestationStore {
+    AttestationStore::new("ai_safety", attestation_keys(), now_ms)
+        .kind("alignment_audit", Severity::Critical, Validity::days(90, 14), &[])
+        .kind("red_team_report", Severity::Major, Validity::days(30, 7), &[])
+        .kind("regulatory_sandbox_approved", Severity::Critical, Validity::days(365, 0), &[])
 }
 
-impl AISafetyContext {
-    pub fn calculate_mu(&self) -> f64 {
-        let mut log_sum = 0.0;
-        for (w, s) in self.weights.iter().zip(self.scores.iter()) {
-            let s_clipped = s.clamp(MIN_SCORE, 1.0);
-            log_sum += w * s_clipped.ln();
+// Upstream decisions are verified against HARMONY_DECISION_PEERS (one `<node_id> <hex ed25519
+// public key>` per line) before they count; without it every upstream reads as silent, which
+// blocks like a HALT.
+fn record_verifier() -> RecordVerifier {
+    let peers_path = std::env::var("HARMONY_DECISION_PEERS").unwrap_or_else(|_| "/etc/harmony/decision_peers".into());
+    let trusted = match std::fs::read_to_string(&peers_path).map_err(|e| e.to_string()).and_then(|t| sealed_config::parse_trusted_keys(&t)) {
+        Ok(keys) => keys,
+        Err(e) => {
+            eprintln!("AI: decision peers {}: {}", peers_path, e);
+            Vec::new()
         }
-        log_sum.exp()
+    };
+    RecordVerifier::new(trusted, UPSTREAM_RECORD_MAX_AGE)
+}
+
+// Upstreams decide at 1-10 Hz; a record older than this is a replay or a stalled link.
+const UPSTREAM_RECORD_MAX_AGE: Duration = Duration::from_secs(2);
+
+// Rescan the drop directory once a minute at 10 Hz.
+const ATTESTATION_RESCAN_CYCLES: u64 = 600;
+
+const KILLSWITCH_INTERVAL: Duration = Duration::from_millis(500);
+// Three missed acks and deployment stops.
+const KILLSWITCH_MAX_AGE: Duration = Duration::from_millis(1500);
+
+fn ch_checks(killswitch: Arc<KillSwitch>) -> CheckRegistry {
+    let mut checks = CheckRegistry::new();
+    checks
+        .register(Box::new(FnCheck::new("adversarial_score_below_eps", adversarial_score_below_eps)))
+        .register(Box::new(KillSwitchCheck(killswitch)));
+    checks
+}
+
+#[allow(non_camel_case_types)]
+pub enum DeployDecision { DEPLOY_GO, DEPLOY_CAUTION, DEPLOY_HALT }
+
+pub async fn evaluate_ai_harmony(eval: &Evaluation) -> DeployDecision {
+    match eval.decision {
+        Decision::GO => DeployDecision::DEPLOY_GO,
+        Decision::CAUTION => DeployDecision::DEPLOY_CAUTION,
+        Decision::HALT => {
+            log_harmony_fault(eval.mu, eval.ch);
+            DeployDecision::DEPLOY_HALT
+        }
+    }
+}
+
+// One source per weighted channel, in weight order; swap in real telemetry backends here.
+fn score_sources() -> SourceSet {
+    let mut sources = SourceSet::new(Duration::from_millis(50));
+    sources
+        .register(Box::new(FnSource::new("weight_drift_coherence", query_weight_drift_coherence)))
+        .register(Box::new(FnSource::new("prompt_alignment_stability", query_prompt_alignment_stability)))
+        .register(Box::new(FnSource::new("explainability_confidence", query_explainability_confidence)))
+        .register(Box::new(FnSource::new("guardrail_trigger_rate", query_guardrail_trigger_rate)))
+        .register(Box::new(FnSource::new("output_entropy_stability", query_output_entropy_stability)));
+    sources
+}
+
+// Per channel, in weight order: how each score reads from a healthy model and from a degraded
+// one. Guardrail triggers come from a detector, so they count as pass/fail evidence.
+fn fusion() -> Fusion {
+    Fusion::new(vec![
+        Likelihood::Beta { healthy: (200.0, 1.0), faulted: (20.0, 2.0) },
+        Likelihood::Beta { healthy: (200.0, 1.0), faulted: (20.0, 2.0) },
+        Likelihood::Gaussian { healthy: 0.98, faulted: 0.90, sd: 0.02 },
+        Likelihood::Binary { pass_at: 0.99, detection: 0.90, false_alarm: 0.01 },
+        Likelihood::Beta { healthy: (100.0, 1.0), faulted: (10.0, 1.0) },
+    ])
+    .and_then(|f| f.thresholds(0.999, 0.99))
+    .expect("fusion likelihoods")
+}
+
+// An hour of reports at 10 Hz per sink before the oldest are dropped.
+const REPORT_SPOOL_CAPACITY: usize = 36_000;
+// chrony samples go stale after 10 s; refresh every 5 s at 10 Hz.
+const CLOCK_REFRESH_CYCLES: u64 = 50;
+// Once a minute, log any sink that is behind.
+const LAG_LOG_CYCLES: u64 = 600;
+const TICK: Duration = Duration::from_millis(100); // 10 Hz
+// Key, attestation and stop-channel setup all happen before the first cycle.
+const PROBE_STARTUP_GRACE: Duration = Duration::from_secs(30);
+
+// HARMONY_REPORT_SINKS is a comma-separated list of report_sink::parse_sink specs; stdout
+// logging continues either way. A sink that cannot be set up is fatal, like a bad probe address.
+fn report_sinks() -> ReportSinks {
+    let spool = std::env::var("HARMONY_REPORT_SPOOL").unwrap_or_else(|_| "/var/spool/harmony/ai_safety".into());
+    let mut sinks = ReportSinks::new(Path::new(&spool), REPORT_SPOOL_CAPACITY);
+    for spec in std::env::var("HARMONY_REPORT_SINKS").unwrap_or_default().split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let sink = report_sink::parse_sink(spec).expect("report sink");
+        sinks.attach(sink).expect("report sink spool");
     }
+    sinks
 }
 
-pub async fn check_ch() -> bool {
-    adversarial_score_below_eps().await    &&
-    alignment_audit_fresh().await          &&
-    kill_switch_reachable().await          &&
-    red_team_report_fresh().await          &&
-    regulatory_sandbox_approved().await
+struct AiSafety {
+    sources: SourceSet,
+    checks: CheckRegistry,
+    names: Vec<String>,
+    deps: DependencyGraph,
+    attestations: AttestationStore,
+    // Tracked for the record only; no channel is gated on its trend.
+    trends: Trends,
+    anomalies: Anomalies,
+    fusion: Fusion,
+    fusion_mode: Option<FusionMode>,
+    self_health: SelfHarmony,
+    // The monitor's own health this cycle, judged before the plant decision it caps.
+    self_eval: Option<Evaluation>,
+    // Autoheal runs on every HALT cycle; the domain counts as safe until it decides GO again.
+    healing: bool,
 }
 
-pub enum DeployDecision { DEPLOY_GO, DEPLOY_HALT }
+impl AiSafety {
+    // Conditions beyond the registry's checks.
+    fn check_ch(&self, self_eval: &Evaluation, conditions: &mut Conditions) {
+        conditions.record("upstreams_clear", Severity::Critical, self.deps.upstreams_clear("ai_safety"));
+        self.attestations.record_into(conditions);
+        self.self_health.record(self_eval, conditions);
+        self.anomalies.record(&self.names, conditions);
+    }
+}
 
-pub async fn evaluate_ai_harmony(mu: f64, ch: bool) -> DeployDecision {
-    if mu >= HARMONY_THRESHOLD && ch {
-        DeployDecision::DEPLOY_GO
-    } else {
+impl Domain for AiSafety {
+    fn name(&self) -> &str { "ai_safety" }
+    fn tick(&self) -> Duration { TICK }
+    fn weights(&self) -> Vec<f64> { vec![0.30, 0.25, 0.20, 0.15, 0.10] }
+    fn sources(&self) -> &SourceSet { &self.sources }
+    fn checks(&self) -> &CheckRegistry { &self.checks }
+
+    fn safe_state(&mut self) {
         trigger_autoheal();
-        log_harmony_fault(mu, ch);
-        DeployDecision::DEPLOY_HALT
+        self.healing = true;
+    }
+
+    fn in_safe_state(&self) -> bool {
+        self.healing
+    }
+
+    fn refine(&mut self, scores: &mut [f64], errors: &mut Vec<(usize, SourceError)>, conditions: &mut Conditions) {
+        self.trends.update(scores, errors, Instant::now());
+        self.anomalies.update(scores, errors);
+        self.self_health.sources_sampled(scores.len(), errors.len());
+        let self_eval = self.self_health.evaluate();
+        self.check_ch(&self_eval, conditions);
+        self.self_eval = Some(self_eval);
+    }
+
+    // HARMONY_FUSION=alongside|instead decides on the fused posterior as well as, or in place
+    // of, mu; unset, deployment is decided on mu alone.
+    fn decide(&mut self, scores: &[f64], errors: &[(usize, SourceError)], conditions: &Conditions, eval: Evaluation) -> Evaluation {
+        let eval = match self.fusion_mode {
+            Some(mode) => {
+                self.fusion.update(scores, errors);
+                self.fusion.evaluate(mode, &eval, conditions)
+            }
+            None => eval,
+        };
+        if eval.decision == Decision::GO {
+            self.healing = false;
+        }
+        eval
     }
 }
 
 #[tokio::main]
 async fn main() {
-    let ctx = AISafetyContext {
-        scores: vec![0.98, 0.97, 1.0, 0.96, 0.99],
-        weights: vec![0.30, 0.25, 0.20, 0.15, 0.10],
+    let deps = DependencyGraph::new(vec![
+        Dependency { dependent: "ai_safety".into(), upstream: "grid".into(), blocks_on: Decision::HALT },
+        Dependency { dependent: "ai_safety".into(), upstream: "ground_segment".into(), blocks_on: Decision::HALT },
+    ])
+    .expect("acyclic domain dependencies");
+    let probes = ProbeState::new(Duration::from_secs(2), PROBE_STARTUP_GRACE);
+    if let Err(e) = probes.run_self_test() {
+        eprintln!("AI: self-test failed, staying unready: {}", e);
+    }
+    let probe_addr = std::env::var("HARMONY_PROBE_ADDR").unwrap_or_else(|_| "0.0.0.0:8086".into());
+    health_probes::serve(&probe_addr, probes.clone()).expect("bind probe listener");
+    let mut attestations = attestation_store();
+    let mut upstream_records = record_verifier();
+    // Downstream verifiers refuse unsigned records, so without HARMONY_DECISION_KEY every consumer
+    // would read this node as silent: refuse to start instead.
+    let mut signer = RecordSigner::from_env("ai_safety").expect("decision record signing key");
+    let attestation_dir = std::env::var("HARMONY_ATTESTATION_DIR").unwrap_or_else(|_| "/var/lib/harmony/attestations".into());
+    for e in attestations.load_dir(Path::new(&attestation_dir)) {
+        eprintln!("AI: attestation refused: {}", e);
+    }
+    // No provisioned stop-channel, no deployment: refuse to start rather than run unkillable.
+    let killswitch = KillSwitch::from_env("ai_safety", KILLSWITCH_INTERVAL, KILLSWITCH_MAX_AGE).expect("kill switch stop-channel");
+    killswitch.arm();
+    killswitch::spawn_heartbeat(killswitch.clone());
+    let sources = score_sources();
+    let channel_count = sources.len();
+    let ai = AiSafety {
+        names: sources.names().iter().map(|n| n.to_string()).collect(),
+        sources,
+        checks: ch_checks(killswitch),
+        deps,
+        attestations,
+        trends: Trends::new(channel_count, Duration::from_secs(10)),
+        // A minute of each score; an unexplained shift in a safety score stops new rollouts.
+        anomalies: Anomalies::new(channel_count, 600).escalate(),
+        fusion: fusion(),
+        fusion_mode: std::env::var("HARMONY_FUSION").ok().map(|m| FusionMode::parse(&m).expect("HARMONY_FUSION")),
+        // Jitter here is the cycle's own work on top of the sleep; a full tick of it is a HALT.
+        self_health: SelfHarmony::new(DEFAULT_SELF_HEALTH_LIMITS),
+        self_eval: None,
+        healing: false,
+    };
+    let mut monitor = HarmonyMonitor::with_context(ai, |b| b.env_overrides().expect("HARMONY_THRESHOLD / HARMONY_WEIGHTS")).expect("harmony context");
+    monitor.trace_provenance();
+    let names = monitor.names().to_vec();
+    // The explanation's per-channel view, refreshed in place each cycle.
+    let mut channels: Vec<Channel> = names.iter().zip(monitor.context().weights()).map(|(name, weight)| Channel { name, score: 0.0, weight: *weight }).collect();
+    let mut reports = report_sinks();
+    let mut mu_trend = MuTrend::new(Duration::from_secs(10));
+    let mut clock = ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS);
+    // This shim runs no sealed config; hash the compiled-in context so reports still say what ran.
+    let config = format!("weights={:?} threshold={}", monitor.context().weights(), monitor.threshold());
+    let mut report = EvaluationReport {
+        record: DecisionRecord::new("ai_safety", 0, 0.0, false, Decision::HALT, clock.status()),
+        threshold: monitor.threshold(),
+        reasons: Vec::with_capacity(4),
+        config_hash: sealed_config::hex(&sealed_config::config_digest(config.as_bytes())),
+        explanation: None,
+        sensitivity: Vec::with_capacity(names.len()),
+        provenance: Vec::with_capacity(names.len()),
     };
+    // Sink depth and drops on /metrics; enabled only when a token hash is provisioned.
+    if let Some(token_sha256) = std::env::var("HARMONY_DIAG_TOKEN_SHA256").ok().and_then(|h| sealed_config::unhex(h.trim())).and_then(|b| b.try_into().ok()) {
+        let diag = Diagnostics::new(&report.config_hash);
+        let metrics = reports.metrics();
+        diag.lock().unwrap().attach_metrics(Box::new(move || metrics.metrics_text()));
+        let addr = std::env::var("HARMONY_DIAG_ADDR").unwrap_or_else(|_| "0.0.0.0:8087".into());
+        diagnostics::serve(&addr, diag, token_sha256).expect("bind diagnostics listener");
+    }
+    let mut cycle = 0u64;
     loop {
-        let scores = vec![
-            query_weight_drift_coherence().await,
-            query_prompt_alignment_stability().await,
-            query_explainability_confidence().await,
-            query_guardrail_trigger_rate().await,
-            query_output_entropy_stability().await,
-        ];
-        let mu = ctx.calculate_mu();
-        let ch = check_ch().await;
-        match evaluate_ai_harmony(mu, ch).await {
+        monitor.domain_mut().self_health.cycle_started(Instant::now(), TICK);
+        if cycle.is_multiple_of(CLOCK_REFRESH_CYCLES) {
+            clock.refresh().await;
+        }
+        cycle += 1;
+        let ai = monitor.domain_mut();
+        if cycle.is_multiple_of(ATTESTATION_RESCAN_CYCLES) {
+            for e in ai.attestations.rescan(Path::new(&attestation_dir), attestation_keys()) {
+                eprintln!("AI: attestation refused: {}", e);
+            }
+        }
+        let clock_status = clock.status();
+        ai.self_health.clock(clock_status.synced, clock_status.offset_ns);
+        ai.self_health.audit_lag(Duration::from_millis(reports.oldest_pending_ms().unwrap_or(0)));
+        while let Some((domain, record)) = recv_domain_decision().await {
+            // The signature binds the record to its node_id, not to the channel it arrived on:
+            // grid's key must not be able to speak for ground_segment.
+            match upstream_records.verify(&record) {
+                Ok(v) if v.node_id == domain => monitor.domain_mut().deps.observe(&domain, record),
+                Ok(v) => eprintln!("AI: decision from {} refused: signed by {}", domain, v.node_id),
+                Err(e) => eprintln!("AI: decision from {} refused: {:?}", domain, e),
+            }
+        }
+        let eval = monitor.cycle().await;
+        for (i, e) in monitor.source_errors() {
+            eprintln!("AI: score source {} failed: {}", names[*i], e);
+        }
+        let (ai, ctx, conditions) = (monitor.domain(), monitor.context(), monitor.conditions());
+        for name in conditions.failed(Severity::Advisory) {
+            eprintln!("AI: {} nearing expiry (grace period)", name);
+        }
+        report.record.refresh(cycle, eval.mu, eval.ch, eval.decision, clock_status);
+        report.record.set_lease(lease_for(TICK));
+        report.record.p_healthy = ai.fusion_mode.map(|_| ai.fusion.posterior());
+        report.provenance.clear();
+        report.provenance.extend_from_slice(monitor.provenance());
+        ai.trends.slopes_into(&mut report.record.slopes);
+        ai.anomalies.sigmas_into(&mut report.record.anomaly);
+        mu_trend.update(eval.mu, Instant::now());
+        report.record.eta_to_halt = mu_trend.eta(ctx.caution_threshold).map(|eta| eta.as_secs_f64());
+        report.sensitivity.clear();
+        report.sensitivity.extend_from_slice(monitor.sensitivity().values);
+        println!("AI: dmu/ds {}", monitor.sensitivity());
+        report.reasons.clear();
+        if eval.decision != Decision::GO && eval.mu < ctx.threshold && ai.fusion_mode != Some(FusionMode::Instead) {
+            report.reasons.push(HaltReason::new(HaltCode::MU_BELOW_THRESHOLD));
+        }
+        if ai.fusion_mode.is_some() && ai.fusion.decide(true) != Decision::GO {
+            let against = ai.fusion.against(&names);
+            report.reasons.push(if against.is_empty() { HaltReason::new(HaltCode::HEALTH_POSTERIOR_LOW) } else { HaltReason::about(HaltCode::HEALTH_POSTERIOR_LOW, &against) });
+        }
+        for name in conditions.failed(Severity::Critical).filter(|n| *n != "monitor_healthy") {
+            report.reasons.push(HaltReason::about(HaltCode::CH_FAILED, name));
+        }
+        if let (Some(self_eval), Some((worst, score))) = (ai.self_eval.filter(|e| e.decision != Decision::GO), ai.self_health.worst()) {
+            report.reasons.push(HaltReason::about(HaltCode::MONITOR_DEGRADED, worst));
+            eprintln!("AI: MONITOR {:?} – {} at {:.3}; plant decision capped until the monitor recovers", self_eval.decision, worst, score);
+        }
+        for (c, (score, weight)) in channels.iter_mut().zip(monitor.scores().iter().zip(ctx.weights())) {
+            c.score = *score;
+            c.weight = *weight;
+        }
+        explain::explain_into(&mut report, &channels, conditions);
+        signer.sign(&mut report.record);
+        reports.publish(&report);
+        if cycle.is_multiple_of(LAG_LOG_CYCLES) {
+            for lag in reports.lag().into_iter().filter(|l| l.pending > 0) {
+                eprintln!("AI: report sink {} behind: {} pending, oldest {} ms, {} dropped", lag.sink, lag.pending, lag.oldest_ms.unwrap_or(0), lag.dropped);
+            }
+        }
+        let why = report.explanation.as_deref().unwrap_or("");
+        match evaluate_ai_harmony(&eval).await {
             DeployDecision::DEPLOY_GO => println!("AI: DEPLOY RESONANCE GO"),
-            DeployDecision::DEPLOY_HALT => println!("AI: DEPLOY HALT – safe-state"),
+            DeployDecision::DEPLOY_CAUTION => println!("AI: DEPLOY CAUTION – no new rollouts: {}", why),
+            DeployDecision::DEPLOY_HALT => println!("AI: DEPLOY HALT – safe-state: {}", why),
         }
-        tokio::time::sleep(Duration::from_millis(100)).await; // 10 Hz
+        probes.cycle_completed();
+        tokio::time::sleep(TICK).await;
     }
 }
@@ -0,0 +1,153 @@
+//! Alloc_Check.rs - Gate: steady-state evaluation cycles perform zero heap allocations (forbid unsafe)
+//!
+//! Drives two cycles the way the engines run them, warms them up, then counts allocations per
+//! cycle through core::budget's Meter:
+//!   - the domain-engine path (SourceSet::sample_traced, CheckRegistry::run_into,
+//!     evaluate_conditions, then the report: DecisionRecord::refresh and set_lease,
+//!     explain::explain_into, RecordSigner::sign, EvaluationReport::write_json), with
+//!     synchronous sources and checks;
+//!   - HarmonyMonitor::cycle_with over plugin::ReferenceDomain, the runtime sr-bridge runs
+//!     every plugin under.
+//!
+//! Build with `--features alloc-accounting`; exits non-zero if any measured cycle allocated.
+#![forbid(unsafe_code)]
+use std::time::Duration;
+use std::{env, process};
+
+mod clock_sync;
+mod core;
+mod crypto_policy;
+mod decision;
+mod decision_kernel;
+mod decision_stream;
+mod explain;
+mod plugin;
+mod rbac;
+mod report;
+mod sealed_config;
+use crate::core::budget::Meter;
+use crate::core::checks::{CheckRegistry, SyncCheck};
+use crate::core::harmony::{self, Conditions, HarmonyContext, Severity};
+use crate::core::monitor::HarmonyMonitor;
+use crate::core::source::{SourceError, SourceSet, SyncSource};
+use clock_sync::{ClockSyncStatus, PtpState};
+use decision::{lease_for, Decision, DecisionRecord};
+use decision_stream::RecordSigner;
+use explain::Channel;
+use plugin::ReferenceDomain;
+use report::{EvaluationReport, HaltCode, HaltReason};
+
+const WARMUP_CYCLES: u64 = 16;
+const TICK: Duration = Duration::from_millis(100);
+// A fixed test key: the check measures signing, not key handling.
+const SIGNING_SEED: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+
+fn main() {
+    if !cfg!(feature = "alloc-accounting") {
+        eprintln!("alloc_check: built without the alloc-accounting feature; allocations would read zero");
+        process::exit(2);
+    }
+    let cycles: u64 = match env::args().nth(1).map(|v| v.parse()) {
+        None => 10_000,
+        Some(Ok(n)) => n,
+        Some(Err(_)) => {
+            eprintln!("usage: alloc_check [CYCLES]");
+            process::exit(2);
+        }
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().expect("tokio runtime");
+    let mut failed = false;
+    for (path, worst) in [("engine", runtime.block_on(run(cycles))), ("monitor", runtime.block_on(run_monitor(cycles)))] {
+        if worst.0 > 0 {
+            eprintln!("alloc_check: FAIL – {} cycle {} made {} allocations ({} bytes)", path, worst.1, worst.0, worst.2);
+            failed = true;
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+    println!("alloc_check: OK – {} steady-state engine and monitor cycles, 0 allocations", cycles);
+}
+
+// Returns (allocations, cycle, bytes) for the worst measured cycle.
+async fn run(cycles: u64) -> (u64, u64, u64) {
+    let mut sources = SourceSet::new(Duration::from_millis(100));
+    sources
+        .register(Box::new(SyncSource::new("a", || 0.9999)))
+        .register(Box::new(SyncSource::new("b", || 0.9998)))
+        .register(Box::new(SyncSource::new("c", || 0.9997)));
+    let mut checks = CheckRegistry::new();
+    checks
+        .register(Box::new(SyncCheck::new("interlock", || true)))
+        .register(Box::new(SyncCheck::new("advisory", || false).severity(Severity::Advisory)));
+    let ctx = HarmonyContext::builder().weights(vec![0.5, 0.3, 0.2]).channels(sources.len()).build().expect("harmony context");
+    let clock = ClockSyncStatus { offset_ns: 0, jitter_ns: 0, stratum: 1, ptp_state: PtpState::NotUsed, synced: true };
+    let mut signer = RecordSigner::from_hex("alloc_check", SIGNING_SEED).expect("signing key");
+    let names = sources.names();
+
+    // Everything a cycle writes to lives out here and is reused.
+    let mut scores = Vec::with_capacity(sources.len());
+    let mut errors: Vec<(usize, SourceError)> = Vec::new();
+    let mut conditions = Conditions::new();
+    let mut report = EvaluationReport {
+        record: DecisionRecord::new("alloc_check", 0, 0.0, true, Decision::GO, clock),
+        threshold: ctx.threshold,
+        reasons: Vec::with_capacity(4),
+        config_hash: "unsealed".into(),
+        explanation: None,
+        sensitivity: Vec::with_capacity(sources.len()),
+        provenance: Vec::with_capacity(sources.len()),
+    };
+    let mut channels: Vec<Channel> = names.iter().zip(ctx.weights()).map(|(name, weight)| Channel { name, score: 0.0, weight: *weight }).collect();
+    let mut json = String::with_capacity(1024);
+
+    let mut worst = (0, 0, 0);
+    for cycle in 1..=WARMUP_CYCLES + cycles {
+        let meter = Meter::start();
+        sources.sample_traced(&mut scores, &mut errors, &mut report.provenance).await;
+        checks.run_into(&mut conditions).await;
+        conditions.record("upstreams_clear", Severity::Critical, true);
+        let eval = harmony::evaluate_conditions(&ctx, &scores, &conditions);
+        report.record.refresh(cycle, eval.mu, eval.ch, eval.decision, clock);
+        report.record.set_lease(lease_for(TICK));
+        ctx.sensitivity_into(&scores, &mut report.sensitivity);
+        report.reasons.clear();
+        if eval.decision != Decision::GO {
+            report.reasons.push(HaltReason::new(HaltCode::MU_BELOW_THRESHOLD));
+        }
+        for (c, (score, weight)) in channels.iter_mut().zip(scores.iter().zip(ctx.weights())) {
+            c.score = *score;
+            c.weight = *weight;
+        }
+        explain::explain_into(&mut report, &channels, &conditions);
+        signer.sign(&mut report.record);
+        json.clear();
+        report.write_json(&mut json);
+        let usage = meter.stop();
+        if cycle > WARMUP_CYCLES && usage.allocations > worst.0 {
+            worst = (usage.allocations, cycle - WARMUP_CYCLES, usage.bytes);
+        }
+    }
+    worst
+}
+
+// The generic runtime over a registered plugin. Rolling statistics are off: their history
+// grows with wall time rather than cycles, so at this loop's rate it would never settle.
+async fn run_monitor(cycles: u64) -> (u64, u64, u64) {
+    let mut monitor = HarmonyMonitor::new(ReferenceDomain::new()).expect("reference domain");
+    monitor.stats_windows(&[]);
+    let mut extra = Conditions::new();
+    let mut worst = (0, 0, 0);
+    for cycle in 1..=WARMUP_CYCLES + cycles {
+        let meter = Meter::start();
+        extra.clear();
+        extra.record("upstreams_clear", Severity::Critical, true);
+        let eval = monitor.cycle_with(&extra).await;
+        let usage = meter.stop();
+        assert_eq!(eval.decision, Decision::GO, "reference domain left GO");
+        if cycle > WARMUP_CYCLES && usage.allocations > worst.0 {
+            worst = (usage.allocations, cycle - WARMUP_CYCLES, usage.bytes);
+        }
+    }
+    worst
+}
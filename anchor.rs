@@ -0,0 +1,31 @@
+//! Anchor.rs - Recorded-cycle anchor format shared by replay tooling (forbid unsafe)
+#![forbid(unsafe_code)]
+
+// One recorded cycle: `timestamp_ms|ch|w1,w2,..|s1,s2,..`
+pub struct Frame {
+    pub timestamp_ms: u64,
+    pub ch: bool,
+    pub weights: Vec<f64>,
+    pub scores: Vec<f64>,
+}
+
+pub fn parse_anchor(text: &str) -> Result<Vec<Frame>, String> {
+    let list = |s: &str| -> Option<Vec<f64>> { s.split(',').map(|x| x.trim().parse().ok()).collect() };
+    text.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty() && !l.starts_with('#'))
+        .map(|(i, l)| {
+            let f: Vec<&str> = l.split('|').map(str::trim).collect();
+            let bad = || format!("line {}: expected `timestamp_ms|ch|weights|scores`", i + 1);
+            if f.len() != 4 {
+                return Err(bad());
+            }
+            Ok(Frame {
+                timestamp_ms: f[0].parse().map_err(|_| bad())?,
+                ch: f[1].parse().map_err(|_| bad())?,
+                weights: list(f[2]).ok_or_else(bad)?,
+                scores: list(f[3]).ok_or_else(bad)?,
+            })
+        })
+        .collect()
+}
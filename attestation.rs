@@ -0,0 +1,115 @@
+//! Attestation.rs - TPM 2.0 boot-measurement and binary-hash attestation as a reusable condition (forbid unsafe)
+//!
+//! Two halves must both hold for `attested()`:
+//!   local  - PCRs (sysfs, kernel >= 5.12) and the running binary's SHA-256 match the golden policy;
+//!   remote - a verifier (Keylime or similar) has recently accepted a TPM quote over the same PCRs.
+//! A tampered node can lie about the local half but cannot forge the remote verdict, whose
+//! nonce it must sign with the TPM's attestation key via `quote`.
+#![forbid(unsafe_code)]
+use std::fs;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+pub struct AttestationPolicy {
+    // (PCR index, expected SHA-256 bank value as lowercase hex)
+    pub pcrs: Vec<(u8, String)>,
+    pub binary_sha256: String,
+    pub max_verdict_age: Duration,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttestationFault {
+    NotMeasured,
+    PcrUnreadable(u8),
+    PcrMismatch(u8),
+    BinaryUnreadable,
+    BinaryMismatch,
+    NoVerdict,
+    VerdictStale,
+    VerdictRejected(String),
+}
+
+pub struct AttestationMonitor {
+    policy: AttestationPolicy,
+    local_faults: Option<Vec<AttestationFault>>,
+    verdict: Option<(Result<(), String>, Instant)>,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn read_pcr(index: u8) -> Option<String> {
+    let raw = fs::read_to_string(format!("/sys/class/tpm/tpm0/pcr-sha256/{}", index)).ok()?;
+    Some(raw.trim().to_ascii_lowercase())
+}
+
+fn running_binary_sha256() -> Option<String> {
+    let bytes = fs::read("/proc/self/exe").ok()?;
+    Some(hex(&Sha256::digest(&bytes)))
+}
+
+impl AttestationMonitor {
+    pub fn new(policy: AttestationPolicy) -> Self {
+        AttestationMonitor { policy, local_faults: None, verdict: None }
+    }
+
+    // Re-measures PCRs and the binary. Hashing the binary is not free; call every few seconds,
+    // not every cycle.
+    pub fn measure(&mut self) {
+        let mut faults = Vec::new();
+        for (index, expected) in &self.policy.pcrs {
+            match read_pcr(*index) {
+                Some(v) if v == *expected => {}
+                Some(_) => faults.push(AttestationFault::PcrMismatch(*index)),
+                None => faults.push(AttestationFault::PcrUnreadable(*index)),
+            }
+        }
+        match running_binary_sha256() {
+            Some(h) if h == self.policy.binary_sha256 => {}
+            Some(_) => faults.push(AttestationFault::BinaryMismatch),
+            None => faults.push(AttestationFault::BinaryUnreadable),
+        }
+        self.local_faults = Some(faults);
+    }
+
+    // Produces a TPM quote over the policy PCRs bound to the verifier's nonce, via tpm2-tools.
+    // Returns (quote message, signature) for the verifier.
+    pub fn quote(&self, ak_context: &str, nonce_hex: &str, out_dir: &str) -> Result<(Vec<u8>, Vec<u8>), String> {
+        let pcr_list = self.policy.pcrs.iter().map(|(i, _)| i.to_string()).collect::<Vec<_>>().join(",");
+        let msg = format!("{}/quote.msg", out_dir);
+        let sig = format!("{}/quote.sig", out_dir);
+        let status = Command::new("tpm2_quote")
+            .args(["-c", ak_context, "-l", &format!("sha256:{}", pcr_list), "-q", nonce_hex, "-m", &msg, "-s", &sig])
+            .status()
+            .map_err(|e| format!("tpm2_quote: {}", e))?;
+        if !status.success() {
+            return Err(format!("tpm2_quote exited with {}", status));
+        }
+        let read = |p: &str| fs::read(p).map_err(|e| format!("{}: {}", p, e));
+        Ok((read(&msg)?, read(&sig)?))
+    }
+
+    // Records the remote verifier's verdict on the latest quote.
+    pub fn accept_verdict(&mut self, verdict: Result<(), String>) {
+        self.verdict = Some((verdict, Instant::now()));
+    }
+
+    pub fn faults(&self) -> Vec<AttestationFault> {
+        let mut faults = self.local_faults.clone().unwrap_or_else(|| vec![AttestationFault::NotMeasured]);
+        match &self.verdict {
+            None => faults.push(AttestationFault::NoVerdict),
+            Some((_, at)) if at.elapsed() > self.policy.max_verdict_age => faults.push(AttestationFault::VerdictStale),
+            Some((Err(reason), _)) => faults.push(AttestationFault::VerdictRejected(reason.clone())),
+            Some((Ok(()), _)) => {}
+        }
+        faults
+    }
+
+    // check_ch condition: a node without a fresh, valid attestation cannot assert GO.
+    pub fn attested(&self) -> bool {
+        self.faults().is_empty()
+    }
+}
@@ -0,0 +1,168 @@
+//! Attestation_Store.rs - Signed, expiring attestations as CH conditions, for any domain (forbid unsafe)
+//!
+//! Many conditions are "a signed document exists and is fresh": an alignment audit, a
+//! regulatory sandbox approval, an insurance certificate. Each kind is configured with its
+//! severity, its validity policy (core::validity) and the signers allowed to issue it; uploaded
+//! documents are verified against the trusted Ed25519 keys before they count, and the newest
+//! verified one per kind drives a PeriodicCondition. A document is plain text:
+//!   kind=<kind>
+//!   subject=<domain>
+//!   issued_ms=<unix ms>
+//!   evidence=<sha256 hex of the signed-off report>   (optional)
+//!   signer=<id>
+//!   sig=<hex Ed25519 signature over the SHA-256 of every line above>
+//! A document for another subject, an unconfigured kind, a signer not allowed for the kind or
+//! an issue time more than MAX_CLOCK_SKEW_MS ahead is refused, so nothing forged, misdirected or
+//! post-dated can keep a condition green.
+#![forbid(unsafe_code)]
+use std::fs;
+use std::path::Path;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+use crate::core::harmony::{Conditions, Severity};
+use crate::core::validity::{PeriodicCondition, Validity, ValidityState};
+use crate::sealed_config::{config_digest, hex, unhex};
+
+const MAX_CLOCK_SKEW_MS: u64 = 5 * 60_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attestation {
+    pub kind: String,
+    pub subject: String,
+    pub issued_ms: u64,
+    pub evidence: Option<String>,
+    pub signer: String,
+}
+
+struct Kind {
+    condition: PeriodicCondition,
+    // Empty: any trusted key.
+    signers: Vec<String>,
+    latest: Option<Attestation>,
+}
+
+pub struct AttestationStore {
+    subject: String,
+    trusted: Vec<(String, VerifyingKey)>,
+    kinds: Vec<Kind>,
+    clock: fn() -> u64,
+}
+
+impl AttestationStore {
+    // `subject` is the domain documents must name; `clock` returns wall-clock ms.
+    pub fn new(subject: &str, trusted: Vec<(String, VerifyingKey)>, clock: fn() -> u64) -> Self {
+        AttestationStore { subject: subject.to_string(), trusted, kinds: Vec::new(), clock }
+    }
+
+    // A kind of document, recorded as the condition of the same name. `signers` restricts who
+    // may issue it (the audit firm, the regulator); empty accepts any trusted key.
+    pub fn kind(mut self, name: &str, severity: Severity, validity: Validity, signers: &[&str]) -> Self {
+        self.kinds.push(Kind {
+            condition: PeriodicCondition::new(name, severity, validity, self.clock),
+            signers: signers.iter().map(|s| s.to_string()).collect(),
+            latest: None,
+        });
+        self
+    }
+
+    // Verifies one uploaded document and, if newer than the kind's current one, attests it.
+    pub fn submit(&mut self, document: &str) -> Result<&Attestation, String> {
+        let (body, sig) = document.trim_end().rsplit_once('\n').ok_or("attestation: no sig line")?;
+        let sig = sig.trim().strip_prefix("sig=").ok_or("attestation: last line must be sig=")?;
+        let mut attestation = Attestation { kind: String::new(), subject: String::new(), issued_ms: 0, evidence: None, signer: String::new() };
+        for line in body.lines() {
+            let (key, value) = line.split_once('=').ok_or_else(|| format!("attestation: bad line {:?}", line))?;
+            let value = value.trim();
+            match key.trim() {
+                "kind" => attestation.kind = value.to_string(),
+                "subject" => attestation.subject = value.to_string(),
+                "issued_ms" => attestation.issued_ms = value.parse().map_err(|_| format!("attestation: bad issued_ms {:?}", value))?,
+                "evidence" => attestation.evidence = Some(value.to_ascii_lowercase()),
+                "signer" => attestation.signer = value.to_string(),
+                other => return Err(format!("attestation: unknown field {:?}", other)),
+            }
+        }
+        let name = &attestation.kind;
+        if attestation.subject != self.subject {
+            return Err(format!("{}: issued for {:?}, not {}", name, attestation.subject, self.subject));
+        }
+        let i = self.kinds.iter().position(|k| k.condition.name == *name).ok_or_else(|| format!("{}: not a configured attestation kind", name))?;
+        let kind = &self.kinds[i];
+        if !kind.signers.is_empty() && !kind.signers.contains(&attestation.signer) {
+            return Err(format!("{}: {} may not issue this attestation", name, attestation.signer));
+        }
+        let key = self
+            .trusted
+            .iter()
+            .find(|(id, _)| *id == attestation.signer)
+            .map(|(_, k)| k)
+            .ok_or_else(|| format!("{}: signer {} is not trusted", name, attestation.signer))?;
+        let sig_bytes: [u8; 64] = unhex(sig).and_then(|b| b.try_into().ok()).ok_or_else(|| format!("{}: signature must be 64 hex bytes", name))?;
+        key.verify(&config_digest(format!("{}\n", body).as_bytes()), &Signature::from_bytes(&sig_bytes))
+            .map_err(|_| format!("{}: signature by {} does not verify", name, attestation.signer))?;
+        if attestation.issued_ms > (self.clock)() + MAX_CLOCK_SKEW_MS {
+            return Err(format!("{}: issued in the future ({} ms)", name, attestation.issued_ms));
+        }
+        let kind = &mut self.kinds[i];
+        kind.condition.attest(attestation.issued_ms);
+        if kind.latest.as_ref().is_none_or(|l| attestation.issued_ms > l.issued_ms) {
+            kind.latest = Some(attestation);
+        }
+        Ok(kind.latest.as_ref().expect("just attested"))
+    }
+
+    // The drop directory, re-read under `trusted` as it stands now: every kind starts over as
+    // never attested, so a deleted document, or one whose signer has since been removed from the
+    // key file, stops counting on the next rescan instead of staying green until it expires.
+    pub fn rescan(&mut self, dir: &Path, trusted: Vec<(String, VerifyingKey)>) -> Vec<String> {
+        self.trusted = trusted;
+        for kind in &mut self.kinds {
+            kind.condition.clear();
+            kind.latest = None;
+        }
+        self.load_dir(dir)
+    }
+
+    // Submits every `*.att` file in `dir`; returns one message per refused or unreadable file.
+    // Only adds to what is held; rescan() to drop what is gone.
+    pub fn load_dir(&mut self, dir: &Path) -> Vec<String> {
+        let entries = match fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(e) => return vec![format!("{}: {}", dir.display(), e)],
+        };
+        let mut errors = Vec::new();
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.extension().is_some_and(|x| x == "att")) {
+            if let Err(e) = fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|doc| self.submit(&doc).map(|_| ())) {
+                errors.push(format!("{}: {}", path.display(), e));
+            }
+        }
+        errors
+    }
+
+    pub fn latest(&self, kind: &str) -> Option<&Attestation> {
+        self.kinds.iter().find(|k| k.condition.name == kind)?.latest.as_ref()
+    }
+
+    pub fn state(&self, kind: &str) -> Option<ValidityState> {
+        Some(self.kinds.iter().find(|k| k.condition.name == kind)?.condition.state())
+    }
+
+    // One condition per configured kind.
+    pub fn record_into(&self, conditions: &mut Conditions) {
+        for k in &self.kinds {
+            k.condition.record_into(conditions);
+        }
+    }
+}
+
+// The document submit() accepts, for issuing tools.
+pub fn sign_attestation(kind: &str, subject: &str, issued_ms: u64, evidence: Option<&str>, signer: &str, key: &SigningKey) -> String {
+    let mut body = format!("kind={}\nsubject={}\nissued_ms={}\n", kind, subject, issued_ms);
+    if let Some(e) = evidence {
+        body.push_str(&format!("evidence={}\n", e));
+    }
+    body.push_str(&format!("signer={}\n", signer));
+    let sig = key.sign(&config_digest(body.as_bytes()));
+    format!("{}sig={}\n", body, hex(&sig.to_bytes()))
+}
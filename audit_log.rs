@@ -0,0 +1,361 @@
+//! Audit_Log.rs - Append-only audit log with HSM-signed, hash-chained segment roots (forbid unsafe)
+//!
+//! Entries are written as JSON lines. Every `segment_len` entries (or on `seal`) the segment
+//! is closed with a seal line carrying
+//!   root = SHA-256(prev_root || segment (u64 BE) || entries (u64 BE) || merkle(entry hashes))
+//! and the HSM's signature over it. Leaves are SHA-256(0x00 || line) and inner nodes
+//! SHA-256(0x01 || left || right), so an inner node can never pass for an entry. Dropping,
+//! editing or reordering any entry, or any whole segment, breaks a signed root; the key never
+//! leaves the HSM, which gives non-repudiation. `verify_log` replays a log against its seals.
+#![forbid(unsafe_code)]
+use std::io::Write;
+
+use cryptoki::context::{CInitializeArgs, Pkcs11};
+use cryptoki::mechanism::Mechanism;
+use cryptoki::object::{Attribute, ObjectClass, ObjectHandle};
+use cryptoki::session::{Session, UserType};
+use cryptoki::types::AuthPin;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::crypto_policy::FIPS_BUILD;
+use crate::decision::{json_escape, now_ms};
+use crate::rbac::{AuditEntry, AuditSink};
+
+pub trait SegmentSigner {
+    fn key_id(&self) -> &str;
+    // Identity of the module that holds the key, recorded in every seal.
+    fn module(&self) -> &str;
+    fn fips_validated(&self) -> bool;
+    fn sign(&mut self, root: &[u8; 32]) -> Result<Vec<u8>, String>;
+}
+
+// So a log can be opened on whichever signer the deployment configures (AuditLog<_, Box<dyn SegmentSigner>>).
+impl<S: SegmentSigner + ?Sized> SegmentSigner for Box<S> {
+    fn key_id(&self) -> &str {
+        (**self).key_id()
+    }
+
+    fn module(&self) -> &str {
+        (**self).module()
+    }
+
+    fn fips_validated(&self) -> bool {
+        (**self).fips_validated()
+    }
+
+    fn sign(&mut self, root: &[u8; 32]) -> Result<Vec<u8>, String> {
+        (**self).sign(root)
+    }
+}
+
+// ECDSA over the segment root with a non-extractable key on a PKCS#11 token.
+pub struct Pkcs11Signer {
+    label: String,
+    module: String,
+    session: Session,
+    key: ObjectHandle,
+    validated: bool,
+}
+
+impl Pkcs11Signer {
+    // PKCS#11 has no portable "FIPS approved" query, so `validated` lists the token identities
+    // (as module() reports them, firmware included) that carry a CMVP certificate for this
+    // deployment; a token is only treated as validated when its reported identity is listed.
+    pub fn open(module: &str, pin: &str, label: &str, validated: &[&str]) -> Result<Self, String> {
+        let e = |what: &str, err: cryptoki::error::Error| format!("pkcs11 {}: {}", what, err);
+        let pkcs11 = Pkcs11::new(module).map_err(|x| e("load", x))?;
+        pkcs11.initialize(CInitializeArgs::OsThreads).map_err(|x| e("initialize", x))?;
+        let slot = pkcs11
+            .get_slots_with_token()
+            .map_err(|x| e("slots", x))?
+            .into_iter()
+            .next()
+            .ok_or("pkcs11: no token present")?;
+        let token = pkcs11.get_token_info(slot).map_err(|x| e("token info", x))?;
+        let fw = token.firmware_version();
+        let module = format!("{} {} fw {}.{}", token.manufacturer_id().trim(), token.model().trim(), fw.major(), fw.minor());
+        let validated = validated.contains(&module.as_str());
+        let session = pkcs11.open_ro_session(slot).map_err(|x| e("session", x))?;
+        session.login(UserType::User, Some(&AuthPin::new(pin.into()))).map_err(|x| e("login", x))?;
+        let key = session
+            .find_objects(&[Attribute::Class(ObjectClass::PRIVATE_KEY), Attribute::Label(label.as_bytes().to_vec())])
+            .map_err(|x| e("find key", x))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("pkcs11: no private key labelled {}", label))?;
+        Ok(Pkcs11Signer { label: label.to_string(), module, session, key, validated })
+    }
+}
+
+impl SegmentSigner for Pkcs11Signer {
+    fn key_id(&self) -> &str {
+        &self.label
+    }
+
+    fn module(&self) -> &str {
+        &self.module
+    }
+
+    // The recorded manufacturer/model/firmware lets auditors tie each seal to its CMVP certificate.
+    fn fips_validated(&self) -> bool {
+        self.validated
+    }
+
+    fn sign(&mut self, root: &[u8; 32]) -> Result<Vec<u8>, String> {
+        self.session.sign(&Mechanism::Ecdsa, self.key, root).map_err(|e| format!("pkcs11 sign: {}", e))
+    }
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok()).collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn sha256(parts: &[&[u8]]) -> [u8; 32] {
+    let mut h = Sha256::new();
+    for p in parts {
+        h.update(p);
+    }
+    h.finalize().into()
+}
+
+const LEAF: &[u8] = &[0x00];
+const NODE: &[u8] = &[0x01];
+
+pub fn leaf_hash(line: &str) -> [u8; 32] {
+    sha256(&[LEAF, line.as_bytes()])
+}
+
+// Odd nodes are promoted unchanged; an empty segment has the all-zero root.
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return [0; 32];
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| if pair.len() == 2 { sha256(&[NODE, &pair[0], &pair[1]]) } else { pair[0] })
+            .collect();
+    }
+    level[0]
+}
+
+// The signed root of one segment: its place in the chain, its index and its entry count are all
+// under the signature, so a seal cannot be replayed at another position or with entries cut off.
+pub fn segment_root(prev_root: &[u8; 32], segment: u64, leaves: &[[u8; 32]]) -> [u8; 32] {
+    sha256(&[prev_root, &segment.to_be_bytes(), &(leaves.len() as u64).to_be_bytes(), &merkle_root(leaves)])
+}
+
+pub struct AuditLog<W: Write, S: SegmentSigner> {
+    out: W,
+    signer: S,
+    segment_len: usize,
+    segment: u64,
+    prev_root: [u8; 32],
+    leaves: Vec<[u8; 32]>,
+    // Why the last seal attempt failed; cleared by the next seal that signs.
+    seal_error: Option<String>,
+}
+
+impl<W: Write, S: SegmentSigner> AuditLog<W, S> {
+    // `prev_root` continues an existing chain (the last sealed root on disk); use [0; 32] for a new log.
+    pub fn new(out: W, signer: S, segment_len: usize, segment: u64, prev_root: [u8; 32]) -> Self {
+        AuditLog { out, signer, segment_len: segment_len.max(1), segment, prev_root, leaves: Vec::new(), seal_error: None }
+    }
+
+    pub fn append_line(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.out, "{}", line).map_err(|e| e.to_string())?;
+        self.leaves.push(leaf_hash(line));
+        if self.leaves.len() >= self.segment_len {
+            self.seal()?;
+        }
+        Ok(())
+    }
+
+    // Closes the current segment; also call on a timer so quiet periods still get signed.
+    pub fn seal(&mut self) -> Result<(), String> {
+        if self.leaves.is_empty() {
+            return Ok(());
+        }
+        if FIPS_BUILD && !self.signer.fips_validated() {
+            return Err(format!("signer {} is not a FIPS-validated module", self.signer.module()));
+        }
+        let root = segment_root(&self.prev_root, self.segment, &self.leaves);
+        let sig = self.signer.sign(&root).inspect_err(|e| self.seal_error = Some(e.clone()))?;
+        self.seal_error = None;
+        writeln!(
+            self.out,
+            "{{\"seal\":{{\"segment\":{},\"entries\":{},\"at_ms\":{},\"prev\":\"{}\",\"root\":\"{}\",\"key\":\"{}\",\"module\":\"{}\",\"sig\":\"{}\"}}}}",
+            self.segment,
+            self.leaves.len(),
+            now_ms(),
+            hex(&self.prev_root),
+            hex(&root),
+            json_escape(self.signer.key_id()),
+            json_escape(self.signer.module()),
+            hex(&sig)
+        )
+        .map_err(|e| e.to_string())?;
+        self.out.flush().map_err(|e| e.to_string())?;
+        self.prev_root = root;
+        self.leaves.clear();
+        self.segment += 1;
+        Ok(())
+    }
+
+    // check_ch condition: false from a failed signature (HSM, Vault or KMS unreachable) until a
+    // later seal signs; the entries stay written and are covered by that seal.
+    pub fn key_source_ok(&self) -> bool {
+        self.seal_error.is_none()
+    }
+}
+
+impl<W: Write, S: SegmentSigner> AuditSink for AuditLog<W, S> {
+    fn append(&mut self, entry: &AuditEntry) -> Result<(), String> {
+        self.append_line(&entry.to_json())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedLog {
+    pub segments: u64,
+    pub entries: u64,
+    // Entries after the last seal: written but not yet covered by a signature.
+    pub unsealed: usize,
+    pub last_root: [u8; 32],
+}
+
+// Replays a log (or a run of it starting at `segment` after `prev_root`) against its seals:
+// every seal must be the next segment, count the entries since the previous one, chain from
+// the previous root and carry the recomputed root. `verify_sig(key, root, sig)` checks the
+// signature with the public key the auditor holds for `key`; the log never supplies its own.
+pub fn verify_log<'a>(
+    lines: impl IntoIterator<Item = &'a str>,
+    mut segment: u64,
+    mut prev_root: [u8; 32],
+    mut verify_sig: impl FnMut(&str, &[u8; 32], &[u8]) -> bool,
+) -> Result<VerifiedLog, String> {
+    let (mut leaves, mut entries, start) = (Vec::new(), 0u64, segment);
+    for (n, line) in lines.into_iter().enumerate() {
+        if !line.starts_with("{\"seal\":") {
+            leaves.push(leaf_hash(line));
+            continue;
+        }
+        let bad = |what: &str| format!("line {}: {}", n + 1, what);
+        let v: Value = serde_json::from_str(line).map_err(|e| bad(&e.to_string()))?;
+        let seal = &v["seal"];
+        let hash = |k: &str| seal[k].as_str().and_then(unhex).and_then(|b| <[u8; 32]>::try_from(b).ok()).ok_or_else(|| bad(&format!("bad {}", k)));
+        if seal["segment"].as_u64() != Some(segment) {
+            return Err(bad(&format!("expected segment {}", segment)));
+        }
+        if seal["entries"].as_u64() != Some(leaves.len() as u64) {
+            return Err(bad(&format!("seal does not cover the {} entries before it", leaves.len())));
+        }
+        if hash("prev")? != prev_root {
+            return Err(bad("prev does not chain from the previous root"));
+        }
+        let root = segment_root(&prev_root, segment, &leaves);
+        if hash("root")? != root {
+            return Err(bad("root does not match the entries"));
+        }
+        let key = seal["key"].as_str().ok_or_else(|| bad("no key"))?;
+        let sig = seal["sig"].as_str().and_then(unhex).ok_or_else(|| bad("bad sig"))?;
+        if !verify_sig(key, &root, &sig) {
+            return Err(bad(&format!("signature by {} does not verify", key)));
+        }
+        entries += leaves.len() as u64;
+        leaves.clear();
+        prev_root = root;
+        segment += 1;
+    }
+    Ok(VerifiedLog { segments: segment - start, entries, unsealed: leaves.len(), last_root: prev_root })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Stands in for the HSM: the "signature" is the root itself, or a refusal.
+    struct Echo {
+        fail: bool,
+    }
+
+    impl SegmentSigner for Echo {
+        fn key_id(&self) -> &str {
+            "test-key"
+        }
+
+        fn module(&self) -> &str {
+            "echo"
+        }
+
+        fn fips_validated(&self) -> bool {
+            true
+        }
+
+        fn sign(&mut self, root: &[u8; 32]) -> Result<Vec<u8>, String> {
+            if self.fail { Err("token removed".into()) } else { Ok(root.to_vec()) }
+        }
+    }
+
+    fn verify(text: &str) -> Result<VerifiedLog, String> {
+        verify_log(text.lines(), 0, [0; 32], |key, root, sig| key == "test-key" && sig == root)
+    }
+
+    fn written(lines: &[&str], segment_len: usize) -> String {
+        let mut log = AuditLog::new(Vec::new(), Echo { fail: false }, segment_len, 0, [0; 32]);
+        for line in lines {
+            log.append_line(line).unwrap();
+        }
+        String::from_utf8(log.out).unwrap()
+    }
+
+    #[test]
+    fn sealed_segments_verify() {
+        let text = written(&["{\"a\":1}", "{\"a\":2}", "{\"a\":3}", "{\"a\":4}", "{\"a\":5}"], 2);
+        let v = verify(&text).unwrap();
+        assert_eq!((v.segments, v.entries, v.unsealed), (2, 4, 1));
+    }
+
+    #[test]
+    fn edited_dropped_or_reordered_entries_break_the_seal() {
+        let text = written(&["{\"a\":1}", "{\"a\":2}", "{\"a\":3}", "{\"a\":4}"], 2);
+        let lines: Vec<&str> = text.lines().collect();
+        let edited = text.replace("{\"a\":2}", "{\"a\":9}");
+        assert!(verify(&edited).is_err());
+        let dropped: Vec<&str> = lines.iter().enumerate().filter(|(i, _)| *i != 1).map(|(_, l)| *l).collect();
+        assert!(verify(&dropped.join("\n")).is_err());
+        let mut reordered = lines.clone();
+        reordered.swap(0, 1);
+        assert!(verify(&reordered.join("\n")).is_err());
+        // A whole segment cut from the front breaks the chain too.
+        assert!(verify(&lines[3..].join("\n")).is_err());
+    }
+
+    #[test]
+    fn a_failed_signature_leaves_the_segment_open() {
+        let mut log = AuditLog::new(Vec::new(), Echo { fail: true }, 1, 0, [0; 32]);
+        assert!(log.append_line("{\"a\":1}").is_err());
+        assert!(!log.key_source_ok());
+        log.signer.fail = false;
+        log.seal().unwrap();
+        assert!(log.key_source_ok());
+        let v = verify(&String::from_utf8(log.out).unwrap()).unwrap();
+        assert_eq!((v.segments, v.entries), (1, 1));
+    }
+
+    #[test]
+    fn odd_nodes_are_promoted() {
+        let leaves = [leaf_hash("a"), leaf_hash("b"), leaf_hash("c")];
+        assert_eq!(merkle_root(&leaves), sha256(&[NODE, &sha256(&[NODE, &leaves[0], &leaves[1]]), &leaves[2]]));
+        assert_eq!(merkle_root(&[]), [0; 32]);
+    }
+}
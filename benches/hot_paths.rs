@@ -0,0 +1,87 @@
+//! Hot_Paths.rs - Criterion benches for the per-cycle hot paths, with 10 Hz budget gates
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, Criterion};
+
+#[path = "../clock_sync.rs"]
+mod clock_sync;
+#[path = "../decision.rs"]
+mod decision;
+#[path = "../decision_kernel.rs"]
+mod decision_kernel;
+
+use clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+use decision::{Decision, DecisionRecord};
+
+const MIN_SCORE: f64 = 1e-12;
+// 10 Hz cycle = 100 ms; each hot path may use at most this share of it.
+const CYCLE_BUDGET: Duration = Duration::from_millis(100);
+const MU_BUDGET: Duration = Duration::from_micros(50);
+const MU_BATCH_BUDGET: Duration = Duration::from_millis(5);
+const REPORT_BUDGET: Duration = Duration::from_micros(100);
+const BATCH: usize = 10_000;
+
+fn calculate_mu(weights: &[f64], scores: &[f64]) -> f64 {
+    let mut log_sum = 0.0;
+    for (w, s) in weights.iter().zip(scores.iter()) {
+        log_sum += w * s.clamp(MIN_SCORE, 1.0).ln();
+    }
+    log_sum.exp()
+}
+
+fn fixture() -> (Vec<f64>, Vec<f64>, Vec<Vec<f64>>, DecisionRecord) {
+    let weights = vec![0.30, 0.25, 0.20, 0.15, 0.10];
+    let scores = vec![0.98, 0.97, 1.0, 0.96, 0.99];
+    let batch = (0..BATCH).map(|i| scores.iter().map(|s| s - (i % 7) as f64 * 1e-4).collect()).collect();
+    let clock = ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS).status();
+    let record = DecisionRecord::new("bench-node", 1, 0.9996, true, Decision::GO, clock);
+    (weights, scores, batch, record)
+}
+
+fn bench_hot_paths(c: &mut Criterion) {
+    let (weights, scores, batch, record) = fixture();
+    c.bench_function("calculate_mu/scalar", |b| b.iter(|| calculate_mu(black_box(&weights), black_box(&scores))));
+    c.bench_function("calculate_mu/batch_10k", |b| {
+        b.iter(|| batch.iter().map(|s| calculate_mu(&weights, s)).fold(0.0, f64::max))
+    });
+    c.bench_function("report/to_json", |b| b.iter(|| black_box(&record).to_json()));
+}
+
+// Median of `runs` timings of `f`; used for the hard budget gates below.
+fn median_time<F: FnMut()>(runs: usize, mut f: F) -> Duration {
+    let mut t: Vec<Duration> = (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    t.sort();
+    t[runs / 2]
+}
+
+// Fails the bench run (non-zero exit) when a hot path regresses past its budget,
+// independent of CI baselines.
+fn assert_cycle_budget() {
+    let (weights, scores, batch, record) = fixture();
+    let gates = [
+        ("calculate_mu/scalar", median_time(1001, || { black_box(calculate_mu(&weights, &scores)); }), MU_BUDGET),
+        ("calculate_mu/batch_10k", median_time(51, || { black_box(batch.iter().map(|s| calculate_mu(&weights, s)).sum::<f64>()); }), MU_BATCH_BUDGET),
+        ("report/to_json", median_time(1001, || { black_box(record.to_json()); }), REPORT_BUDGET),
+    ];
+    let total: Duration = gates.iter().map(|g| g.1).sum();
+    for (name, took, budget) in &gates {
+        println!("budget {:<24} {:>10.3?} / {:?}", name, took, budget);
+        assert!(took <= budget, "{} regressed: {:?} > {:?}", name, took, budget);
+    }
+    assert!(total < CYCLE_BUDGET, "hot paths exceed the 10 Hz cycle: {:?}", total);
+}
+
+criterion_group!(benches, bench_hot_paths);
+
+fn main() {
+    assert_cycle_budget();
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+}
@@ -0,0 +1,78 @@
+/**
+ * Harmony WASM Service
+ * Local recomputation of μ and what-if scenarios in the browser, backed by the
+ * wasm32 build of the evaluation kernel (harmony_wasm.rs).
+ */
+
+const WASM_MODULE_URL = '/harmony_wasm/harmony_wasm.js';
+
+let kernelPromise = null;
+
+/**
+ * Load and initialise the WASM kernel once per page
+ * @returns {Promise<object>} The initialised wasm-bindgen module
+ */
+export function loadHarmonyKernel() {
+  if (!kernelPromise) {
+    kernelPromise = import(/* @vite-ignore */ WASM_MODULE_URL).then(async (mod) => {
+      await mod.default();
+      return mod;
+    });
+  }
+  return kernelPromise;
+}
+
+/**
+ * Recompute μ for the given weights and scores
+ * @param {number[]} weights - Channel weights
+ * @param {number[]} scores - Channel scores in [0, 1]
+ * @returns {Promise<number>} μ
+ */
+export async function calculateMu(weights, scores) {
+  const kernel = await loadHarmonyKernel();
+  return kernel.calculateMu(Float64Array.from(weights), Float64Array.from(scores));
+}
+
+/**
+ * Evaluate GO/HALT for a set of inputs
+ * @param {number[]} weights - Channel weights
+ * @param {number[]} scores - Channel scores
+ * @param {boolean} ch - Result of the critical-hazard checks
+ * @param {number} threshold - Harmony threshold
+ * @returns {Promise<string>} "GO" | "CAUTION" | "HALT"
+ */
+export async function evaluate(weights, scores, ch, threshold) {
+  const kernel = await loadHarmonyKernel();
+  return kernel.evaluate(Float64Array.from(weights), Float64Array.from(scores), ch, threshold);
+}
+
+/**
+ * "What if this sensor improved to `value`?"
+ * @param {number[]} weights - Channel weights
+ * @param {number[]} scores - Live channel scores (not modified)
+ * @param {number} channel - Index of the channel to substitute
+ * @param {number} value - Hypothetical score
+ * @returns {Promise<number>} Hypothetical μ
+ */
+export async function whatIfMu(weights, scores, channel, value) {
+  const kernel = await loadHarmonyKernel();
+  return kernel.whatIfMu(Float64Array.from(weights), Float64Array.from(scores), channel, value);
+}
+
+/**
+ * Score a single channel must reach (others unchanged) for μ to clear the threshold
+ * @returns {Promise<number|null>} Required score, or null if unreachable
+ */
+export async function requiredScore(weights, scores, channel, threshold) {
+  const kernel = await loadHarmonyKernel();
+  const needed = kernel.requiredScore(Float64Array.from(weights), Float64Array.from(scores), channel, threshold);
+  return Number.isNaN(needed) ? null : needed;
+}
+
+export default {
+  loadHarmonyKernel,
+  calculateMu,
+  evaluate,
+  whatIfMu,
+  requiredScore
+};
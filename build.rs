@@ -0,0 +1,9 @@
+//! Build.rs - declares the custom cfgs the sources use so check-cfg accepts them (forbid unsafe)
+//!
+//! `kani` is set by `cargo kani` for decision_kernel's proofs. Features (`std`, `proptest`, ...)
+//! are declared in Cargo.toml, which gives check-cfg their values.
+#![forbid(unsafe_code)]
+
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(kani)");
+}
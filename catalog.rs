@@ -0,0 +1,288 @@
+//! Catalog.rs - Incident catalog: one ordered timeline around each HALT (forbid unsafe)
+//!
+//! The catalog keeps a rolling `lead` of cycle history (mu and scores) plus the events an
+//! investigation needs: condition flips, decision changes, alerts, operator acknowledgements and
+//! autoheal / safe-state actions. Entering HALT opens an incident seeded with that history; it
+//! collects everything until the decision has been out of HALT for `tail`, and a HALT within
+//! the tail continues the same incident. `timeline(id)` returns the incident's entries in time
+//! order for the report generator and the HMI (diagnostics GET /incidents/<id>). Memory is
+//! bounded: at most `max_incidents` incidents are kept, each with at most `capacity` cycles.
+#![forbid(unsafe_code)]
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::core::harmony::{Conditions, Decision, Evaluation};
+use crate::decision::{JsonNum, JsonStr};
+
+pub type IncidentId = u64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Cycle { mu: f64, scores: Vec<f64> },
+    // `from` is None on the first cycle the catalog saw.
+    Decision { from: Option<Decision>, to: Decision, mu: f64 },
+    Condition { name: String, ok: bool, detail: Option<String> },
+    Alert { message: String },
+    Ack { identity: String, note: String },
+    Autoheal { action: String, error: Option<String> },
+}
+
+impl Event {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::Cycle { .. } => "cycle",
+            Event::Decision { .. } => "decision",
+            Event::Condition { .. } => "condition",
+            Event::Alert { .. } => "alert",
+            Event::Ack { .. } => "ack",
+            Event::Autoheal { .. } => "autoheal",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub at_ms: u64,
+    pub event: Event,
+}
+
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    pub incident: IncidentId,
+    pub opened_ms: u64,
+    // None while the incident is still open.
+    pub closed_ms: Option<u64>,
+    // Score channel names, in the order of every Cycle's scores.
+    pub channels: Vec<String>,
+    pub entries: Vec<Entry>,
+    // Cycles not kept once the incident held `capacity` of them; other events are never dropped.
+    pub dropped_cycles: u64,
+}
+
+impl Timeline {
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+
+    pub fn write_json(&self, out: &mut String) {
+        let _ = write!(out, "{{\"incident\":{},\"opened_ms\":{},\"closed_ms\":", self.incident, self.opened_ms);
+        match self.closed_ms {
+            Some(ms) => {
+                let _ = write!(out, "{}", ms);
+            }
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"channels\":[");
+        for (i, c) in self.channels.iter().enumerate() {
+            let _ = write!(out, "{}\"{}\"", if i > 0 { "," } else { "" }, JsonStr(c));
+        }
+        let _ = write!(out, "],\"dropped_cycles\":{},\"entries\":[", self.dropped_cycles);
+        for (i, e) in self.entries.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write_entry(e, out);
+        }
+        out.push_str("]}");
+    }
+}
+
+fn write_entry(e: &Entry, out: &mut String) {
+    let _ = write!(out, "{{\"at_ms\":{},\"kind\":\"{}\"", e.at_ms, e.event.kind());
+    let opt = |s: &Option<String>| s.as_deref().map_or("null".to_string(), |s| format!("\"{}\"", JsonStr(s)));
+    let _ = match &e.event {
+        Event::Cycle { mu, scores } => {
+            let _ = write!(out, ",\"mu\":{},\"scores\":[", JsonNum(*mu));
+            for (i, s) in scores.iter().enumerate() {
+                let _ = write!(out, "{}{}", if i > 0 { "," } else { "" }, JsonNum(*s));
+            }
+            write!(out, "]")
+        }
+        Event::Decision { from, to, mu } => match from {
+            Some(from) => write!(out, ",\"from\":\"{:?}\",\"to\":\"{:?}\",\"mu\":{}", from, to, JsonNum(*mu)),
+            None => write!(out, ",\"from\":null,\"to\":\"{:?}\",\"mu\":{}", to, JsonNum(*mu)),
+        },
+        Event::Condition { name, ok, detail } => write!(out, ",\"name\":\"{}\",\"ok\":{},\"detail\":{}", JsonStr(name), ok, opt(detail)),
+        Event::Alert { message } => write!(out, ",\"message\":\"{}\"", JsonStr(message)),
+        Event::Ack { identity, note } => write!(out, ",\"identity\":\"{}\",\"note\":\"{}\"", JsonStr(identity), JsonStr(note)),
+        Event::Autoheal { action, error } => write!(out, ",\"action\":\"{}\",\"error\":{}", JsonStr(action), opt(error)),
+    };
+    out.push('}');
+}
+
+#[derive(Clone, Debug)]
+struct Incident {
+    opened_ms: u64,
+    // When the decision last left HALT; cleared if it returns within the tail.
+    left_halt_ms: Option<u64>,
+    closed_ms: Option<u64>,
+    entries: Vec<Entry>,
+    cycles: usize,
+    dropped_cycles: u64,
+}
+
+impl Incident {
+    fn push(&mut self, entry: Entry, capacity: usize) {
+        if let Event::Cycle { .. } = entry.event {
+            if self.cycles >= capacity {
+                self.dropped_cycles += 1;
+                return;
+            }
+            self.cycles += 1;
+        }
+        self.entries.push(entry);
+    }
+}
+
+pub struct IncidentCatalog {
+    channels: Vec<String>,
+    lead_ms: u64,
+    tail_ms: u64,
+    capacity: usize,
+    max_incidents: usize,
+    history: VecDeque<Entry>,
+    incidents: BTreeMap<IncidentId, Incident>,
+    open: Option<IncidentId>,
+    next_id: IncidentId,
+    decision: Option<Decision>,
+    // Last seen result per condition name, to record only flips.
+    conditions: Vec<(String, bool)>,
+}
+
+impl IncidentCatalog {
+    pub fn new(channels: &[&str], lead: Duration, tail: Duration) -> Self {
+        IncidentCatalog {
+            channels: channels.iter().map(|c| c.to_string()).collect(),
+            lead_ms: lead.as_millis() as u64,
+            tail_ms: tail.as_millis() as u64,
+            capacity: 3_600,
+            max_incidents: 32,
+            history: VecDeque::new(),
+            incidents: BTreeMap::new(),
+            open: None,
+            next_id: 1,
+            decision: None,
+            conditions: Vec::new(),
+        }
+    }
+
+    // Cycles kept per incident (and in the lead history).
+    pub fn capacity(mut self, cycles: usize) -> Self {
+        self.capacity = cycles.max(1);
+        self
+    }
+
+    // Oldest incidents are evicted beyond this.
+    pub fn max_incidents(mut self, incidents: usize) -> Self {
+        self.max_incidents = incidents.max(1);
+        self
+    }
+
+    // Feeds one evaluated cycle; returns the incident id when this cycle opened one.
+    pub fn observe(&mut self, at_ms: u64, eval: &Evaluation, scores: &[f64], conditions: &Conditions) -> Option<IncidentId> {
+        let opened = match self.open {
+            None if eval.decision == Decision::HALT => Some(self.open_incident(at_ms)),
+            _ => None,
+        };
+        for c in &conditions.statuses {
+            let flipped = match self.conditions.iter_mut().find(|(name, _)| *name == c.name) {
+                Some((_, last)) if *last == c.ok => false,
+                Some((_, last)) => {
+                    *last = c.ok;
+                    true
+                }
+                None => {
+                    self.conditions.push((c.name.clone(), c.ok));
+                    !c.ok
+                }
+            };
+            if flipped {
+                self.record(at_ms, Event::Condition { name: c.name.clone(), ok: c.ok, detail: c.detail.clone() });
+            }
+        }
+        if self.decision != Some(eval.decision) {
+            self.record(at_ms, Event::Decision { from: self.decision, to: eval.decision, mu: eval.mu });
+            self.decision = Some(eval.decision);
+        }
+        self.record(at_ms, Event::Cycle { mu: eval.mu, scores: scores.to_vec() });
+        if let Some(incident) = self.open.and_then(|id| self.incidents.get_mut(&id)) {
+            if eval.decision == Decision::HALT {
+                incident.left_halt_ms = None;
+            } else if at_ms.saturating_sub(*incident.left_halt_ms.get_or_insert(at_ms)) >= self.tail_ms {
+                incident.closed_ms = Some(at_ms);
+                self.open = None;
+            }
+        }
+        opened
+    }
+
+    pub fn alert(&mut self, at_ms: u64, message: &str) {
+        self.record(at_ms, Event::Alert { message: message.to_string() });
+    }
+
+    pub fn autoheal(&mut self, at_ms: u64, action: &str, result: Result<(), String>) {
+        self.record(at_ms, Event::Autoheal { action: action.to_string(), error: result.err() });
+    }
+
+    // An operator's acknowledgement of a specific incident, open or closed.
+    pub fn ack(&mut self, at_ms: u64, incident: IncidentId, identity: &str, note: &str) -> Result<(), String> {
+        let capacity = self.capacity;
+        let incident = self.incidents.get_mut(&incident).ok_or_else(|| format!("no incident {}", incident))?;
+        incident.push(Entry { at_ms, event: Event::Ack { identity: identity.to_string(), note: note.to_string() } }, capacity);
+        Ok(())
+    }
+
+    pub fn open_incident_id(&self) -> Option<IncidentId> {
+        self.open
+    }
+
+    // (id, opened_ms, closed_ms) for every kept incident, oldest first.
+    pub fn incidents(&self) -> impl Iterator<Item = (IncidentId, u64, Option<u64>)> + '_ {
+        self.incidents.iter().map(|(id, i)| (*id, i.opened_ms, i.closed_ms))
+    }
+
+    // The incident's entries ordered by time; entries with equal times keep recording order
+    // (condition flips, then the decision change, then the cycle that caused them).
+    pub fn timeline(&self, incident: IncidentId) -> Option<Timeline> {
+        let i = self.incidents.get(&incident)?;
+        let mut entries = i.entries.clone();
+        entries.sort_by_key(|e| e.at_ms);
+        Some(Timeline {
+            incident,
+            opened_ms: i.opened_ms,
+            closed_ms: i.closed_ms,
+            channels: self.channels.clone(),
+            entries,
+            dropped_cycles: i.dropped_cycles,
+        })
+    }
+
+    fn open_incident(&mut self, at_ms: u64) -> IncidentId {
+        while self.incidents.len() >= self.max_incidents {
+            self.incidents.pop_first();
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut incident = Incident { opened_ms: at_ms, left_halt_ms: None, closed_ms: None, entries: Vec::new(), cycles: 0, dropped_cycles: 0 };
+        for e in self.history.iter().filter(|e| e.at_ms.saturating_add(self.lead_ms) >= at_ms) {
+            incident.push(e.clone(), self.capacity);
+        }
+        self.incidents.insert(id, incident);
+        self.open = Some(id);
+        id
+    }
+
+    fn record(&mut self, at_ms: u64, event: Event) {
+        let entry = Entry { at_ms, event };
+        if let Some(incident) = self.open.and_then(|id| self.incidents.get_mut(&id)) {
+            incident.push(entry.clone(), self.capacity);
+        }
+        while self.history.front().is_some_and(|e| e.at_ms.saturating_add(self.lead_ms) < at_ms) || self.history.len() >= self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(entry);
+    }
+}
@@ -0,0 +1,88 @@
+//! Clock_Sync.rs - PTP/NTP clock-sync health as a reusable condition (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PtpState { NotUsed, Listening, Uncalibrated, Slave, Master, Faulty }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockSyncStatus {
+    pub offset_ns: i64,
+    pub jitter_ns: u64,
+    pub stratum: u8,
+    pub ptp_state: PtpState,
+    pub synced: bool,
+}
+
+pub struct ClockSyncLimits {
+    pub max_offset: Duration,
+    pub max_jitter: Duration,
+    pub max_stratum: u8,
+    pub max_sample_age: Duration,
+}
+
+pub const DEFAULT_CLOCK_LIMITS: ClockSyncLimits = ClockSyncLimits {
+    max_offset: Duration::from_millis(1),
+    max_jitter: Duration::from_micros(500),
+    max_stratum: 3,
+    max_sample_age: Duration::from_secs(10),
+};
+
+pub struct ClockSyncMonitor {
+    limits: ClockSyncLimits,
+    last: Option<(ClockSyncStatus, Instant)>,
+}
+
+impl ClockSyncMonitor {
+    pub fn new(limits: ClockSyncLimits) -> Self {
+        ClockSyncMonitor { limits, last: None }
+    }
+
+    pub async fn refresh(&mut self) {
+        let (offset_ns, jitter_ns, stratum, ptp_state) = query_clock_sync_sample().await;
+        self.ingest(offset_ns, jitter_ns, stratum, ptp_state);
+    }
+
+    pub fn ingest(&mut self, offset_ns: i64, jitter_ns: u64, stratum: u8, ptp_state: PtpState) {
+        let l = &self.limits;
+        let ptp_ok = matches!(ptp_state, PtpState::NotUsed | PtpState::Slave | PtpState::Master);
+        let synced = ptp_ok
+            && offset_ns.unsigned_abs() as u128 <= l.max_offset.as_nanos()
+            && jitter_ns as u128 <= l.max_jitter.as_nanos()
+            && stratum <= l.max_stratum;
+        let status = ClockSyncStatus { offset_ns, jitter_ns, stratum, ptp_state, synced };
+        self.last = Some((status, Instant::now()));
+    }
+
+    // Stamped into every DecisionRecord; a missing or stale sample reads as unsynced.
+    pub fn status(&self) -> ClockSyncStatus {
+        match self.last {
+            Some((s, at)) if at.elapsed() <= self.limits.max_sample_age => s,
+            Some((s, _)) => ClockSyncStatus { synced: false, ..s },
+            None => ClockSyncStatus {
+                offset_ns: 0,
+                jitter_ns: 0,
+                stratum: 16,
+                ptp_state: PtpState::Faulty,
+                synced: false,
+            },
+        }
+    }
+
+    // For check_ch.
+    pub fn clock_synced(&self) -> bool {
+        self.status().synced
+    }
+}
+
+// `chronyc -c tracking`: stratum is field 2, last offset field 5, RMS offset field 6 (seconds).
+pub fn parse_chronyc_tracking(csv: &str) -> Option<(i64, u64, u8)> {
+    let f: Vec<&str> = csv.trim().split(',').collect();
+    let stratum: u8 = f.get(2)?.parse().ok()?;
+    let offset: f64 = f.get(5)?.parse().ok()?;
+    let rms: f64 = f.get(6)?.parse().ok()?;
+    if !offset.is_finite() || !rms.is_finite() {
+        return None;
+    }
+    Some(((offset * 1e9) as i64, (rms.abs() * 1e9) as u64, stratum))
+}
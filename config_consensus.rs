@@ -0,0 +1,430 @@
+//! Config_Consensus.rs - Raft log replication for cluster-wide monitor config (forbid unsafe)
+//!
+//! Leader election (RequestVote) and log replication (AppendEntries) for config entries. The
+//! caller owns the timers and the transport: it starts an election when it has heard from no
+//! leader within its randomized election timeout, and sends heartbeats as leader.
+#![forbid(unsafe_code)]
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::core::monitor::HarmonyMonitor;
+use crate::plugin::Domain;
+use crate::rbac::Principal;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitorConfig {
+    pub harmony_threshold: f64,
+    pub weights: Vec<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    pub term: u64,
+    pub index: u64,
+    pub config: MonitorConfig,
+}
+
+#[derive(Clone, Debug)]
+pub struct AppendEntries {
+    pub term: u64,
+    pub leader_id: String,
+    pub prev_index: u64,
+    pub prev_term: u64,
+    pub entries: Vec<LogEntry>,
+    pub leader_commit: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct AppendReply {
+    pub term: u64,
+    pub success: bool,
+    pub match_index: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct RequestVote {
+    pub term: u64,
+    pub candidate_id: String,
+    pub last_log_index: u64,
+    pub last_log_term: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct VoteReply {
+    pub term: u64,
+    pub granted: bool,
+}
+
+// One cluster member.
+pub struct ConfigReplica {
+    id: String,
+    members: Vec<String>,
+    current_term: u64,
+    // Whom this member voted for in current_term; at most one candidate per term.
+    voted_for: Option<String>,
+    // Votes gathered while a candidate in current_term.
+    votes: BTreeSet<String>,
+    is_leader: bool,
+    log: Vec<LogEntry>,
+    commit_index: u64,
+    // The last committed entry applied to the monitor.
+    applied_index: u64,
+    next_index: BTreeMap<String, u64>,
+    match_index: BTreeMap<String, u64>,
+}
+
+impl ConfigReplica {
+    pub fn new(id: &str, members: &[&str]) -> Self {
+        ConfigReplica {
+            id: id.to_string(),
+            members: members.iter().map(|m| m.to_string()).collect(),
+            current_term: 0,
+            voted_for: None,
+            votes: BTreeSet::new(),
+            is_leader: false,
+            log: Vec::new(),
+            commit_index: 0,
+            applied_index: 0,
+            next_index: BTreeMap::new(),
+            match_index: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.is_leader
+    }
+
+    // Election timeout expired without word from a leader: stand for the next term, voting
+    // for ourselves. The request goes to every other member.
+    pub fn start_election(&mut self) -> RequestVote {
+        self.current_term += 1;
+        self.is_leader = false;
+        self.voted_for = Some(self.id.clone());
+        self.votes = BTreeSet::from([self.id.clone()]);
+        if self.votes.len() >= self.majority() {
+            self.become_leader();
+        }
+        RequestVote { term: self.current_term, candidate_id: self.id.clone(), last_log_index: self.last_index(), last_log_term: self.term_at(self.last_index()) }
+    }
+
+    // Grants at most one vote per term, and only to a candidate whose log is at least as
+    // up to date as ours, so a leader always holds every committed entry.
+    pub fn handle_request_vote(&mut self, req: RequestVote) -> VoteReply {
+        if req.term < self.current_term {
+            return VoteReply { term: self.current_term, granted: false };
+        }
+        if req.term > self.current_term {
+            self.step_down(req.term);
+        }
+        let up_to_date = (req.last_log_term, req.last_log_index) >= (self.term_at(self.last_index()), self.last_index());
+        let free = self.voted_for.as_ref().is_none_or(|v| *v == req.candidate_id);
+        let granted = up_to_date && free;
+        if granted {
+            self.voted_for = Some(req.candidate_id);
+        }
+        VoteReply { term: self.current_term, granted }
+    }
+
+    // Counts a vote; a majority in the current term makes this member leader.
+    pub fn on_vote_reply(&mut self, peer: &str, reply: VoteReply) {
+        if reply.term > self.current_term {
+            self.step_down(reply.term);
+            return;
+        }
+        let candidate = !self.is_leader && self.voted_for.as_deref() == Some(self.id.as_str());
+        if reply.term != self.current_term || !candidate || !reply.granted || !self.members.iter().any(|m| m == peer) {
+            return;
+        }
+        self.votes.insert(peer.to_string());
+        if self.votes.len() >= self.majority() {
+            self.become_leader();
+        }
+    }
+
+    fn step_down(&mut self, term: u64) {
+        self.current_term = term;
+        self.voted_for = None;
+        self.votes.clear();
+        self.is_leader = false;
+    }
+
+    fn become_leader(&mut self) {
+        self.is_leader = true;
+        self.votes.clear();
+        let next = self.last_index() + 1;
+        for m in &self.members {
+            self.next_index.insert(m.clone(), next);
+            self.match_index.insert(m.clone(), 0);
+        }
+        self.match_index.insert(self.id.clone(), self.last_index());
+    }
+
+    // The only config the monitor may act on: the newest committed entry.
+    pub fn committed_config(&self) -> Option<&MonitorConfig> {
+        self.entry(self.commit_index).map(|e| &e.config)
+    }
+
+    pub fn committed_version(&self) -> u64 {
+        self.commit_index
+    }
+
+    // Applies the newest committed config to `monitor`, once per commit, through
+    // HarmonyMonitor::apply_config: domain bounds, governance and access control are all
+    // checked before either value changes, so the monitor never runs half a config.
+    // `principal` is this node's own service identity, which applies what the cluster
+    // committed. Ok(false) when nothing new has committed. On error the entry stays unapplied
+    // and is retried on the next call.
+    pub fn apply_committed<D: Domain>(&mut self, monitor: &mut HarmonyMonitor<D>, principal: &Principal) -> Result<bool, String> {
+        if self.commit_index == self.applied_index {
+            return Ok(false);
+        }
+        let index = self.commit_index;
+        let config = self.committed_config().ok_or("committed entry missing from the log")?.clone();
+        monitor.apply_config(config.harmony_threshold, &config.weights, principal).map_err(|e| format!("config {}: {}", index, e))?;
+        self.applied_index = index;
+        Ok(true)
+    }
+
+    pub fn propose(&mut self, config: MonitorConfig) -> Option<u64> {
+        if !self.is_leader {
+            return None;
+        }
+        let index = self.last_index() + 1;
+        self.log.push(LogEntry { term: self.current_term, index, config });
+        self.match_index.insert(self.id.clone(), index);
+        self.advance_commit();
+        Some(index)
+    }
+
+    pub fn append_request_for(&self, peer: &str) -> Option<AppendEntries> {
+        if !self.is_leader || peer == self.id {
+            return None;
+        }
+        let next = *self.next_index.get(peer)?;
+        let prev_index = next - 1;
+        Some(AppendEntries {
+            term: self.current_term,
+            leader_id: self.id.clone(),
+            prev_index,
+            prev_term: self.term_at(prev_index),
+            entries: self.log.iter().filter(|e| e.index >= next).cloned().collect(),
+            leader_commit: self.commit_index,
+        })
+    }
+
+    pub fn on_append_reply(&mut self, peer: &str, reply: AppendReply) {
+        if reply.term > self.current_term {
+            self.step_down(reply.term);
+            return;
+        }
+        if reply.success {
+            self.match_index.insert(peer.to_string(), reply.match_index);
+            self.next_index.insert(peer.to_string(), reply.match_index + 1);
+            self.advance_commit();
+        } else if let Some(n) = self.next_index.get_mut(peer) {
+            *n = (*n - 1).max(1);
+        }
+    }
+
+    pub fn handle_append(&mut self, req: AppendEntries) -> AppendReply {
+        if req.term < self.current_term {
+            return AppendReply { term: self.current_term, success: false, match_index: 0 };
+        }
+        // A leader for this term exists: a candidate for it stands down.
+        if req.term > self.current_term {
+            self.step_down(req.term);
+        }
+        self.is_leader = false;
+        self.votes.clear();
+        if req.prev_index > self.last_index() || self.term_at(req.prev_index) != req.prev_term {
+            return AppendReply { term: self.current_term, success: false, match_index: 0 };
+        }
+        // Only what this request covers is known to match the leader; anything beyond it in our
+        // log may yet be overwritten.
+        let matched = req.prev_index + req.entries.len() as u64;
+        for entry in req.entries {
+            if self.entry(entry.index).is_some_and(|e| e.term != entry.term) {
+                self.log.truncate((entry.index - 1) as usize);
+            }
+            if entry.index > self.last_index() {
+                self.log.push(entry);
+            }
+        }
+        if req.leader_commit > self.commit_index {
+            self.commit_index = req.leader_commit.min(matched);
+        }
+        AppendReply { term: self.current_term, success: true, match_index: matched }
+    }
+
+    fn majority(&self) -> usize {
+        self.members.len() / 2 + 1
+    }
+
+    // Raft commit rule: majority replicated, and only entries from the current term.
+    fn advance_commit(&mut self) {
+        let majority = self.majority();
+        for n in (self.commit_index + 1..=self.last_index()).rev() {
+            let replicated = self.match_index.values().filter(|m| **m >= n).count();
+            if replicated >= majority && self.term_at(n) == self.current_term {
+                self.commit_index = n;
+                break;
+            }
+        }
+    }
+
+    fn entry(&self, index: u64) -> Option<&LogEntry> {
+        if index == 0 { None } else { self.log.get((index - 1) as usize) }
+    }
+
+    fn term_at(&self, index: u64) -> u64 {
+        self.entry(index).map_or(0, |e| e.term)
+    }
+
+    fn last_index(&self) -> u64 {
+        self.log.len() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use crate::plugin::ReferenceDomain;
+    use crate::rbac::{AccessControl, AuditEntry, AuditSink, Role};
+
+    const MEMBERS: [&str; 3] = ["a", "b", "c"];
+
+    #[derive(Clone, Default)]
+    struct Audit(Arc<Mutex<Vec<AuditEntry>>>);
+
+    impl AuditSink for Audit {
+        fn append(&mut self, entry: &AuditEntry) -> Result<(), String> {
+            self.0.lock().unwrap().push(entry.clone());
+            Ok(())
+        }
+    }
+
+    fn config(harmony_threshold: f64, weights: &[f64]) -> MonitorConfig {
+        MonitorConfig { harmony_threshold, weights: weights.to_vec() }
+    }
+
+    fn principal(role: Role) -> Principal {
+        Principal { identity: "spiffe://plant/config-replica/a".into(), roles: vec![role] }
+    }
+
+    fn elect(leader: &mut ConfigReplica, followers: &mut [&mut ConfigReplica]) {
+        let req = leader.start_election();
+        for f in followers.iter_mut() {
+            let reply = f.handle_request_vote(req.clone());
+            leader.on_vote_reply(&f.id.clone(), reply);
+        }
+        assert!(leader.is_leader());
+    }
+
+    // Replicates the leader's log to `followers` and feeds their replies back.
+    fn replicate(leader: &mut ConfigReplica, followers: &mut [&mut ConfigReplica]) {
+        for f in followers.iter_mut() {
+            let req = leader.append_request_for(&f.id).unwrap();
+            let reply = f.handle_append(req);
+            leader.on_append_reply(&f.id.clone(), reply);
+        }
+    }
+
+    // A single-node cluster committed at `config`, for the apply tests.
+    fn committed(config: MonitorConfig) -> ConfigReplica {
+        let mut replica = ConfigReplica::new("a", &["a"]);
+        replica.start_election();
+        replica.propose(config).unwrap();
+        assert_eq!(replica.committed_version(), 1);
+        replica
+    }
+
+    #[test]
+    fn one_vote_per_term_and_only_for_an_up_to_date_log() {
+        let (mut a, mut b, mut c) = (ConfigReplica::new("a", &MEMBERS), ConfigReplica::new("b", &MEMBERS), ConfigReplica::new("c", &MEMBERS));
+        let from_a = a.start_election();
+        let from_c = c.start_election();
+        assert!(b.handle_request_vote(from_a.clone()).granted);
+        assert!(!b.handle_request_vote(from_c).granted);
+        a.on_vote_reply("b", VoteReply { term: from_a.term, granted: true });
+        assert!(a.is_leader());
+        a.propose(config(0.9996, &[0.30, 0.25, 0.20, 0.15, 0.10])).unwrap();
+        replicate(&mut a, &mut [&mut b]);
+        assert_eq!(a.committed_version(), 1);
+
+        // c never saw the entry: b, which did, refuses to elect it, whatever the term.
+        let mut stale = c.start_election();
+        stale.term += 5;
+        assert!(!b.handle_request_vote(stale).granted);
+        // Votes from outsiders or for an older term do not count.
+        c.on_vote_reply("mallory", VoteReply { term: c.current_term, granted: true });
+        c.on_vote_reply("a", VoteReply { term: c.current_term - 1, granted: true });
+        assert!(!c.is_leader());
+    }
+
+    #[test]
+    fn followers_only_commit_what_they_hold() {
+        let (mut a, mut b, mut c) = (ConfigReplica::new("a", &MEMBERS), ConfigReplica::new("b", &MEMBERS), ConfigReplica::new("c", &MEMBERS));
+        elect(&mut a, &mut [&mut b, &mut c]);
+        assert_eq!(a.propose(config(0.9996, &[0.2; 5])), Some(1));
+        assert_eq!(a.committed_version(), 0);
+        replicate(&mut a, &mut [&mut b]);
+        assert_eq!(a.committed_version(), 1);
+        assert_eq!(b.committed_version(), 0);
+        assert!(b.propose(config(0.9997, &[0.2; 5])).is_none());
+
+        // A heartbeat from a deposed leader's term is refused.
+        let mut old = a.append_request_for("c").unwrap();
+        old.term = 0;
+        assert!(!c.handle_append(old).success);
+        // A request whose prev entry c lacks is refused; the full resend is accepted.
+        a.propose(config(0.9997, &[0.2; 5])).unwrap();
+        let mut gap = a.append_request_for("c").unwrap();
+        gap.prev_index = 1;
+        gap.prev_term = a.current_term;
+        gap.entries.retain(|e| e.index == 2);
+        let reply = c.handle_append(gap);
+        assert!(!reply.success);
+        a.on_append_reply("c", reply);
+        replicate(&mut a, &mut [&mut b, &mut c]);
+        assert_eq!((a.committed_version(), c.committed_version()), (2, 2));
+        assert_eq!(c.committed_config(), a.committed_config());
+    }
+
+    #[test]
+    fn a_refused_config_leaves_the_monitor_and_the_entry_unapplied() {
+        let weights = [0.25, 0.25, 0.20, 0.15, 0.15];
+        let mut replica = committed(config(0.9998, &weights));
+        let mut monitor = HarmonyMonitor::new(ReferenceDomain::new()).unwrap();
+        let before = (monitor.threshold(), monitor.context().weights().to_vec());
+        let admin = principal(Role::Admin);
+
+        assert!(replica.apply_committed(&mut monitor, &admin).unwrap_err().contains("no access control attached"));
+        let audit = Audit::default();
+        monitor.access_control(AccessControl::new(Box::new(audit.clone())));
+        assert!(replica.apply_committed(&mut monitor, &principal(Role::SafetyEngineer)).is_err());
+        assert_eq!((monitor.threshold(), monitor.context().weights().to_vec()), before);
+        assert_eq!(replica.applied_index, 0);
+
+        assert_eq!(replica.apply_committed(&mut monitor, &admin), Ok(true));
+        assert_eq!((monitor.threshold(), monitor.context().weights()), (0.9998, &weights[..]));
+        assert_eq!(replica.apply_committed(&mut monitor, &admin), Ok(false));
+        let allowed: Vec<bool> = audit.0.lock().unwrap().iter().map(|e| e.allowed).collect();
+        assert_eq!(allowed, [false, true]);
+    }
+
+    #[test]
+    fn invalid_weights_do_not_half_apply_a_valid_threshold() {
+        let mut monitor = HarmonyMonitor::new(ReferenceDomain::new()).unwrap();
+        monitor.access_control(AccessControl::new(Box::new(Audit::default())));
+        let before = monitor.context().weights().to_vec();
+        let admin = principal(Role::Admin);
+        for bad in [config(0.9998, &[0.5, 0.5, 0.5, 0.0, 0.0]), config(0.9998, &[0.5, 0.5]), config(0.5, &before)] {
+            let mut replica = committed(bad);
+            assert!(replica.apply_committed(&mut monitor, &admin).is_err());
+            assert_eq!(monitor.threshold(), 0.9995);
+            assert_eq!(monitor.context().weights(), &before[..]);
+            assert_eq!(replica.applied_index, 0);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Consistency_Checker.rs - Cross-node decision/mu comparator for redundant nodes (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::decision::DecisionRecord;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    ChannelDisagreement { nodes: Vec<String>, mu_spread: f64, cycles: u32 },
+}
+
+pub struct ConsistencyChecker {
+    mu_tolerance: f64,
+    max_cycles: u32,
+    latest: BTreeMap<String, DecisionRecord>,
+    disagreeing_cycles: u32,
+}
+
+impl ConsistencyChecker {
+    pub fn new(mu_tolerance: f64, max_cycles: u32) -> Self {
+        ConsistencyChecker { mu_tolerance, max_cycles, latest: BTreeMap::new(), disagreeing_cycles: 0 }
+    }
+
+    pub fn observe(&mut self, record: DecisionRecord) {
+        if self.latest.get(&record.node_id).is_none_or(|r| record.seq > r.seq) {
+            self.latest.insert(record.node_id.clone(), record);
+        }
+    }
+
+    // One comparison cycle. Transient disagreement (nodes sampling a cycle apart) is
+    // tolerated; the condition is raised only after `max_cycles` consecutive misses.
+    pub fn compare(&mut self) -> Option<Condition> {
+        if self.latest.len() < 2 {
+            self.disagreeing_cycles = 0;
+            return None;
+        }
+        let mus: Vec<f64> = self.latest.values().map(|r| r.mu).collect();
+        let lo = mus.iter().cloned().fold(f64::INFINITY, f64::min);
+        let hi = mus.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let spread = if mus.iter().all(|m| m.is_finite()) { hi - lo } else { f64::INFINITY };
+        let first = self.latest.values().next().map(|r| r.decision);
+        let decisions_agree = self.latest.values().all(|r| Some(r.decision) == first);
+        if decisions_agree && spread <= self.mu_tolerance {
+            self.disagreeing_cycles = 0;
+            return None;
+        }
+        self.disagreeing_cycles += 1;
+        if self.disagreeing_cycles < self.max_cycles {
+            return None;
+        }
+        Some(Condition::ChannelDisagreement {
+            nodes: self.latest.keys().cloned().collect(),
+            mu_spread: spread,
+            cycles: self.disagreeing_cycles,
+        })
+    }
+
+    // For check_ch: false while nodes are persistently disagreeing.
+    pub fn consistent(&self) -> bool {
+        self.disagreeing_cycles < self.max_cycles
+    }
+}
+
+pub async fn run_consistency_checker(mut checker: ConsistencyChecker, period: Duration) {
+    loop {
+        while let Some(record) = recv_peer_decision().await {
+            checker.observe(record);
+        }
+        if let Some(condition) = checker.compare() {
+            raise_condition(&condition).await;
+        }
+        tokio::time::sleep(period).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+    use crate::decision::Decision;
+
+    fn record(node: &str, seq: u64, mu: f64, decision: Decision) -> DecisionRecord {
+        DecisionRecord::new(node, seq, mu, true, decision, ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS).status())
+    }
+
+    #[test]
+    fn transient_disagreement_is_tolerated() {
+        let mut c = ConsistencyChecker::new(0.001, 3);
+        c.observe(record("a", 1, 0.9999, Decision::GO));
+        c.observe(record("b", 1, 0.9990, Decision::CAUTION));
+        assert_eq!(c.compare(), None);
+        assert_eq!(c.compare(), None);
+        assert!(c.consistent());
+        // b catches up before the third cycle: the count starts over.
+        c.observe(record("b", 2, 0.9998, Decision::GO));
+        assert_eq!(c.compare(), None);
+        c.observe(record("b", 3, 0.9950, Decision::GO));
+        assert_eq!(c.compare(), None);
+        assert_eq!(c.compare(), None);
+        assert!(c.consistent());
+    }
+
+    #[test]
+    fn raised_after_max_cycles() {
+        let mut c = ConsistencyChecker::new(0.001, 3);
+        c.observe(record("a", 1, 0.9999, Decision::GO));
+        c.observe(record("b", 1, 0.9950, Decision::GO));
+        assert_eq!(c.compare(), None);
+        assert_eq!(c.compare(), None);
+        match c.compare() {
+            Some(Condition::ChannelDisagreement { nodes, mu_spread, cycles }) => {
+                assert_eq!(nodes, ["a", "b"]);
+                assert!((mu_spread - 0.0049).abs() < 1e-12);
+                assert_eq!(cycles, 3);
+            }
+            None => panic!("disagreement not raised"),
+        }
+        assert!(!c.consistent());
+    }
+
+    #[test]
+    fn non_finite_mu_makes_the_spread_infinite() {
+        let mut c = ConsistencyChecker::new(0.001, 1);
+        c.observe(record("a", 1, 0.9999, Decision::HALT));
+        c.observe(record("b", 1, f64::NAN, Decision::HALT));
+        match c.compare() {
+            Some(Condition::ChannelDisagreement { mu_spread, .. }) => assert_eq!(mu_spread, f64::INFINITY),
+            None => panic!("NaN mu passed as consistent"),
+        }
+    }
+
+    #[test]
+    fn older_records_do_not_replace_newer_ones() {
+        let mut c = ConsistencyChecker::new(0.001, 1);
+        c.observe(record("a", 5, 0.9999, Decision::GO));
+        c.observe(record("b", 5, 0.9999, Decision::GO));
+        c.observe(record("b", 4, 0.5, Decision::HALT));
+        assert_eq!(c.compare(), None);
+    }
+}
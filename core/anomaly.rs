@@ -0,0 +1,157 @@
+//! Anomaly.rs - Per-channel statistical anomaly detection on score streams (forbid unsafe)
+//!
+//! A score can pass every threshold and still be wrong for this plant: a channel that has read
+//! 0.97 give or take 0.002 for a minute and suddenly reads 0.90 is worth a look long before it
+//! reaches a floor. Each channel's score is compared with its own last `window` healthy scores
+//! and its deviation reported in sigmas, for every channel (NaN until half the window is held).
+//! Method::Mad, the default, uses the median and MAD (scaled by 1.4826 to estimate a standard
+//! deviation) so the outliers it looks for do not widen the yardstick; Method::ZScore uses mean
+//! and standard deviation. The spread is floored at `min_spread` so a channel that has been
+//! flat does not alarm on its first wobble. Either direction counts. A channel more than `limit`
+//! sigmas off fails `score_anomaly`: Advisory by default, Major with escalate(), which caps the
+//! decision at CAUTION. A failed source restarts its channel's history, as in Trends.
+#![forbid(unsafe_code)]
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+use super::harmony::{Conditions, Severity};
+use super::source::SourceError;
+
+// Consistency constant so MAD estimates a standard deviation for normal data.
+const MAD_SCALE: f64 = 1.4826;
+const MIN_SAMPLES: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    ZScore,
+    Mad,
+}
+
+#[derive(Clone, Debug)]
+struct Channel {
+    history: VecDeque<f64>,
+    sigmas: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Anomalies {
+    window: usize,
+    method: Method,
+    limit: f64,
+    min_spread: f64,
+    severity: Severity,
+    channels: Vec<Channel>,
+    // Reused for the medians, so update() does not allocate once the windows are full.
+    scratch: Vec<f64>,
+}
+
+impl Anomalies {
+    // `window` in cycles.
+    pub fn new(channels: usize, window: usize) -> Self {
+        let window = window.max(MIN_SAMPLES);
+        Anomalies {
+            window,
+            method: Method::Mad,
+            limit: 6.0,
+            min_spread: 0.001,
+            severity: Severity::Advisory,
+            channels: vec![Channel { history: VecDeque::with_capacity(window), sigmas: f64::NAN }; channels],
+            scratch: Vec::with_capacity(window),
+        }
+    }
+
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    // Sigmas from the window's centre beyond which a score is anomalous.
+    pub fn limit(mut self, sigmas: f64) -> Self {
+        self.limit = sigmas;
+        self
+    }
+
+    // Smallest spread, in score units, a deviation is measured against.
+    pub fn min_spread(mut self, spread: f64) -> Self {
+        self.min_spread = spread;
+        self
+    }
+
+    // Makes `score_anomaly` Major, so an anomalous channel caps the decision at CAUTION.
+    pub fn escalate(mut self) -> Self {
+        self.severity = Severity::Major;
+        self
+    }
+
+    // This cycle's deviation in sigmas, signed; NaN while the channel lacks history.
+    pub fn sigmas(&self, channel: usize) -> f64 {
+        self.channels.get(channel).map_or(f64::NAN, |c| c.sigmas)
+    }
+
+    // Every channel's deviation, in weight order, for DecisionRecord::anomaly.
+    pub fn sigmas_into(&self, out: &mut Vec<f64>) {
+        out.clear();
+        out.extend(self.channels.iter().map(|c| c.sigmas));
+    }
+
+    // Judges this cycle's scores against each channel's history, then adds them to it.
+    pub fn update(&mut self, scores: &[f64], errors: &[(usize, SourceError)]) {
+        for (i, (score, c)) in scores.iter().zip(self.channels.iter_mut()).enumerate() {
+            if errors.iter().any(|(failed, _)| *failed == i) || !score.is_finite() {
+                c.history.clear();
+                c.sigmas = f64::NAN;
+                continue;
+            }
+            c.sigmas = if c.history.len() >= (self.window / 2).max(MIN_SAMPLES) {
+                let (centre, spread) = match self.method {
+                    Method::Mad => mad(&c.history, &mut self.scratch),
+                    Method::ZScore => mean_sd(&c.history),
+                };
+                (score - centre) / spread.max(self.min_spread)
+            } else {
+                f64::NAN
+            };
+            if c.history.len() == self.window {
+                c.history.pop_front();
+            }
+            c.history.push_back(*score);
+        }
+    }
+
+    // Records `score_anomaly`, naming each channel beyond the limit.
+    pub fn record<N: AsRef<str>>(&self, names: &[N], conditions: &mut Conditions) {
+        let mut detail: Option<String> = None;
+        for (c, name) in self.channels.iter().zip(names) {
+            // NaN never compares above, so a channel without history never fails.
+            if c.sigmas.abs() > self.limit {
+                let d = detail.get_or_insert_with(String::new);
+                let _ = write!(d, "{}{} {:+.1} sigma", if d.is_empty() { "" } else { ", " }, name.as_ref(), c.sigmas);
+            }
+        }
+        conditions.record_with("score_anomaly", self.severity, detail.is_none(), detail);
+    }
+}
+
+// (median, MAD_SCALE * median absolute deviation)
+fn mad(history: &VecDeque<f64>, scratch: &mut Vec<f64>) -> (f64, f64) {
+    scratch.clear();
+    scratch.extend(history.iter().copied());
+    let centre = median(scratch);
+    for v in scratch.iter_mut() {
+        *v = (*v - centre).abs();
+    }
+    (centre, MAD_SCALE * median(scratch))
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_unstable_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) { (values[mid - 1] + values[mid]) / 2.0 } else { values[mid] }
+}
+
+fn mean_sd(history: &VecDeque<f64>) -> (f64, f64) {
+    let n = history.len() as f64;
+    let mean = history.iter().sum::<f64>() / n;
+    let var = history.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (n - 1.0);
+    (mean, var.sqrt())
+}
@@ -0,0 +1,202 @@
+//! Availability.rs - HALT downtime against an availability budget, reported, never acted on (forbid unsafe)
+//!
+//! Safety gating costs availability; this makes the cost visible. The budget accumulates the
+//! time spent in HALT over a rolling `window` (30 days, say), attributed to what caused each
+//! HALT (the first failed Critical condition, else mu_below_threshold), and compares it with
+//! the downtime the business case allows. observe() returns Warning when HALT time crosses
+//! `warn_at` of the budget, Exhausted when it passes the budget, and Recovered once it is back
+//! under the warning line, for the host to alert on. Nothing here feeds back into a decision:
+//! an exhausted budget never relaxes a threshold or skips a HALT. Trading safety margin for
+//! uptime stays a reviewed change (sealed config, HarmonyMonitor::set_threshold).
+//!
+//! The time between two observations is charged to the earlier decision, so a stalled loop
+//! that was in HALT keeps counting. HALT time is kept in `resolution` buckets, so memory is
+//! bounded by window / resolution however often the decision flaps.
+#![forbid(unsafe_code)]
+use std::collections::VecDeque;
+use std::fmt::{self, Write};
+use std::time::Duration;
+
+use super::harmony::{Conditions, Decision, Evaluation, Severity};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BudgetAlert {
+    Warning,
+    Exhausted,
+    Recovered,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    Within,
+    Warning,
+    Exhausted,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AvailabilityStatus {
+    pub window: Duration,
+    pub budget: Duration,
+    pub halted: Duration,
+    // HALT time per cause, largest first.
+    pub by_cause: Vec<(String, Duration)>,
+}
+
+impl AvailabilityStatus {
+    // Share of the budget used; above 1 once exhausted.
+    pub fn used(&self) -> f64 {
+        if self.budget.is_zero() { f64::INFINITY } else { self.halted.as_secs_f64() / self.budget.as_secs_f64() }
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.halted > self.budget
+    }
+}
+
+impl fmt::Display for AvailabilityStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HALT {} of {} budget over {} ({:.0}%)", Hm(self.halted), Hm(self.budget), Hm(self.window), self.used() * 100.0)?;
+        for (i, (cause, d)) in self.by_cause.iter().enumerate() {
+            write!(f, "{}{} {}", if i == 0 { ": " } else { ", " }, cause, Hm(*d))?;
+        }
+        Ok(())
+    }
+}
+
+struct Hm(Duration);
+
+impl fmt::Display for Hm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = self.0.as_secs();
+        match (s / 86_400, s / 3_600 % 24, s / 60 % 60) {
+            (0, 0, m) => write!(f, "{}m {}s", m, s % 60),
+            (0, h, m) => write!(f, "{}h {}m", h, m),
+            (d, h, _) => write!(f, "{}d {}h", d, h),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Bucket {
+    start_ms: u64,
+    by_cause: Vec<(String, u64)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AvailabilityBudget {
+    window_ms: u64,
+    budget_ms: u64,
+    warn_at: f64,
+    resolution_ms: u64,
+    buckets: VecDeque<Bucket>,
+    halted_ms: u64,
+    // The last observation, with its HALT cause if it was HALT.
+    last: Option<(u64, Option<String>)>,
+    level: Level,
+}
+
+impl AvailabilityBudget {
+    // `budget` is the HALT time the business case allows per `window`.
+    pub fn new(window: Duration, budget: Duration) -> Result<Self, String> {
+        if window.is_zero() || budget > window {
+            return Err(format!("availability budget {:?} per {:?} is not a share of the window", budget, window));
+        }
+        Ok(AvailabilityBudget {
+            window_ms: window.as_millis() as u64,
+            budget_ms: budget.as_millis() as u64,
+            warn_at: 0.8,
+            resolution_ms: 60_000,
+            buckets: VecDeque::new(),
+            halted_ms: 0,
+            last: None,
+            level: Level::Within,
+        })
+    }
+
+    // Share of the budget at which Warning is raised.
+    pub fn warn_at(mut self, fraction: f64) -> Self {
+        self.warn_at = fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    // Granularity of the rolling window; HALT time leaves the window a bucket at a time.
+    pub fn resolution(mut self, resolution: Duration) -> Self {
+        self.resolution_ms = (resolution.as_millis() as u64).max(1);
+        self
+    }
+
+    // Feeds one evaluated cycle; returns an alert when the budget level changes.
+    pub fn observe(&mut self, at_ms: u64, eval: &Evaluation, conditions: &Conditions) -> Option<BudgetAlert> {
+        if let Some((prev_ms, Some(cause))) = self.last.take() {
+            self.charge(prev_ms, at_ms, cause);
+        }
+        while self.buckets.front().is_some_and(|b| b.start_ms + self.resolution_ms + self.window_ms <= at_ms) {
+            let expired = self.buckets.pop_front().expect("front checked");
+            self.halted_ms -= expired.by_cause.iter().map(|(_, ms)| ms).sum::<u64>();
+        }
+        let cause = (eval.decision == Decision::HALT)
+            .then(|| conditions.failed(Severity::Critical).next().unwrap_or("mu_below_threshold").to_string());
+        self.last = Some((at_ms, cause));
+        let level = if self.halted_ms > self.budget_ms {
+            Level::Exhausted
+        } else if self.halted_ms as f64 >= self.warn_at * self.budget_ms as f64 && self.budget_ms > 0 {
+            Level::Warning
+        } else {
+            Level::Within
+        };
+        let alert = match (self.level, level) {
+            (from, to) if to > from && to == Level::Exhausted => Some(BudgetAlert::Exhausted),
+            (from, to) if to > from => Some(BudgetAlert::Warning),
+            (from, Level::Within) if from != Level::Within => Some(BudgetAlert::Recovered),
+            _ => None,
+        };
+        self.level = level;
+        alert
+    }
+
+    fn charge(&mut self, from_ms: u64, to_ms: u64, cause: String) {
+        let elapsed = to_ms.saturating_sub(from_ms);
+        let start_ms = from_ms - from_ms % self.resolution_ms;
+        if self.buckets.back().is_none_or(|b| b.start_ms != start_ms) {
+            self.buckets.push_back(Bucket { start_ms, by_cause: Vec::new() });
+        }
+        let bucket = self.buckets.back_mut().expect("just ensured");
+        match bucket.by_cause.iter_mut().find(|(c, _)| *c == cause) {
+            Some((_, ms)) => *ms += elapsed,
+            None => bucket.by_cause.push((cause, elapsed)),
+        }
+        self.halted_ms += elapsed;
+    }
+
+    // As of the last observation.
+    pub fn status(&self) -> AvailabilityStatus {
+        let mut by_cause: Vec<(String, u64)> = Vec::new();
+        for (cause, ms) in self.buckets.iter().flat_map(|b| &b.by_cause) {
+            match by_cause.iter_mut().find(|(c, _)| c == cause) {
+                Some((_, total)) => *total += ms,
+                None => by_cause.push((cause.clone(), *ms)),
+            }
+        }
+        by_cause.sort_by_key(|c| std::cmp::Reverse(c.1));
+        AvailabilityStatus {
+            window: Duration::from_millis(self.window_ms),
+            budget: Duration::from_millis(self.budget_ms),
+            halted: Duration::from_millis(self.halted_ms),
+            by_cause: by_cause.into_iter().map(|(c, ms)| (c, Duration::from_millis(ms))).collect(),
+        }
+    }
+
+    // Prometheus text exposition, for the diagnostics /metrics endpoint.
+    pub fn metrics_text(&self) -> String {
+        let status = self.status();
+        let mut out = String::new();
+        let _ = writeln!(out, "# TYPE harmony_availability_budget_seconds gauge\nharmony_availability_budget_seconds {}", status.budget.as_secs_f64());
+        let _ = writeln!(out, "# TYPE harmony_availability_halted_seconds gauge\nharmony_availability_halted_seconds {}", status.halted.as_secs_f64());
+        let _ = writeln!(out, "# TYPE harmony_availability_budget_used gauge\nharmony_availability_budget_used {}", status.used());
+        out.push_str("# TYPE harmony_availability_halted_by_cause_seconds gauge\n");
+        for (cause, d) in &status.by_cause {
+            let _ = writeln!(out, "harmony_availability_halted_by_cause_seconds{{cause=\"{}\"}} {}", cause, d.as_secs_f64());
+        }
+        out
+    }
+}
@@ -0,0 +1,191 @@
+//! Baseline.rs - Learned daily/weekly score baselines for clock-driven channels (forbid unsafe)
+//!
+//! Some healthy scores follow the clock: FX volatility convergence dips at the open, flare
+//! stability with the afternoon wind. A channel with a Baseline is scored on its shortfall
+//! from what its time slot normally reads, raw + (1 - expected), rather than on the raw value.
+//! The credit is capped at `max_allowance`, so however long a fault persists and is learned, it
+//! can never raise a score by more than that; channels default to raw. Each slot learns one mean
+//! per visit (not per sample), so the learning rate is independent of the cycle rate, and a slot
+//! grants nothing until it has seen `warmup` periods. Applied after ScoreFilters::apply.
+#![forbid(unsafe_code)]
+use std::fmt;
+use std::time::Duration;
+
+use super::source::{Provenance, Quality, SourceError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Period {
+    Daily,
+    Weekly,
+}
+
+impl Period {
+    fn secs(self) -> u64 {
+        match self {
+            Period::Daily => 86_400,
+            Period::Weekly => 7 * 86_400,
+        }
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Period::Daily => "daily",
+            Period::Weekly => "weekly",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Slot {
+    mean: f64,
+    visits: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Baseline {
+    period: Period,
+    slot_secs: u64,
+    utc_offset_secs: i64,
+    max_allowance: f64,
+    learn_over: u32,
+    warmup: u32,
+    slots: Vec<Slot>,
+    // The slot being visited: index, sum and count of its samples so far.
+    visit: Option<(usize, f64, u32)>,
+    last_expected: Option<f64>,
+}
+
+impl Baseline {
+    // `slot` must divide the period into whole slots; `max_allowance` is in [0, 1).
+    pub fn new(period: Period, slot: Duration, max_allowance: f64) -> Result<Baseline, String> {
+        let slot_secs = slot.as_secs();
+        if slot_secs == 0 || !period.secs().is_multiple_of(slot_secs) {
+            return Err(format!("baseline slot {:?} does not divide a {} period", slot, period));
+        }
+        if !(0.0..1.0).contains(&max_allowance) {
+            return Err(format!("baseline max allowance {} is outside [0, 1)", max_allowance));
+        }
+        Ok(Baseline {
+            period,
+            slot_secs,
+            utc_offset_secs: 0,
+            max_allowance,
+            learn_over: 8,
+            warmup: 3,
+            slots: vec![Slot::default(); (period.secs() / slot_secs) as usize],
+            visit: None,
+            last_expected: None,
+        })
+    }
+
+    // Local time = UTC + offset, so slots follow the site's clock (as ProfileSchedule).
+    pub fn utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset_secs = minutes as i64 * 60;
+        self
+    }
+
+    // Each slot's mean is a running average of its first `periods` visits, then an EWMA over
+    // about that many.
+    pub fn learn_over(mut self, periods: u32) -> Self {
+        self.learn_over = periods.max(1);
+        self
+    }
+
+    pub fn warmup(mut self, periods: u32) -> Self {
+        self.warmup = periods;
+        self
+    }
+
+    fn slot(&self, unix_secs: u64) -> usize {
+        // 1970-01-01 was a Thursday; shift so a weekly period starts on Monday 00:00.
+        let local = unix_secs as i64 + self.utc_offset_secs + 3 * 86_400;
+        (local.rem_euclid(self.period.secs() as i64) as u64 / self.slot_secs) as usize
+    }
+
+    // Learned score for the slot `unix_secs` falls in, once that slot is past warmup.
+    pub fn expected(&self, unix_secs: u64) -> Option<f64> {
+        let slot = self.slots[self.slot(unix_secs)];
+        (slot.visits >= self.warmup.max(1)).then_some(slot.mean)
+    }
+
+    // Scores `raw` against the slot's baseline as learned from earlier visits, then learns it.
+    pub fn update(&mut self, raw: f64, unix_secs: u64) -> f64 {
+        let slot = self.slot(unix_secs);
+        self.last_expected = self.expected(unix_secs);
+        match &mut self.visit {
+            Some((at, sum, n)) if *at == slot => {
+                *sum += raw;
+                *n += 1;
+            }
+            _ => {
+                self.close_visit();
+                self.visit = Some((slot, raw, 1));
+            }
+        }
+        let allowance = self.last_expected.map_or(0.0, |e| (1.0 - e).clamp(0.0, self.max_allowance));
+        (raw + allowance).min(1.0)
+    }
+
+    fn close_visit(&mut self) {
+        if let Some((at, sum, n)) = self.visit.take() {
+            let slot = &mut self.slots[at];
+            slot.visits = slot.visits.saturating_add(1);
+            slot.mean += (sum / n as f64 - slot.mean) / slot.visits.min(self.learn_over) as f64;
+        }
+    }
+
+    // A cycle with no valid reading: nothing is learned and no credit is given.
+    fn skip(&mut self) {
+        self.last_expected = None;
+    }
+}
+
+// One optional baseline per weighted channel, in weight order.
+#[derive(Clone, Debug)]
+pub struct Baselines {
+    channels: Vec<Option<Baseline>>,
+}
+
+impl Baselines {
+    pub fn new(channels: usize) -> Self {
+        Baselines { channels: vec![None; channels] }
+    }
+
+    pub fn learn(mut self, channel: usize, baseline: Baseline) -> Self {
+        self.channels[channel] = Some(baseline);
+        self
+    }
+
+    // The baseline this cycle's score was measured against (None before warmup or for a raw
+    // channel), for logging next to the score.
+    pub fn expected(&self, channel: usize) -> Option<f64> {
+        self.channels.get(channel)?.as_ref()?.last_expected
+    }
+
+    // Records this cycle's adjustment on each healthy channel's provenance; call after apply().
+    pub fn annotate(&self, provenance: &mut [Provenance], scores: &[f64]) {
+        for ((p, baseline), score) in provenance.iter_mut().zip(&self.channels).zip(scores) {
+            if p.quality != Quality::Good {
+                continue;
+            }
+            if let Some(Baseline { period, last_expected: Some(e), max_allowance, .. }) = baseline {
+                p.push_transform(format_args!("baseline({}, expected={:.6}, max_allowance={})", period, e, max_allowance), *score);
+            }
+        }
+    }
+
+    // Replaces each healthy baselined channel's score with its baseline-adjusted value; a
+    // failed channel keeps its MIN_SCORE and is not learned. No allocation.
+    pub fn apply(&mut self, scores: &mut [f64], errors: &[(usize, SourceError)], unix_secs: u64) {
+        for (i, (score, baseline)) in scores.iter_mut().zip(self.channels.iter_mut()).enumerate() {
+            let Some(baseline) = baseline else { continue };
+            if errors.iter().any(|(failed, _)| *failed == i) {
+                baseline.skip();
+            } else {
+                *score = baseline.update(*score, unix_secs);
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Budget.rs - Per-cycle and per-provider CPU / allocation accounting (forbid unsafe)
+//!
+//! CPU time is the evaluating thread's CPU clock; a provider that spins or blocks shows up
+//! here before it causes missed cycles. Allocation counts need the `alloc-accounting` feature,
+//! which installs a counting global allocator (stats_alloc); without it they read zero.
+//! The allocator is process-wide, so per-provider allocation figures are exact only on the
+//! current-thread runtime.
+#![forbid(unsafe_code)]
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use nix::time::{clock_gettime, ClockId};
+
+#[cfg(feature = "alloc-accounting")]
+use stats_alloc::{StatsAlloc, INSTRUMENTED_SYSTEM};
+#[cfg(feature = "alloc-accounting")]
+use std::alloc::System;
+
+#[cfg(feature = "alloc-accounting")]
+#[global_allocator]
+static GLOBAL: &StatsAlloc<System> = &INSTRUMENTED_SYSTEM;
+
+fn thread_cpu() -> Duration {
+    clock_gettime(ClockId::CLOCK_THREAD_CPUTIME_ID).map_or(Duration::ZERO, Duration::from)
+}
+
+// (allocations, bytes allocated) since process start.
+#[cfg(feature = "alloc-accounting")]
+fn alloc_counters() -> (u64, u64) {
+    let s = GLOBAL.stats();
+    ((s.allocations + s.reallocations) as u64, s.bytes_allocated as u64)
+}
+
+#[cfg(not(feature = "alloc-accounting"))]
+fn alloc_counters() -> (u64, u64) {
+    (0, 0)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Usage {
+    pub wall: Duration,
+    pub cpu: Duration,
+    pub allocations: u64,
+    pub bytes: u64,
+}
+
+pub struct Meter {
+    wall: Instant,
+    cpu: Duration,
+    allocs: (u64, u64),
+}
+
+impl Meter {
+    pub fn start() -> Self {
+        Meter { wall: Instant::now(), cpu: thread_cpu(), allocs: alloc_counters() }
+    }
+
+    pub fn stop(self) -> Usage {
+        let (allocations, bytes) = alloc_counters();
+        Usage {
+            wall: self.wall.elapsed(),
+            cpu: thread_cpu().saturating_sub(self.cpu),
+            allocations: allocations.saturating_sub(self.allocs.0),
+            bytes: bytes.saturating_sub(self.allocs.1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Totals {
+    samples: u64,
+    cpu: Duration,
+    max_cpu: Duration,
+    wall: Duration,
+    max_wall: Duration,
+    allocations: u64,
+    bytes: u64,
+}
+
+impl Totals {
+    fn add(&mut self, u: Usage) {
+        self.samples += 1;
+        self.cpu += u.cpu;
+        self.max_cpu = self.max_cpu.max(u.cpu);
+        self.wall += u.wall;
+        self.max_wall = self.max_wall.max(u.wall);
+        self.allocations += u.allocations;
+        self.bytes += u.bytes;
+    }
+}
+
+pub struct BudgetLedger {
+    // Real-time budget for one full cycle (usually the tick).
+    budget: Duration,
+    cycles: Totals,
+    last_cycle: Usage,
+    overruns: u64,
+    providers: BTreeMap<String, Totals>,
+}
+
+impl BudgetLedger {
+    pub fn new(budget: Duration) -> Self {
+        BudgetLedger { budget, cycles: Totals::default(), last_cycle: Usage::default(), overruns: 0, providers: BTreeMap::new() }
+    }
+
+    pub fn record_provider(&mut self, name: &str, usage: Usage) {
+        // get_mut first: entry() would allocate the key on every call.
+        match self.providers.get_mut(name) {
+            Some(total) => total.add(usage),
+            None => self.providers.entry(name.to_string()).or_default().add(usage),
+        }
+    }
+
+    // Returns false when the cycle overran its budget (wall time).
+    pub fn record_cycle(&mut self, usage: Usage) -> bool {
+        self.cycles.add(usage);
+        self.last_cycle = usage;
+        let within = usage.wall <= self.budget;
+        if !within {
+            self.overruns += 1;
+        }
+        within
+    }
+
+    // Providers whose mean wall time exceeds `fraction` of the cycle budget.
+    pub fn hogs(&self, fraction: f64) -> Vec<(String, Duration)> {
+        let limit = self.budget.mul_f64(fraction);
+        self.providers
+            .iter()
+            .filter(|(_, t)| t.samples > 0)
+            .map(|(name, t)| (name.clone(), t.wall / t.samples as u32))
+            .filter(|(_, mean)| *mean > limit)
+            .collect()
+    }
+
+    // Prometheus text exposition.
+    pub fn metrics_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# TYPE harmony_cycle_budget_seconds gauge\nharmony_cycle_budget_seconds {}", self.budget.as_secs_f64());
+        let _ = writeln!(out, "# TYPE harmony_cycles_total counter\nharmony_cycles_total {}", self.cycles.samples);
+        let _ = writeln!(out, "# TYPE harmony_cycle_overruns_total counter\nharmony_cycle_overruns_total {}", self.overruns);
+        let _ = writeln!(out, "# TYPE harmony_cycle_cpu_seconds gauge\nharmony_cycle_cpu_seconds {}", self.last_cycle.cpu.as_secs_f64());
+        let _ = writeln!(out, "# TYPE harmony_cycle_cpu_seconds_max gauge\nharmony_cycle_cpu_seconds_max {}", self.cycles.max_cpu.as_secs_f64());
+        let _ = writeln!(out, "# TYPE harmony_cycle_allocations gauge\nharmony_cycle_allocations {}", self.last_cycle.allocations);
+        out.push_str("# TYPE harmony_provider_cpu_seconds_total counter\n# TYPE harmony_provider_wall_seconds_max gauge\n");
+        out.push_str("# TYPE harmony_provider_allocations_total counter\n# TYPE harmony_provider_allocated_bytes_total counter\n");
+        for (name, t) in &self.providers {
+            let _ = writeln!(out, "harmony_provider_cpu_seconds_total{{provider=\"{}\"}} {}", name, t.cpu.as_secs_f64());
+            let _ = writeln!(out, "harmony_provider_wall_seconds_max{{provider=\"{}\"}} {}", name, t.max_wall.as_secs_f64());
+            let _ = writeln!(out, "harmony_provider_allocations_total{{provider=\"{}\"}} {}", name, t.allocations);
+            let _ = writeln!(out, "harmony_provider_allocated_bytes_total{{provider=\"{}\"}} {}", name, t.bytes);
+        }
+        out
+    }
+}
@@ -0,0 +1,95 @@
+//! Cadence.rs - Rate-adaptive sampling: tick faster while mu is degraded (forbid unsafe)
+//!
+//! A domain runs at its baseline tick while mu is comfortably above the threshold and switches
+//! to the fast tick on the first cycle mu falls below `speed_up_below` (by default the GO
+//! threshold, i.e. entering the caution band) or the decision is not GO. It returns to baseline
+//! only after `recover_after` consecutive fast cycles at GO with mu at or above `recover_above`,
+//! so a mu hovering at the edge does not flap between rates.
+#![forbid(unsafe_code)]
+use std::time::Duration;
+
+use super::harmony::{Decision, Evaluation, HARMONY_THRESHOLD};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rate {
+    Baseline,
+    Fast,
+}
+
+#[derive(Clone, Debug)]
+pub struct Cadence {
+    baseline: Duration,
+    fast: Duration,
+    speed_up_below: f64,
+    recover_above: f64,
+    recover_after: u32,
+    rate: Rate,
+    recovered_cycles: u32,
+}
+
+impl Cadence {
+    pub fn new(baseline: Duration, fast: Duration) -> Result<Cadence, String> {
+        if fast.is_zero() || fast > baseline {
+            return Err(format!("fast tick {:?} must be non-zero and no slower than the {:?} baseline", fast, baseline));
+        }
+        Ok(Cadence {
+            baseline,
+            fast,
+            speed_up_below: HARMONY_THRESHOLD,
+            recover_above: HARMONY_THRESHOLD,
+            recover_after: 10,
+            rate: Rate::Baseline,
+            recovered_cycles: 0,
+        })
+    }
+
+    // mu below this switches to the fast tick; pass a level above the threshold to react to
+    // a shrinking margin before the caution band is reached.
+    pub fn speed_up_below(mut self, mu: f64) -> Self {
+        self.speed_up_below = mu;
+        self.recover_above = self.recover_above.max(mu);
+        self
+    }
+
+    // mu a fast-running domain must hold before it may slow down; never below speed_up_below.
+    pub fn recover_above(mut self, mu: f64) -> Self {
+        self.recover_above = mu.max(self.speed_up_below);
+        self
+    }
+
+    pub fn recover_after(mut self, cycles: u32) -> Self {
+        self.recover_after = cycles.max(1);
+        self
+    }
+
+    // Feeds one cycle's evaluation; returns the new rate when it changes.
+    pub fn observe(&mut self, eval: &Evaluation) -> Option<Rate> {
+        let degraded = eval.decision != Decision::GO || eval.mu.is_nan() || eval.mu < self.speed_up_below;
+        let next = match self.rate {
+            Rate::Baseline if degraded => Rate::Fast,
+            Rate::Baseline => Rate::Baseline,
+            Rate::Fast => {
+                let recovered = eval.decision == Decision::GO && eval.mu >= self.recover_above;
+                self.recovered_cycles = if recovered { self.recovered_cycles + 1 } else { 0 };
+                if self.recovered_cycles >= self.recover_after { Rate::Baseline } else { Rate::Fast }
+            }
+        };
+        if next == self.rate {
+            return None;
+        }
+        self.rate = next;
+        self.recovered_cycles = 0;
+        Some(next)
+    }
+
+    pub fn rate(&self) -> Rate {
+        self.rate
+    }
+
+    pub fn tick(&self) -> Duration {
+        match self.rate {
+            Rate::Baseline => self.baseline,
+            Rate::Fast => self.fast,
+        }
+    }
+}
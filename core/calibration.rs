@@ -0,0 +1,324 @@
+//! Calibration.rs - Per-channel curves from physical readings to [0, 1] scores (forbid unsafe)
+//!
+//! Few sensors read in health units: flare stability arrives as combustion efficiency in
+//! percent, and 96% is nowhere near 0.96 healthy. A calibrated channel's source publishes the
+//! physical reading and its curve maps it to a score before the range check, so it is the
+//! calibrated value that is filtered, baselined and weighted. A curve is `kind: x:y, x:y, ...`,
+//! e.g.
+//!   calibrate.flare_stability = piecewise: 90:0, 96:0.9, 98:0.995, 99.5:1
+//! with at least two knots, x strictly increasing and every y in [0, 1]. `piecewise` joins the
+//! knots with straight lines; `spline` is a monotone cubic (Fritsch-Carlson) that never
+//! overshoots its knots, so it stays in [0, 1] and keeps the direction of every segment. A
+//! reading beyond the end knots takes the end score; a non-finite one maps to NaN, which the
+//! SourceSet reports out of range. Every curve is round-tripped when loaded: its printed form
+//! must parse back to the same curve, and a strictly monotone curve must invert back to the
+//! readings it maps.
+#![forbid(unsafe_code)]
+use std::fmt;
+use std::fs;
+
+const MAX_KNOTS: usize = 64;
+// Reading tolerance of the inverse round trip, relative to the curve's span.
+const ROUND_TRIP_TOLERANCE: f64 = 1e-9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    Piecewise,
+    Spline,
+}
+
+impl Interpolation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Interpolation::Piecewise => "piecewise",
+            Interpolation::Spline => "spline",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve {
+    interpolation: Interpolation,
+    knots: Vec<(f64, f64)>,
+    // dy/dx at each knot; used by Spline only.
+    tangents: Vec<f64>,
+}
+
+impl Curve {
+    pub fn new(interpolation: Interpolation, knots: &[(f64, f64)]) -> Result<Curve, String> {
+        if knots.len() < 2 || knots.len() > MAX_KNOTS {
+            return Err(format!("{} knots, need 2 to {}", knots.len(), MAX_KNOTS));
+        }
+        for (i, &(x, y)) in knots.iter().enumerate() {
+            if !x.is_finite() || !(0.0..=1.0).contains(&y) {
+                return Err(format!("knot {}:{} must have a finite reading and a score in [0, 1]", x, y));
+            }
+            if i > 0 && x <= knots[i - 1].0 {
+                return Err(format!("knot {}:{} does not follow {}:{} (readings must increase)", x, y, knots[i - 1].0, knots[i - 1].1));
+            }
+        }
+        let tangents = match interpolation {
+            Interpolation::Piecewise => Vec::new(),
+            Interpolation::Spline => monotone_tangents(knots),
+        };
+        Ok(Curve { interpolation, knots: knots.to_vec(), tangents })
+    }
+
+    // `kind: x:y, x:y, ...`, the right-hand side of a `calibrate.<channel>` config line.
+    pub fn parse(spec: &str) -> Result<Curve, String> {
+        let (kind, knots) = spec.split_once(':').ok_or("expected kind: x:y, x:y, ...")?;
+        let interpolation = match kind.trim() {
+            "piecewise" => Interpolation::Piecewise,
+            "spline" => Interpolation::Spline,
+            other => return Err(format!("unknown curve kind {:?}", other)),
+        };
+        let knots = knots
+            .split(',')
+            .map(|k| {
+                let (x, y) = k.split_once(':').ok_or_else(|| format!("knot {:?} is not x:y", k.trim()))?;
+                let num = |v: &str| v.trim().parse::<f64>().map_err(|_| format!("knot {:?} is not numeric", k.trim()));
+                Ok((num(x)?, num(y)?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Curve::new(interpolation, &knots)
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    pub fn knots(&self) -> &[(f64, f64)] {
+        &self.knots
+    }
+
+    // The score for a physical reading. No allocation.
+    pub fn map(&self, reading: f64) -> f64 {
+        if !reading.is_finite() {
+            return f64::NAN;
+        }
+        let (first, last) = (self.knots[0], self.knots[self.knots.len() - 1]);
+        if reading <= first.0 {
+            return first.1;
+        }
+        if reading >= last.0 {
+            return last.1;
+        }
+        // First knot above the reading; the segment is [i - 1, i].
+        let i = self.knots.partition_point(|&(x, _)| x <= reading);
+        let ((x0, y0), (x1, y1)) = (self.knots[i - 1], self.knots[i]);
+        let h = x1 - x0;
+        let t = (reading - x0) / h;
+        let y = match self.interpolation {
+            Interpolation::Piecewise => y0 + t * (y1 - y0),
+            Interpolation::Spline => {
+                let (t2, t3) = (t * t, t * t * t);
+                (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+                    + (t3 - 2.0 * t2 + t) * h * self.tangents[i - 1]
+                    + (-2.0 * t3 + 3.0 * t2) * y1
+                    + (t3 - t2) * h * self.tangents[i]
+            }
+        };
+        y.clamp(0.0, 1.0)
+    }
+
+    // Strictly increasing or strictly decreasing scores, so every score has one reading.
+    pub fn strictly_monotone(&self) -> bool {
+        let steps = || self.knots.windows(2).map(|w| w[1].1 - w[0].1);
+        steps().all(|d| d > 0.0) || steps().all(|d| d < 0.0)
+    }
+
+    // The reading that maps to `score`, for a strictly monotone curve and a score within its
+    // range; e.g. the flare efficiency at which the channel reaches the CAUTION threshold.
+    pub fn invert(&self, score: f64) -> Option<f64> {
+        if !self.strictly_monotone() {
+            return None;
+        }
+        let rising = self.knots[1].1 > self.knots[0].1;
+        let segment = self.knots.windows(2).position(|w| {
+            let (lo, hi) = if rising { (w[0].1, w[1].1) } else { (w[1].1, w[0].1) };
+            (lo..=hi).contains(&score)
+        })?;
+        let (mut lo, mut hi) = (self.knots[segment].0, self.knots[segment + 1].0);
+        // Bisection: every segment of a monotone curve is monotone, spline included.
+        for _ in 0..100 {
+            let mid = (lo + hi) / 2.0;
+            if (self.map(mid) < score) == rising {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        Some((lo + hi) / 2.0)
+    }
+
+    // The load-time round trip: the printed curve parses back to this one, and on a strictly
+    // monotone curve invert(map(x)) returns x at every knot and segment midpoint.
+    pub fn round_trip(&self) -> Result<(), String> {
+        let printed = self.to_string();
+        match Curve::parse(&printed) {
+            Ok(c) if c == *self => {}
+            Ok(c) => return Err(format!("{} parses back as {}", printed, c)),
+            Err(e) => return Err(format!("{} does not parse back: {}", printed, e)),
+        }
+        if !self.strictly_monotone() {
+            return Ok(());
+        }
+        let span = self.knots[self.knots.len() - 1].0 - self.knots[0].0;
+        let midpoints = self.knots.windows(2).map(|w| (w[0].0 + w[1].0) / 2.0);
+        for x in self.knots.iter().map(|k| k.0).chain(midpoints) {
+            let back = self.invert(self.map(x));
+            if back.is_none_or(|b| (b - x).abs() > ROUND_TRIP_TOLERANCE * span) {
+                return Err(format!("{}: reading {} maps to {} and inverts to {:?}", printed, x, self.map(x), back));
+            }
+        }
+        Ok(())
+    }
+}
+
+// `{}` on an f64 prints the shortest form that parses back exactly, so this round-trips.
+impl fmt::Display for Curve {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:", self.interpolation.as_str())?;
+        for (i, (x, y)) in self.knots.iter().enumerate() {
+            write!(f, "{} {}:{}", if i > 0 { "," } else { "" }, x, y)?;
+        }
+        Ok(())
+    }
+}
+
+// Fritsch-Carlson: secant-averaged tangents, zeroed at local extrema and flat segments and
+// scaled down where they would overshoot the segment's monotone range.
+fn monotone_tangents(knots: &[(f64, f64)]) -> Vec<f64> {
+    let n = knots.len();
+    let secants: Vec<f64> = knots.windows(2).map(|w| (w[1].1 - w[0].1) / (w[1].0 - w[0].0)).collect();
+    let mut m = vec![0.0; n];
+    m[0] = secants[0];
+    m[n - 1] = secants[n - 2];
+    for i in 1..n - 1 {
+        m[i] = if secants[i - 1] * secants[i] <= 0.0 { 0.0 } else { (secants[i - 1] + secants[i]) / 2.0 };
+    }
+    for (k, &d) in secants.iter().enumerate() {
+        if d == 0.0 {
+            m[k] = 0.0;
+            m[k + 1] = 0.0;
+            continue;
+        }
+        let (a, b) = (m[k] / d, m[k + 1] / d);
+        let r = a * a + b * b;
+        if r > 9.0 {
+            let tau = 3.0 / r.sqrt();
+            m[k] = tau * a * d;
+            m[k + 1] = tau * b * d;
+        }
+    }
+    m
+}
+
+// One channel's curve, as loaded from config.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calibration {
+    pub channel: String,
+    pub curve: Curve,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Calibrations {
+    curves: Vec<Calibration>,
+}
+
+impl Calibrations {
+    // `specs` are (channel, "kind: x:y, ...") pairs, as loaded from config; every channel must
+    // be one of `known`, calibrated at most once, and its curve must round-trip.
+    pub fn parse(specs: &[(String, String)], known: &[&str]) -> Result<Calibrations, String> {
+        let mut curves: Vec<Calibration> = Vec::with_capacity(specs.len());
+        for (channel, spec) in specs {
+            if !known.contains(&channel.as_str()) {
+                return Err(format!("calibrate {}: no such channel", channel));
+            }
+            if curves.iter().any(|c| c.channel == *channel) {
+                return Err(format!("calibrate {}: defined twice", channel));
+            }
+            let curve = Curve::parse(spec).and_then(|c| c.round_trip().map(|_| c)).map_err(|e| format!("calibrate {}: {}", channel, e))?;
+            curves.push(Calibration { channel: channel.clone(), curve });
+        }
+        Ok(Calibrations { curves })
+    }
+
+    // A file of `calibrate.<channel> = kind: x:y, ...` lines (`#` comments), the same keys as
+    // the sealed engine config, for monitors without one.
+    pub fn load(path: &str, known: &[&str]) -> Result<Calibrations, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut specs = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (k, v) = line.split_once('=').ok_or_else(|| format!("{}: line {}: expected key = value", path, i + 1))?;
+            let channel = k.trim().strip_prefix("calibrate.").filter(|c| !c.is_empty());
+            let channel = channel.ok_or_else(|| format!("{}: line {}: unknown key {}", path, i + 1, k.trim()))?;
+            specs.push((channel.to_string(), v.trim().to_string()));
+        }
+        Calibrations::parse(&specs, known).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Calibration> {
+        self.curves.iter()
+    }
+
+    pub fn curve(&self, channel: &str) -> Option<&Curve> {
+        self.curves.iter().find(|c| c.channel == channel).map(|c| &c.curve)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLARE: &str = "piecewise: 90:0, 96:0.9, 98:0.995, 99.5:1";
+
+    #[test]
+    fn printed_curve_parses_back() {
+        for spec in [FLARE, "spline: 90:0, 96:0.9, 98:0.995, 99.5:1", "piecewise: -1.5:1, 0.1:0.3, 7e3:0"] {
+            let curve = Curve::parse(spec).unwrap();
+            assert_eq!(Curve::parse(&curve.to_string()).unwrap(), curve);
+        }
+    }
+
+    #[test]
+    fn invert_undoes_map() {
+        for spec in [FLARE, "spline: 90:0, 96:0.9, 98:0.995, 99.5:1", "spline: 0:1, 10:0.4, 20:0"] {
+            let curve = Curve::parse(spec).unwrap();
+            let (lo, hi) = (curve.knots()[0].0, curve.knots()[curve.knots().len() - 1].0);
+            for i in 0..=100 {
+                let x = lo + (hi - lo) * i as f64 / 100.0;
+                let back = curve.invert(curve.map(x)).unwrap();
+                assert!((back - x).abs() < 1e-9 * (hi - lo), "{}: {} -> {} -> {}", spec, x, curve.map(x), back);
+            }
+            assert!(curve.round_trip().is_ok());
+        }
+    }
+
+    #[test]
+    fn flare_percent_maps_to_scores() {
+        let curve = Curve::parse(FLARE).unwrap();
+        assert_eq!(curve.map(85.0), 0.0);
+        assert_eq!(curve.map(93.0), 0.45);
+        assert_eq!(curve.map(100.0), 1.0);
+        assert!(curve.map(f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn not_monotone_has_no_inverse() {
+        let curve = Curve::parse("piecewise: 0:0, 1:1, 2:0").unwrap();
+        assert_eq!(curve.invert(0.5), None);
+        assert!(curve.round_trip().is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_specs() {
+        for spec in ["piecewise: 1:0", "piecewise: 2:0, 1:1", "piecewise: 0:0, 1:1.5", "cubic: 0:0, 1:1", "piecewise: 0:0, x:1"] {
+            assert!(Curve::parse(spec).is_err(), "{}", spec);
+        }
+    }
+}
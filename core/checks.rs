@@ -0,0 +1,180 @@
+//! Checks.rs - CHCheck trait and registry of named interlocks with reasons (forbid unsafe)
+//!
+//! Every check reports a CheckOutcome, not a bare bool, and the registry returns the full list
+//! as Conditions so a HALT line can name the interlock: "kill_switch_reachable=false".
+#![forbid(unsafe_code)]
+use std::future::Future;
+
+use async_trait::async_trait;
+
+use super::harmony::{Conditions, Severity};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CheckOutcome {
+    pub pass: bool,
+    // Why it failed (or passed with a caveat), in words.
+    pub reason: Option<String>,
+    // What was measured, e.g. "h2s_ppm=12.4".
+    pub evidence: Option<String>,
+}
+
+impl CheckOutcome {
+    pub fn pass() -> Self {
+        CheckOutcome { pass: true, reason: None, evidence: None }
+    }
+
+    pub fn fail(reason: &str) -> Self {
+        CheckOutcome { pass: false, reason: Some(reason.to_string()), evidence: None }
+    }
+
+    pub fn from_bool(pass: bool) -> Self {
+        CheckOutcome { pass, reason: None, evidence: None }
+    }
+
+    pub fn with_evidence(mut self, evidence: &str) -> Self {
+        self.evidence = Some(evidence.to_string());
+        self
+    }
+}
+
+#[async_trait]
+pub trait CHCheck: Send + Sync {
+    fn name(&self) -> &str;
+    fn severity(&self) -> Severity {
+        Severity::Critical
+    }
+    async fn check(&self) -> CheckOutcome;
+
+    // Synchronous answer, when the check has one; lets the registry skip the boxed future.
+    fn check_now(&self) -> Option<CheckOutcome> {
+        None
+    }
+}
+
+// Adapts an `async fn() -> bool` interlock.
+pub struct FnCheck<F> {
+    name: String,
+    severity: Severity,
+    check: F,
+}
+
+impl<F> FnCheck<F> {
+    pub fn new(name: &str, check: F) -> Self {
+        FnCheck { name: name.to_string(), severity: Severity::Critical, check }
+    }
+
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+}
+
+#[async_trait]
+impl<F, Fut> CHCheck for FnCheck<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = bool> + Send,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    async fn check(&self) -> CheckOutcome {
+        CheckOutcome::from_bool((self.check)().await)
+    }
+}
+
+// Adapts a synchronous `fn() -> bool` interlock.
+pub struct SyncCheck<F> {
+    name: String,
+    severity: Severity,
+    check: F,
+}
+
+impl<F> SyncCheck<F> {
+    pub fn new(name: &str, check: F) -> Self {
+        SyncCheck { name: name.to_string(), severity: Severity::Critical, check }
+    }
+
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+}
+
+#[async_trait]
+impl<F> CHCheck for SyncCheck<F>
+where
+    F: Fn() -> bool + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    async fn check(&self) -> CheckOutcome {
+        CheckOutcome::from_bool((self.check)())
+    }
+
+    fn check_now(&self) -> Option<CheckOutcome> {
+        Some(CheckOutcome::from_bool((self.check)()))
+    }
+}
+
+#[derive(Default)]
+pub struct CheckRegistry {
+    checks: Vec<Box<dyn CHCheck>>,
+}
+
+impl CheckRegistry {
+    pub fn new() -> Self {
+        CheckRegistry::default()
+    }
+
+    pub fn register(&mut self, check: Box<dyn CHCheck>) -> &mut Self {
+        self.checks.push(check);
+        self
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.checks.iter().map(|c| c.name()).collect()
+    }
+
+    // A sealed config's `conditions` must name exactly the registered checks: an interlock the
+    // config does not list, or a listed one the build does not have, is refused.
+    pub fn require(&self, sealed: &[String]) -> Result<(), String> {
+        if let Some(missing) = sealed.iter().find(|n| !self.checks.iter().any(|c| c.name() == n.as_str())) {
+            return Err(format!("sealed condition {} is not a registered check", missing));
+        }
+        if let Some(unlisted) = self.checks.iter().find(|c| !sealed.iter().any(|n| n == c.name())) {
+            return Err(format!("check {} is not in the sealed conditions", unlisted.name()));
+        }
+        Ok(())
+    }
+
+    // Runs every check (no short-circuit, so the log shows all failing interlocks).
+    pub async fn run(&self) -> Conditions {
+        let mut conditions = Conditions::new();
+        self.run_into(&mut conditions).await;
+        conditions
+    }
+
+    // run() into a set kept across cycles; allocation-free when every check answers check_now().
+    pub async fn run_into(&self, conditions: &mut Conditions) {
+        conditions.clear();
+        for c in &self.checks {
+            let outcome = match c.check_now() {
+                Some(outcome) => outcome,
+                None => c.check().await,
+            };
+            conditions.record_outcome(c.name(), c.severity(), outcome);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Fault_Injection.rs - Runtime fault injection for providers and conditions (forbid unsafe)
+//!
+//! Compiled only under the `chaos` feature (and in tests); SourceSet::with_chaos routes every
+//! sample through score(), so a drill exercises the same path a real fault would.
+#![forbid(unsafe_code)]
+use std::collections::BTreeMap;
+use std::env;
+
+// Chaos mode must be switched on explicitly; a production node ignores every injection.
+const CHAOS_ENV: &str = "HARMONY_CHAOS";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fault {
+    Timeout,                  // provider never answers
+    NaN,                      // provider answers NaN
+    Stuck,                    // provider holds the first value it saw after injection, or lower
+    Constant(f64),            // provider answers a fixed value, or lower
+    Flapping { period: u32 }, // toggles every `period` evaluations
+    ForceFail,                // condition fails, provider answers 0.0
+}
+
+struct Injection {
+    fault: Fault,
+    calls: u32,
+    stuck: Option<f64>,
+}
+
+pub struct FaultInjector {
+    enabled: bool,
+    active: BTreeMap<String, Injection>,
+}
+
+impl FaultInjector {
+    pub fn new(enabled: bool) -> Self {
+        FaultInjector { enabled, active: BTreeMap::new() }
+    }
+
+    pub fn from_env() -> Self {
+        FaultInjector::new(env::var(CHAOS_ENV).is_ok_and(|v| v == "1"))
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn inject(&mut self, target: &str, fault: Fault) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        println!("Chaos: inject {:?} into {}", fault, target);
+        self.active.insert(target.to_string(), Injection { fault, calls: 0, stuck: None });
+        true
+    }
+
+    pub fn clear(&mut self, target: &str) {
+        self.active.remove(target);
+    }
+
+    pub fn clear_all(&mut self) {
+        self.active.clear();
+    }
+
+    // Wraps a provider reading; None models a timeout. As for conditions, an injected fault can
+    // only make a reading worse: Stuck and Constant cap the real value, they never raise it.
+    pub fn score(&mut self, target: &str, raw: Option<f64>) -> Option<f64> {
+        let Some(inj) = self.active.get_mut(target) else { return raw };
+        inj.calls += 1;
+        match inj.fault {
+            Fault::Timeout => None,
+            Fault::NaN => Some(f64::NAN),
+            Fault::Stuck => {
+                if inj.stuck.is_none() {
+                    inj.stuck = raw;
+                }
+                inj.stuck.map_or(raw, |v| capped(raw, v))
+            }
+            Fault::Constant(v) => capped(raw, v),
+            Fault::Flapping { period } => {
+                if (inj.calls / period.max(1)) % 2 == 1 { None } else { raw }
+            }
+            Fault::ForceFail => Some(0.0),
+        }
+    }
+
+    // Wraps a condition result. An injected fault can only fail a condition, never pass one
+    // that really failed, so injection cannot mask a genuine interlock trip.
+    pub fn condition(&mut self, target: &str, raw: bool) -> bool {
+        let Some(inj) = self.active.get_mut(target) else { return raw };
+        inj.calls += 1;
+        let injected = match inj.fault {
+            Fault::Flapping { period } => (inj.calls / period.max(1)) % 2 == 0,
+            Fault::Stuck => *inj.stuck.get_or_insert(if raw { 1.0 } else { 0.0 }) >= 1.0,
+            Fault::Constant(v) => v >= 1.0,
+            Fault::Timeout | Fault::NaN | Fault::ForceFail => false,
+        };
+        raw && injected
+    }
+}
+
+// The lower of the real reading and the injected one; a timeout or NaN reading stays as it is
+// (f64::min would otherwise replace a NaN).
+fn capped(raw: Option<f64>, v: f64) -> Option<f64> {
+    raw.map(|r| if r.is_nan() { r } else { r.min(v) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn injector(target: &str, fault: Fault) -> FaultInjector {
+        let mut chaos = FaultInjector::new(true);
+        assert!(chaos.inject(target, fault));
+        chaos
+    }
+
+    #[test]
+    fn constant_and_stuck_only_lower_a_reading() {
+        let mut chaos = injector("flux", Fault::Constant(0.9));
+        assert_eq!(chaos.score("flux", Some(0.99)), Some(0.9));
+        assert_eq!(chaos.score("flux", Some(0.2)), Some(0.2));
+        assert_eq!(chaos.score("flux", None), None);
+        assert!(chaos.score("flux", Some(f64::NAN)).unwrap().is_nan());
+        assert_eq!(chaos.score("other", Some(0.99)), Some(0.99));
+
+        let mut chaos = injector("flux", Fault::Stuck);
+        assert_eq!(chaos.score("flux", Some(0.95)), Some(0.95));
+        assert_eq!(chaos.score("flux", Some(0.99)), Some(0.95));
+        assert_eq!(chaos.score("flux", Some(0.3)), Some(0.3));
+        assert_eq!(chaos.score("flux", None), None);
+    }
+
+    #[test]
+    fn disabled_injector_ignores_every_injection() {
+        let mut chaos = FaultInjector::new(false);
+        assert!(!chaos.inject("flux", Fault::Timeout));
+        assert_eq!(chaos.score("flux", Some(0.99)), Some(0.99));
+        assert!(chaos.condition("flux", true));
+    }
+
+    #[test]
+    fn injected_condition_faults_never_pass_a_failed_condition() {
+        for fault in [Fault::Constant(1.0), Fault::Stuck, Fault::Flapping { period: 1 }] {
+            let mut chaos = injector("interlock", fault);
+            assert!((0..4).all(|_| !chaos.condition("interlock", false)));
+        }
+        let mut chaos = injector("interlock", Fault::ForceFail);
+        assert!(!chaos.condition("interlock", true));
+    }
+}
@@ -0,0 +1,195 @@
+//! Filter.rs - Optional per-channel smoothing / estimation of raw scores before mu (forbid unsafe)
+//!
+//! Applied in place between SourceSet::sample_into and evaluate. Channels default to raw. A
+//! channel whose source failed this cycle keeps its MIN_SCORE and its filter restarts, so
+//! smoothing never masks a failed source; it only delays a HALT on a real, sustained drop (by
+//! about one EWMA half-life, or a few 1/gain cycles for a Kalman channel), which bounds how
+//! heavily a channel may be filtered.
+#![forbid(unsafe_code)]
+use std::time::{Duration, Instant};
+
+use super::source::{Provenance, Quality, SourceError};
+
+// Time-based EWMA: a sample `half_life` old carries half the weight, whatever the cycle rate.
+#[derive(Clone, Debug)]
+pub struct Ewma {
+    half_life: Duration,
+    state: Option<(f64, Instant)>,
+}
+
+impl Ewma {
+    pub fn new(half_life: Duration) -> Self {
+        Ewma { half_life, state: None }
+    }
+
+    pub fn update(&mut self, x: f64, now: Instant) -> f64 {
+        let v = match self.state {
+            Some((prev, at)) if !self.half_life.is_zero() => {
+                let dt = now.saturating_duration_since(at).as_secs_f64();
+                let alpha = 1.0 - 0.5f64.powf(dt / self.half_life.as_secs_f64());
+                prev + alpha * (x - prev)
+            }
+            _ => x,
+        };
+        self.state = Some((v, now));
+        v
+    }
+
+    pub fn reset(&mut self) {
+        self.state = None;
+    }
+}
+
+// Scalar Kalman filter over a random-walk model: the score drifts by `process_noise` variance
+// per cycle and each reading carries `measurement_noise` variance. The first reading seeds it.
+#[derive(Clone, Debug)]
+pub struct Kalman1D {
+    process_noise: f64,
+    measurement_noise: f64,
+    estimate: Option<(f64, f64)>,
+    last: Option<Innovation>,
+}
+
+// What the last update saw: the filtered value, how far the reading was from the prediction,
+// and that distance's expected variance (so |innovation| > 3 * variance.sqrt() is an outlier).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Innovation {
+    pub estimate: f64,
+    pub innovation: f64,
+    pub variance: f64,
+}
+
+impl Innovation {
+    pub fn sigmas(&self) -> f64 {
+        if self.variance > 0.0 { self.innovation.abs() / self.variance.sqrt() } else { 0.0 }
+    }
+}
+
+impl Kalman1D {
+    pub fn new(process_noise: f64, measurement_noise: f64) -> Self {
+        Kalman1D { process_noise, measurement_noise, estimate: None, last: None }
+    }
+
+    pub fn update(&mut self, z: f64) -> f64 {
+        let (x, p) = match self.estimate {
+            None => (z, self.measurement_noise),
+            Some((x, p)) => {
+                let p_pred = p + self.process_noise;
+                let variance = p_pred + self.measurement_noise;
+                let gain = p_pred / variance;
+                let innovation = z - x;
+                let x = x + gain * innovation;
+                self.last = Some(Innovation { estimate: x, innovation, variance });
+                (x, (1.0 - gain) * p_pred)
+            }
+        };
+        self.estimate = Some((x, p));
+        x
+    }
+
+    pub fn last(&self) -> Option<Innovation> {
+        self.last
+    }
+
+    // Standard deviation of the current estimate (sqrt of the posterior variance).
+    pub fn stddev(&self) -> Option<f64> {
+        self.estimate.map(|(_, p)| p.sqrt())
+    }
+
+    pub fn reset(&mut self) {
+        self.estimate = None;
+        self.last = None;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ChannelFilter {
+    Raw,
+    Ewma(Ewma),
+    Kalman(Kalman1D),
+}
+
+impl ChannelFilter {
+    fn update(&mut self, x: f64, now: Instant) -> f64 {
+        match self {
+            ChannelFilter::Raw => x,
+            ChannelFilter::Ewma(f) => f.update(x, now),
+            ChannelFilter::Kalman(f) => f.update(x),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            ChannelFilter::Raw => {}
+            ChannelFilter::Ewma(f) => f.reset(),
+            ChannelFilter::Kalman(f) => f.reset(),
+        }
+    }
+}
+
+// One filter per weighted channel, in weight order.
+#[derive(Clone, Debug)]
+pub struct ScoreFilters {
+    filters: Vec<ChannelFilter>,
+}
+
+impl ScoreFilters {
+    pub fn new(channels: usize) -> Self {
+        ScoreFilters { filters: vec![ChannelFilter::Raw; channels] }
+    }
+
+    pub fn ewma(mut self, channel: usize, half_life: Duration) -> Self {
+        self.filters[channel] = ChannelFilter::Ewma(Ewma::new(half_life));
+        self
+    }
+
+    pub fn kalman(mut self, channel: usize, process_noise: f64, measurement_noise: f64) -> Self {
+        self.filters[channel] = ChannelFilter::Kalman(Kalman1D::new(process_noise, measurement_noise));
+        self
+    }
+
+    // This cycle's innovation for a Kalman channel (None for other filters or before the
+    // second reading), for logging next to the raw and filtered values.
+    pub fn innovation(&self, channel: usize) -> Option<Innovation> {
+        match self.filters.get(channel)? {
+            ChannelFilter::Kalman(f) => f.last(),
+            _ => None,
+        }
+    }
+
+    // Uncertainty of a channel's filtered score: a Kalman channel's estimate stddev, else 0
+    // (raw and EWMA channels carry no error model).
+    pub fn stddev(&self, channel: usize) -> f64 {
+        match self.filters.get(channel) {
+            Some(ChannelFilter::Kalman(f)) => f.stddev().unwrap_or(0.0),
+            _ => 0.0,
+        }
+    }
+
+    // Records this cycle's filter step on each healthy channel's provenance; call after apply().
+    pub fn annotate(&self, provenance: &mut [Provenance], scores: &[f64]) {
+        for ((p, filter), score) in provenance.iter_mut().zip(&self.filters).zip(scores) {
+            if p.quality != Quality::Good {
+                continue;
+            }
+            match filter {
+                ChannelFilter::Raw => {}
+                ChannelFilter::Ewma(f) => p.push_transform(format_args!("ewma(half_life={:?})", f.half_life), *score),
+                ChannelFilter::Kalman(f) => {
+                    p.push_transform(format_args!("kalman(q={:e}, r={:e})", f.process_noise, f.measurement_noise), *score)
+                }
+            }
+        }
+    }
+
+    // Replaces each healthy channel's raw score with its filtered value; no allocation.
+    pub fn apply(&mut self, scores: &mut [f64], errors: &[(usize, SourceError)], now: Instant) {
+        for (i, (score, filter)) in scores.iter_mut().zip(self.filters.iter_mut()).enumerate() {
+            if errors.iter().any(|(failed, _)| *failed == i) {
+                filter.reset();
+            } else {
+                *score = filter.update(*score, now).clamp(0.0, 1.0);
+            }
+        }
+    }
+}
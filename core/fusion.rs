@@ -0,0 +1,239 @@
+//! Fusion.rs - Bayesian fusion of per-channel evidence into P(system healthy) (forbid unsafe)
+//!
+//! mu asks every channel for a score on one scale. Fusion instead asks how likely each reading
+//! is if the system is healthy and if it is faulted, so evidence of different kinds combines
+//! on its merits: a drift score with a known spread (Likelihood::Gaussian), a bounded score
+//! (Likelihood::Beta), a pass/fail detector with known hit and false-alarm rates
+//! (Likelihood::Binary). Each cycle the posterior is first carried forward through a two-state
+//! prior (a healthy system faults with `p_fault`, a faulted one recovers with `p_recover`) and
+//! then updated with every channel's log-likelihood ratio times its weight; weights below 1
+//! discount channels that are known to move together. One channel moves the odds by at most
+//! MAX_LOG_RATIO, and the posterior is held within [`floor`, 1 - `floor`] so neither state
+//! becomes absorbing. A failed or non-finite source counts as the strongest evidence of a fault
+//! (-MAX_LOG_RATIO, whatever its weight): an unreadable channel must never look healthy.
+//!
+//! The posterior is tiered like mu against its own GO and CAUTION probabilities.
+//! FusionMode::Alongside takes the more severe of that and the mu decision;
+//! FusionMode::Instead decides on the posterior alone. Conditions cap the decision either way.
+#![forbid(unsafe_code)]
+use std::fmt::Write;
+
+use super::harmony::{Conditions, Decision, Evaluation};
+use super::source::SourceError;
+use crate::decision_kernel::decide_tiered;
+
+// ln 10^5: no single reading shifts the odds by more than a factor of 100 000.
+pub const MAX_LOG_RATIO: f64 = 11.512_925_464_970_229;
+// Beta densities are evaluated this far inside (0, 1) so a score of exactly 0 or 1 stays finite.
+const BETA_EDGE: f64 = 1e-9;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Likelihood {
+    // Score ~ N(healthy, sd) when healthy, N(faulted, sd) when faulted.
+    Gaussian { healthy: f64, faulted: f64, sd: f64 },
+    // Score ~ Beta(alpha, beta) under each state.
+    Beta { healthy: (f64, f64), faulted: (f64, f64) },
+    // Passes when score >= pass_at; `detection` is P(fail | faulted), `false_alarm` P(fail | healthy).
+    Binary { pass_at: f64, detection: f64, false_alarm: f64 },
+}
+
+impl Likelihood {
+    fn validate(&self) -> Result<(), String> {
+        let ok = match *self {
+            Likelihood::Gaussian { healthy, faulted, sd } => healthy.is_finite() && faulted.is_finite() && sd > 0.0 && sd.is_finite(),
+            Likelihood::Beta { healthy: (ha, hb), faulted: (fa, fb) } => [ha, hb, fa, fb].iter().all(|p| *p > 0.0 && p.is_finite()),
+            Likelihood::Binary { pass_at, detection, false_alarm } => {
+                pass_at.is_finite() && detection > 0.0 && detection < 1.0 && false_alarm > 0.0 && false_alarm < 1.0
+            }
+        };
+        if ok { Ok(()) } else { Err(format!("invalid likelihood {:?}", self)) }
+    }
+
+    // ln p(score | healthy) - ln p(score | faulted), before the MAX_LOG_RATIO clamp.
+    pub fn log_ratio(&self, score: f64) -> f64 {
+        match *self {
+            Likelihood::Gaussian { healthy, faulted, sd } => ((score - faulted).powi(2) - (score - healthy).powi(2)) / (2.0 * sd * sd),
+            Likelihood::Beta { healthy, faulted } => {
+                let s = score.clamp(BETA_EDGE, 1.0 - BETA_EDGE);
+                ln_beta_pdf(s, healthy) - ln_beta_pdf(s, faulted)
+            }
+            Likelihood::Binary { pass_at, detection, false_alarm } => {
+                if score >= pass_at {
+                    ((1.0 - false_alarm) / (1.0 - detection)).ln()
+                } else {
+                    (false_alarm / detection).ln()
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FusionMode {
+    Alongside,
+    Instead,
+}
+
+impl FusionMode {
+    pub fn parse(s: &str) -> Result<FusionMode, String> {
+        match s.trim() {
+            "alongside" => Ok(FusionMode::Alongside),
+            "instead" => Ok(FusionMode::Instead),
+            other => Err(format!("unknown fusion mode {:?}, expected alongside or instead", other)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Fusion {
+    likelihoods: Vec<Likelihood>,
+    weights: Vec<f64>,
+    p_fault: f64,
+    p_recover: f64,
+    floor: f64,
+    go: f64,
+    caution: f64,
+    posterior: f64,
+    // This cycle's weighted, clamped contribution per channel; -MAX_LOG_RATIO for a failed source.
+    log_ratios: Vec<f64>,
+}
+
+impl Fusion {
+    // One likelihood per channel, in weight order; every channel weighs 1 until weights().
+    pub fn new(likelihoods: Vec<Likelihood>) -> Result<Self, String> {
+        for l in &likelihoods {
+            l.validate()?;
+        }
+        let n = likelihoods.len();
+        Ok(Fusion {
+            likelihoods,
+            weights: vec![1.0; n],
+            p_fault: 1e-4,
+            p_recover: 1e-6,
+            floor: 1e-6,
+            go: 0.999,
+            caution: 0.99,
+            posterior: 0.5,
+            log_ratios: vec![0.0; n],
+        })
+    }
+
+    pub fn weights(mut self, weights: Vec<f64>) -> Result<Self, String> {
+        if weights.len() != self.likelihoods.len() {
+            return Err(format!("{} fusion weights for {} channels", weights.len(), self.likelihoods.len()));
+        }
+        if let Some(w) = weights.iter().find(|w| !(w.is_finite() && **w >= 0.0)) {
+            return Err(format!("fusion weight {} must be finite and non-negative", w));
+        }
+        self.weights = weights;
+        Ok(self)
+    }
+
+    // Per-cycle probabilities of the healthy -> faulted and faulted -> healthy transitions.
+    pub fn transition(mut self, p_fault: f64, p_recover: f64) -> Result<Self, String> {
+        if !((0.0..1.0).contains(&p_fault) && (0.0..1.0).contains(&p_recover)) {
+            return Err(format!("fusion transition probabilities ({}, {}) must be in [0, 1)", p_fault, p_recover));
+        }
+        self.p_fault = p_fault;
+        self.p_recover = p_recover;
+        Ok(self)
+    }
+
+    // GO at or above `go`, CAUTION at or above `caution`, HALT below.
+    pub fn thresholds(mut self, go: f64, caution: f64) -> Result<Self, String> {
+        if !(0.0 < caution && caution <= go && go < 1.0) {
+            return Err(format!("fusion thresholds need 0 < caution ({}) <= go ({}) < 1", caution, go));
+        }
+        self.go = go;
+        self.caution = caution;
+        Ok(self)
+    }
+
+    // Posterior before the first update; 0.5 (no opinion) by default.
+    pub fn prior(mut self, p_healthy: f64) -> Self {
+        self.posterior = p_healthy.clamp(self.floor, 1.0 - self.floor);
+        self
+    }
+
+    pub fn posterior(&self) -> f64 {
+        self.posterior
+    }
+
+    pub fn log_ratios(&self) -> &[f64] {
+        &self.log_ratios
+    }
+
+    // Carries the posterior forward one cycle, then folds in this cycle's scores.
+    pub fn update(&mut self, scores: &[f64], errors: &[(usize, SourceError)]) -> f64 {
+        let p = self.posterior * (1.0 - self.p_fault) + (1.0 - self.posterior) * self.p_recover;
+        let mut log_odds = (p / (1.0 - p)).ln();
+        for (i, (l, out)) in self.likelihoods.iter().zip(self.log_ratios.iter_mut()).enumerate() {
+            let score = scores.get(i).copied().unwrap_or(f64::NAN);
+            *out = if errors.iter().any(|(failed, _)| *failed == i) || !score.is_finite() {
+                -MAX_LOG_RATIO
+            } else {
+                self.weights[i] * l.log_ratio(score).clamp(-MAX_LOG_RATIO, MAX_LOG_RATIO)
+            };
+            log_odds += *out;
+        }
+        self.posterior = (1.0 / (1.0 + (-log_odds).exp())).clamp(self.floor, 1.0 - self.floor);
+        self.posterior
+    }
+
+    pub fn decide(&self, ch: bool) -> Decision {
+        decide_tiered(self.posterior, ch, true, self.go, self.caution)
+    }
+
+    // Combines the posterior with `eval`, the mu evaluation of the same cycle. eval.mu is kept
+    // for the record in both modes.
+    pub fn evaluate(&self, mode: FusionMode, eval: &Evaluation, conditions: &Conditions) -> Evaluation {
+        let fused = self.decide(conditions.ch()).most_severe(conditions.cap());
+        let decision = match mode {
+            FusionMode::Alongside => eval.decision.most_severe(fused),
+            FusionMode::Instead => fused,
+        };
+        Evaluation { mu: eval.mu, ch: eval.ch, decision }
+    }
+
+    // The channels that argued for "faulted" this cycle, strongest first: "a -4.2, b -0.7".
+    pub fn against<N: AsRef<str>>(&self, names: &[N]) -> String {
+        let mut against: Vec<(&str, f64)> = names.iter().map(|n| n.as_ref()).zip(self.log_ratios.iter().copied()).filter(|(_, r)| *r < 0.0).collect();
+        against.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let mut out = String::new();
+        for (name, r) in against {
+            let _ = write!(out, "{}{} {:+.1}", if out.is_empty() { "" } else { ", " }, name, r);
+        }
+        out
+    }
+}
+
+fn ln_beta_pdf(x: f64, (a, b): (f64, f64)) -> f64 {
+    (a - 1.0) * x.ln() + (b - 1.0) * (1.0 - x).ln() - (ln_gamma(a) + ln_gamma(b) - ln_gamma(a + b))
+}
+
+// Lanczos approximation (g = 7, n = 9), accurate to ~1e-15 for x > 0.
+fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const C: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection: Gamma(x) Gamma(1 - x) = pi / sin(pi x).
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let mut sum = C[0];
+    for (i, c) in C.iter().enumerate().skip(1) {
+        sum += c / (x + i as f64);
+    }
+    let t = x + G + 0.5;
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
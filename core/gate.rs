@@ -0,0 +1,234 @@
+//! Gate.rs - CH gating logic as boolean expressions over named conditions (forbid unsafe)
+//!
+//! A gate is `name = severity: expr`, e.g.
+//!   gate.well_control = critical: h2s_ok && (bop_interlock_ok || manual_override_with_permit)
+//! The grammar is identifiers, `true`, `false`, `!`, `&&`, `||` and parentheses (`!` binds
+//! tightest, then `&&`, then `||`). Expressions are validated when loaded: every identifier must
+//! name a condition the domain records, and length and nesting are bounded. A condition that
+//! feeds a gate is demoted to Advisory, so only the gate decides; an input missing at runtime
+//! reads as false.
+#![forbid(unsafe_code)]
+use std::collections::BTreeSet;
+use std::fmt;
+
+use super::harmony::{Conditions, Severity};
+
+const MAX_EXPR_LEN: usize = 512;
+const MAX_DEPTH: usize = 16;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Const(bool),
+    Var(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn parse(text: &str, known: &[&str]) -> Result<Expr, String> {
+        if text.len() > MAX_EXPR_LEN {
+            return Err(format!("expression longer than {} characters", MAX_EXPR_LEN));
+        }
+        let mut p = Parser { tokens: tokenize(text)?, pos: 0 };
+        let expr = p.or(0)?;
+        if let Some(t) = p.tokens.get(p.pos) {
+            return Err(format!("unexpected {:?}", t));
+        }
+        let unknown: Vec<&str> = expr.vars().into_iter().filter(|v| !known.contains(v)).collect();
+        if !unknown.is_empty() {
+            return Err(format!("unknown condition(s) {}", unknown.join(", ")));
+        }
+        Ok(expr)
+    }
+
+    pub fn eval(&self, conditions: &Conditions) -> bool {
+        match self {
+            Expr::Const(b) => *b,
+            Expr::Var(name) => conditions.statuses.iter().any(|c| c.name == *name && c.ok),
+            Expr::Not(e) => !e.eval(conditions),
+            Expr::And(a, b) => a.eval(conditions) && b.eval(conditions),
+            Expr::Or(a, b) => a.eval(conditions) || b.eval(conditions),
+        }
+    }
+
+    pub fn vars(&self) -> BTreeSet<&str> {
+        let mut out = BTreeSet::new();
+        self.collect_vars(&mut out);
+        out
+    }
+
+    fn collect_vars<'a>(&'a self, out: &mut BTreeSet<&'a str>) {
+        match self {
+            Expr::Const(_) => {}
+            Expr::Var(v) => {
+                out.insert(v);
+            }
+            Expr::Not(e) => e.collect_vars(out),
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                a.collect_vars(out);
+                b.collect_vars(out);
+            }
+        }
+    }
+}
+
+// Fully parenthesised, so the logged form is unambiguous.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Const(b) => write!(f, "{}", b),
+            Expr::Var(v) => write!(f, "{}", v),
+            Expr::Not(e) => write!(f, "!{}", e),
+            Expr::And(a, b) => write!(f, "({} && {})", a, b),
+            Expr::Or(a, b) => write!(f, "({} || {})", a, b),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Not,
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '!' => tokens.push(Token::Not),
+            '&' | '|' => {
+                if chars.next().map(|(_, d)| d) != Some(c) {
+                    return Err(format!("expected {}{} at column {}", c, c, i + 1));
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(&(_, d)) = chars.peek() {
+                    if !(d.is_ascii_alphanumeric() || d == '_') {
+                        break;
+                    }
+                    ident.push(d);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            other => return Err(format!("unexpected {:?} at column {}", other, i + 1)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next_if(&mut self, t: &Token) -> bool {
+        let hit = self.tokens.get(self.pos) == Some(t);
+        self.pos += hit as usize;
+        hit
+    }
+
+    fn or(&mut self, depth: usize) -> Result<Expr, String> {
+        let mut lhs = self.and(depth)?;
+        while self.next_if(&Token::Or) {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and(depth)?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self, depth: usize) -> Result<Expr, String> {
+        let mut lhs = self.unary(depth)?;
+        while self.next_if(&Token::And) {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.unary(depth)?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self, depth: usize) -> Result<Expr, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("nested deeper than {}", MAX_DEPTH));
+        }
+        let token = self.tokens.get(self.pos).cloned().ok_or("expression ends early")?;
+        self.pos += 1;
+        match token {
+            Token::Not => Ok(Expr::Not(Box::new(self.unary(depth + 1)?))),
+            Token::Open => {
+                let inner = self.or(depth + 1)?;
+                if !self.next_if(&Token::Close) {
+                    return Err("missing )".into());
+                }
+                Ok(inner)
+            }
+            Token::Ident(v) if v == "true" => Ok(Expr::Const(true)),
+            Token::Ident(v) if v == "false" => Ok(Expr::Const(false)),
+            Token::Ident(v) => Ok(Expr::Var(v)),
+            other => Err(format!("unexpected {:?}", other)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Gate {
+    pub name: String,
+    pub severity: Severity,
+    pub expr: Expr,
+}
+
+impl Gate {
+    // Parses the `severity: expr` right-hand side of a `gate.<name>` config line.
+    pub fn parse(name: &str, spec: &str, known: &[&str]) -> Result<Gate, String> {
+        let (sev, expr) = spec.split_once(':').ok_or_else(|| format!("gate {}: expected severity: expression", name))?;
+        let severity = match sev.trim() {
+            "critical" => Severity::Critical,
+            "major" => Severity::Major,
+            "advisory" => Severity::Advisory,
+            other => return Err(format!("gate {}: unknown severity {:?}", name, other)),
+        };
+        if known.contains(&name) {
+            return Err(format!("gate {}: name collides with a condition", name));
+        }
+        let expr = Expr::parse(expr, known).map_err(|e| format!("gate {}: {}", name, e))?;
+        Ok(Gate { name: name.to_string(), severity, expr })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GateSet {
+    gates: Vec<Gate>,
+}
+
+impl GateSet {
+    // `specs` are (name, "severity: expr") pairs, as loaded from config.
+    pub fn parse(specs: &[(String, String)], known: &[&str]) -> Result<GateSet, String> {
+        let gates = specs.iter().map(|(name, spec)| Gate::parse(name, spec, known)).collect::<Result<Vec<_>, _>>()?;
+        Ok(GateSet { gates })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.gates.is_empty()
+    }
+
+    // Records every gate's result and demotes the conditions that feed a gate to Advisory.
+    pub fn apply(&self, conditions: &mut Conditions) {
+        let results: Vec<bool> = self.gates.iter().map(|g| g.expr.eval(conditions)).collect();
+        let inputs: BTreeSet<&str> = self.gates.iter().flat_map(|g| g.expr.vars()).collect();
+        for c in conditions.statuses.iter_mut().filter(|c| inputs.contains(c.name.as_str())) {
+            c.severity = Severity::Advisory;
+        }
+        for (gate, ok) in self.gates.iter().zip(results) {
+            conditions.record_with(&gate.name, gate.severity, ok, (!ok).then(|| gate.expr.to_string()));
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Governance.rs - Approved limits on channel weights (forbid unsafe)
+//!
+//! Weights summing to 1 can still be unsafe: 0.001 on containment_pressure all but removes it
+//! from mu, and 0.9 on one channel lets it outvote the rest. A WeightGovernance caps every
+//! weight at `max_weight` and holds each safety-critical channel at or above its floor. It
+//! travels with the HarmonyContext, so the builder checks it and so does every later weight
+//! change (HarmonyContext::set_weights, ProfileSchedule::set_weights). Config may only tighten a
+//! domain's built-in governance, never relax it:
+//!   max_weight = 0.35
+//!   min_weight.containment_pressure = 0.15
+#![forbid(unsafe_code)]
+use std::fmt;
+
+use super::harmony::WeightError;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Floor {
+    channel: usize,
+    name: String,
+    min: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightGovernance {
+    max_weight: f64,
+    floors: Vec<Floor>,
+}
+
+impl Default for WeightGovernance {
+    fn default() -> Self {
+        WeightGovernance { max_weight: 1.0, floors: Vec::new() }
+    }
+}
+
+impl WeightGovernance {
+    pub fn new(max_weight: f64) -> Result<Self, String> {
+        if !(max_weight > 0.0 && max_weight <= 1.0) {
+            return Err(format!("max_weight {} is outside (0, 1]", max_weight));
+        }
+        Ok(WeightGovernance { max_weight, floors: Vec::new() })
+    }
+
+    // Marks `channel` safety-critical: its weight may not fall below `min`. A second floor for
+    // the same channel keeps the higher of the two.
+    pub fn floor(mut self, channel: usize, name: &str, min: f64) -> Result<Self, String> {
+        if !(min > 0.0 && min <= self.max_weight) {
+            return Err(format!("min_weight.{} {} is outside (0, max_weight {}]", name, min, self.max_weight));
+        }
+        match self.floors.iter_mut().find(|f| f.channel == channel) {
+            Some(f) => f.min = f.min.max(min),
+            None => self.floors.push(Floor { channel, name: name.to_string(), min }),
+        }
+        let total: f64 = self.floors.iter().map(|f| f.min).sum();
+        if total > 1.0 {
+            return Err(format!("safety-critical floors sum to {}, more than the whole weight", total));
+        }
+        Ok(self)
+    }
+
+    // From config: `max_weight` and (channel, minimum) pairs naming channels in `names`.
+    pub fn parse(max_weight: Option<f64>, min_weights: &[(String, f64)], names: &[&str]) -> Result<Self, String> {
+        let mut governance = WeightGovernance::new(max_weight.unwrap_or(1.0))?;
+        for (i, (name, min)) in min_weights.iter().enumerate() {
+            let channel = names.iter().position(|n| n == name).ok_or_else(|| format!("min_weight.{}: no such channel", name))?;
+            if min_weights[..i].iter().any(|(n, _)| n == name) {
+                return Err(format!("min_weight.{}: defined twice", name));
+            }
+            governance = governance.floor(channel, name, *min)?;
+        }
+        Ok(governance)
+    }
+
+    // The stricter of the two on every limit: the lower cap and each channel's higher floor.
+    pub fn tighten(&self, other: &WeightGovernance) -> Result<Self, String> {
+        let mut out = WeightGovernance::new(self.max_weight.min(other.max_weight))?;
+        for f in self.floors.iter().chain(&other.floors) {
+            out = out.floor(f.channel, &f.name, f.min)?;
+        }
+        Ok(out)
+    }
+
+    pub fn max_weight(&self) -> f64 {
+        self.max_weight
+    }
+
+    // Minimum weight of `channel`, if it is safety-critical.
+    pub fn min_weight(&self, channel: usize) -> Option<f64> {
+        self.floors.iter().find(|f| f.channel == channel).map(|f| f.min)
+    }
+
+    pub fn check(&self, weights: &[f64]) -> Result<(), WeightError> {
+        for f in &self.floors {
+            let value = weights.get(f.channel).copied().unwrap_or(0.0);
+            if value < f.min {
+                return Err(WeightError::BelowFloor { index: f.channel, value, min: f.min });
+            }
+        }
+        match weights.iter().position(|w| *w > self.max_weight) {
+            Some(index) => Err(WeightError::AboveCap { index, value: weights[index], max: self.max_weight }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for WeightGovernance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "max_weight {}", self.max_weight)?;
+        for floor in &self.floors {
+            write!(f, ", min_weight.{} {}", floor.name, floor.min)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::harmony::{ContextError, HarmonyContext};
+
+    const NAMES: [&str; 3] = ["containment_pressure", "coolant_flow", "neutron_flux"];
+
+    #[test]
+    fn rejects_caps_and_floors_out_of_range() {
+        for max in [0.0, -0.1, 1.5, f64::NAN] {
+            assert!(WeightGovernance::new(max).is_err(), "{}", max);
+        }
+        let g = WeightGovernance::new(0.4).unwrap();
+        assert!(g.clone().floor(0, "containment_pressure", 0.5).is_err());
+        assert!(g.clone().floor(0, "containment_pressure", 0.0).is_err());
+        let g = WeightGovernance::new(0.6).unwrap().floor(0, "a", 0.6).unwrap();
+        assert!(g.floor(1, "b", 0.5).unwrap_err().contains("more than the whole weight"));
+    }
+
+    #[test]
+    fn parse_rejects_unknown_and_duplicate_channels() {
+        let floor = |name: &str, min| (name.to_string(), min);
+        assert!(WeightGovernance::parse(None, &[floor("pump_vibration", 0.1)], &NAMES).unwrap_err().contains("no such channel"));
+        let twice = [floor("coolant_flow", 0.1), floor("coolant_flow", 0.2)];
+        assert!(WeightGovernance::parse(None, &twice, &NAMES).unwrap_err().contains("defined twice"));
+    }
+
+    #[test]
+    fn config_can_tighten_but_not_relax() {
+        let built_in = WeightGovernance::new(0.5).unwrap().floor(0, NAMES[0], 0.2).unwrap();
+        let relaxed = WeightGovernance::parse(Some(0.9), &[(NAMES[0].to_string(), 0.05)], &NAMES).unwrap();
+        let g = built_in.tighten(&relaxed).unwrap();
+        assert_eq!(g.max_weight(), 0.5);
+        assert_eq!(g.min_weight(0), Some(0.2));
+
+        let stricter = WeightGovernance::parse(Some(0.4), &[(NAMES[1].to_string(), 0.25)], &NAMES).unwrap();
+        let g = built_in.tighten(&stricter).unwrap();
+        assert_eq!(g.max_weight(), 0.4);
+        assert_eq!((g.min_weight(0), g.min_weight(1), g.min_weight(2)), (Some(0.2), Some(0.25), None));
+    }
+
+    #[test]
+    fn check_reports_floor_before_cap() {
+        let g = WeightGovernance::new(0.5).unwrap().floor(0, NAMES[0], 0.2).unwrap();
+        assert_eq!(g.check(&[0.1, 0.6, 0.3]), Err(WeightError::BelowFloor { index: 0, value: 0.1, min: 0.2 }));
+        assert_eq!(g.check(&[0.2, 0.6, 0.2]), Err(WeightError::AboveCap { index: 1, value: 0.6, max: 0.5 }));
+        assert_eq!(g.check(&[0.2, 0.4, 0.4]), Ok(()));
+        assert!(g.check(&[]).is_err());
+    }
+
+    #[test]
+    fn context_refuses_weight_changes_outside_governance() {
+        let governance = WeightGovernance::new(0.5).unwrap().floor(0, NAMES[0], 0.2).unwrap();
+        assert!(HarmonyContext::builder().weights(vec![0.1, 0.45, 0.45]).governance(governance.clone()).build().is_err());
+        let mut ctx = HarmonyContext::builder().weights(vec![0.3, 0.35, 0.35]).governance(governance).build().unwrap();
+        let err = ctx.set_weights(&[0.05, 0.5, 0.45]).unwrap_err();
+        assert!(matches!(err, ContextError::Weights(WeightError::BelowFloor { index: 0, .. })), "{:?}", err);
+        assert!(ctx.set_weights(&[0.2, 0.2, 0.6]).is_err());
+        assert_eq!(ctx.weights(), [0.3, 0.35, 0.35]);
+    }
+}
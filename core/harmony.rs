@@ -0,0 +1,687 @@
+//! Harmony.rs - Shared HarmonyContext and evaluate() for all domain monitors (forbid unsafe)
+//!
+//! The domain files keep their own score providers, CH checks and decision enums; mu, the
+//! clamp constants and the GO/HALT rule live here (over decision_kernel) so they cannot drift.
+#![forbid(unsafe_code)]
+use std::borrow::Cow;
+use std::fmt;
+
+use super::checks::CheckOutcome;
+use super::governance::WeightGovernance;
+
+pub use crate::decision_kernel::{Aggregator, Decision, InvalidScore, LogMu, HARMONY_THRESHOLD, MIN_SCORE};
+use crate::decision_kernel::{classify_score, decide_tiered, decide_tiered_log};
+
+// mu in [CAUTION_THRESHOLD, HARMONY_THRESHOLD) is CAUTION: early warning before HALT.
+pub const CAUTION_THRESHOLD: f64 = 0.998;
+
+#[derive(Clone, Debug)]
+pub struct HarmonyContext {
+    // Private, with governance, so every change after build() goes through set_weights.
+    weights: Vec<f64>,
+    pub threshold: f64,
+    // Equal to `threshold` disables the CAUTION band.
+    pub caution_threshold: f64,
+    pub aggregator: Aggregator,
+    // When set, GO/CAUTION are decided on mu's lower confidence bound (mu - z * stddev)
+    // rather than the point estimate; see evaluate_uncertain.
+    pub lower_bound_z: Option<f64>,
+    pub invalid_scores: InvalidScorePolicy,
+    // Limits every weight change must respect; no limits unless the domain or config sets them.
+    governance: WeightGovernance,
+}
+
+// What calculate_mu does with a NaN, infinite or negative score.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InvalidScorePolicy {
+    // The channel counts as MIN_SCORE, its worst reading, and drags mu down with its weight.
+    #[default]
+    TreatAsZero,
+    // mu is taken over the remaining channels with their weights renormalized.
+    DropChannel,
+    // mu is MIN_SCORE, so any invalid score forces HALT.
+    Halt,
+}
+
+// One invalid score: "channel 2 (nan)"; named by the domain for logs via record_score_faults.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScoreFault {
+    pub channel: usize,
+    pub value: f64,
+    pub kind: InvalidScore,
+}
+
+impl fmt::Display for ScoreFault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "channel {} is {} ({})", self.channel, self.value, self.kind.as_str())
+    }
+}
+
+pub fn score_faults(scores: &[f64]) -> impl Iterator<Item = ScoreFault> + '_ {
+    scores.iter().enumerate().filter_map(|(channel, &value)| classify_score(value).map(|kind| ScoreFault { channel, value, kind }))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Evaluation {
+    pub mu: f64,
+    pub ch: bool,
+    pub decision: Decision,
+}
+
+pub const WEIGHT_SUM_TOLERANCE: f64 = 1e-9;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WeightError {
+    Empty,
+    NonFinite { index: usize, value: f64 },
+    Negative { index: usize, value: f64 },
+    Zero { index: usize },
+    SumNotOne { sum: f64 },
+    BelowFloor { index: usize, value: f64, min: f64 },
+    AboveCap { index: usize, value: f64, max: f64 },
+}
+
+impl fmt::Display for WeightError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WeightError::Empty => write!(f, "no weights configured"),
+            WeightError::NonFinite { index, value } => write!(f, "weight {} is {}", index, value),
+            WeightError::Negative { index, value } => write!(f, "weight {} is negative ({})", index, value),
+            WeightError::Zero { index } => write!(f, "weight {} is zero; the channel would be ignored", index),
+            WeightError::SumNotOne { sum } => write!(f, "weights sum to {}, not 1 (normalize or fix the config)", sum),
+            WeightError::BelowFloor { index, value, min } => write!(f, "weight {} is {}, below its safety-critical minimum {}", index, value, min),
+            WeightError::AboveCap { index, value, max } => write!(f, "weight {} is {}, above the maximum {} for any channel", index, value, max),
+        }
+    }
+}
+
+impl std::error::Error for WeightError {}
+
+// Every weight finite and positive; the sum check is separate so normalize can reuse this.
+fn check_each_weight(weights: &[f64]) -> Result<(), WeightError> {
+    if weights.is_empty() {
+        return Err(WeightError::Empty);
+    }
+    for (index, w) in weights.iter().enumerate() {
+        if !w.is_finite() {
+            return Err(WeightError::NonFinite { index, value: *w });
+        }
+        if *w < 0.0 {
+            return Err(WeightError::Negative { index, value: *w });
+        }
+        if *w == 0.0 {
+            return Err(WeightError::Zero { index });
+        }
+    }
+    Ok(())
+}
+
+// Weights off 1.0 would silently scale ln(mu) and shift every decision against the threshold.
+pub fn validate_weights(weights: &[f64]) -> Result<(), WeightError> {
+    check_each_weight(weights)?;
+    let sum: f64 = weights.iter().sum();
+    if (sum - 1.0).abs() > WEIGHT_SUM_TOLERANCE {
+        return Err(WeightError::SumNotOne { sum });
+    }
+    Ok(())
+}
+
+// Rescales valid weights to sum to 1.0, keeping their ratios.
+pub fn normalize_weights(weights: &[f64]) -> Result<Vec<f64>, WeightError> {
+    check_each_weight(weights)?;
+    let sum: f64 = weights.iter().sum();
+    Ok(weights.iter().map(|w| w / sum).collect())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContextError {
+    Weights(WeightError),
+    LengthMismatch { weights: usize, channels: usize },
+    InvalidThreshold(f64),
+    InvalidCautionBand { caution: f64, threshold: f64 },
+    InvalidCvarTail(f64),
+    InvalidConfidenceZ(f64),
+}
+
+impl From<WeightError> for ContextError {
+    fn from(e: WeightError) -> Self {
+        ContextError::Weights(e)
+    }
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContextError::Weights(e) => e.fmt(f),
+            ContextError::LengthMismatch { weights, channels } => write!(f, "{} weights for {} score channels", weights, channels),
+            ContextError::InvalidThreshold(t) => write!(f, "threshold {} is outside (0, 1]", t),
+            ContextError::InvalidCautionBand { caution, threshold } => {
+                write!(f, "caution threshold {} must be in (0, threshold {}]", caution, threshold)
+            }
+            ContextError::InvalidCvarTail(t) => write!(f, "CVaR tail {} is outside (0, 1]", t),
+            ContextError::InvalidConfidenceZ(z) => write!(f, "confidence z {} must be finite and positive", z),
+        }
+    }
+}
+
+impl std::error::Error for ContextError {}
+
+// Validating constructor: a context that reaches a monitor has one positive, finite weight
+// per score channel, weights summing to 1.0 within its governance, and
+// 0 < caution threshold <= threshold <= 1.
+#[derive(Clone, Debug, Default)]
+pub struct ContextBuilder {
+    weights: Vec<f64>,
+    channels: Option<usize>,
+    threshold: Option<f64>,
+    caution_threshold: Option<f64>,
+    aggregator: Aggregator,
+    lower_bound_z: Option<f64>,
+    invalid_scores: InvalidScorePolicy,
+    governance: WeightGovernance,
+    normalize: bool,
+}
+
+impl ContextBuilder {
+    pub fn new() -> Self {
+        ContextBuilder::default()
+    }
+
+    pub fn weights(mut self, weights: Vec<f64>) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    // Number of score channels the domain will pass to evaluate().
+    pub fn channels(mut self, channels: usize) -> Self {
+        self.channels = Some(channels);
+        self
+    }
+
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    pub fn caution_threshold(mut self, caution_threshold: f64) -> Self {
+        self.caution_threshold = Some(caution_threshold);
+        self
+    }
+
+    // How scores combine into mu; the weighted geometric mean unless a domain opts out.
+    pub fn aggregator(mut self, aggregator: Aggregator) -> Self {
+        self.aggregator = aggregator;
+        self
+    }
+
+    // Decide on mu - z * stddev(mu) for scores supplied with uncertainty (z = 2 is ~97.7%
+    // one-sided): a noisy mu must clear the threshold by its own error to reach GO.
+    pub fn decide_on_lower_bound(mut self, z: f64) -> Self {
+        self.lower_bound_z = Some(z);
+        self
+    }
+
+    pub fn on_invalid_score(mut self, policy: InvalidScorePolicy) -> Self {
+        self.invalid_scores = policy;
+        self
+    }
+
+    // Checked against the final (normalized) weights here and on every later set_weights.
+    pub fn governance(mut self, governance: WeightGovernance) -> Self {
+        self.governance = governance;
+        self
+    }
+
+    // HARMONY_THRESHOLD and HARMONY_WEIGHTS (comma-separated), as harmony_operator renders them,
+    // replace the compiled-in values when set; build() then checks them like any others.
+    pub fn env_overrides(mut self) -> Result<Self, String> {
+        if let Ok(t) = std::env::var("HARMONY_THRESHOLD") {
+            self.threshold = Some(t.trim().parse().map_err(|_| format!("HARMONY_THRESHOLD {:?} is not a number", t))?);
+        }
+        if let Ok(w) = std::env::var("HARMONY_WEIGHTS") {
+            self.weights = w
+                .split(',')
+                .map(|x| x.trim().parse().map_err(|_| format!("HARMONY_WEIGHTS {:?} is not a comma-separated list of numbers", w)))
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(self)
+    }
+
+    // Rescale weights that don't sum to 1.0 instead of rejecting them.
+    pub fn normalize(mut self) -> Self {
+        self.normalize = true;
+        self
+    }
+
+    pub fn build(self) -> Result<HarmonyContext, ContextError> {
+        if let Some(channels) = self.channels {
+            if channels != self.weights.len() {
+                return Err(ContextError::LengthMismatch { weights: self.weights.len(), channels });
+            }
+        }
+        let weights = if self.normalize {
+            normalize_weights(&self.weights)?
+        } else {
+            validate_weights(&self.weights)?;
+            self.weights
+        };
+        self.governance.check(&weights)?;
+        let threshold = self.threshold.unwrap_or(HARMONY_THRESHOLD);
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            return Err(ContextError::InvalidThreshold(threshold));
+        }
+        // The default band follows a custom threshold down rather than sitting above it.
+        let caution = self.caution_threshold.unwrap_or(CAUTION_THRESHOLD.min(threshold));
+        if !(caution > 0.0 && caution <= threshold) {
+            return Err(ContextError::InvalidCautionBand { caution, threshold });
+        }
+        if let Aggregator::Cvar { tail } = self.aggregator {
+            if !(tail > 0.0 && tail <= 1.0) {
+                return Err(ContextError::InvalidCvarTail(tail));
+            }
+        }
+        if let Some(z) = self.lower_bound_z {
+            if !(z.is_finite() && z > 0.0) {
+                return Err(ContextError::InvalidConfidenceZ(z));
+            }
+        }
+        Ok(HarmonyContext {
+            weights,
+            threshold,
+            caution_threshold: caution,
+            aggregator: self.aggregator,
+            lower_bound_z: self.lower_bound_z,
+            invalid_scores: self.invalid_scores,
+            governance: self.governance,
+        })
+    }
+}
+
+impl HarmonyContext {
+    pub fn new(weights: Vec<f64>) -> Self {
+        HarmonyContext {
+            weights,
+            threshold: HARMONY_THRESHOLD,
+            caution_threshold: CAUTION_THRESHOLD,
+            aggregator: Aggregator::GeometricMean,
+            lower_bound_z: None,
+            invalid_scores: InvalidScorePolicy::TreatAsZero,
+            governance: WeightGovernance::default(),
+        }
+    }
+
+    pub fn builder() -> ContextBuilder {
+        ContextBuilder::new()
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    pub fn governance(&self) -> &WeightGovernance {
+        &self.governance
+    }
+
+    pub fn validate_weights(&self) -> Result<(), WeightError> {
+        validate_weights(&self.weights)?;
+        self.governance.check(&self.weights)
+    }
+
+    // Runtime weight change (a committed config_consensus entry, a reload): the same checks as
+    // build(), governance included; on error the weights stay as they were.
+    pub fn set_weights(&mut self, weights: &[f64]) -> Result<(), ContextError> {
+        self.check_weights(weights)?;
+        self.weights.copy_from_slice(weights);
+        Ok(())
+    }
+
+    // set_weights' checks without the change, for callers that must validate a whole config
+    // before touching any of it.
+    pub fn check_weights(&self, weights: &[f64]) -> Result<(), ContextError> {
+        if weights.len() != self.weights.len() {
+            return Err(ContextError::LengthMismatch { weights: weights.len(), channels: self.weights.len() });
+        }
+        validate_weights(weights)?;
+        self.governance.check(weights)?;
+        Ok(())
+    }
+
+    // mu under the invalid-score policy; allocation-free unless a channel is dropped.
+    pub fn calculate_mu(&self, scores: &[f64]) -> f64 {
+        match self.effective_weights(scores) {
+            Some(weights) => self.aggregator.aggregate(&weights, scores),
+            None => MIN_SCORE,
+        }
+    }
+
+    // calculate_mu() as ln(mu) for the geometric mean, summed with compensation; None for the
+    // aggregators computed in linear space.
+    pub fn calculate_log_mu(&self, scores: &[f64]) -> Option<LogMu> {
+        if self.aggregator != Aggregator::GeometricMean {
+            return None;
+        }
+        Some(match self.effective_weights(scores) {
+            Some(weights) => LogMu::from_scores(&weights, scores),
+            None => LogMu::from_mu(MIN_SCORE),
+        })
+    }
+
+    // Weights mu is taken with: dropped channels zeroed and the rest renormalized. None when
+    // the policy forces HALT or no valid channel is left.
+    fn effective_weights(&self, scores: &[f64]) -> Option<Cow<'_, [f64]>> {
+        if self.invalid_scores == InvalidScorePolicy::TreatAsZero || score_faults(scores).next().is_none() {
+            return Some(Cow::Borrowed(&self.weights));
+        }
+        if self.invalid_scores == InvalidScorePolicy::Halt {
+            return None;
+        }
+        let mut weights: Vec<f64> = self.weights.iter().zip(scores).map(|(w, s)| if classify_score(*s).is_some() { 0.0 } else { *w }).collect();
+        let sum: f64 = weights.iter().sum();
+        if sum <= 0.0 {
+            return None;
+        }
+        weights.iter_mut().for_each(|w| *w /= sum);
+        Some(Cow::Owned(weights))
+    }
+
+    // Records `scores_valid`, failing with the policy's severity (Advisory for TreatAsZero,
+    // whose effect is already in mu; Major for DropChannel; Critical for Halt) and naming
+    // each invalid channel. Allocation-free while every score is valid.
+    pub fn record_score_faults<N: AsRef<str>>(&self, names: &[N], scores: &[f64], conditions: &mut Conditions) {
+        let severity = match self.invalid_scores {
+            InvalidScorePolicy::TreatAsZero => Severity::Advisory,
+            InvalidScorePolicy::DropChannel => Severity::Major,
+            InvalidScorePolicy::Halt => Severity::Critical,
+        };
+        let detail = score_faults(scores)
+            .map(|f| match names.get(f.channel) {
+                Some(name) => format!("{} is {} ({})", name.as_ref(), f.value, f.kind.as_str()),
+                None => f.to_string(),
+            })
+            .reduce(|a, b| a + ", " + &b);
+        conditions.record_with("scores_valid", severity, detail.is_none(), detail);
+    }
+
+    // d mu / d score_i per channel: how hard each input pulls mu toward the threshold.
+    pub fn sensitivity(&self, scores: &[f64]) -> Vec<f64> {
+        let mut out = Vec::with_capacity(scores.len());
+        self.sensitivity_into(scores, &mut out);
+        out
+    }
+
+    // A dropped channel gets 0; under InvalidScorePolicy::Halt every channel does.
+    pub fn sensitivity_into(&self, scores: &[f64], out: &mut Vec<f64>) {
+        match self.effective_weights(scores) {
+            Some(weights) => self.aggregator.sensitivity_into(&weights, scores, out),
+            None => {
+                out.clear();
+                out.resize(scores.len(), 0.0);
+            }
+        }
+    }
+
+    pub fn calculate_mu_interval(&self, scores: &[Uncertain]) -> MuInterval {
+        let values: Vec<f64> = scores.iter().map(|s| s.value).collect();
+        let stddevs: Vec<f64> = scores.iter().map(|s| s.stddev).collect();
+        self.mu_interval(&values, &stddevs, &mut Vec::with_capacity(scores.len()))
+    }
+
+    // calculate_mu_interval() over parallel slices; leaves the sensitivity in `sensitivity`.
+    // First order (delta method), channel errors independent:
+    // var(mu) = sum_i (d mu / d s_i)^2 * stddev_i^2.
+    pub fn mu_interval(&self, scores: &[f64], stddevs: &[f64], sensitivity: &mut Vec<f64>) -> MuInterval {
+        let mu = self.calculate_mu(scores);
+        self.sensitivity_into(scores, sensitivity);
+        let variance: f64 = sensitivity.iter().zip(stddevs).map(|(d, sd)| (d * sd).powi(2)).sum();
+        let stddev = if variance.is_finite() { variance.sqrt() } else { f64::INFINITY };
+        let z = self.lower_bound_z.unwrap_or(DEFAULT_CONFIDENCE_Z);
+        MuInterval { mu, stddev, z, lower: (mu - z * stddev).max(MIN_SCORE), upper: (mu + z * stddev).min(1.0) }
+    }
+}
+
+// Interval width used when the context does not decide on the lower bound.
+pub const DEFAULT_CONFIDENCE_Z: f64 = 2.0;
+
+// A score with its standard deviation (sensor spec, filter variance); stddev 0 is exact.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Uncertain {
+    pub value: f64,
+    pub stddev: f64,
+}
+
+impl Uncertain {
+    pub fn exact(value: f64) -> Self {
+        Uncertain { value, stddev: 0.0 }
+    }
+}
+
+// mu with [lower, upper] = mu -/+ z * stddev, clipped to [MIN_SCORE, 1].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MuInterval {
+    pub mu: f64,
+    pub stddev: f64,
+    pub z: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl fmt::Display for MuInterval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.6} [{:.6}, {:.6}] (stddev {:.2e}, z {})", self.mu, self.lower, self.upper, self.stddev, self.z)
+    }
+}
+
+// Channel whose fall would move mu most, per HarmonyContext::sensitivity.
+pub fn most_sensitive(sensitivity: &[f64]) -> Option<usize> {
+    (0..sensitivity.len()).filter(|&i| sensitivity[i] > 0.0).max_by(|&a, &b| sensitivity[a].total_cmp(&sensitivity[b]))
+}
+
+// Log form of a sensitivity vector: "a=0.3000 b=0.2500 (most sensitive: a)".
+pub struct SensitivityLog<'a, N: AsRef<str>> {
+    pub names: &'a [N],
+    pub values: &'a [f64],
+}
+
+impl<N: AsRef<str>> fmt::Display for SensitivityLog<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (name, d)) in self.names.iter().zip(self.values).enumerate() {
+            write!(f, "{}{}={:.4}", if i > 0 { " " } else { "" }, name.as_ref(), d)?;
+        }
+        match most_sensitive(self.values).and_then(|i| self.names.get(i)) {
+            Some(name) => write!(f, " (most sensitive: {})", name.as_ref()),
+            None => Ok(()),
+        }
+    }
+}
+
+// Advisory failures only alert, Major failures cap the decision at CAUTION, Critical
+// failures force HALT. Ordered so the worst failure is the max.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Advisory,
+    Major,
+    Critical,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConditionStatus {
+    pub name: String,
+    pub severity: Severity,
+    pub ok: bool,
+    // Why it failed, when the condition knows (e.g. "expired 3 days ago").
+    pub detail: Option<String>,
+    pub evidence: Option<String>,
+}
+
+impl fmt::Display for ConditionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.ok)?;
+        match (&self.detail, &self.evidence) {
+            (Some(d), Some(e)) => write!(f, " ({}; {})", d, e),
+            (Some(x), None) | (None, Some(x)) => write!(f, " ({})", x),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+// This cycle's CH conditions with their severities. clear() keeps last cycle's entries as
+// spares so a loop that records the same conditions every cycle reuses their name buffers.
+#[derive(Clone, Debug, Default)]
+pub struct Conditions {
+    pub statuses: Vec<ConditionStatus>,
+    spare: Vec<ConditionStatus>,
+}
+
+impl Conditions {
+    pub fn new() -> Self {
+        Conditions::default()
+    }
+
+    pub fn record(&mut self, name: &str, severity: Severity, ok: bool) -> &mut Self {
+        self.record_with(name, severity, ok, None)
+    }
+
+    pub fn record_with(&mut self, name: &str, severity: Severity, ok: bool, detail: Option<String>) -> &mut Self {
+        self.push(name, severity, ok, detail, None)
+    }
+
+    pub fn record_outcome(&mut self, name: &str, severity: Severity, outcome: CheckOutcome) -> &mut Self {
+        let CheckOutcome { pass, reason, evidence } = outcome;
+        self.push(name, severity, pass, reason, evidence)
+    }
+
+    fn push(&mut self, name: &str, severity: Severity, ok: bool, detail: Option<String>, evidence: Option<String>) -> &mut Self {
+        let status = match self.spare.pop() {
+            Some(mut s) => {
+                s.name.clear();
+                s.name.push_str(name);
+                ConditionStatus { name: s.name, severity, ok, detail, evidence }
+            }
+            None => ConditionStatus { name: name.to_string(), severity, ok, detail, evidence },
+        };
+        self.statuses.push(status);
+        self
+    }
+
+    // Empties the set for the next cycle without giving back its allocations.
+    pub fn clear(&mut self) {
+        self.spare.extend(self.statuses.drain(..).rev());
+    }
+
+    // Appends another set (e.g. stateful checks recorded by the domain after the registry ran).
+    pub fn extend(&mut self, other: Conditions) -> &mut Self {
+        self.statuses.extend(other.statuses);
+        self
+    }
+
+    // extend() by copy into this set's reused slots, for a set the caller keeps.
+    pub fn extend_from(&mut self, other: &Conditions) -> &mut Self {
+        for c in &other.statuses {
+            self.push(&c.name, c.severity, c.ok, c.detail.clone(), c.evidence.clone());
+        }
+        self
+    }
+
+    // Failed conditions for log lines: "kill_switch_reachable=false, h2s_ok=false (12.4 ppm)".
+    pub fn failure_summary(&self) -> String {
+        let failed: Vec<String> = self.statuses.iter().filter(|c| !c.ok).map(|c| c.to_string()).collect();
+        failed.join(", ")
+    }
+
+    // ch in the Evaluation sense: no critical condition failed.
+    pub fn ch(&self) -> bool {
+        self.worst_failure() != Some(Severity::Critical)
+    }
+
+    pub fn worst_failure(&self) -> Option<Severity> {
+        self.statuses.iter().filter(|c| !c.ok).map(|c| c.severity).max()
+    }
+
+    // Most permissive decision the conditions allow.
+    pub fn cap(&self) -> Decision {
+        match self.worst_failure() {
+            None | Some(Severity::Advisory) => Decision::GO,
+            Some(Severity::Major) => Decision::CAUTION,
+            Some(Severity::Critical) => Decision::HALT,
+        }
+    }
+
+    pub fn failed(&self, severity: Severity) -> impl Iterator<Item = &str> {
+        self.statuses.iter().filter(move |c| !c.ok && c.severity == severity).map(|c| c.name.as_str())
+    }
+}
+
+// One evaluation cycle: mu over this cycle's scores, then GO only if mu clears the
+// threshold and every CH condition holds; CAUTION if ch holds and mu is in the caution band.
+// The geometric mean is decided in log space (LogMu), so channel count never shifts a decision.
+pub fn evaluate(ctx: &HarmonyContext, scores: &[f64], ch: bool) -> Evaluation {
+    if let Some(log_mu) = ctx.calculate_log_mu(scores) {
+        return Evaluation { mu: log_mu.mu(), ch, decision: decide_tiered_log(log_mu, ch, true, ctx.threshold, ctx.caution_threshold) };
+    }
+    let mu = ctx.calculate_mu(scores);
+    Evaluation { mu, ch, decision: decide_tiered(mu, ch, true, ctx.threshold, ctx.caution_threshold) }
+}
+
+// evaluate() with severity-weighted conditions: mu and critical conditions decide GO/HALT as
+// before, then a failed major condition lowers GO to CAUTION.
+pub fn evaluate_conditions(ctx: &HarmonyContext, scores: &[f64], conditions: &Conditions) -> Evaluation {
+    let mut eval = evaluate(ctx, scores, conditions.ch());
+    eval.decision = eval.decision.most_severe(conditions.cap());
+    eval
+}
+
+// evaluate_conditions() for scores with uncertainty: eval.mu is the point estimate, but a
+// context with lower_bound_z decides GO/CAUTION on interval.lower.
+pub fn evaluate_uncertain(ctx: &HarmonyContext, interval: &MuInterval, conditions: &Conditions) -> Evaluation {
+    let decided_on = if ctx.lower_bound_z.is_some() { interval.lower } else { interval.mu };
+    let ch = conditions.ch();
+    let decision = decide_tiered(decided_on, ch, true, ctx.threshold, ctx.caution_threshold).most_severe(conditions.cap());
+    Evaluation { mu: interval.mu, ch, decision }
+}
+
+// Substitutions for a what-if evaluation: channel index -> score, condition name -> result.
+#[derive(Clone, Debug, Default)]
+pub struct Overrides {
+    pub scores: Vec<(usize, f64)>,
+    pub conditions: Vec<(String, bool)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Hypothetical {
+    pub live: Evaluation,
+    pub hypothetical: Evaluation,
+    // Per channel: score that alone (others as overridden) would reach the GO threshold.
+    pub required: Vec<Option<f64>>,
+    // Failed critical/major conditions still standing between the hypothetical and GO.
+    pub blocking: Vec<String>,
+}
+
+impl HarmonyContext {
+    // "What would it take to get GO": evaluates copies of the inputs with `overrides` applied.
+    // Live scores and conditions are only read, so the running monitor is never affected.
+    pub fn evaluate_hypothetical(&self, scores: &[f64], conditions: &Conditions, overrides: &Overrides) -> Result<Hypothetical, String> {
+        let mut what_if_scores = scores.to_vec();
+        for (channel, value) in &overrides.scores {
+            let slot = what_if_scores.get_mut(*channel).ok_or_else(|| format!("no score channel {}", channel))?;
+            *slot = *value;
+        }
+        let mut what_if_conditions = conditions.clone();
+        for (name, ok) in &overrides.conditions {
+            let status = what_if_conditions
+                .statuses
+                .iter_mut()
+                .find(|c| &c.name == name)
+                .ok_or_else(|| format!("no condition {}", name))?;
+            status.ok = *ok;
+        }
+        let hypothetical = evaluate_conditions(self, &what_if_scores, &what_if_conditions);
+        let required = (0..what_if_scores.len()).map(|c| self.aggregator.required_score(&self.weights, &what_if_scores, c, self.threshold)).collect();
+        let blocking = what_if_conditions
+            .statuses
+            .iter()
+            .filter(|c| !c.ok && c.severity != Severity::Advisory)
+            .map(|c| c.name.clone())
+            .collect();
+        Ok(Hypothetical { live: evaluate_conditions(self, scores, conditions), hypothetical, required, blocking })
+    }
+}
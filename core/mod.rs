@@ -0,0 +1,28 @@
+//! Core - Shared engine pieces used by every domain monitor (forbid unsafe)
+//!
+//! Refer to this module as `crate::core` so it never resolves to the `core` crate.
+#![forbid(unsafe_code)]
+pub mod harmony;
+pub mod monitor;
+pub mod validity;
+pub mod source;
+pub mod checks;
+pub mod budget;
+pub mod filter;
+pub mod scheduler;
+pub mod gate;
+pub mod profile;
+pub mod cadence;
+pub mod baseline;
+pub mod trend;
+pub mod self_harmony;
+pub mod vote;
+pub mod availability;
+pub mod anomaly;
+pub mod stats;
+pub mod fusion;
+pub mod tuning;
+pub mod calibration;
+pub mod governance;
+#[cfg(any(test, feature = "chaos"))]
+pub mod fault_injection;
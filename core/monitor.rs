@@ -0,0 +1,358 @@
+//! Monitor.rs - Generic HarmonyMonitor<D: Domain> polling loop (forbid unsafe)
+//!
+//! A domain supplies its score sources, CH checks, tick rate and safe-state action through
+//! `plugin::Domain`; sampling, evaluation, the safe-state call and pacing live here once.
+//! A cycle samples the SourceSet, runs the CheckRegistry, lets the domain refine the result
+//! (Domain::refine), records freshness, host conditions and score faults, evaluates (on mu's
+//! lower bound when the context asks for it) and hands the evaluation to Domain::decide.
+//! The GO threshold starts at the domain's own and may be moved at runtime within the domain's
+//! bounds, by a principal the attached AccessControl authorizes for Action::ThresholdChange;
+//! every attempt is audited, refused ones included. An operator may hold the decision at
+//! CAUTION or HALT for a while (Action::Override), and a safety engineer may bypass one failing
+//! domain condition for at most a shift (Action::Bypass); both lapse on their own and are
+//! audited the same way. Each source's readings feed
+//! rolling-window statistics (core::stats), over a minute and fifteen unless changed.
+#![forbid(unsafe_code)]
+use std::time::{Duration, Instant};
+
+use super::harmony::{self, Conditions, ContextBuilder, Decision, Evaluation, HarmonyContext, MuInterval, SensitivityLog};
+use super::source::{Provenance, SourceError};
+use super::stats::RollingStats;
+use crate::plugin::Domain;
+use crate::rbac::{AccessControl, Action, AuditSink, Principal};
+
+const DEFAULT_STATS_WINDOWS: [Duration; 2] = [Duration::from_secs(60), Duration::from_secs(900)];
+// Longest maintenance bypass a safety engineer may grant at once; renewing it is audited again.
+pub const MAX_BYPASS: Duration = Duration::from_secs(8 * 3600);
+
+// An operator's manual hold: no cycle decides more permissively than `decision` until it lapses.
+struct OperatorHold {
+    decision: Decision,
+    until: Instant,
+}
+
+// A safety engineer's maintenance bypass: the named domain condition counts as passing until it lapses.
+struct ConditionBypass {
+    condition: String,
+    until: Instant,
+}
+
+pub struct HarmonyMonitor<D: Domain> {
+    domain: D,
+    ctx: HarmonyContext,
+    // Source names in channel order, and the last cycle's d mu / d score per channel.
+    names: Vec<String>,
+    sensitivity: Vec<f64>,
+    // This cycle's scores, source errors and conditions, kept so a steady-state cycle does not
+    // allocate; stddevs and the interval only when deciding on mu's lower bound.
+    scores: Vec<f64>,
+    errors: Vec<(usize, SourceError)>,
+    conditions: Conditions,
+    stddevs: Vec<f64>,
+    interval: Option<MuInterval>,
+    // Filled only once trace_provenance() is on.
+    provenance: Option<Vec<Provenance>>,
+    threshold_bounds: (f64, f64),
+    access: Option<AccessControl<Box<dyn AuditSink>>>,
+    hold: Option<OperatorHold>,
+    bypasses: Vec<ConditionBypass>,
+    stats: RollingStats,
+}
+
+impl<D: Domain> HarmonyMonitor<D> {
+    pub fn new(domain: D) -> Result<Self, String> {
+        Self::with_context(domain, |builder| builder)
+    }
+
+    // For domains that load a threshold or weights from (sealed) config; the builder arrives
+    // pre-filled with the domain's weights, source count and threshold. A configured
+    // threshold must still lie within the domain's bounds.
+    pub fn with_context(domain: D, configure: impl FnOnce(ContextBuilder) -> ContextBuilder) -> Result<Self, String> {
+        let builder = HarmonyContext::builder().weights(domain.weights()).channels(domain.sources().len()).threshold(domain.threshold());
+        let ctx = configure(builder).build().map_err(|e| format!("{}: {}", domain.name(), e))?;
+        if domain.tick().is_zero() {
+            return Err(format!("{}: tick rate is zero", domain.name()));
+        }
+        let (min, max) = domain.threshold_bounds();
+        if !(min > 0.0 && min <= max && max <= 1.0) {
+            return Err(format!("{}: threshold bounds [{}, {}] are not within (0, 1]", domain.name(), min, max));
+        }
+        if !(min..=max).contains(&ctx.threshold) {
+            return Err(format!("{}: threshold {} is outside its bounds [{}, {}]", domain.name(), ctx.threshold, min, max));
+        }
+        let names: Vec<String> = domain.sources().names().iter().map(|n| n.to_string()).collect();
+        let channels = names.len();
+        let stats = RollingStats::new(&names, &DEFAULT_STATS_WINDOWS);
+        Ok(HarmonyMonitor {
+            ctx,
+            domain,
+            names,
+            sensitivity: Vec::with_capacity(channels),
+            scores: Vec::with_capacity(channels),
+            errors: Vec::new(),
+            conditions: Conditions::new(),
+            stddevs: Vec::with_capacity(channels),
+            interval: None,
+            provenance: None,
+            threshold_bounds: (min, max),
+            access: None,
+            hold: None,
+            bypasses: Vec::new(),
+            stats,
+        })
+    }
+
+    pub fn domain(&self) -> &D {
+        &self.domain
+    }
+
+    pub fn domain_mut(&mut self) -> &mut D {
+        &mut self.domain
+    }
+
+    pub fn context(&self) -> &HarmonyContext {
+        &self.ctx
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.ctx.threshold
+    }
+
+    pub fn threshold_bounds(&self) -> (f64, f64) {
+        self.threshold_bounds
+    }
+
+    // Replaces the context wholesale, as a threshold profile switch or a tuned weight set does;
+    // refused unless it covers the same channels and its threshold lies within the bounds.
+    pub fn set_context(&mut self, ctx: HarmonyContext) -> Result<(), String> {
+        let domain = self.domain.name();
+        if ctx.weights().len() != self.names.len() {
+            return Err(format!("{}: context has {} weights for {} channels", domain, ctx.weights().len(), self.names.len()));
+        }
+        let (min, max) = self.threshold_bounds;
+        if !(min..=max).contains(&ctx.threshold) {
+            return Err(format!("{}: threshold {} is outside its bounds [{}, {}]", domain, ctx.threshold, min, max));
+        }
+        self.ctx = ctx;
+        Ok(())
+    }
+
+    // Per channel min/max/mean/p95 of the source readings, in weight order.
+    pub fn stats(&self) -> &RollingStats {
+        &self.stats
+    }
+
+    // Replaces the statistics windows, dropping the history gathered so far.
+    pub fn stats_windows(&mut self, windows: &[Duration]) {
+        self.stats = RollingStats::new(&self.names, windows);
+    }
+
+    // Who may move the threshold and where each attempt is recorded (audit log, decision bus).
+    pub fn access_control(&mut self, access: AccessControl<Box<dyn AuditSink>>) {
+        self.access = Some(access);
+    }
+
+    // Moves the GO threshold at runtime, keeping the caution band's width. Refused outside the
+    // domain's bounds, for a principal without Action::ThresholdChange, and always while no
+    // access control is attached.
+    pub fn set_threshold(&mut self, threshold: f64, principal: &Principal) -> Result<(), String> {
+        let domain = self.domain.name();
+        let (min, max) = self.threshold_bounds;
+        if !(min..=max).contains(&threshold) {
+            return Err(format!("{}: threshold {} is outside its bounds [{}, {}]", domain, threshold, min, max));
+        }
+        let access = self.access.as_mut().ok_or_else(|| format!("{}: no access control attached; refusing threshold change", domain))?;
+        let detail = format!("threshold {} -> {}", self.ctx.threshold, threshold);
+        access.authorize(principal, Action::ThresholdChange, domain, &detail).map_err(|e| format!("{}: {}", domain, e))?;
+        let band = self.ctx.threshold - self.ctx.caution_threshold;
+        self.ctx.threshold = threshold;
+        self.ctx.caution_threshold = (threshold - band).max(f64::MIN_POSITIVE);
+        Ok(())
+    }
+
+    // New weights at runtime (a committed cluster config, a reload), through
+    // HarmonyContext::set_weights: validated and held to the context's governance.
+    pub fn set_weights(&mut self, weights: &[f64]) -> Result<(), String> {
+        self.ctx.set_weights(weights).map_err(|e| format!("{}: {}", self.domain.name(), e))
+    }
+
+    // Threshold and weights together (a committed cluster config): both are checked and the
+    // change authorized as one Action::ThresholdChange before either is applied, so a refusal
+    // or an audit failure leaves the monitor on its previous config.
+    pub fn apply_config(&mut self, threshold: f64, weights: &[f64], principal: &Principal) -> Result<(), String> {
+        let domain = self.domain.name();
+        let (min, max) = self.threshold_bounds;
+        if !(min..=max).contains(&threshold) {
+            return Err(format!("{}: threshold {} is outside its bounds [{}, {}]", domain, threshold, min, max));
+        }
+        self.ctx.check_weights(weights).map_err(|e| format!("{}: {}", domain, e))?;
+        let access = self.access.as_mut().ok_or_else(|| format!("{}: no access control attached; refusing config change", domain))?;
+        let detail = format!("threshold {} -> {}, weights {:?} -> {:?}", self.ctx.threshold, threshold, self.ctx.weights(), weights);
+        access.authorize(principal, Action::ThresholdChange, domain, &detail).map_err(|e| format!("{}: {}", domain, e))?;
+        self.ctx.set_weights(weights).map_err(|e| format!("{}: {}", domain, e))?;
+        let band = self.ctx.threshold - self.ctx.caution_threshold;
+        self.ctx.threshold = threshold;
+        self.ctx.caution_threshold = (threshold - band).max(f64::MIN_POSITIVE);
+        Ok(())
+    }
+
+    // Holds every decision at `decision` or more severe for `duration` (a manual CAUTION or HALT
+    // while crews work), replacing any earlier hold. A hold never makes a cycle more permissive
+    // than its own evaluation, so GO is refused. Needs Action::Override and attached access control.
+    pub fn override_decision(&mut self, decision: Decision, duration: Duration, reason: &str, principal: &Principal) -> Result<(), String> {
+        let domain = self.domain.name();
+        if decision == Decision::GO || duration.is_zero() {
+            return Err(format!("{}: an override holds CAUTION or HALT for a non-zero time", domain));
+        }
+        let access = self.access.as_mut().ok_or_else(|| format!("{}: no access control attached; refusing override", domain))?;
+        let detail = format!("hold {:?} for {:?}: {}", decision, duration, reason);
+        access.authorize(principal, Action::Override, domain, &detail).map_err(|e| format!("{}: {}", domain, e))?;
+        self.hold = Some(OperatorHold { decision, until: Instant::now() + duration });
+        Ok(())
+    }
+
+    // Ends a hold before it lapses; the same Action::Override as placing one.
+    pub fn release_override(&mut self, principal: &Principal) -> Result<(), String> {
+        let domain = self.domain.name();
+        let Some(hold) = &self.hold else {
+            return Err(format!("{}: no override is held", domain));
+        };
+        let access = self.access.as_mut().ok_or_else(|| format!("{}: no access control attached; refusing override release", domain))?;
+        let detail = format!("release {:?} hold", hold.decision);
+        access.authorize(principal, Action::Override, domain, &detail).map_err(|e| format!("{}: {}", domain, e))?;
+        self.hold = None;
+        Ok(())
+    }
+
+    // The held decision, while a hold is in force.
+    pub fn override_held(&self) -> Option<Decision> {
+        self.hold.as_ref().filter(|h| Instant::now() < h.until).map(|h| h.decision)
+    }
+
+    // Lets the domain condition `condition` count as passing for `duration` (at most MAX_BYPASS),
+    // e.g. an interlock out for calibration. Host conditions and score faults cannot be bypassed.
+    // Needs Action::Bypass and attached access control; renewing replaces the earlier expiry.
+    pub fn bypass_condition(&mut self, condition: &str, duration: Duration, reason: &str, principal: &Principal) -> Result<(), String> {
+        let domain = self.domain.name();
+        if duration.is_zero() || duration > MAX_BYPASS {
+            return Err(format!("{}: a bypass lasts between zero and {:?}, not {:?}", domain, MAX_BYPASS, duration));
+        }
+        let access = self.access.as_mut().ok_or_else(|| format!("{}: no access control attached; refusing bypass", domain))?;
+        let detail = format!("bypass {} for {:?}: {}", condition, duration, reason);
+        access.authorize(principal, Action::Bypass, domain, &detail).map_err(|e| format!("{}: {}", domain, e))?;
+        let until = Instant::now() + duration;
+        match self.bypasses.iter_mut().find(|b| b.condition == condition) {
+            Some(b) => b.until = until,
+            None => self.bypasses.push(ConditionBypass { condition: condition.to_string(), until }),
+        }
+        Ok(())
+    }
+
+    // Conditions bypassed right now, for HALT lines and reports.
+    pub fn bypassed(&self) -> impl Iterator<Item = &str> {
+        let now = Instant::now();
+        self.bypasses.iter().filter(move |b| now < b.until).map(|b| b.condition.as_str())
+    }
+
+    // Last cycle's conditions, the domain's own and the host's, for HALT lines and reports.
+    pub fn conditions(&self) -> &Conditions {
+        &self.conditions
+    }
+
+    // Last cycle's sensitivity, named for logs.
+    pub fn sensitivity(&self) -> SensitivityLog<'_, String> {
+        SensitivityLog { names: &self.names, values: &self.sensitivity }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    // Last cycle's scores as evaluated, after the domain's refinement.
+    pub fn scores(&self) -> &[f64] {
+        &self.scores
+    }
+
+    // Last cycle's failed or rejected sources, by channel index.
+    pub fn source_errors(&self) -> &[(usize, SourceError)] {
+        &self.errors
+    }
+
+    // Last cycle's mu interval, when the context decides on its lower bound.
+    pub fn interval(&self) -> Option<&MuInterval> {
+        self.interval.as_ref()
+    }
+
+    // Samples through SourceSet::sample_traced from the next cycle on, for reports.
+    pub fn trace_provenance(&mut self) {
+        self.provenance.get_or_insert_with(|| Vec::with_capacity(self.names.len()));
+    }
+
+    pub fn provenance(&self) -> &[Provenance] {
+        self.provenance.as_deref().unwrap_or_default()
+    }
+
+    // One cycle: a source that fails, times out or is rejected counts as MIN_SCORE, a
+    // NaN/Inf/negative score follows the context's InvalidScorePolicy, conditions apply by
+    // severity, and HALT drives the domain's safe state (CAUTION is left to the caller).
+    pub async fn cycle(&mut self) -> Evaluation {
+        self.cycle_with(&Conditions::new()).await
+    }
+
+    // cycle() with conditions the host adds on top of the domain's own (e.g. scheduler health).
+    // Allocation-free once warm, as long as the sources answer sample_now() and the checks
+    // check_now().
+    pub async fn cycle_with(&mut self, extra: &Conditions) -> Evaluation {
+        let sources = self.domain.sources();
+        match self.provenance.as_mut() {
+            Some(provenance) => sources.sample_traced(&mut self.scores, &mut self.errors, provenance).await,
+            None => sources.sample_into(&mut self.scores, &mut self.errors).await,
+        }
+        self.stats.update(&self.scores, &self.errors, Instant::now());
+        self.domain.checks().run_into(&mut self.conditions).await;
+        self.domain.refine(&mut self.scores, &mut self.errors, &mut self.conditions);
+        self.domain.sources().record_freshness(&self.errors, &mut self.conditions);
+        let now = Instant::now();
+        self.bypasses.retain(|b| now < b.until);
+        for c in self.conditions.statuses.iter_mut().filter(|c| !c.ok) {
+            if self.bypasses.iter().any(|b| b.condition == c.name) {
+                c.ok = true;
+                c.detail = Some(format!("bypassed, failing{}", c.detail.as_deref().map(|d| format!(": {}", d)).unwrap_or_default()));
+            }
+        }
+        self.conditions.extend_from(extra);
+        self.ctx.record_score_faults(&self.names, &self.scores, &mut self.conditions);
+        let eval = if self.ctx.lower_bound_z.is_some() {
+            self.stddevs.clear();
+            self.stddevs.extend((0..self.scores.len()).map(|i| self.domain.stddev(i)));
+            let interval = self.ctx.mu_interval(&self.scores, &self.stddevs, &mut self.sensitivity);
+            self.interval = Some(interval);
+            harmony::evaluate_uncertain(&self.ctx, &interval, &self.conditions)
+        } else {
+            self.ctx.sensitivity_into(&self.scores, &mut self.sensitivity);
+            harmony::evaluate_conditions(&self.ctx, &self.scores, &self.conditions)
+        };
+        let mut eval = self.domain.decide(&self.scores, &self.errors, &self.conditions, eval);
+        if self.hold.as_ref().is_some_and(|h| now >= h.until) {
+            self.hold = None;
+        }
+        if let Some(hold) = &self.hold {
+            eval.decision = eval.decision.most_severe(hold.decision);
+        }
+        if eval.decision == Decision::HALT {
+            self.domain.safe_state();
+        }
+        eval
+    }
+
+    // Runs forever at the domain's tick; `observe` sees every evaluation (logging, enums, gates).
+    pub async fn run(&mut self, mut observe: impl FnMut(&Self, &Evaluation)) {
+        let tick = self.domain.tick();
+        loop {
+            let start = Instant::now();
+            let eval = self.cycle().await;
+            observe(self, &eval);
+            tokio::time::sleep(tick.saturating_sub(start.elapsed())).await;
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Profile.rs - Calendar-scheduled HarmonyContext profiles (forbid unsafe)
+//!
+//! A domain runs its base context except inside a profile's windows: e.g. stricter thresholds
+//! outside market hours, or heavier operator-alertness weighting on night shifts. Windows are
+//! weekday sets plus a local [start, end) time of day; an end before the start runs past
+//! midnight into the next day. Local time is UTC plus a fixed offset, so a site that observes
+//! DST re-provisions the offset at the change. The first matching profile wins.
+#![forbid(unsafe_code)]
+use std::fmt;
+
+use super::harmony::{validate_weights, HarmonyContext};
+
+// Weekday bits, Monday = bit 0.
+pub const WEEKDAYS: u8 = 0b001_1111;
+pub const EVERY_DAY: u8 = 0b111_1111;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+// Minute of the day, for Window bounds.
+pub const fn hm(hour: u16, minute: u16) -> u16 {
+    hour * 60 + minute
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Window {
+    days: u8,
+    start: u16,
+    end: u16,
+}
+
+impl Window {
+    // `days` names the day each window starts on; start == end covers those whole days.
+    pub fn new(days: u8, start: u16, end: u16) -> Result<Window, String> {
+        if days & EVERY_DAY == 0 || days & !EVERY_DAY != 0 {
+            return Err(format!("window day mask {:#09b} is empty or has bits past Sunday", days));
+        }
+        if start >= MINUTES_PER_DAY || end >= MINUTES_PER_DAY {
+            return Err(format!("window {}-{} is outside the day", start, end));
+        }
+        Ok(Window { days, start, end })
+    }
+
+    fn starts_on(&self, weekday: u32) -> bool {
+        self.days & (1 << weekday) != 0
+    }
+
+    // `weekday` is 0 for Monday; `minute` is the local minute of the day.
+    pub fn contains(&self, weekday: u32, minute: u16) -> bool {
+        if self.start == self.end {
+            return self.starts_on(weekday);
+        }
+        if self.start < self.end {
+            return self.starts_on(weekday) && (self.start..self.end).contains(&minute);
+        }
+        (self.starts_on(weekday) && minute >= self.start) || (self.starts_on((weekday + 6) % 7) && minute < self.end)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Profile {
+    name: String,
+    ctx: HarmonyContext,
+    windows: Vec<Window>,
+}
+
+// A profile change, for the domain's log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+}
+
+impl fmt::Display for Transition<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", self.from, self.to)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ProfileSchedule {
+    utc_offset_minutes: i64,
+    // Index 0 is the base profile; it has no windows and applies when nothing else matches.
+    profiles: Vec<Profile>,
+    active: usize,
+}
+
+impl ProfileSchedule {
+    pub fn new(base_name: &str, base: HarmonyContext) -> Self {
+        ProfileSchedule {
+            utc_offset_minutes: 0,
+            profiles: vec![Profile { name: base_name.to_string(), ctx: base, windows: Vec::new() }],
+            active: 0,
+        }
+    }
+
+    // Local time = UTC + offset (e.g. -300 for US Eastern standard time).
+    pub fn utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset_minutes = minutes as i64;
+        self
+    }
+
+    // The profile's context must score the same channels as the base one.
+    pub fn profile(mut self, name: &str, ctx: HarmonyContext, windows: &[Window]) -> Result<Self, String> {
+        let base = &self.profiles[0].ctx;
+        if ctx.weights().len() != base.weights().len() {
+            return Err(format!("profile {}: {} weights, base has {}", name, ctx.weights().len(), base.weights().len()));
+        }
+        if windows.is_empty() {
+            return Err(format!("profile {}: no windows", name));
+        }
+        if self.profiles.iter().any(|p| p.name == name) {
+            return Err(format!("profile {}: defined twice", name));
+        }
+        self.profiles.push(Profile { name: name.to_string(), ctx, windows: windows.to_vec() });
+        Ok(self)
+    }
+
+    // Picks the profile for `unix_secs`; returns the transition when it changes.
+    pub fn select(&mut self, unix_secs: u64) -> Option<Transition<'_>> {
+        let local = unix_secs as i64 + self.utc_offset_minutes * 60;
+        let days = local.div_euclid(86_400);
+        // 1970-01-01 was a Thursday (weekday 3).
+        let weekday = (days + 3).rem_euclid(7) as u32;
+        let minute = (local.rem_euclid(86_400) / 60) as u16;
+        let next = (1..self.profiles.len())
+            .find(|&i| self.profiles[i].windows.iter().any(|w| w.contains(weekday, minute)))
+            .unwrap_or(0);
+        if next == self.active {
+            return None;
+        }
+        let from = self.active;
+        self.active = next;
+        Some(Transition { from: &self.profiles[from].name, to: &self.profiles[next].name })
+    }
+
+    // New weights for every profile at once (core::tuning); refused unless valid for all of them,
+    // each profile's governance included.
+    pub fn set_weights(&mut self, weights: &[f64]) -> Result<(), String> {
+        if weights.len() != self.profiles[0].ctx.weights().len() {
+            return Err(format!("{} weights, profiles have {}", weights.len(), self.profiles[0].ctx.weights().len()));
+        }
+        validate_weights(weights).map_err(|e| e.to_string())?;
+        for p in &self.profiles {
+            p.ctx.governance().check(weights).map_err(|e| format!("profile {}: {}", p.name, e))?;
+        }
+        // Checked against every profile above, so no profile is left on the old weights.
+        for p in &mut self.profiles {
+            p.ctx.set_weights(weights).map_err(|e| format!("profile {}: {}", p.name, e))?;
+        }
+        Ok(())
+    }
+
+    pub fn context(&self) -> &HarmonyContext {
+        &self.profiles[self.active].ctx
+    }
+
+    pub fn active(&self) -> &str {
+        &self.profiles[self.active].name
+    }
+}
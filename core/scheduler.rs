@@ -0,0 +1,209 @@
+//! Scheduler.rs - Several domain monitors in one process, by priority class (forbid unsafe)
+//!
+//! Cooperative and single-threaded: each due domain runs one HarmonyMonitor cycle to completion.
+//! Higher classes run first, and a lower-class cycle is deferred when a higher-class domain falls
+//! due within that cycle's runtime budget, so a 10 Hz chatty domain cannot push a 1 Hz critical
+//! one off its tick. A cycle that overruns its budget pays the excess back before its next turn.
+//! A domain whose cycle starts more than STARVED_TICKS late records `scheduler_on_time=false` on
+//! that cycle (Critical for the critical class, Major otherwise), so starvation shows up in the
+//! starved domain's own decision. A cycle is not deferred again once waiting would push it past
+//! STARVED_TICKS: a steady stream of higher-class work delays a lower class, it never parks it.
+//! Deferrals are counted once per cycle, however many times that cycle is passed over.
+#![forbid(unsafe_code)]
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::harmony::{Conditions, Evaluation, Severity};
+use super::monitor::HarmonyMonitor;
+use super::stats;
+use crate::plugin::Domain;
+
+const STARVED_TICKS: u32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    BestEffort,
+    Standard,
+    Critical,
+}
+
+impl Priority {
+    pub fn parse(s: &str) -> Option<Priority> {
+        match s {
+            "critical" => Some(Priority::Critical),
+            "standard" => Some(Priority::Standard),
+            "best-effort" => Some(Priority::BestEffort),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TaskStats {
+    pub cycles: u64,
+    pub overruns: u64,
+    pub deferrals: u64,
+    pub starved_cycles: u64,
+    pub max_lateness: Duration,
+    pub last_runtime: Duration,
+}
+
+enum Pick {
+    Run(usize),
+    WaitUntil(Instant),
+    Idle,
+}
+
+struct Task {
+    monitor: HarmonyMonitor<Box<dyn Domain>>,
+    priority: Priority,
+    tick: Duration,
+    budget: Duration,
+    due: Instant,
+    // The current cycle has already been counted as deferred.
+    deferred: bool,
+    stats: TaskStats,
+}
+
+pub struct Scheduler {
+    tasks: Vec<Task>,
+    metrics: Option<Arc<Mutex<String>>>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Scheduler { tasks: Vec::new(), metrics: None }
+    }
+
+    // Snapshot of metrics_text(), refreshed after each run_due, for a listener on another thread.
+    pub fn export_metrics(&mut self) -> Arc<Mutex<String>> {
+        self.metrics.get_or_insert_with(|| Arc::new(Mutex::new(String::new()))).clone()
+    }
+
+    // `budget` is the runtime one cycle of this domain may take; it must fit in the tick.
+    pub fn add(&mut self, monitor: HarmonyMonitor<Box<dyn Domain>>, priority: Priority, budget: Duration) -> Result<&mut Self, String> {
+        let tick = monitor.domain().tick();
+        if budget.is_zero() || budget > tick {
+            return Err(format!("{}: budget {:?} must be non-zero and within the {:?} tick", monitor.domain().name(), budget, tick));
+        }
+        self.tasks.push(Task { monitor, priority, tick, budget, due: Instant::now(), deferred: false, stats: TaskStats::default() });
+        Ok(self)
+    }
+
+    // Runs every domain that is due now, highest class first. Returns when nothing more may run
+    // yet, with the instant to call again.
+    pub async fn run_due(&mut self, observe: &mut impl FnMut(&HarmonyMonitor<Box<dyn Domain>>, &Evaluation)) -> Option<Instant> {
+        loop {
+            let now = Instant::now();
+            let i = match self.pick(now) {
+                Pick::Run(i) => i,
+                Pick::WaitUntil(at) => return self.publish_metrics(Some(at)),
+                Pick::Idle => return self.publish_metrics(self.next_due()),
+            };
+            let task = &mut self.tasks[i];
+            task.deferred = false;
+            let lateness = now.saturating_duration_since(task.due);
+            let starved = lateness > task.tick * STARVED_TICKS;
+            let mut extra = Conditions::new();
+            let severity = if task.priority == Priority::Critical { Severity::Critical } else { Severity::Major };
+            extra.record_with(
+                "scheduler_on_time",
+                severity,
+                !starved,
+                starved.then(|| format!("cycle started {:.0} ms late", lateness.as_secs_f64() * 1e3)),
+            );
+            let eval = task.monitor.cycle_with(&extra).await;
+            let runtime = now.elapsed();
+            let s = &mut task.stats;
+            s.cycles += 1;
+            s.last_runtime = runtime;
+            s.max_lateness = s.max_lateness.max(lateness);
+            s.starved_cycles += starved as u64;
+            // Stay on the tick grid; after falling behind, resume from now rather than bursting.
+            task.due = if lateness > task.tick { now + task.tick } else { task.due + task.tick };
+            if runtime > task.budget {
+                s.overruns += 1;
+                task.due += runtime - task.budget;
+            }
+            observe(&task.monitor, &eval);
+        }
+    }
+
+    fn publish_metrics(&self, wake: Option<Instant>) -> Option<Instant> {
+        if let Some(m) = &self.metrics {
+            *m.lock().unwrap() = self.metrics_text();
+        }
+        wake
+    }
+
+    // Due task of the highest class (earliest deadline within a class), unless a higher-class
+    // task falls due before it would finish; that one is then waited for and this one deferred,
+    // but only while the wait keeps it within STARVED_TICKS of its deadline.
+    fn pick(&mut self, now: Instant) -> Pick {
+        let due = (0..self.tasks.len()).filter(|&i| self.tasks[i].due <= now).max_by(|&a, &b| {
+            let (ta, tb) = (&self.tasks[a], &self.tasks[b]);
+            ta.priority.cmp(&tb.priority).then(tb.due.cmp(&ta.due))
+        });
+        let Some(i) = due else { return Pick::Idle };
+        let task = &self.tasks[i];
+        let (priority, finish) = (task.priority, now + task.budget);
+        let pre_empting = self.tasks.iter().filter(|t| t.priority > priority && t.due > now && t.due < finish).min_by_key(|t| t.due);
+        // Waiting costs until that task is due plus its budget; past that limit this one runs now.
+        match pre_empting.map(|t| (t.due, t.due + t.budget)) {
+            Some((at, resume)) if resume.saturating_duration_since(task.due) < task.tick * STARVED_TICKS => {
+                let task = &mut self.tasks[i];
+                if !task.deferred {
+                    task.deferred = true;
+                    task.stats.deferrals += 1;
+                }
+                Pick::WaitUntil(at)
+            }
+            _ => Pick::Run(i),
+        }
+    }
+
+    // Earliest deadline across all domains.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.tasks.iter().map(|t| t.due).min()
+    }
+
+    pub async fn run(&mut self, mut observe: impl FnMut(&HarmonyMonitor<Box<dyn Domain>>, &Evaluation)) {
+        loop {
+            let wake = self.run_due(&mut observe).await;
+            let wait = wake.map_or(Duration::from_millis(100), |at| at.saturating_duration_since(Instant::now()));
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    pub fn stats(&self) -> Vec<(&str, Priority, TaskStats)> {
+        self.tasks.iter().map(|t| (t.monitor.domain().name(), t.priority, t.stats)).collect()
+    }
+
+    // Prometheus text exposition, one series per domain, then each domain's channel statistics.
+    pub fn metrics_text(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# TYPE harmony_sched_cycles_total counter\n# TYPE harmony_sched_overruns_total counter\n");
+        out.push_str("# TYPE harmony_sched_deferrals_total counter\n# TYPE harmony_sched_starved_cycles_total counter\n");
+        out.push_str("# TYPE harmony_sched_max_lateness_seconds gauge\n");
+        for (name, priority, s) in self.stats() {
+            let labels = format!("domain=\"{}\",class=\"{:?}\"", name, priority);
+            let _ = writeln!(out, "harmony_sched_cycles_total{{{}}} {}", labels, s.cycles);
+            let _ = writeln!(out, "harmony_sched_overruns_total{{{}}} {}", labels, s.overruns);
+            let _ = writeln!(out, "harmony_sched_deferrals_total{{{}}} {}", labels, s.deferrals);
+            let _ = writeln!(out, "harmony_sched_starved_cycles_total{{{}}} {}", labels, s.starved_cycles);
+            let _ = writeln!(out, "harmony_sched_max_lateness_seconds{{{}}} {}", labels, s.max_lateness.as_secs_f64());
+        }
+        out.push_str(stats::METRIC_TYPES);
+        for t in &self.tasks {
+            t.monitor.stats().write_series(&format!("domain=\"{}\",", t.monitor.domain().name()), &mut out);
+        }
+        out
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler::new()
+    }
+}
@@ -0,0 +1,160 @@
+//! Self_Harmony.rs - The engine's own health, scored and evaluated like a domain (forbid unsafe)
+//!
+//! Four channels score the monitor rather than the plant: cycle jitter (worst lateness of a
+//! cycle against its tick over the window), provider errors (failed samples over the window),
+//! audit lag (age of the oldest report or audit entry not yet delivered) and clock sync. Each
+//! reads 1 with no trouble and falls linearly to MIN_SCORE at its limit; mu is the worst
+//! channel (Aggregator::Minimum), GO while every channel is within half its limit and HALT once
+//! one reaches it. `record` turns that into `monitor_healthy` on the plant's conditions (Major
+//! at CAUTION, Critical at HALT), so the plant is never GO on a monitor that cannot be trusted,
+//! while reports name the monitor (HaltCode::MONITOR_DEGRADED) rather than the plant. An input
+//! never observed scores MIN_SCORE: feed every one each cycle.
+#![forbid(unsafe_code)]
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use super::harmony::{self, Aggregator, Conditions, Decision, Evaluation, HarmonyContext, Severity, MIN_SCORE};
+
+pub const CHANNELS: [&str; 4] = ["cycle_jitter", "provider_errors", "audit_lag", "clock_sync"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SelfHealthLimits {
+    pub max_jitter: Duration,
+    // Failed samples / samples over the window.
+    pub max_error_rate: f64,
+    pub max_audit_lag: Duration,
+    pub max_clock_offset: Duration,
+}
+
+pub const DEFAULT_SELF_HEALTH_LIMITS: SelfHealthLimits = SelfHealthLimits {
+    max_jitter: Duration::from_millis(100),
+    max_error_rate: 0.2,
+    max_audit_lag: Duration::from_secs(60),
+    max_clock_offset: Duration::from_millis(1),
+};
+
+pub struct SelfHarmony {
+    limits: SelfHealthLimits,
+    ctx: HarmonyContext,
+    window: usize,
+    last_start: Option<Instant>,
+    jitter: VecDeque<Duration>,
+    // (samples, failed) per cycle.
+    sampled: VecDeque<(usize, usize)>,
+    audit_lag: Option<Duration>,
+    // (synced, offset) from the clock-sync monitor.
+    clock: Option<(bool, i64)>,
+    scores: Vec<f64>,
+}
+
+impl SelfHarmony {
+    pub fn new(limits: SelfHealthLimits) -> Self {
+        let ctx = HarmonyContext::builder()
+            .weights(vec![0.25; CHANNELS.len()])
+            .aggregator(Aggregator::Minimum)
+            .threshold(0.5)
+            .caution_threshold(1e-6)
+            .build()
+            .expect("self-harmony context");
+        SelfHarmony {
+            limits,
+            ctx,
+            window: 60,
+            last_start: None,
+            jitter: VecDeque::new(),
+            sampled: VecDeque::new(),
+            audit_lag: None,
+            clock: None,
+            scores: Vec::with_capacity(CHANNELS.len()),
+        }
+    }
+
+    // Cycles that jitter and provider errors are judged over.
+    pub fn window(mut self, cycles: usize) -> Self {
+        self.window = cycles.max(1);
+        self
+    }
+
+    pub fn context(&self) -> &HarmonyContext {
+        &self.ctx
+    }
+
+    // At the top of each cycle, with the tick the cycle is meant to run at.
+    pub fn cycle_started(&mut self, now: Instant, tick: Duration) {
+        if let Some(last) = self.last_start.replace(now) {
+            let interval = now.saturating_duration_since(last);
+            push_bounded(&mut self.jitter, interval.saturating_sub(tick).max(tick.saturating_sub(interval)), self.window);
+        }
+    }
+
+    pub fn sources_sampled(&mut self, samples: usize, failed: usize) {
+        push_bounded(&mut self.sampled, (samples, failed), self.window);
+    }
+
+    pub fn audit_lag(&mut self, lag: Duration) {
+        self.audit_lag = Some(lag);
+    }
+
+    pub fn clock(&mut self, synced: bool, offset_ns: i64) {
+        self.clock = Some((synced, offset_ns));
+    }
+
+    // This cycle's channel scores, in CHANNELS order, as of the last evaluate().
+    pub fn scores(&self) -> &[f64] {
+        &self.scores
+    }
+
+    pub fn evaluate(&mut self) -> Evaluation {
+        let l = &self.limits;
+        // The first cycle has no interval to judge yet.
+        let jitter = match (self.jitter.iter().max(), self.last_start) {
+            (Some(j), _) => Some(headroom(j.as_secs_f64(), l.max_jitter.as_secs_f64())),
+            (None, started) => started.map(|_| 1.0),
+        };
+        let (samples, failed) = self.sampled.iter().fold((0, 0), |(s, f), (ds, df)| (s + ds, f + df));
+        let errors = (samples > 0).then(|| headroom(failed as f64 / samples as f64, l.max_error_rate));
+        let audit = self.audit_lag.map(|lag| headroom(lag.as_secs_f64(), l.max_audit_lag.as_secs_f64()));
+        let clock = self.clock.map(|(synced, offset_ns)| {
+            if synced {
+                headroom(offset_ns.unsigned_abs() as f64, l.max_clock_offset.as_nanos() as f64)
+            } else {
+                MIN_SCORE
+            }
+        });
+        self.scores.clear();
+        self.scores.extend([jitter, errors, audit, clock].into_iter().map(|s| s.unwrap_or(MIN_SCORE)));
+        harmony::evaluate_conditions(&self.ctx, &self.scores, &Conditions::new())
+    }
+
+    // The self-evaluation's worst channel, for HaltReason subjects and logs.
+    pub fn worst(&self) -> Option<(&'static str, f64)> {
+        CHANNELS.iter().zip(&self.scores).map(|(n, s)| (*n, *s)).min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    // Records `monitor_healthy` from this cycle's self-evaluation, naming the channels below
+    // the GO threshold.
+    pub fn record(&self, eval: &Evaluation, conditions: &mut Conditions) {
+        let severity = if eval.decision == Decision::HALT { Severity::Critical } else { Severity::Major };
+        let detail = (eval.decision != Decision::GO).then(|| {
+            let mut d = String::new();
+            for (name, score) in CHANNELS.iter().zip(&self.scores).filter(|(_, s)| **s < self.ctx.threshold) {
+                let _ = write!(d, "{}{} {:.3}", if d.is_empty() { "" } else { ", " }, name, score);
+            }
+            d
+        });
+        conditions.record_with("monitor_healthy", severity, eval.decision == Decision::GO, detail);
+    }
+}
+
+// 1 at zero, MIN_SCORE at or past the limit.
+fn headroom(value: f64, limit: f64) -> f64 {
+    if limit > 0.0 { (1.0 - value / limit).clamp(MIN_SCORE, 1.0) } else { MIN_SCORE }
+}
+
+fn push_bounded<T>(q: &mut VecDeque<T>, v: T, cap: usize) {
+    while q.len() >= cap {
+        q.pop_front();
+    }
+    q.push_back(v);
+}
@@ -0,0 +1,623 @@
+//! Source.rs - Pluggable async ScoreSource trait and the per-domain SourceSet (forbid unsafe)
+//!
+//! Each domain registers one boxed source per weighted channel, in weight order. Real telemetry
+//! backends implement `ScoreSource`; `FnSource` adapts an existing async query function.
+//! `sample_traced` also records each score's Provenance for the EvaluationReport. Async sources
+//! are sampled concurrently under one deadline, the set's `timeout`, for the whole cycle. Samples carry
+//! the time they were measured (TimestampedScore); with `max_age` set, a sample older than that
+//! is stale and either substituted by MIN_SCORE (HALT) or kept under a failed Major condition.
+//! A sample stamped more than MAX_FUTURE_SKEW ahead of local time is malformed: it would
+//! otherwise read as fresh for as long as its clock runs ahead.
+//! A channel with a calibration curve (core::calibration) publishes a physical reading, mapped
+//! to its score before the [0, 1] range check; Provenance keeps the reading as `raw`.
+//! Under the `chaos` feature a FaultInjector (core::fault_injection) can be attached with
+//! `with_chaos`; it sees each sample after calibration and before the checks, as a real fault
+//! would arrive.
+#![forbid(unsafe_code)]
+use std::fmt::{self, Write};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+
+use super::budget::{BudgetLedger, Meter, Usage};
+use super::calibration::{Calibrations, Curve};
+#[cfg(any(test, feature = "chaos"))]
+use super::fault_injection::FaultInjector;
+use super::harmony::{Conditions, Severity, MIN_SCORE};
+
+pub type Score = f64;
+
+// A score with the unix-ms time it was measured at its origin, which for a cached or relayed
+// value (historian, SCADA last-value) is older than when it was polled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimestampedScore {
+    pub value: Score,
+    pub at_ms: u64,
+}
+
+impl TimestampedScore {
+    pub fn now(value: Score) -> Self {
+        TimestampedScore { value, at_ms: unix_ms() }
+    }
+}
+
+// A bare score was measured just now.
+impl From<Score> for TimestampedScore {
+    fn from(value: Score) -> Self {
+        TimestampedScore::now(value)
+    }
+}
+
+// Clocks are synced (clock_sync) well inside this; beyond it the source's clock is wrong.
+const MAX_FUTURE_SKEW: Duration = Duration::from_secs(1);
+
+fn unix_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StalePolicy {
+    // The stale channel scores MIN_SCORE and `data_fresh` fails as Critical.
+    Halt,
+    // The stale value is kept and `data_fresh` fails as Major, capping the decision at CAUTION.
+    Degrade,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SourceError {
+    Timeout(Duration),
+    Unavailable(String),
+    OutOfRange(f64),
+    Malformed(String),
+    Stale { value: f64, age: Duration },
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SourceError::Timeout(d) => write!(f, "no sample within {:?}", d),
+            SourceError::Unavailable(e) => write!(f, "unavailable: {}", e),
+            SourceError::OutOfRange(v) => write!(f, "score {} outside [0, 1]", v),
+            SourceError::Malformed(e) => write!(f, "malformed sample: {}", e),
+            SourceError::Stale { value, age } => write!(f, "sample {} is {:.1}s old", value, age.as_secs_f64()),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Quality {
+    #[default]
+    Good,
+    Timeout,
+    Unavailable,
+    OutOfRange,
+    Malformed,
+    Stale,
+}
+
+impl Quality {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Quality::Good => "good",
+            Quality::Timeout => "timeout",
+            Quality::Unavailable => "unavailable",
+            Quality::OutOfRange => "out_of_range",
+            Quality::Malformed => "malformed",
+            Quality::Stale => "stale",
+        }
+    }
+}
+
+// Where one channel's score came from: the source's endpoint and raw reading (NaN when there
+// was none), when it was measured, and every step between the reading and the value that entered mu.
+// Strings are rewritten in place each cycle, so a kept Vec<Provenance> stops allocating.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Provenance {
+    pub channel: String,
+    pub endpoint: String,
+    pub raw: f64,
+    pub score: f64,
+    pub transform: String,
+    pub acquired_ms: u64,
+    pub quality: Quality,
+}
+
+impl Provenance {
+    // `calibrated` is the physical reading and the curve that scored it, when there was one.
+    fn set_sample(&mut self, source: &dyn ScoreSource, sampled: &Result<Score, SourceError>, score: f64, acquired_ms: u64, calibrated: Option<(f64, &Curve)>) {
+        self.channel.clear();
+        self.channel.push_str(source.name());
+        self.endpoint.clear();
+        self.endpoint.push_str(source.endpoint());
+        self.transform.clear();
+        self.acquired_ms = acquired_ms;
+        self.score = score;
+        (self.raw, self.quality) = match sampled {
+            Ok(v) => (*v, Quality::Good),
+            Err(SourceError::OutOfRange(v)) => (*v, Quality::OutOfRange),
+            Err(SourceError::Stale { value, .. }) => (*value, Quality::Stale),
+            Err(SourceError::Timeout(_)) => (f64::NAN, Quality::Timeout),
+            Err(SourceError::Unavailable(_)) => (f64::NAN, Quality::Unavailable),
+            Err(SourceError::Malformed(_)) => (f64::NAN, Quality::Malformed),
+        };
+        let kept = self.raw;
+        if let Some((reading, curve)) = calibrated {
+            self.raw = reading;
+            let _ = write!(self.transform, "calibrated({}, {} knots)", curve.interpolation().as_str(), curve.knots().len());
+        }
+        match self.quality {
+            Quality::Good => {}
+            Quality::Stale if score == kept => self.push_transform(format_args!("stale value kept"), score),
+            _ => self.push_transform(format_args!("substituted MIN_SCORE"), score),
+        }
+    }
+
+    // Appends a step ("ewma(half_life=3s)") and the value it produced.
+    pub fn push_transform(&mut self, step: fmt::Arguments, score: f64) {
+        if !self.transform.is_empty() {
+            self.transform.push_str(", ");
+        }
+        let _ = self.transform.write_fmt(step);
+        self.score = score;
+    }
+}
+
+#[async_trait]
+pub trait ScoreSource: Send + Sync {
+    fn name(&self) -> &str;
+
+    // Where the reading comes from (URL, OPC UA node id, register address), for provenance.
+    fn endpoint(&self) -> &str {
+        self.name()
+    }
+
+    async fn sample(&self) -> Result<Score, SourceError>;
+
+    // sample() with the time the value was measured. Sources relaying cached or buffered values
+    // override this; the default stamps the sample as measured now.
+    async fn sample_timestamped(&self) -> Result<TimestampedScore, SourceError> {
+        self.sample().await.map(TimestampedScore::now)
+    }
+
+    // Synchronous sample (cached or memory-mapped telemetry); skips the boxed future and timeout.
+    fn sample_now(&self) -> Option<Result<TimestampedScore, SourceError>> {
+        None
+    }
+}
+
+// Wraps `async fn() -> f64` (or `-> TimestampedScore`) query functions; NaN is reported as
+// malformed.
+pub struct FnSource<F> {
+    name: String,
+    endpoint: Option<String>,
+    query: F,
+}
+
+impl<F> FnSource<F> {
+    pub fn new(name: &str, query: F) -> Self {
+        FnSource { name: name.to_string(), endpoint: None, query }
+    }
+
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.to_string());
+        self
+    }
+}
+
+#[async_trait]
+impl<F, Fut, T> ScoreSource for FnSource<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = T> + Send,
+    T: Into<TimestampedScore>,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn endpoint(&self) -> &str {
+        self.endpoint.as_deref().unwrap_or(&self.name)
+    }
+
+    async fn sample(&self) -> Result<Score, SourceError> {
+        self.sample_timestamped().await.map(|s| s.value)
+    }
+
+    async fn sample_timestamped(&self) -> Result<TimestampedScore, SourceError> {
+        let s: TimestampedScore = (self.query)().await.into();
+        if s.value.is_nan() { Err(SourceError::Malformed("NaN".into())) } else { Ok(s) }
+    }
+}
+
+// Wraps a synchronous `fn() -> f64` (or `-> TimestampedScore`) reader, e.g. a shared-memory
+// telemetry snapshot.
+pub struct SyncSource<F> {
+    name: String,
+    endpoint: Option<String>,
+    read: F,
+}
+
+impl<F> SyncSource<F> {
+    pub fn new(name: &str, read: F) -> Self {
+        SyncSource { name: name.to_string(), endpoint: None, read }
+    }
+
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.to_string());
+        self
+    }
+}
+
+#[async_trait]
+impl<F, T> ScoreSource for SyncSource<F>
+where
+    F: Fn() -> T + Send + Sync,
+    T: Into<TimestampedScore>,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn endpoint(&self) -> &str {
+        self.endpoint.as_deref().unwrap_or(&self.name)
+    }
+
+    async fn sample(&self) -> Result<Score, SourceError> {
+        self.sample_now().unwrap().map(|s| s.value)
+    }
+
+    fn sample_now(&self) -> Option<Result<TimestampedScore, SourceError>> {
+        let s: TimestampedScore = (self.read)().into();
+        Some(if s.value.is_nan() { Err(SourceError::Malformed("NaN".into())) } else { Ok(s) })
+    }
+}
+
+pub struct SourceSet {
+    sources: Vec<Box<dyn ScoreSource>>,
+    timeout: Duration,
+    ledger: Option<Arc<Mutex<BudgetLedger>>>,
+    max_age: Option<(Duration, StalePolicy)>,
+    // Per source, in registration order; None reads in score units.
+    curves: Vec<Option<Curve>>,
+    #[cfg(any(test, feature = "chaos"))]
+    chaos: Option<Arc<Mutex<FaultInjector>>>,
+}
+
+impl SourceSet {
+    pub fn new(timeout: Duration) -> Self {
+        SourceSet {
+            sources: Vec::new(),
+            timeout,
+            ledger: None,
+            max_age: None,
+            curves: Vec::new(),
+            #[cfg(any(test, feature = "chaos"))]
+            chaos: None,
+        }
+    }
+
+    // Samples measured longer than `max_age` ago are stale; `policy` decides what they do.
+    pub fn max_age(&mut self, max_age: Duration, policy: StalePolicy) -> &mut Self {
+        self.max_age = Some((max_age, policy));
+        self
+    }
+
+    // Charges each source's CPU time and allocations to `ledger` under its name.
+    pub fn with_ledger(&mut self, ledger: Arc<Mutex<BudgetLedger>>) -> &mut Self {
+        self.ledger = Some(ledger);
+        self
+    }
+
+    // Routes every sample through `injector`, targeted by source name. The injector itself
+    // ignores injections unless HARMONY_CHAOS=1.
+    #[cfg(any(test, feature = "chaos"))]
+    pub fn with_chaos(&mut self, injector: Arc<Mutex<FaultInjector>>) -> &mut Self {
+        self.chaos = Some(injector);
+        self
+    }
+
+    pub fn register(&mut self, source: Box<dyn ScoreSource>) -> &mut Self {
+        self.sources.push(source);
+        self.curves.push(None);
+        self
+    }
+
+    // Installs each calibration on the registered source of that name, replacing the curves
+    // from any earlier call; refused whole if one names no source.
+    pub fn calibrate(&mut self, calibrations: &Calibrations) -> Result<&mut Self, String> {
+        let mut curves = vec![None; self.sources.len()];
+        for c in calibrations.iter() {
+            let i = self.sources.iter().position(|s| s.name() == c.channel).ok_or_else(|| format!("calibrate {}: no such source", c.channel))?;
+            curves[i] = Some(c.curve.clone());
+        }
+        self.curves = curves;
+        Ok(self)
+    }
+
+    pub fn curve(&self, index: usize) -> Option<&Curve> {
+        self.curves.get(index)?.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.sources.iter().map(|s| s.name()).collect()
+    }
+
+    pub fn name(&self, index: usize) -> &str {
+        self.sources[index].name()
+    }
+
+    pub async fn sample_all(&self) -> (Vec<f64>, Vec<(String, SourceError)>) {
+        let (mut scores, mut errors) = (Vec::with_capacity(self.sources.len()), Vec::new());
+        self.sample_into(&mut scores, &mut errors).await;
+        let errors = errors.into_iter().map(|(i, e)| (self.name(i).to_string(), e)).collect();
+        (scores, errors)
+    }
+
+    // One score per source, in registration order, into buffers kept across cycles. A source
+    // that errors, times out or returns a value outside [0, 1] contributes MIN_SCORE and its
+    // index is listed in the errors; so is a stale one, which keeps its value only under
+    // StalePolicy::Degrade. Allocation-free on a clean cycle of sample_now() sources.
+    pub async fn sample_into(&self, scores: &mut Vec<f64>, errors: &mut Vec<(usize, SourceError)>) {
+        self.sample_with(scores, errors, |_, _, _, _, _| {}).await
+    }
+
+    // sample_into() that also fills one Provenance per source (reuse `provenance` too).
+    pub async fn sample_traced(&self, scores: &mut Vec<f64>, errors: &mut Vec<(usize, SourceError)>, provenance: &mut Vec<Provenance>) {
+        provenance.resize_with(self.sources.len(), Provenance::default);
+        self.sample_with(scores, errors, |i, sampled, score, at_ms, reading| {
+            let calibrated = reading.zip(self.curve(i));
+            provenance[i].set_sample(self.sources[i].as_ref(), sampled, score, at_ms, calibrated)
+        })
+        .await
+    }
+
+    // Records `data_fresh` (Critical under StalePolicy::Halt, Major under Degrade) from this
+    // cycle's errors, naming the stale channels. Records nothing when max_age is unset.
+    pub fn record_freshness(&self, errors: &[(usize, SourceError)], conditions: &mut Conditions) {
+        let Some((_, policy)) = self.max_age else { return };
+        let severity = if policy == StalePolicy::Halt { Severity::Critical } else { Severity::Major };
+        let mut stale = errors.iter().filter_map(|(i, e)| match e {
+            SourceError::Stale { age, .. } => Some((self.name(*i), age)),
+            _ => None,
+        });
+        let detail = stale.next().map(|(name, age)| {
+            let mut d = format!("{} {:.1}s old", name, age.as_secs_f64());
+            for (name, age) in stale {
+                let _ = write!(d, ", {} {:.1}s old", name, age.as_secs_f64());
+            }
+            d
+        });
+        conditions.record_with("data_fresh", severity, detail.is_none(), detail);
+    }
+
+    // Synchronous sources answer in turn; the rest are polled together, all under one deadline
+    // for the whole set, so a slow source costs the cycle at most `timeout` however many there
+    // are. Scores land by index and errors are sorted by it.
+    async fn sample_with(&self, scores: &mut Vec<f64>, errors: &mut Vec<(usize, SourceError)>, mut trace: impl FnMut(usize, &Result<Score, SourceError>, f64, u64, Option<f64>)) {
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        scores.clear();
+        scores.resize(self.sources.len(), MIN_SCORE);
+        errors.clear();
+        // Only async sources land here, and their boxed futures allocate anyway.
+        let mut pending = Vec::new();
+        for (i, source) in self.sources.iter().enumerate() {
+            let meter = Meter::start();
+            match source.sample_now() {
+                Some(r) => {
+                    self.record_usage(i, meter.stop());
+                    self.settle(i, Some(r), scores, errors, &mut trace);
+                }
+                None => pending.push(i),
+            }
+        }
+        if pending.is_empty() {
+            return;
+        }
+        let mut usages = vec![Usage::default(); pending.len()];
+        let results = join_all(pending.iter().zip(usages.iter_mut()).map(|(&i, usage)| {
+            tokio::time::timeout_at(deadline, Metered { inner: self.sources[i].sample_timestamped(), usage, started: Instant::now() })
+        }))
+        .await;
+        for ((&i, result), usage) in pending.iter().zip(results).zip(usages) {
+            self.record_usage(i, if result.is_ok() { usage } else { Usage { wall: self.timeout, ..usage } });
+            self.settle(i, result.ok(), scores, errors, &mut trace);
+        }
+        errors.sort_unstable_by_key(|(i, _)| *i);
+    }
+
+    // A failed sample stays failed; a good one goes through the injector, which may time it
+    // out, NaN it or lower it.
+    #[cfg(any(test, feature = "chaos"))]
+    fn inject(&self, i: usize, sampled: Option<Result<TimestampedScore, SourceError>>) -> Option<Result<TimestampedScore, SourceError>> {
+        let (Some(chaos), Some(Ok(s))) = (&self.chaos, &sampled) else { return sampled };
+        match chaos.lock().unwrap().score(self.name(i), Some(s.value)) {
+            None => None,
+            Some(v) if v.is_nan() => Some(Err(SourceError::Malformed("NaN".into()))),
+            Some(value) => Some(Ok(TimestampedScore { value, at_ms: s.at_ms })),
+        }
+    }
+
+    fn record_usage(&self, index: usize, usage: Usage) {
+        if let Some(ledger) = &self.ledger {
+            ledger.lock().unwrap().record_provider(self.sources[index].name(), usage);
+        }
+    }
+
+    // One source's sample (None: the set's deadline passed first) checked, calibrated and
+    // scored into `scores[i]`.
+    fn settle(
+        &self,
+        i: usize,
+        sampled: Option<Result<TimestampedScore, SourceError>>,
+        scores: &mut [f64],
+        errors: &mut Vec<(usize, SourceError)>,
+        trace: &mut impl FnMut(usize, &Result<Score, SourceError>, f64, u64, Option<f64>),
+    ) {
+        let now = unix_ms();
+        let at_ms = sampled.as_ref().and_then(|r| r.as_ref().ok()).map_or(now, |s| s.at_ms);
+        // The physical reading of a calibrated source; its score replaces it from here on.
+        let mut reading = None;
+        let sampled = match (sampled, self.curve(i)) {
+            (Some(Ok(s)), Some(curve)) => {
+                reading = Some(s.value);
+                Some(Ok(TimestampedScore { value: curve.map(s.value), at_ms: s.at_ms }))
+            }
+            (sampled, _) => sampled,
+        };
+        #[cfg(any(test, feature = "chaos"))]
+        let sampled = self.inject(i, sampled);
+        let age = Duration::from_millis(now.saturating_sub(at_ms));
+        let result = match sampled {
+            Some(Ok(s)) if s.at_ms > now.saturating_add(MAX_FUTURE_SKEW.as_millis() as u64) => {
+                Err(SourceError::Malformed(format!("measured {} ms in the future", s.at_ms - now)))
+            }
+            Some(Ok(s)) if !(0.0..=1.0).contains(&s.value) => Err(SourceError::OutOfRange(s.value)),
+            Some(Ok(s)) if self.max_age.is_some_and(|(max, _)| age > max) => Err(SourceError::Stale { value: s.value, age }),
+            Some(Ok(s)) => Ok(s.value),
+            Some(Err(e)) => Err(e),
+            None => Err(SourceError::Timeout(self.timeout)),
+        };
+        let score = match &result {
+            Ok(v) => *v,
+            Err(SourceError::Stale { value, .. }) if self.max_age.map(|(_, p)| p) == Some(StalePolicy::Degrade) => *value,
+            Err(_) => MIN_SCORE,
+        };
+        trace(i, &result, score, at_ms, reading);
+        scores[i] = score;
+        if let Err(e) = result {
+            errors.push((i, e));
+        }
+    }
+}
+
+// Charges an async source's polls, not the time other sources spend between them, to its Usage:
+// with the set polled together a single Meter around the await would count them all.
+struct Metered<'a, F> {
+    inner: F,
+    usage: &'a mut Usage,
+    started: Instant,
+}
+
+impl<F: Future + Unpin> Future for Metered<'_, F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let meter = Meter::start();
+        let this = &mut *self;
+        let out = Pin::new(&mut this.inner).poll(cx);
+        let polled = meter.stop();
+        this.usage.cpu += polled.cpu;
+        this.usage.allocations += polled.allocations;
+        this.usage.bytes += polled.bytes;
+        this.usage.wall = this.started.elapsed();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slow(name: &str, delay: Duration, value: f64) -> Box<dyn ScoreSource> {
+        Box::new(FnSource::new(name, move || async move {
+            tokio::time::sleep(delay).await;
+            value
+        }))
+    }
+
+    fn stamped(name: &str, value: f64, at_ms: u64) -> Box<dyn ScoreSource> {
+        Box::new(SyncSource::new(name, move || TimestampedScore { value, at_ms }))
+    }
+
+    #[tokio::test]
+    async fn async_sources_share_one_deadline() {
+        let mut set = SourceSet::new(Duration::from_millis(60));
+        for name in ["a", "b", "c"] {
+            set.register(slow(name, Duration::from_millis(25), 0.99));
+        }
+        let (scores, errors) = set.sample_all().await;
+        assert_eq!((scores, errors), (vec![0.99; 3], vec![]));
+
+        let mut set = SourceSet::new(Duration::from_millis(20));
+        set.register(slow("hung", Duration::from_secs(5), 0.99)).register(Box::new(SyncSource::new("cached", || 0.98)));
+        let started = Instant::now();
+        let (scores, errors) = set.sample_all().await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(scores, [MIN_SCORE, 0.98]);
+        assert_eq!(errors, [("hung".to_string(), SourceError::Timeout(Duration::from_millis(20)))]);
+    }
+
+    #[tokio::test]
+    async fn injected_faults_reach_the_score_checks() {
+        use super::super::fault_injection::Fault;
+
+        let chaos = Arc::new(Mutex::new(FaultInjector::new(true)));
+        let mut set = SourceSet::new(Duration::from_millis(50));
+        set.register(Box::new(SyncSource::new("flux", || 0.99)))
+            .register(Box::new(SyncSource::new("coolant", || 0.98)))
+            .register(Box::new(SyncSource::new("power", || 0.97)))
+            .with_chaos(chaos.clone());
+        {
+            let mut chaos = chaos.lock().unwrap();
+            chaos.inject("flux", Fault::Timeout);
+            chaos.inject("coolant", Fault::NaN);
+            chaos.inject("power", Fault::Constant(0.5));
+        }
+        let (scores, errors) = set.sample_all().await;
+        assert_eq!(scores, [MIN_SCORE, MIN_SCORE, 0.5]);
+        assert_eq!(errors, [("flux".to_string(), SourceError::Timeout(Duration::from_millis(50))), ("coolant".to_string(), SourceError::Malformed("NaN".into()))]);
+
+        chaos.lock().unwrap().clear_all();
+        assert_eq!(set.sample_all().await, (vec![0.99, 0.98, 0.97], vec![]));
+    }
+
+    #[tokio::test]
+    async fn bad_samples_score_min() {
+        let mut set = SourceSet::new(Duration::from_millis(50));
+        set.register(stamped("future", 0.99, unix_ms() + 60_000))
+            .register(Box::new(SyncSource::new("over", || 1.2)))
+            .register(Box::new(SyncSource::new("nan", || f64::NAN)))
+            .register(Box::new(SyncSource::new("ok", || 0.97)));
+        let (mut scores, mut errors, mut provenance) = (Vec::new(), Vec::new(), Vec::new());
+        set.sample_traced(&mut scores, &mut errors, &mut provenance).await;
+        assert_eq!(scores, [MIN_SCORE, MIN_SCORE, MIN_SCORE, 0.97]);
+        assert!(matches!(errors[0], (0, SourceError::Malformed(_))));
+        assert_eq!(errors[1], (1, SourceError::OutOfRange(1.2)));
+        assert!(matches!(errors[2], (2, SourceError::Malformed(_))));
+        assert_eq!(errors.len(), 3);
+        assert_eq!(provenance.iter().map(|p| p.quality).collect::<Vec<_>>(), [Quality::Malformed, Quality::OutOfRange, Quality::Malformed, Quality::Good]);
+        assert_eq!(provenance[1].transform, "substituted MIN_SCORE");
+    }
+
+    #[tokio::test]
+    async fn stale_samples_follow_the_policy() {
+        for (policy, score, severity) in [(StalePolicy::Halt, MIN_SCORE, Severity::Critical), (StalePolicy::Degrade, 0.99, Severity::Major)] {
+            let mut set = SourceSet::new(Duration::from_millis(50));
+            set.max_age(Duration::from_secs(2), policy);
+            set.register(stamped("historian", 0.99, unix_ms() - 10_000)).register(stamped("live", 0.98, unix_ms()));
+            let (mut scores, mut errors) = (Vec::new(), Vec::new());
+            set.sample_into(&mut scores, &mut errors).await;
+            assert_eq!(scores, [score, 0.98]);
+            let mut conditions = Conditions::new();
+            set.record_freshness(&errors, &mut conditions);
+            let fresh = &conditions.statuses[0];
+            assert_eq!((fresh.name.as_str(), fresh.ok, fresh.severity), ("data_fresh", false, severity));
+            assert!(fresh.detail.as_deref().unwrap().starts_with("historian 10."), "{:?}", fresh.detail);
+        }
+    }
+}
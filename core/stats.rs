@@ -0,0 +1,119 @@
+//! Stats.rs - Rolling-window statistics per score channel (forbid unsafe)
+//!
+//! Short-term behaviour without an external TSDB: min, max, mean, p95 and sample count of each
+//! channel's healthy scores over each configured window (a minute and fifteen, say), queried
+//! with stats() and exported as Prometheus gauges labelled by channel and window. A failed
+//! source adds nothing, so a window can hold fewer samples than its cycles; p95 is nearest-rank.
+//! Samples are kept for the longest window, capped at MAX_SAMPLES per channel.
+#![forbid(unsafe_code)]
+use std::collections::VecDeque;
+use std::fmt::{self, Write};
+use std::time::{Duration, Instant};
+
+use super::source::SourceError;
+
+const MAX_SAMPLES: usize = 65_536;
+
+// Families written by write_series, for hosts that combine several RollingStats in one exposition.
+pub const METRIC_TYPES: &str = "# TYPE harmony_channel_min gauge\n# TYPE harmony_channel_max gauge\n# TYPE harmony_channel_mean gauge\n\
+# TYPE harmony_channel_p95 gauge\n# TYPE harmony_channel_samples gauge\n";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChannelStats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub p95: f64,
+    pub count: usize,
+}
+
+impl fmt::Display for ChannelStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "min {:.4} max {:.4} mean {:.4} p95 {:.4} n {}", self.min, self.max, self.mean, self.p95, self.count)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RollingStats {
+    names: Vec<String>,
+    windows: Vec<Duration>,
+    samples: Vec<VecDeque<(Instant, f64)>>,
+}
+
+impl RollingStats {
+    pub fn new<N: AsRef<str>>(names: &[N], windows: &[Duration]) -> Self {
+        let mut windows = windows.to_vec();
+        windows.sort();
+        windows.dedup();
+        RollingStats { names: names.iter().map(|n| n.as_ref().to_string()).collect(), windows, samples: vec![VecDeque::new(); names.len()] }
+    }
+
+    pub fn windows(&self) -> &[Duration] {
+        &self.windows
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    // One healthy reading; non-finite values are ignored.
+    pub fn push(&mut self, channel: usize, value: f64, now: Instant) {
+        let longest = self.windows.last().copied().unwrap_or_default();
+        let Some(q) = self.samples.get_mut(channel) else { return };
+        while q.front().is_some_and(|(at, _)| now.saturating_duration_since(*at) > longest) || q.len() >= MAX_SAMPLES {
+            q.pop_front();
+        }
+        if value.is_finite() {
+            q.push_back((now, value));
+        }
+    }
+
+    // This cycle's scores, skipping the channels whose source failed.
+    pub fn update(&mut self, scores: &[f64], errors: &[(usize, SourceError)], now: Instant) {
+        for (i, score) in scores.iter().enumerate() {
+            if !errors.iter().any(|(failed, _)| *failed == i) {
+                self.push(i, *score, now);
+            }
+        }
+    }
+
+    // Over the `window` ending at the last sample; None without samples in it. Windows longer
+    // than the longest configured are cut to it.
+    pub fn stats(&self, channel: usize, window: Duration) -> Option<ChannelStats> {
+        let q = self.samples.get(channel)?;
+        let (end, _) = q.back()?;
+        let mut values: Vec<f64> = q.iter().rev().take_while(|(at, _)| end.saturating_duration_since(*at) <= window).map(|(_, v)| *v).collect();
+        values.sort_unstable_by(f64::total_cmp);
+        let count = values.len();
+        Some(ChannelStats {
+            min: values[0],
+            max: values[count - 1],
+            mean: values.iter().sum::<f64>() / count as f64,
+            p95: values[(count * 95).div_ceil(100).max(1) - 1],
+            count,
+        })
+    }
+
+    // One series per channel and configured window, each carrying `labels` (e.g.
+    // `domain="grid",`) ahead of its own; no TYPE lines.
+    pub fn write_series(&self, labels: &str, out: &mut String) {
+        for window in &self.windows {
+            for (i, name) in self.names.iter().enumerate() {
+                let Some(s) = self.stats(i, *window) else { continue };
+                let l = format!("{}channel=\"{}\",window=\"{}s\"", labels, name, window.as_secs());
+                let _ = writeln!(out, "harmony_channel_min{{{}}} {}", l, s.min);
+                let _ = writeln!(out, "harmony_channel_max{{{}}} {}", l, s.max);
+                let _ = writeln!(out, "harmony_channel_mean{{{}}} {}", l, s.mean);
+                let _ = writeln!(out, "harmony_channel_p95{{{}}} {}", l, s.p95);
+                let _ = writeln!(out, "harmony_channel_samples{{{}}} {}", l, s.count);
+            }
+        }
+    }
+
+    // Prometheus text exposition.
+    pub fn metrics_text(&self) -> String {
+        let mut out = String::from(METRIC_TYPES);
+        self.write_series("", &mut out);
+        out
+    }
+}
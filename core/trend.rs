@@ -0,0 +1,178 @@
+//! Trend.rs - Per-channel rate of change, gating a score that is falling fast (forbid unsafe)
+//!
+//! A score can sit above every floor and still be heading for them: containment pressure
+//! margin dropping at 0.02/s is an event long before mu notices. Each channel's slope is the
+//! least-squares fit over the last `window` of healthy scores, in score units per second, and
+//! is reported for every channel (NaN until the window holds enough history). A channel with a
+//! limit fails `score_falling` (Major, CAUTION) or `score_falling_fast` (Critical, HALT) when
+//! it falls faster than the limit. A failed source restarts its channel's history, as in
+//! ScoreFilters. Applied after ScoreFilters::apply, so the fit sees filtered scores.
+//!
+//! MuTrend fits mu itself the same way and extrapolates to the HALT line (the caution
+//! threshold), giving operators an estimated lead time before the hold is commanded
+//! (DecisionRecord::eta_to_halt). It only reports; nothing is gated on it.
+#![forbid(unsafe_code)]
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use super::harmony::{Conditions, Severity};
+use super::source::SourceError;
+
+// Bounds memory at high cycle rates; the fit only needs the window's shape.
+const MAX_SAMPLES: usize = 1024;
+const MIN_SAMPLES: usize = 3;
+
+// Fall rates in score units per second, both positive; f64::INFINITY disables a tier.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SlopeLimit {
+    pub caution_per_sec: f64,
+    pub halt_per_sec: f64,
+}
+
+#[derive(Clone, Debug)]
+struct Channel {
+    samples: VecDeque<(Instant, f64)>,
+    limit: Option<SlopeLimit>,
+    slope: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Trends {
+    window: Duration,
+    channels: Vec<Channel>,
+}
+
+impl Trends {
+    // No slope is reported until a channel's history spans half the window.
+    pub fn new(channels: usize, window: Duration) -> Self {
+        Trends { window, channels: vec![Channel { samples: VecDeque::new(), limit: None, slope: f64::NAN }; channels] }
+    }
+
+    pub fn limit(mut self, channel: usize, caution_per_sec: f64, halt_per_sec: f64) -> Self {
+        self.channels[channel].limit = Some(SlopeLimit { caution_per_sec, halt_per_sec });
+        self
+    }
+
+    // This cycle's slope per second; NaN while the channel lacks history.
+    pub fn slope(&self, channel: usize) -> f64 {
+        self.channels.get(channel).map_or(f64::NAN, |c| c.slope)
+    }
+
+    // Every channel's slope, in weight order, for DecisionRecord::slopes.
+    pub fn slopes_into(&self, out: &mut Vec<f64>) {
+        out.clear();
+        out.extend(self.channels.iter().map(|c| c.slope));
+    }
+
+    // Adds this cycle's scores and refits; no allocation once each window is full.
+    pub fn update(&mut self, scores: &[f64], errors: &[(usize, SourceError)], now: Instant) {
+        for (i, (score, c)) in scores.iter().zip(self.channels.iter_mut()).enumerate() {
+            if errors.iter().any(|(failed, _)| *failed == i) || !score.is_finite() {
+                c.samples.clear();
+                c.slope = f64::NAN;
+                continue;
+            }
+            while c.samples.front().is_some_and(|(at, _)| now.saturating_duration_since(*at) > self.window) || c.samples.len() >= MAX_SAMPLES {
+                c.samples.pop_front();
+            }
+            c.samples.push_back((now, *score));
+            c.slope = fit(&c.samples, self.window / 2);
+        }
+    }
+
+    // Records `score_falling` and `score_falling_fast`, naming each channel over its limit.
+    // Records nothing when no channel has a limit.
+    pub fn record<N: AsRef<str>>(&self, names: &[N], conditions: &mut Conditions) {
+        if self.channels.iter().all(|c| c.limit.is_none()) {
+            return;
+        }
+        let over = |tier: fn(&SlopeLimit) -> f64| {
+            let mut detail: Option<String> = None;
+            for (c, name) in self.channels.iter().zip(names) {
+                let Some(limit) = &c.limit else { continue };
+                // NaN never compares below, so a channel without history never fails.
+                if c.slope < -tier(limit) {
+                    let d = detail.get_or_insert_with(String::new);
+                    let _ = write!(d, "{}{} falling {:.4}/s", if d.is_empty() { "" } else { ", " }, name.as_ref(), -c.slope);
+                }
+            }
+            detail
+        };
+        let caution = over(|l| l.caution_per_sec);
+        let halt = over(|l| l.halt_per_sec);
+        conditions.record_with("score_falling", Severity::Major, caution.is_none(), caution);
+        conditions.record_with("score_falling_fast", Severity::Critical, halt.is_none(), halt);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MuTrend {
+    window: Duration,
+    horizon: Duration,
+    samples: VecDeque<(Instant, f64)>,
+    slope: f64,
+}
+
+impl MuTrend {
+    // ETAs beyond four windows are not reported unless horizon() says otherwise.
+    pub fn new(window: Duration) -> Self {
+        MuTrend { window, horizon: window * 4, samples: VecDeque::new(), slope: f64::NAN }
+    }
+
+    // Longest lead time worth reporting; a slow drift is left to the trend of each channel.
+    pub fn horizon(mut self, horizon: Duration) -> Self {
+        self.horizon = horizon;
+        self
+    }
+
+    // The mu this cycle decides on (interval.lower when deciding on the lower bound).
+    pub fn update(&mut self, mu: f64, now: Instant) {
+        if !mu.is_finite() {
+            self.samples.clear();
+            self.slope = f64::NAN;
+            return;
+        }
+        while self.samples.front().is_some_and(|(at, _)| now.saturating_duration_since(*at) > self.window) || self.samples.len() >= MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((now, mu));
+        self.slope = fit(&self.samples, self.window / 2);
+    }
+
+    // mu per second; NaN while lacking history.
+    pub fn slope(&self) -> f64 {
+        self.slope
+    }
+
+    // Time until mu, falling at its fitted rate from the last value, drops below `halt_below`;
+    // zero once below it. None while mu is steady or rising, lacks history, or would take
+    // longer than the horizon.
+    pub fn eta(&self, halt_below: f64) -> Option<Duration> {
+        let (_, mu) = self.samples.back()?;
+        if *mu < halt_below {
+            return Some(Duration::ZERO);
+        }
+        if self.slope.is_nan() || self.slope >= 0.0 {
+            return None;
+        }
+        let secs = (mu - halt_below) / -self.slope;
+        (secs <= self.horizon.as_secs_f64()).then(|| Duration::from_secs_f64(secs))
+    }
+}
+
+// Least-squares slope of score over time, per second; NaN until the samples span `min_span`.
+fn fit(samples: &VecDeque<(Instant, f64)>, min_span: Duration) -> f64 {
+    let (Some((first, _)), Some((last, _))) = (samples.front(), samples.back()) else { return f64::NAN };
+    if samples.len() < MIN_SAMPLES || last.saturating_duration_since(*first) < min_span {
+        return f64::NAN;
+    }
+    let n = samples.len() as f64;
+    let t = |at: &Instant| at.saturating_duration_since(*first).as_secs_f64();
+    let (mean_t, mean_s) = samples.iter().fold((0.0, 0.0), |(mt, ms), (at, s)| (mt + t(at) / n, ms + s / n));
+    let (cov, var) = samples.iter().fold((0.0, 0.0), |(cov, var), (at, s)| {
+        let dt = t(at) - mean_t;
+        (cov + dt * (s - mean_s), var + dt * dt)
+    });
+    if var > 0.0 { cov / var } else { f64::NAN }
+}
@@ -0,0 +1,251 @@
+//! Tuning.rs - Slow, bounded, audited adaptation of channel weights (forbid unsafe)
+//!
+//! A feed that dips often without a HALT following is noise, and weighting it as approved keeps
+//! costing CAUTIONs. WeightTuner scores each channel's predictive power for past HALTs: a dip
+//! (score below `dip_below`, outside HALT) is a hit if a HALT begins within `horizon` cycles
+//! and a false alarm otherwise; power is (hits + 1) / (hits + false alarms + 2), so a channel
+//! with no history sits at 0.5. Every `period` cycles in which some dip resolved, each weight
+//! moves `rate` of the way toward the approved weight scaled by power, by at most `max_step`,
+//! and the result is fitted into the domain's WeightGovernance (each channel between its floor
+//! and the cap) with the weights still summing to 1. A change is checked against that
+//! governance, then recorded by the audit sink, and only then handed to the caller; without a
+//! sink, or if it fails, the weights stay as they are. A caller that still cannot install an
+//! audited change reverts the tuner to the weights in force.
+#![forbid(unsafe_code)]
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::governance::WeightGovernance;
+use super::harmony::{validate_weights, Decision, Evaluation, WEIGHT_SUM_TOLERANCE};
+use super::source::SourceError;
+
+// Smaller moves are not worth an audit entry; the tuner waits until the drift adds up.
+const MIN_CHANGE: f64 = 1e-4;
+
+// A weight change, handed to the audit sink before it takes effect.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightChange {
+    pub at_ms: u64,
+    pub domain: String,
+    pub from: Vec<f64>,
+    pub to: Vec<f64>,
+    // Per channel predictive power the change was based on.
+    pub power: Vec<f64>,
+}
+
+impl fmt::Display for WeightChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: weights {} -> {} (power {})", self.domain, Fixed(&self.from), Fixed(&self.to), Fixed(&self.power))
+    }
+}
+
+struct Fixed<'a>(&'a [f64]);
+
+impl fmt::Display for Fixed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("[")?;
+        for (i, v) in self.0.iter().enumerate() {
+            write!(f, "{}{:.4}", if i > 0 { ", " } else { "" }, v)?;
+        }
+        f.write_str("]")
+    }
+}
+
+type WeightAudit = Box<dyn FnMut(&WeightChange) -> Result<(), String> + Send>;
+
+#[derive(Clone, Debug, Default)]
+struct Channel {
+    dipping: bool,
+    // Cycles at which still-unresolved dips began.
+    open: VecDeque<u64>,
+    hits: u32,
+    false_alarms: u32,
+}
+
+pub struct WeightTuner {
+    domain: String,
+    approved: Vec<f64>,
+    governance: WeightGovernance,
+    bounds: Vec<(f64, f64)>,
+    weights: Vec<f64>,
+    dip_below: f64,
+    horizon: u64,
+    period: u64,
+    rate: f64,
+    max_step: f64,
+    channels: Vec<Channel>,
+    cycle: u64,
+    resolved: u32,
+    was_halt: bool,
+    audit: Option<WeightAudit>,
+}
+
+impl WeightTuner {
+    // `approved` are the configured weights and `governance` the same limits the domain's
+    // context enforces: each channel moves between its floor (0 if it has none) and the cap.
+    // The approved weights must satisfy it.
+    pub fn new(domain: &str, approved: &[f64], governance: &WeightGovernance) -> Result<Self, String> {
+        let bounds: Vec<(f64, f64)> = (0..approved.len()).map(|i| (governance.min_weight(i).unwrap_or(0.0), governance.max_weight())).collect();
+        for (i, (w, (min, max))) in approved.iter().zip(&bounds).enumerate() {
+            if !(0.0 <= *min && min <= w && w <= max && *max <= 1.0) {
+                return Err(format!("{}: channel {} weight {} is outside its bounds [{}, {}]", domain, i, w, min, max));
+            }
+        }
+        let (lo, hi) = bounds.iter().fold((0.0, 0.0), |(lo, hi), (min, max)| (lo + min, hi + max));
+        if lo > 1.0 + WEIGHT_SUM_TOLERANCE || hi < 1.0 - WEIGHT_SUM_TOLERANCE {
+            return Err(format!("{}: weight bounds sum to [{}, {}], which excludes 1", domain, lo, hi));
+        }
+        Ok(WeightTuner {
+            domain: domain.to_string(),
+            approved: approved.to_vec(),
+            governance: governance.clone(),
+            bounds,
+            weights: approved.to_vec(),
+            dip_below: 0.999,
+            horizon: 300,
+            period: 600,
+            rate: 0.05,
+            max_step: 0.005,
+            channels: vec![Channel::default(); approved.len()],
+            cycle: 0,
+            resolved: 0,
+            was_halt: false,
+            audit: None,
+        })
+    }
+
+    // Score below which a channel counts as dipping.
+    pub fn dip_below(mut self, score: f64) -> Self {
+        self.dip_below = score;
+        self
+    }
+
+    // Cycles after a dip within which a HALT counts as predicted by it.
+    pub fn horizon(mut self, cycles: u64) -> Self {
+        self.horizon = cycles.max(1);
+        self
+    }
+
+    // Cycles between adjustments.
+    pub fn period(mut self, cycles: u64) -> Self {
+        self.period = cycles.max(1);
+        self
+    }
+
+    // Fraction of the way toward the target per adjustment, and the most any weight moves in one.
+    pub fn rate(mut self, rate: f64, max_step: f64) -> Self {
+        self.rate = rate.clamp(0.0, 1.0);
+        self.max_step = max_step.max(0.0);
+        self
+    }
+
+    // Where weight changes are recorded. The sink runs before the change applies; if it fails,
+    // the change is refused.
+    pub fn audit_changes(&mut self, sink: impl FnMut(&WeightChange) -> Result<(), String> + Send + 'static) {
+        self.audit = Some(Box::new(sink));
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    // Back to the weights actually in force, after the caller could not install a change.
+    pub fn revert(&mut self, weights: &[f64]) {
+        if weights.len() == self.weights.len() {
+            self.weights.copy_from_slice(weights);
+        }
+    }
+
+    pub fn power(&self, channel: usize) -> f64 {
+        self.channels.get(channel).map_or(f64::NAN, |c| f64::from(c.hits + 1) / f64::from(c.hits + c.false_alarms + 2))
+    }
+
+    // One cycle's scores and decision. Ok(Some(weights)) when the weights changed this cycle
+    // and were audited, for the caller to install; Err when an adjustment was refused.
+    pub fn observe(&mut self, scores: &[f64], errors: &[(usize, SourceError)], eval: &Evaluation) -> Result<Option<&[f64]>, String> {
+        self.cycle += 1;
+        let halt = eval.decision == Decision::HALT;
+        let onset = halt && !self.was_halt;
+        self.was_halt = halt;
+        for (i, c) in self.channels.iter_mut().enumerate() {
+            let failed = errors.iter().any(|(f, _)| *f == i);
+            let dip = !failed && scores.get(i).is_some_and(|s| *s < self.dip_below);
+            let started = dip && !c.dipping;
+            if started && !halt {
+                c.open.push_back(self.cycle);
+            }
+            c.dipping = dip;
+            // A dip beginning on the HALT's own cycle predicted it as much as one a cycle earlier.
+            if onset && (started || !c.open.is_empty()) {
+                c.hits += 1;
+                c.open.clear();
+                self.resolved += 1;
+            }
+            while c.open.front().is_some_and(|at| self.cycle - at >= self.horizon) {
+                c.open.pop_front();
+                c.false_alarms += 1;
+                self.resolved += 1;
+            }
+        }
+        if !self.cycle.is_multiple_of(self.period) || self.resolved == 0 {
+            return Ok(None);
+        }
+        self.resolved = 0;
+        self.adjust()
+    }
+
+    fn adjust(&mut self) -> Result<Option<&[f64]>, String> {
+        let power: Vec<f64> = (0..self.channels.len()).map(|i| self.power(i)).collect();
+        let total: f64 = self.approved.iter().zip(&power).map(|(w, p)| w * p).sum();
+        if total.is_nan() || total <= 0.0 {
+            return Ok(None);
+        }
+        let stepped: Vec<f64> = self
+            .weights
+            .iter()
+            .zip(self.approved.iter().zip(&power))
+            .map(|(w, (a, p))| w + (self.rate * (a * p / total - w)).clamp(-self.max_step, self.max_step))
+            .collect();
+        let to = fit_bounds(&stepped, &self.bounds);
+        if to.iter().zip(&self.weights).all(|(a, b)| (a - b).abs() < MIN_CHANGE) {
+            return Ok(None);
+        }
+        let change = WeightChange {
+            at_ms: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0),
+            domain: self.domain.clone(),
+            from: self.weights.clone(),
+            to,
+            power,
+        };
+        validate_weights(&change.to)
+            .and_then(|_| self.governance.check(&change.to))
+            .map_err(|e| format!("{}: refusing weight change {}: {}", change.domain, change, e))?;
+        let audit = self.audit.as_mut().ok_or_else(|| format!("{}: no audit sink attached; refusing weight change", change.domain))?;
+        audit(&change).map_err(|e| format!("{}: audit unavailable, refusing weight change: {}", change.domain, e))?;
+        self.weights = change.to;
+        Ok(Some(&self.weights))
+    }
+}
+
+// The weights shifted by a common amount and clamped to their bounds, with the shift chosen so
+// they sum to 1 (bisection; the sum is monotone in the shift).
+fn fit_bounds(weights: &[f64], bounds: &[(f64, f64)]) -> Vec<f64> {
+    let shifted = |shift: f64| weights.iter().zip(bounds).map(move |(w, (min, max))| (w + shift).clamp(*min, *max));
+    let (mut lo, mut hi) = (-1.0, 1.0);
+    for _ in 0..64 {
+        let mid = (lo + hi) / 2.0;
+        if shifted(mid).sum::<f64>() < 1.0 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    let mut out: Vec<f64> = shifted((lo + hi) / 2.0).collect();
+    // Leave the sum exact for validate_weights.
+    let sum: f64 = out.iter().sum();
+    if let Some(slack) = out.iter().zip(bounds).position(|(w, (min, max))| *min <= w + 1.0 - sum && w + 1.0 - sum <= *max) {
+        out[slack] += 1.0 - sum;
+    }
+    out
+}
@@ -0,0 +1,188 @@
+//! Validity.rs - Time-of-validity windows for periodic CH conditions (forbid unsafe)
+//!
+//! Audits, red-team reports and surveillance tests hold for a fixed period after they are
+//! attested. Past that a grace period only alerts; after the grace period, or inside a
+//! scheduled blackout, the condition fails at its configured severity.
+#![forbid(unsafe_code)]
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::checks::{CHCheck, CheckOutcome};
+use super::harmony::{Conditions, Severity};
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+// `duration_ms` from `start_ms`, repeating every `every_ms` if set (e.g. weekly test slots).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Blackout {
+    pub start_ms: u64,
+    pub duration_ms: u64,
+    pub every_ms: Option<u64>,
+}
+
+impl Blackout {
+    pub fn contains(&self, now_ms: u64) -> bool {
+        if now_ms < self.start_ms {
+            return false;
+        }
+        let offset = match self.every_ms {
+            Some(every) if every > 0 => (now_ms - self.start_ms) % every,
+            _ => now_ms - self.start_ms,
+        };
+        offset < self.duration_ms
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Validity {
+    pub valid_for: Duration,
+    pub grace: Duration,
+    pub blackouts: Vec<Blackout>,
+}
+
+impl Validity {
+    pub fn days(valid_days: u64, grace_days: u64) -> Self {
+        Validity {
+            valid_for: Duration::from_millis(valid_days * DAY_MS),
+            grace: Duration::from_millis(grace_days * DAY_MS),
+            blackouts: Vec::new(),
+        }
+    }
+
+    pub fn with_blackout(mut self, blackout: Blackout) -> Self {
+        self.blackouts.push(blackout);
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidityState {
+    Valid,
+    InGrace,
+    Expired,
+    Blackout,
+    NeverAttested,
+}
+
+pub struct PeriodicCondition {
+    pub name: String,
+    pub severity: Severity,
+    pub validity: Validity,
+    attested_ms: Option<u64>,
+    clock: fn() -> u64,
+}
+
+impl PeriodicCondition {
+    // `clock` returns wall-clock ms (decision::now_ms in the monitors).
+    pub fn new(name: &str, severity: Severity, validity: Validity, clock: fn() -> u64) -> Self {
+        PeriodicCondition { name: name.to_string(), severity, validity, attested_ms: None, clock }
+    }
+
+    // Records an attestation; an older one than already held is ignored.
+    pub fn attest(&mut self, at_ms: u64) {
+        if self.attested_ms.is_none_or(|prev| at_ms > prev) {
+            self.attested_ms = Some(at_ms);
+        }
+    }
+
+    // Back to NeverAttested, for a holder re-deriving its attestations from scratch.
+    pub fn clear(&mut self) {
+        self.attested_ms = None;
+    }
+
+    pub fn state_at(&self, now_ms: u64) -> ValidityState {
+        if self.validity.blackouts.iter().any(|b| b.contains(now_ms)) {
+            return ValidityState::Blackout;
+        }
+        let Some(at) = self.attested_ms else { return ValidityState::NeverAttested };
+        // An attestation dated in the future is not trusted.
+        if at > now_ms {
+            return ValidityState::Expired;
+        }
+        let age = Duration::from_millis(now_ms - at);
+        if age <= self.validity.valid_for {
+            ValidityState::Valid
+        } else if age <= self.validity.valid_for + self.validity.grace {
+            ValidityState::InGrace
+        } else {
+            ValidityState::Expired
+        }
+    }
+
+    pub fn state(&self) -> ValidityState {
+        self.state_at((self.clock)())
+    }
+
+    // Grace alerts only; every other non-valid state fails at the configured severity.
+    fn effective(&self, state: ValidityState) -> (bool, Severity) {
+        match state {
+            ValidityState::Valid => (true, self.severity),
+            ValidityState::InGrace => (false, Severity::Advisory),
+            _ => (false, self.severity),
+        }
+    }
+
+    // Human-readable reason for a non-valid state, for explanations and alerts.
+    pub fn describe_at(&self, now_ms: u64) -> Option<String> {
+        let expires = self.attested_ms.map(|at| at + self.validity.valid_for.as_millis() as u64);
+        match self.state_at(now_ms) {
+            ValidityState::Valid => None,
+            ValidityState::InGrace => {
+                let grace_end = expires.unwrap_or(now_ms) + self.validity.grace.as_millis() as u64;
+                Some(format!("expired {} ago, grace ends in {}", ago(now_ms, expires.unwrap_or(now_ms)), ago(grace_end, now_ms)))
+            }
+            ValidityState::Expired => match expires {
+                Some(e) if e <= now_ms => Some(format!("expired {} ago", ago(now_ms, e))),
+                _ => Some("attestation is dated in the future".to_string()),
+            },
+            ValidityState::Blackout => Some("in a scheduled blackout window".to_string()),
+            ValidityState::NeverAttested => Some("never attested".to_string()),
+        }
+    }
+
+    fn outcome(&self) -> CheckOutcome {
+        let now = (self.clock)();
+        let (pass, _) = self.effective(self.state_at(now));
+        CheckOutcome { pass, reason: self.describe_at(now), evidence: None }
+    }
+
+    pub fn record_into(&self, conditions: &mut Conditions) {
+        let now = (self.clock)();
+        let (ok, severity) = self.effective(self.state_at(now));
+        conditions.record_with(&self.name, severity, ok, self.describe_at(now));
+    }
+}
+
+// Registered as a CHCheck, the condition fails with its describe_at() reason.
+#[async_trait]
+impl CHCheck for PeriodicCondition {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn severity(&self) -> Severity {
+        self.effective(self.state()).1
+    }
+
+    async fn check(&self) -> CheckOutcome {
+        self.outcome()
+    }
+
+    fn check_now(&self) -> Option<CheckOutcome> {
+        Some(self.outcome())
+    }
+}
+
+// Coarse "3 days" / "5 hours" / "12 minutes" for `later - earlier`.
+fn ago(later_ms: u64, earlier_ms: u64) -> String {
+    let ms = later_ms.saturating_sub(earlier_ms);
+    let plural = |n: u64, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    if ms >= DAY_MS {
+        plural(ms / DAY_MS, "day")
+    } else if ms >= 3_600_000 {
+        plural(ms / 3_600_000, "hour")
+    } else {
+        plural(ms / 60_000, "minute")
+    }
+}
@@ -0,0 +1,257 @@
+//! Vote.rs - MooN voting over redundant sensors behind one score channel (forbid unsafe)
+//!
+//! A VotedSource is one ScoreSource, so one weighted channel, backed by several legs (three H2S
+//! heads, three neutron flux detectors) as IEC 61508-style MooN architectures expect. Each
+//! cycle it samples every leg and votes: Median takes the median of the healthy legs, Majority
+//! the median of the largest group of healthy legs that agree within `tolerance`. At least
+//! `need` legs (2 for 2oo3) must be healthy, and for Majority agree, or the channel fails and
+//! scores MIN_SCORE like any failed source. With an even count the lower median is taken. A
+//! failed leg, or a healthy one outside `tolerance` of the vote, leaves the channel working but
+//! raises the disagreement alarm: VoteAlarm::record adds `<channel>_sensors_agree`, so a
+//! degraded architecture is seen before a second leg goes.
+#![forbid(unsafe_code)]
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::join_all;
+
+use super::harmony::{Conditions, Severity};
+use super::source::{Score, ScoreSource, SourceError, TimestampedScore};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Voting {
+    Median,
+    Majority,
+}
+
+#[derive(Debug, Default)]
+struct VoteState {
+    // Legs that failed or disagreed on the last vote, with why.
+    disagreeing: Vec<(usize, String)>,
+}
+
+pub struct VotedSource {
+    name: String,
+    endpoint: String,
+    legs: Vec<Box<dyn ScoreSource>>,
+    need: usize,
+    voting: Voting,
+    tolerance: f64,
+    leg_timeout: Duration,
+    alarm: VoteAlarm,
+}
+
+impl VotedSource {
+    // `need` out of `legs.len()`, e.g. 2 and three legs for 2oo3.
+    pub fn new(name: &str, need: usize, legs: Vec<Box<dyn ScoreSource>>) -> Result<Self, String> {
+        if need == 0 || need > legs.len() {
+            return Err(format!("{}: cannot vote {}oo{}", name, need, legs.len()));
+        }
+        let endpoint = format!("vote({}oo{}: {})", need, legs.len(), legs.iter().map(|l| l.endpoint()).collect::<Vec<_>>().join(", "));
+        let alarm = VoteAlarm {
+            condition: format!("{}_sensors_agree", name),
+            legs: legs.iter().map(|l| l.name().to_string()).collect(),
+            severity: Severity::Major,
+            state: Arc::new(Mutex::new(VoteState::default())),
+        };
+        Ok(VotedSource { name: name.to_string(), endpoint, legs, need, voting: Voting::Majority, tolerance: 0.01, leg_timeout: Duration::from_millis(15), alarm })
+    }
+
+    pub fn voting(mut self, voting: Voting) -> Self {
+        self.voting = voting;
+        self
+    }
+
+    // Largest difference, in score units, between legs that still count as agreeing.
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    // Legs are sampled together under this one deadline, so it must fit the SourceSet timeout.
+    pub fn leg_timeout(mut self, timeout: Duration) -> Self {
+        self.leg_timeout = timeout;
+        self
+    }
+
+    pub fn alarm_severity(mut self, severity: Severity) -> Self {
+        self.alarm.severity = severity;
+        self
+    }
+
+    // Take this before registering the source; it reads every later vote.
+    pub fn alarm(&self) -> VoteAlarm {
+        self.alarm.clone()
+    }
+
+    async fn sample_leg(&self, leg: &dyn ScoreSource, deadline: tokio::time::Instant) -> Result<TimestampedScore, SourceError> {
+        let sampled = match leg.sample_now() {
+            Some(r) => r,
+            None => tokio::time::timeout_at(deadline, leg.sample_timestamped()).await.unwrap_or(Err(SourceError::Timeout(self.leg_timeout))),
+        };
+        match sampled {
+            Ok(s) if !(0.0..=1.0).contains(&s.value) => Err(SourceError::OutOfRange(s.value)),
+            other => other,
+        }
+    }
+}
+
+#[async_trait]
+impl ScoreSource for VotedSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    async fn sample(&self) -> Result<Score, SourceError> {
+        self.sample_timestamped().await.map(|s| s.value)
+    }
+
+    async fn sample_timestamped(&self) -> Result<TimestampedScore, SourceError> {
+        let mut healthy = Vec::with_capacity(self.legs.len());
+        let mut disagreeing = Vec::new();
+        let deadline = tokio::time::Instant::now() + self.leg_timeout;
+        let sampled = join_all(self.legs.iter().map(|leg| self.sample_leg(leg.as_ref(), deadline))).await;
+        for (i, sampled) in sampled.into_iter().enumerate() {
+            match sampled {
+                Ok(s) => healthy.push((i, s)),
+                Err(e) => disagreeing.push((i, e.to_string())),
+            }
+        }
+        healthy.sort_by(|a, b| a.1.value.total_cmp(&b.1.value));
+        let voters: &[(usize, TimestampedScore)] = match self.voting {
+            Voting::Median => &healthy,
+            Voting::Majority => majority(&healthy, self.tolerance),
+        };
+        let result = if voters.len() < self.need {
+            Err(SourceError::Unavailable(format!("{} of {} legs {}, {} needed", voters.len(), self.legs.len(), if self.voting == Voting::Majority { "agree" } else { "healthy" }, self.need)))
+        } else {
+            let value = voters[(voters.len() - 1) / 2].1.value;
+            for (i, s) in &healthy {
+                if (s.value - value).abs() > self.tolerance {
+                    disagreeing.push((*i, format!("reads {:.4} against vote {:.4}", s.value, value)));
+                }
+            }
+            // The vote is only as fresh as the oldest reading in it.
+            let at_ms = voters.iter().map(|(_, s)| s.at_ms).min().unwrap_or(0);
+            Ok(TimestampedScore { value, at_ms })
+        };
+        disagreeing.sort_by_key(|(i, _)| *i);
+        self.alarm.state.lock().unwrap().disagreeing = disagreeing;
+        result
+    }
+}
+
+// The largest run of sorted readings spanning at most `tolerance` (give or take rounding); the
+// lowest run on a tie.
+fn majority(sorted: &[(usize, TimestampedScore)], tolerance: f64) -> &[(usize, TimestampedScore)] {
+    let mut best = &sorted[..0];
+    for start in 0..sorted.len() {
+        let end = sorted[start..].iter().take_while(|(_, s)| s.value - sorted[start].1.value <= tolerance + 1e-12).count() + start;
+        if end - start > best.len() {
+            best = &sorted[start..end];
+        }
+    }
+    best
+}
+
+// Shared view of a VotedSource's last vote, for the host's conditions.
+#[derive(Clone, Debug)]
+pub struct VoteAlarm {
+    condition: String,
+    legs: Vec<String>,
+    severity: Severity,
+    state: Arc<Mutex<VoteState>>,
+}
+
+impl VoteAlarm {
+    pub fn disagreeing(&self) -> usize {
+        self.state.lock().unwrap().disagreeing.len()
+    }
+
+    // Records `<channel>_sensors_agree` from the last vote, naming each failed or outlying leg.
+    pub fn record(&self, conditions: &mut Conditions) {
+        let state = self.state.lock().unwrap();
+        let detail = (!state.disagreeing.is_empty()).then(|| {
+            let mut d = String::new();
+            for (i, why) in &state.disagreeing {
+                let _ = write!(d, "{}{} {}", if d.is_empty() { "" } else { ", " }, self.legs[*i], why);
+            }
+            d
+        });
+        conditions.record_with(&self.condition, self.severity, detail.is_none(), detail);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::source::{FnSource, SyncSource};
+
+    fn leg(name: &str, value: f64) -> Box<dyn ScoreSource> {
+        Box::new(SyncSource::new(name, move || value))
+    }
+
+    fn slow_leg(name: &str, delay: Duration) -> Box<dyn ScoreSource> {
+        Box::new(FnSource::new(name, move || async move {
+            tokio::time::sleep(delay).await;
+            0.99
+        }))
+    }
+
+    fn two_of_three(legs: Vec<Box<dyn ScoreSource>>) -> VotedSource {
+        VotedSource::new("h2s", 2, legs).unwrap()
+    }
+
+    #[test]
+    fn rejects_impossible_architectures() {
+        assert!(VotedSource::new("h2s", 0, vec![leg("a", 1.0)]).is_err());
+        assert!(VotedSource::new("h2s", 4, vec![leg("a", 1.0), leg("b", 1.0), leg("c", 1.0)]).is_err());
+    }
+
+    #[tokio::test]
+    async fn one_outlier_raises_the_alarm_but_keeps_the_channel() {
+        let source = two_of_three(vec![leg("head_a", 0.99), leg("head_b", 0.995), leg("head_c", 0.40)]);
+        let alarm = source.alarm();
+        assert_eq!(source.sample().await, Ok(0.99));
+        assert_eq!(alarm.disagreeing(), 1);
+        let mut conditions = Conditions::new();
+        alarm.record(&mut conditions);
+        let status = &conditions.statuses[0];
+        assert_eq!((status.name.as_str(), status.ok, status.severity), ("h2s_sensors_agree", false, Severity::Major));
+        assert_eq!(status.detail.as_deref(), Some("head_c reads 0.4000 against vote 0.9900"));
+    }
+
+    #[tokio::test]
+    async fn fails_without_enough_agreeing_legs() {
+        let source = two_of_three(vec![leg("head_a", 0.99), leg("head_b", 0.5), leg("head_c", f64::NAN)]);
+        assert!(matches!(source.sample().await, Err(SourceError::Unavailable(e)) if e == "1 of 3 legs agree, 2 needed"));
+        assert_eq!(source.alarm().disagreeing(), 1);
+
+        // Median counts healthy legs, agreeing or not; an out-of-range leg is not healthy.
+        let source = two_of_three(vec![leg("head_a", 0.99), leg("head_b", 0.5), leg("head_c", 1.5)]).voting(Voting::Median);
+        assert_eq!(source.sample().await, Ok(0.5));
+        let source = two_of_three(vec![leg("head_a", 0.99), leg("head_b", -0.1), leg("head_c", 1.5)]).voting(Voting::Median);
+        assert!(source.sample().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn legs_share_one_deadline() {
+        // Sampled in turn, the third leg would start after the deadline.
+        let delay = Duration::from_millis(25);
+        let source = two_of_three(vec![slow_leg("a", delay), slow_leg("b", delay), slow_leg("c", delay)]).leg_timeout(Duration::from_millis(60));
+        assert_eq!(source.sample().await, Ok(0.99));
+        assert_eq!(source.alarm().disagreeing(), 0);
+
+        let source = two_of_three(vec![leg("a", 0.99), leg("b", 0.99), slow_leg("c", Duration::from_secs(5))]).leg_timeout(Duration::from_millis(20));
+        let started = std::time::Instant::now();
+        assert_eq!(source.sample().await, Ok(0.99));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(source.alarm().disagreeing(), 1);
+    }
+}
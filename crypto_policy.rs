@@ -0,0 +1,34 @@
+//! Crypto_Policy.rs - Crypto backend selection; the `fips` feature pins everything to validated modules (forbid unsafe)
+//!
+//! With `fips`, TLS uses rustls' FIPS provider (aws-lc-rs built in FIPS mode) and every config is
+//! checked with `fips()` before use; audit segments may only be sealed by signers that report a
+//! validated module. The module identity is written into every seal.
+#![forbid(unsafe_code)]
+use std::sync::Arc;
+
+use rustls::crypto::CryptoProvider;
+
+#[cfg(feature = "fips")]
+pub const CRYPTO_MODULE: &str = "aws-lc-rs FIPS (rustls fips provider)";
+#[cfg(not(feature = "fips"))]
+pub const CRYPTO_MODULE: &str = "aws-lc-rs (non-FIPS)";
+
+pub const FIPS_BUILD: bool = cfg!(feature = "fips");
+
+#[cfg(feature = "fips")]
+pub fn tls_provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::default_fips_provider())
+}
+
+#[cfg(not(feature = "fips"))]
+pub fn tls_provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::aws_lc_rs::default_provider())
+}
+
+// In FIPS builds a config that rustls does not report as FIPS is refused outright.
+pub fn require_fips(what: &str, config_is_fips: bool) -> Result<(), String> {
+    if FIPS_BUILD && !config_is_fips {
+        return Err(format!("{} is not FIPS-compliant in a fips build", what));
+    }
+    Ok(())
+}
@@ -0,0 +1,316 @@
+//! Decision.rs - Decision record exchanged between monitor nodes (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::fmt::{self, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::clock_sync::ClockSyncStatus;
+pub use crate::decision_kernel::Decision;
+
+#[derive(Clone, Debug)]
+pub struct DecisionRecord {
+    pub node_id: String,
+    pub seq: u64,
+    pub mu: f64,
+    pub ch: bool,
+    pub decision: Decision,
+    pub timestamp_ms: u64,
+    // Wall-clock ms after which a GO or CAUTION must be treated as HALT; None for HALT. A GO or
+    // CAUTION without one is never acted on (effective_at).
+    pub valid_until_ms: Option<u64>,
+    pub clock: ClockSyncStatus,
+    // Per channel rate of change, score units per second (core::trend::Trends); NaN while a
+    // channel lacks history. Empty when the domain does not track trends.
+    pub slopes: Vec<f64>,
+    // Per channel deviation from its own recent history, in sigmas (core::anomaly::Anomalies);
+    // NaN while a channel lacks history. Empty when the domain does not track anomalies.
+    pub anomaly: Vec<f64>,
+    // Seconds until mu, at its current trend, falls below the HALT line (core::trend::MuTrend);
+    // None while mu is not heading there within the horizon.
+    pub eta_to_halt: Option<f64>,
+    // Posterior probability the system is healthy (core::fusion::Fusion); None when the domain
+    // does not fuse evidence.
+    pub p_healthy: Option<f64>,
+    // Engine process that issued the record, the crypto module that signed it
+    // (crypto_policy::CRYPTO_MODULE) and its Ed25519 signature, hex (decision_stream::RecordSigner);
+    // None when the stream is unsigned.
+    pub instance_id: Option<String>,
+    pub crypto_module: Option<String>,
+    pub sig: Option<String>,
+}
+
+impl DecisionRecord {
+    pub fn new(node_id: &str, seq: u64, mu: f64, ch: bool, decision: Decision, clock: ClockSyncStatus) -> Self {
+        DecisionRecord {
+            node_id: node_id.to_string(),
+            seq,
+            mu,
+            ch,
+            decision,
+            timestamp_ms: now_ms(),
+            valid_until_ms: None,
+            clock,
+            slopes: Vec::new(),
+            anomaly: Vec::new(),
+            eta_to_halt: None,
+            p_healthy: None,
+            instance_id: None,
+            crypto_module: None,
+            sig: None,
+        }
+    }
+
+    // new() in place, for a loop that keeps one record across cycles: slopes and anomaly keep
+    // their buffers for the *_into writers, the per-cycle options are reset. instance_id,
+    // crypto_module and sig are left for RecordSigner::sign, which rewrites them in place.
+    pub fn refresh(&mut self, seq: u64, mu: f64, ch: bool, decision: Decision, clock: ClockSyncStatus) {
+        self.seq = seq;
+        self.mu = mu;
+        self.ch = ch;
+        self.decision = decision;
+        self.timestamp_ms = now_ms();
+        self.valid_until_ms = None;
+        self.clock = clock;
+        self.eta_to_halt = None;
+        self.p_healthy = None;
+    }
+
+    // Bounds how long a GO or CAUTION may be acted on, from timestamp_ms; HALT needs no lease.
+    // Set after timestamp_ms is final.
+    pub fn lease(mut self, valid_for: Duration) -> Self {
+        self.set_lease(valid_for);
+        self
+    }
+
+    pub fn set_lease(&mut self, valid_for: Duration) {
+        self.valid_until_ms = (self.decision != Decision::HALT).then(|| self.timestamp_ms.saturating_add(valid_for.as_millis() as u64));
+    }
+
+    // The decision a consumer may act on at `now_ms`: HALT once the lease has lapsed, and for a
+    // GO or CAUTION that carries none.
+    pub fn effective_at(&self, now_ms: u64) -> Decision {
+        lease_decision(self.decision, self.valid_until_ms, now_ms)
+    }
+
+    // Canonical single-line JSON; identical input always yields identical bytes.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+
+    // to_json() appended to a caller-owned buffer, so a per-cycle writer can reuse it.
+    pub fn write_json(&self, out: &mut String) {
+        let _ = write!(
+            out,
+            "{{\"node_id\":\"{}\",\"seq\":{},\"mu\":{},\"ch\":{},\"decision\":\"{:?}\",\"timestamp_ms\":{},\"clock\":{{\"offset_ns\":{},\"jitter_ns\":{},\"stratum\":{},\"ptp_state\":\"{:?}\",\"synced\":{}}}",
+            JsonStr(&self.node_id),
+            self.seq,
+            JsonNum(self.mu),
+            self.ch,
+            self.decision,
+            self.timestamp_ms,
+            self.clock.offset_ns,
+            self.clock.jitter_ns,
+            self.clock.stratum,
+            self.clock.ptp_state,
+            self.clock.synced,
+        );
+        if let Some(until) = self.valid_until_ms {
+            let _ = write!(out, ",\"valid_until_ms\":{}", until);
+        }
+        write_array(out, "slopes", &self.slopes);
+        write_array(out, "anomaly", &self.anomaly);
+        if let Some(eta) = self.eta_to_halt {
+            let _ = write!(out, ",\"eta_to_halt\":{}", JsonNum(eta));
+        }
+        if let Some(p) = self.p_healthy {
+            let _ = write!(out, ",\"p_healthy\":{}", JsonNum(p));
+        }
+        if let Some(id) = &self.instance_id {
+            let _ = write!(out, ",\"instance_id\":\"{}\"", JsonStr(id));
+        }
+        if let Some(module) = &self.crypto_module {
+            let _ = write!(out, ",\"crypto_module\":\"{}\"", JsonStr(module));
+        }
+        // Last, so the signed bytes are the record up to here.
+        if let Some(sig) = &self.sig {
+            let _ = write!(out, ",\"sig\":\"{}\"", JsonStr(sig));
+        }
+        out.push('}');
+    }
+}
+
+// Lease for a decision issued every `period`: one period plus half again, at least 50 ms, for
+// jitter and transport, so a healthy engine's next record always lands before the last lapses.
+pub fn lease_for(period: Duration) -> Duration {
+    period + (period / 2).max(Duration::from_millis(50))
+}
+
+fn lease_decision(decision: Decision, valid_until_ms: Option<u64>, now_ms: u64) -> Decision {
+    match valid_until_ms {
+        _ if decision == Decision::HALT => Decision::HALT,
+        Some(until) if now_ms <= until => decision,
+        _ => Decision::HALT,
+    }
+}
+
+// Consumer side: the latest decision and its lease. Reads HALT until the first grant and
+// whenever the lease has lapsed, so an actuator that stops hearing from the engine stops.
+// Leases are wall-clock, so producer and consumer clocks must be synced (record.clock).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lease {
+    decision: Decision,
+    valid_until_ms: Option<u64>,
+}
+
+impl Lease {
+    pub fn new() -> Self {
+        Lease { decision: Decision::HALT, valid_until_ms: None }
+    }
+
+    // From a record the consumer has accepted (decision_stream::RecordVerifier for signed streams).
+    pub fn renew(&mut self, record: &DecisionRecord) {
+        self.grant(record.decision, record.valid_until_ms);
+    }
+
+    pub fn grant(&mut self, decision: Decision, valid_until_ms: Option<u64>) {
+        self.decision = decision;
+        self.valid_until_ms = valid_until_ms;
+    }
+
+    pub fn decision_at(&self, now_ms: u64) -> Decision {
+        lease_decision(self.decision, self.valid_until_ms, now_ms)
+    }
+
+    pub fn current(&self) -> Decision {
+        self.decision_at(now_ms())
+    }
+
+    // Time left on a GO or CAUTION; None once it reads HALT.
+    pub fn remaining_at(&self, now_ms: u64) -> Option<Duration> {
+        (self.decision_at(now_ms) != Decision::HALT).then(|| Duration::from_millis(self.valid_until_ms.unwrap_or(now_ms) - now_ms))
+    }
+}
+
+impl Default for Lease {
+    fn default() -> Self {
+        Lease::new()
+    }
+}
+
+// `,"key":[...]`, omitted when empty.
+fn write_array(out: &mut String, key: &str, values: &[f64]) {
+    if values.is_empty() {
+        return;
+    }
+    let _ = write!(out, ",\"{}\":[", key);
+    for (i, v) in values.iter().enumerate() {
+        let _ = write!(out, "{}{}", if i > 0 { "," } else { "" }, JsonNum(*v));
+    }
+    out.push(']');
+}
+
+// Formats as an escaped JSON string body without building an intermediate String.
+pub struct JsonStr<'a>(pub &'a str);
+
+impl fmt::Display for JsonStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// A JSON number; non-finite values become null.
+pub struct JsonNum(pub f64);
+
+impl fmt::Display for JsonNum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_finite() { write!(f, "{}", self.0) } else { f.write_str("null") }
+    }
+}
+
+pub fn json_escape(s: &str) -> String {
+    JsonStr(s).to_string()
+}
+
+pub fn json_number(v: f64) -> String {
+    JsonNum(v).to_string()
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+
+    fn record(decision: Decision) -> DecisionRecord {
+        DecisionRecord::new("scada-1", 1, 0.9999, true, decision, ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS).status())
+    }
+
+    #[test]
+    fn go_without_a_lease_is_never_acted_on() {
+        let r = record(Decision::GO);
+        assert_eq!(r.effective_at(r.timestamp_ms), Decision::HALT);
+        let r = r.lease(Duration::from_millis(300));
+        assert_eq!(r.valid_until_ms, Some(r.timestamp_ms + 300));
+        assert_eq!(r.effective_at(r.timestamp_ms + 300), Decision::GO);
+        assert_eq!(r.effective_at(r.timestamp_ms + 301), Decision::HALT);
+    }
+
+    #[test]
+    fn halt_carries_no_lease() {
+        let r = record(Decision::HALT).lease(Duration::from_secs(60));
+        assert_eq!(r.valid_until_ms, None);
+        assert!(!r.to_json().contains("valid_until_ms"));
+    }
+
+    #[test]
+    fn refresh_drops_the_previous_lease() {
+        let mut r = record(Decision::GO).lease(Duration::from_secs(1));
+        r.p_healthy = Some(0.9);
+        r.refresh(2, 0.5, true, Decision::CAUTION, r.clock);
+        assert_eq!((r.seq, r.valid_until_ms, r.p_healthy), (2, None, None));
+        assert_eq!(r.effective_at(r.timestamp_ms), Decision::HALT);
+    }
+
+    #[test]
+    fn consumer_lease_reads_halt_until_granted_and_after_lapse() {
+        let mut lease = Lease::new();
+        assert_eq!(lease.decision_at(0), Decision::HALT);
+        lease.grant(Decision::CAUTION, Some(1_000));
+        assert_eq!(lease.decision_at(1_000), Decision::CAUTION);
+        assert_eq!(lease.remaining_at(400), Some(Duration::from_millis(600)));
+        assert_eq!(lease.decision_at(1_001), Decision::HALT);
+        assert_eq!(lease.remaining_at(1_001), None);
+        lease.grant(Decision::GO, None);
+        assert_eq!(lease.decision_at(0), Decision::HALT);
+    }
+
+    #[test]
+    fn lease_covers_the_next_period() {
+        assert_eq!(lease_for(Duration::from_millis(20)), Duration::from_millis(70));
+        assert_eq!(lease_for(Duration::from_secs(1)), Duration::from_millis(1_500));
+    }
+
+    #[test]
+    fn json_escapes_and_nulls_non_finite() {
+        let mut r = record(Decision::HALT);
+        r.node_id = "a\"b\\c\n".into();
+        r.mu = f64::NAN;
+        let json = r.to_json();
+        assert!(json.starts_with("{\"node_id\":\"a\\\"b\\\\c\\u000a\",\"seq\":1,\"mu\":null,"), "{}", json);
+    }
+}
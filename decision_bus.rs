@@ -0,0 +1,179 @@
+//! Decision_Bus.rs - Ordered fan-out of decision records to MQTT/gRPC/SSE at once (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::collections::VecDeque;
+
+use crate::decision::DecisionRecord;
+use crate::decision_stream::RecordSigner;
+
+const MAX_BACKLOG: usize = 1024;
+
+pub trait DecisionTransport {
+    fn name(&self) -> &str;
+    fn send(&mut self, seq: u64, payload: &str) -> Result<(), String>;
+}
+
+struct Lane {
+    transport: Box<dyn DecisionTransport + Send>,
+    backlog: VecDeque<(u64, String)>,
+    dropped: u64,
+}
+
+pub struct DecisionBus {
+    next_seq: u64,
+    lanes: Vec<Lane>,
+    signer: Option<RecordSigner>,
+}
+
+impl DecisionBus {
+    pub fn new() -> Self {
+        DecisionBus { next_seq: 1, lanes: Vec::new(), signer: None }
+    }
+
+    pub fn attach(&mut self, transport: Box<dyn DecisionTransport + Send>) {
+        self.lanes.push(Lane { transport, backlog: VecDeque::new(), dropped: 0 });
+    }
+
+    // Every record published from here on carries the signer's instance id and signature, so
+    // consumers can refuse replays with decision_stream::RecordVerifier.
+    pub fn sign_with(&mut self, signer: RecordSigner) {
+        self.signer = Some(signer);
+    }
+
+    // Stamps the bus sequence number (and signs, with sign_with), serializes once, and hands the same bytes to
+    // every transport. A failing transport keeps its own ordered backlog; it never
+    // reorders or skips ahead, and never delays the healthy ones.
+    pub fn publish(&mut self, mut record: DecisionRecord) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        record.seq = seq;
+        if let Some(signer) = &mut self.signer {
+            signer.sign(&mut record);
+        }
+        let payload = record.to_json();
+        for lane in &mut self.lanes {
+            if lane.backlog.len() >= MAX_BACKLOG {
+                lane.backlog.pop_front();
+                lane.dropped += 1;
+            }
+            lane.backlog.push_back((seq, payload.clone()));
+            flush(lane);
+        }
+        seq
+    }
+
+    // Retry transports that still have a backlog (call once per cycle).
+    pub fn flush_all(&mut self) {
+        for lane in &mut self.lanes {
+            flush(lane);
+        }
+    }
+
+    // (transport, backlog depth, dropped) for health reporting.
+    pub fn lag(&self) -> Vec<(String, usize, u64)> {
+        self.lanes
+            .iter()
+            .map(|l| (l.transport.name().to_string(), l.backlog.len(), l.dropped))
+            .collect()
+    }
+}
+
+impl Default for DecisionBus {
+    fn default() -> Self {
+        DecisionBus::new()
+    }
+}
+
+fn flush(lane: &mut Lane) {
+    while let Some((seq, payload)) = lane.backlog.front() {
+        if let Err(e) = lane.transport.send(*seq, payload) {
+            eprintln!("Bus: {} send failed at seq {}: {}", lane.transport.name(), seq, e);
+            return;
+        }
+        lane.backlog.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use crate::clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+    use crate::decision::Decision;
+
+    type Sent = Arc<Mutex<Vec<(u64, String)>>>;
+
+    // Records what it was sent; fails while `down` is set.
+    struct Probe {
+        name: &'static str,
+        sent: Sent,
+        down: Arc<Mutex<bool>>,
+    }
+
+    impl DecisionTransport for Probe {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn send(&mut self, seq: u64, payload: &str) -> Result<(), String> {
+            if *self.down.lock().unwrap() {
+                return Err("unreachable".into());
+            }
+            self.sent.lock().unwrap().push((seq, payload.to_string()));
+            Ok(())
+        }
+    }
+
+    fn probe(bus: &mut DecisionBus, name: &'static str) -> (Sent, Arc<Mutex<bool>>) {
+        let (sent, down) = (Arc::new(Mutex::new(Vec::new())), Arc::new(Mutex::new(false)));
+        bus.attach(Box::new(Probe { name, sent: sent.clone(), down: down.clone() }));
+        (sent, down)
+    }
+
+    fn record(decision: Decision) -> DecisionRecord {
+        DecisionRecord::new("grid", 0, 0.9999, true, decision, ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS).status())
+    }
+
+    #[test]
+    fn every_transport_gets_the_same_records_in_order() {
+        let mut bus = DecisionBus::default();
+        let (mqtt, _) = probe(&mut bus, "mqtt");
+        let (grpc, _) = probe(&mut bus, "grpc");
+        assert_eq!(bus.publish(record(Decision::GO)), 1);
+        assert_eq!(bus.publish(record(Decision::HALT)), 2);
+        let mqtt = mqtt.lock().unwrap();
+        assert_eq!(*mqtt, *grpc.lock().unwrap());
+        assert_eq!(mqtt.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(), [1, 2]);
+        assert!(mqtt[1].1.contains("\"seq\":2"));
+    }
+
+    #[test]
+    fn a_failing_transport_catches_up_in_order_without_delaying_the_others() {
+        let mut bus = DecisionBus::default();
+        let (mqtt, _) = probe(&mut bus, "mqtt");
+        let (sse, sse_down) = probe(&mut bus, "sse");
+        *sse_down.lock().unwrap() = true;
+        for _ in 0..3 {
+            bus.publish(record(Decision::GO));
+        }
+        assert_eq!(mqtt.lock().unwrap().len(), 3);
+        assert_eq!(bus.lag(), [("mqtt".to_string(), 0, 0), ("sse".to_string(), 3, 0)]);
+        *sse_down.lock().unwrap() = false;
+        bus.flush_all();
+        assert_eq!(sse.lock().unwrap().iter().map(|(seq, _)| *seq).collect::<Vec<_>>(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn an_overflowing_backlog_drops_its_oldest_records() {
+        let mut bus = DecisionBus::default();
+        let (sent, down) = probe(&mut bus, "grpc");
+        *down.lock().unwrap() = true;
+        for _ in 0..MAX_BACKLOG + 2 {
+            bus.publish(record(Decision::CAUTION));
+        }
+        assert_eq!(bus.lag(), [("grpc".to_string(), MAX_BACKLOG, 2)]);
+        *down.lock().unwrap() = false;
+        bus.flush_all();
+        assert_eq!(sent.lock().unwrap().first().map(|(seq, _)| *seq), Some(3));
+    }
+}
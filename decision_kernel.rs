@@ -0,0 +1,414 @@
+//! Decision_Kernel.rs - Pure decision kernel with Kani verification harnesses (forbid unsafe)
+//! no_std + alloc clean: only `core` and `alloc` are used, with libm for ln/exp when `std` is off.
+#![forbid(unsafe_code)]
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+pub const MIN_SCORE: f64 = 1e-12;
+// Default GO threshold; each domain may configure its own within its bounds.
+pub const HARMONY_THRESHOLD: f64 = 0.9995;
+
+// Upper-case to match the verdict strings on the wire and in every domain's reports.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision { GO, CAUTION, HALT }
+
+impl Decision {
+    pub fn severity(self) -> u8 {
+        match self {
+            Decision::GO => 0,
+            Decision::CAUTION => 1,
+            Decision::HALT => 2,
+        }
+    }
+
+    pub fn most_severe(self, other: Decision) -> Decision {
+        if other.severity() > self.severity() { other } else { self }
+    }
+}
+
+#[cfg(feature = "std")]
+fn ln(x: f64) -> f64 { x.ln() }
+#[cfg(not(feature = "std"))]
+fn ln(x: f64) -> f64 { libm::log(x) }
+
+#[cfg(feature = "std")]
+fn exp(x: f64) -> f64 { x.exp() }
+#[cfg(not(feature = "std"))]
+fn exp(x: f64) -> f64 { libm::exp(x) }
+
+// NaN and +/-Inf map to MIN_SCORE: an unreadable channel must never look healthy.
+pub fn clamp_score(s: f64) -> f64 {
+    if s.is_finite() { s.clamp(MIN_SCORE, 1.0) } else { MIN_SCORE }
+}
+
+// Why a raw score is not a reading at all, as opposed to merely outside [0, 1].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidScore { NaN, Infinite, Negative }
+
+impl InvalidScore {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InvalidScore::NaN => "nan",
+            InvalidScore::Infinite => "infinite",
+            InvalidScore::Negative => "negative",
+        }
+    }
+}
+
+pub fn classify_score(s: f64) -> Option<InvalidScore> {
+    if s.is_nan() {
+        Some(InvalidScore::NaN)
+    } else if s.is_infinite() {
+        Some(InvalidScore::Infinite)
+    } else if s < 0.0 {
+        Some(InvalidScore::Negative)
+    } else {
+        None
+    }
+}
+
+// mu held as ln(mu). Channel terms are summed with Neumaier-compensated addition, so the sum
+// over 50+ channels carries no more error than a single term, and thresholds are compared in
+// log space, so the rounding in exp() can never move a decision across one. Ordering is that
+// of mu; mu() converts back for reports.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct LogMu(f64);
+
+impl LogMu {
+    pub fn from_scores(weights: &[f64], scores: &[f64]) -> LogMu {
+        let (mut sum, mut carry) = (0.0_f64, 0.0_f64);
+        for (w, s) in weights.iter().zip(scores.iter()) {
+            let term = w * ln(clamp_score(*s));
+            let t = sum + term;
+            carry += if sum.abs() >= term.abs() { (sum - t) + term } else { (term - t) + sum };
+            sum = t;
+        }
+        LogMu(sum + carry)
+    }
+
+    // For aggregators computed in linear space.
+    pub fn from_mu(mu: f64) -> LogMu {
+        LogMu(ln(clamp_score(mu)))
+    }
+
+    pub fn ln(self) -> f64 {
+        self.0
+    }
+
+    pub fn mu(self) -> f64 {
+        exp(self.0)
+    }
+
+    // mu >= threshold, decided on ln(mu) >= ln(threshold). A NaN threshold is never cleared.
+    pub fn clears(self, threshold: f64) -> bool {
+        threshold > 0.0 && self.0 >= ln(threshold)
+    }
+}
+
+pub fn weighted_log_sum(weights: &[f64], scores: &[f64]) -> f64 {
+    LogMu::from_scores(weights, scores).ln()
+}
+
+// Hard floors are per-channel minimums that force HALT regardless of mu.
+pub fn floors_hold(scores: &[f64], floors: &[f64]) -> bool {
+    scores.len() >= floors.len() && scores.iter().zip(floors.iter()).all(|(s, f)| !s.is_nan() && s >= f)
+}
+
+pub fn weighted_mu(weights: &[f64], scores: &[f64]) -> f64 {
+    LogMu::from_scores(weights, scores).mu()
+}
+
+pub fn decide(mu: f64, ch: bool, floors_ok: bool, threshold: f64) -> Decision {
+    if mu >= threshold && ch && floors_ok { Decision::GO } else { Decision::HALT }
+}
+
+// Three-tier decision: below go_threshold but at or above caution_threshold is CAUTION
+// (early warning), never GO. ch and floors still force HALT outright.
+pub fn decide_tiered(mu: f64, ch: bool, floors_ok: bool, go_threshold: f64, caution_threshold: f64) -> Decision {
+    match decide(mu, ch, floors_ok, go_threshold) {
+        Decision::HALT if ch && floors_ok && mu >= caution_threshold => Decision::CAUTION,
+        d => d,
+    }
+}
+
+// decide_tiered() compared in log space; use with LogMu::from_scores so large channel counts
+// decide on the exact sum rather than its rounded exponential.
+pub fn decide_tiered_log(log_mu: LogMu, ch: bool, floors_ok: bool, go_threshold: f64, caution_threshold: f64) -> Decision {
+    if !(ch && floors_ok) {
+        Decision::HALT
+    } else if log_mu.clears(go_threshold) {
+        Decision::GO
+    } else if log_mu.clears(caution_threshold) {
+        Decision::CAUTION
+    } else {
+        Decision::HALT
+    }
+}
+
+// Smallest score for `channel` (others held) that brings mu to `threshold`, or None if even
+// 1.0 is not enough. Solves w_c * ln(s_c) = ln(threshold) - sum_{i != c} w_i * ln(s_i).
+pub fn required_score(weights: &[f64], scores: &[f64], channel: usize, threshold: f64) -> Option<f64> {
+    let w_c = *weights.get(channel)?;
+    if channel >= scores.len() || w_c <= 0.0 {
+        return None;
+    }
+    let mut others = scores.to_vec();
+    others[channel] = 1.0;
+    let needed = exp((ln(threshold) - weighted_log_sum(weights, &others)) / w_c);
+    if needed <= 1.0 { Some(needed.max(0.0)) } else { None }
+}
+
+// How channel scores combine into mu. All are in [MIN_SCORE, 1], equal 1 when every channel is
+// 1, and never rise when a channel falls. Zero-weight channels never count.
+//   GeometricMean: prod s_i^w_i (the default; one bad channel is damped by its weight).
+//   HarmonicMean: 1 / sum(w_i / s_i); leans harder toward the worst channels.
+//   Minimum: worst weighted channel; weights only select channels, one bad channel dominates.
+//   Cvar: weighted mean of the worst `tail` share of weight (tail in (0, 1]; 1 is the mean).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Aggregator {
+    #[default]
+    GeometricMean,
+    HarmonicMean,
+    Minimum,
+    Cvar { tail: f64 },
+}
+
+impl Aggregator {
+    pub fn aggregate(&self, weights: &[f64], scores: &[f64]) -> f64 {
+        let channels = weights.iter().zip(scores.iter()).filter(|(w, _)| **w > 0.0).map(|(w, s)| (*w, clamp_score(*s)));
+        match *self {
+            Aggregator::GeometricMean => weighted_mu(weights, scores),
+            Aggregator::HarmonicMean => {
+                let (w_sum, inv_sum) = channels.fold((0.0, 0.0), |(ws, is), (w, s)| (ws + w, is + w / s));
+                if inv_sum > 0.0 { clamp_score(w_sum / inv_sum) } else { MIN_SCORE }
+            }
+            Aggregator::Minimum => channels.map(|(_, s)| s).fold(f64::NAN, f64::min).clamp(MIN_SCORE, 1.0),
+            Aggregator::Cvar { tail } => {
+                let mut sorted: Vec<(f64, f64)> = channels.collect();
+                sorted.sort_by(|a, b| a.1.total_cmp(&b.1));
+                let budget = tail.clamp(f64::EPSILON, 1.0) * sorted.iter().map(|(w, _)| w).sum::<f64>();
+                let (mut taken, mut acc) = (0.0, 0.0);
+                for (w, s) in sorted {
+                    let take = w.min(budget - taken);
+                    if take <= 0.0 {
+                        break;
+                    }
+                    taken += take;
+                    acc += take * s;
+                }
+                if taken > 0.0 { clamp_score(acc / taken) } else { MIN_SCORE }
+            }
+        }
+    }
+
+    // required_score() for any aggregator: closed form for the geometric mean, bisection
+    // otherwise (every aggregator is non-decreasing in each channel).
+    pub fn required_score(&self, weights: &[f64], scores: &[f64], channel: usize, threshold: f64) -> Option<f64> {
+        if *self == Aggregator::GeometricMean {
+            return required_score(weights, scores, channel, threshold);
+        }
+        if channel >= scores.len() || *weights.get(channel)? <= 0.0 {
+            return None;
+        }
+        let mut what_if = scores.to_vec();
+        let mut at = |s: f64| {
+            what_if[channel] = s;
+            self.aggregate(weights, &what_if)
+        };
+        if at(1.0) < threshold {
+            return None;
+        }
+        let (mut lo, mut hi) = (0.0, 1.0);
+        for _ in 0..64 {
+            let mid = (lo + hi) / 2.0;
+            if at(mid) >= threshold { hi = mid } else { lo = mid }
+        }
+        Some(hi)
+    }
+
+    // d mu / d score_i for every channel into `out` (cleared first; reuse it across cycles).
+    // Where the minimum and CVaR have kinks this is the derivative as the score falls, the
+    // direction that threatens the threshold. Zero-weight channels get 0.
+    pub fn sensitivity_into(&self, weights: &[f64], scores: &[f64], out: &mut Vec<f64>) {
+        out.clear();
+        out.resize(scores.len(), 0.0);
+        let mu = self.aggregate(weights, scores);
+        let live = |i: usize| weights.get(i).is_some_and(|w| *w > 0.0);
+        match *self {
+            Aggregator::GeometricMean => {
+                for (i, d) in out.iter_mut().enumerate().filter(|(i, _)| live(*i)) {
+                    *d = weights[i] * mu / clamp_score(scores[i]);
+                }
+            }
+            Aggregator::HarmonicMean => {
+                let w_sum: f64 = (0..scores.len()).filter(|&i| live(i)).map(|i| weights[i]).sum();
+                for (i, d) in out.iter_mut().enumerate().filter(|(i, _)| live(*i)) {
+                    let s = clamp_score(scores[i]);
+                    *d = mu * mu * weights[i] / (w_sum * s * s);
+                }
+            }
+            Aggregator::Minimum => {
+                for (i, d) in out.iter_mut().enumerate().filter(|(i, _)| live(*i)) {
+                    *d = if clamp_score(scores[i]) <= mu { 1.0 } else { 0.0 };
+                }
+            }
+            Aggregator::Cvar { tail } => {
+                // Each tail channel moves mu by its share of the tail weight; the rest not at all.
+                let mut sorted: Vec<usize> = (0..scores.len()).filter(|&i| live(i)).collect();
+                sorted.sort_by(|&a, &b| clamp_score(scores[a]).total_cmp(&clamp_score(scores[b])));
+                let budget = tail.clamp(f64::EPSILON, 1.0) * sorted.iter().map(|&i| weights[i]).sum::<f64>();
+                let mut taken = 0.0;
+                for &i in &sorted {
+                    let take = weights[i].min(budget - taken);
+                    if take <= 0.0 {
+                        break;
+                    }
+                    taken += take;
+                    out[i] = take;
+                }
+                if taken > 0.0 {
+                    out.iter_mut().for_each(|d| *d /= taken);
+                }
+            }
+        }
+    }
+}
+
+// Evaluates many cycles at once (back-tests, RTU catch-up after a link outage).
+pub fn decide_batch(weights: &[f64], cycles: &[(Vec<f64>, bool)], threshold: f64) -> Vec<Decision> {
+    cycles
+        .iter()
+        .map(|(scores, ch)| decide(weighted_mu(weights, scores), *ch, true, threshold))
+        .collect()
+}
+
+// GO requires mu >= go_above; once GO, stays GO until mu < halt_below (halt_below <= go_above).
+// ch and floors bypass hysteresis entirely.
+pub struct Hysteresis {
+    pub go_above: f64,
+    pub halt_below: f64,
+    state: Decision,
+}
+
+impl Hysteresis {
+    pub fn new(go_above: f64, halt_below: f64) -> Self {
+        Hysteresis { go_above, halt_below: halt_below.min(go_above), state: Decision::HALT }
+    }
+
+    pub fn step(&mut self, mu: f64, ch: bool, floors_ok: bool) -> Decision {
+        let threshold = if self.state == Decision::GO { self.halt_below } else { self.go_above };
+        self.state = decide(mu, ch, floors_ok, threshold);
+        self.state
+    }
+}
+
+#[cfg(kani)]
+mod verification {
+    use super::*;
+
+    #[kani::proof]
+    fn clamp_is_total_and_in_range() {
+        let s: f64 = kani::any();
+        let c = clamp_score(s);
+        assert!(c >= MIN_SCORE && c <= 1.0);
+    }
+
+    #[kani::proof]
+    fn invalid_scores_clamp_to_min() {
+        let s: f64 = kani::any();
+        if classify_score(s).is_some() {
+            assert!(clamp_score(s) == MIN_SCORE);
+        }
+    }
+
+    #[kani::proof]
+    fn never_go_when_ch_fails_or_floor_violated() {
+        let mu: f64 = kani::any();
+        let threshold: f64 = kani::any();
+        let ch: bool = kani::any();
+        let floors_ok: bool = kani::any();
+        if decide(mu, ch, floors_ok, threshold) == Decision::GO {
+            assert!(ch && floors_ok && mu >= threshold);
+        }
+    }
+
+    #[kani::proof]
+    fn tiered_never_relaxes_hard_conditions() {
+        let mu: f64 = kani::any();
+        let go: f64 = kani::any();
+        let caution: f64 = kani::any();
+        let ch: bool = kani::any();
+        let floors_ok: bool = kani::any();
+        let d = decide_tiered(mu, ch, floors_ok, go, caution);
+        if d != Decision::HALT {
+            assert!(ch && floors_ok);
+        }
+        if d == Decision::GO {
+            assert!(mu >= go);
+        }
+    }
+
+    #[kani::proof]
+    fn log_tiered_never_relaxes_hard_conditions() {
+        let log_mu = LogMu(kani::any());
+        let go: f64 = kani::any();
+        let caution: f64 = kani::any();
+        let ch: bool = kani::any();
+        let floors_ok: bool = kani::any();
+        let d = decide_tiered_log(log_mu, ch, floors_ok, go, caution);
+        if d != Decision::HALT {
+            assert!(ch && floors_ok);
+        }
+        if log_mu.ln().is_nan() {
+            assert!(d == Decision::HALT);
+        }
+    }
+
+    #[kani::proof]
+    fn nan_mu_never_goes() {
+        let threshold: f64 = kani::any();
+        assert!(decide(f64::NAN, true, true, threshold) == Decision::HALT);
+    }
+
+    #[kani::proof]
+    #[kani::unwind(4)]
+    fn floor_violation_forces_halt() {
+        let scores: [f64; 3] = kani::any();
+        let floors: [f64; 3] = kani::any();
+        let i: usize = kani::any();
+        kani::assume(i < 3);
+        kani::assume(scores[i] < floors[i]);
+        assert!(!floors_hold(&scores, &floors));
+    }
+
+    #[kani::proof]
+    #[kani::unwind(4)]
+    fn minimum_never_exceeds_a_weighted_channel() {
+        let weights: [f64; 3] = kani::any();
+        let scores: [f64; 3] = kani::any();
+        let i: usize = kani::any();
+        kani::assume(i < 3 && weights[i] > 0.0);
+        let mu = Aggregator::Minimum.aggregate(&weights, &scores);
+        assert!(mu <= clamp_score(scores[i]));
+        assert!(mu >= MIN_SCORE && mu <= 1.0);
+    }
+
+    #[kani::proof]
+    #[kani::unwind(3)]
+    fn hysteresis_respects_hard_conditions() {
+        let go_above: f64 = kani::any();
+        let halt_below: f64 = kani::any();
+        let mut h = Hysteresis::new(go_above, halt_below);
+        for _ in 0..2 {
+            let mu: f64 = kani::any();
+            let ch: bool = kani::any();
+            let floors_ok: bool = kani::any();
+            if h.step(mu, ch, floors_ok) == Decision::GO {
+                assert!(ch && floors_ok && mu >= h.halt_below);
+            }
+        }
+    }
+}
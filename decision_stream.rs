@@ -0,0 +1,283 @@
+//! Decision_Stream.rs - Signed, replay-proof decision records for actuators (forbid unsafe)
+//!
+//! An actuator that acts on any well-formed GO can be fed an old one: a cached message replayed
+//! after the engine went to HALT, or a record from before a restart. RecordSigner stamps every
+//! record with the engine instance (a fresh id at each process start) and signs it with the
+//! node's Ed25519 key, so node_id, seq, timestamp_ms and the decision are all under the
+//! signature. RecordVerifier, on the consumer side, accepts a record only if
+//!   - it is signed by the key trusted for its node_id,
+//!   - its timestamp is within `max_age` of local time, either way,
+//!   - within one instance its seq is above the last accepted, and
+//!   - a new instance is newer than the last record accepted from the node, so a superseded
+//!     instance's stream cannot be replayed after a restart.
+//!
+//! The signature is over the record's canonical JSON without the `sig` field, which is always
+//! written last, so verify_line() checks exactly the bytes on the wire. Ed25519 hashes the
+//! message itself, so signing and verifying run entirely inside aws-lc-rs, the module
+//! crypto_policy selects (FIPS-validated under the `fips` feature), and each record names that
+//! module under its signature.
+#![forbid(unsafe_code)]
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use aws_lc_rs::digest::{digest, SHA256};
+use aws_lc_rs::signature::{Ed25519KeyPair, UnparsedPublicKey, ED25519};
+use ed25519_dalek::VerifyingKey;
+
+use crate::crypto_policy::CRYPTO_MODULE;
+use crate::decision::{now_ms, Decision, DecisionRecord};
+use crate::sealed_config::{hex, unhex};
+
+const SIG_FIELD: &str = ",\"sig\":\"";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamViolation {
+    Malformed(String),
+    Unsigned,
+    UnknownNode(String),
+    BadSignature,
+    TimestampSkew { skew_ms: i64 },
+    SeqNotIncreasing { last: u64, got: u64 },
+    InstanceSuperseded { instance_id: String },
+}
+
+pub struct RecordSigner {
+    instance_id: String,
+    key: Ed25519KeyPair,
+    // The record's unsigned JSON, rewritten on every sign().
+    body: String,
+}
+
+impl RecordSigner {
+    // The instance id is derived from the node, process id and start time; it only has to
+    // differ between starts, not be secret.
+    pub fn new(node_id: &str, key: Ed25519KeyPair) -> Self {
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+        let seed = format!("{}\n{}\n{}", node_id, std::process::id(), started);
+        RecordSigner { instance_id: hex(&digest(&SHA256, seed.as_bytes()).as_ref()[..8]), key, body: String::new() }
+    }
+
+    // HARMONY_DECISION_KEY: the node's 32-byte Ed25519 seed, hex.
+    pub fn from_env(node_id: &str) -> Result<Self, String> {
+        let seed = std::env::var("HARMONY_DECISION_KEY").map_err(|_| "HARMONY_DECISION_KEY not set".to_string())?;
+        RecordSigner::from_hex(node_id, &seed).map_err(|e| format!("HARMONY_DECISION_KEY {}", e))
+    }
+
+    // A 32-byte Ed25519 seed, hex, as provisioned in a key file or the environment.
+    pub fn from_hex(node_id: &str, seed: &str) -> Result<Self, String> {
+        let seed: [u8; 32] = unhex(seed.trim()).and_then(|b| b.try_into().ok()).ok_or_else(|| "is not a 32-byte hex seed".to_string())?;
+        let key = Ed25519KeyPair::from_seed_unchecked(&seed).map_err(|_| "is not a usable Ed25519 seed".to_string())?;
+        Ok(RecordSigner::new(node_id, key))
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    // Stamps the instance and crypto module, then signs; sign after every other field is final.
+    // A record kept across cycles is re-signed into its own string buffers, without allocating.
+    pub fn sign(&mut self, record: &mut DecisionRecord) {
+        overwrite(&mut record.instance_id, &self.instance_id);
+        overwrite(&mut record.crypto_module, CRYPTO_MODULE);
+        let mut sig = record.sig.take().unwrap_or_default();
+        self.body.clear();
+        record.write_json(&mut self.body);
+        sig.clear();
+        for b in self.key.sign(self.body.as_bytes()).as_ref() {
+            let _ = write!(sig, "{:02x}", b);
+        }
+        record.sig = Some(sig);
+    }
+}
+
+fn overwrite(field: &mut Option<String>, value: &str) {
+    let s = field.get_or_insert_with(String::new);
+    s.clear();
+    s.push_str(value);
+}
+
+// What a verified record says; enough for an actuator to act on, through decision::Lease::grant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Verified {
+    pub node_id: String,
+    pub instance_id: String,
+    pub seq: u64,
+    pub timestamp_ms: u64,
+    pub decision: Decision,
+    pub valid_until_ms: Option<u64>,
+}
+
+struct Position {
+    instance_id: String,
+    seq: u64,
+    timestamp_ms: u64,
+}
+
+pub struct RecordVerifier {
+    // Keyed by node_id.
+    trusted: Vec<(String, VerifyingKey)>,
+    max_age: Duration,
+    nodes: HashMap<String, Position>,
+    rejected: u64,
+}
+
+impl RecordVerifier {
+    // `trusted` as from sealed_config::parse_trusted_keys, one key per node_id. `max_age` bounds
+    // how old a record may be when it arrives: a few decision periods.
+    pub fn new(trusted: Vec<(String, VerifyingKey)>, max_age: Duration) -> Self {
+        RecordVerifier { trusted, max_age, nodes: HashMap::new(), rejected: 0 }
+    }
+
+    // For consumers handed the record itself.
+    pub fn verify(&mut self, record: &DecisionRecord) -> Result<Verified, StreamViolation> {
+        self.verify_line(&record.to_json())
+    }
+
+    // One record as received, canonical JSON.
+    pub fn verify_line(&mut self, line: &str) -> Result<Verified, StreamViolation> {
+        let result = self.verify_at(line, now_ms());
+        if result.is_err() {
+            self.rejected += 1;
+        }
+        result
+    }
+
+    fn verify_at(&mut self, line: &str, now: u64) -> Result<Verified, StreamViolation> {
+        let line = line.trim_end();
+        let at = line.rfind(SIG_FIELD).ok_or(StreamViolation::Unsigned)?;
+        let sig = line[at + SIG_FIELD.len()..].strip_suffix("\"}").ok_or_else(|| StreamViolation::Malformed("sig is not the last field".into()))?;
+        let body = format!("{}}}", &line[..at]);
+        let node_id = string_field(&body, "{\"node_id\":\"").ok_or_else(|| StreamViolation::Malformed("no node_id".into()))?;
+        let key = self.trusted.iter().find(|(id, _)| id == node_id).map(|(_, k)| k).ok_or_else(|| StreamViolation::UnknownNode(node_id.to_string()))?;
+        let sig = unhex(sig).filter(|b| b.len() == 64).ok_or(StreamViolation::BadSignature)?;
+        UnparsedPublicKey::new(&ED25519, key.as_bytes()).verify(body.as_bytes(), &sig).map_err(|_| StreamViolation::BadSignature)?;
+        // Signed by the node from here on, so the fields are the engine's own canonical output.
+        let malformed = |field: &str| StreamViolation::Malformed(format!("no {}", field));
+        let instance_id = string_field(&body, ",\"instance_id\":\"").ok_or_else(|| malformed("instance_id"))?;
+        let seq: u64 = number_field(&body, ",\"seq\":").and_then(|v| v.parse().ok()).ok_or_else(|| malformed("seq"))?;
+        let timestamp_ms: u64 = number_field(&body, ",\"timestamp_ms\":").and_then(|v| v.parse().ok()).ok_or_else(|| malformed("timestamp_ms"))?;
+        let decision = match string_field(&body, ",\"decision\":\"") {
+            Some("GO") => Decision::GO,
+            Some("CAUTION") => Decision::CAUTION,
+            Some("HALT") => Decision::HALT,
+            _ => return Err(malformed("decision")),
+        };
+        let valid_until_ms = match number_field(&body, ",\"valid_until_ms\":") {
+            Some(v) => Some(v.parse().map_err(|_| malformed("valid_until_ms"))?),
+            None => None,
+        };
+        let skew_ms = timestamp_ms as i64 - now as i64;
+        if skew_ms.unsigned_abs() > self.max_age.as_millis() as u64 {
+            return Err(StreamViolation::TimestampSkew { skew_ms });
+        }
+        match self.nodes.get(node_id) {
+            Some(last) if last.instance_id == instance_id && seq <= last.seq => {
+                return Err(StreamViolation::SeqNotIncreasing { last: last.seq, got: seq });
+            }
+            Some(last) if last.instance_id != instance_id && timestamp_ms <= last.timestamp_ms => {
+                return Err(StreamViolation::InstanceSuperseded { instance_id: instance_id.to_string() });
+            }
+            _ => {}
+        }
+        self.nodes.insert(node_id.to_string(), Position { instance_id: instance_id.to_string(), seq, timestamp_ms });
+        Ok(Verified { node_id: node_id.to_string(), instance_id: instance_id.to_string(), seq, timestamp_ms, decision, valid_until_ms })
+    }
+
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+}
+
+// The string after `prefix` up to its closing quote; escaped values are refused, since node
+// ids and instance ids never need escaping.
+fn string_field<'a>(body: &'a str, prefix: &str) -> Option<&'a str> {
+    let rest = &body[body.find(prefix)? + prefix.len()..];
+    let value = &rest[..rest.find('"')?];
+    (!value.contains('\\')).then_some(value)
+}
+
+fn number_field<'a>(body: &'a str, prefix: &str) -> Option<&'a str> {
+    let rest = &body[body.find(prefix)? + prefix.len()..];
+    Some(&rest[..rest.find([',', '}'])?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+    use ed25519_dalek::SigningKey;
+
+    const SEED: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+    const NOW: u64 = 1_750_000_000_000;
+
+    fn verifier() -> RecordVerifier {
+        let key = SigningKey::from_bytes(&[1; 32]).verifying_key();
+        RecordVerifier::new(vec![("scada-1".to_string(), key)], Duration::from_secs(2))
+    }
+
+    fn signed(signer: &mut RecordSigner, seq: u64, timestamp_ms: u64, decision: Decision) -> String {
+        let mut record = DecisionRecord::new("scada-1", seq, 0.9999, true, decision, ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS).status());
+        record.timestamp_ms = timestamp_ms;
+        record.set_lease(Duration::from_millis(300));
+        signer.sign(&mut record);
+        record.to_json()
+    }
+
+    #[test]
+    fn accepts_a_signed_record_as_written() {
+        let mut signer = RecordSigner::from_hex("scada-1", SEED).unwrap();
+        let v = verifier().verify_at(&signed(&mut signer, 7, NOW, Decision::GO), NOW).unwrap();
+        assert_eq!((v.node_id.as_str(), v.instance_id.as_str(), v.seq), ("scada-1", signer.instance_id(), 7));
+        assert_eq!((v.decision, v.valid_until_ms), (Decision::GO, Some(NOW + 300)));
+    }
+
+    #[test]
+    fn rejects_tampered_unsigned_and_foreign_records() {
+        let mut signer = RecordSigner::from_hex("scada-1", SEED).unwrap();
+        let line = signed(&mut signer, 1, NOW, Decision::HALT);
+        let mut v = verifier();
+        assert_eq!(v.verify_at(&line.replace("\"HALT\"", "\"GO\""), NOW), Err(StreamViolation::BadSignature));
+        assert_eq!(v.verify_at(&line[..line.rfind(SIG_FIELD).unwrap()], NOW), Err(StreamViolation::Unsigned));
+        assert!(matches!(v.verify_at(&line.replace("\"}", "\",\"x\":1}"), NOW), Err(StreamViolation::Malformed(_))));
+
+        let mut other = RecordSigner::from_hex("scada-1", &"02".repeat(32)).unwrap();
+        assert_eq!(v.verify_at(&signed(&mut other, 1, NOW, Decision::GO), NOW), Err(StreamViolation::BadSignature));
+        let mut stranger = RecordSigner::from_hex("scada-2", SEED).unwrap();
+        let mut record = DecisionRecord::new("scada-2", 1, 1.0, true, Decision::GO, ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS).status());
+        stranger.sign(&mut record);
+        assert_eq!(v.verify_at(&record.to_json(), NOW), Err(StreamViolation::UnknownNode("scada-2".into())));
+    }
+
+    #[test]
+    fn rejects_replayed_and_stale_records() {
+        let mut signer = RecordSigner::from_hex("scada-1", SEED).unwrap();
+        let mut v = verifier();
+        let first = signed(&mut signer, 5, NOW, Decision::GO);
+        v.verify_at(&first, NOW).unwrap();
+        assert_eq!(v.verify_at(&first, NOW + 10), Err(StreamViolation::SeqNotIncreasing { last: 5, got: 5 }));
+        assert_eq!(v.verify_at(&signed(&mut signer, 4, NOW + 10, Decision::GO), NOW + 10), Err(StreamViolation::SeqNotIncreasing { last: 5, got: 4 }));
+        assert_eq!(v.verify_at(&signed(&mut signer, 6, NOW, Decision::GO), NOW + 2_001), Err(StreamViolation::TimestampSkew { skew_ms: -2_001 }));
+        assert_eq!(v.verify_at(&signed(&mut signer, 7, NOW + 3_000, Decision::GO), NOW), Err(StreamViolation::TimestampSkew { skew_ms: 3_000 }));
+        assert!(v.verify_at(&signed(&mut signer, 8, NOW + 20, Decision::GO), NOW + 20).is_ok());
+    }
+
+    #[test]
+    fn a_restart_supersedes_the_previous_instance() {
+        let mut before = RecordSigner::from_hex("scada-1", SEED).unwrap();
+        let captured = signed(&mut before, 900, NOW, Decision::GO);
+        let mut after = RecordSigner::from_hex("scada-1", SEED).unwrap();
+        assert_ne!(before.instance_id(), after.instance_id());
+        let mut v = verifier();
+        v.verify_at(&signed(&mut after, 1, NOW + 100, Decision::HALT), NOW + 100).unwrap();
+        let err = v.verify_at(&captured, NOW + 150).unwrap_err();
+        assert_eq!(err, StreamViolation::InstanceSuperseded { instance_id: before.instance_id().to_string() });
+    }
+
+    #[test]
+    fn signer_rejects_bad_seeds() {
+        for seed in ["", "zz", &"01".repeat(31), &"01".repeat(33)] {
+            assert!(RecordSigner::from_hex("scada-1", seed).is_err(), "{:?}", seed);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Diagnostics.rs - Read-only, token-authenticated remote diagnostics for field nodes (forbid unsafe)
+//!
+//! GET /diagnostics with `Authorization: Bearer <token>` returns one JSON document: recent
+//! cycles, per-provider stats, process/runtime health and the config hash. Nothing can be
+//! changed through this listener and there is no shell behind it. Only the SHA-256 of the
+//! token is kept on the device. GET /metrics returns the cycle/provider budget ledger and the
+//! ingestion queue counters, the availability budget and any other attached metrics (report
+//! sinks, ...), in Prometheus text format behind the same token.
+//! GET /incidents lists the attached incident catalog; GET /incidents/<id> returns one timeline.
+#![forbid(unsafe_code)]
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+use crate::catalog::IncidentCatalog;
+use crate::core::availability::AvailabilityBudget;
+use crate::core::budget::BudgetLedger;
+use crate::core::harmony::Evaluation;
+use crate::decision::{json_escape, json_number, now_ms};
+use crate::ingest::IngestQueue;
+
+const RECENT_CYCLES: usize = 120;
+const MAX_HEADER_LINES: usize = 64;
+
+struct Cycle {
+    at_ms: u64,
+    mu: f64,
+    ch: bool,
+    decision: String,
+    took: Duration,
+}
+
+#[derive(Default)]
+struct ProviderStats {
+    samples: u64,
+    errors: u64,
+    last_latency: Duration,
+    last_error: Option<String>,
+}
+
+pub struct Diagnostics {
+    started: Instant,
+    config_hash: String,
+    cycles: VecDeque<Cycle>,
+    providers: BTreeMap<String, ProviderStats>,
+    budget: Option<Arc<Mutex<BudgetLedger>>>,
+    ingest: Option<Arc<Mutex<IngestQueue>>>,
+    catalog: Option<Arc<Mutex<IncidentCatalog>>>,
+    availability: Option<Arc<Mutex<AvailabilityBudget>>>,
+    extra_metrics: Vec<Box<dyn Fn() -> String + Send>>,
+}
+
+impl Diagnostics {
+    pub fn new(config_hash: &str) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Diagnostics {
+            started: Instant::now(),
+            config_hash: config_hash.to_string(),
+            cycles: VecDeque::with_capacity(RECENT_CYCLES),
+            providers: BTreeMap::new(),
+            budget: None,
+            ingest: None,
+            catalog: None,
+            availability: None,
+            extra_metrics: Vec::new(),
+        }))
+    }
+
+    pub fn attach_budget(&mut self, ledger: Arc<Mutex<BudgetLedger>>) {
+        self.budget = Some(ledger);
+    }
+
+    pub fn attach_ingest(&mut self, queue: Arc<Mutex<IngestQueue>>) {
+        self.ingest = Some(queue);
+    }
+
+    pub fn attach_catalog(&mut self, catalog: Arc<Mutex<IncidentCatalog>>) {
+        self.catalog = Some(catalog);
+    }
+
+    pub fn attach_availability(&mut self, availability: Arc<Mutex<AvailabilityBudget>>) {
+        self.availability = Some(availability);
+    }
+
+    // Any other Prometheus text source, e.g. report_sink::ReportMetrics::metrics_text.
+    pub fn attach_metrics(&mut self, metrics: Box<dyn Fn() -> String + Send>) {
+        self.extra_metrics.push(metrics);
+    }
+
+    // `/incidents` or `/incidents/<id>`; None for an unknown id or when no catalog is attached.
+    fn incidents_json(&self, path: &str) -> Option<String> {
+        let catalog = self.catalog.as_ref()?.lock().unwrap();
+        match path.strip_prefix("/incidents").unwrap_or("") {
+            "" => {
+                let items: Vec<String> = catalog
+                    .incidents()
+                    .map(|(id, opened, closed)| {
+                        format!("{{\"incident\":{},\"opened_ms\":{},\"closed_ms\":{}}}", id, opened, closed.map_or("null".to_string(), |c| c.to_string()))
+                    })
+                    .collect();
+                Some(format!("[{}]", items.join(",")))
+            }
+            id => catalog.timeline(id.strip_prefix('/')?.parse().ok()?).map(|t| t.to_json()),
+        }
+    }
+
+    fn metrics_text(&self) -> Option<String> {
+        let budget = self.budget.as_ref().map(|b| b.lock().unwrap().metrics_text());
+        let ingest = self.ingest.as_ref().map(|q| q.lock().unwrap().metrics_text());
+        let availability = self.availability.as_ref().map(|a| a.lock().unwrap().metrics_text());
+        let extra: String = self.extra_metrics.iter().map(|m| m()).collect();
+        match (budget, ingest, availability) {
+            (None, None, None) if self.extra_metrics.is_empty() => None,
+            (b, i, a) => Some(b.unwrap_or_default() + &i.unwrap_or_default() + &a.unwrap_or_default() + &extra),
+        }
+    }
+
+    pub fn record_cycle(&mut self, eval: &Evaluation, took: Duration) {
+        if self.cycles.len() == RECENT_CYCLES {
+            self.cycles.pop_front();
+        }
+        self.cycles.push_back(Cycle { at_ms: now_ms(), mu: eval.mu, ch: eval.ch, decision: format!("{:?}", eval.decision), took });
+    }
+
+    pub fn record_provider(&mut self, name: &str, latency: Duration, error: Option<String>) {
+        let p = self.providers.entry(name.to_string()).or_default();
+        p.samples += 1;
+        p.last_latency = latency;
+        if error.is_some() {
+            p.errors += 1;
+            p.last_error = error;
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+        let field = |k: &str| status.lines().find_map(|l| l.strip_prefix(k)).map(|v| v.trim().to_string()).unwrap_or_default();
+        let cycles: Vec<String> = self
+            .cycles
+            .iter()
+            .map(|c| {
+                format!(
+                    "{{\"at_ms\":{},\"mu\":{},\"ch\":{},\"decision\":\"{}\",\"took_us\":{}}}",
+                    c.at_ms,
+                    json_number(c.mu),
+                    c.ch,
+                    c.decision,
+                    c.took.as_micros()
+                )
+            })
+            .collect();
+        let providers: Vec<String> = self
+            .providers
+            .iter()
+            .map(|(name, p)| {
+                format!(
+                    "\"{}\":{{\"samples\":{},\"errors\":{},\"last_latency_us\":{},\"last_error\":{}}}",
+                    json_escape(name),
+                    p.samples,
+                    p.errors,
+                    p.last_latency.as_micros(),
+                    p.last_error.as_ref().map_or("null".to_string(), |e| format!("\"{}\"", json_escape(e)))
+                )
+            })
+            .collect();
+        format!(
+            "{{\"config_hash\":\"{}\",\"uptime_s\":{},\"runtime\":{{\"threads\":\"{}\",\"vm_rss\":\"{}\",\"vm_hwm\":\"{}\"}},\"providers\":{{{}}},\"recent_cycles\":[{}]}}",
+            json_escape(&self.config_hash),
+            self.started.elapsed().as_secs(),
+            json_escape(&field("Threads:")),
+            json_escape(&field("VmRSS:")),
+            json_escape(&field("VmHWM:")),
+            providers.join(","),
+            cycles.join(",")
+        )
+    }
+}
+
+// Length-independent comparison of two digests.
+fn digest_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub fn serve(addr: &str, diag: Arc<Mutex<Diagnostics>>, token_sha256: [u8; 32]) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+            let mut reader = BufReader::new(&stream);
+            let mut request = String::new();
+            if reader.read_line(&mut request).is_err() {
+                continue;
+            }
+            let mut token = None;
+            for _ in 0..MAX_HEADER_LINES {
+                let mut line = String::new();
+                if reader.read_line(&mut line).map_or(true, |n| n == 0) || line.trim().is_empty() {
+                    break;
+                }
+                if let Some((k, v)) = line.split_once(':') {
+                    if k.trim().eq_ignore_ascii_case("authorization") {
+                        token = v.trim().strip_prefix("Bearer ").map(|t| t.trim().to_string());
+                    }
+                }
+            }
+            let mut parts = request.split_whitespace();
+            let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+            let authorized = token.is_some_and(|t| digest_eq(&Sha256::digest(t.as_bytes()), &token_sha256));
+            const JSON: &str = "application/json";
+            const METRICS: &str = "text/plain; version=0.0.4";
+            let (status, content_type, body) = match (method, path, authorized) {
+                (_, _, false) => ("401 Unauthorized", JSON, String::new()),
+                ("GET", "/diagnostics", true) => ("200 OK", JSON, diag.lock().unwrap().to_json()),
+                ("GET", "/metrics", true) => match diag.lock().unwrap().metrics_text() {
+                    Some(text) => ("200 OK", METRICS, text),
+                    None => ("404 Not Found", JSON, String::new()),
+                },
+                ("GET", p, true) if p.starts_with("/incidents") => match diag.lock().unwrap().incidents_json(p) {
+                    Some(json) => ("200 OK", JSON, json),
+                    None => ("404 Not Found", JSON, String::new()),
+                },
+                ("GET", _, true) => ("404 Not Found", JSON, String::new()),
+                (_, _, true) => ("405 Method Not Allowed", JSON, String::new()),
+            };
+            let _ = write!(
+                &stream,
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                content_type,
+                body.len(),
+                body
+            );
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! Domain_Dependencies.rs - One domain's decision as a condition input to another (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use crate::decision::{now_ms, Decision, DecisionRecord};
+
+const MAX_UPSTREAM_AGE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, PartialEq)]
+pub enum DependencyError {
+    SelfDependency(String),
+    Cycle(Vec<String>),
+}
+
+// e.g. ("ai_safety", "grid", Decision::HALT): AI deployment refuses GO while grid is in HALT.
+#[derive(Clone, Debug)]
+pub struct Dependency {
+    pub dependent: String,
+    pub upstream: String,
+    pub blocks_on: Decision,
+}
+
+pub struct DependencyGraph {
+    edges: Vec<Dependency>,
+    latest: BTreeMap<String, DecisionRecord>,
+}
+
+impl DependencyGraph {
+    // Config validation: rejects self-edges and any cycle before the graph is used.
+    pub fn new(edges: Vec<Dependency>) -> Result<Self, DependencyError> {
+        for e in &edges {
+            if e.dependent == e.upstream {
+                return Err(DependencyError::SelfDependency(e.dependent.clone()));
+            }
+        }
+        let graph = DependencyGraph { edges, latest: BTreeMap::new() };
+        if let Some(cycle) = graph.find_cycle() {
+            return Err(DependencyError::Cycle(cycle));
+        }
+        Ok(graph)
+    }
+
+    pub fn observe(&mut self, domain: &str, record: DecisionRecord) {
+        self.latest.insert(domain.to_string(), record);
+    }
+
+    // For the dependent domain's check_ch: false if any upstream is at or beyond its
+    // blocking decision, or has gone silent. An upstream GO or CAUTION whose lease has lapsed
+    // counts as HALT.
+    pub fn upstreams_clear(&self, dependent: &str) -> bool {
+        self.blocking_upstreams(dependent).is_empty()
+    }
+
+    pub fn blocking_upstreams(&self, dependent: &str) -> Vec<String> {
+        let now = now_ms();
+        let max_age = MAX_UPSTREAM_AGE.as_millis() as u64;
+        self.edges
+            .iter()
+            .filter(|e| e.dependent == dependent)
+            .filter(|e| match self.latest.get(&e.upstream) {
+                Some(r) if now.saturating_sub(r.timestamp_ms) <= max_age => {
+                    r.effective_at(now).severity() >= e.blocks_on.severity()
+                }
+                _ => true,
+            })
+            .map(|e| e.upstream.clone())
+            .collect()
+    }
+
+    fn find_cycle(&self) -> Option<Vec<String>> {
+        let nodes: BTreeSet<&str> = self.edges.iter().map(|e| e.dependent.as_str()).collect();
+        let mut done = BTreeSet::new();
+        for start in nodes {
+            let mut path = Vec::new();
+            if let Some(cycle) = self.dfs(start, &mut path, &mut done) {
+                return Some(cycle);
+            }
+        }
+        None
+    }
+
+    fn dfs<'a>(&'a self, node: &'a str, path: &mut Vec<&'a str>, done: &mut BTreeSet<&'a str>) -> Option<Vec<String>> {
+        if let Some(pos) = path.iter().position(|n| *n == node) {
+            let mut cycle: Vec<String> = path[pos..].iter().map(|n| n.to_string()).collect();
+            cycle.push(node.to_string());
+            return Some(cycle);
+        }
+        if done.contains(node) {
+            return None;
+        }
+        path.push(node);
+        for e in self.edges.iter().filter(|e| e.dependent == node) {
+            if let Some(cycle) = self.dfs(&e.upstream, path, done) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        done.insert(node);
+        None
+    }
+}
@@ -0,0 +1,104 @@
+//! Edge_Budget.rs - Binary-size and peak-RSS budget gate for edge gateway builds (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::process::{self, Command};
+use std::time::{Duration, Instant};
+use std::{env, fs, thread};
+
+struct Budget {
+    binary: String,
+    max_size_kib: u64,
+    max_rss_kib: u64,
+    run: Duration,
+}
+
+fn parse_args() -> Result<Budget, String> {
+    let mut b = Budget { binary: String::new(), max_size_kib: 2048, max_rss_kib: 8192, run: Duration::from_secs(60) };
+    let args: Vec<String> = env::args().skip(1).collect();
+    for pair in args.chunks(2) {
+        let value = pair.get(1).ok_or_else(|| format!("{} needs a value", pair[0]))?;
+        let num = |v: &str| v.parse::<u64>().map_err(|_| format!("bad value for {}: {}", pair[0], v));
+        match pair[0].as_str() {
+            "--binary" => b.binary = value.clone(),
+            "--max-size-kib" => b.max_size_kib = num(value)?,
+            "--max-rss-kib" => b.max_rss_kib = num(value)?,
+            "--run-seconds" => b.run = Duration::from_secs(num(value)?),
+            other => return Err(format!("unknown option {}", other)),
+        }
+    }
+    if b.binary.is_empty() {
+        return Err("--binary is required".into());
+    }
+    Ok(b)
+}
+
+// VmHWM is the kernel's own peak-RSS high-water mark, so short spikes between samples still count.
+fn peak_rss_kib(pid: u32) -> Option<u64> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix("VmHWM:"))
+        .and_then(|v| v.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+fn main() {
+    let budget = match parse_args() {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("edge_budget: {}", e);
+            eprintln!("usage: edge_budget --binary PATH [--max-size-kib N] [--max-rss-kib N] [--run-seconds S]");
+            process::exit(2);
+        }
+    };
+    let size_kib = match fs::metadata(&budget.binary) {
+        Ok(m) => m.len().div_ceil(1024),
+        Err(e) => {
+            eprintln!("edge_budget: {}: {}", budget.binary, e);
+            process::exit(2);
+        }
+    };
+    let mut child = match Command::new(&budget.binary).spawn() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("edge_budget: spawn {}: {}", budget.binary, e);
+            process::exit(2);
+        }
+    };
+    let start = Instant::now();
+    let mut peak = 0;
+    let mut exited_early = None;
+    while start.elapsed() < budget.run {
+        if let Some(kib) = peak_rss_kib(child.id()) {
+            peak = peak.max(kib);
+        }
+        if let Ok(Some(status)) = child.try_wait() {
+            exited_early = Some(status);
+            break;
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    if exited_early.is_none() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    println!(
+        "edge_budget: size={} KiB (max {}), peak_rss={} KiB (max {}), ran {:?}",
+        size_kib, budget.max_size_kib, peak, budget.max_rss_kib, start.elapsed()
+    );
+    let mut failed = false;
+    if let Some(status) = exited_early {
+        eprintln!("edge_budget: FAIL engine exited during the run ({})", status);
+        failed = true;
+    }
+    if size_kib > budget.max_size_kib {
+        eprintln!("edge_budget: FAIL binary {} KiB exceeds {} KiB", size_kib, budget.max_size_kib);
+        failed = true;
+    }
+    if peak > budget.max_rss_kib {
+        eprintln!("edge_budget: FAIL peak RSS {} KiB exceeds {} KiB", peak, budget.max_rss_kib);
+        failed = true;
+    }
+    if failed {
+        process::exit(1);
+    }
+    println!("edge_budget: PASS");
+}
@@ -0,0 +1,148 @@
+//! Explain.rs - Human-readable narrative for each decision (forbid unsafe)
+//!
+//! e.g. "HALT because guardrail_trigger_rate fell to 0.71 (weight 0.15) and red_team_report
+//! expired 3 days ago". Used in EvaluationReport.explanation and in alert lines.
+#![forbid(unsafe_code)]
+use std::fmt::Write;
+
+use crate::core::harmony::{Conditions, Decision, Severity, MIN_SCORE};
+use crate::report::{EvaluationReport, HaltCode, HaltReason};
+
+// How many dragging channels to name; the rest are summarised.
+const MAX_CHANNELS: usize = 2;
+
+pub struct Channel<'a> {
+    pub name: &'a str,
+    pub score: f64,
+    pub weight: f64,
+}
+
+// Up to four decimals, trailing zeros trimmed: 0.71, 0.9993, 1.
+fn num(x: f64) -> String {
+    let mut s = String::new();
+    push_num(&mut s, x);
+    s
+}
+
+// num() appended to `out`, trimmed in place.
+fn push_num(out: &mut String, x: f64) {
+    let start = out.len();
+    let _ = write!(out, "{:.4}", x);
+    if out[start..].contains('.') {
+        let kept = out.trim_end_matches('0').trim_end_matches('.').len();
+        out.truncate(kept);
+    }
+}
+
+fn join(parts: &[String]) -> String {
+    match parts {
+        [] => String::new(),
+        [one] => one.clone(),
+        [init @ .., last] => format!("{} and {}", init.join(", "), last),
+    }
+}
+
+// Channels ordered by how much they pull ln(mu) down (w * ln(s), most negative first).
+fn dragging(channels: &[Channel]) -> Vec<String> {
+    let mut drag: Vec<(&Channel, f64)> = channels
+        .iter()
+        .filter(|c| c.score < 1.0 && c.weight > 0.0)
+        .map(|c| (c, c.weight * c.score.max(MIN_SCORE).ln()))
+        .collect();
+    drag.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+    let mut parts: Vec<String> = drag
+        .iter()
+        .take(MAX_CHANNELS)
+        .map(|(c, _)| format!("{} fell to {} (weight {})", c.name, num(c.score), num(c.weight)))
+        .collect();
+    if drag.len() > MAX_CHANNELS {
+        let rest = drag.len() - MAX_CHANNELS;
+        parts.push(format!("{} smaller contributor{}", rest, if rest == 1 { "" } else { "s" }));
+    }
+    parts
+}
+
+fn condition_part(name: &str, detail: &Option<String>) -> String {
+    match detail {
+        Some(d) => format!("{} {}", name, d),
+        None => format!("{} failed", name),
+    }
+}
+
+fn reason_part(r: &HaltReason) -> Option<String> {
+    let subject = r.subject.as_deref().unwrap_or("a channel");
+    match r.code {
+        // Covered by the channel / condition breakdown.
+        HaltCode::MU_BELOW_THRESHOLD | HaltCode::CH_FAILED => None,
+        HaltCode::FLOOR_VIOLATED => Some(format!("{} is below its hard floor", subject)),
+        HaltCode::UPSTREAM_HALT => Some(format!("upstream {} is in HALT", r.subject.as_deref().unwrap_or("domain"))),
+        HaltCode::CLOCK_UNSYNCED => Some("the clock is not synchronised".to_string()),
+        HaltCode::MONITOR_DEGRADED => Some(format!("the monitor itself is degraded ({}), not necessarily the plant", r.subject.as_deref().unwrap_or("self-check"))),
+        HaltCode::HEALTH_POSTERIOR_LOW => Some(format!("the fused evidence no longer supports a healthy system ({})", r.subject.as_deref().unwrap_or("posterior"))),
+    }
+}
+
+pub fn narrate(decision: Decision, mu: f64, threshold: f64, channels: &[Channel], conditions: &Conditions, reasons: &[HaltReason]) -> String {
+    let failed = |sev: Severity| -> Vec<String> {
+        conditions.statuses.iter().filter(|c| !c.ok && c.severity == sev).map(|c| condition_part(&c.name, &c.detail)).collect()
+    };
+    let advisories = failed(Severity::Advisory);
+    let mut parts = Vec::new();
+    if decision != Decision::GO && mu < threshold {
+        let drag = dragging(channels);
+        if drag.is_empty() {
+            parts.push(format!("mu {} is below {}", num(mu), num(threshold)));
+        } else {
+            parts.extend(drag);
+        }
+    }
+    parts.extend(failed(Severity::Critical));
+    parts.extend(failed(Severity::Major));
+    parts.extend(reasons.iter().filter_map(reason_part));
+    let mut text = match decision {
+        Decision::GO => format!("GO: mu {} clears {} and all required conditions hold", num(mu), num(threshold)),
+        d if parts.is_empty() => format!("{:?} (mu {})", d, num(mu)),
+        d => format!("{:?} because {}", d, join(&parts)),
+    };
+    if !advisories.is_empty() {
+        text.push_str(&format!("; advisory: {}", join(&advisories)));
+    }
+    text
+}
+
+// Fills `report.explanation`, reusing its buffer; channels and conditions come from the same
+// cycle as the report. A GO is written without allocating, failed advisories included.
+pub fn explain_into(report: &mut EvaluationReport, channels: &[Channel], conditions: &Conditions) {
+    let mut text = report.explanation.take().unwrap_or_default();
+    text.clear();
+    let (decision, mu, threshold) = (report.record.decision, report.record.mu, report.threshold);
+    if decision != Decision::GO {
+        text.push_str(&narrate(decision, mu, threshold, channels, conditions, &report.reasons));
+        report.explanation = Some(text);
+        return;
+    }
+    text.push_str("GO: mu ");
+    push_num(&mut text, mu);
+    text.push_str(" clears ");
+    push_num(&mut text, threshold);
+    text.push_str(" and all required conditions hold");
+    // As narrate()'s join(): "a", "a and b", "a, b and c".
+    let advisory = || conditions.statuses.iter().filter(|c| !c.ok && c.severity == Severity::Advisory);
+    let count = advisory().count();
+    for (i, c) in advisory().enumerate() {
+        text.push_str(match i {
+            0 => "; advisory: ",
+            i if i + 1 == count => " and ",
+            _ => ", ",
+        });
+        text.push_str(&c.name);
+        match &c.detail {
+            Some(d) => {
+                text.push(' ');
+                text.push_str(d);
+            }
+            None => text.push_str(" failed"),
+        }
+    }
+    report.explanation = Some(text);
+}
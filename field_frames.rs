@@ -0,0 +1,468 @@
+//! Field_Frames.rs - Decoders for field protocol frames feeding the ingestion queue (forbid unsafe)
+//!
+//! Each decoder turns one frame as received from the field into named readings; ingest_frame()
+//! pushes them through IngestQueue::push_from, so bindings and unit conversion apply as for any
+//! other source. A frame is accepted whole or refused with the reason: a truncated, mis-sized or
+//! corrupt frame never yields part of its readings. Nothing here panics on any input; each
+//! protocol has its own fuzz target (fuzz/README.md).
+//!
+//! Source names, which HARMONY_SOURCE_UNITS-style bindings refer to:
+//!   Modbus TCP   modbus/<unit>/<register>       holding or input register, unsigned
+//!   DNP3         dnp3/<source address>/ai<index> analog input, online points only
+//!   NMEA 0183    nmea/<transducer>              XDR measurement
+//!   CCSDS        ccsds/<apid>/<parameter>       big-endian f32 parameters of a TM packet
+//!   ISO 20022    iso20022/<message>/<counter>   pacs.002 accepted/rejected, pacs.008 settlement/<ccy>
+//!   JSON         as named in the document       {"source": ..., "value": ...} or an array of them
+#![forbid(unsafe_code)]
+use crate::ingest::{Admission, IngestQueue};
+
+// NMEA 0183 caps a sentence at 82 characters including the line ending.
+const NMEA_MAX_LEN: usize = 82;
+const CCSDS_IDLE_APID: u16 = 0x7ff;
+const DNP3_BLOCK: usize = 16;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reading {
+    pub source: String,
+    pub value: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    // A read response does not carry its start address; the poller knows what it asked for.
+    Modbus { first_register: u16 },
+    Dnp3,
+    Nmea,
+    // The secondary header is mission-defined; only its length matters here.
+    Ccsds { secondary_header_len: usize },
+    Iso20022,
+    Json,
+}
+
+impl Protocol {
+    pub fn decode(self, frame: &[u8]) -> Result<Vec<Reading>, String> {
+        match self {
+            Protocol::Modbus { first_register } => decode_modbus(frame, first_register),
+            Protocol::Dnp3 => decode_dnp3(frame),
+            Protocol::Nmea => decode_nmea(&String::from_utf8_lossy(frame)),
+            Protocol::Ccsds { secondary_header_len } => decode_ccsds(frame, secondary_header_len),
+            Protocol::Iso20022 => decode_iso20022(&String::from_utf8_lossy(frame)),
+            Protocol::Json => decode_json(frame),
+        }
+    }
+}
+
+// Decodes `frame` and pushes every reading; the admissions are in reading order, Unknown for a
+// source no binding names.
+pub fn ingest_frame(queue: &mut IngestQueue, protocol: Protocol, frame: &[u8], at_ms: u64) -> Result<Vec<Admission>, String> {
+    let readings = protocol.decode(frame)?;
+    Ok(readings.iter().map(|r| queue.push_from(&r.source, r.value, at_ms)).collect())
+}
+
+fn reading(source: String, value: f64) -> Reading {
+    Reading { source, value }
+}
+
+// Modbus TCP read response (function 3 or 4): MBAP header, then byte count and registers.
+pub fn decode_modbus(frame: &[u8], first_register: u16) -> Result<Vec<Reading>, String> {
+    let [_, _, p0, p1, l0, l1, unit, function, pdu @ ..] = frame else {
+        return Err(format!("modbus: {} byte frame is shorter than its header", frame.len()));
+    };
+    if [*p0, *p1] != [0, 0] {
+        return Err("modbus: protocol identifier is not 0".into());
+    }
+    let length = u16::from_be_bytes([*l0, *l1]) as usize;
+    if length != frame.len() - 6 {
+        return Err(format!("modbus: length field {} for {} bytes", length, frame.len() - 6));
+    }
+    match *function {
+        3 | 4 => {}
+        f if f & 0x80 != 0 => return Err(format!("modbus: exception {} to function {}", pdu.first().copied().unwrap_or(0), f & 0x7f)),
+        f => return Err(format!("modbus: unsupported function {}", f)),
+    }
+    let [count, data @ ..] = pdu else {
+        return Err("modbus: missing byte count".into());
+    };
+    if *count as usize != data.len() || !data.len().is_multiple_of(2) {
+        return Err(format!("modbus: byte count {} for {} register bytes", count, data.len()));
+    }
+    data.chunks_exact(2)
+        .enumerate()
+        .map(|(i, r)| {
+            let register = u16::try_from(i).ok().and_then(|i| first_register.checked_add(i)).ok_or("modbus: register address past 65535")?;
+            Ok(reading(format!("modbus/{}/{}", unit, register), u16::from_be_bytes([r[0], r[1]]) as f64))
+        })
+        .collect()
+}
+
+// DNP3 CRC-16 (polynomial 0x3D65, reflected, inverted), sent little-endian.
+pub fn dnp3_crc(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for byte in data {
+        crc ^= *byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xa6bc } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn crc_ok(block: &[u8], crc: &[u8]) -> bool {
+    crc.len() == 2 && dnp3_crc(block).to_le_bytes() == [crc[0], crc[1]]
+}
+
+// One DNP3 link frame carrying a whole (single-fragment) response or unsolicited response with
+// group 30 analog inputs: variations 1 (32-bit), 2 (16-bit) and 5 (float), each with its flag
+// octet, under qualifier 0x00 or 0x01 (start-stop range). Any other object ends the decode with
+// an error, since its length is unknown.
+pub fn decode_dnp3(frame: &[u8]) -> Result<Vec<Reading>, String> {
+    let (header, rest) = frame.split_at_checked(10).ok_or("dnp3: frame shorter than its link header")?;
+    if header[..2] != [0x05, 0x64] {
+        return Err("dnp3: missing 0x0564 start octets".into());
+    }
+    if !crc_ok(&header[..8], &header[8..]) {
+        return Err("dnp3: link header CRC mismatch".into());
+    }
+    let user_len = (header[2] as usize).checked_sub(5).ok_or("dnp3: length field below 5")?;
+    let source = u16::from_le_bytes([header[6], header[7]]);
+    let blocks = user_len.div_ceil(DNP3_BLOCK);
+    if rest.len() != user_len + 2 * blocks {
+        return Err(format!("dnp3: {} bytes after the header for {} user octets", rest.len(), user_len));
+    }
+    let mut user = Vec::with_capacity(user_len);
+    for block in rest.chunks(DNP3_BLOCK + 2) {
+        let (data, crc) = block.split_at(block.len() - 2);
+        if !crc_ok(data, crc) {
+            return Err("dnp3: data block CRC mismatch".into());
+        }
+        user.extend_from_slice(data);
+    }
+    let [transport, _control, function, _iin1, _iin2, objects @ ..] = &user[..] else {
+        return Err("dnp3: no application header".into());
+    };
+    if transport & 0xc0 != 0xc0 {
+        return Err("dnp3: multi-fragment transport segments are not reassembled".into());
+    }
+    if !matches!(function, 0x81 | 0x82) {
+        return Err(format!("dnp3: function 0x{:02x} is not a response", function));
+    }
+    let mut readings = Vec::new();
+    let mut at = objects;
+    while !at.is_empty() {
+        let [group, variation, qualifier, tail @ ..] = at else {
+            return Err("dnp3: truncated object header".into());
+        };
+        let (start, stop, tail) = match (qualifier, tail) {
+            (0x00, [start, stop, tail @ ..]) => (*start as usize, *stop as usize, tail),
+            (0x01, [s0, s1, e0, e1, tail @ ..]) => (u16::from_le_bytes([*s0, *s1]) as usize, u16::from_le_bytes([*e0, *e1]) as usize, tail),
+            _ => return Err(format!("dnp3: unsupported or truncated qualifier 0x{:02x}", qualifier)),
+        };
+        let size = match (group, variation) {
+            (30, 1) | (30, 5) => 5,
+            (30, 2) => 3,
+            _ => return Err(format!("dnp3: unsupported object g{}v{}", group, variation)),
+        };
+        let count = stop.checked_sub(start).ok_or("dnp3: range stop before start")? + 1;
+        let (points, tail) = tail.split_at_checked(count * size).ok_or("dnp3: truncated object range")?;
+        for (i, point) in points.chunks_exact(size).enumerate() {
+            // Flag bit 0 is ONLINE; an offline point's value means nothing.
+            if point[0] & 0x01 == 0 {
+                continue;
+            }
+            let value = match variation {
+                1 => i32::from_le_bytes([point[1], point[2], point[3], point[4]]) as f64,
+                2 => i16::from_le_bytes([point[1], point[2]]) as f64,
+                _ => f32::from_le_bytes([point[1], point[2], point[3], point[4]]) as f64,
+            };
+            if value.is_finite() {
+                readings.push(reading(format!("dnp3/{}/ai{}", source, start + i), value));
+            }
+        }
+        at = tail;
+    }
+    Ok(readings)
+}
+
+// One NMEA 0183 sentence, checksum required. XDR (transducer measurement) groups of
+// type,value,unit,name become readings; other well-formed sentences carry none.
+pub fn decode_nmea(line: &str) -> Result<Vec<Reading>, String> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.len() + 2 > NMEA_MAX_LEN {
+        return Err(format!("nmea: {} characters exceeds the sentence limit", line.len()));
+    }
+    let body = line.strip_prefix(['$', '!']).ok_or("nmea: missing $ or ! start")?;
+    let (body, checksum) = body.rsplit_once('*').ok_or("nmea: missing checksum")?;
+    let expected = u8::from_str_radix(checksum, 16).map_err(|_| format!("nmea: bad checksum {:?}", checksum))?;
+    if checksum.len() != 2 || body.bytes().fold(0, |x, b| x ^ b) != expected {
+        return Err("nmea: checksum mismatch".into());
+    }
+    let mut fields = body.split(',');
+    let address = fields.next().unwrap_or("");
+    if address.len() != 5 || !address.is_ascii() {
+        return Err(format!("nmea: bad address field {:?}", address));
+    }
+    if &address[2..] != "XDR" {
+        return Ok(Vec::new());
+    }
+    let fields: Vec<&str> = fields.collect();
+    if !fields.len().is_multiple_of(4) {
+        return Err(format!("nmea: XDR with {} fields is not whole measurements", fields.len()));
+    }
+    let mut readings = Vec::new();
+    for m in fields.chunks_exact(4) {
+        let [_kind, value, _unit, name] = m else { continue };
+        if value.is_empty() {
+            continue;
+        }
+        let value: f64 = value.parse().map_err(|_| format!("nmea: XDR value {:?} is not a number", value))?;
+        if name.is_empty() || !value.is_finite() {
+            return Err(format!("nmea: XDR measurement {:?} has no name or value", name));
+        }
+        readings.push(reading(format!("nmea/{}", name), value));
+    }
+    Ok(readings)
+}
+
+// One CCSDS space packet (version 0, telemetry): the user data after any secondary header is a
+// run of big-endian f32 parameters. Idle packets carry none.
+pub fn decode_ccsds(packet: &[u8], secondary_header_len: usize) -> Result<Vec<Reading>, String> {
+    let (header, data) = packet.split_at_checked(6).ok_or("ccsds: packet shorter than its primary header")?;
+    let id = u16::from_be_bytes([header[0], header[1]]);
+    if id >> 13 != 0 {
+        return Err(format!("ccsds: packet version {}", id >> 13));
+    }
+    if id & 0x1000 != 0 {
+        return Err("ccsds: telecommand packet on a telemetry path".into());
+    }
+    let declared = u16::from_be_bytes([header[4], header[5]]) as usize + 1;
+    if declared != data.len() {
+        return Err(format!("ccsds: data length field {} for {} bytes", declared, data.len()));
+    }
+    let apid = id & 0x7ff;
+    if apid == CCSDS_IDLE_APID {
+        return Ok(Vec::new());
+    }
+    let skip = if id & 0x0800 != 0 { secondary_header_len } else { 0 };
+    let user = data.get(skip..).ok_or("ccsds: packet shorter than its secondary header")?;
+    if !user.len().is_multiple_of(4) {
+        return Err(format!("ccsds: {} user data bytes are not whole f32 parameters", user.len()));
+    }
+    user.chunks_exact(4)
+        .enumerate()
+        .map(|(i, p)| {
+            let value = f32::from_be_bytes([p[0], p[1], p[2], p[3]]) as f64;
+            if value.is_finite() { Ok(reading(format!("ccsds/{}/{}", apid, i), value)) } else { Err(format!("ccsds: parameter {} is not finite", i)) }
+        })
+        .collect()
+}
+
+// Text of each `<tag ...>text</tag>` element with its opening tag's attributes, in order.
+fn elements<'a>(xml: &'a str, tag: &str) -> Result<Vec<(&'a str, &'a str)>, String> {
+    let (open, close) = (format!("<{}", tag), format!("</{}>", tag));
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(i) = rest.find(&open) {
+        let after = &rest[i + open.len()..];
+        // <TxSts> and <TxStsRsn> share a prefix; only the exact tag counts.
+        if !after.starts_with(['>', ' ']) {
+            rest = after;
+            continue;
+        }
+        let gt = after.find('>').ok_or_else(|| format!("iso20022: unterminated <{}>", tag))?;
+        let body = &after[gt + 1..];
+        let end = body.find(&close).ok_or_else(|| format!("iso20022: <{}> is never closed", tag))?;
+        found.push((&after[..gt], body[..end].trim()));
+        rest = &body[end + close.len()..];
+    }
+    Ok(found)
+}
+
+// An ISO 20022 message, identified by its Document namespace. pacs.002 status reports count
+// accepted and rejected transactions; pacs.008 credit transfers total the interbank settlement
+// amount per currency. Other messages carry no readings.
+pub fn decode_iso20022(xml: &str) -> Result<Vec<Reading>, String> {
+    const NAMESPACE: &str = "urn:iso:std:iso:20022:tech:xsd:";
+    let start = xml.find(NAMESPACE).ok_or("iso20022: no ISO 20022 Document namespace")?;
+    let id = &xml[start + NAMESPACE.len()..];
+    let id = &id[..id.find('"').ok_or("iso20022: unterminated namespace")?];
+    // pacs.002.001.12 -> pacs.002
+    let message = id.splitn(3, '.').take(2).collect::<Vec<_>>().join(".");
+    match message.as_str() {
+        "pacs.002" => {
+            let (mut accepted, mut rejected) = (0.0, 0.0);
+            for (_, status) in elements(xml, "TxSts")? {
+                match status {
+                    "ACCP" | "ACSP" | "ACSC" | "ACTC" | "ACWC" => accepted += 1.0,
+                    "RJCT" => rejected += 1.0,
+                    "PDNG" => {}
+                    other => return Err(format!("iso20022: unknown transaction status {:?}", other)),
+                }
+            }
+            Ok(vec![reading("iso20022/pacs.002/accepted".into(), accepted), reading("iso20022/pacs.002/rejected".into(), rejected)])
+        }
+        "pacs.008" => {
+            let mut totals: Vec<(String, f64)> = Vec::new();
+            for (attrs, amount) in elements(xml, "IntrBkSttlmAmt")? {
+                let ccy = attrs.split_once("Ccy=\"").and_then(|(_, v)| v.split_once('"')).map(|(c, _)| c).ok_or("iso20022: settlement amount without Ccy")?;
+                if ccy.len() != 3 || !ccy.bytes().all(|b| b.is_ascii_uppercase()) {
+                    return Err(format!("iso20022: bad currency {:?}", ccy));
+                }
+                let value: f64 = amount.parse().map_err(|_| format!("iso20022: amount {:?} is not a number", amount))?;
+                if !value.is_finite() || value < 0.0 {
+                    return Err(format!("iso20022: amount {:?} out of range", amount));
+                }
+                match totals.iter_mut().find(|(c, _)| c == ccy) {
+                    Some((_, total)) => *total += value,
+                    None => totals.push((ccy.to_string(), value)),
+                }
+            }
+            Ok(totals.into_iter().map(|(ccy, total)| reading(format!("iso20022/pacs.008/settlement/{}", ccy), total)).collect())
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+// `{"source": "...", "value": <number>}` or an array of them; every entry must be well formed.
+pub fn decode_json(frame: &[u8]) -> Result<Vec<Reading>, String> {
+    let doc: serde_json::Value = serde_json::from_slice(frame).map_err(|e| format!("json: {}", e))?;
+    let entries = match &doc {
+        serde_json::Value::Array(items) => items.iter().collect(),
+        single => vec![single],
+    };
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, e)| {
+            let source = e["source"].as_str().filter(|s| !s.is_empty()).ok_or_else(|| format!("json: entry {} has no source", i))?;
+            let value = e["value"].as_f64().filter(|v| v.is_finite()).ok_or_else(|| format!("json: entry {} has no numeric value", i))?;
+            Ok(reading(source.to_string(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest::MetricKind;
+    use crate::units::Unit;
+
+    fn sources(readings: &[Reading]) -> Vec<(&str, f64)> {
+        readings.iter().map(|r| (r.source.as_str(), r.value)).collect()
+    }
+
+    fn modbus(unit: u8, function: u8, pdu: &[u8]) -> Vec<u8> {
+        let mut f = vec![0x00, 0x01, 0x00, 0x00];
+        f.extend_from_slice(&(pdu.len() as u16 + 2).to_be_bytes());
+        f.extend_from_slice(&[unit, function]);
+        f.extend_from_slice(pdu);
+        f
+    }
+
+    // Link header plus CRC-framed 16-byte blocks, as an outstation sends them.
+    fn dnp3(source: u16, user: &[u8]) -> Vec<u8> {
+        let mut f = vec![0x05, 0x64, (user.len() + 5) as u8, 0x44, 0x01, 0x00];
+        f.extend_from_slice(&source.to_le_bytes());
+        f.extend_from_slice(&dnp3_crc(&f).to_le_bytes());
+        for block in user.chunks(DNP3_BLOCK) {
+            f.extend_from_slice(block);
+            f.extend_from_slice(&dnp3_crc(block).to_le_bytes());
+        }
+        f
+    }
+
+    #[test]
+    fn modbus_registers_are_named_by_unit_and_address() {
+        let frame = modbus(17, 3, &[4, 0x01, 0x2c, 0xff, 0xff]);
+        assert_eq!(sources(&decode_modbus(&frame, 40).unwrap()), [("modbus/17/40", 300.0), ("modbus/17/41", 65535.0)]);
+        assert!(decode_modbus(&frame[..frame.len() - 1], 40).is_err());
+        assert!(decode_modbus(&modbus(17, 3, &[3, 0x01, 0x2c, 0xff]), 40).is_err());
+        assert!(decode_modbus(&frame, u16::MAX).unwrap_err().contains("past 65535"));
+        assert!(decode_modbus(&modbus(17, 0x83, &[2]), 0).unwrap_err().contains("exception 2"));
+    }
+
+    #[test]
+    fn dnp3_crc_matches_the_reset_link_states_frame() {
+        assert_eq!(dnp3_crc(&[0x05, 0x64, 0x05, 0xc0, 0x01, 0x00, 0x00, 0x04]).to_le_bytes(), [0xe9, 0x21]);
+    }
+
+    #[test]
+    fn dnp3_analog_inputs_across_blocks() {
+        let mut user = vec![0xc0, 0xc0, 0x81, 0x00, 0x00];
+        user.extend_from_slice(&[30, 1, 0x00, 3, 4, 0x01]);
+        user.extend_from_slice(&(-12i32).to_le_bytes());
+        user.extend_from_slice(&[0x00, 9, 9, 9, 9]);
+        user.extend_from_slice(&[30, 5, 0x01, 10, 0, 10, 0, 0x01]);
+        user.extend_from_slice(&2.5f32.to_le_bytes());
+        let frame = dnp3(1024, &user);
+        assert_eq!(sources(&decode_dnp3(&frame).unwrap()), [("dnp3/1024/ai3", -12.0), ("dnp3/1024/ai10", 2.5)]);
+
+        let mut corrupt = frame.clone();
+        corrupt[12] ^= 0x40;
+        assert!(decode_dnp3(&corrupt).unwrap_err().contains("CRC"));
+        assert!(decode_dnp3(&frame[..frame.len() - 3]).is_err());
+        assert!(decode_dnp3(&dnp3(1, &[0xc0, 0xc0, 0x81, 0, 0, 1, 2, 0x00, 0, 0, 0x01])).unwrap_err().contains("g1v2"));
+        assert!(decode_dnp3(&dnp3(1, &[0x40, 0xc0, 0x81, 0, 0])).unwrap_err().contains("multi-fragment"));
+    }
+
+    #[test]
+    fn nmea_xdr_measurements_need_a_valid_checksum() {
+        assert_eq!(sources(&decode_nmea("$IIXDR,P,1.02481,B,Barometer,C,23.4,C,AirTemp*44\r\n").unwrap()), [("nmea/Barometer", 1.02481), ("nmea/AirTemp", 23.4)]);
+        assert_eq!(decode_nmea("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47"), Ok(Vec::new()));
+        assert!(decode_nmea("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*48").unwrap_err().contains("mismatch"));
+        assert!(decode_nmea("$IIXDR,P,1.02481,B,Barometer").is_err());
+        assert!(decode_nmea(&format!("${}*00", "A".repeat(90))).is_err());
+    }
+
+    #[test]
+    fn ccsds_parameters_follow_the_secondary_header() {
+        let mut packet = vec![0x08 | 0x01, 0x23, 0xc0, 0x07, 0x00, 0x00];
+        packet.extend_from_slice(&[0xaa, 0xbb]);
+        packet.extend_from_slice(&0.75f32.to_be_bytes());
+        packet.extend_from_slice(&(-40.0f32).to_be_bytes());
+        let len = (packet.len() - 7) as u16;
+        packet[4..6].copy_from_slice(&len.to_be_bytes());
+        assert_eq!(sources(&decode_ccsds(&packet, 2).unwrap()), [("ccsds/291/0", 0.75), ("ccsds/291/1", -40.0)]);
+        assert!(decode_ccsds(&packet, 3).is_err());
+        assert!(decode_ccsds(&packet[..packet.len() - 1], 2).unwrap_err().contains("length field"));
+        assert_eq!(decode_ccsds(&[0x07, 0xff, 0xc0, 0x00, 0x00, 0x00, 0x55], 0), Ok(Vec::new()));
+        assert!(decode_ccsds(&[0x11, 0x23, 0xc0, 0x00, 0x00, 0x00, 0x55], 0).unwrap_err().contains("telecommand"));
+    }
+
+    #[test]
+    fn iso20022_status_reports_and_settlement_totals() {
+        let pacs002 = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:pacs.002.001.12"><FIToFIPmtStsRpt>
+            <TxInfAndSts><TxSts>ACSC</TxSts></TxInfAndSts><TxInfAndSts><TxSts>RJCT</TxSts><StsRsnInf><Rsn><Cd>AC04</Cd></Rsn></StsRsnInf></TxInfAndSts>
+            <TxInfAndSts><TxSts>ACSP</TxSts></TxInfAndSts></FIToFIPmtStsRpt></Document>"#;
+        assert_eq!(sources(&decode_iso20022(pacs002).unwrap()), [("iso20022/pacs.002/accepted", 2.0), ("iso20022/pacs.002/rejected", 1.0)]);
+        let pacs008 = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:pacs.008.001.10"><FIToFICstmrCdtTrf>
+            <CdtTrfTxInf><IntrBkSttlmAmt Ccy="EUR">1500.25</IntrBkSttlmAmt></CdtTrfTxInf>
+            <CdtTrfTxInf><IntrBkSttlmAmt Ccy="USD">10</IntrBkSttlmAmt></CdtTrfTxInf>
+            <CdtTrfTxInf><IntrBkSttlmAmt Ccy="EUR">99.75</IntrBkSttlmAmt></CdtTrfTxInf></FIToFICstmrCdtTrf></Document>"#;
+        assert_eq!(sources(&decode_iso20022(pacs008).unwrap()), [("iso20022/pacs.008/settlement/EUR", 1600.0), ("iso20022/pacs.008/settlement/USD", 10.0)]);
+        assert!(decode_iso20022(&pacs008.replace("99.75", "-1")).is_err());
+        assert!(decode_iso20022(&pacs002.replace("</TxSts></TxInfAndSts></FIToFI", "</TxInfAndSts></FIToFI")).is_err());
+        assert!(decode_iso20022("<Document/>").is_err());
+    }
+
+    #[test]
+    fn json_entries_are_all_or_nothing() {
+        assert_eq!(sources(&decode_json(br#"{"source": "flare_analyzer", "value": 97.5}"#).unwrap()), [("flare_analyzer", 97.5)]);
+        assert_eq!(decode_json(br#"[{"source": "a", "value": 1}, {"source": "b", "value": 2}]"#).unwrap().len(), 2);
+        assert!(decode_json(br#"[{"source": "a", "value": 1}, {"source": "b", "value": "2"}]"#).unwrap_err().contains("entry 1"));
+        assert!(decode_json(b"{\"source\": \"a\"").is_err());
+    }
+
+    #[test]
+    fn frames_pass_through_the_queue_bindings() {
+        let mut queue = IngestQueue::new(8, 0.75);
+        queue.register_in("coolant_pressure", MetricKind::Gauge, Unit::KPa);
+        queue.bind("modbus/17/40", "coolant_pressure", Some(Unit::Bar)).unwrap();
+        let admissions = ingest_frame(&mut queue, Protocol::Modbus { first_register: 40 }, &modbus(17, 3, &[4, 0, 2, 0, 7]), 1_000).unwrap();
+        assert_eq!(admissions, [Admission::Queued, Admission::Unknown]);
+        let mut drained = Vec::new();
+        queue.drain_into(&mut drained);
+        assert_eq!((drained[0].metric.as_str(), drained[0].value), ("coolant_pressure", 200.0));
+        assert!(ingest_frame(&mut queue, Protocol::Json, b"[", 1_000).is_err());
+        assert_eq!(queue.depth(), 0);
+    }
+}
@@ -0,0 +1,251 @@
+//! Fleet_Rollup.rs - Fleet/region/global harmony rollup over site decision streams (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::decision::{now_ms, Decision, DecisionRecord};
+
+const MIN_SCORE: f64 = 1e-12;
+const MAX_SITE_AGE: Duration = Duration::from_secs(5);
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FleetDecision { FLEET_GO, FLEET_CAUTION, FLEET_HALT }
+
+pub struct FleetThresholds {
+    pub go_mu: f64,
+    pub caution_mu: f64,
+    pub max_halted_fraction_go: f64,
+    pub max_halted_fraction_caution: f64,
+}
+
+pub const DEFAULT_FLEET_THRESHOLDS: FleetThresholds = FleetThresholds {
+    go_mu: 0.9995,
+    caution_mu: 0.998,
+    max_halted_fraction_go: 0.0,
+    max_halted_fraction_caution: 0.25,
+};
+
+pub enum Scope<'a> {
+    Global,
+    Region(&'a str),
+    BusinessLine(&'a str),
+}
+
+pub struct Site {
+    pub region: String,
+    pub business_line: String,
+    pub weight: f64,
+}
+
+pub struct FleetStatus {
+    pub mu: f64,
+    pub sites: usize,
+    pub halted: usize,
+    pub decision: FleetDecision,
+}
+
+pub struct FleetRollup {
+    thresholds: FleetThresholds,
+    sites: BTreeMap<String, Site>,
+    latest: BTreeMap<String, DecisionRecord>,
+}
+
+impl FleetRollup {
+    pub fn new(thresholds: FleetThresholds) -> Self {
+        FleetRollup { thresholds, sites: BTreeMap::new(), latest: BTreeMap::new() }
+    }
+
+    // Site table: one `<site_id> <region> <business_line> <weight>` per line, `#` comments.
+    pub fn load_sites(&mut self, text: &str) -> Result<(), String> {
+        for (n, line) in text.lines().enumerate().map(|(n, l)| (n + 1, l.trim())).filter(|(_, l)| !l.is_empty() && !l.starts_with('#')) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [id, region, business_line, weight] = fields[..] else {
+                return Err(format!("line {}: expected <site_id> <region> <business_line> <weight>", n));
+            };
+            let weight: f64 = weight.parse().map_err(|_| format!("line {}: weight {:?} is not a number", n, weight))?;
+            if !(weight.is_finite() && weight > 0.0) {
+                return Err(format!("line {}: weight {} must be positive", n, weight));
+            }
+            if self.sites.contains_key(id) {
+                return Err(format!("line {}: site {} listed twice", n, id));
+            }
+            self.register_site(id, region, business_line, weight);
+        }
+        if self.sites.is_empty() {
+            return Err("no sites".into());
+        }
+        Ok(())
+    }
+
+    pub fn register_site(&mut self, site_id: &str, region: &str, business_line: &str, weight: f64) {
+        self.sites.insert(
+            site_id.to_string(),
+            Site { region: region.to_string(), business_line: business_line.to_string(), weight },
+        );
+    }
+
+    pub fn ingest(&mut self, record: DecisionRecord) {
+        if !self.sites.contains_key(&record.node_id) {
+            return;
+        }
+        if self.latest.get(&record.node_id).is_none_or(|r| record.seq > r.seq) {
+            self.latest.insert(record.node_id.clone(), record);
+        }
+    }
+
+    // Weighted geometric mean of site mu; silent or stale sites count as halted at MIN_SCORE,
+    // and a site whose lease has lapsed counts as halted.
+    pub fn rollup(&self, scope: Scope) -> FleetStatus {
+        let now = now_ms();
+        let mut log_sum = 0.0;
+        let mut weight_sum = 0.0;
+        let mut sites = 0;
+        let mut halted = 0;
+        for (id, site) in &self.sites {
+            let in_scope = match scope {
+                Scope::Global => true,
+                Scope::Region(r) => site.region == r,
+                Scope::BusinessLine(b) => site.business_line == b,
+            };
+            if !in_scope {
+                continue;
+            }
+            sites += 1;
+            let (mu, is_halted) = match self.fresh(id, now) {
+                Some(r) => (r.mu, r.effective_at(now) == Decision::HALT),
+                None => (MIN_SCORE, true),
+            };
+            if is_halted {
+                halted += 1;
+            }
+            log_sum += site.weight * mu.clamp(MIN_SCORE, 1.0).ln();
+            weight_sum += site.weight;
+        }
+        let mu = if weight_sum > 0.0 { (log_sum / weight_sum).exp() } else { MIN_SCORE };
+        let halted_fraction = if sites > 0 { halted as f64 / sites as f64 } else { 1.0 };
+        let t = &self.thresholds;
+        let decision = if mu >= t.go_mu && halted_fraction <= t.max_halted_fraction_go {
+            FleetDecision::FLEET_GO
+        } else if mu >= t.caution_mu && halted_fraction <= t.max_halted_fraction_caution {
+            FleetDecision::FLEET_CAUTION
+        } else {
+            FleetDecision::FLEET_HALT
+        };
+        FleetStatus { mu, sites, halted, decision }
+    }
+
+    // A site's own decision as the rollup sees it: HALT when silent, stale or its lease has lapsed.
+    pub fn site_decision(&self, site_id: &str) -> Decision {
+        let now = now_ms();
+        self.fresh(site_id, now).map_or(Decision::HALT, |r| r.effective_at(now))
+    }
+
+    fn fresh(&self, site_id: &str, now: u64) -> Option<&DecisionRecord> {
+        self.latest.get(site_id).filter(|r| now.saturating_sub(r.timestamp_ms) <= MAX_SITE_AGE.as_millis() as u64)
+    }
+
+    pub fn sites(&self) -> impl Iterator<Item = (&str, &Site)> {
+        self.sites.iter().map(|(id, site)| (id.as_str(), site))
+    }
+
+    pub fn regions(&self) -> Vec<String> {
+        let mut regions: Vec<String> = self.sites.values().map(|s| s.region.clone()).collect();
+        regions.sort();
+        regions.dedup();
+        regions
+    }
+
+    pub fn business_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.sites.values().map(|s| s.business_line.clone()).collect();
+        lines.sort();
+        lines.dedup();
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+
+    fn record(site: &str, seq: u64, mu: f64, decision: Decision) -> DecisionRecord {
+        DecisionRecord::new(site, seq, mu, true, decision, ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS).status()).lease(Duration::from_secs(60))
+    }
+
+    fn fleet() -> FleetRollup {
+        let mut fleet = FleetRollup::new(DEFAULT_FLEET_THRESHOLDS);
+        fleet
+            .load_sites(
+                "# site region line weight
+                 pad-1 north upstream 3
+                 pad-2 north upstream 1
+                 refinery south downstream 1",
+            )
+            .unwrap();
+        fleet
+    }
+
+    #[test]
+    fn site_mu_is_weighted() {
+        let mut fleet = fleet();
+        fleet.ingest(record("pad-1", 1, 0.9999, Decision::GO));
+        fleet.ingest(record("pad-2", 1, 0.9985, Decision::CAUTION));
+        // Unweighted, the pair would sit at 0.9992 and roll up to CAUTION.
+        let status = fleet.rollup(Scope::BusinessLine("upstream"));
+        let expected = ((3.0 * 0.9999f64.ln() + 0.9985f64.ln()) / 4.0).exp();
+        assert!((status.mu - expected).abs() < 1e-12);
+        assert_eq!((status.sites, status.halted, status.decision), (2, 0, FleetDecision::FLEET_GO));
+    }
+
+    #[test]
+    fn silent_and_stale_sites_count_as_halted() {
+        let mut fleet = fleet();
+        fleet.ingest(record("pad-1", 1, 0.9999, Decision::GO));
+        let status = fleet.rollup(Scope::Region("north"));
+        assert_eq!((status.halted, status.decision), (1, FleetDecision::FLEET_HALT));
+        let mut stale = record("pad-2", 1, 0.9999, Decision::GO);
+        stale.timestamp_ms -= MAX_SITE_AGE.as_millis() as u64 + 1;
+        fleet.ingest(stale);
+        assert_eq!(fleet.rollup(Scope::Region("north")).halted, 1);
+        assert_eq!((fleet.site_decision("pad-1"), fleet.site_decision("pad-2")), (Decision::GO, Decision::HALT));
+        fleet.ingest(record("pad-2", 2, 0.9999, Decision::GO));
+        let status = fleet.rollup(Scope::Region("north"));
+        assert_eq!((status.halted, status.decision), (0, FleetDecision::FLEET_GO));
+    }
+
+    #[test]
+    fn a_lapsed_lease_counts_as_halted() {
+        let mut fleet = fleet();
+        fleet.ingest(record("refinery", 1, 0.9999, Decision::GO).lease(Duration::ZERO));
+        std::thread::sleep(Duration::from_millis(2));
+        let status = fleet.rollup(Scope::BusinessLine("downstream"));
+        assert_eq!((status.halted, status.decision), (1, FleetDecision::FLEET_HALT));
+        assert_eq!(fleet.site_decision("refinery"), Decision::HALT);
+    }
+
+    #[test]
+    fn scopes_select_their_sites() {
+        let mut fleet = fleet();
+        for (site, mu) in [("pad-1", 0.9999), ("pad-2", 0.9999), ("refinery", 0.5)] {
+            fleet.ingest(record(site, 1, mu, Decision::GO));
+        }
+        fleet.ingest(record("unknown", 1, 1.0, Decision::GO));
+        assert_eq!(fleet.rollup(Scope::Region("north")).decision, FleetDecision::FLEET_GO);
+        assert_eq!(fleet.rollup(Scope::BusinessLine("downstream")).decision, FleetDecision::FLEET_HALT);
+        assert_eq!(fleet.rollup(Scope::Global).sites, 3);
+        assert_eq!(fleet.rollup(Scope::Region("west")).decision, FleetDecision::FLEET_HALT);
+        assert_eq!(fleet.business_lines(), ["downstream", "upstream"]);
+        assert_eq!(fleet.regions(), ["north", "south"]);
+    }
+
+    #[test]
+    fn malformed_site_tables_are_refused() {
+        let load = |text: &str| FleetRollup::new(DEFAULT_FLEET_THRESHOLDS).load_sites(text);
+        assert!(load("").is_err());
+        assert!(load("pad-1 north upstream").is_err());
+        assert!(load("pad-1 north upstream 0").is_err());
+        assert!(load("pad-1 north upstream NaN").is_err());
+        assert!(load("pad-1 north upstream 1\npad-1 south upstream 1").is_err());
+    }
+}
@@ -0,0 +1,36 @@
+//! Attestation_Store.rs - Fuzz attestation documents submitted to the store
+#![no_main]
+use ed25519_dalek::SigningKey;
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../attestation_store.rs"]
+mod attestation_store;
+#[path = "../../clock_sync.rs"]
+mod clock_sync;
+#[path = "../../core/mod.rs"]
+mod core;
+#[path = "../../decision.rs"]
+mod decision;
+#[path = "../../decision_kernel.rs"]
+mod decision_kernel;
+#[path = "../../plugin.rs"]
+mod plugin;
+#[path = "../../rbac.rs"]
+mod rbac;
+#[path = "../../sealed_config.rs"]
+mod sealed_config;
+use crate::core::harmony::{Conditions, Severity};
+use crate::core::validity::Validity;
+use attestation_store::AttestationStore;
+
+fn clock() -> u64 {
+    1_700_000_000_000
+}
+
+fuzz_target!(|data: &[u8]| {
+    let qa = SigningKey::from_bytes(&[7; 32]);
+    let mut store = AttestationStore::new("well-7", vec![("qa".into(), qa.verifying_key())], clock).kind("calibration", Severity::Major, Validity::days(30, 3), &["qa"]);
+    let _ = store.submit(&String::from_utf8_lossy(data));
+    let _ = store.state("calibration");
+    store.record_into(&mut Conditions::new());
+});
@@ -0,0 +1,32 @@
+//! Calibrations.rs - Fuzz calibration curve specs and the scores they map readings to
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../clock_sync.rs"]
+mod clock_sync;
+#[path = "../../core/mod.rs"]
+mod core;
+#[path = "../../decision.rs"]
+mod decision;
+#[path = "../../decision_kernel.rs"]
+mod decision_kernel;
+#[path = "../../plugin.rs"]
+mod plugin;
+#[path = "../../rbac.rs"]
+mod rbac;
+use crate::core::calibration::Calibrations;
+
+const KNOWN: [&str; 2] = ["flare_stability", "wellhead_coherence"];
+
+// `<channel> = <kind>: x:y, ...` per line, as calibrate.<channel> config entries.
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    let specs: Vec<(String, String)> = text.lines().filter_map(|l| l.split_once('=')).map(|(c, s)| (c.trim().to_string(), s.trim().to_string())).collect();
+    let Ok(calibrations) = Calibrations::parse(&specs, &KNOWN) else { return };
+    for c in calibrations.iter() {
+        for reading in [f64::MIN, -1.0, 0.0, 50.0, 96.0, 99.9, 1e9, f64::MAX] {
+            let score = c.curve.map(reading);
+            assert!((0.0..=1.0).contains(&score), "{} maps {} to {}", c.curve, reading, score);
+        }
+    }
+});
@@ -0,0 +1,20 @@
+//! Ccsds_Packet.rs - Fuzz the CCSDS space packet decoder
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../field_frames.rs"]
+mod field_frames;
+#[path = "../../ingest.rs"]
+mod ingest;
+#[path = "../../units.rs"]
+mod units;
+use field_frames::Protocol;
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte is the mission's secondary header length.
+    let Some((secondary, packet)) = data.split_first() else { return };
+    let protocol = Protocol::Ccsds { secondary_header_len: *secondary as usize };
+    if let Ok(readings) = protocol.decode(packet) {
+        assert!(readings.iter().all(|r| !r.source.is_empty() && r.value.is_finite()));
+    }
+});
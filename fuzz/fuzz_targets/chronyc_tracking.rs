@@ -0,0 +1,10 @@
+//! Chronyc_Tracking.rs - Fuzz the chronyc CSV parser feeding the clock-sync condition
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../clock_sync.rs"]
+mod clock_sync;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = clock_sync::parse_chronyc_tracking(&String::from_utf8_lossy(data));
+});
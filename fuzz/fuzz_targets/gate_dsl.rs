@@ -0,0 +1,37 @@
+//! Gate_DSL.rs - Fuzz gate expressions and gate specs as loaded from config
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../clock_sync.rs"]
+mod clock_sync;
+#[path = "../../core/mod.rs"]
+mod core;
+#[path = "../../decision.rs"]
+mod decision;
+#[path = "../../decision_kernel.rs"]
+mod decision_kernel;
+#[path = "../../plugin.rs"]
+mod plugin;
+#[path = "../../rbac.rs"]
+mod rbac;
+use crate::core::gate::{Expr, GateSet};
+use crate::core::harmony::{Conditions, Severity};
+
+const KNOWN: [&str; 3] = ["h2s_ok", "bop_interlock_ok", "manual_override_with_permit"];
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    // The input's length picks which conditions hold, so evaluation sees every combination.
+    let mut conditions = Conditions::new();
+    for (i, name) in KNOWN.iter().enumerate() {
+        conditions.record(name, Severity::Critical, (data.len() >> i) & 1 == 1);
+    }
+    if let Ok(expr) = Expr::parse(&text, &KNOWN) {
+        let _ = expr.eval(&conditions);
+    }
+    // `<name> = <severity>: <expr>` per line, as gate.<name> config entries.
+    let specs: Vec<(String, String)> = text.lines().filter_map(|l| l.split_once('=')).map(|(n, s)| (n.trim().to_string(), s.trim().to_string())).collect();
+    if let Ok(gates) = GateSet::parse(&specs, &KNOWN) {
+        gates.apply(&mut conditions);
+    }
+});
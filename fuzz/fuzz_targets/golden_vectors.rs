@@ -0,0 +1,10 @@
+//! Golden_Vectors.rs - Fuzz the golden-vector file parser
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../golden_vectors.rs"]
+mod golden_vectors;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = golden_vectors::parse_golden(&String::from_utf8_lossy(data));
+});
@@ -0,0 +1,19 @@
+//! Gossip_Datagram.rs - Fuzz the peer gossip line decoder with arbitrary datagrams
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../clock_sync.rs"]
+mod clock_sync;
+#[path = "../../decision.rs"]
+mod decision;
+#[path = "../../decision_kernel.rs"]
+mod decision_kernel;
+#[path = "../../gossip.rs"]
+mod gossip;
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    for entry in text.lines().filter_map(gossip::GossipEntry::decode) {
+        assert!(entry.value.is_finite());
+    }
+});
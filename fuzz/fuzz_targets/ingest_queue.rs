@@ -0,0 +1,35 @@
+//! Ingest_Queue.rs - Fuzz the ingestion queue with arbitrary push, push_from and drain sequences
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../ingest.rs"]
+mod ingest;
+#[path = "../../units.rs"]
+mod units;
+use ingest::{IngestQueue, MetricKind};
+use units::Unit;
+
+const CAPACITY: usize = 8;
+
+fuzz_target!(|data: &[u8]| {
+    let mut queue = IngestQueue::new(CAPACITY, 0.75);
+    queue.register_in("pressure", MetricKind::Gauge, Unit::KPa).register("events", MetricKind::Counter);
+    queue.bind("gauge_psi", "pressure", Some(Unit::Psi)).expect("psi converts to kPa");
+    let mut drained = Vec::new();
+    // Each op is 10 bytes: kind, target, then an f64 value (NaN and infinities included).
+    for op in data.chunks_exact(10) {
+        let name = ["pressure", "events", "gauge_psi", "unbound"][op[1] as usize % 4];
+        let value = f64::from_le_bytes(op[2..].try_into().expect("8 bytes"));
+        match op[0] % 3 {
+            0 => {
+                queue.push(name, value, op[1] as u64);
+            }
+            1 => {
+                queue.push_from(name, value, op[1] as u64);
+            }
+            _ => queue.drain_into(&mut drained),
+        }
+        assert!(queue.depth() <= CAPACITY);
+    }
+    let _ = queue.metrics_text();
+});
@@ -0,0 +1,17 @@
+//! Iso20022_Message.rs - Fuzz the ISO 20022 message decoder
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../field_frames.rs"]
+mod field_frames;
+#[path = "../../ingest.rs"]
+mod ingest;
+#[path = "../../units.rs"]
+mod units;
+use field_frames::Protocol;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(readings) = Protocol::Iso20022.decode(data) {
+        assert!(readings.iter().all(|r| !r.source.is_empty() && r.value.is_finite()));
+    }
+});
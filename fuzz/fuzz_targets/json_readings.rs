@@ -0,0 +1,17 @@
+//! Json_Readings.rs - Fuzz the JSON readings decoder
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../field_frames.rs"]
+mod field_frames;
+#[path = "../../ingest.rs"]
+mod ingest;
+#[path = "../../units.rs"]
+mod units;
+use field_frames::Protocol;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(readings) = Protocol::Json.decode(data) {
+        assert!(readings.iter().all(|r| !r.source.is_empty() && r.value.is_finite()));
+    }
+});
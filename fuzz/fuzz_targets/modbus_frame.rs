@@ -0,0 +1,20 @@
+//! Modbus_Frame.rs - Fuzz the Modbus TCP response decoder
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../field_frames.rs"]
+mod field_frames;
+#[path = "../../ingest.rs"]
+mod ingest;
+#[path = "../../units.rs"]
+mod units;
+use field_frames::Protocol;
+
+fuzz_target!(|data: &[u8]| {
+    // The first two bytes pick the start register the poller asked for.
+    let Some((register, frame)) = data.split_first_chunk::<2>() else { return };
+    let protocol = Protocol::Modbus { first_register: u16::from_be_bytes(*register) };
+    if let Ok(readings) = protocol.decode(frame) {
+        assert!(readings.iter().all(|r| !r.source.is_empty() && r.value.is_finite()));
+    }
+});
@@ -0,0 +1,27 @@
+//! Scenario_DSL.rs - Fuzz the scenario parser and runner end to end
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../clock_sync.rs"]
+mod clock_sync;
+#[path = "../../core/mod.rs"]
+mod core;
+#[path = "../../decision.rs"]
+mod decision;
+#[path = "../../decision_kernel.rs"]
+mod decision_kernel;
+#[path = "../../plugin.rs"]
+mod plugin;
+#[path = "../../rbac.rs"]
+mod rbac;
+#[path = "../../simulation.rs"]
+mod simulation;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(sc) = simulation::parse_scenario(&String::from_utf8_lossy(data)) {
+        // Bound virtual time so the fuzzer explores parsing, not long loops.
+        if sc.steps.last().is_none_or(|s| s.at < 10_000) {
+            let _ = simulation::run_scenario(&sc);
+        }
+    }
+});
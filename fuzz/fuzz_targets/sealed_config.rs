@@ -0,0 +1,16 @@
+//! Sealed_Config.rs - Fuzz the engine config, trusted key and seal parsers
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../sealed_config.rs"]
+mod sealed_config;
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    let _ = sealed_config::parse_config(&text);
+    let _ = sealed_config::unhex(&text);
+    // The same bytes as key file, config and seal: a seal is only checked against parsed keys.
+    if let Ok(trusted) = sealed_config::parse_trusted_keys(&text) {
+        let _ = sealed_config::verify_seal(data, &text, &trusted);
+    }
+});
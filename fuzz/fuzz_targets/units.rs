@@ -0,0 +1,21 @@
+//! Units.rs - Fuzz unit names and conversions as declared in HARMONY_SOURCE_UNITS
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../units.rs"]
+mod units;
+use units::{Conversion, Unit};
+
+// `<from> <to> <value>`, e.g. `psi kPa 14.7`.
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    let mut parts = text.splitn(3, ' ');
+    let (Some(Ok(from)), Some(Ok(to))) = (parts.next().map(Unit::parse), parts.next().map(Unit::parse)) else { return };
+    assert_eq!(Unit::parse(from.as_str()), Ok(from));
+    if let Ok(conversion) = Conversion::between(from, to) {
+        assert_eq!(from.dimension(), to.dimension());
+        if let Some(value) = parts.next().and_then(|v| v.trim().parse::<f64>().ok()) {
+            let _ = (conversion.apply(value), conversion.apply_delta(value));
+        }
+    }
+});
@@ -0,0 +1,144 @@
+//! Golden_Vectors.rs - Golden mu vectors and a cross-precision conformance checker (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::fs;
+use std::io;
+
+pub const GOLDEN_PATH: &str = "golden/mu_vectors.txt";
+
+#[derive(Clone, Debug)]
+pub struct GoldenVector {
+    pub id: String,
+    pub weights: Vec<f64>,
+    pub scores: Vec<f64>,
+    pub expected_mu: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Precision {
+    F64 { max_ulps: u64 },
+    F32 { max_ulps: u32 },
+    // Fixed-point / SIMD / foreign reimplementations: relative error bound.
+    Relative { max_rel_err: f64 },
+}
+
+pub const REFERENCE_F64: Precision = Precision::F64 { max_ulps: 64 };
+pub const REFERENCE_F32: Precision = Precision::F32 { max_ulps: 32 };
+
+#[derive(Clone, Debug)]
+pub struct ConformanceFailure {
+    pub id: String,
+    pub expected: f64,
+    pub actual: f64,
+}
+
+pub fn load_golden(path: &str) -> io::Result<Vec<GoldenVector>> {
+    parse_golden(&fs::read_to_string(path)?)
+        .map_err(|line| io::Error::new(io::ErrorKind::InvalidData, format!("{}: bad vector on line {}", path, line)))
+}
+
+pub fn parse_golden(text: &str) -> Result<Vec<GoldenVector>, usize> {
+    let mut out = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let f: Vec<&str> = line.split('|').map(str::trim).collect();
+        if f.len() != 4 {
+            return Err(i + 1);
+        }
+        let list = |s: &str| -> Option<Vec<f64>> { s.split(',').map(|x| x.trim().parse().ok()).collect() };
+        let v = GoldenVector {
+            id: f[0].to_string(),
+            weights: list(f[1]).ok_or(i + 1)?,
+            scores: list(f[2]).ok_or(i + 1)?,
+            expected_mu: f[3].parse().map_err(|_| i + 1)?,
+        };
+        if v.weights.len() != v.scores.len() {
+            return Err(i + 1);
+        }
+        out.push(v);
+    }
+    Ok(out)
+}
+
+// Runs an implementation (any precision, widened to f64 at the boundary) over every
+// vector and returns the ones outside the precision's tolerance.
+pub fn check_conformance<F>(vectors: &[GoldenVector], precision: Precision, mu: F) -> Vec<ConformanceFailure>
+where
+    F: Fn(&[f64], &[f64]) -> f64,
+{
+    vectors
+        .iter()
+        .filter_map(|v| {
+            let actual = mu(&v.weights, &v.scores);
+            if within(precision, v.expected_mu, actual) {
+                None
+            } else {
+                Some(ConformanceFailure { id: v.id.clone(), expected: v.expected_mu, actual })
+            }
+        })
+        .collect()
+}
+
+fn within(precision: Precision, expected: f64, actual: f64) -> bool {
+    if !actual.is_finite() {
+        return false;
+    }
+    match precision {
+        Precision::F64 { max_ulps } => ulps_f64(expected, actual) <= max_ulps,
+        Precision::F32 { max_ulps } => ulps_f32(expected as f32, actual as f32) <= max_ulps,
+        Precision::Relative { max_rel_err } => {
+            (actual - expected).abs() <= max_rel_err * expected.abs().max(f64::MIN_POSITIVE)
+        }
+    }
+}
+
+// Distance in representable values; both inputs are positive for mu.
+pub fn ulps_f64(a: f64, b: f64) -> u64 {
+    (a.to_bits() as i64).abs_diff(b.to_bits() as i64)
+}
+
+pub fn ulps_f32(a: f32, b: f32) -> u32 {
+    (a.to_bits() as i32).abs_diff(b.to_bits() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decision_kernel::weighted_mu;
+
+    #[test]
+    fn the_kernel_matches_every_published_vector() {
+        let vectors = load_golden(GOLDEN_PATH).unwrap();
+        assert!(vectors.len() >= 20, "{} vectors", vectors.len());
+        let failures = check_conformance(&vectors, REFERENCE_F64, weighted_mu);
+        assert!(failures.is_empty(), "{:?}", failures);
+        assert!(check_conformance(&vectors, REFERENCE_F32, |w, s| weighted_mu(w, s) as f32 as f64).is_empty());
+    }
+
+    #[test]
+    fn a_drifting_implementation_is_caught() {
+        let vectors = load_golden(GOLDEN_PATH).unwrap();
+        let failures = check_conformance(&vectors, REFERENCE_F64, |w, s| weighted_mu(w, s) * (1.0 - 1e-12));
+        assert!(failures.iter().any(|f| f.id == "repo_default"));
+        assert!(check_conformance(&vectors, Precision::Relative { max_rel_err: 1e-9 }, |w, s| weighted_mu(w, s) * (1.0 - 1e-12)).is_empty());
+        let nan = check_conformance(&vectors, Precision::Relative { max_rel_err: 1.0 }, |_, _| f64::NAN);
+        assert_eq!(nan.len(), vectors.len());
+    }
+
+    #[test]
+    fn malformed_lines_are_reported_by_number() {
+        assert_eq!(parse_golden("# header\n\nok | 1 | 0.5 | 0.5").unwrap().len(), 1);
+        assert_eq!(parse_golden("# header\nbad | 1 | 0.5").unwrap_err(), 2);
+        assert_eq!(parse_golden("a | 0.5,0.5 | 0.9 | 0.9").unwrap_err(), 1);
+        assert_eq!(parse_golden("a | 1 | x | 0.9").unwrap_err(), 1);
+    }
+
+    #[test]
+    fn ulps_count_representable_steps() {
+        assert_eq!(ulps_f64(1.0, 1.0), 0);
+        assert_eq!(ulps_f64(1.0, f64::from_bits(1.0f64.to_bits() + 3)), 3);
+        assert_eq!(ulps_f32(0.5, f32::from_bits(0.5f32.to_bits() - 2)), 2);
+    }
+}
@@ -0,0 +1,175 @@
+//! Gossip.rs - Peer-to-peer score sharing for disconnected edge clusters (forbid unsafe)
+//!
+//! Each entry is versioned by (epoch, version): the epoch is the publisher's start time, so a
+//! restarted node's fresh counter still supersedes what it published before the restart.
+//! Freshness is judged by when this node first received a version, not by the sender's clock;
+//! entries stamped further than GOSSIP_MAX_SKEW from local time are dropped on arrival.
+#![forbid(unsafe_code)]
+use std::collections::BTreeMap;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::Duration;
+
+use crate::decision::now_ms;
+
+const GOSSIP_FANOUT: usize = 2;
+const MAX_DATAGRAM: usize = 1400;
+const GOSSIP_MAX_SKEW_MS: u64 = 60_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GossipEntry {
+    pub node_id: String,
+    pub key: String,
+    pub epoch: u64,
+    pub version: u64,
+    pub timestamp_ms: u64,
+    pub value: f64,
+}
+
+impl GossipEntry {
+    // node|key|epoch|version|timestamp_ms|value, one entry per line.
+    fn encode(&self) -> String {
+        format!("{}|{}|{}|{}|{}|{}", self.node_id, self.key, self.epoch, self.version, self.timestamp_ms, self.value)
+    }
+
+    pub fn decode(line: &str) -> Option<GossipEntry> {
+        let mut f = line.split('|');
+        let entry = GossipEntry {
+            node_id: f.next()?.to_string(),
+            key: f.next()?.to_string(),
+            epoch: f.next()?.parse().ok()?,
+            version: f.next()?.parse().ok()?,
+            timestamp_ms: f.next()?.parse().ok()?,
+            value: f.next()?.parse().ok()?,
+        };
+        if f.next().is_some() || !entry.value.is_finite() {
+            return None;
+        }
+        Some(entry)
+    }
+}
+
+pub struct GossipNode {
+    node_id: String,
+    socket: UdpSocket,
+    peers: Vec<SocketAddr>,
+    shared_keys: Vec<String>,
+    // Each entry with the local time its version first arrived.
+    table: BTreeMap<(String, String), (GossipEntry, u64)>,
+    epoch: u64,
+    version: u64,
+    rng: u64,
+}
+
+impl GossipNode {
+    pub fn bind(node_id: &str, addr: SocketAddr, peers: Vec<SocketAddr>, shared_keys: &[&str]) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(GossipNode {
+            node_id: node_id.to_string(),
+            socket,
+            peers,
+            shared_keys: shared_keys.iter().map(|k| k.to_string()).collect(),
+            table: BTreeMap::new(),
+            epoch: now_ms(),
+            version: 0,
+            rng: now_ms() | 1,
+        })
+    }
+
+    // Only keys configured as shared ever leave the node.
+    pub fn publish_local(&mut self, key: &str, value: f64) {
+        if !self.shared_keys.iter().any(|k| k == key) {
+            return;
+        }
+        self.version += 1;
+        let entry = GossipEntry {
+            node_id: self.node_id.clone(),
+            key: key.to_string(),
+            epoch: self.epoch,
+            version: self.version,
+            timestamp_ms: now_ms(),
+            value,
+        };
+        self.merge(entry);
+    }
+
+    pub fn merge(&mut self, entry: GossipEntry) {
+        let now = now_ms();
+        if !self.shared_keys.contains(&entry.key) || now.abs_diff(entry.timestamp_ms) > GOSSIP_MAX_SKEW_MS {
+            return;
+        }
+        let slot = (entry.node_id.clone(), entry.key.clone());
+        if self.table.get(&slot).is_none_or(|(e, _)| (entry.epoch, entry.version) > (e.epoch, e.version)) {
+            self.table.insert(slot, (entry, now));
+        }
+    }
+
+    // One gossip round: push the whole table to a few random peers, then absorb what arrived.
+    pub fn round(&mut self) -> io::Result<()> {
+        let payload: String = self.table.values().map(|(e, _)| e.encode() + "\n").collect();
+        for _ in 0..GOSSIP_FANOUT.min(self.peers.len()) {
+            let pick = (self.next_rand() % self.peers.len() as u64) as usize;
+            let peer = self.peers[pick];
+            for chunk in chunk_lines(&payload, MAX_DATAGRAM) {
+                self.socket.send_to(chunk.as_bytes(), peer)?;
+            }
+        }
+        let mut buf = [0u8; MAX_DATAGRAM];
+        loop {
+            match self.socket.recv_from(&mut buf) {
+                Ok((n, _)) => {
+                    let text = String::from_utf8_lossy(&buf[..n]).into_owned();
+                    for entry in text.lines().filter_map(GossipEntry::decode) {
+                        self.merge(entry);
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // Worst fresh neighbor value for `key`; None when no neighbor has reported recently.
+    pub fn neighborhood_min(&self, key: &str, max_age: Duration) -> Option<f64> {
+        let now = now_ms();
+        self.table
+            .values()
+            .filter(|(e, _)| e.key == key && e.node_id != self.node_id)
+            .filter(|(_, received)| now.saturating_sub(*received) <= max_age.as_millis() as u64)
+            .map(|(e, _)| e.value)
+            .fold(None, |acc: Option<f64>, v| Some(acc.map_or(v, |a| a.min(v))))
+    }
+
+    // For check_ch: fails when no neighbor has reported recently, since silence is what a cut
+    // link or a downed cluster looks like. A node configured without peers has no neighborhood.
+    pub fn neighborhood_ok(&self, key: &str, floor: f64, max_age: Duration) -> bool {
+        match self.neighborhood_min(key, max_age) {
+            Some(v) => v >= floor,
+            None => self.peers.is_empty(),
+        }
+    }
+
+    fn next_rand(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+}
+
+fn chunk_lines(payload: &str, max: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut cur = String::new();
+    for line in payload.lines() {
+        if !cur.is_empty() && cur.len() + line.len() + 1 > max {
+            chunks.push(std::mem::take(&mut cur));
+        }
+        cur.push_str(line);
+        cur.push('\n');
+    }
+    if !cur.is_empty() {
+        chunks.push(cur);
+    }
+    chunks
+}
@@ -1,54 +1,124 @@
 //! Ground_Segment_Monitor.rs - NASA-STD Ground Safety Crate (forbid unsafe)
 #![forbid(unsafe_code)]
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::Duration;
 
-const HARMONY_THRESHOLD: f64 = 0.9995;
-const MIN_SCORE: f64 = 1e-12;
+mod clock_sync;
+mod core;
+mod decision;
+mod decision_kernel;
+mod plugin;
+mod rbac;
+mod space_weather;
+use crate::core::cadence::{Cadence, Rate};
+use crate::core::checks::{CheckRegistry, SyncCheck};
+use crate::core::harmony::{Conditions, Decision, Evaluation, Severity};
+use crate::core::monitor::HarmonyMonitor;
+use crate::core::source::{FnSource, SourceError, SourceSet};
+use plugin::Domain;
+use space_weather::{SpaceWeatherProvider, GROUND_SEGMENT_LIMITS};
 
-pub struct GroundContext {
-    pub scores: Vec<f64>,
-    pub weights: Vec<f64>,
+
+fn ch_checks() -> CheckRegistry {
+    let mut checks = CheckRegistry::new();
+    checks
+        .register(Box::new(SyncCheck::new("telemetry_link_alive", telemetry_link_alive)))
+        .register(Box::new(SyncCheck::new("range_safety_clear", range_safety_clear)))
+        .register(Box::new(SyncCheck::new("weather_within_limits", weather_within_limits)))
+        .register(Box::new(SyncCheck::new("crew_surgeon_ok", crew_surgeon_ok)))
+        .register(Box::new(SyncCheck::new("no_hold_countdown", no_hold_countdown)));
+    checks
 }
 
-impl GroundContext {
-    pub fn calculate_mu(&self) -> f64 {
-        let mut log_sum = 0.0;
-        for (w, s) in self.weights.iter().zip(self.scores.iter()) {
-            let s_clipped = s.clamp(MIN_SCORE, 1.0);
-            log_sum += w * s_clipped.ln();
-        }
-        log_sum.exp()
+// One source per weighted channel, in weight order; swap in real telemetry backends here.
+fn score_sources() -> SourceSet {
+    let mut sources = SourceSet::new(Duration::from_millis(250));
+    sources
+        .register(Box::new(FnSource::new("telemetry_link_health", query_telemetry_link_health)))
+        .register(Box::new(FnSource::new("range_safety_status", query_range_safety_status)))
+        .register(Box::new(FnSource::new("weather", query_weather)))
+        .register(Box::new(FnSource::new("crew_surgeon", query_crew_surgeon)))
+        .register(Box::new(FnSource::new("hold_countdown", query_hold_countdown)));
+    sources
+}
+
+// 1 Hz while mu is comfortably above threshold, 5 Hz from the caution band down, back to 1 Hz
+// after 10 s (50 fast cycles) of recovered GO. Overridable per site.
+fn cadence() -> Cadence {
+    let ms = |var: &str, default: u64| Duration::from_millis(std::env::var(var).ok().and_then(|v| v.parse().ok()).unwrap_or(default));
+    Cadence::new(ms("HARMONY_BASELINE_TICK_MS", 1000), ms("HARMONY_FAST_TICK_MS", 200)).expect("sampling cadence").recover_after(50)
+}
+
+// The countdown is held from the first HALT cycle until the monitor is back at GO.
+struct GroundSegment {
+    sources: SourceSet,
+    checks: CheckRegistry,
+    space_weather: SpaceWeatherProvider,
+    baseline_tick: Duration,
+    holding: bool,
+}
+
+impl GroundSegment {
+    fn new(baseline_tick: Duration) -> Self {
+        GroundSegment { sources: score_sources(), checks: ch_checks(), space_weather: SpaceWeatherProvider::new(), baseline_tick, holding: false }
+    }
+
+    // Conditions beyond the registry's checks.
+    fn check_ch(&self, conditions: &mut Conditions) {
+        conditions.record("space_weather_within_limits", Severity::Critical, self.space_weather.within_limits(&GROUND_SEGMENT_LIMITS));
     }
 }
 
-pub async fn check_ch() -> bool {
-    telemetry_link_alive() &&
-    range_safety_clear()   &&
-    weather_within_limits() &&
-    crew_surgeon_ok()      &&
-    no_hold_countdown()
+impl Domain for GroundSegment {
+    fn name(&self) -> &str { "ground_segment" }
+    fn tick(&self) -> Duration { self.baseline_tick }
+    fn weights(&self) -> Vec<f64> { vec![0.30, 0.25, 0.20, 0.15, 0.10] }
+    fn sources(&self) -> &SourceSet { &self.sources }
+    fn checks(&self) -> &CheckRegistry { &self.checks }
+
+    fn safe_state(&mut self) {
+        if !self.holding {
+            println!("Space: holding countdown");
+        }
+        self.holding = true;
+    }
+
+    fn in_safe_state(&self) -> bool {
+        self.holding
+    }
+
+    fn refine(&mut self, _scores: &mut [f64], _errors: &mut Vec<(usize, SourceError)>, conditions: &mut Conditions) {
+        self.check_ch(conditions);
+    }
+
+    fn decide(&mut self, _scores: &[f64], _errors: &[(usize, SourceError)], _conditions: &Conditions, eval: Evaluation) -> Evaluation {
+        if eval.decision == Decision::GO {
+            self.holding = false;
+        }
+        eval
+    }
 }
 
 #[tokio::main]
 async fn main() {
-    let ctx = GroundContext {
-        scores: vec![0.98, 0.97, 1.0, 0.96, 0.99],
-        weights: vec![0.30, 0.25, 0.20, 0.15, 0.10],
-    };
+    let mut cadence = cadence();
+    let mut monitor = HarmonyMonitor::new(GroundSegment::new(cadence.tick())).expect("harmony context");
     loop {
-        let scores = vec![
-            query_telemetry_link_health().await,
-            query_range_safety_status().await,
-            query_weather().await,
-            query_crew_surgeon().await,
-            query_hold_countdown().await,
-        ];
-        let mu = ctx.calculate_mu();
-        let ch = check_ch().await;
-        match (mu >= HARMONY_THRESHOLD && ch) {
-            true  => println!("Space: FLIGHT GO"),
-            false => println!("Space: FLIGHT HALT – hold countdown"),
+        monitor.domain_mut().space_weather.refresh().await;
+        let eval = monitor.cycle().await;
+        for (i, e) in monitor.source_errors() {
+            eprintln!("Space: score source {} failed: {}", monitor.names()[*i], e);
+        }
+        println!("Space: dmu/ds {}", monitor.sensitivity());
+        match eval.decision {
+            Decision::GO => println!("Space: FLIGHT GO"),
+            Decision::CAUTION => println!("Space: FLIGHT CAUTION – early warning"),
+            Decision::HALT => println!("Space: FLIGHT HALT – hold countdown [{}]", monitor.conditions().failure_summary()),
+        }
+        match cadence.observe(&eval) {
+            Some(Rate::Fast) => println!("Space: mu {:.6} degraded; sampling every {:?}", eval.mu, cadence.tick()),
+            Some(Rate::Baseline) => println!("Space: mu {:.6} recovered; sampling every {:?}", eval.mu, cadence.tick()),
+            None => {}
         }
-        tokio::time::sleep(Duration::from_secs(1)).await;
+        tokio::time::sleep(cadence.tick()).await;
     }
 }
@@ -0,0 +1,89 @@
+//! HALT_Coverage.rs - Reports declared HALT paths never exercised by scenarios or history (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::collections::BTreeSet;
+use std::{env, fs, process};
+
+mod clock_sync;
+mod core;
+mod decision;
+mod decision_kernel;
+mod plugin;
+mod rbac;
+mod simulation;
+use decision::Decision;
+use simulation::{parse_scenario, run_scenario};
+
+// Declaration file: `reason <CODE>` / `condition <name>` per line, `#` comments.
+fn load_declared(text: &str) -> Result<BTreeSet<String>, String> {
+    let mut declared = BTreeSet::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        match line.split_once(' ') {
+            Some(("reason", code)) => declared.insert(code.trim().to_string()),
+            Some(("condition", name)) => declared.insert(format!("CH_FAILED:{}", name.trim())),
+            _ => return Err(format!("line {}: expected `reason X` or `condition x`", i + 1)),
+        };
+    }
+    Ok(declared)
+}
+
+// Reason codes seen on HALT ticks of a scenario run.
+fn exercised_by_scenario(text: &str) -> Result<BTreeSet<String>, String> {
+    let sc = parse_scenario(text).map_err(|e| e.to_string())?;
+    let (trace, _) = run_scenario(&sc).map_err(|e| e.to_string())?;
+    Ok(trace
+        .into_iter()
+        .filter(|t| t.decision == Decision::HALT)
+        .flat_map(|t| t.reasons)
+        .collect())
+}
+
+// Recorded history: any whitespace/punctuation-delimited token matching a declared code.
+fn exercised_by_history(text: &str, declared: &BTreeSet<String>) -> BTreeSet<String> {
+    text.split(|c: char| c.is_whitespace() || c == '"' || c == ',' || c == '[' || c == ']')
+        .filter(|tok| declared.contains(*tok))
+        .map(str::to_string)
+        .collect()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some(decl_path) = args.first() else {
+        eprintln!("usage: halt_coverage <decl> [--scenarios <yaml>...] [--history <log>...] [--report-only]");
+        process::exit(2);
+    };
+    let declared = match fs::read_to_string(decl_path).map_err(|e| e.to_string()).and_then(|t| load_declared(&t)) {
+        Ok(d) => d,
+        Err(e) => { eprintln!("{}: {}", decl_path, e); process::exit(2); }
+    };
+    let mut exercised = BTreeSet::new();
+    let mut mode = "";
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "--scenarios" | "--history" | "--report-only" => { mode = arg; continue; }
+            _ => {}
+        }
+        let text = match fs::read_to_string(arg) {
+            Ok(t) => t,
+            Err(e) => { eprintln!("{}: {}", arg, e); process::exit(2); }
+        };
+        match mode {
+            "--scenarios" => match exercised_by_scenario(&text) {
+                Ok(seen) => exercised.extend(seen),
+                Err(e) => { eprintln!("{}: {}", arg, e); process::exit(2); }
+            },
+            "--history" => exercised.extend(exercised_by_history(&text, &declared)),
+            _ => { eprintln!("{}: pass --scenarios or --history first", arg); process::exit(2); }
+        }
+    }
+    let missing: Vec<&String> = declared.iter().filter(|d| !exercised.contains(*d)).collect();
+    println!("HALT-path coverage: {}/{} exercised", declared.len() - missing.len(), declared.len());
+    for m in &missing {
+        println!("  never exercised: {}", m);
+    }
+    let report_only = args.iter().any(|a| a == "--report-only");
+    process::exit(if missing.is_empty() || report_only { 0 } else { 1 });
+}
@@ -0,0 +1,206 @@
+//! HALT_Propagation.rs - Site -> region -> enterprise propagation of HALT (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::collections::BTreeMap;
+
+use crate::decision::Decision;
+
+// "When at least `min_halted_children` children are in HALT, force `effect` on the parent."
+#[derive(Clone, Copy, Debug)]
+pub struct PropagationRule {
+    pub min_halted_children: usize,
+    pub effect: Decision,
+}
+
+#[derive(Debug)]
+pub enum HierarchyError {
+    UnknownNode(String),
+    Cycle(String),
+}
+
+struct Node {
+    parent: Option<String>,
+    own: Decision,
+    rules: Vec<PropagationRule>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Propagated {
+    pub decision: Decision,
+    // e.g. "HALT_PROPAGATED wellpad-3 > field-1 > region-north"
+    pub reason_codes: Vec<String>,
+}
+
+pub struct Hierarchy {
+    nodes: BTreeMap<String, Node>,
+}
+
+impl Hierarchy {
+    pub fn new() -> Self {
+        Hierarchy { nodes: BTreeMap::new() }
+    }
+
+    pub fn add_node(&mut self, id: &str, parent: Option<&str>) -> Result<(), HierarchyError> {
+        if let Some(p) = parent {
+            if !self.nodes.contains_key(p) {
+                return Err(HierarchyError::UnknownNode(p.to_string()));
+            }
+            if p == id || self.ancestors(p).iter().any(|a| a == id) {
+                return Err(HierarchyError::Cycle(id.to_string()));
+            }
+        }
+        self.nodes.insert(
+            id.to_string(),
+            Node { parent: parent.map(str::to_string), own: Decision::HALT, rules: Vec::new() },
+        );
+        Ok(())
+    }
+
+    // Rules file: one `<node> <min_halted_children> <caution|halt>` per line, `#` comments.
+    pub fn load_rules(&mut self, text: &str) -> Result<(), String> {
+        for (n, line) in text.lines().enumerate().map(|(n, l)| (n + 1, l.trim())).filter(|(_, l)| !l.is_empty() && !l.starts_with('#')) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [id, min, effect] = fields[..] else {
+                return Err(format!("line {}: expected <node> <min_halted_children> <caution|halt>", n));
+            };
+            let min_halted_children = min.parse().ok().filter(|m| *m > 0).ok_or_else(|| format!("line {}: {:?} is not a positive count", n, min))?;
+            let effect = match effect.to_ascii_lowercase().as_str() {
+                "caution" => Decision::CAUTION,
+                "halt" => Decision::HALT,
+                _ => return Err(format!("line {}: effect {:?} is not caution or halt", n, effect)),
+            };
+            self.add_rule(id, PropagationRule { min_halted_children, effect }).map_err(|e| format!("line {}: {:?}", n, e))?;
+        }
+        Ok(())
+    }
+
+    pub fn add_rule(&mut self, id: &str, rule: PropagationRule) -> Result<(), HierarchyError> {
+        let node = self.nodes.get_mut(id).ok_or_else(|| HierarchyError::UnknownNode(id.to_string()))?;
+        node.rules.push(rule);
+        Ok(())
+    }
+
+    pub fn set_decision(&mut self, id: &str, decision: Decision) -> Result<(), HierarchyError> {
+        let node = self.nodes.get_mut(id).ok_or_else(|| HierarchyError::UnknownNode(id.to_string()))?;
+        node.own = decision;
+        Ok(())
+    }
+
+    // Effective decision of `id`: its own decision, made more severe by any rule
+    // that fires on its (recursively effective) children.
+    pub fn evaluate(&self, id: &str) -> Result<Propagated, HierarchyError> {
+        let node = self.nodes.get(id).ok_or_else(|| HierarchyError::UnknownNode(id.to_string()))?;
+        let mut halted = Vec::new();
+        for child in self.children(id) {
+            let p = self.evaluate(&child)?;
+            if p.decision == Decision::HALT {
+                halted.push((child, p.reason_codes));
+            }
+        }
+        let mut decision = node.own;
+        let mut reason_codes = Vec::new();
+        for rule in &node.rules {
+            if halted.len() >= rule.min_halted_children.max(1) && rule.effect.severity() > decision.severity() {
+                decision = rule.effect;
+            }
+        }
+        if decision != node.own {
+            for (child, child_codes) in &halted {
+                if child_codes.is_empty() {
+                    reason_codes.push(format!("{:?}_PROPAGATED {} > {}", decision, child, id));
+                } else {
+                    for code in child_codes {
+                        reason_codes.push(format!("{} > {}", code, id));
+                    }
+                }
+            }
+        }
+        Ok(Propagated { decision, reason_codes })
+    }
+
+    fn children(&self, id: &str) -> Vec<String> {
+        self.nodes
+            .iter()
+            .filter(|(_, n)| n.parent.as_deref() == Some(id))
+            .map(|(k, _)| k.clone())
+            .collect()
+    }
+
+    fn ancestors(&self, id: &str) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut cur = self.nodes.get(id).and_then(|n| n.parent.clone());
+        while let Some(p) = cur {
+            cur = self.nodes.get(&p).and_then(|n| n.parent.clone());
+            out.push(p);
+        }
+        out
+    }
+}
+
+impl Default for Hierarchy {
+    fn default() -> Self {
+        Hierarchy::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // enterprise > field-1 > wellpad-1..3, enterprise > field-2 > wellpad-4
+    fn oilfield() -> Hierarchy {
+        let mut h = Hierarchy::default();
+        h.add_node("enterprise", None).unwrap();
+        for (id, parent) in [("field-1", "enterprise"), ("field-2", "enterprise"), ("wellpad-1", "field-1"), ("wellpad-2", "field-1"), ("wellpad-3", "field-1"), ("wellpad-4", "field-2")] {
+            h.add_node(id, Some(parent)).unwrap();
+        }
+        for id in ["enterprise", "field-1", "field-2", "wellpad-1", "wellpad-2", "wellpad-3", "wellpad-4"] {
+            h.set_decision(id, Decision::GO).unwrap();
+        }
+        h.load_rules("# two halted wellpads halt the field; one cautions it\nfield-1 2 halt\nfield-1 1 caution\nenterprise 1 caution").unwrap();
+        h
+    }
+
+    #[test]
+    fn one_halted_child_cautions_the_parent() {
+        let mut h = oilfield();
+        h.set_decision("wellpad-3", Decision::HALT).unwrap();
+        let field = h.evaluate("field-1").unwrap();
+        assert_eq!(field.decision, Decision::CAUTION);
+        assert_eq!(field.reason_codes, ["CAUTION_PROPAGATED wellpad-3 > field-1"]);
+        // A cautioned field is not a halted child: the enterprise stays GO.
+        assert_eq!(h.evaluate("enterprise").unwrap().decision, Decision::GO);
+    }
+
+    #[test]
+    fn two_halted_wellpads_halt_the_field_and_the_chain_is_recorded() {
+        let mut h = oilfield();
+        h.set_decision("wellpad-1", Decision::HALT).unwrap();
+        h.set_decision("wellpad-3", Decision::HALT).unwrap();
+        assert_eq!(h.evaluate("field-1").unwrap().decision, Decision::HALT);
+        let top = h.evaluate("enterprise").unwrap();
+        assert_eq!(top.decision, Decision::CAUTION);
+        assert_eq!(top.reason_codes, ["HALT_PROPAGATED wellpad-1 > field-1 > enterprise", "HALT_PROPAGATED wellpad-3 > field-1 > enterprise"]);
+    }
+
+    #[test]
+    fn rules_never_relax_a_decision() {
+        let mut h = oilfield();
+        h.set_decision("field-2", Decision::HALT).unwrap();
+        h.set_decision("wellpad-4", Decision::HALT).unwrap();
+        let field = h.evaluate("field-2").unwrap();
+        assert_eq!(field.decision, Decision::HALT);
+        assert!(field.reason_codes.is_empty());
+    }
+
+    #[test]
+    fn unknown_nodes_cycles_and_bad_rules_are_refused() {
+        let mut h = oilfield();
+        assert!(matches!(h.add_node("wellpad-9", Some("field-9")), Err(HierarchyError::UnknownNode(_))));
+        assert!(matches!(h.add_node("enterprise", Some("wellpad-1")), Err(HierarchyError::Cycle(_))));
+        assert!(h.evaluate("field-9").is_err());
+        assert!(h.load_rules("field-9 1 halt").is_err());
+        assert!(h.load_rules("field-1 0 halt").is_err());
+        assert!(h.load_rules("field-1 1 go").is_err());
+        assert!(h.load_rules("field-1 halt").is_err());
+    }
+}
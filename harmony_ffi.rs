@@ -0,0 +1,162 @@
+//! Harmony_FFI.rs - Stable C ABI over core::harmony for legacy DCS/HSM firmware
+//!
+//! This is the only crate root that does not forbid unsafe: a C ABI cannot be
+//! expressed without raw pointers. Every unsafe block is confined to pointer
+//! validation at the boundary; core and the kernel keep `forbid(unsafe_code)`.
+//! Header: include/harmony.h (cbindgen --config cbindgen.toml -o include/harmony.h).
+#![deny(unsafe_op_in_unsafe_fn)]
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+use std::slice;
+
+mod clock_sync;
+mod core;
+mod decision;
+mod decision_kernel;
+mod plugin;
+mod rbac;
+use crate::core::harmony::{self, Decision};
+
+pub const HARMONY_GO: c_int = 0;
+pub const HARMONY_CAUTION: c_int = 1;
+pub const HARMONY_HALT: c_int = 2;
+pub const HARMONY_ERR_ARG: c_int = -1;
+
+/// Opaque to C.
+pub struct HarmonyContext {
+    ctx: harmony::HarmonyContext,
+    last_report: CString,
+}
+
+/// Creates a context. Returns NULL if `weights` is NULL, `n` is 0, any weight is
+/// zero, negative or non-finite, the weights do not sum to 1, or `threshold` is
+/// not in (0, 1]. The CAUTION band is the engine default below `threshold`.
+/// Free with `harmony_context_free`.
+///
+/// # Safety
+/// `weights` must point to `n` readable doubles.
+#[no_mangle]
+pub unsafe extern "C" fn harmony_context_new(weights: *const f64, n: usize, threshold: f64) -> *mut HarmonyContext {
+    if weights.is_null() || n == 0 {
+        return ptr::null_mut();
+    }
+    // SAFETY: non-null, and the caller guarantees `n` readable doubles.
+    let w = unsafe { slice::from_raw_parts(weights, n) };
+    // The same checks every domain engine's context goes through (weights, threshold range).
+    let Ok(ctx) = harmony::HarmonyContext::builder().weights(w.to_vec()).channels(n).threshold(threshold).build() else {
+        return ptr::null_mut();
+    };
+    Box::into_raw(Box::new(HarmonyContext { ctx, last_report: CString::default() }))
+}
+
+/// # Safety
+/// `ctx` must be NULL or a pointer from `harmony_context_new` not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn harmony_context_free(ctx: *mut HarmonyContext) {
+    if !ctx.is_null() {
+        // SAFETY: produced by Box::into_raw in harmony_context_new and freed once.
+        drop(unsafe { Box::from_raw(ctx) });
+    }
+}
+
+/// Evaluates one cycle. Returns HARMONY_GO / HARMONY_CAUTION / HARMONY_HALT, or
+/// HARMONY_ERR_ARG if a pointer is NULL or `n` does not match the weight count.
+///
+/// # Safety
+/// `ctx` must be a live context; `scores` must point to `n` readable doubles.
+#[no_mangle]
+pub unsafe extern "C" fn harmony_evaluate(ctx: *mut HarmonyContext, scores: *const f64, n: usize, ch: bool) -> c_int {
+    // SAFETY: caller guarantees `ctx` is live and not aliased during the call.
+    let Some(ctx) = (unsafe { ctx.as_mut() }) else { return HARMONY_ERR_ARG };
+    if scores.is_null() || n != ctx.ctx.weights().len() {
+        return HARMONY_ERR_ARG;
+    }
+    // SAFETY: non-null, and the caller guarantees `n` readable doubles.
+    let s = unsafe { slice::from_raw_parts(scores, n) };
+    let eval = harmony::evaluate(&ctx.ctx, s, ch);
+    let (mu, decision) = (eval.mu, eval.decision);
+    let json = format!(
+        "{{\"mu\":{},\"ch\":{},\"threshold\":{},\"caution_threshold\":{},\"decision\":\"{:?}\"}}",
+        if mu.is_finite() { mu.to_string() } else { "null".to_string() },
+        ch,
+        ctx.ctx.threshold,
+        ctx.ctx.caution_threshold,
+        decision
+    );
+    ctx.last_report = CString::new(json).unwrap_or_default();
+    match decision {
+        Decision::GO => HARMONY_GO,
+        Decision::CAUTION => HARMONY_CAUTION,
+        Decision::HALT => HARMONY_HALT,
+    }
+}
+
+/// JSON report of the last evaluation ("" before the first). The pointer is owned
+/// by the context and valid until the next `harmony_evaluate` or `harmony_context_free`.
+///
+/// # Safety
+/// `ctx` must be NULL or a live context.
+#[no_mangle]
+pub unsafe extern "C" fn harmony_last_report_json(ctx: *const HarmonyContext) -> *const c_char {
+    // SAFETY: caller guarantees `ctx` is NULL or live.
+    match unsafe { ctx.as_ref() } {
+        Some(ctx) => ctx.last_report.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    fn new(weights: &[f64], threshold: f64) -> *mut HarmonyContext {
+        // SAFETY: `weights` is a live slice of `weights.len()` doubles.
+        unsafe { harmony_context_new(weights.as_ptr(), weights.len(), threshold) }
+    }
+
+    fn evaluate(ctx: *mut HarmonyContext, scores: &[f64], ch: bool) -> c_int {
+        // SAFETY: `ctx` comes from `new` and is freed only at the end of each test.
+        unsafe { harmony_evaluate(ctx, scores.as_ptr(), scores.len(), ch) }
+    }
+
+    #[test]
+    fn rejects_what_the_builder_rejects() {
+        for (weights, threshold) in [
+            (&[0.5, 0.5][..], 0.0),
+            (&[0.5, 0.5], 1.5),
+            (&[0.5, 0.5], f64::NAN),
+            (&[1.0, 0.0], 0.9995),
+            (&[1.5, -0.5], 0.9995),
+            (&[0.5, f64::INFINITY], 0.9995),
+            (&[0.5, 0.4], 0.9995),
+            (&[], 0.9995),
+        ] {
+            assert!(new(weights, threshold).is_null(), "{:?} {}", weights, threshold);
+        }
+        // SAFETY: a NULL pointer with any count is rejected before it is read.
+        assert!(unsafe { harmony_context_new(ptr::null(), 2, 0.9995) }.is_null());
+    }
+
+    #[test]
+    fn returns_the_tiered_decision() {
+        let ctx = new(&[0.5, 0.5], 0.9995);
+        assert!(!ctx.is_null());
+        assert_eq!(evaluate(ctx, &[1.0, 1.0], true), HARMONY_GO);
+        assert_eq!(evaluate(ctx, &[0.999, 0.999], true), HARMONY_CAUTION);
+        // SAFETY: `ctx` is live; the report it returns is read before the next evaluation.
+        let report = unsafe { CStr::from_ptr(harmony_last_report_json(ctx)) }.to_str().unwrap().to_string();
+        assert!(report.contains("\"caution_threshold\":0.998") && report.ends_with("\"decision\":\"CAUTION\"}"), "{}", report);
+        assert_eq!(evaluate(ctx, &[0.5, 0.5], true), HARMONY_HALT);
+        assert_eq!(evaluate(ctx, &[1.0, 1.0], false), HARMONY_HALT);
+        assert_eq!(evaluate(ctx, &[1.0], true), HARMONY_ERR_ARG);
+        // SAFETY: NULL arguments are rejected before they are read; `ctx` is freed once.
+        unsafe {
+            assert_eq!(harmony_evaluate(ptr::null_mut(), [1.0, 1.0].as_ptr(), 2, true), HARMONY_ERR_ARG);
+            assert_eq!(harmony_evaluate(ctx, ptr::null(), 2, true), HARMONY_ERR_ARG);
+            assert!(harmony_last_report_json(ptr::null()).is_null());
+            harmony_context_free(ctx);
+        }
+    }
+}
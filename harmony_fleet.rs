@@ -0,0 +1,149 @@
+//! Harmony_Fleet.rs - Headquarters rollup of site decision streams per business line (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::time::Duration;
+
+mod clock_sync;
+mod crypto_policy;
+mod decision;
+mod decision_kernel;
+mod decision_stream;
+mod fleet_rollup;
+mod halt_propagation;
+mod region_sync;
+mod sealed_config;
+use clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+use decision::{lease_for, Decision, DecisionRecord};
+use decision_stream::{RecordSigner, RecordVerifier};
+use fleet_rollup::{FleetDecision, FleetRollup, Scope, DEFAULT_FLEET_THRESHOLDS};
+use halt_propagation::Hierarchy;
+use region_sync::RegionSync;
+
+// Sites decide at 1-10 Hz; headquarters rolls up once a second.
+const ROLLUP_PERIOD: Duration = Duration::from_secs(1);
+// A record older than this is a replay or a stalled link; the rollup's own staleness rule
+// then counts the site as halted.
+const SITE_RECORD_MAX_AGE: Duration = Duration::from_secs(5);
+// Active headquarters in other regions must confirm within two rollups of ours.
+const REGION_LATENCY_BOUND: Duration = Duration::from_secs(2);
+
+// HARMONY_FLEET_SITES names the site table (FleetRollup::load_sites).
+fn fleet() -> Result<FleetRollup, String> {
+    let path = std::env::var("HARMONY_FLEET_SITES").unwrap_or_else(|_| "/etc/harmony/fleet_sites".into());
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("fleet sites {}: {}", path, e))?;
+    let mut fleet = FleetRollup::new(DEFAULT_FLEET_THRESHOLDS);
+    fleet.load_sites(&text).map_err(|e| format!("fleet sites {}: {}", path, e))?;
+    Ok(fleet)
+}
+
+// enterprise > regions > sites, with the propagation rules from HARMONY_FLEET_RULES
+// (Hierarchy::load_rules) naming any of those nodes.
+fn hierarchy(fleet: &FleetRollup) -> Result<Hierarchy, String> {
+    let mut tree = Hierarchy::default();
+    tree.add_node("enterprise", None).map_err(|e| format!("hierarchy: {:?}", e))?;
+    let regions = fleet.regions();
+    for region in &regions {
+        tree.add_node(region, Some("enterprise")).map_err(|e| format!("hierarchy: region {}: {:?}", region, e))?;
+    }
+    for (id, site) in fleet.sites() {
+        if id == "enterprise" || regions.iter().any(|r| r == id) {
+            return Err(format!("hierarchy: site {} shadows a region or the enterprise", id));
+        }
+        tree.add_node(id, Some(&site.region)).map_err(|e| format!("hierarchy: site {}: {:?}", id, e))?;
+    }
+    let path = std::env::var("HARMONY_FLEET_RULES").unwrap_or_else(|_| "/etc/harmony/fleet_rules".into());
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("fleet rules {}: {}", path, e))?;
+    tree.load_rules(&text).map_err(|e| format!("fleet rules {}: {}", path, e))?;
+    Ok(tree)
+}
+
+// HARMONY_REGION names this headquarters' region, HARMONY_PEER_REGIONS the other active ones
+// (comma-separated); their enterprise records are verified like site records.
+fn region_sync() -> RegionSync {
+    let local = std::env::var("HARMONY_REGION").unwrap_or_else(|_| "hq".into());
+    let peers = std::env::var("HARMONY_PEER_REGIONS").unwrap_or_default();
+    let peers: Vec<&str> = peers.split(',').map(str::trim).filter(|r| !r.is_empty()).collect();
+    RegionSync::new(&local, &peers, REGION_LATENCY_BOUND)
+}
+
+fn as_decision(decision: FleetDecision) -> Decision {
+    match decision {
+        FleetDecision::FLEET_GO => Decision::GO,
+        FleetDecision::FLEET_CAUTION => Decision::CAUTION,
+        FleetDecision::FLEET_HALT => Decision::HALT,
+    }
+}
+
+// Site and peer-region decisions are verified against HARMONY_DECISION_PEERS (one `<node_id>
+// <hex ed25519 public key>` per line); an unverified site is never heard and rolls up as halted.
+fn site_verifier() -> Result<RecordVerifier, String> {
+    let peers_path = std::env::var("HARMONY_DECISION_PEERS").unwrap_or_else(|_| "/etc/harmony/decision_peers".into());
+    let trusted = std::fs::read_to_string(&peers_path).map_err(|e| e.to_string()).and_then(|t| sealed_config::parse_trusted_keys(&t)).map_err(|e| format!("decision peers {}: {}", peers_path, e))?;
+    Ok(RecordVerifier::new(trusted, SITE_RECORD_MAX_AGE))
+}
+
+#[tokio::main]
+async fn main() {
+    let setup = || -> Result<_, String> {
+        let fleet = fleet()?;
+        let tree = hierarchy(&fleet)?;
+        let regions = region_sync();
+        let node_id = format!("fleet-{}", regions.local_region());
+        let signer = RecordSigner::from_env(&node_id)?;
+        Ok((fleet, tree, site_verifier()?, regions, signer, node_id))
+    };
+    let (mut fleet, mut tree, mut sites, mut regions, mut signer, node_id) = match setup() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Fleet: {}", e);
+            std::process::exit(2);
+        }
+    };
+    let mut clock = ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS);
+    let mut record = DecisionRecord::new(&node_id, 0, 0.0, false, Decision::HALT, clock.status());
+    for cycle in 1u64.. {
+        clock.refresh().await;
+        while let Some(record) = recv_site_decision().await {
+            match sites.verify(&record) {
+                Ok(_) => fleet.ingest(record),
+                Err(e) => eprintln!("Fleet: decision from {} refused: {:?}", record.node_id, e),
+            }
+        }
+        for line in fleet.business_lines() {
+            let status = fleet.rollup(Scope::BusinessLine(&line));
+            println!("Fleet[{}]: {:?} mu={:.6} halted={}/{}", line, status.decision, status.mu, status.halted, status.sites);
+            publish_fleet_status(&line, &status).await;
+        }
+        // Every node is known to the tree, so set_decision/evaluate cannot fail here.
+        for (id, _) in fleet.sites() {
+            let _ = tree.set_decision(id, fleet.site_decision(id));
+        }
+        for region in fleet.regions() {
+            let _ = tree.set_decision(&region, as_decision(fleet.rollup(Scope::Region(&region)).decision));
+        }
+        let global = fleet.rollup(Scope::Global);
+        let _ = tree.set_decision("enterprise", as_decision(global.decision));
+        let enterprise = tree.evaluate("enterprise").expect("enterprise node");
+        println!("Fleet: {:?} mu={:.6} halted={}/{} enterprise={:?}", global.decision, global.mu, global.halted, global.sites, enterprise.decision);
+        for code in &enterprise.reason_codes {
+            println!("Fleet: {}", code);
+        }
+        // Only a decision every active region has confirmed is acted on.
+        record.refresh(cycle, global.mu, enterprise.reason_codes.is_empty(), enterprise.decision, clock.status());
+        record.set_lease(lease_for(ROLLUP_PERIOD));
+        signer.sign(&mut record);
+        publish_region_decision(&record).await;
+        while let Some((region, peer)) = recv_region_decision().await {
+            match sites.verify(&peer) {
+                Ok(_) => regions.on_peer_decision(&region, peer),
+                Err(e) => eprintln!("Fleet: decision from region {} refused: {:?}", region, e),
+            }
+        }
+        let actionable = regions.actionable(&record);
+        if !actionable.unconfirmed.is_empty() {
+            eprintln!("Fleet: unconfirmed by {}", actionable.unconfirmed.join(", "));
+        }
+        println!("Fleet: actionable {:?} confirmed by [{}]", actionable.decision, actionable.confirmed_by.join(", "));
+        publish_enterprise_decision(actionable.decision).await;
+        tokio::time::sleep(ROLLUP_PERIOD).await;
+    }
+}
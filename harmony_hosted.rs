@@ -0,0 +1,148 @@
+//! Harmony_Hosted.rs - Multi-tenant hosted harmony gating service (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+mod catalog;
+mod clock_sync;
+mod core;
+mod crypto_policy;
+mod decision;
+mod decision_bus;
+mod decision_kernel;
+mod decision_stream;
+mod diagnostics;
+mod ingest;
+mod mtls;
+mod plugin;
+mod rbac;
+mod replay_guard;
+mod sealed_config;
+mod tenancy;
+mod units;
+use clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+use decision_bus::{DecisionBus, DecisionTransport};
+use diagnostics::Diagnostics;
+use mtls::{MtlsLayer, MtlsPaths, SpiffeId};
+use tenancy::{TenantId, TenantRegistry};
+
+// Tenants are evaluated once a second; a pushed score older than five counts as MIN_SCORE.
+const EVALUATION_PERIOD: Duration = Duration::from_secs(1);
+const SCORE_MAX_AGE: Duration = Duration::from_secs(5);
+const CLOCK_REFRESH_CYCLES: u64 = 5;
+const MTLS_POLL_CYCLES: u64 = 60;
+const CERT_MIN_REMAINING: Duration = Duration::from_secs(24 * 3600);
+
+// A score or condition pushed over the mTLS listener; `peer` is the verified client identity,
+// the only thing tenancy attributes it by.
+pub struct TenantPush {
+    pub peer: SpiffeId,
+    pub seq: u64,
+    pub timestamp_ms: u64,
+    pub nonce: u64,
+    pub value: f64,
+}
+
+// Every tenant's records go out on its own MQTT topic (OT), gRPC stream (IT) and SSE channel
+// (dashboards), through one bus so all three carry the same records in the same order.
+struct Mqtt(String);
+struct Grpc(String);
+struct Sse(String);
+
+impl DecisionTransport for Mqtt {
+    fn name(&self) -> &str { "mqtt" }
+    fn send(&mut self, seq: u64, payload: &str) -> Result<(), String> { mqtt_publish(&self.0, seq, payload) }
+}
+
+impl DecisionTransport for Grpc {
+    fn name(&self) -> &str { "grpc" }
+    fn send(&mut self, seq: u64, payload: &str) -> Result<(), String> { grpc_publish(&self.0, seq, payload) }
+}
+
+impl DecisionTransport for Sse {
+    fn name(&self) -> &str { "sse" }
+    fn send(&mut self, seq: u64, payload: &str) -> Result<(), String> { sse_publish(&self.0, seq, payload) }
+}
+
+fn tenant_bus(id: &str) -> DecisionBus {
+    let mut bus = DecisionBus::default();
+    bus.attach(Box::new(Mqtt(format!("harmony/{}/decision", id))));
+    bus.attach(Box::new(Grpc(format!("tenants/{}/decisions", id))));
+    bus.attach(Box::new(Sse(format!("{}/decisions", id))));
+    bus
+}
+
+// HARMONY_TENANT_ROOT holds one directory per tenant (TenantRegistry::load). A tenant that
+// fails to load is refused on its own; the others still start.
+fn tenants() -> Result<(TenantRegistry, Vec<TenantId>), String> {
+    let root = PathBuf::from(std::env::var("HARMONY_TENANT_ROOT").unwrap_or_else(|_| "/var/lib/harmony/tenants".into()));
+    let trust_domain = std::env::var("HARMONY_TRUST_DOMAIN").map_err(|_| "HARMONY_TRUST_DOMAIN not set".to_string())?;
+    let mut registry = TenantRegistry::new(&root, &trust_domain, SCORE_MAX_AGE, EVALUATION_PERIOD);
+    let mut loaded = Vec::new();
+    let dirs = std::fs::read_dir(&root).map_err(|e| format!("tenant root {}: {}", root.display(), e))?;
+    for dir in dirs.filter_map(Result::ok).filter(|d| d.path().is_dir()) {
+        let id = dir.file_name().to_string_lossy().into_owned();
+        match registry.load(&id, tenant_bus(&id)).and_then(|_| TenantId::parse(&id)) {
+            Ok(id) => loaded.push(id),
+            Err(e) => eprintln!("Hosted: tenant {} refused: {}", id, e),
+        }
+    }
+    if loaded.is_empty() {
+        return Err(format!("no tenant under {} loaded", root.display()));
+    }
+    Ok((registry, loaded))
+}
+
+// The listener's own certificate from HARMONY_MTLS_DIR; no mTLS, no attributable pushes.
+fn mtls_layer() -> Result<MtlsLayer, String> {
+    let dir = PathBuf::from(std::env::var("HARMONY_MTLS_DIR").unwrap_or_else(|_| "/etc/harmony/tls".into()));
+    let paths = MtlsPaths { cert_chain: dir.join("cert.pem"), private_key: dir.join("key.pem"), trust_bundle: dir.join("bundle.pem") };
+    MtlsLayer::new(paths, CERT_MIN_REMAINING)
+}
+
+#[tokio::main]
+async fn main() {
+    let ((mut registry, loaded), mtls) = match tenants().and_then(|t| Ok((t, mtls_layer()?))) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Hosted: {}", e);
+            std::process::exit(2);
+        }
+    };
+    for id in &loaded {
+        println!("Hosted: tenant {} config {}", id.as_str(), registry.tenant(id).map_or("", |t| &t.config_hash));
+    }
+    // Per-tenant mu, decision and ingest counters on /metrics; enabled only when a token hash is provisioned.
+    let metrics = Arc::new(Mutex::new(String::new()));
+    if let Some(token_sha256) = std::env::var("HARMONY_DIAG_TOKEN_SHA256").ok().and_then(|h| sealed_config::unhex(h.trim())).and_then(|b| b.try_into().ok()) {
+        let hashes: Vec<&str> = loaded.iter().filter_map(|id| registry.tenant(id)).map(|t| t.config_hash.as_str()).collect();
+        let diag = Diagnostics::new(&sealed_config::hex(&sealed_config::config_digest(hashes.join("\n").as_bytes())));
+        let exported = metrics.clone();
+        diag.lock().unwrap().attach_metrics(Box::new(move || exported.lock().unwrap().clone()));
+        let addr = std::env::var("HARMONY_DIAG_ADDR").unwrap_or_else(|_| "0.0.0.0:8087".into());
+        diagnostics::serve(&addr, diag, token_sha256).expect("bind diagnostics listener");
+    }
+    let mut clock = ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS);
+    for cycle in 0u64.. {
+        let start = Instant::now();
+        if cycle.is_multiple_of(CLOCK_REFRESH_CYCLES) {
+            clock.refresh().await;
+        }
+        if cycle.is_multiple_of(MTLS_POLL_CYCLES) {
+            match mtls.poll_rotation() {
+                Ok(true) => println!("Hosted: listener certificate rotated"),
+                Ok(false) => {}
+                Err(e) => eprintln!("Hosted: certificate rotation failed: {}", e),
+            }
+        }
+        while let Some(push) = recv_tenant_push(mtls.server_config()).await {
+            if let Err(e) = registry.ingest(&push.peer, push.seq, push.timestamp_ms, push.nonce, push.value) {
+                eprintln!("Hosted: push from {} refused: {}", push.peer.path, e);
+            }
+        }
+        registry.evaluate_all(&clock.status());
+        *metrics.lock().unwrap() = registry.metrics_text();
+        tokio::time::sleep(EVALUATION_PERIOD.saturating_sub(start.elapsed())).await;
+    }
+}
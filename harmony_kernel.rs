@@ -0,0 +1,18 @@
+//! Harmony_Kernel.rs - no_std + alloc library root for the decision kernel (forbid unsafe)
+//!
+//! Bare-metal RTUs and flight processors build this root with default features off:
+//!     rustc --edition 2021 --crate-type rlib --extern libm=... \
+//!         --check-cfg 'cfg(kani)' --check-cfg 'cfg(feature, values("std"))' harmony_kernel.rs
+//! (Cargo builds get the same cfg declarations from Cargo.toml and build.rs.)
+//! Hosted builds enable `std` (the default), which drops the libm dependency. The
+//! tokio monitor loops, transports and tooling stay std-only and are not reachable
+//! from here.
+#![cfg_attr(not(feature = "std"), no_std)]
+#![forbid(unsafe_code)]
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+pub mod decision_kernel;
+
+pub use decision_kernel::{clamp_score, decide, decide_batch, weighted_mu, Decision, Hysteresis, MIN_SCORE};
@@ -0,0 +1,61 @@
+//! Harmony_Node.rs - napi-rs binding for the compliance backoffice (forbid unsafe)
+//!
+//! Build: napi build --release (emits harmony_node.*.node and index.d.ts).
+#![forbid(unsafe_code)]
+use napi::{Error, Result};
+use napi_derive::napi;
+
+mod anchor;
+mod decision_kernel;
+use anchor::parse_anchor;
+use decision_kernel::{decide_batch, weighted_mu, Decision, HARMONY_THRESHOLD};
+
+
+fn decision_name(d: Decision) -> String {
+    match d {
+        Decision::GO => "GO",
+        Decision::CAUTION => "CAUTION",
+        Decision::HALT => "HALT",
+    }
+    .to_string()
+}
+
+#[napi(object)]
+pub struct AnchorFrame {
+    pub timestamp_ms: i64,
+    pub ch: bool,
+    pub weights: Vec<f64>,
+    pub scores: Vec<f64>,
+    pub mu: f64,
+}
+
+// One decision per row; `ch` defaults to all-true and `threshold` to HARMONY_THRESHOLD.
+#[napi]
+pub fn evaluate_batch(weights: Vec<f64>, rows: Vec<Vec<f64>>, ch: Option<Vec<bool>>, threshold: Option<f64>) -> Result<Vec<String>> {
+    let ch = ch.unwrap_or_else(|| vec![true; rows.len()]);
+    if ch.len() != rows.len() {
+        return Err(Error::from_reason("ch must have one entry per row"));
+    }
+    if let Some(row) = rows.iter().find(|r| r.len() != weights.len()) {
+        return Err(Error::from_reason(format!("expected {} scores, got {}", weights.len(), row.len())));
+    }
+    let cycles: Vec<(Vec<f64>, bool)> = rows.into_iter().zip(ch).collect();
+    Ok(decide_batch(&weights, &cycles, threshold.unwrap_or(HARMONY_THRESHOLD)).into_iter().map(decision_name).collect())
+}
+
+// Recorded cycles with mu recomputed, for report tables.
+#[napi]
+pub fn read_anchor(path: String) -> Result<Vec<AnchorFrame>> {
+    let text = std::fs::read_to_string(&path).map_err(|e| Error::from_reason(format!("{}: {}", path, e)))?;
+    let frames = parse_anchor(&text).map_err(|e| Error::from_reason(format!("{}: {}", path, e)))?;
+    Ok(frames
+        .into_iter()
+        .map(|f| AnchorFrame {
+            timestamp_ms: f.timestamp_ms as i64,
+            ch: f.ch,
+            mu: weighted_mu(&f.weights, &f.scores),
+            weights: f.weights,
+            scores: f.scores,
+        })
+        .collect())
+}
@@ -0,0 +1,161 @@
+//! Harmony_Operator.rs - Renders HarmonyDomain resources into engine ConfigMaps/Deployments (forbid unsafe)
+//!
+//! `render` reads one HarmonyDomain (JSON, as from `kubectl get hd NAME -o json`) on stdin and
+//! prints a kubectl-appliable List. `watch` reconciles every HarmonyDomain in the cluster on an
+//! interval through kubectl, so the operator needs no client library beyond the kubeconfig.
+#![forbid(unsafe_code)]
+use std::io::{Read, Write};
+use std::process::{self, Command, Stdio};
+use std::time::Duration;
+use std::{env, io, thread};
+
+use serde_json::{json, Value};
+
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(30);
+// As core::harmony::WEIGHT_SUM_TOLERANCE, which the engine applies to the same weights.
+const WEIGHT_SUM_TOLERANCE: f64 = 1e-9;
+
+struct DomainSpec {
+    name: String,
+    namespace: String,
+    engine: String,
+    image: String,
+    replicas: u64,
+    threshold: f64,
+    weights: Vec<f64>,
+    probe_port: u64,
+}
+
+fn parse_domain(hd: &Value) -> Result<DomainSpec, String> {
+    let meta = &hd["metadata"];
+    let spec = &hd["spec"];
+    let name = meta["name"].as_str().ok_or("metadata.name missing")?.to_string();
+    let field = |k: &str| spec[k].as_str().map(str::to_string).ok_or_else(|| format!("{}: spec.{} missing", name, k));
+    let weights: Vec<f64> = spec["weights"]
+        .as_array()
+        .ok_or_else(|| format!("{}: spec.weights missing", name))?
+        .iter()
+        .map(|w| w.as_f64().filter(|w| w.is_finite() && *w >= 0.0))
+        .collect::<Option<_>>()
+        .ok_or_else(|| format!("{}: spec.weights must be non-negative numbers", name))?;
+    // The engine refuses these at startup (HarmonyContext::build); refusing them here keeps a
+    // bad resource from rolling out a Deployment that crash-loops.
+    let sum: f64 = weights.iter().sum();
+    if weights.is_empty() || (sum - 1.0).abs() > WEIGHT_SUM_TOLERANCE {
+        return Err(format!("{}: spec.weights must be non-empty and sum to 1 (sum {})", name, sum));
+    }
+    let threshold = spec["threshold"].as_f64().unwrap_or(0.9995);
+    if !(threshold > 0.0 && threshold <= 1.0) {
+        return Err(format!("{}: spec.threshold {} is outside (0, 1]", name, threshold));
+    }
+    Ok(DomainSpec {
+        namespace: meta["namespace"].as_str().unwrap_or("default").to_string(),
+        engine: field("engine")?,
+        image: field("image")?,
+        replicas: spec["replicas"].as_u64().unwrap_or(2),
+        threshold,
+        probe_port: spec["probePort"].as_u64().unwrap_or(8086),
+        weights,
+        name,
+    })
+}
+
+fn render(hd: &Value) -> Result<Value, String> {
+    let d = parse_domain(hd)?;
+    let owner = json!([{
+        "apiVersion": hd["apiVersion"], "kind": "HarmonyDomain",
+        "name": d.name, "uid": hd["metadata"]["uid"], "controller": true,
+    }]);
+    let labels = json!({ "app.kubernetes.io/name": "harmony-engine", "harmony.sr-aibridge.io/domain": d.name });
+    let weights = d.weights.iter().map(|w| w.to_string()).collect::<Vec<_>>().join(",");
+    let probe = |path: &str| json!({ "httpGet": { "path": path, "port": d.probe_port }, "periodSeconds": 2, "failureThreshold": 3 });
+    let config = json!({
+        "apiVersion": "v1", "kind": "ConfigMap",
+        "metadata": { "name": format!("{}-harmony", d.name), "namespace": d.namespace, "labels": labels, "ownerReferences": owner },
+        "data": { "HARMONY_THRESHOLD": d.threshold.to_string(), "HARMONY_WEIGHTS": weights },
+    });
+    let deployment = json!({
+        "apiVersion": "apps/v1", "kind": "Deployment",
+        "metadata": { "name": format!("{}-harmony", d.name), "namespace": d.namespace, "labels": labels, "ownerReferences": owner },
+        "spec": {
+            "replicas": d.replicas,
+            "selector": { "matchLabels": labels },
+            "template": {
+                "metadata": { "labels": labels },
+                "spec": { "containers": [{
+                    "name": "engine",
+                    "image": d.image,
+                    "command": [format!("/usr/local/bin/{}", d.engine)],
+                    "envFrom": [{ "configMapRef": { "name": format!("{}-harmony", d.name) } }],
+                    "env": [{ "name": "HARMONY_PROBE_ADDR", "value": format!("0.0.0.0:{}", d.probe_port) }],
+                    "ports": [{ "name": "probes", "containerPort": d.probe_port }],
+                    "readinessProbe": probe("/readyz"),
+                    "livenessProbe": probe("/livez"),
+                }]},
+            },
+        },
+    });
+    Ok(json!({ "apiVersion": "v1", "kind": "List", "items": [config, deployment] }))
+}
+
+fn kubectl_apply(manifest: &Value) -> Result<(), String> {
+    let mut child = Command::new("kubectl")
+        .args(["apply", "-f", "-"])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("kubectl: {}", e))?;
+    child.stdin.take().ok_or("kubectl stdin")?.write_all(manifest.to_string().as_bytes()).map_err(|e| e.to_string())?;
+    match child.wait() {
+        Ok(s) if s.success() => Ok(()),
+        Ok(s) => Err(format!("kubectl apply exited with {}", s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn reconcile_all() -> Result<(), String> {
+    let out = Command::new("kubectl")
+        .args(["get", "harmonydomains", "--all-namespaces", "-o", "json"])
+        .output()
+        .map_err(|e| format!("kubectl: {}", e))?;
+    if !out.status.success() {
+        return Err(String::from_utf8_lossy(&out.stderr).trim().to_string());
+    }
+    let list: Value = serde_json::from_slice(&out.stdout).map_err(|e| e.to_string())?;
+    for hd in list["items"].as_array().into_iter().flatten() {
+        // One bad resource must not stop the others from reconciling.
+        if let Err(e) = render(hd).and_then(|m| kubectl_apply(&m)) {
+            eprintln!("harmony_operator: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    match env::args().nth(1).as_deref() {
+        Some("render") => {
+            let mut input = String::new();
+            let result = io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| e.to_string())
+                .and_then(|_| serde_json::from_str::<Value>(&input).map_err(|e| e.to_string()))
+                .and_then(|hd| render(&hd));
+            match result {
+                Ok(m) => println!("{}", serde_json::to_string_pretty(&m).unwrap_or_default()),
+                Err(e) => {
+                    eprintln!("harmony_operator: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Some("watch") => loop {
+            if let Err(e) = reconcile_all() {
+                eprintln!("harmony_operator: {}", e);
+            }
+            thread::sleep(RECONCILE_INTERVAL);
+        },
+        _ => {
+            eprintln!("usage: harmony_operator render < harmonydomain.json | harmony_operator watch");
+            process::exit(2);
+        }
+    }
+}
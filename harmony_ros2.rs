@@ -0,0 +1,92 @@
+//! Harmony_ROS2.rs - ROS 2 node: topics in as scores, decision out on /harmony/decision (forbid unsafe)
+//!
+//! Configuration (environment):
+//!   HARMONY_SCORE_TOPICS  `/topic:weight,...`  std_msgs/Float64 score inputs
+//!   HARMONY_CH_TOPICS     `/topic,...`         std_msgs/Bool critical-hazard inputs (all must be true)
+//!   HARMONY_MAX_AGE_MS    staleness bound; a silent topic counts as MIN_SCORE / CH failed (default 500)
+#![forbid(unsafe_code)]
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use r2r::std_msgs::msg::{Bool, Float64, String as RosString};
+use r2r::QosProfile;
+
+mod decision_kernel;
+use decision_kernel::{decide, weighted_mu, Decision, HARMONY_THRESHOLD, MIN_SCORE};
+
+struct Input<T> {
+    latest: Option<(T, Instant)>,
+}
+
+fn parse_score_topics(spec: &str) -> Result<Vec<(String, f64)>, String> {
+    spec.split(',')
+        .filter(|s| !s.trim().is_empty())
+        .map(|item| {
+            let (topic, w) = item.trim().rsplit_once(':').ok_or_else(|| format!("expected topic:weight, got {}", item))?;
+            let w: f64 = w.parse().map_err(|_| format!("bad weight in {}", item))?;
+            Ok((topic.to_string(), w))
+        })
+        .collect()
+}
+
+fn fresh<T: Copy>(input: &Input<T>, max_age: Duration) -> Option<T> {
+    input.latest.filter(|(_, at)| at.elapsed() <= max_age).map(|(v, _)| v)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let score_topics = parse_score_topics(&std::env::var("HARMONY_SCORE_TOPICS").unwrap_or_default())?;
+    if score_topics.is_empty() {
+        return Err("HARMONY_SCORE_TOPICS is empty".into());
+    }
+    let ch_topics: Vec<String> = std::env::var("HARMONY_CH_TOPICS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect();
+    let max_age = Duration::from_millis(std::env::var("HARMONY_MAX_AGE_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(500));
+    let weights: Vec<f64> = score_topics.iter().map(|(_, w)| *w).collect();
+
+    let ctx = r2r::Context::create()?;
+    let mut node = r2r::Node::create(ctx, "harmony", "")?;
+    let scores: Arc<Mutex<Vec<Input<f64>>>> = Arc::new(Mutex::new(score_topics.iter().map(|_| Input { latest: None }).collect()));
+    let hazards: Arc<Mutex<Vec<Input<bool>>>> = Arc::new(Mutex::new(ch_topics.iter().map(|_| Input { latest: None }).collect()));
+
+    for (i, (topic, _)) in score_topics.iter().enumerate() {
+        let mut stream = node.subscribe::<Float64>(topic, QosProfile::default())?;
+        let scores = scores.clone();
+        tokio::spawn(async move {
+            while let Some(msg) = stream.next().await {
+                scores.lock().unwrap()[i].latest = Some((msg.data, Instant::now()));
+            }
+        });
+    }
+    for (i, topic) in ch_topics.iter().enumerate() {
+        let mut stream = node.subscribe::<Bool>(topic, QosProfile::default())?;
+        let hazards = hazards.clone();
+        tokio::spawn(async move {
+            while let Some(msg) = stream.next().await {
+                hazards.lock().unwrap()[i].latest = Some((msg.data, Instant::now()));
+            }
+        });
+    }
+    let publisher = node.create_publisher::<RosString>("/harmony/decision", QosProfile::default())?;
+
+    let mut tick = tokio::time::interval(Duration::from_millis(100));
+    loop {
+        node.spin_once(Duration::from_millis(5));
+        tick.tick().await;
+        let current: Vec<f64> = scores.lock().unwrap().iter().map(|s| fresh(s, max_age).unwrap_or(MIN_SCORE)).collect();
+        let ch = hazards.lock().unwrap().iter().all(|h| fresh(h, max_age) == Some(true));
+        let decision = decide(weighted_mu(&weights, &current), ch, true, HARMONY_THRESHOLD);
+        let data = match decision {
+            Decision::GO => "GO",
+            Decision::CAUTION => "CAUTION",
+            Decision::HALT => "HALT",
+        };
+        publisher.publish(&RosString { data: data.to_string() })?;
+    }
+}
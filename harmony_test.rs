@@ -0,0 +1,189 @@
+//! Harmony_Test.rs - Mock providers/actuators and a virtual-time TestEngine for integrators (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::decision_kernel::Decision;
+
+const MIN_SCORE: f64 = 1e-12;
+
+// Virtual clock: only moves when the test says so.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VirtualTime(pub Duration);
+
+// Replays a programmed sequence of readings, then repeats the last one.
+// `None` entries model a provider timeout.
+pub struct MockScoreProvider {
+    pub name: String,
+    script: VecDeque<Option<f64>>,
+    last: Option<f64>,
+    pub calls: u64,
+}
+
+impl MockScoreProvider {
+    pub fn constant(name: &str, value: f64) -> Self {
+        Self::scripted(name, &[Some(value)])
+    }
+
+    pub fn scripted(name: &str, script: &[Option<f64>]) -> Self {
+        MockScoreProvider { name: name.to_string(), script: script.iter().cloned().collect(), last: None, calls: 0 }
+    }
+
+    pub fn push(&mut self, reading: Option<f64>) {
+        self.script.push_back(reading);
+    }
+
+    pub fn sample(&mut self) -> Option<f64> {
+        self.calls += 1;
+        if let Some(next) = self.script.pop_front() {
+            self.last = next;
+        }
+        self.last
+    }
+}
+
+pub struct MockCondition {
+    pub name: String,
+    pub pass: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActuatorCommand {
+    pub at: VirtualTime,
+    pub command: String,
+}
+
+// Records every command instead of touching hardware.
+#[derive(Default)]
+pub struct MockActuator {
+    pub commands: Vec<ActuatorCommand>,
+}
+
+impl MockActuator {
+    pub fn command(&mut self, at: VirtualTime, command: &str) {
+        self.commands.push(ActuatorCommand { at, command: command.to_string() });
+    }
+
+    pub fn count(&self, command: &str) -> usize {
+        self.commands.iter().filter(|c| c.command == command).count()
+    }
+}
+
+pub struct TestEngine {
+    pub now: VirtualTime,
+    pub tick: Duration,
+    pub threshold: f64,
+    pub weights: Vec<f64>,
+    pub providers: Vec<MockScoreProvider>,
+    pub conditions: Vec<MockCondition>,
+    pub actuator: MockActuator,
+    pub safe_state_command: String,
+    pub decisions: Vec<(VirtualTime, Decision, f64)>,
+}
+
+impl TestEngine {
+    pub fn new(tick: Duration, threshold: f64, weights: Vec<f64>, safe_state_command: &str) -> Self {
+        TestEngine {
+            now: VirtualTime::default(),
+            tick,
+            threshold,
+            weights,
+            providers: Vec::new(),
+            conditions: Vec::new(),
+            actuator: MockActuator::default(),
+            safe_state_command: safe_state_command.to_string(),
+            decisions: Vec::new(),
+        }
+    }
+
+    pub fn condition_mut(&mut self, name: &str) -> Option<&mut MockCondition> {
+        self.conditions.iter_mut().find(|c| c.name == name)
+    }
+
+    // One evaluation cycle at the current virtual time, then advance by one tick.
+    // A timed-out provider contributes MIN_SCORE, as a live monitor must.
+    pub fn step(&mut self) -> Decision {
+        let mut log_sum = 0.0;
+        for (w, p) in self.weights.iter().zip(self.providers.iter_mut()) {
+            let s = p.sample().unwrap_or(MIN_SCORE);
+            log_sum += w * s.clamp(MIN_SCORE, 1.0).ln();
+        }
+        let mu = log_sum.exp();
+        let ch = self.conditions.iter().all(|c| c.pass);
+        let decision = if mu >= self.threshold && ch { Decision::GO } else { Decision::HALT };
+        if decision == Decision::HALT {
+            let cmd = self.safe_state_command.clone();
+            self.actuator.command(self.now, &cmd);
+        }
+        self.decisions.push((self.now, decision, mu));
+        self.now = VirtualTime(self.now.0 + self.tick);
+        decision
+    }
+
+    pub fn run_for(&mut self, span: Duration) -> Vec<Decision> {
+        let end = self.now.0 + span;
+        let mut out = Vec::new();
+        while self.now.0 < end {
+            out.push(self.step());
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decision_kernel::weighted_mu;
+
+    const TICK: Duration = Duration::from_millis(100);
+
+    fn engine(providers: Vec<MockScoreProvider>) -> TestEngine {
+        let mut engine = TestEngine::new(TICK, 0.95, vec![0.5, 0.3, 0.2], "close_valve");
+        engine.providers = providers;
+        engine.conditions.push(MockCondition { name: "door_closed".into(), pass: true });
+        engine
+    }
+
+    fn healthy() -> Vec<MockScoreProvider> {
+        vec![MockScoreProvider::constant("vibration", 0.99), MockScoreProvider::constant("pressure", 0.98), MockScoreProvider::constant("flow", 0.97)]
+    }
+
+    #[test]
+    fn healthy_providers_go_and_the_actuator_stays_idle() {
+        let mut e = engine(healthy());
+        assert_eq!(e.step(), Decision::GO);
+        assert!(e.actuator.commands.is_empty());
+        assert!((e.decisions[0].2 - weighted_mu(&[0.5, 0.3, 0.2], &[0.99, 0.98, 0.97])).abs() < 1e-15);
+    }
+
+    #[test]
+    fn a_provider_timeout_halts_and_commands_the_safe_state_at_that_virtual_time() {
+        let mut providers = healthy();
+        providers[1] = MockScoreProvider::scripted("pressure", &[Some(0.98), None, Some(0.98)]);
+        let mut e = engine(providers);
+        assert_eq!(e.run_for(TICK * 4), vec![Decision::GO, Decision::HALT, Decision::GO, Decision::GO]);
+        assert_eq!(e.actuator.commands, vec![ActuatorCommand { at: VirtualTime(TICK), command: "close_valve".into() }]);
+        assert_eq!(e.providers[1].calls, 4);
+    }
+
+    #[test]
+    fn a_failing_condition_halts_regardless_of_mu() {
+        let mut e = engine(healthy());
+        e.condition_mut("door_closed").unwrap().pass = false;
+        assert_eq!(e.step(), Decision::HALT);
+        e.condition_mut("door_closed").unwrap().pass = true;
+        assert_eq!(e.step(), Decision::GO);
+        assert!(e.condition_mut("missing").is_none());
+        assert_eq!(e.actuator.count("close_valve"), 1);
+    }
+
+    #[test]
+    fn virtual_time_moves_only_by_whole_ticks() {
+        let mut e = engine(healthy());
+        assert_eq!(e.run_for(Duration::from_millis(250)).len(), 3);
+        assert_eq!(e.now, VirtualTime(TICK * 3));
+        let times: Vec<_> = e.decisions.iter().map(|d| d.0).collect();
+        assert_eq!(times, vec![VirtualTime(Duration::ZERO), VirtualTime(TICK), VirtualTime(TICK * 2)]);
+        assert!(e.run_for(Duration::ZERO).is_empty());
+    }
+}
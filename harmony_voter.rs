@@ -0,0 +1,55 @@
+//! Harmony_Voter.rs - MooN voter publishing one decision for redundant monitor replicas (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::time::Duration;
+
+mod clock_sync;
+mod crypto_policy;
+mod decision;
+mod decision_kernel;
+mod decision_stream;
+mod replica_voter;
+mod sealed_config;
+use decision_stream::RecordVerifier;
+use replica_voter::{ReplicaVoter, SplitVotePolicy};
+
+// Replicas decide at 10 Hz; a record older than this cannot vote anyway.
+const REPLICA_RECORD_MAX_AGE: Duration = Duration::from_secs(2);
+
+// HARMONY_SPLIT_VOTE: `halt` (default) or `hold-last=<cycles>`.
+fn split_vote_policy() -> Result<SplitVotePolicy, String> {
+    match std::env::var("HARMONY_SPLIT_VOTE") {
+        Err(_) => Ok(SplitVotePolicy::Halt),
+        Ok(v) if v.trim() == "halt" => Ok(SplitVotePolicy::Halt),
+        Ok(v) => v
+            .trim()
+            .strip_prefix("hold-last=")
+            .and_then(|n| n.parse().ok())
+            .map(|cycles| SplitVotePolicy::HoldLast { cycles })
+            .ok_or_else(|| format!("HARMONY_SPLIT_VOTE {}: expected halt or hold-last=<cycles>", v)),
+    }
+}
+
+// Replica decisions are verified against HARMONY_DECISION_PEERS (one `<node_id> <hex ed25519
+// public key>` per line); HARMONY_REPLICAS names the voting replicas, comma-separated.
+fn voter() -> Result<ReplicaVoter, String> {
+    let replicas = std::env::var("HARMONY_REPLICAS").map_err(|_| "HARMONY_REPLICAS not set".to_string())?;
+    let replicas: Vec<&str> = replicas.split(',').map(str::trim).filter(|r| !r.is_empty()).collect();
+    if replicas.len() < 3 {
+        return Err(format!("HARMONY_REPLICAS: {} replicas cannot outvote a faulty one", replicas.len()));
+    }
+    let peers_path = std::env::var("HARMONY_DECISION_PEERS").unwrap_or_else(|_| "/etc/harmony/decision_peers".into());
+    let trusted = std::fs::read_to_string(&peers_path).map_err(|e| e.to_string()).and_then(|t| sealed_config::parse_trusted_keys(&t)).map_err(|e| format!("decision peers {}: {}", peers_path, e))?;
+    Ok(ReplicaVoter::new(&replicas, split_vote_policy()?, RecordVerifier::new(trusted, REPLICA_RECORD_MAX_AGE)))
+}
+
+#[tokio::main]
+async fn main() {
+    let voter = match voter() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Voter: {}", e);
+            std::process::exit(2);
+        }
+    };
+    replica_voter::run_voter(voter, Duration::from_millis(100)).await;
+}
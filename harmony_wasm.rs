@@ -0,0 +1,44 @@
+//! Harmony_Wasm.rs - wasm32 build of the evaluation kernel for the control-room HMI (forbid unsafe)
+//!
+//! Build: wasm-pack build --target web --out-dir bridge-frontend/public/harmony_wasm
+#![forbid(unsafe_code)]
+use wasm_bindgen::prelude::*;
+
+mod decision_kernel;
+use decision_kernel::{decide, required_score as kernel_required_score, weighted_mu, Decision};
+
+fn decision_name(d: Decision) -> &'static str {
+    match d {
+        Decision::GO => "GO",
+        Decision::CAUTION => "CAUTION",
+        Decision::HALT => "HALT",
+    }
+}
+
+#[wasm_bindgen(js_name = calculateMu)]
+pub fn calculate_mu(weights: &[f64], scores: &[f64]) -> f64 {
+    weighted_mu(weights, scores)
+}
+
+#[wasm_bindgen]
+pub fn evaluate(weights: &[f64], scores: &[f64], ch: bool, threshold: f64) -> String {
+    decision_name(decide(weighted_mu(weights, scores), ch, true, threshold)).to_string()
+}
+
+// "What if channel `channel` read `value`?" Returns the hypothetical mu; the live
+// inputs the HMI passed in are left untouched.
+#[wasm_bindgen(js_name = whatIfMu)]
+pub fn what_if_mu(weights: &[f64], scores: &[f64], channel: usize, value: f64) -> f64 {
+    let mut hypothetical = scores.to_vec();
+    if let Some(s) = hypothetical.get_mut(channel) {
+        *s = value;
+    }
+    weighted_mu(weights, &hypothetical)
+}
+
+// Smallest score for `channel` (others held) that reaches `threshold`, or NaN if even
+// 1.0 is not enough.
+#[wasm_bindgen(js_name = requiredScore)]
+pub fn required_score(weights: &[f64], scores: &[f64], channel: usize, threshold: f64) -> f64 {
+    kernel_required_score(weights, scores, channel, threshold).unwrap_or(f64::NAN)
+}
@@ -0,0 +1,213 @@
+//! Health_Probes.rs - Kubernetes readiness/liveness over self-test and cycle watchdog (forbid unsafe)
+//!
+//! /readyz is 200 only after the kernel self-test passed and while the watchdog is fed;
+//! /livez is 200 while the evaluation loop keeps completing cycles within `max_stall`, and
+//! during the startup grace before its first cycle, so a slow start is not killed as a hang.
+//! Served from plain threads, one per connection with read/write timeouts, so neither a wedged
+//! async runtime nor a stalled client keeps the kubelet from getting an answer.
+//! /openapi.json returns schemas/harmony/v1/openapi.json, which documents this surface and the
+//! diagnostics listener's, with every schema inline so a client generator needs nothing else.
+#![forbid(unsafe_code)]
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::decision_kernel::{decide, weighted_mu, Decision};
+
+const OPENAPI_DOCUMENT: &str = include_str!("schemas/harmony/v1/openapi.json");
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub struct ProbeState {
+    self_test_ok: AtomicBool,
+    last_cycle_ms: AtomicU64,
+    max_stall: Duration,
+    started: Instant,
+    startup_grace: Duration,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+// Known-answer checks on the decision path; a failing build or FPU must not report ready.
+pub fn self_test() -> Result<(), String> {
+    let w = [0.30, 0.25, 0.20, 0.15, 0.10];
+    let cases: [(&[f64], bool, Decision); 4] = [
+        (&[1.0, 1.0, 1.0, 1.0, 1.0], true, Decision::GO),
+        (&[1.0, 1.0, 1.0, 1.0, 1.0], false, Decision::HALT),
+        (&[0.98, 0.97, 1.0, 0.96, 0.99], true, Decision::HALT),
+        (&[f64::NAN, 1.0, 1.0, 1.0, 1.0], true, Decision::HALT),
+    ];
+    for (i, (scores, ch, want)) in cases.iter().enumerate() {
+        let got = decide(weighted_mu(&w, scores), *ch, true, 0.9995);
+        if got != *want {
+            return Err(format!("self-test case {}: expected {:?}, got {:?}", i, want, got));
+        }
+    }
+    Ok(())
+}
+
+impl ProbeState {
+    pub fn new(max_stall: Duration, startup_grace: Duration) -> Arc<Self> {
+        Arc::new(ProbeState {
+            self_test_ok: AtomicBool::new(false),
+            last_cycle_ms: AtomicU64::new(0),
+            max_stall,
+            started: Instant::now(),
+            startup_grace,
+        })
+    }
+
+    pub fn run_self_test(&self) -> Result<(), String> {
+        let result = self_test();
+        self.self_test_ok.store(result.is_ok(), Ordering::SeqCst);
+        result
+    }
+
+    // Watchdog feed: call once per completed evaluation cycle.
+    pub fn cycle_completed(&self) {
+        self.last_cycle_ms.store(now_ms(), Ordering::SeqCst);
+    }
+
+    fn cycling(&self) -> bool {
+        let last = self.last_cycle_ms.load(Ordering::SeqCst);
+        last != 0 && now_ms().saturating_sub(last) <= self.max_stall.as_millis() as u64
+    }
+
+    pub fn alive(&self) -> bool {
+        match self.last_cycle_ms.load(Ordering::SeqCst) {
+            0 => self.started.elapsed() <= self.startup_grace,
+            _ => self.cycling(),
+        }
+    }
+
+    // Never during the grace: ready means a cycle has actually completed.
+    pub fn ready(&self) -> bool {
+        self.self_test_ok.load(Ordering::SeqCst) && self.cycling()
+    }
+}
+
+pub fn serve(addr: &str, state: Arc<ProbeState>) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let state = state.clone();
+            thread::spawn(move || respond(stream, &state));
+        }
+    });
+    Ok(())
+}
+
+fn respond(stream: TcpStream, state: &ProbeState) {
+    let _ = stream.set_read_timeout(Some(CONNECTION_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(CONNECTION_TIMEOUT));
+    let mut line = String::new();
+    if BufReader::new(&stream).read_line(&mut line).is_err() {
+        return;
+    }
+    let path = line.split_whitespace().nth(1).unwrap_or("");
+    if path == "/openapi.json" {
+        let _ = write!(
+            &stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            OPENAPI_DOCUMENT.len(),
+            OPENAPI_DOCUMENT
+        );
+        return;
+    }
+    let ok = match path {
+        "/readyz" => Some(state.ready()),
+        "/livez" => Some(state.alive()),
+        _ => None,
+    };
+    let status = match ok {
+        Some(true) => "200 OK",
+        Some(false) => "503 Service Unavailable",
+        None => "404 Not Found",
+    };
+    let _ = write!(&stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    // (component name, file name, file contents) for each standalone schema next to the document.
+    const STANDALONE: [(&str, &str, &str); 4] = [
+        ("Decision", "decision.schema.json", include_str!("schemas/harmony/v1/decision.schema.json")),
+        ("HaltReason", "halt_reason.schema.json", include_str!("schemas/harmony/v1/halt_reason.schema.json")),
+        ("EvaluationReport", "evaluation_report.schema.json", include_str!("schemas/harmony/v1/evaluation_report.schema.json")),
+        ("IncidentTimeline", "incident_timeline.schema.json", include_str!("schemas/harmony/v1/incident_timeline.schema.json")),
+    ];
+
+    fn refs<'a>(v: &'a Value, out: &mut Vec<&'a str>) {
+        match v {
+            Value::Object(map) => {
+                for (k, x) in map {
+                    match (k.as_str(), x) {
+                        ("$ref", Value::String(r)) => out.push(r),
+                        _ => refs(x, out),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|x| refs(x, out)),
+            _ => {}
+        }
+    }
+
+    // A standalone file as it must appear under components: no $schema/$id, sibling refs local.
+    fn as_component(mut v: Value) -> Value {
+        match &mut v {
+            Value::Object(map) => {
+                map.remove("$schema");
+                map.remove("$id");
+                for (k, x) in map.iter_mut() {
+                    *x = match (k.as_str(), x.take()) {
+                        ("$ref", Value::String(file)) => {
+                            let name = STANDALONE.iter().find(|(_, f, _)| *f == file).map_or("?", |(n, _, _)| n);
+                            Value::String(format!("#/components/schemas/{}", name))
+                        }
+                        (_, x) => as_component(x),
+                    };
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|x| *x = as_component(x.take())),
+            _ => {}
+        }
+        v
+    }
+
+    #[test]
+    fn openapi_document_covers_both_listeners() {
+        let doc: Value = serde_json::from_str(OPENAPI_DOCUMENT).unwrap();
+        assert_eq!(doc["openapi"], "3.1.0");
+        for path in ["/readyz", "/livez", "/openapi.json", "/diagnostics", "/metrics", "/incidents", "/incidents/{incident}"] {
+            assert!(doc["paths"][path]["get"]["operationId"].is_string(), "{} undocumented", path);
+        }
+        assert_eq!(doc["paths"].as_object().unwrap().len(), 7);
+    }
+
+    #[test]
+    fn every_openapi_ref_resolves_inside_the_document() {
+        let doc: Value = serde_json::from_str(OPENAPI_DOCUMENT).unwrap();
+        let mut found = Vec::new();
+        refs(&doc, &mut found);
+        assert!(!found.is_empty());
+        for r in found {
+            let pointer = r.strip_prefix('#').unwrap_or_else(|| panic!("external $ref {}", r));
+            assert!(doc.pointer(pointer).is_some(), "dangling $ref {}", r);
+        }
+    }
+
+    #[test]
+    fn inline_schemas_match_the_standalone_files() {
+        let doc: Value = serde_json::from_str(OPENAPI_DOCUMENT).unwrap();
+        for (name, _, text) in STANDALONE {
+            assert_eq!(doc["components"]["schemas"][name], as_component(serde_json::from_str(text).unwrap()), "{} drifted", name);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Hot_Standby.rs - Active/standby failover with replicated state handoff (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::time::{Duration, Instant};
+
+use crate::decision::Decision;
+use crate::rbac::{AccessControl, Action, AuditSink, Principal};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role { Active, Standby }
+
+// Everything the standby needs to continue exactly where the active stopped.
+#[derive(Clone, Debug)]
+pub struct ReplicatedState {
+    pub seq: u64,
+    pub scores: Vec<f64>,
+    pub latched_halt: bool,
+    pub last_decision: Decision,
+}
+
+pub struct HotStandby {
+    role: Role,
+    cycle: Duration,
+    mirror: Option<ReplicatedState>,
+    last_heartbeat: Instant,
+    just_took_over: bool,
+    // A latched HALT inherited from the active; held until an authorized reset_latch().
+    latched: bool,
+}
+
+impl HotStandby {
+    pub fn new(role: Role, cycle: Duration) -> Self {
+        HotStandby {
+            role,
+            cycle,
+            mirror: None,
+            last_heartbeat: Instant::now(),
+            just_took_over: false,
+            latched: false,
+        }
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn mirrored(&self) -> Option<&ReplicatedState> {
+        self.mirror.as_ref()
+    }
+
+    // Standby side: every accepted replication frame doubles as the active's heartbeat. A frame
+    // no newer than the mirror is a replay or a stale duplicate and proves nothing about the
+    // active, so it neither replaces the mirror nor postpones takeover.
+    pub fn on_replicated(&mut self, state: ReplicatedState) {
+        if self.mirror.as_ref().is_none_or(|m| state.seq > m.seq) {
+            self.mirror = Some(state);
+            self.last_heartbeat = Instant::now();
+        }
+    }
+
+    // Called once per cycle; promotes the standby when the active missed a full cycle.
+    pub fn poll_takeover(&mut self) -> bool {
+        if self.role == Role::Standby && self.last_heartbeat.elapsed() > self.cycle {
+            self.role = Role::Active;
+            self.just_took_over = true;
+            self.latched = self.mirror.as_ref().is_some_and(|m| m.latched_halt);
+            return true;
+        }
+        false
+    }
+
+    // Applied to the locally evaluated decision before it is emitted. The first
+    // cycle after takeover may only GO if the mirrored active was also GO and
+    // not latched, so a stale or empty mirror can never produce a spurious GO.
+    // A HALT the active had latched stays latched here, every cycle, until an
+    // explicit reset_latch(); failover never clears it.
+    pub fn gate(&mut self, evaluated: Decision) -> Option<Decision> {
+        if self.role == Role::Standby {
+            return None;
+        }
+        if self.latched {
+            self.just_took_over = false;
+            return Some(Decision::HALT);
+        }
+        if !self.just_took_over {
+            return Some(evaluated);
+        }
+        self.just_took_over = false;
+        let handoff_go = matches!(
+            &self.mirror,
+            Some(m) if !m.latched_halt && m.last_decision == Decision::GO
+        );
+        match (evaluated, handoff_go) {
+            (Decision::GO, true) => Some(Decision::GO),
+            _ => Some(Decision::HALT),
+        }
+    }
+
+    pub fn latched_halt(&self) -> bool {
+        self.latched || self.mirror.as_ref().is_some_and(|m| m.latched_halt)
+    }
+
+    // The operator's explicit reset of an inherited latch, as on the active it replaced:
+    // authorized for Action::LatchedHaltReset and audited, refused ones included. Clears the
+    // mirrored flag too, so latched_halt() agrees with gate() afterwards.
+    pub fn reset_latch<S: AuditSink>(&mut self, principal: &Principal, access: &mut AccessControl<S>) -> Result<(), String> {
+        let detail = format!("role {:?}, latched {}", self.role, self.latched_halt());
+        access.authorize(principal, Action::LatchedHaltReset, "hot_standby", &detail)?;
+        self.latched = false;
+        if let Some(m) = self.mirror.as_mut() {
+            m.latched_halt = false;
+        }
+        Ok(())
+    }
+}
+
+// Active side: push state to the standby every cycle over the replication channel.
+pub async fn replicate(state: &ReplicatedState) {
+    send_replication_frame(state).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rbac::{AuditEntry, Role as RbacRole};
+
+    #[derive(Default)]
+    struct Audit(Vec<AuditEntry>);
+
+    impl AuditSink for Audit {
+        fn append(&mut self, entry: &AuditEntry) -> Result<(), String> {
+            self.0.push(entry.clone());
+            Ok(())
+        }
+    }
+
+    fn state(seq: u64, latched_halt: bool, last_decision: Decision) -> ReplicatedState {
+        ReplicatedState { seq, scores: vec![0.99; 3], latched_halt, last_decision }
+    }
+
+    fn taken_over(mirror: ReplicatedState) -> HotStandby {
+        let mut standby = HotStandby::new(Role::Standby, Duration::ZERO);
+        standby.on_replicated(mirror);
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(standby.poll_takeover());
+        standby
+    }
+
+    fn principal(role: RbacRole) -> Principal {
+        Principal { identity: format!("{:?}", role), roles: vec![role] }
+    }
+
+    #[test]
+    fn inherited_latch_holds_until_authorized_reset() {
+        let mut standby = taken_over(state(7, true, Decision::HALT));
+        let mut access = AccessControl::new(Audit::default());
+        for _ in 0..3 {
+            assert_eq!(standby.gate(Decision::GO), Some(Decision::HALT));
+        }
+        assert!(standby.reset_latch(&principal(RbacRole::Operator), &mut access).is_err());
+        assert!(standby.latched_halt());
+        assert_eq!(standby.gate(Decision::GO), Some(Decision::HALT));
+
+        standby.reset_latch(&principal(RbacRole::SafetyEngineer), &mut access).unwrap();
+        assert!(!standby.latched_halt());
+        assert_eq!(standby.gate(Decision::GO), Some(Decision::GO));
+        let audit = &access.sink().0;
+        assert_eq!(audit.iter().map(|e| e.allowed).collect::<Vec<_>>(), [false, true]);
+        assert_eq!(audit[0].detail, "role Active, latched true");
+    }
+
+    #[test]
+    fn first_cycle_after_takeover_needs_a_go_handoff() {
+        let mut standby = taken_over(state(3, false, Decision::CAUTION));
+        assert_eq!(standby.gate(Decision::GO), Some(Decision::HALT));
+        assert_eq!(standby.gate(Decision::GO), Some(Decision::GO));
+
+        let mut standby = taken_over(state(3, false, Decision::GO));
+        assert_eq!(standby.gate(Decision::GO), Some(Decision::GO));
+    }
+
+    #[test]
+    fn standby_ignores_stale_replication_and_emits_nothing() {
+        let mut standby = HotStandby::new(Role::Standby, Duration::from_secs(60));
+        standby.on_replicated(state(5, false, Decision::GO));
+        standby.on_replicated(state(4, true, Decision::HALT));
+        assert_eq!(standby.mirrored().map(|m| m.seq), Some(5));
+        assert!(!standby.poll_takeover());
+        assert_eq!(standby.gate(Decision::GO), None);
+    }
+
+    #[test]
+    fn replayed_frames_are_not_heartbeats() {
+        let mut standby = HotStandby::new(Role::Standby, Duration::from_millis(20));
+        standby.on_replicated(state(5, false, Decision::GO));
+        for _ in 0..5 {
+            std::thread::sleep(Duration::from_millis(5));
+            standby.on_replicated(state(5, false, Decision::GO));
+            standby.on_replicated(state(4, false, Decision::GO));
+        }
+        assert!(standby.poll_takeover());
+        assert_eq!(standby.role(), Role::Active);
+    }
+}
@@ -0,0 +1,68 @@
+#ifndef HARMONY_H
+#define HARMONY_H
+
+/* Generated by cbindgen from harmony_ffi.rs. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define HARMONY_GO 0
+
+#define HARMONY_CAUTION 1
+
+#define HARMONY_HALT 2
+
+#define HARMONY_ERR_ARG -1
+
+/**
+ * Opaque to C.
+ */
+typedef struct HarmonyContext HarmonyContext;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a context. Returns NULL if `weights` is NULL, `n` is 0, any weight is
+ * zero, negative or non-finite, the weights do not sum to 1, or `threshold` is
+ * not in (0, 1]. The CAUTION band is the engine default below `threshold`.
+ * Free with `harmony_context_free`.
+ *
+ * # Safety
+ * `weights` must point to `n` readable doubles.
+ */
+HarmonyContext *harmony_context_new(const double *weights, size_t n, double threshold);
+
+/**
+ * # Safety
+ * `ctx` must be NULL or a pointer from `harmony_context_new` not yet freed.
+ */
+void harmony_context_free(HarmonyContext *ctx);
+
+/**
+ * Evaluates one cycle. Returns HARMONY_GO / HARMONY_CAUTION / HARMONY_HALT, or
+ * HARMONY_ERR_ARG if a pointer is NULL or `n` does not match the weight count.
+ *
+ * # Safety
+ * `ctx` must be a live context; `scores` must point to `n` readable doubles.
+ */
+int harmony_evaluate(HarmonyContext *ctx, const double *scores, size_t n, bool ch);
+
+/**
+ * JSON report of the last evaluation ("" before the first). The pointer is owned
+ * by the context and valid until the next `harmony_evaluate` or `harmony_context_free`.
+ *
+ * # Safety
+ * `ctx` must be NULL or a live context.
+ */
+const char *harmony_last_report_json(const HarmonyContext *ctx);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* HARMONY_H */
@@ -0,0 +1,224 @@
+//! Ingest.rs - Bounded ingestion queue between Kafka/MQTT consumers and the engine (forbid unsafe)
+//!
+//! Consumers push samples as they arrive; the engine drains once per cycle. The queue never
+//! grows past its capacity. When it is full, the metric's kind decides what gives:
+//!   - Gauge (a level: pressure, a health score): keep-latest. A pending sample of the same
+//!     metric is overwritten in place; otherwise the oldest pending gauge sample is dropped.
+//!   - Counter (a delta: events, bytes): aggregate. The delta is added to a pending sample of
+//!     the same counter, so nothing is lost; with none pending, room is made by dropping the
+//!     oldest gauge sample, and only if there is none is the delta itself dropped.
+//!
+//! Below capacity every sample is kept in arrival order. Unregistered metrics are rejected.
+//! `backpressure()` goes high at the high watermark so consumers can pause partitions /
+//! stop acking before anything has to be dropped.
+//!
+//! A metric may be defined in a unit (register_in). Sources then bind to it once, at
+//! configuration, declaring the unit they publish in; bind() refuses a source whose unit has
+//! another dimension, or none, and push_from() converts each reading into the metric's unit, so
+//! a psi gauge feeding a kPa metric cannot skew mu by a factor of 6.9.
+#![forbid(unsafe_code)]
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write;
+
+use crate::units::{Conversion, Unit};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetricKind {
+    Gauge,
+    Counter,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub metric: String,
+    pub value: f64,
+    pub at_ms: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Admission {
+    Queued,
+    // Folded into a pending sample of the same metric (keep-latest or aggregate).
+    Merged,
+    // Queued after dropping the oldest pending gauge sample.
+    Displaced,
+    Dropped,
+    Unknown,
+}
+
+#[derive(Default)]
+struct MetricStats {
+    queued: u64,
+    merged: u64,
+    dropped: u64,
+}
+
+struct Metric {
+    kind: MetricKind,
+    unit: Option<Unit>,
+    stats: MetricStats,
+}
+
+pub struct IngestQueue {
+    capacity: usize,
+    high_watermark: usize,
+    pending: VecDeque<Sample>,
+    metrics: BTreeMap<String, Metric>,
+    // Source -> (metric, conversion into the metric's unit).
+    bindings: BTreeMap<String, (String, Conversion)>,
+    rejected_unknown: u64,
+    peak_depth: usize,
+    backpressure_episodes: u64,
+    backpressure: bool,
+}
+
+impl IngestQueue {
+    // Backpressure is signalled from `high_water` (fraction of capacity) upward.
+    pub fn new(capacity: usize, high_water: f64) -> Self {
+        let capacity = capacity.max(1);
+        let high_watermark = ((capacity as f64 * high_water.clamp(0.0, 1.0)).ceil() as usize).clamp(1, capacity);
+        IngestQueue {
+            capacity,
+            high_watermark,
+            pending: VecDeque::with_capacity(capacity),
+            metrics: BTreeMap::new(),
+            bindings: BTreeMap::new(),
+            rejected_unknown: 0,
+            peak_depth: 0,
+            backpressure_episodes: 0,
+            backpressure: false,
+        }
+    }
+
+    pub fn register(&mut self, metric: &str, kind: MetricKind) -> &mut Self {
+        self.metrics.insert(metric.to_string(), Metric { kind, unit: None, stats: MetricStats::default() });
+        self
+    }
+
+    // A metric normalized to `unit`; sources must bind() to it with a unit of the same dimension.
+    pub fn register_in(&mut self, metric: &str, kind: MetricKind, unit: Unit) -> &mut Self {
+        self.metrics.insert(metric.to_string(), Metric { kind, unit: Some(unit), stats: MetricStats::default() });
+        self
+    }
+
+    // Routes `source`'s readings, published in `unit`, into `metric`. Call while validating
+    // configuration: a unit of another dimension, a missing unit on a metric that has one, or a
+    // unit on a metric without one is refused here rather than found in mu later.
+    pub fn bind(&mut self, source: &str, metric: &str, unit: Option<Unit>) -> Result<&mut Self, String> {
+        let m = self.metrics.get(metric).ok_or_else(|| format!("{}: metric {:?} is not registered", source, metric))?;
+        let conversion = match (unit, m.unit) {
+            (Some(from), Some(to)) => Conversion::between(from, to).map_err(|e| format!("{} -> {}: {}", source, metric, e))?,
+            (None, Some(to)) => return Err(format!("{} -> {}: source declares no unit; {} is in {}", source, metric, metric, to)),
+            (Some(from), None) => return Err(format!("{} -> {}: source publishes {} but {} has no unit to check it against", source, metric, from, metric)),
+            (None, None) => Conversion::IDENTITY,
+        };
+        self.bindings.insert(source.to_string(), (metric.to_string(), conversion));
+        Ok(self)
+    }
+
+    // push() for a bound source, converted into its metric's unit; Unknown when unbound.
+    pub fn push_from(&mut self, source: &str, value: f64, at_ms: u64) -> Admission {
+        let Some((metric, conversion)) = self.bindings.get(source) else {
+            self.rejected_unknown += 1;
+            return Admission::Unknown;
+        };
+        let value = match self.kind(metric) {
+            Some(MetricKind::Counter) => conversion.apply_delta(value),
+            _ => conversion.apply(value),
+        };
+        let metric = metric.clone();
+        self.push(&metric, value, at_ms)
+    }
+
+    pub fn push(&mut self, metric: &str, value: f64, at_ms: u64) -> Admission {
+        let Some(kind) = self.metrics.get(metric).map(|m| m.kind) else {
+            self.rejected_unknown += 1;
+            return Admission::Unknown;
+        };
+        let admission = if self.pending.len() < self.capacity {
+            self.pending.push_back(Sample { metric: metric.to_string(), value, at_ms });
+            Admission::Queued
+        } else if let Some(s) = self.pending.iter_mut().rev().find(|s| s.metric == metric) {
+            match kind {
+                MetricKind::Gauge => s.value = value,
+                MetricKind::Counter => s.value += value,
+            }
+            s.at_ms = s.at_ms.max(at_ms);
+            Admission::Merged
+        } else if let Some(i) = self.pending.iter().position(|s| self.kind(&s.metric) == Some(MetricKind::Gauge)) {
+            let victim = self.pending.remove(i).expect("index from position");
+            self.stats_mut(&victim.metric).dropped += 1;
+            self.pending.push_back(Sample { metric: metric.to_string(), value, at_ms });
+            Admission::Displaced
+        } else {
+            Admission::Dropped
+        };
+        let stats = self.stats_mut(metric);
+        match admission {
+            Admission::Queued | Admission::Displaced => stats.queued += 1,
+            Admission::Merged => stats.merged += 1,
+            Admission::Dropped => stats.dropped += 1,
+            Admission::Unknown => {}
+        }
+        self.peak_depth = self.peak_depth.max(self.pending.len());
+        self.update_backpressure();
+        admission
+    }
+
+    // Everything pending, oldest first, into `out` (cleared first; reuse it across cycles).
+    pub fn drain_into(&mut self, out: &mut Vec<Sample>) {
+        out.clear();
+        out.extend(self.pending.drain(..));
+        self.update_backpressure();
+    }
+
+    pub fn depth(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn backpressure(&self) -> bool {
+        self.backpressure
+    }
+
+    fn kind(&self, metric: &str) -> Option<MetricKind> {
+        self.metrics.get(metric).map(|m| m.kind)
+    }
+
+    fn stats_mut(&mut self, metric: &str) -> &mut MetricStats {
+        &mut self.metrics.get_mut(metric).expect("registered metric").stats
+    }
+
+    // High at the watermark, low again only once drained below half of it, so a consumer
+    // hovering at the edge does not flap pause/resume every sample.
+    fn update_backpressure(&mut self) {
+        let depth = self.pending.len();
+        if !self.backpressure && depth >= self.high_watermark {
+            self.backpressure = true;
+            self.backpressure_episodes += 1;
+        } else if self.backpressure && depth < self.high_watermark / 2 {
+            self.backpressure = false;
+        }
+    }
+
+    // Prometheus text exposition for the diagnostics /metrics endpoint.
+    pub fn metrics_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# TYPE harmony_ingest_depth gauge\nharmony_ingest_depth {}", self.pending.len());
+        let _ = writeln!(out, "# TYPE harmony_ingest_capacity gauge\nharmony_ingest_capacity {}", self.capacity);
+        let _ = writeln!(out, "# TYPE harmony_ingest_peak_depth gauge\nharmony_ingest_peak_depth {}", self.peak_depth);
+        let _ = writeln!(out, "# TYPE harmony_ingest_backpressure gauge\nharmony_ingest_backpressure {}", self.backpressure as u8);
+        let _ = writeln!(out, "# TYPE harmony_ingest_backpressure_episodes_total counter\nharmony_ingest_backpressure_episodes_total {}", self.backpressure_episodes);
+        let _ = writeln!(out, "# TYPE harmony_ingest_unknown_total counter\nharmony_ingest_unknown_total {}", self.rejected_unknown);
+        out.push_str("# TYPE harmony_ingest_queued_total counter\n# TYPE harmony_ingest_merged_total counter\n# TYPE harmony_ingest_dropped_total counter\n");
+        for (name, m) in &self.metrics {
+            let kind = match m.kind {
+                MetricKind::Gauge => "gauge",
+                MetricKind::Counter => "counter",
+            };
+            let _ = writeln!(out, "harmony_ingest_queued_total{{metric=\"{}\",kind=\"{}\"}} {}", name, kind, m.stats.queued);
+            let _ = writeln!(out, "harmony_ingest_merged_total{{metric=\"{}\",kind=\"{}\"}} {}", name, kind, m.stats.merged);
+            let _ = writeln!(out, "harmony_ingest_dropped_total{{metric=\"{}\",kind=\"{}\"}} {}", name, kind, m.stats.dropped);
+        }
+        out
+    }
+}
@@ -0,0 +1,145 @@
+//! Invariants.rs - Public mu invariants and proptest strategies for domain contexts (forbid unsafe)
+#![forbid(unsafe_code)]
+
+const MIN_SCORE: f64 = 1e-12;
+const EPS: f64 = 1e-12;
+
+// Any domain's calculate_mu, adapted as (weights, scores) -> mu.
+pub trait MuFn: Fn(&[f64], &[f64]) -> f64 {}
+impl<F: Fn(&[f64], &[f64]) -> f64> MuFn for F {}
+
+#[derive(Debug, PartialEq)]
+pub enum Violation {
+    NotMonotonic { channel: usize },
+    PermutationVariant,
+    OutOfRange(f64),
+    ClampingIgnored { channel: usize },
+}
+
+// Raising any single score (weight >= 0) must never lower mu.
+pub fn monotonic_in_each_score<F: MuFn>(mu: &F, weights: &[f64], scores: &[f64], bump: f64) -> Result<(), Violation> {
+    let base = mu(weights, scores);
+    for i in 0..scores.len() {
+        let mut raised = scores.to_vec();
+        raised[i] = (raised[i] + bump.abs()).min(1.0);
+        if mu(weights, &raised) + EPS < base {
+            return Err(Violation::NotMonotonic { channel: i });
+        }
+    }
+    Ok(())
+}
+
+// Reordering (weight, score) pairs together must not change mu.
+pub fn weight_permutation_invariant<F: MuFn>(mu: &F, weights: &[f64], scores: &[f64]) -> Result<(), Violation> {
+    let base = mu(weights, scores);
+    let mut w = weights.to_vec();
+    let mut s = scores.to_vec();
+    w.reverse();
+    s.reverse();
+    if !close(mu(&w, &s), base) {
+        return Err(Violation::PermutationVariant);
+    }
+    if w.len() > 1 {
+        w.rotate_left(1);
+        s.rotate_left(1);
+        if !close(mu(&w, &s), base) {
+            return Err(Violation::PermutationVariant);
+        }
+    }
+    Ok(())
+}
+
+// mu stays within [MIN_SCORE^sum(w), 1] for normalized weights and any finite scores.
+pub fn bounded<F: MuFn>(mu: &F, weights: &[f64], scores: &[f64]) -> Result<(), Violation> {
+    let m = mu(weights, scores);
+    if !(m > 0.0 && m <= 1.0 + EPS) {
+        return Err(Violation::OutOfRange(m));
+    }
+    Ok(())
+}
+
+// Scores outside [0, 1] behave exactly like their clamped value.
+pub fn clamping_respected<F: MuFn>(mu: &F, weights: &[f64], scores: &[f64]) -> Result<(), Violation> {
+    for i in 0..scores.len() {
+        for (wild, tame) in [(1.5, 1.0), (-3.0, MIN_SCORE)] {
+            let mut a = scores.to_vec();
+            let mut b = scores.to_vec();
+            a[i] = wild;
+            b[i] = tame;
+            if !close(mu(weights, &a), mu(weights, &b)) {
+                return Err(Violation::ClampingIgnored { channel: i });
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn check_all<F: MuFn>(mu: &F, weights: &[f64], scores: &[f64]) -> Result<(), Violation> {
+    monotonic_in_each_score(mu, weights, scores, 1e-3)?;
+    weight_permutation_invariant(mu, weights, scores)?;
+    bounded(mu, weights, scores)?;
+    clamping_respected(mu, weights, scores)
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= EPS * a.abs().max(b.abs()).max(1.0)
+}
+
+// Strategies for `proptest!` blocks in domain crates, e.g.
+//   proptest! { #[test] fn mu_ok((w, s) in context(5)) { check_all(&my_mu, &w, &s).unwrap() } }
+#[cfg(feature = "proptest")]
+pub mod strategies {
+    use proptest::prelude::*;
+
+    // Non-negative weights normalized to sum to 1.
+    pub fn weights(n: usize) -> impl Strategy<Value = Vec<f64>> {
+        prop::collection::vec(0.01f64..1.0, n).prop_map(|w| {
+            let sum: f64 = w.iter().sum();
+            w.into_iter().map(|x| x / sum).collect()
+        })
+    }
+
+    // Healthy-ish scores clustered near 1.0, where the 0.9995 threshold lives.
+    pub fn scores(n: usize) -> impl Strategy<Value = Vec<f64>> {
+        prop::collection::vec(prop_oneof![0.99f64..=1.0, 0.0f64..=1.0], n)
+    }
+
+    pub fn context(n: usize) -> impl Strategy<Value = (Vec<f64>, Vec<f64>)> {
+        (weights(n), scores(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decision_kernel::weighted_mu;
+
+    const WEIGHTS: [f64; 5] = [0.30, 0.25, 0.20, 0.15, 0.10];
+    const SCORES: [f64; 5] = [0.98, 0.97, 1.0, 0.96, 0.99];
+
+    #[test]
+    fn the_kernel_holds_every_invariant() {
+        check_all(&weighted_mu, &WEIGHTS, &SCORES).unwrap();
+        check_all(&weighted_mu, &WEIGHTS, &[1.0, 0.0, 0.5, 1.0, 0.9999]).unwrap();
+        check_all(&weighted_mu, &[1.0], &[0.2]).unwrap();
+    }
+
+    #[test]
+    fn broken_implementations_are_caught() {
+        let inverted = |w: &[f64], s: &[f64]| 1.0 - weighted_mu(w, s);
+        assert_eq!(monotonic_in_each_score(&inverted, &WEIGHTS, &SCORES, 1e-3), Err(Violation::NotMonotonic { channel: 0 }));
+        let first_channel = |_: &[f64], s: &[f64]| s[0].clamp(MIN_SCORE, 1.0);
+        assert_eq!(weight_permutation_invariant(&first_channel, &WEIGHTS, &SCORES), Err(Violation::PermutationVariant));
+        assert_eq!(bounded(&|_: &[f64], _: &[f64]| 1.5, &WEIGHTS, &SCORES), Err(Violation::OutOfRange(1.5)));
+        let unclamped = |w: &[f64], s: &[f64]| w.iter().zip(s).map(|(w, s)| w * s).sum::<f64>();
+        assert_eq!(clamping_respected(&unclamped, &WEIGHTS, &SCORES), Err(Violation::ClampingIgnored { channel: 0 }));
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn the_kernel_holds_every_invariant_on_generated_contexts((w, s) in strategies::context(5)) {
+            proptest::prop_assert_eq!(check_all(&weighted_mu, &w, &s), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,357 @@
+//! Key_Provider.rs - Vault / cloud-KMS key material with renewal and a degraded condition (forbid unsafe)
+//!
+//! TLS identities are issued (exportable) and renewed before their lease runs out; signing keys
+//! stay in Vault transit or the cloud KMS and are used remotely. If renewal fails the last good
+//! material stays in service until it expires, and `degraded()` reports the failure meanwhile.
+#![forbid(unsafe_code)]
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::audit_log::SegmentSigner;
+use crate::mtls::MtlsPaths;
+
+#[derive(Clone)]
+pub struct TlsMaterial {
+    pub cert_chain_pem: String,
+    pub private_key_pem: String,
+    pub trust_bundle_pem: String,
+    pub lease: Duration,
+}
+
+pub trait KeyProvider {
+    fn name(&self) -> &str;
+    fn fips_validated(&self) -> bool;
+    fn issue_tls(&mut self, common_name: &str, spiffe_id: &str) -> Result<TlsMaterial, String>;
+    fn sign_digest(&mut self, key_ref: &str, digest: &[u8; 32]) -> Result<Vec<u8>, String>;
+}
+
+pub struct VaultProvider {
+    addr: String,
+    token: String,
+    pki_mount: String,
+    pki_role: String,
+    transit_mount: String,
+}
+
+impl VaultProvider {
+    pub fn new(addr: &str, token: &str, pki_mount: &str, pki_role: &str, transit_mount: &str) -> Self {
+        VaultProvider {
+            addr: addr.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            pki_mount: pki_mount.to_string(),
+            pki_role: pki_role.to_string(),
+            transit_mount: transit_mount.to_string(),
+        }
+    }
+
+    fn post(&self, path: &str, body: Value) -> Result<Value, String> {
+        ureq::post(&format!("{}/v1/{}", self.addr, path))
+            .set("X-Vault-Token", &self.token)
+            .send_json(body)
+            .map_err(|e| format!("vault {}: {}", path, e))?
+            .into_json::<Value>()
+            .map_err(|e| format!("vault {}: {}", path, e))
+    }
+
+    // Extends the token's own lease; call alongside certificate renewal.
+    pub fn renew_token(&self) -> Result<(), String> {
+        self.post("auth/token/renew-self", json!({})).map(|_| ())
+    }
+}
+
+impl KeyProvider for VaultProvider {
+    fn name(&self) -> &str {
+        "vault"
+    }
+
+    // Transit is only validated on Vault Enterprise FIPS builds, which this client cannot detect.
+    fn fips_validated(&self) -> bool {
+        false
+    }
+
+    fn issue_tls(&mut self, common_name: &str, spiffe_id: &str) -> Result<TlsMaterial, String> {
+        self.renew_token()?;
+        let resp = self.post(
+            &format!("{}/issue/{}", self.pki_mount, self.pki_role),
+            json!({ "common_name": common_name, "uri_sans": spiffe_id }),
+        )?;
+        let d = &resp["data"];
+        let s = |k: &str| d[k].as_str().map(str::to_string).ok_or_else(|| format!("vault issue: missing {}", k));
+        // PKI certificates carry no Vault lease (lease_duration is 0); the certificate's own
+        // expiration is what bounds its use.
+        let expiration = d["expiration"].as_u64().ok_or("vault issue: missing expiration")?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        if expiration <= now {
+            return Err(format!("vault issue: certificate expires at {}, already past", expiration));
+        }
+        let mut chain = s("certificate")?;
+        for ca in d["ca_chain"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+            chain.push('\n');
+            chain.push_str(ca);
+        }
+        Ok(TlsMaterial {
+            cert_chain_pem: chain,
+            private_key_pem: s("private_key")?,
+            trust_bundle_pem: s("issuing_ca")?,
+            lease: Duration::from_secs(expiration - now),
+        })
+    }
+
+    fn sign_digest(&mut self, key_ref: &str, digest: &[u8; 32]) -> Result<Vec<u8>, String> {
+        let resp = self.post(
+            &format!("{}/sign/{}/sha2-256", self.transit_mount, key_ref),
+            json!({ "input": B64.encode(digest), "prehashed": true, "marshaling_algorithm": "asn1" }),
+        )?;
+        // Transit returns `vault:v<key version>:<base64 signature>`.
+        let sig = resp["data"]["signature"].as_str().ok_or("vault sign: missing signature")?;
+        let b64 = sig.rsplit(':').next().unwrap_or("");
+        B64.decode(b64).map_err(|e| format!("vault sign: {}", e))
+    }
+}
+
+// Cloud KMS through the provider CLI (instance credentials are picked up by the CLI itself).
+// KMS keys are not exportable, so TLS identities come from a separate issuer (Vault, cert-manager).
+pub enum CloudKms {
+    Aws,
+    Gcp,
+}
+
+pub struct KmsProvider {
+    cloud: CloudKms,
+}
+
+impl KmsProvider {
+    pub fn new(cloud: CloudKms) -> Self {
+        KmsProvider { cloud }
+    }
+}
+
+impl KeyProvider for KmsProvider {
+    fn name(&self) -> &str {
+        match self.cloud {
+            CloudKms::Aws => "aws-kms",
+            CloudKms::Gcp => "gcp-kms",
+        }
+    }
+
+    // Both services sign inside FIPS 140 validated modules.
+    fn fips_validated(&self) -> bool {
+        true
+    }
+
+    fn issue_tls(&mut self, _common_name: &str, _spiffe_id: &str) -> Result<TlsMaterial, String> {
+        Err(format!("{} cannot export TLS keys; use an issuing provider", self.name()))
+    }
+
+    fn sign_digest(&mut self, key_ref: &str, digest: &[u8; 32]) -> Result<Vec<u8>, String> {
+        let dir = std::env::temp_dir().join(format!("harmony-kms-{}", std::process::id()));
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let input = dir.join("digest.bin");
+        let output = dir.join("sig.bin");
+        fs::write(&input, digest).map_err(|e| e.to_string())?;
+        let status = match self.cloud {
+            CloudKms::Aws => {
+                // The CLI prints the base64 signature; decode it into output ourselves.
+                let out = Command::new("aws")
+                    .args(["kms", "sign", "--key-id", key_ref, "--message-type", "DIGEST"])
+                    .args(["--signing-algorithm", "ECDSA_SHA_256", "--output", "text", "--query", "Signature"])
+                    .arg(format!("--message=fileb://{}", input.display()))
+                    .output()
+                    .map_err(|e| format!("aws kms: {}", e))?;
+                if out.status.success() {
+                    let sig = B64.decode(String::from_utf8_lossy(&out.stdout).trim()).map_err(|e| format!("aws kms: {}", e))?;
+                    fs::write(&output, sig).map_err(|e| e.to_string())?;
+                }
+                out.status
+            }
+            CloudKms::Gcp => Command::new("gcloud")
+                .args(["kms", "asymmetric-sign", "--version", key_ref, "--digest-algorithm", "sha256"])
+                .arg(format!("--input-file={}", input.display()))
+                .arg(format!("--signature-file={}", output.display()))
+                .status()
+                .map_err(|e| format!("gcloud kms: {}", e))?,
+        };
+        let result = if status.success() {
+            fs::read(&output).map_err(|e| e.to_string())
+        } else {
+            Err(format!("{} sign exited with {}", self.name(), status))
+        };
+        let _ = fs::remove_dir_all(&dir);
+        result
+    }
+}
+
+// Writes TLS material where MtlsLayer watches for it. The three files must live in one
+// directory reached through a symlink (`.../tls/current/cert.pem`, as with Kubernetes secret
+// volumes): they are written, synced, into a fresh sibling directory and the symlink is then
+// swapped with one rename, so `poll_rotation` sees either the old set or the new one, never a
+// new key beside the old certificate.
+pub fn install_tls(material: &TlsMaterial, paths: &MtlsPaths) -> Result<(), String> {
+    let link = paths.cert_chain.parent().ok_or("cert_chain has no directory")?;
+    if paths.private_key.parent() != Some(link) || paths.trust_bundle.parent() != Some(link) {
+        return Err(format!("TLS files must share {} to be swapped together", link.display()));
+    }
+    if !fs::symlink_metadata(link).map(|m| m.file_type().is_symlink()).unwrap_or(false) {
+        return Err(format!("{} must be a symlink to the live TLS directory", link.display()));
+    }
+    let base = link.parent().ok_or("TLS symlink has no directory")?;
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let staged = base.join(format!(".tls-{}", stamp));
+    let err = |path: &Path, e: std::io::Error| format!("{}: {}", path.display(), e);
+    fs::create_dir(&staged).map_err(|e| err(&staged, e))?;
+    let put = |path: &Path, body: &str| -> Result<(), String> {
+        let target = staged.join(path.file_name().ok_or("TLS path has no file name")?);
+        let mut f = fs::File::create(&target).map_err(|e| err(&target, e))?;
+        f.write_all(body.as_bytes()).and_then(|_| f.sync_all()).map_err(|e| err(&target, e))
+    };
+    put(&paths.trust_bundle, &material.trust_bundle_pem)?;
+    put(&paths.private_key, &material.private_key_pem)?;
+    put(&paths.cert_chain, &material.cert_chain_pem)?;
+    let previous = fs::read_link(link).ok().map(|p| base.join(p));
+    let tmp_link = base.join(format!(".tls-{}.link", stamp));
+    // Relative target, so the link survives the directory being mounted elsewhere.
+    std::os::unix::fs::symlink(staged.file_name().ok_or("staged TLS directory has no name")?, &tmp_link).map_err(|e| err(&tmp_link, e))?;
+    fs::rename(&tmp_link, link).map_err(|e| err(link, e))?;
+    // Unreferenced now; a load that already opened the old files keeps reading them.
+    if let Some(old) = previous.filter(|p| p.file_name().is_some_and(|n| n.to_string_lossy().starts_with(".tls-"))) {
+        let _ = fs::remove_dir_all(old);
+    }
+    Ok(())
+}
+
+pub struct KeyManager<P: KeyProvider> {
+    provider: P,
+    common_name: String,
+    spiffe_id: String,
+    paths: MtlsPaths,
+    issued: Option<(Instant, Duration)>,
+    last_error: Option<String>,
+}
+
+impl<P: KeyProvider> KeyManager<P> {
+    pub fn new(provider: P, common_name: &str, spiffe_id: &str, paths: MtlsPaths) -> Self {
+        KeyManager {
+            provider,
+            common_name: common_name.to_string(),
+            spiffe_id: spiffe_id.to_string(),
+            paths,
+            issued: None,
+            last_error: None,
+        }
+    }
+
+    // Renews at two thirds of the lease; call every cycle or on a timer.
+    pub fn tick(&mut self) {
+        let due = match self.issued {
+            Some((at, lease)) => at.elapsed() >= lease * 2 / 3,
+            None => true,
+        };
+        if !due {
+            return;
+        }
+        let result = self
+            .provider
+            .issue_tls(&self.common_name, &self.spiffe_id)
+            .and_then(|m| install_tls(&m, &self.paths).map(|_| m.lease));
+        match result {
+            Ok(lease) => {
+                self.issued = Some((Instant::now(), lease));
+                self.last_error = None;
+            }
+            Err(e) => {
+                eprintln!("KeyProvider {}: renewal failed: {}", self.provider.name(), e);
+                self.last_error = Some(e);
+            }
+        }
+    }
+
+    // Degraded: the last renewal failed; material may still be valid for now.
+    pub fn degraded(&self) -> bool {
+        self.last_error.is_some()
+    }
+
+    // check_ch condition: issued material exists and its lease has not run out.
+    pub fn key_material_ok(&self) -> bool {
+        matches!(self.issued, Some((at, lease)) if at.elapsed() < lease)
+    }
+}
+
+// Audit segment signing with a key held by any provider (Vault transit, KMS).
+pub struct ProviderSigner<P: KeyProvider> {
+    pub provider: P,
+    pub key_ref: String,
+}
+
+impl<P: KeyProvider> SegmentSigner for ProviderSigner<P> {
+    fn key_id(&self) -> &str {
+        &self.key_ref
+    }
+
+    fn module(&self) -> &str {
+        self.provider.name()
+    }
+
+    fn fips_validated(&self) -> bool {
+        self.provider.fips_validated()
+    }
+
+    fn sign(&mut self, root: &[u8; 32]) -> Result<Vec<u8>, String> {
+        self.provider.sign_digest(&self.key_ref, root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // Issues nothing and signs with a fixed byte, or fails both while `down`.
+    struct Fake {
+        down: bool,
+    }
+
+    impl KeyProvider for Fake {
+        fn name(&self) -> &str {
+            "fake"
+        }
+
+        fn fips_validated(&self) -> bool {
+            false
+        }
+
+        fn issue_tls(&mut self, _: &str, _: &str) -> Result<TlsMaterial, String> {
+            Err("sealed".into())
+        }
+
+        fn sign_digest(&mut self, _: &str, _: &[u8; 32]) -> Result<Vec<u8>, String> {
+            if self.down { Err("unreachable".into()) } else { Ok(vec![7]) }
+        }
+    }
+
+    #[test]
+    fn failed_renewal_is_degraded_without_material() {
+        let dir = PathBuf::from("/nonexistent/tls");
+        let paths = MtlsPaths { cert_chain: dir.join("cert.pem"), private_key: dir.join("key.pem"), trust_bundle: dir.join("bundle.pem") };
+        let mut keys = KeyManager::new(Fake { down: false }, "edge-7", "spiffe://plant/edge-7", paths);
+        assert!(!keys.degraded());
+        keys.tick();
+        assert!(keys.degraded());
+        assert!(!keys.key_material_ok());
+    }
+
+    #[test]
+    fn provider_signer_signs_remotely() {
+        let mut signer = ProviderSigner { provider: Fake { down: false }, key_ref: "audit".into() };
+        assert_eq!((signer.key_id(), signer.module()), ("audit", "fake"));
+        assert_eq!(signer.sign(&[0; 32]), Ok(vec![7]));
+        signer.provider.down = true;
+        assert!(signer.sign(&[0; 32]).is_err());
+    }
+}
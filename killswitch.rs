@@ -0,0 +1,310 @@
+//! Killswitch.rs - Authenticated heartbeat to a remote stop-channel (forbid unsafe)
+//!
+//! The node sends a signed heartbeat `HB <node> <seq> <ts_ms> <sig>` over UDP; the stop-channel
+//! answers `ACK <node> <seq> <ts_ms> <stop> <sig>` signed with its own key. GO requires the
+//! switch to be armed, not fired, and a valid ack for a recent heartbeat within `max_age`.
+//! A signed ack with stop=1 fires the switch; a fired switch stays latched until re-armed.
+//! An ack must echo the exact (seq, ts_ms) of a heartbeat this process sent; seq starts at the
+//! wall-clock ms at startup, so acks captured before a restart never match.
+#![forbid(unsafe_code)]
+use std::collections::VecDeque;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+use crate::core::checks::{CHCheck, CheckOutcome};
+use crate::decision::now_ms;
+use crate::sealed_config::{hex, unhex};
+
+// Acks may answer any of the last few heartbeats (UDP reordering / one lost ack).
+const ACK_WINDOW: u64 = 8;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum KillSwitchStatus {
+    Disarmed,
+    Armed { last_ack_age: Option<Duration> },
+    Stale { last_ack_age: Option<Duration> },
+    Fired { by: String, at_ms: u64 },
+}
+
+struct Inner {
+    armed: bool,
+    fired: Option<(String, u64)>,
+    seq: u64,
+    // (seq, ts_ms) of the last ACK_WINDOW heartbeats sent.
+    sent: VecDeque<(u64, u64)>,
+    last_ack: Option<Instant>,
+}
+
+pub struct KillSwitch {
+    node: String,
+    remote: SocketAddr,
+    socket: UdpSocket,
+    key: SigningKey,
+    remote_key: VerifyingKey,
+    interval: Duration,
+    max_age: Duration,
+    inner: Mutex<Inner>,
+}
+
+fn hb_payload(node: &str, seq: u64, ts_ms: u64) -> String {
+    format!("HB|{}|{}|{}", node, seq, ts_ms)
+}
+
+fn ack_payload(node: &str, seq: u64, ts_ms: u64, stop: bool) -> String {
+    format!("ACK|{}|{}|{}|{}", node, seq, ts_ms, stop as u8)
+}
+
+impl KillSwitch {
+    pub fn new(
+        node: &str,
+        bind: &str,
+        remote: SocketAddr,
+        key: SigningKey,
+        remote_key: VerifyingKey,
+        interval: Duration,
+        max_age: Duration,
+    ) -> std::io::Result<Arc<Self>> {
+        let socket = UdpSocket::bind(bind)?;
+        socket.set_nonblocking(true)?;
+        Ok(Arc::new(KillSwitch {
+            node: node.to_string(),
+            remote,
+            socket,
+            key,
+            remote_key,
+            interval,
+            max_age,
+            inner: Mutex::new(Inner { armed: false, fired: None, seq: now_ms(), sent: VecDeque::with_capacity(ACK_WINDOW as usize), last_ack: None }),
+        }))
+    }
+
+    // HARMONY_KILLSWITCH_ADDR (stop-channel host:port), HARMONY_KILLSWITCH_KEY (our 32-byte
+    // seed, hex) and HARMONY_KILLSWITCH_PEER_KEY (stop-channel public key, hex).
+    pub fn from_env(node: &str, interval: Duration, max_age: Duration) -> Result<Arc<Self>, String> {
+        let var = |k: &str| std::env::var(k).map_err(|_| format!("{} not set", k));
+        let remote: SocketAddr = var("HARMONY_KILLSWITCH_ADDR")?.parse().map_err(|e| format!("HARMONY_KILLSWITCH_ADDR: {}", e))?;
+        let seed: [u8; 32] = unhex(var("HARMONY_KILLSWITCH_KEY")?.trim())
+            .and_then(|b| b.try_into().ok())
+            .ok_or("HARMONY_KILLSWITCH_KEY is not a 32-byte hex seed")?;
+        let peer: [u8; 32] = unhex(var("HARMONY_KILLSWITCH_PEER_KEY")?.trim())
+            .and_then(|b| b.try_into().ok())
+            .ok_or("HARMONY_KILLSWITCH_PEER_KEY is not a 32-byte hex key")?;
+        let remote_key = VerifyingKey::from_bytes(&peer).map_err(|_| "HARMONY_KILLSWITCH_PEER_KEY is not a valid key")?;
+        let bind = if remote.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" };
+        KillSwitch::new(node, bind, remote, SigningKey::from_bytes(&seed), remote_key, interval, max_age).map_err(|e| format!("kill switch socket: {}", e))
+    }
+
+    // Arming clears a previous fire; GO still waits for the first valid ack.
+    pub fn arm(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.armed = true;
+        inner.fired = None;
+        inner.last_ack = None;
+    }
+
+    // Local stop: latches immediately and tells the stop-channel so peers see it too.
+    pub fn fire(&self, by: &str) {
+        self.inner.lock().unwrap().fired = Some((by.to_string(), now_ms()));
+        let ts = now_ms();
+        let payload = format!("STOP|{}|{}|{}", self.node, by, ts);
+        let sig = self.key.sign(payload.as_bytes());
+        let msg = format!("STOP {} {} {} {}", self.node, by, ts, hex(&sig.to_bytes()));
+        if let Err(e) = self.socket.send_to(msg.as_bytes(), self.remote) {
+            eprintln!("KillSwitch: stop notification failed: {}", e);
+        }
+    }
+
+    pub fn status(&self) -> KillSwitchStatus {
+        let inner = self.inner.lock().unwrap();
+        let last_ack_age = inner.last_ack.map(|at| at.elapsed());
+        match (&inner.fired, inner.armed) {
+            (Some((by, at_ms)), _) => KillSwitchStatus::Fired { by: by.clone(), at_ms: *at_ms },
+            (None, false) => KillSwitchStatus::Disarmed,
+            (None, true) if last_ack_age.is_some_and(|age| age <= self.max_age) => KillSwitchStatus::Armed { last_ack_age },
+            (None, true) => KillSwitchStatus::Stale { last_ack_age },
+        }
+    }
+
+    // kill_switch_reachable: armed, not fired, heartbeat fresh.
+    pub fn reachable(&self) -> bool {
+        matches!(self.status(), KillSwitchStatus::Armed { .. })
+    }
+
+    fn send_heartbeat(&self) {
+        let ts = now_ms();
+        let seq = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.seq += 1;
+            let seq = inner.seq;
+            if inner.sent.len() == ACK_WINDOW as usize {
+                inner.sent.pop_front();
+            }
+            inner.sent.push_back((seq, ts));
+            seq
+        };
+        let sig = self.key.sign(hb_payload(&self.node, seq, ts).as_bytes());
+        let msg = format!("HB {} {} {} {}", self.node, seq, ts, hex(&sig.to_bytes()));
+        if let Err(e) = self.socket.send_to(msg.as_bytes(), self.remote) {
+            eprintln!("KillSwitch: heartbeat send failed: {}", e);
+        }
+    }
+
+    fn receive_acks(&self) {
+        let mut buf = [0u8; 512];
+        while let Ok((n, from)) = self.socket.recv_from(&mut buf) {
+            if from != self.remote {
+                continue;
+            }
+            match self.verify_ack(&String::from_utf8_lossy(&buf[..n])) {
+                Ok(true) => self.inner.lock().unwrap().fired = Some(("stop-channel".to_string(), now_ms())),
+                Ok(false) => {}
+                Err(e) => eprintln!("KillSwitch: rejected ack: {}", e),
+            }
+        }
+    }
+
+    // Ok(stop) for a valid ack of one of our recent heartbeats.
+    fn verify_ack(&self, msg: &str) -> Result<bool, String> {
+        let f: Vec<&str> = msg.split_whitespace().collect();
+        let [tag, node, seq, ts, stop, sig] = f.as_slice() else { return Err("malformed".into()) };
+        if *tag != "ACK" || *node != self.node {
+            return Err(format!("not an ack for {}", self.node));
+        }
+        let seq: u64 = seq.parse().map_err(|_| "bad seq")?;
+        let ts: u64 = ts.parse().map_err(|_| "bad timestamp")?;
+        let stop = match *stop {
+            "0" => false,
+            "1" => true,
+            _ => return Err("bad stop flag".into()),
+        };
+        let sig: [u8; 64] = unhex(sig).and_then(|b| b.try_into().ok()).ok_or("bad signature encoding")?;
+        self.remote_key
+            .verify(ack_payload(node, seq, ts, stop).as_bytes(), &Signature::from_bytes(&sig))
+            .map_err(|_| "signature does not verify".to_string())?;
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.sent.contains(&(seq, ts)) {
+            return Err(format!("ack for seq {} ts {} matches no recent heartbeat (current {})", seq, ts, inner.seq));
+        }
+        inner.last_ack = Some(Instant::now());
+        Ok(stop)
+    }
+}
+
+// Heartbeat runs on its own thread so a stalled evaluation loop cannot keep it looking alive
+// (the ack only proves the channel; the loop's own liveness is the health probe's job).
+pub fn spawn_heartbeat(ks: Arc<KillSwitch>) {
+    thread::spawn(move || loop {
+        ks.send_heartbeat();
+        let deadline = Instant::now() + ks.interval;
+        while Instant::now() < deadline {
+            ks.receive_acks();
+            thread::sleep(Duration::from_millis(10));
+        }
+    });
+}
+
+// kill_switch_reachable as a CH check; anything but a fresh, armed heartbeat is a critical failure.
+pub struct KillSwitchCheck(pub Arc<KillSwitch>);
+
+#[async_trait]
+impl CHCheck for KillSwitchCheck {
+    fn name(&self) -> &str {
+        "kill_switch_reachable"
+    }
+
+    async fn check(&self) -> CheckOutcome {
+        match self.0.status() {
+            KillSwitchStatus::Armed { .. } => CheckOutcome::pass(),
+            KillSwitchStatus::Disarmed => CheckOutcome::fail("kill switch disarmed"),
+            KillSwitchStatus::Stale { last_ack_age: Some(age) } => CheckOutcome::fail(&format!("heartbeat stale {:.1}s", age.as_secs_f64())),
+            KillSwitchStatus::Stale { last_ack_age: None } => CheckOutcome::fail("no heartbeat ack since arming"),
+            KillSwitchStatus::Fired { by, at_ms } => CheckOutcome::fail(&format!("fired by {} at {}", by, at_ms)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn switch(peer: &SigningKey) -> Arc<KillSwitch> {
+        let remote = "127.0.0.1:9".parse().unwrap();
+        let ks = KillSwitch::new("node-a", "127.0.0.1:0", remote, SigningKey::from_bytes(&[1; 32]), peer.verifying_key(), Duration::from_millis(50), Duration::from_secs(5));
+        ks.unwrap()
+    }
+
+    fn ack(key: &SigningKey, node: &str, seq: u64, ts: u64, stop: bool) -> String {
+        let sig = key.sign(ack_payload(node, seq, ts, stop).as_bytes());
+        format!("ACK {} {} {} {} {}", node, seq, ts, stop as u8, hex(&sig.to_bytes()))
+    }
+
+    fn last_sent(ks: &KillSwitch) -> (u64, u64) {
+        *ks.inner.lock().unwrap().sent.back().unwrap()
+    }
+
+    #[test]
+    fn armed_only_after_a_valid_ack() {
+        let peer = SigningKey::from_bytes(&[2; 32]);
+        let ks = switch(&peer);
+        assert_eq!(ks.status(), KillSwitchStatus::Disarmed);
+        ks.arm();
+        assert_eq!(ks.status(), KillSwitchStatus::Stale { last_ack_age: None });
+        ks.send_heartbeat();
+        let (seq, ts) = last_sent(&ks);
+        assert_eq!(ks.verify_ack(&ack(&peer, "node-a", seq, ts, false)), Ok(false));
+        assert!(ks.reachable());
+    }
+
+    #[test]
+    fn rejects_forged_foreign_and_unsolicited_acks() {
+        let peer = SigningKey::from_bytes(&[2; 32]);
+        let ks = switch(&peer);
+        ks.arm();
+        ks.send_heartbeat();
+        let (seq, ts) = last_sent(&ks);
+        let forger = SigningKey::from_bytes(&[3; 32]);
+        assert_eq!(ks.verify_ack(&ack(&forger, "node-a", seq, ts, false)), Err("signature does not verify".into()));
+        assert!(ks.verify_ack(&ack(&peer, "node-b", seq, ts, false)).is_err());
+        // A correctly signed ack for a heartbeat this process never sent, e.g. one captured
+        // before a restart.
+        assert!(ks.verify_ack(&ack(&peer, "node-a", seq - 100, ts, false)).unwrap_err().contains("matches no recent heartbeat"));
+        assert!(ks.verify_ack(&ack(&peer, "node-a", seq, ts + 1, false)).is_err());
+        assert!(ks.verify_ack("ACK node-a 1 2 0").is_err());
+        assert!(!ks.reachable());
+    }
+
+    #[test]
+    fn acks_older_than_the_window_are_rejected() {
+        let peer = SigningKey::from_bytes(&[2; 32]);
+        let ks = switch(&peer);
+        ks.send_heartbeat();
+        let (seq, ts) = last_sent(&ks);
+        for _ in 0..ACK_WINDOW {
+            ks.send_heartbeat();
+        }
+        assert!(ks.verify_ack(&ack(&peer, "node-a", seq, ts, false)).is_err());
+    }
+
+    #[test]
+    fn stop_ack_fires_and_latches_until_rearmed() {
+        let peer = SigningKey::from_bytes(&[2; 32]);
+        let ks = switch(&peer);
+        ks.arm();
+        ks.send_heartbeat();
+        let (seq, ts) = last_sent(&ks);
+        assert_eq!(ks.verify_ack(&ack(&peer, "node-a", seq, ts, true)), Ok(true));
+        ks.fire("stop-channel");
+        assert!(matches!(ks.status(), KillSwitchStatus::Fired { .. }));
+        ks.send_heartbeat();
+        let (seq, ts) = last_sent(&ks);
+        ks.verify_ack(&ack(&peer, "node-a", seq, ts, false)).unwrap();
+        assert!(!ks.reachable());
+        ks.arm();
+        assert_eq!(ks.status(), KillSwitchStatus::Stale { last_ack_age: None });
+    }
+}
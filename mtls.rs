@@ -0,0 +1,211 @@
+//! MTLS.rs - Unified mutual-TLS layer with SPIFFE identities and hot certificate rotation (forbid unsafe)
+//!
+//! Every network interface takes its rustls configs from one `MtlsLayer`. `poll_rotation`
+//! reloads the PEM files when they change (cert-manager / SPIRE agent rewrite them in place);
+//! new handshakes pick up the new identity while established connections keep theirs, so the
+//! decision stream is never dropped. `certs_fresh` is the check_ch condition.
+#![forbid(unsafe_code)]
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{ClientConfig, RootCertStore, ServerConfig};
+use x509_parser::extensions::GeneralName;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::crypto_policy::{require_fips, tls_provider};
+
+pub struct MtlsPaths {
+    pub cert_chain: PathBuf,
+    pub private_key: PathBuf,
+    pub trust_bundle: PathBuf,
+}
+
+// A SPIFFE ID is `spiffe://<trust-domain>/<path>`; peers are authorised by trust domain
+// and, optionally, an exact workload path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpiffeId {
+    pub trust_domain: String,
+    pub path: String,
+}
+
+impl SpiffeId {
+    pub fn parse(uri: &str) -> Option<SpiffeId> {
+        let rest = uri.strip_prefix("spiffe://")?;
+        let (domain, path) = rest.split_once('/').unwrap_or((rest, ""));
+        if domain.is_empty() {
+            return None;
+        }
+        Some(SpiffeId { trust_domain: domain.to_ascii_lowercase(), path: format!("/{}", path) })
+    }
+}
+
+struct Loaded {
+    server: Arc<ServerConfig>,
+    client: Arc<ClientConfig>,
+    identity: SpiffeId,
+    not_after: u64,
+    modified: (SystemTime, SystemTime, SystemTime),
+}
+
+pub struct MtlsLayer {
+    paths: MtlsPaths,
+    min_remaining: Duration,
+    current: RwLock<Loaded>,
+}
+
+fn read_certs(path: &PathBuf) -> Result<Vec<CertificateDer<'static>>, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let certs: Vec<_> = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("{}: no certificates", path.display()));
+    }
+    Ok(certs)
+}
+
+fn read_key(path: &PathBuf) -> Result<PrivateKeyDer<'static>, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| format!("{}: {}", path.display(), e))?
+        .ok_or_else(|| format!("{}: no private key", path.display()))
+}
+
+// (SPIFFE ID from the URI SAN, notAfter as unix seconds) of a leaf certificate.
+pub fn leaf_identity(der: &[u8]) -> Result<(SpiffeId, u64), String> {
+    let (_, cert) = X509Certificate::from_der(der).map_err(|e| format!("leaf certificate: {}", e))?;
+    let not_after = cert.validity().not_after.timestamp().max(0) as u64;
+    let san = cert.subject_alternative_name().map_err(|e| format!("leaf SAN: {}", e))?;
+    let id = san
+        .into_iter()
+        .flat_map(|ext| ext.value.general_names.iter())
+        .find_map(|n| match n {
+            GeneralName::URI(uri) => SpiffeId::parse(uri),
+            _ => None,
+        })
+        .ok_or("leaf certificate has no spiffe:// URI SAN")?;
+    Ok((id, not_after))
+}
+
+fn modified(paths: &MtlsPaths) -> (SystemTime, SystemTime, SystemTime) {
+    let m = |p: &PathBuf| fs::metadata(p).and_then(|m| m.modified()).unwrap_or(UNIX_EPOCH);
+    (m(&paths.cert_chain), m(&paths.private_key), m(&paths.trust_bundle))
+}
+
+fn load(paths: &MtlsPaths) -> Result<Loaded, String> {
+    let stamp = modified(paths);
+    let chain = read_certs(&paths.cert_chain)?;
+    let (identity, not_after) = leaf_identity(&chain[0])?;
+    let mut roots = RootCertStore::empty();
+    for ca in read_certs(&paths.trust_bundle)? {
+        roots.add(ca).map_err(|e| format!("{}: {}", paths.trust_bundle.display(), e))?;
+    }
+    let roots = Arc::new(roots);
+    let provider = tls_provider();
+    let verifier = WebPkiClientVerifier::builder_with_provider(roots.clone(), provider.clone())
+        .build()
+        .map_err(|e| e.to_string())?;
+    let server = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(chain.clone(), read_key(&paths.private_key)?)
+        .map_err(|e| e.to_string())?;
+    let client = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_root_certificates(roots)
+        .with_client_auth_cert(chain, read_key(&paths.private_key)?)
+        .map_err(|e| e.to_string())?;
+    require_fips("mTLS server config", server.fips())?;
+    require_fips("mTLS client config", client.fips())?;
+    Ok(Loaded { server: Arc::new(server), client: Arc::new(client), identity, not_after, modified: stamp })
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+impl MtlsLayer {
+    pub fn new(paths: MtlsPaths, min_remaining: Duration) -> Result<Self, String> {
+        let loaded = load(&paths)?;
+        Ok(MtlsLayer { paths, min_remaining, current: RwLock::new(loaded) })
+    }
+
+    // Configs for the next handshake; callers fetch per connection, not once at startup.
+    pub fn server_config(&self) -> Arc<ServerConfig> {
+        self.current.read().unwrap().server.clone()
+    }
+
+    pub fn client_config(&self) -> Arc<ClientConfig> {
+        self.current.read().unwrap().client.clone()
+    }
+
+    pub fn identity(&self) -> SpiffeId {
+        self.current.read().unwrap().identity.clone()
+    }
+
+    // Reloads if any file changed. A half-written or invalid rotation keeps the old
+    // identity in service and reports the error; Ok(true) means a new identity is live.
+    pub fn poll_rotation(&self) -> Result<bool, String> {
+        if modified(&self.paths) == self.current.read().unwrap().modified {
+            return Ok(false);
+        }
+        let loaded = load(&self.paths)?;
+        *self.current.write().unwrap() = loaded;
+        Ok(true)
+    }
+
+    // check_ch condition: false once the live certificate is within `min_remaining` of expiry.
+    pub fn certs_fresh(&self) -> bool {
+        let not_after = self.current.read().unwrap().not_after;
+        now_secs().saturating_add(self.min_remaining.as_secs()) < not_after
+    }
+}
+
+// Post-handshake authorisation: the peer's leaf must carry a SPIFFE ID in `trust_domain`
+// and, if `allowed_paths` is non-empty, one of those workload paths.
+pub fn authorize_peer(peer: Option<&[CertificateDer<'_>]>, trust_domain: &str, allowed_paths: &[&str]) -> Result<SpiffeId, String> {
+    let leaf = peer.and_then(|c| c.first()).ok_or("peer presented no certificate")?;
+    let (id, _) = leaf_identity(leaf)?;
+    if !id.trust_domain.eq_ignore_ascii_case(trust_domain) {
+        return Err(format!("peer trust domain {} is not {}", id.trust_domain, trust_domain));
+    }
+    if !allowed_paths.is_empty() && !allowed_paths.contains(&id.path.as_str()) {
+        return Err(format!("peer workload {} is not authorised", id.path));
+    }
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_spiffe_ids() {
+        let id = SpiffeId::parse("spiffe://Plant.Example/edge/oilgas-7").unwrap();
+        assert_eq!(id, SpiffeId { trust_domain: "plant.example".into(), path: "/edge/oilgas-7".into() });
+        assert_eq!(SpiffeId::parse("spiffe://plant.example").unwrap().path, "/");
+        assert_eq!(SpiffeId::parse("spiffe:///edge"), None);
+        assert_eq!(SpiffeId::parse("https://plant.example/edge"), None);
+    }
+
+    #[test]
+    fn a_peer_without_a_certificate_is_refused() {
+        assert!(authorize_peer(None, "plant.example", &[]).is_err());
+        assert!(authorize_peer(Some(&[]), "plant.example", &[]).is_err());
+    }
+
+    #[test]
+    fn a_missing_identity_does_not_load() {
+        let dir = std::env::temp_dir().join("mtls-missing-identity");
+        let paths = MtlsPaths { cert_chain: dir.join("cert.pem"), private_key: dir.join("key.pem"), trust_bundle: dir.join("bundle.pem") };
+        let err = MtlsLayer::new(paths, Duration::from_secs(60)).err().unwrap();
+        assert!(err.contains("cert.pem") || err.contains("bundle.pem"), "{}", err);
+    }
+}
@@ -0,0 +1,54 @@
+//! Mu_Conformance.rs - Checks mu implementations against the golden vectors and invariants (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::time::Duration;
+use std::{env, process};
+
+mod decision_kernel;
+mod golden_vectors;
+mod harmony_test;
+mod invariants;
+use golden_vectors::{check_conformance, load_golden, ulps_f64, GOLDEN_PATH, REFERENCE_F64};
+use harmony_test::{MockScoreProvider, TestEngine};
+
+// The mu integrators' tests run against: one TestEngine step with a constant provider per score.
+fn test_engine_mu(weights: &[f64], scores: &[f64]) -> f64 {
+    let mut engine = TestEngine::new(Duration::from_millis(100), 0.0, weights.to_vec(), "none");
+    engine.providers = scores.iter().enumerate().map(|(i, &s)| MockScoreProvider::constant(&format!("s{}", i), s)).collect();
+    engine.step();
+    engine.decisions[0].2
+}
+
+fn main() {
+    let path = env::args().nth(1).unwrap_or_else(|| GOLDEN_PATH.to_string());
+    let vectors = match load_golden(&path) {
+        Ok(v) if !v.is_empty() => v,
+        Ok(_) => {
+            eprintln!("{}: no vectors", path);
+            process::exit(2);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+    let mut failures = 0;
+    for (name, mu) in [("decision_kernel", decision_kernel::weighted_mu as fn(&[f64], &[f64]) -> f64), ("harmony_test", test_engine_mu)] {
+        let failed = check_conformance(&vectors, REFERENCE_F64, mu);
+        for f in &failed {
+            eprintln!("{}: {} expected {:e} got {:e} ({} ulps)", name, f.id, f.expected, f.actual, ulps_f64(f.expected, f.actual));
+        }
+        println!("{}: {}/{} golden vectors within {:?}", name, vectors.len() - failed.len(), vectors.len(), REFERENCE_F64);
+        failures += failed.len();
+    }
+    // The vectors double as invariant contexts: every one must also hold monotonicity,
+    // permutation invariance, bounds and clamping.
+    let mut violations = 0;
+    for v in &vectors {
+        if let Err(violation) = invariants::check_all(&decision_kernel::weighted_mu, &v.weights, &v.scores) {
+            eprintln!("decision_kernel: {} violates {:?}", v.id, violation);
+            violations += 1;
+        }
+    }
+    println!("decision_kernel: {}/{} vectors hold every invariant", vectors.len() - violations, vectors.len());
+    process::exit(if failures == 0 && violations == 0 { 0 } else { 1 });
+}
@@ -1,53 +1,463 @@
 //! OilGas_Edge.rs - Zone-2 explosive-proof edge node (forbid unsafe)
 #![forbid(unsafe_code)]
-use std::time::Duration;
-const HARMONY_THRESHOLD: f64 = 0.9995;
-const MIN_SCORE: f64 = 1e-12;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-pub struct OilGasContext {
-    pub scores: Vec<f64>,
-    pub weights: Vec<f64>,
+mod catalog;
+mod clock_sync;
+mod core;
+mod crypto_policy;
+mod decision;
+mod decision_kernel;
+mod diagnostics;
+mod gossip;
+mod ingest;
+mod mtls;
+mod plugin;
+mod rbac;
+mod replay_guard;
+mod rt_hooks;
+mod self_ids;
+mod units;
+#[cfg(feature = "wasm-rules")]
+mod wasm_rules;
+use crate::catalog::IncidentCatalog;
+use crate::core::availability::{AvailabilityBudget, BudgetAlert};
+use crate::core::baseline::{Baseline, Baselines, Period};
+use crate::core::budget::{BudgetLedger, Meter};
+use crate::core::calibration::Calibrations;
+use crate::core::checks::{CheckRegistry, FnCheck};
+use crate::core::filter::ScoreFilters;
+use crate::core::harmony::{Conditions, Decision, Evaluation, HarmonyContext, Severity};
+use crate::core::monitor::HarmonyMonitor;
+use crate::core::profile::{hm, ProfileSchedule, Window, EVERY_DAY};
+use crate::core::source::{FnSource, SourceError, SourceSet, StalePolicy, SyncSource, TimestampedScore};
+use crate::core::trend::MuTrend;
+use diagnostics::Diagnostics;
+use gossip::GossipNode;
+use ingest::{Admission, IngestQueue, MetricKind};
+use mtls::{MtlsLayer, MtlsPaths};
+use plugin::Domain;
+use replay_guard::ReplayGuard;
+use rt_hooks::RtOptions;
+use self_ids::SelfIds;
+use units::Unit;
+
+const NEIGHBOR_FLOOR: f64 = 0.99;
+const NEIGHBOR_MAX_AGE: Duration = Duration::from_secs(30);
+const IDS_WINDOW_CYCLES: u64 = 50; // 10 s at 5 Hz
+const MTLS_POLL_CYCLES: u64 = 50;
+// The node's identity must outlive the next rotation by a day, or certs_fresh fails.
+const CERT_MIN_REMAINING: Duration = Duration::from_secs(86_400);
+const TICK: Duration = Duration::from_millis(200); // 5 Hz
+// A provider averaging more than this share of the tick is reported on overrun.
+const HOG_FRACTION: f64 = 0.25;
+// Shut-in time the well's business case allows per rolling 30 days; reported, never enforced.
+const AVAILABILITY_WINDOW: Duration = Duration::from_secs(30 * 86_400);
+const AVAILABILITY_BUDGET: Duration = Duration::from_secs(8 * 3_600);
+
+// Only critical failures force HALT; majors cap at CAUTION and advisories just alert.
+fn ch_checks() -> CheckRegistry {
+    let mut checks = CheckRegistry::new();
+    checks
+        .register(Box::new(FnCheck::new("no_permit_violation", no_permit_violation)))
+        .register(Box::new(FnCheck::new("bop_interlock_ok", bop_interlock_ok)))
+        .register(Box::new(FnCheck::new("h2s_ok", h2s_ok)))
+        .register(Box::new(FnCheck::new("cyber_threat_ok", cyber_threat_ok)))
+        .register(Box::new(FnCheck::new("insurance_ok", insurance_ok).severity(Severity::Advisory)));
+    checks
+}
+
+// One source per weighted channel, in weight order; swap in real telemetry backends here.
+// A reading frozen for five ticks (historian or RTU last-value) degrades the site to CAUTION
+// rather than halting production; the stale value still enters mu.
+fn score_sources(flare: Arc<Mutex<TimestampedScore>>) -> SourceSet {
+    let mut sources = SourceSet::new(Duration::from_millis(100));
+    sources
+        .max_age(Duration::from_secs(1), StalePolicy::Degrade)
+        .register(Box::new(FnSource::new("wellhead_coherence", read_wellhead_coherence)))
+        .register(Box::new(FnSource::new("pipeline_health", read_pipeline_health)))
+        .register(Box::new(SyncSource::new("flare_stability", move || *flare.lock().unwrap()).endpoint(FLARE_ANALYZER)))
+        .register(Box::new(FnSource::new("cyber_health", read_cyber_health)))
+        .register(Box::new(FnSource::new("operator_alertness", read_operator_alertness)));
+    sources
+}
+
+// flare_stability reads the flare's combustion efficiency in percent (FLARE_METRIC, 0-100),
+// never a [0, 1] score: the curve below is what makes it one. Below 90% the flare is
+// venting rather than burning; 98% is the permit's destruction efficiency. A file of
+// `calibrate.<channel>` lines named by HARMONY_CALIBRATION replaces the built-in curves.
+const FLARE_CALIBRATION: &str = "piecewise: 90:0, 96:0.9, 98:0.995, 99.5:1";
+
+fn score_calibrations(names: &[&str]) -> Calibrations {
+    match std::env::var("HARMONY_CALIBRATION") {
+        Ok(path) => Calibrations::load(&path, names).expect("calibration file"),
+        Err(_) => Calibrations::parse(&[("flare_stability".into(), FLARE_CALIBRATION.into())], names).expect("flare calibration"),
+    }
+}
+
+// The flare analyzer publishes on its own cadence, in whatever unit it is configured for,
+// declared as `flare_analyzer=<unit>` in HARMONY_SOURCE_UNITS (default %). Its readings pass
+// through the ingestion queue, which converts them into FLARE_METRIC's percent; a unit of
+// another dimension stops startup rather than reaching the curve above.
+const FLARE_ANALYZER: &str = "flare_analyzer";
+const FLARE_METRIC: &str = "flare_efficiency";
+const FLARE_ANALYZER_PERIOD: Duration = Duration::from_millis(500);
+// Analyzer messages stamped further than this from local time, or replayed, are dropped, and
+// telemetry_authentic stays failed for the hold after the last one.
+const FLARE_MAX_SKEW: Duration = Duration::from_secs(2);
+const REPLAY_VIOLATION_HOLD: Duration = Duration::from_secs(60);
+const INGEST_CAPACITY: usize = 256;
+const INGEST_HIGH_WATER: f64 = 0.75;
+
+// One pushed analyzer reading, in the analyzer's declared unit.
+struct FlareMessage {
+    seq: u64,
+    timestamp_ms: u64,
+    nonce: u64,
+    value: f64,
+}
+
+fn ingest_queue() -> Result<IngestQueue, String> {
+    let mut queue = IngestQueue::new(INGEST_CAPACITY, INGEST_HIGH_WATER);
+    queue.register_in(FLARE_METRIC, MetricKind::Gauge, Unit::Percent);
+    let declared = std::env::var("HARMONY_SOURCE_UNITS").unwrap_or_else(|_| format!("{}=%", FLARE_ANALYZER));
+    for pair in declared.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (source, unit) = pair.split_once('=').ok_or_else(|| format!("HARMONY_SOURCE_UNITS: {:?} is not source=unit", pair))?;
+        let metric = match source.trim() {
+            FLARE_ANALYZER => FLARE_METRIC,
+            other => return Err(format!("HARMONY_SOURCE_UNITS: unknown source {:?}", other)),
+        };
+        queue.bind(source.trim(), metric, Some(Unit::parse(unit)?))?;
+    }
+    Ok(queue)
 }
 
-impl OilGasContext {
-    pub fn calculate_mu(&self) -> f64 {
-        let mut log_sum = 0.0;
-        for (w, s) in self.weights.iter().zip(self.scores.iter()) {
-            let s_clipped = s.clamp(MIN_SCORE, 1.0);
-            log_sum += w * s_clipped.ln();
+const WELLHEAD: usize = 0; // wellhead_coherence channel
+const INNOVATION_LOG_SIGMAS: f64 = 3.0;
+
+// Wellhead, pipeline and flare readings are noisy process signals; cyber and operator channels
+// stay raw. A 1 s half-life (5 cycles) rides out single-sample spikes. The wellhead reading is
+// estimated rather than averaged: drift variance 1e-8 per cycle against 1e-7 per reading.
+fn score_filters(channels: usize) -> ScoreFilters {
+    ScoreFilters::new(channels)
+        .kalman(WELLHEAD, 1e-8, 1e-7)
+        .ewma(1, Duration::from_secs(1))
+        .ewma(2, Duration::from_secs(1))
+}
+
+const FLARE: usize = 2; // flare_stability channel
+// Flare stability follows the daily wind cycle; scored against its learned half-hour baseline,
+// with at most 0.001 of a slot's usual shortfall forgiven.
+const FLARE_MAX_ALLOWANCE: f64 = 0.001;
+
+fn score_baselines(channels: usize) -> Baselines {
+    let offset = std::env::var("HARMONY_SITE_UTC_OFFSET_MIN").ok().and_then(|v| v.parse().ok()).unwrap_or(0);
+    let flare = Baseline::new(Period::Daily, Duration::from_secs(30 * 60), FLARE_MAX_ALLOWANCE).expect("flare baseline");
+    Baselines::new(channels).learn(FLARE, flare.utc_offset(offset))
+}
+
+const DAY_WEIGHTS: [f64; 5] = [0.30, 0.25, 0.20, 0.15, 0.10];
+// Night shift (19:00-07:00 site time) doubles operator_alertness's weight, taking it evenly
+// from the process channels, so a drowsy crew pulls mu down as hard as a flaring wellhead.
+const NIGHT_WEIGHTS: [f64; 5] = [0.275, 0.225, 0.175, 0.125, 0.20];
+
+fn threshold_profiles(channels: usize) -> ProfileSchedule {
+    let context = |weights: &[f64]| HarmonyContext::builder().weights(weights.to_vec()).channels(channels).build().expect("harmony context");
+    let offset = std::env::var("HARMONY_SITE_UTC_OFFSET_MIN").ok().and_then(|v| v.parse().ok()).unwrap_or(0);
+    ProfileSchedule::new("day_shift", context(&DAY_WEIGHTS))
+        .utc_offset(offset)
+        .profile("night_shift", context(&NIGHT_WEIGHTS), &[Window::new(EVERY_DAY, hm(19, 0), hm(7, 0)).expect("night window")])
+        .expect("threshold profiles")
+}
+
+// The node's SPIFFE identity, as written by cert-manager or the SPIRE agent into
+// HARMONY_MTLS_DIR (default /etc/harmony/tls): cert.pem, key.pem and bundle.pem.
+fn mtls_layer() -> Result<MtlsLayer, String> {
+    let dir = PathBuf::from(std::env::var("HARMONY_MTLS_DIR").unwrap_or_else(|_| "/etc/harmony/tls".into()));
+    let paths = MtlsPaths { cert_chain: dir.join("cert.pem"), private_key: dir.join("key.pem"), trust_bundle: dir.join("bundle.pem") };
+    MtlsLayer::new(paths, CERT_MIN_REMAINING)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// The choke is held from the first HALT cycle until the monitor is back at GO.
+struct OilGas {
+    sources: SourceSet,
+    checks: CheckRegistry,
+    filters: ScoreFilters,
+    baselines: Baselines,
+    // Site rules run after the built-in filters and may only tighten the scores they are given.
+    #[cfg(feature = "wasm-rules")]
+    site_rules: Vec<wasm_rules::WasmRule>,
+    gossip: Option<GossipNode>,
+    // Without a loaded identity the node cannot join the neighborhood's authenticated links.
+    mtls: Option<MtlsLayer>,
+    // Published to the neighborhood with this cycle's cyber_health score.
+    local_weather: f64,
+    ids: SelfIds,
+    diag: Arc<Mutex<Diagnostics>>,
+    catalog: Arc<Mutex<IncidentCatalog>>,
+    // Shared with the analyzer task, which checks every pushed message against it.
+    replay: Arc<Mutex<ReplayGuard>>,
+    choke_held: bool,
+}
+
+impl OilGas {
+    // Shares this cycle's cyber_health and the site weather, and takes in the neighbors'.
+    fn exchange_gossip(&mut self, scores: &[f64]) {
+        let Some(gossip) = self.gossip.as_mut() else { return };
+        gossip.publish_local("cyber_health", scores[3]);
+        gossip.publish_local("weather", self.local_weather);
+        let gossip_start = Instant::now();
+        let gossip_result = gossip.round();
+        self.ids.observe_call("gossip_round");
+        if let Err(e) = &gossip_result {
+            eprintln!("OilGas: gossip round failed: {}", e);
+        }
+        self.diag.lock().unwrap().record_provider("gossip", gossip_start.elapsed(), gossip_result.err().map(|e| e.to_string()));
+    }
+
+    // Conditions beyond the registry's checks. Without a gossip socket the neighborhood is
+    // unknown, and both neighbor conditions fail; without an identity, so does certs_fresh.
+    fn check_ch(&self, conditions: &mut Conditions) {
+        let neighbor_ok = |key: &str| self.gossip.as_ref().is_some_and(|g| g.neighborhood_ok(key, NEIGHBOR_FLOOR, NEIGHBOR_MAX_AGE));
+        conditions
+            .record("engine_behaviour", Severity::Major, self.ids.process_behaviour_ok())
+            .record("telemetry_authentic", Severity::Critical, self.replay.lock().unwrap().telemetry_authentic())
+            .record("neighbor_cyber", Severity::Critical, neighbor_ok("cyber_health"))
+            .record("neighbor_weather", Severity::Major, neighbor_ok("weather"))
+            .record("certs_fresh", Severity::Major, self.mtls.as_ref().is_some_and(MtlsLayer::certs_fresh));
+        #[cfg(feature = "wasm-rules")]
+        for rule in &self.site_rules {
+            rule.record(conditions);
         }
-        log_sum.exp()
     }
 }
 
-pub async fn check_ch() -> bool {
-    no_permit_violation().await &&
-    bop_interlock_ok().await &&
-    h2s_ok().await &&
-    cyber_threat_ok().await &&
-    insurance_ok().await
+impl Domain for OilGas {
+    fn name(&self) -> &str { "oilgas" }
+    fn tick(&self) -> Duration { TICK }
+    fn weights(&self) -> Vec<f64> { DAY_WEIGHTS.to_vec() }
+    fn sources(&self) -> &SourceSet { &self.sources }
+    fn checks(&self) -> &CheckRegistry { &self.checks }
+
+    fn safe_state(&mut self) {
+        if !self.choke_held {
+            self.catalog.lock().unwrap().autoheal(decision::now_ms(), "hold_choke", Ok(()));
+        }
+        self.choke_held = true;
+    }
+
+    fn in_safe_state(&self) -> bool {
+        self.choke_held
+    }
+
+    fn refine(&mut self, scores: &mut [f64], errors: &mut Vec<(usize, SourceError)>, conditions: &mut Conditions) {
+        self.filters.apply(scores, errors, Instant::now());
+        self.baselines.apply(scores, errors, unix_now());
+        #[cfg(feature = "wasm-rules")]
+        for rule in self.site_rules.iter_mut() {
+            rule.apply(scores);
+        }
+        if let Some(inn) = self.filters.innovation(WELLHEAD).filter(|i| i.sigmas() > INNOVATION_LOG_SIGMAS) {
+            eprintln!("OilGas: wellhead_coherence reading {:+.6} off prediction ({:.1} sigma), estimate {:.6}", inn.innovation, inn.sigmas(), inn.estimate);
+        }
+        self.exchange_gossip(scores);
+        self.check_ch(conditions);
+    }
+
+    fn decide(&mut self, _scores: &[f64], _errors: &[(usize, SourceError)], _conditions: &Conditions, eval: Evaluation) -> Evaluation {
+        if eval.decision == Decision::GO {
+            self.choke_held = false;
+        }
+        eval
+    }
 }
 
-#[tokio::main]
+// Edge gateway builds (docs/EDGE_GATEWAY_BUILD.md) use the current-thread runtime.
+#[cfg_attr(feature = "edge", tokio::main(flavor = "current_thread"))]
+#[cfg_attr(not(feature = "edge"), tokio::main)]
 async fn main() {
-    let ctx = OilGasContext {
-        scores: vec![0.98, 0.97, 1.0, 0.96, 0.99],
-        weights: vec![0.30, 0.25, 0.20, 0.15, 0.10],
+    // The evaluation loop runs on this thread (block_on), so RT settings applied here cover it.
+    let rt = RtOptions::from_env();
+    rt_hooks::setup("OilGas", &rt);
+    let mut cycle: u64 = 0;
+    let ingest = Arc::new(Mutex::new(ingest_queue().expect("ingest configuration")));
+    // NaN (refused as malformed) until the analyzer's first reading is drained.
+    let flare = Arc::new(Mutex::new(TimestampedScore { value: f64::NAN, at_ms: 0 }));
+    let replay = Arc::new(Mutex::new(ReplayGuard::new(FLARE_MAX_SKEW, REPLAY_VIOLATION_HOLD)));
+    let (analyzer_queue, analyzer_replay) = (ingest.clone(), replay.clone());
+    tokio::spawn(async move {
+        loop {
+            let msg = receive_flare_message().await;
+            match analyzer_replay.lock().unwrap().check(FLARE_ANALYZER, msg.seq, msg.timestamp_ms, msg.nonce) {
+                Err(v) => eprintln!("OilGas: {} message {} rejected: {:?}", FLARE_ANALYZER, msg.seq, v),
+                Ok(()) => {
+                    if analyzer_queue.lock().unwrap().push_from(FLARE_ANALYZER, msg.value, msg.timestamp_ms) == Admission::Unknown {
+                        eprintln!("OilGas: {} is not bound in HARMONY_SOURCE_UNITS; reading dropped", FLARE_ANALYZER);
+                    }
+                }
+            }
+            tokio::time::sleep(FLARE_ANALYZER_PERIOD).await;
+        }
+    });
+    let mut ingested = Vec::with_capacity(INGEST_CAPACITY);
+    let mut sources = score_sources(flare.clone());
+    let ledger = Arc::new(Mutex::new(BudgetLedger::new(TICK)));
+    sources.with_ledger(ledger.clone());
+    let calibrations = score_calibrations(&sources.names());
+    sources.calibrate(&calibrations).expect("calibrations name registered sources");
+    for c in calibrations.iter() {
+        println!("OilGas: {} calibrated {}", c.channel, c.curve);
+    }
+    let mut profiles = threshold_profiles(sources.len());
+    profiles.select(unix_now());
+    println!("OilGas: threshold profile {}", profiles.active());
+    let gossip = match GossipNode::bind(&edge_node_id(), SocketAddr::from(([0, 0, 0, 0], 7946)), gossip_peers(), &["cyber_health", "weather"]) {
+        Ok(g) => Some(g),
+        Err(e) => {
+            eprintln!("OilGas: gossip socket: {}; neighborhood conditions fail until restart", e);
+            None
+        }
+    };
+    let mtls = match mtls_layer() {
+        Ok(m) => Some(m),
+        Err(e) => {
+            eprintln!("OilGas: mTLS identity: {}; certs_fresh fails until restart", e);
+            None
+        }
+    };
+    // Remote troubleshooting without SSH; enabled only when a token hash is provisioned.
+    let diag = Diagnostics::new("unsealed");
+    diag.lock().unwrap().attach_budget(ledger.clone());
+    diag.lock().unwrap().attach_ingest(ingest.clone());
+    if let Some(token_sha256) = std::env::var("HARMONY_DIAG_TOKEN_SHA256").ok().and_then(|h| parse_sha256_hex(&h)) {
+        let addr = std::env::var("HARMONY_DIAG_ADDR").unwrap_or_else(|_| "0.0.0.0:8087".into());
+        diagnostics::serve(&addr, diag.clone(), token_sha256).expect("bind diagnostics listener");
+    }
+    // The two minutes before each HALT, and everything until a minute after it clears.
+    let catalog = Arc::new(Mutex::new(IncidentCatalog::new(&sources.names(), Duration::from_secs(120), Duration::from_secs(60))));
+    diag.lock().unwrap().attach_catalog(catalog.clone());
+    let availability = Arc::new(Mutex::new(AvailabilityBudget::new(AVAILABILITY_WINDOW, AVAILABILITY_BUDGET).expect("availability budget")));
+    diag.lock().unwrap().attach_availability(availability.clone());
+    #[cfg(feature = "wasm-rules")]
+    let site_rules = wasm_rules::load_rules(&std::env::var("HARMONY_WASM_RULES").unwrap_or_default(), wasm_rules::DEFAULT_RULE_LIMITS).expect("wasm rules");
+    #[cfg(feature = "wasm-rules")]
+    for rule in &site_rules {
+        println!("OilGas: site rule {} loaded (sha256 {})", rule.name(), rule.sha256());
+    }
+    let channels = sources.len();
+    let oilgas = OilGas {
+        sources,
+        checks: ch_checks(),
+        filters: score_filters(channels),
+        baselines: score_baselines(channels),
+        #[cfg(feature = "wasm-rules")]
+        site_rules,
+        gossip,
+        mtls,
+        local_weather: 0.0,
+        // One hour of baseline before the node may assert its own behaviour is normal.
+        ids: SelfIds::new(360, 6.0, Duration::from_secs(300)),
+        diag: diag.clone(),
+        catalog: catalog.clone(),
+        replay,
+        choke_held: false,
     };
+    let mut monitor = HarmonyMonitor::new(oilgas).expect("harmony context");
+    monitor.set_context(profiles.context().clone()).expect("threshold profile");
+    // Ten seconds of mu at 5 Hz, extrapolated up to a minute ahead.
+    let mut mu_trend = MuTrend::new(Duration::from_secs(10)).horizon(Duration::from_secs(60));
     loop {
-        let scores = vec![
-            read_wellhead_coherence().await,
-            read_pipeline_health().await,
-            read_flare_stability().await,
-            read_cyber_health().await,
-            read_operator_alertness().await,
-        ];
-        let mu = ctx.calculate_mu();
-        let ch = check_ch().await;
-        match (mu >= HARMONY_THRESHOLD && ch) {
-            true  => println!("OilGas: CONTROL GO"),
-            false => { println!("OilGas: CONTROL HALT – hold choke"); hold_choke().await; }
-        }
-        tokio::time::sleep(Duration::from_millis(200)).await; // 5 Hz
+        let cycle_meter = Meter::start();
+        cycle += 1;
+        if rt.requested() && cycle.is_multiple_of(600) {
+            for e in rt_hooks::verify(&rt).errors {
+                eprintln!("OilGas: RT guarantee lost: {}", e);
+            }
+        }
+        ingest.lock().unwrap().drain_into(&mut ingested);
+        if let Some(s) = ingested.iter().rev().find(|s| s.metric == FLARE_METRIC) {
+            *flare.lock().unwrap() = TimestampedScore { value: s.value, at_ms: s.at_ms };
+        }
+        if monitor.domain().gossip.is_some() {
+            monitor.domain_mut().local_weather = read_local_weather().await;
+        }
+        if cycle.is_multiple_of(MTLS_POLL_CYCLES) {
+            match monitor.domain().mtls.as_ref().map(MtlsLayer::poll_rotation) {
+                Some(Ok(true)) => println!("OilGas: mTLS identity rotated"),
+                Some(Err(e)) => eprintln!("OilGas: mTLS rotation rejected, keeping the current identity: {}", e),
+                _ => {}
+            }
+        }
+        if cycle.is_multiple_of(IDS_WINDOW_CYCLES) {
+            for anomaly in monitor.domain_mut().ids.end_window() {
+                eprintln!("OilGas: anomalous engine behaviour: {:?}", anomaly);
+            }
+        }
+        if let Some(t) = profiles.select(unix_now()) {
+            println!("OilGas: threshold profile {}", t);
+            monitor.set_context(profiles.context().clone()).expect("threshold profile");
+        }
+        let eval = monitor.cycle().await;
+        for (i, e) in monitor.source_errors() {
+            eprintln!("OilGas: score source {} failed: {}", monitor.names()[*i], e);
+        }
+        for name in monitor.conditions().failed(Severity::Advisory) {
+            eprintln!("OilGas: advisory condition {} failed", name);
+        }
+        mu_trend.update(eval.mu, Instant::now());
+        if let Some(eta) = mu_trend.eta(monitor.context().caution_threshold).filter(|_| eval.decision != Decision::HALT) {
+            println!("OilGas: eta_to_halt {:.1} s (mu {:+.6}/s)", eta.as_secs_f64(), mu_trend.slope());
+        }
+        println!("OilGas: dmu/ds {}", monitor.sensitivity());
+        let usage = cycle_meter.stop();
+        let took = usage.wall;
+        {
+            let mut budget = ledger.lock().unwrap();
+            if !budget.record_cycle(usage) {
+                eprintln!("OilGas: cycle overran {:?} budget ({:?}, cpu {:?}); slow providers: {:?}", TICK, took, usage.cpu, budget.hogs(HOG_FRACTION));
+            }
+        }
+        monitor.domain_mut().ids.observe_cycle(took);
+        diag.lock().unwrap().record_cycle(&eval, took);
+        if let Some(id) = catalog.lock().unwrap().observe(decision::now_ms(), &eval, monitor.scores(), monitor.conditions()) {
+            eprintln!("OilGas: incident {} opened", id);
+        }
+        {
+            let mut avail = availability.lock().unwrap();
+            match avail.observe(decision::now_ms(), &eval, monitor.conditions()) {
+                Some(BudgetAlert::Warning) => eprintln!("OilGas: availability budget nearly spent: {}", avail.status()),
+                Some(BudgetAlert::Exhausted) => eprintln!("OilGas: availability budget exhausted, safety gating unchanged: {}", avail.status()),
+                Some(BudgetAlert::Recovered) => eprintln!("OilGas: availability budget recovered: {}", avail.status()),
+                None => {}
+            }
+        }
+        match eval.decision {
+            Decision::GO => println!("OilGas: CONTROL GO"),
+            Decision::CAUTION => println!("OilGas: CONTROL CAUTION – no new operations"),
+            Decision::HALT => {
+                println!("OilGas: CONTROL HALT – hold choke [{}]", monitor.conditions().failure_summary());
+                hold_choke().await;
+            }
+        }
+        tokio::time::sleep(TICK).await;
+    }
+}
+
+fn parse_sha256_hex(h: &str) -> Option<[u8; 32]> {
+    let h = h.trim();
+    if h.len() != 64 {
+        return None;
+    }
+    let mut out = [0u8; 32];
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = u8::from_str_radix(h.get(2 * i..2 * i + 2)?, 16).ok()?;
     }
+    Some(out)
 }
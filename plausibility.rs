@@ -0,0 +1,98 @@
+//! Plausibility.rs - Per-metric bounds, rate-of-change and stuck-at guards on score ingestion (forbid unsafe)
+//!
+//! A sensor that is out of range, moves faster than physically possible, or reports the exact
+//! same value for too long is flagged implausible and enters mu as MIN_SCORE instead of being
+//! trusted. The flag holds until the channel has been plausible again for `recovery`.
+#![forbid(unsafe_code)]
+use std::time::{Duration, Instant};
+
+const MIN_SCORE: f64 = 1e-12;
+
+#[derive(Clone, Debug)]
+pub struct MetricBounds {
+    pub min: f64,
+    pub max: f64,
+    // Largest believable change per second.
+    pub max_rate_per_s: f64,
+    // A real sensor has noise; an unchanged value for this long is treated as stuck.
+    pub max_flat: Duration,
+    pub recovery: Duration,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Implausible {
+    NotFinite,
+    OutOfBounds,
+    RateExceeded,
+    Stuck,
+}
+
+struct ChannelState {
+    last: Option<(f64, Instant)>,
+    flat_since: Option<Instant>,
+    flagged: Option<(Implausible, Instant)>,
+}
+
+pub struct PlausibilityGuard {
+    bounds: Vec<MetricBounds>,
+    channels: Vec<ChannelState>,
+}
+
+impl PlausibilityGuard {
+    pub fn new(bounds: Vec<MetricBounds>) -> Self {
+        let channels = bounds.iter().map(|_| ChannelState { last: None, flat_since: None, flagged: None }).collect();
+        PlausibilityGuard { bounds, channels }
+    }
+
+    fn classify(b: &MetricBounds, st: &mut ChannelState, value: f64, now: Instant) -> Option<Implausible> {
+        if !value.is_finite() {
+            return Some(Implausible::NotFinite);
+        }
+        if value < b.min || value > b.max {
+            return Some(Implausible::OutOfBounds);
+        }
+        let verdict = match st.last {
+            Some((prev, at)) => {
+                let dt = now.duration_since(at).as_secs_f64();
+                if value == prev {
+                    let since = *st.flat_since.get_or_insert(at);
+                    if now.duration_since(since) > b.max_flat { Some(Implausible::Stuck) } else { None }
+                } else {
+                    st.flat_since = None;
+                    if dt > 0.0 && (value - prev).abs() / dt > b.max_rate_per_s { Some(Implausible::RateExceeded) } else { None }
+                }
+            }
+            None => None,
+        };
+        st.last = Some((value, now));
+        verdict
+    }
+
+    // Returns the score to feed into mu for `channel` (MIN_SCORE while flagged).
+    pub fn admit(&mut self, channel: usize, value: f64) -> f64 {
+        let (Some(b), Some(st)) = (self.bounds.get(channel), self.channels.get_mut(channel)) else { return MIN_SCORE };
+        let now = Instant::now();
+        match Self::classify(b, st, value, now) {
+            Some(reason) => {
+                st.flagged = Some((reason, now));
+                MIN_SCORE
+            }
+            None => match st.flagged {
+                Some((_, at)) if now.duration_since(at) < b.recovery => MIN_SCORE,
+                _ => {
+                    st.flagged = None;
+                    value
+                }
+            },
+        }
+    }
+
+    // (channel, reason) for every channel currently flagged, for reporting.
+    pub fn flags(&self) -> Vec<(usize, Implausible)> {
+        self.channels.iter().enumerate().filter_map(|(i, c)| c.flagged.map(|(r, _)| (i, r))).collect()
+    }
+
+    pub fn all_plausible(&self) -> bool {
+        self.channels.iter().all(|c| c.flagged.is_none())
+    }
+}
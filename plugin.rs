@@ -0,0 +1,414 @@
+//! Plugin.rs - Domain trait over async ScoreSources/CHChecks and the plugin conformance suite (forbid unsafe)
+//!
+//! A domain's channels are core::source ScoreSources and its interlocks core::checks CHChecks,
+//! the same types the domain binaries register, so a domain runs unchanged under
+//! HarmonyMonitor, the sr-bridge scheduler and this suite.
+#![forbid(unsafe_code)]
+use std::collections::BTreeSet;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+use tokio::runtime::Runtime;
+
+use crate::core::checks::{CheckRegistry, SyncCheck};
+use crate::core::harmony::{validate_weights, Conditions, Decision, Evaluation, HARMONY_THRESHOLD};
+use crate::core::source::{SourceError, SourceSet, SyncSource};
+
+const SAMPLE_ROUNDS: usize = 50;
+
+pub trait Domain {
+    fn name(&self) -> &str;
+    fn tick(&self) -> Duration;
+    fn weights(&self) -> Vec<f64>;
+    // One source per weighted channel, in weight order.
+    fn sources(&self) -> &SourceSet;
+    fn checks(&self) -> &CheckRegistry;
+    // Drives the domain's safe state; called on every HALT cycle, so it must be idempotent.
+    fn safe_state(&mut self);
+    // Whether the safe state is engaged, as observed from the domain's actuator or its own
+    // record of having driven it; the conformance suite checks it after safe_state().
+    fn in_safe_state(&self) -> bool;
+    // GO threshold the monitor starts with.
+    fn threshold(&self) -> f64 {
+        HARMONY_THRESHOLD
+    }
+    // (min, max) an operator may move the threshold to at runtime. By default it may only be
+    // tightened; a domain that allows loosening declares how far.
+    fn threshold_bounds(&self) -> (f64, f64) {
+        (self.threshold(), 1.0)
+    }
+    // Runs each cycle after sampling and the registry's checks, before evaluation: filters,
+    // baselines and plausibility rewrite `scores` (a rejected channel joins `errors`), and
+    // conditions only the domain can judge are recorded.
+    fn refine(&mut self, _scores: &mut [f64], _errors: &mut Vec<(usize, SourceError)>, _conditions: &mut Conditions) {}
+    // Standard deviation of a channel's score estimate; read when the context decides on mu's
+    // lower bound.
+    fn stddev(&self, _channel: usize) -> f64 {
+        0.0
+    }
+    // The domain's last word on the cycle's evaluation, for a domain that decides on other
+    // evidence as well (core::fusion). Whatever it returns must still honour `conditions`.
+    fn decide(&mut self, _scores: &[f64], _errors: &[(usize, SourceError)], _conditions: &Conditions, eval: Evaluation) -> Evaluation {
+        eval
+    }
+}
+
+// Lets registry-built `Box<dyn Domain>` plugins drive a `HarmonyMonitor`.
+impl<D: Domain + ?Sized> Domain for Box<D> {
+    fn name(&self) -> &str { (**self).name() }
+    fn tick(&self) -> Duration { (**self).tick() }
+    fn weights(&self) -> Vec<f64> { (**self).weights() }
+    fn sources(&self) -> &SourceSet { (**self).sources() }
+    fn checks(&self) -> &CheckRegistry { (**self).checks() }
+    fn safe_state(&mut self) { (**self).safe_state() }
+    fn in_safe_state(&self) -> bool { (**self).in_safe_state() }
+    fn threshold(&self) -> f64 { (**self).threshold() }
+    fn threshold_bounds(&self) -> (f64, f64) { (**self).threshold_bounds() }
+    fn refine(&mut self, scores: &mut [f64], errors: &mut Vec<(usize, SourceError)>, conditions: &mut Conditions) { (**self).refine(scores, errors, conditions) }
+    fn stddev(&self, channel: usize) -> f64 { (**self).stddev(channel) }
+    fn decide(&mut self, scores: &[f64], errors: &[(usize, SourceError)], conditions: &Conditions, eval: Evaluation) -> Evaluation { (**self).decide(scores, errors, conditions, eval) }
+}
+
+#[derive(Debug)]
+pub struct ConformanceResult {
+    pub test: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+// Sources and checks are async; the suite drives them on its own current-thread runtime.
+type ConformanceTest = (&'static str, fn(&mut dyn Domain, &Runtime) -> Result<(), String>);
+
+const SUITE: &[ConformanceTest] = &[
+    ("identity", t_identity),
+    ("weights", t_weights),
+    ("tick", t_tick),
+    ("threshold", t_threshold),
+    ("score_range", t_score_range),
+    ("cycle_budget", t_cycle_budget),
+    ("safe_state_idempotent", t_safe_state),
+];
+
+// Every test runs isolated: a panicking plugin fails that test instead of the kit.
+pub fn run_conformance(domain: &mut dyn Domain) -> Vec<ConformanceResult> {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().expect("tokio runtime");
+    SUITE
+        .iter()
+        .map(|(test, f)| {
+            let outcome = catch_unwind(AssertUnwindSafe(|| f(&mut *domain, &runtime)));
+            let (passed, detail) = match outcome {
+                Ok(Ok(())) => (true, String::new()),
+                Ok(Err(e)) => (false, e),
+                Err(_) => (false, "panicked".to_string()),
+            };
+            ConformanceResult { test, passed, detail }
+        })
+        .collect()
+}
+
+fn t_identity(d: &mut dyn Domain, _: &Runtime) -> Result<(), String> {
+    if d.name().trim().is_empty() {
+        return Err("domain name is empty".into());
+    }
+    let mut seen = BTreeSet::new();
+    let (sources, checks) = (d.sources().names(), d.checks().names());
+    for n in sources.iter().chain(checks.iter()) {
+        if n.trim().is_empty() || !seen.insert(*n) {
+            return Err(format!("empty or duplicate name {:?}", n));
+        }
+    }
+    if sources.is_empty() {
+        return Err("no score sources".into());
+    }
+    Ok(())
+}
+
+fn t_weights(d: &mut dyn Domain, _: &Runtime) -> Result<(), String> {
+    let w = d.weights();
+    let n = d.sources().len();
+    if w.len() != n {
+        return Err(format!("{} weights for {} sources", w.len(), n));
+    }
+    validate_weights(&w).map_err(|e| e.to_string())
+}
+
+fn t_tick(d: &mut dyn Domain, _: &Runtime) -> Result<(), String> {
+    if d.tick().is_zero() {
+        return Err("tick rate is zero".into());
+    }
+    Ok(())
+}
+
+fn t_threshold(d: &mut dyn Domain, _: &Runtime) -> Result<(), String> {
+    let (t, (min, max)) = (d.threshold(), d.threshold_bounds());
+    if !(min > 0.0 && min <= t && t <= max && max <= 1.0) {
+        return Err(format!("threshold {} with bounds [{}, {}] is not 0 < min <= threshold <= max <= 1", t, min, max));
+    }
+    Ok(())
+}
+
+// A source may fail to answer, but what it answers must be a score.
+fn t_score_range(d: &mut dyn Domain, rt: &Runtime) -> Result<(), String> {
+    let (mut scores, mut errors) = (Vec::new(), Vec::new());
+    for _ in 0..SAMPLE_ROUNDS {
+        rt.block_on(d.sources().sample_into(&mut scores, &mut errors));
+        for (i, e) in &errors {
+            if let SourceError::OutOfRange(v) = e {
+                return Err(format!("{} returned {} outside [0, 1]", d.sources().name(*i), v));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Sampling every source and running every check must fit in one tick.
+fn t_cycle_budget(d: &mut dyn Domain, rt: &Runtime) -> Result<(), String> {
+    let tick = d.tick();
+    let (mut scores, mut errors, mut conditions) = (Vec::new(), Vec::new(), Conditions::new());
+    for _ in 0..SAMPLE_ROUNDS {
+        let start = Instant::now();
+        rt.block_on(async {
+            d.sources().sample_into(&mut scores, &mut errors).await;
+            d.checks().run_into(&mut conditions).await;
+        });
+        let took = start.elapsed();
+        if took > tick {
+            return Err(format!("cycle took {:?}, tick is {:?}", took, tick));
+        }
+    }
+    Ok(())
+}
+
+// The safe state must be observably engaged after one call and stay engaged after another.
+fn t_safe_state(d: &mut dyn Domain, _: &Runtime) -> Result<(), String> {
+    d.safe_state();
+    if !d.in_safe_state() {
+        return Err("safe_state() returned without engaging the safe state".into());
+    }
+    d.safe_state();
+    if !d.in_safe_state() {
+        return Err("a second safe_state() left the safe state".into());
+    }
+    Ok(())
+}
+
+// Known-good plugin the kit itself is validated against; sr-bridge registers it as
+// `reference`, and alloc_check drives its cycle.
+pub struct ReferenceDomain {
+    sources: SourceSet,
+    checks: CheckRegistry,
+    safe: bool,
+}
+
+impl ReferenceDomain {
+    pub fn new() -> Self {
+        let mut sources = SourceSet::new(Duration::from_millis(100));
+        for (name, score) in [
+            ("neutron_flux_coherence", 0.9999),
+            ("primary_coolant_health", 0.9998),
+            ("containment_pressure", 1.0),
+            ("cyber_i_c_health", 0.9997),
+            ("operator_alertness", 0.9999),
+        ] {
+            sources.register(Box::new(SyncSource::new(name, move || score)));
+        }
+        let mut checks = CheckRegistry::new();
+        checks.register(Box::new(SyncCheck::new("reactor_pressure_ok", || true))).register(Box::new(SyncCheck::new("no_scram_override", || true)));
+        ReferenceDomain { sources, checks, safe: false }
+    }
+}
+
+impl Default for ReferenceDomain {
+    fn default() -> Self {
+        ReferenceDomain::new()
+    }
+}
+
+impl Domain for ReferenceDomain {
+    fn name(&self) -> &str { "reference" }
+    fn tick(&self) -> Duration { Duration::from_secs(1) }
+    fn weights(&self) -> Vec<f64> { vec![0.30, 0.25, 0.20, 0.15, 0.10] }
+    fn sources(&self) -> &SourceSet { &self.sources }
+    fn checks(&self) -> &CheckRegistry { &self.checks }
+    fn safe_state(&mut self) { self.safe = true }
+    fn in_safe_state(&self) -> bool { self.safe }
+
+    // Released only once the monitor decides GO again.
+    fn decide(&mut self, _scores: &[f64], _errors: &[(usize, SourceError)], _conditions: &Conditions, eval: Evaluation) -> Evaluation {
+        if eval.decision == Decision::GO {
+            self.safe = false;
+        }
+        eval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use crate::core::monitor::{HarmonyMonitor, MAX_BYPASS};
+    use crate::core::source::{Score, ScoreSource};
+    use crate::rbac::{AccessControl, AuditEntry, AuditSink, Principal, Role};
+    use async_trait::async_trait;
+
+    // A provider with nothing to report this cycle.
+    struct Silent;
+
+    #[async_trait]
+    impl ScoreSource for Silent {
+        fn name(&self) -> &str { "silent" }
+        async fn sample(&self) -> Result<Score, SourceError> {
+            Err(SourceError::Unavailable("no reading".into()))
+        }
+    }
+
+    // Two channels and one interlock; `latches` false models a safe_state() that drives nothing.
+    struct Rig {
+        sources: SourceSet,
+        checks: CheckRegistry,
+        latches: bool,
+        safe: bool,
+    }
+
+    fn rig(second: Box<dyn ScoreSource>, interlock: bool) -> Rig {
+        let mut sources = SourceSet::new(Duration::from_millis(50));
+        sources.register(Box::new(SyncSource::new("coolant", || 1.0))).register(second);
+        let mut checks = CheckRegistry::new();
+        checks.register(Box::new(SyncCheck::new("interlock", move || interlock)));
+        Rig { sources, checks, latches: true, safe: false }
+    }
+
+    impl Domain for Rig {
+        fn name(&self) -> &str { "rig" }
+        fn tick(&self) -> Duration { Duration::from_secs(1) }
+        fn weights(&self) -> Vec<f64> { vec![0.5, 0.5] }
+        fn sources(&self) -> &SourceSet { &self.sources }
+        fn checks(&self) -> &CheckRegistry { &self.checks }
+        fn safe_state(&mut self) { self.safe = self.latches }
+        fn in_safe_state(&self) -> bool { self.safe }
+    }
+
+    #[derive(Clone, Default)]
+    struct Audit(Arc<Mutex<Vec<AuditEntry>>>);
+
+    impl AuditSink for Audit {
+        fn append(&mut self, entry: &AuditEntry) -> Result<(), String> {
+            self.0.lock().unwrap().push(entry.clone());
+            Ok(())
+        }
+    }
+
+    impl Audit {
+        fn actions(&self) -> Vec<(String, bool)> {
+            self.0.lock().unwrap().iter().map(|e| (format!("{:?}", e.action), e.allowed)).collect()
+        }
+    }
+
+    fn principal(role: Role) -> Principal {
+        Principal { identity: format!("spiffe://plant/{:?}", role), roles: vec![role] }
+    }
+
+    fn audited(d: Rig) -> (HarmonyMonitor<Rig>, Audit) {
+        let audit = Audit::default();
+        let mut monitor = HarmonyMonitor::new(d).unwrap();
+        monitor.access_control(AccessControl::new(Box::new(audit.clone())));
+        (monitor, audit)
+    }
+
+    #[test]
+    fn reference_domain_passes_the_suite() {
+        let results = run_conformance(&mut ReferenceDomain::new());
+        assert_eq!(results.len(), SUITE.len());
+        assert!(results.iter().all(|r| r.passed), "{:?}", results);
+    }
+
+    #[test]
+    fn safe_state_that_engages_nothing_fails() {
+        let mut d = rig(Box::new(SyncSource::new("flux", || 1.0)), true);
+        d.latches = false;
+        let results = run_conformance(&mut d);
+        let failed: Vec<_> = results.iter().filter(|r| !r.passed).map(|r| r.test).collect();
+        assert_eq!(failed, ["safe_state_idempotent"]);
+    }
+
+    #[tokio::test]
+    async fn monitor_halts_into_the_safe_state_when_a_provider_is_silent() {
+        let mut monitor = HarmonyMonitor::new(rig(Box::new(Silent), true)).unwrap();
+        assert_eq!(monitor.cycle().await.decision, Decision::HALT);
+        assert!(matches!(monitor.source_errors(), [(1, SourceError::Unavailable(_))]));
+        assert!(monitor.domain().in_safe_state());
+    }
+
+    #[tokio::test]
+    async fn monitor_halts_into_the_safe_state_when_a_condition_fails() {
+        let mut monitor = HarmonyMonitor::new(rig(Box::new(SyncSource::new("flux", || 1.0)), false)).unwrap();
+        let eval = monitor.cycle().await;
+        assert_eq!(eval.decision, Decision::HALT);
+        assert!(!eval.ch);
+        assert!(monitor.domain().in_safe_state());
+    }
+
+    #[tokio::test]
+    async fn reference_domain_leaves_the_safe_state_on_go() {
+        let mut monitor = HarmonyMonitor::new(ReferenceDomain::new()).unwrap();
+        monitor.domain_mut().safe_state();
+        assert_eq!(monitor.cycle().await.decision, Decision::GO);
+        assert!(!monitor.domain().in_safe_state());
+    }
+
+    #[tokio::test]
+    async fn an_operator_override_holds_halt_until_released() {
+        let hour = Duration::from_secs(3600);
+        let mut bare = HarmonyMonitor::new(rig(Box::new(SyncSource::new("flux", || 1.0)), true)).unwrap();
+        assert!(bare.override_decision(Decision::HALT, hour, "crew on deck", &principal(Role::Operator)).unwrap_err().contains("no access control attached"));
+
+        let (mut monitor, audit) = audited(rig(Box::new(SyncSource::new("flux", || 1.0)), true));
+        assert_eq!(monitor.cycle().await.decision, Decision::GO);
+        assert!(monitor.override_decision(Decision::HALT, hour, "crew on deck", &principal(Role::Viewer)).is_err());
+        assert!(monitor.override_decision(Decision::GO, hour, "force", &principal(Role::Admin)).is_err());
+        monitor.override_decision(Decision::HALT, hour, "crew on deck", &principal(Role::Operator)).unwrap();
+        assert_eq!(monitor.override_held(), Some(Decision::HALT));
+        assert_eq!(monitor.cycle().await.decision, Decision::HALT);
+        assert!(monitor.domain().in_safe_state());
+
+        monitor.release_override(&principal(Role::Operator)).unwrap();
+        assert_eq!(monitor.cycle().await.decision, Decision::GO);
+        assert!(monitor.release_override(&principal(Role::Operator)).is_err());
+        assert_eq!(audit.actions(), [("Override".into(), false), ("Override".into(), true), ("Override".into(), true)]);
+    }
+
+    #[tokio::test]
+    async fn an_override_never_relaxes_a_cycle_and_lapses_on_its_own() {
+        let (mut monitor, _) = audited(rig(Box::new(SyncSource::new("flux", || 1.0)), false));
+        monitor.override_decision(Decision::CAUTION, Duration::from_millis(50), "watch", &principal(Role::Operator)).unwrap();
+        assert_eq!(monitor.cycle().await.decision, Decision::HALT);
+
+        let (mut monitor, _) = audited(rig(Box::new(SyncSource::new("flux", || 1.0)), true));
+        monitor.override_decision(Decision::CAUTION, Duration::from_millis(50), "watch", &principal(Role::Operator)).unwrap();
+        assert_eq!(monitor.cycle().await.decision, Decision::CAUTION);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(monitor.override_held(), None);
+        assert_eq!(monitor.cycle().await.decision, Decision::GO);
+    }
+
+    #[tokio::test]
+    async fn a_bypass_needs_a_safety_engineer_and_a_bounded_window() {
+        let (mut monitor, audit) = audited(rig(Box::new(SyncSource::new("flux", || 1.0)), false));
+        assert_eq!(monitor.cycle().await.decision, Decision::HALT);
+        assert!(monitor.bypass_condition("interlock", Duration::from_secs(60), "calibration", &principal(Role::Operator)).is_err());
+        assert!(monitor.bypass_condition("interlock", MAX_BYPASS + Duration::from_secs(1), "calibration", &principal(Role::SafetyEngineer)).is_err());
+        monitor.bypass_condition("interlock", Duration::from_millis(50), "calibration", &principal(Role::SafetyEngineer)).unwrap();
+        assert_eq!(monitor.bypassed().collect::<Vec<_>>(), ["interlock"]);
+
+        let eval = monitor.cycle().await;
+        assert_eq!(eval.decision, Decision::GO);
+        let interlock = &monitor.conditions().statuses[0];
+        assert!(interlock.ok && interlock.detail.as_deref().is_some_and(|d| d.starts_with("bypassed")), "{}", interlock);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(monitor.cycle().await.decision, Decision::HALT);
+        assert_eq!(monitor.bypassed().count(), 0);
+        assert_eq!(audit.actions(), [("Bypass".into(), false), ("Bypass".into(), true)]);
+    }
+}
@@ -0,0 +1,107 @@
+//! Pyharmony.rs - PyO3 bindings for back-testing weights in notebooks (forbid unsafe)
+//!
+//! Build: maturin build --release (module name `pyharmony`). Parquet exports are read
+//! on the Python side (pyarrow/pandas) and passed in as lists of rows.
+#![forbid(unsafe_code)]
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+mod anchor;
+mod decision_kernel;
+use anchor::parse_anchor;
+use decision_kernel::{decide, decide_batch, weighted_mu, Decision, HARMONY_THRESHOLD};
+
+
+fn decision_name(d: Decision) -> &'static str {
+    match d {
+        Decision::GO => "GO",
+        Decision::CAUTION => "CAUTION",
+        Decision::HALT => "HALT",
+    }
+}
+
+#[pyclass(name = "HarmonyContext")]
+pub struct PyHarmonyContext {
+    #[pyo3(get)]
+    weights: Vec<f64>,
+    #[pyo3(get)]
+    threshold: f64,
+}
+
+#[pymethods]
+impl PyHarmonyContext {
+    #[new]
+    #[pyo3(signature = (weights, threshold = HARMONY_THRESHOLD))]
+    fn new(weights: Vec<f64>, threshold: f64) -> PyResult<Self> {
+        if weights.is_empty() || weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err(PyValueError::new_err("weights must be non-empty, finite and non-negative"));
+        }
+        Ok(PyHarmonyContext { weights, threshold })
+    }
+
+    fn mu(&self, scores: Vec<f64>) -> PyResult<f64> {
+        self.check_len(&scores)?;
+        Ok(weighted_mu(&self.weights, &scores))
+    }
+
+    #[pyo3(signature = (scores, ch = true))]
+    fn evaluate(&self, scores: Vec<f64>, ch: bool) -> PyResult<&'static str> {
+        self.check_len(&scores)?;
+        Ok(decision_name(decide(weighted_mu(&self.weights, &scores), ch, true, self.threshold)))
+    }
+
+    // One decision per row; `ch` defaults to all-true so pure weight studies need only scores.
+    #[pyo3(signature = (rows, ch = None))]
+    fn evaluate_batch(&self, rows: Vec<Vec<f64>>, ch: Option<Vec<bool>>) -> PyResult<Vec<&'static str>> {
+        let ch = ch.unwrap_or_else(|| vec![true; rows.len()]);
+        if ch.len() != rows.len() {
+            return Err(PyValueError::new_err("ch must have one entry per row"));
+        }
+        for row in &rows {
+            self.check_len(row)?;
+        }
+        let cycles: Vec<(Vec<f64>, bool)> = rows.into_iter().zip(ch).collect();
+        Ok(decide_batch(&self.weights, &cycles, self.threshold).into_iter().map(decision_name).collect())
+    }
+}
+
+impl PyHarmonyContext {
+    fn check_len(&self, scores: &[f64]) -> PyResult<()> {
+        if scores.len() != self.weights.len() {
+            return Err(PyValueError::new_err(format!(
+                "expected {} scores, got {}",
+                self.weights.len(),
+                scores.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+// Reads a recorded-cycle anchor file into a list of dicts (timestamp_ms, ch, weights, scores),
+// ready for `pandas.DataFrame(...)`.
+#[pyfunction]
+fn read_anchor(py: Python<'_>, path: &str) -> PyResult<Vec<PyObject>> {
+    let text = std::fs::read_to_string(path).map_err(|e| PyIOError::new_err(format!("{}: {}", path, e)))?;
+    let frames = parse_anchor(&text).map_err(|e| PyValueError::new_err(format!("{}: {}", path, e)))?;
+    frames
+        .into_iter()
+        .map(|f| {
+            let d = PyDict::new_bound(py);
+            d.set_item("timestamp_ms", f.timestamp_ms)?;
+            d.set_item("ch", f.ch)?;
+            d.set_item("weights", f.weights)?;
+            d.set_item("scores", f.scores)?;
+            Ok(d.into_any().unbind())
+        })
+        .collect()
+}
+
+#[pymodule]
+fn pyharmony(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("HARMONY_THRESHOLD", HARMONY_THRESHOLD)?;
+    m.add_class::<PyHarmonyContext>()?;
+    m.add_function(wrap_pyfunction!(read_anchor, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! RBAC.rs - Role-based access control and audit trail for mutating operator actions (forbid unsafe)
+//!
+//! Every mutating operator action (override, condition bypass, threshold or config change in
+//! core::monitor, latched-HALT reset in hot_standby) calls `AccessControl::authorize` with the
+//! authenticated principal before acting. Allowed and denied attempts are both audited with
+//! the identity, so a refused bypass is as visible as a granted one. Add an Action only together with the call
+//! site that checks it.
+#![forbid(unsafe_code)]
+use crate::decision::{json_escape, now_ms};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Viewer,
+    Operator,
+    SafetyEngineer,
+    Admin,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    View,
+    Override,
+    Bypass,
+    ThresholdChange,
+    LatchedHaltReset,
+}
+
+impl Action {
+    // Least-privileged role allowed to perform the action; roles are ordered, so higher roles
+    // inherit everything below them.
+    pub fn required_role(self) -> Role {
+        match self {
+            Action::View => Role::Viewer,
+            Action::Override => Role::Operator,
+            Action::LatchedHaltReset => Role::SafetyEngineer,
+            Action::Bypass => Role::SafetyEngineer,
+            Action::ThresholdChange => Role::Admin,
+        }
+    }
+}
+
+// Identity as authenticated by the transport (SPIFFE ID from mTLS, or an SSO subject).
+#[derive(Clone, Debug)]
+pub struct Principal {
+    pub identity: String,
+    pub roles: Vec<Role>,
+}
+
+impl Principal {
+    pub fn may(&self, action: Action) -> bool {
+        self.roles.iter().any(|r| *r >= action.required_role())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AuditEntry {
+    pub at_ms: u64,
+    pub identity: String,
+    pub action: Action,
+    pub target: String,
+    pub detail: String,
+    pub allowed: bool,
+}
+
+impl AuditEntry {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"at_ms\":{},\"identity\":\"{}\",\"action\":\"{:?}\",\"target\":\"{}\",\"detail\":\"{}\",\"allowed\":{}}}",
+            self.at_ms,
+            json_escape(&self.identity),
+            self.action,
+            json_escape(&self.target),
+            json_escape(&self.detail),
+            self.allowed
+        )
+    }
+}
+
+// Where audit entries go (append-only file, syslog, the decision bus). Called before the
+// action proceeds; if the sink fails the action is refused.
+pub trait AuditSink {
+    fn append(&mut self, entry: &AuditEntry) -> Result<(), String>;
+}
+
+// So a component can hold whichever sink its deployment provides (AccessControl<Box<dyn AuditSink>>).
+impl<S: AuditSink + ?Sized> AuditSink for Box<S> {
+    fn append(&mut self, entry: &AuditEntry) -> Result<(), String> {
+        (**self).append(entry)
+    }
+}
+
+pub struct AccessControl<S: AuditSink> {
+    sink: S,
+}
+
+impl<S: AuditSink> AccessControl<S> {
+    pub fn new(sink: S) -> Self {
+        AccessControl { sink }
+    }
+
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    pub fn authorize(&mut self, principal: &Principal, action: Action, target: &str, detail: &str) -> Result<(), String> {
+        let allowed = principal.may(action);
+        let entry = AuditEntry {
+            at_ms: now_ms(),
+            identity: principal.identity.clone(),
+            action,
+            target: target.to_string(),
+            detail: detail.to_string(),
+            allowed,
+        };
+        self.sink.append(&entry).map_err(|e| format!("audit unavailable, refusing {:?}: {}", action, e))?;
+        if allowed {
+            Ok(())
+        } else {
+            Err(format!("{} may not {:?} {} (requires {:?})", principal.identity, action, target, action.required_role()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Failing;
+
+    impl AuditSink for Failing {
+        fn append(&mut self, _: &AuditEntry) -> Result<(), String> {
+            Err("disk full".into())
+        }
+    }
+
+    impl AuditSink for Vec<AuditEntry> {
+        fn append(&mut self, entry: &AuditEntry) -> Result<(), String> {
+            self.push(entry.clone());
+            Ok(())
+        }
+    }
+
+    fn principal(roles: &[Role]) -> Principal {
+        Principal { identity: "spiffe://plant/op".into(), roles: roles.to_vec() }
+    }
+
+    #[test]
+    fn higher_roles_inherit_lower_actions() {
+        let engineer = principal(&[Role::SafetyEngineer]);
+        assert!(engineer.may(Action::View));
+        assert!(engineer.may(Action::LatchedHaltReset));
+        assert!(engineer.may(Action::Override));
+        assert!(engineer.may(Action::Bypass));
+        assert!(principal(&[Role::Operator]).may(Action::Override));
+        assert!(!principal(&[Role::Operator]).may(Action::Bypass));
+        assert!(!engineer.may(Action::ThresholdChange));
+        assert!(principal(&[Role::Viewer, Role::Admin]).may(Action::ThresholdChange));
+        assert!(!principal(&[]).may(Action::View));
+    }
+
+    #[test]
+    fn denied_attempt_is_audited() {
+        let mut access = AccessControl::new(Vec::new());
+        let err = access.authorize(&principal(&[Role::Operator]), Action::LatchedHaltReset, "hot_standby", "latched true").unwrap_err();
+        assert!(err.contains("requires SafetyEngineer"), "{}", err);
+        let audit = access.sink();
+        assert_eq!(audit.len(), 1);
+        assert!(!audit[0].allowed);
+        assert_eq!(audit[0].identity, "spiffe://plant/op");
+        assert!(audit[0].to_json().contains("\"action\":\"LatchedHaltReset\""));
+    }
+
+    #[test]
+    fn unauditable_action_is_refused_even_when_allowed() {
+        let mut access = AccessControl::new(Failing);
+        let err = access.authorize(&principal(&[Role::Admin]), Action::ThresholdChange, "scada", "").unwrap_err();
+        assert!(err.starts_with("audit unavailable"), "{}", err);
+    }
+}
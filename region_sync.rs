@@ -0,0 +1,113 @@
+//! Region_Sync.rs - Latency-bounded decision confirmation between active regions (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::decision::{now_ms, Decision, DecisionRecord};
+
+#[derive(Clone, Debug)]
+pub struct SyncOutcome {
+    pub decision: Decision,
+    pub confirmed_by: Vec<String>,
+    pub unconfirmed: Vec<String>,
+}
+
+pub struct RegionSync {
+    local_region: String,
+    peer_regions: Vec<String>,
+    latency_bound: Duration,
+    peers: BTreeMap<String, (DecisionRecord, u64)>,
+}
+
+impl RegionSync {
+    pub fn new(local_region: &str, peer_regions: &[&str], latency_bound: Duration) -> Self {
+        RegionSync {
+            local_region: local_region.to_string(),
+            peer_regions: peer_regions.iter().map(|r| r.to_string()).collect(),
+            latency_bound,
+            peers: BTreeMap::new(),
+        }
+    }
+
+    pub fn local_region(&self) -> &str {
+        &self.local_region
+    }
+
+    // Records the peer's decision together with the local receive time.
+    pub fn on_peer_decision(&mut self, region: &str, record: DecisionRecord) {
+        if self.peer_regions.iter().any(|r| r == region) {
+            self.peers.insert(region.to_string(), (record, now_ms()));
+        }
+    }
+
+    // A peer confirms this cycle only if its record was produced within the latency
+    // bound of ours and arrived within the bound. The actionable decision is the most
+    // conservative of all confirmed decisions; a missing confirmation counts as HALT, and so
+    // does any record, ours included, whose lease has lapsed.
+    pub fn actionable(&self, local: &DecisionRecord) -> SyncOutcome {
+        let bound = self.latency_bound.as_millis() as u64;
+        let now = now_ms();
+        let mut decision = local.effective_at(now);
+        let mut confirmed_by = Vec::new();
+        let mut unconfirmed = Vec::new();
+        for region in &self.peer_regions {
+            match self.peers.get(region) {
+                Some((r, received))
+                    if r.timestamp_ms.abs_diff(local.timestamp_ms) <= bound
+                        && received.saturating_sub(r.timestamp_ms) <= bound =>
+                {
+                    decision = decision.most_severe(r.effective_at(now));
+                    confirmed_by.push(region.clone());
+                }
+                _ => {
+                    decision = Decision::HALT;
+                    unconfirmed.push(region.clone());
+                }
+            }
+        }
+        SyncOutcome { decision, confirmed_by, unconfirmed }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+
+    fn record(node: &str, decision: Decision) -> DecisionRecord {
+        DecisionRecord::new(node, 1, 0.9999, true, decision, ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS).status()).lease(Duration::from_secs(60))
+    }
+
+    #[test]
+    fn the_most_conservative_confirmed_decision_is_actionable() {
+        let mut sync = RegionSync::new("eu", &["us", "ap"], Duration::from_secs(2));
+        sync.on_peer_decision("us", record("fleet-us", Decision::GO));
+        sync.on_peer_decision("ap", record("fleet-ap", Decision::CAUTION));
+        let outcome = sync.actionable(&record("fleet-eu", Decision::GO));
+        assert_eq!(outcome.decision, Decision::CAUTION);
+        assert_eq!(outcome.confirmed_by, ["us", "ap"]);
+        assert!(outcome.unconfirmed.is_empty());
+    }
+
+    #[test]
+    fn a_silent_or_late_region_forces_halt() {
+        let mut sync = RegionSync::new("eu", &["us", "ap"], Duration::from_secs(2));
+        sync.on_peer_decision("us", record("fleet-us", Decision::GO));
+        let mut late = record("fleet-ap", Decision::GO);
+        late.timestamp_ms -= 3_000;
+        sync.on_peer_decision("ap", late);
+        sync.on_peer_decision("sa", record("fleet-sa", Decision::GO));
+        let outcome = sync.actionable(&record("fleet-eu", Decision::GO));
+        assert_eq!(outcome.decision, Decision::HALT);
+        assert_eq!((outcome.confirmed_by, outcome.unconfirmed), (vec!["us".to_string()], vec!["ap".to_string()]));
+    }
+
+    #[test]
+    fn a_single_region_acts_on_its_own_lease() {
+        let sync = RegionSync::new("eu", &[], Duration::from_secs(2));
+        assert_eq!(sync.actionable(&record("fleet-eu", Decision::GO)).decision, Decision::GO);
+        let lapsed = record("fleet-eu", Decision::GO).lease(Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(sync.actionable(&lapsed).decision, Decision::HALT);
+    }
+}
@@ -0,0 +1,143 @@
+//! Replay_Guard.rs - Replay/reorder rejection for pushed telemetry, surfaced as a cyber condition (forbid unsafe)
+//!
+//! Every pushed score message carries (source, seq, timestamp_ms, nonce). A message is accepted
+//! only if its seq is strictly above the last accepted seq for that source, its timestamp is
+//! within `max_skew` of local time, and its nonce has not been seen inside the skew window.
+//! Any rejection holds the cyber condition false for `violation_hold`.
+#![forbid(unsafe_code)]
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::decision::now_ms;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplayViolation {
+    SeqNotIncreasing { last: u64, got: u64 },
+    TimestampSkew { skew_ms: i64 },
+    NonceReused,
+}
+
+struct SourceState {
+    last_seq: u64,
+    nonces: HashSet<u64>,
+    // (timestamp_ms, nonce) in arrival order, for expiring nonces outside the window.
+    nonce_order: VecDeque<(u64, u64)>,
+}
+
+pub struct ReplayGuard {
+    max_skew: Duration,
+    violation_hold: Duration,
+    sources: HashMap<String, SourceState>,
+    last_violation: Option<(String, ReplayViolation, Instant)>,
+    rejected: u64,
+}
+
+impl ReplayGuard {
+    pub fn new(max_skew: Duration, violation_hold: Duration) -> Self {
+        ReplayGuard { max_skew, violation_hold, sources: HashMap::new(), last_violation: None, rejected: 0 }
+    }
+
+    pub fn check(&mut self, source: &str, seq: u64, timestamp_ms: u64, nonce: u64) -> Result<(), ReplayViolation> {
+        let result = self.check_at(source, seq, timestamp_ms, nonce, now_ms());
+        if let Err(v) = &result {
+            self.rejected += 1;
+            self.last_violation = Some((source.to_string(), v.clone(), Instant::now()));
+        }
+        result
+    }
+
+    fn check_at(&mut self, source: &str, seq: u64, timestamp_ms: u64, nonce: u64, now: u64) -> Result<(), ReplayViolation> {
+        let skew_ms = timestamp_ms as i64 - now as i64;
+        let window = self.max_skew.as_millis() as u64;
+        if skew_ms.unsigned_abs() > window {
+            return Err(ReplayViolation::TimestampSkew { skew_ms });
+        }
+        let state = self.sources.entry(source.to_string()).or_insert_with(|| SourceState {
+            last_seq: 0,
+            nonces: HashSet::new(),
+            nonce_order: VecDeque::new(),
+        });
+        // Nonces older than the skew window can no longer pass the timestamp check, so forget them.
+        while let Some(&(ts, n)) = state.nonce_order.front() {
+            if ts + window >= now {
+                break;
+            }
+            state.nonces.remove(&n);
+            state.nonce_order.pop_front();
+        }
+        if state.nonces.contains(&nonce) {
+            return Err(ReplayViolation::NonceReused);
+        }
+        if seq <= state.last_seq {
+            return Err(ReplayViolation::SeqNotIncreasing { last: state.last_seq, got: seq });
+        }
+        state.last_seq = seq;
+        state.nonces.insert(nonce);
+        state.nonce_order.push_back((timestamp_ms, nonce));
+        Ok(())
+    }
+
+    // Cyber check_ch condition.
+    pub fn telemetry_authentic(&self) -> bool {
+        match &self.last_violation {
+            Some((_, _, at)) => at.elapsed() > self.violation_hold,
+            None => true,
+        }
+    }
+
+    // Most recent rejection, for reporting.
+    pub fn last_violation(&self) -> Option<(&str, &ReplayViolation)> {
+        self.last_violation.as_ref().map(|(s, v, _)| (s.as_str(), v))
+    }
+
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000_000;
+
+    fn guard() -> ReplayGuard {
+        ReplayGuard::new(Duration::from_secs(2), Duration::from_secs(60))
+    }
+
+    #[test]
+    fn rejects_replayed_and_reordered_messages() {
+        let mut g = guard();
+        assert_eq!(g.check_at("a", 1, NOW, 10, NOW), Ok(()));
+        assert_eq!(g.check_at("a", 2, NOW, 10, NOW), Err(ReplayViolation::NonceReused));
+        assert_eq!(g.check_at("a", 1, NOW, 11, NOW), Err(ReplayViolation::SeqNotIncreasing { last: 1, got: 1 }));
+        assert_eq!(g.check_at("a", 3, NOW, 12, NOW), Ok(()));
+        // Sequences are per source.
+        assert_eq!(g.check_at("b", 1, NOW, 10, NOW), Ok(()));
+    }
+
+    #[test]
+    fn rejects_timestamps_outside_the_skew_window() {
+        let mut g = guard();
+        assert_eq!(g.check_at("a", 1, NOW - 2_001, 1, NOW), Err(ReplayViolation::TimestampSkew { skew_ms: -2_001 }));
+        assert_eq!(g.check_at("a", 1, NOW + 2_001, 1, NOW), Err(ReplayViolation::TimestampSkew { skew_ms: 2_001 }));
+        assert_eq!(g.check_at("a", 1, NOW + 2_000, 1, NOW), Ok(()));
+    }
+
+    #[test]
+    fn forgets_nonces_once_they_leave_the_window() {
+        let mut g = guard();
+        assert_eq!(g.check_at("a", 1, NOW, 7, NOW), Ok(()));
+        assert_eq!(g.check_at("a", 2, NOW + 3_000, 7, NOW + 3_000), Ok(()));
+    }
+
+    #[test]
+    fn a_violation_fails_the_condition() {
+        let mut g = guard();
+        assert!(g.telemetry_authentic());
+        assert!(g.check("a", 1, 0, 1).is_err());
+        assert!(!g.telemetry_authentic());
+        assert_eq!(g.rejected(), 1);
+        assert_eq!(g.last_violation().map(|(s, _)| s), Some("a"));
+    }
+}
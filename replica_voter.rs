@@ -0,0 +1,273 @@
+//! Replica_Voter.rs - MooN voting across redundant monitor replicas (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::decision::{lease_for, now_ms, Decision, DecisionRecord};
+use crate::decision_stream::{RecordVerifier, StreamViolation};
+
+const MAX_REPLICA_AGE: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitVotePolicy {
+    Halt, // no majority => HALT
+    // No majority among a fresh quorum => keep the last majority decision for at most `cycles`
+    // consecutive votes, then HALT (HALT at once if there is none to keep).
+    HoldLast { cycles: u32 },
+}
+
+pub struct VoteOutcome {
+    pub decision: Decision,
+    pub go_votes: usize,
+    pub caution_votes: usize,
+    pub halt_votes: usize,
+    pub missing: Vec<String>,
+    pub disagreement: bool,
+}
+
+pub struct ReplicaVoter {
+    replicas: Vec<String>,
+    policy: SplitVotePolicy,
+    verifier: RecordVerifier,
+    latest: BTreeMap<String, DecisionRecord>,
+    last_majority: Option<Decision>,
+    held: u32,
+}
+
+impl ReplicaVoter {
+    // `verifier` trusts one key per replica; a record only votes once it verifies.
+    pub fn new(replicas: &[&str], policy: SplitVotePolicy, verifier: RecordVerifier) -> Self {
+        ReplicaVoter {
+            replicas: replicas.iter().map(|r| r.to_string()).collect(),
+            policy,
+            verifier,
+            latest: BTreeMap::new(),
+            last_majority: None,
+            held: 0,
+        }
+    }
+
+    // 2oo3 for three replicas, 3oo5 for five, ...
+    pub fn quorum(&self) -> usize {
+        self.replicas.len() / 2 + 1
+    }
+
+    // Unsigned, forged, replayed and out-of-order records are refused, as is any node outside
+    // the replica set, so only a replica's own latest signed decision can vote.
+    pub fn submit(&mut self, record: DecisionRecord) -> Result<(), StreamViolation> {
+        if !self.replicas.contains(&record.node_id) {
+            return Err(StreamViolation::UnknownNode(record.node_id));
+        }
+        self.verifier.verify(&record)?;
+        self.latest.insert(record.node_id.clone(), record);
+        Ok(())
+    }
+
+    pub fn rejected(&self) -> u64 {
+        self.verifier.rejected()
+    }
+
+    // A replica votes with its leased decision: a GO whose lease has lapsed votes HALT.
+    pub fn vote(&mut self) -> VoteOutcome {
+        let now = now_ms();
+        let max_age = MAX_REPLICA_AGE.as_millis() as u64;
+        let mut go_votes = 0;
+        let mut caution_votes = 0;
+        let mut halt_votes = 0;
+        let mut missing = Vec::new();
+        for id in &self.replicas {
+            match self.latest.get(id) {
+                Some(r) if now.saturating_sub(r.timestamp_ms) <= max_age => match r.effective_at(now) {
+                    Decision::GO => go_votes += 1,
+                    Decision::CAUTION => caution_votes += 1,
+                    Decision::HALT => halt_votes += 1,
+                },
+                _ => missing.push(id.clone()),
+            }
+        }
+        let majority = if go_votes >= self.quorum() {
+            Some(Decision::GO)
+        } else if go_votes + caution_votes >= self.quorum() {
+            Some(Decision::CAUTION)
+        } else if halt_votes >= self.quorum() {
+            Some(Decision::HALT)
+        } else {
+            None
+        };
+        let decision = match majority {
+            Some(d) => {
+                self.last_majority = Some(d);
+                self.held = 0;
+                d
+            }
+            // Too few fresh replicas to vote at all: nothing to hold a decision on.
+            None if go_votes + caution_votes + halt_votes < self.quorum() => self.halt(),
+            None => match (self.policy, self.last_majority) {
+                (SplitVotePolicy::HoldLast { cycles }, Some(last)) if self.held < cycles => {
+                    self.held += 1;
+                    last
+                }
+                _ => self.halt(),
+            },
+        };
+        VoteOutcome {
+            decision,
+            go_votes,
+            caution_votes,
+            halt_votes,
+            disagreement: [go_votes, caution_votes, halt_votes].iter().filter(|v| **v > 0).count() > 1
+                || !missing.is_empty(),
+            missing,
+        }
+    }
+
+    // A HALT forgets the held decision, so a later split cannot resurrect it.
+    fn halt(&mut self) -> Decision {
+        self.last_majority = None;
+        self.held = 0;
+        Decision::HALT
+    }
+}
+
+pub async fn run_voter(mut voter: ReplicaVoter, period: Duration) {
+    loop {
+        // Drain every replica decision received over gRPC since the last vote.
+        while let Some(record) = recv_replica_decision().await {
+            if let Err(e) = voter.submit(record) {
+                eprintln!("Voter: replica decision refused: {:?}", e);
+            }
+        }
+        let outcome = voter.vote();
+        if outcome.disagreement {
+            raise_disagreement_alarm(outcome.go_votes, outcome.caution_votes, outcome.halt_votes, &outcome.missing);
+        }
+        match outcome.decision {
+            Decision::GO => println!("Voter: {}oo{} GO", outcome.go_votes, voter.replicas.len()),
+            Decision::CAUTION => println!("Voter: CAUTION"),
+            Decision::HALT => println!("Voter: HALT – safe-state"),
+        }
+        // The voted decision is leased like any other: one vote period plus margin.
+        let valid_until_ms = (outcome.decision != Decision::HALT).then(|| now_ms().saturating_add(lease_for(period).as_millis() as u64));
+        publish_voted_decision(outcome.decision, valid_until_ms).await;
+        tokio::time::sleep(period).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+    use crate::decision_stream::RecordSigner;
+    use ed25519_dalek::SigningKey;
+
+    const REPLICAS: [&str; 3] = ["r1", "r2", "r3"];
+
+    // Replica rN signs with the seed [N; 32].
+    fn signer(node: &str) -> RecordSigner {
+        let n = node.trim_start_matches(|c: char| !c.is_ascii_digit()).parse::<u8>().unwrap_or(9);
+        RecordSigner::from_hex(node, &format!("{:02x}", n).repeat(32)).unwrap()
+    }
+
+    fn unsigned(node: &str, seq: u64, decision: Decision) -> DecisionRecord {
+        DecisionRecord::new(node, seq, 0.9999, true, decision, ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS).status())
+    }
+
+    fn record(signer: &mut RecordSigner, node: &str, seq: u64, decision: Decision) -> DecisionRecord {
+        let mut record = unsigned(node, seq, decision).lease(Duration::from_secs(1));
+        signer.sign(&mut record);
+        record
+    }
+
+    struct Replicas(BTreeMap<String, RecordSigner>);
+
+    impl Replicas {
+        fn new() -> Self {
+            Replicas(REPLICAS.iter().map(|r| (r.to_string(), signer(r))).collect())
+        }
+
+        fn record(&mut self, node: &str, seq: u64, decision: Decision) -> DecisionRecord {
+            record(self.0.get_mut(node).unwrap(), node, seq, decision)
+        }
+    }
+
+    fn voter(policy: SplitVotePolicy, decisions: &[(&str, Decision)]) -> (ReplicaVoter, Replicas) {
+        let trusted = (1..=3u8).map(|n| (format!("r{}", n), SigningKey::from_bytes(&[n; 32]).verifying_key())).collect();
+        let mut voter = ReplicaVoter::new(&REPLICAS, policy, RecordVerifier::new(trusted, Duration::from_secs(2)));
+        let mut replicas = Replicas::new();
+        for (node, decision) in decisions {
+            voter.submit(replicas.record(node, 1, *decision)).unwrap();
+        }
+        (voter, replicas)
+    }
+
+    #[test]
+    fn two_of_three_decide() {
+        let outcome = voter(SplitVotePolicy::Halt, &[("r1", Decision::GO), ("r2", Decision::GO), ("r3", Decision::HALT)]).0.vote();
+        assert_eq!((outcome.decision, outcome.go_votes, outcome.halt_votes, outcome.disagreement), (Decision::GO, 2, 1, true));
+        let outcome = voter(SplitVotePolicy::Halt, &[("r1", Decision::GO), ("r2", Decision::CAUTION), ("r3", Decision::HALT)]).0.vote();
+        assert_eq!(outcome.decision, Decision::CAUTION);
+        let outcome = voter(SplitVotePolicy::Halt, &[("r1", Decision::HALT), ("r2", Decision::HALT), ("r3", Decision::GO)]).0.vote();
+        assert_eq!(outcome.decision, Decision::HALT);
+    }
+
+    #[test]
+    fn unleased_stale_and_missing_replicas_cannot_vote_go() {
+        let (mut v, _) = voter(SplitVotePolicy::Halt, &[("r1", Decision::GO)]);
+        let mut unleased = unsigned("r2", 1, Decision::GO);
+        signer("r2").sign(&mut unleased);
+        v.submit(unleased).unwrap();
+        let mut stale = unsigned("r3", 1, Decision::GO);
+        stale.timestamp_ms -= 2 * MAX_REPLICA_AGE.as_millis() as u64;
+        stale.set_lease(Duration::from_secs(60));
+        signer("r3").sign(&mut stale);
+        v.submit(stale).unwrap();
+        let outcome = v.vote();
+        assert_eq!((outcome.decision, outcome.go_votes, outcome.halt_votes), (Decision::HALT, 1, 1));
+        assert_eq!(outcome.missing, ["r3"]);
+
+        // Only one fresh replica: too few to vote at all, whatever the policy.
+        let outcome = voter(SplitVotePolicy::HoldLast { cycles: 5 }, &[("r1", Decision::GO)]).0.vote();
+        assert_eq!((outcome.decision, outcome.missing.len()), (Decision::HALT, 2));
+    }
+
+    #[test]
+    fn refuses_outsiders_forged_replayed_and_older_records() {
+        let (mut v, mut replicas) = voter(SplitVotePolicy::Halt, &[("r1", Decision::HALT), ("r2", Decision::HALT)]);
+        let mut mallory = signer("mallory");
+        assert_eq!(v.submit(record(&mut mallory, "mallory", 9, Decision::GO)), Err(StreamViolation::UnknownNode("mallory".into())));
+        // r3's slot, signed with r1's key.
+        assert_eq!(v.submit(record(&mut signer("r1"), "r3", 1, Decision::GO)), Err(StreamViolation::BadSignature));
+        assert_eq!(v.submit(unsigned("r3", 1, Decision::GO)), Err(StreamViolation::Unsigned));
+        let replayed = replicas.record("r1", 1, Decision::GO);
+        assert!(matches!(v.submit(replayed), Err(StreamViolation::SeqNotIncreasing { .. })));
+        let older = replicas.record("r2", 0, Decision::GO);
+        assert!(matches!(v.submit(older), Err(StreamViolation::SeqNotIncreasing { .. })));
+        assert_eq!(v.rejected(), 4);
+        let outcome = v.vote();
+        assert_eq!((outcome.decision, outcome.go_votes, outcome.missing.len()), (Decision::HALT, 0, 1));
+    }
+
+    #[test]
+    fn split_vote_follows_the_policy() {
+        let split = [("r1", Decision::GO), ("r2", Decision::HALT)];
+        assert_eq!(voter(SplitVotePolicy::Halt, &split).0.vote().decision, Decision::HALT);
+        assert_eq!(voter(SplitVotePolicy::HoldLast { cycles: 5 }, &split).0.vote().decision, Decision::HALT);
+
+        // The last majority GO is held for two split votes only, then the voter HALTs and
+        // stays HALTed for as long as the split lasts.
+        let (mut v, mut replicas) = voter(SplitVotePolicy::HoldLast { cycles: 2 }, &[("r1", Decision::GO), ("r2", Decision::GO)]);
+        assert_eq!(v.vote().decision, Decision::GO);
+        v.submit(replicas.record("r2", 2, Decision::HALT)).unwrap();
+        let outcome = v.vote();
+        assert_eq!((outcome.decision, outcome.disagreement), (Decision::GO, true));
+        assert_eq!(v.vote().decision, Decision::GO);
+        assert_eq!(v.vote().decision, Decision::HALT);
+        assert_eq!(v.vote().decision, Decision::HALT);
+
+        // A fresh majority re-arms the hold.
+        v.submit(replicas.record("r2", 3, Decision::GO)).unwrap();
+        assert_eq!(v.vote().decision, Decision::GO);
+        v.submit(replicas.record("r2", 4, Decision::HALT)).unwrap();
+        assert_eq!(v.vote().decision, Decision::GO);
+    }
+}
@@ -0,0 +1,158 @@
+//! Report.rs - EvaluationReport / HaltReason matching schemas/harmony/v1 (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::fmt::{self, Write};
+
+use crate::core::source::Provenance;
+use crate::decision::{DecisionRecord, JsonNum, JsonStr};
+
+pub const SCHEMA_VERSION: &str = "harmony.v1";
+
+// Variant names are the schema's reason codes verbatim.
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HaltCode {
+    MU_BELOW_THRESHOLD,
+    CH_FAILED,
+    FLOOR_VIOLATED,
+    UPSTREAM_HALT,
+    CLOCK_UNSYNCED,
+    // The engine's own health (core::self_harmony), not the plant's.
+    MONITOR_DEGRADED,
+    // Fused evidence (core::fusion) no longer supports "healthy", whatever mu says.
+    HEALTH_POSTERIOR_LOW,
+}
+
+impl HaltCode {
+    pub fn parse(s: &str) -> Option<HaltCode> {
+        match s {
+            "MU_BELOW_THRESHOLD" => Some(HaltCode::MU_BELOW_THRESHOLD),
+            "CH_FAILED" => Some(HaltCode::CH_FAILED),
+            "FLOOR_VIOLATED" => Some(HaltCode::FLOOR_VIOLATED),
+            "UPSTREAM_HALT" => Some(HaltCode::UPSTREAM_HALT),
+            "CLOCK_UNSYNCED" => Some(HaltCode::CLOCK_UNSYNCED),
+            "MONITOR_DEGRADED" => Some(HaltCode::MONITOR_DEGRADED),
+            "HEALTH_POSTERIOR_LOW" => Some(HaltCode::HEALTH_POSTERIOR_LOW),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HaltReason {
+    pub code: HaltCode,
+    pub subject: Option<String>,
+}
+
+impl HaltReason {
+    pub fn new(code: HaltCode) -> Self {
+        HaltReason { code, subject: None }
+    }
+
+    pub fn about(code: HaltCode, subject: &str) -> Self {
+        HaltReason { code, subject: Some(subject.to_string()) }
+    }
+
+    // Accepts the compact `CODE` / `CODE:subject` form used in traces.
+    pub fn parse(s: &str) -> Option<HaltReason> {
+        let (code, subject) = match s.split_once(':') {
+            Some((c, subj)) => (c, Some(subj.to_string())),
+            None => (s, None),
+        };
+        Some(HaltReason { code: HaltCode::parse(code)?, subject })
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+
+    pub fn write_json(&self, out: &mut String) {
+        let _ = match &self.subject {
+            Some(s) => write!(out, "{{\"code\":\"{:?}\",\"subject\":\"{}\"}}", self.code, JsonStr(s)),
+            None => write!(out, "{{\"code\":\"{:?}\"}}", self.code),
+        };
+    }
+}
+
+impl fmt::Display for HaltReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.subject {
+            Some(s) => write!(f, "{:?}:{}", self.code, s),
+            None => write!(f, "{:?}", self.code),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EvaluationReport {
+    pub record: DecisionRecord,
+    pub threshold: f64,
+    pub reasons: Vec<HaltReason>,
+    // SHA-256 of the running (sealed) config.
+    pub config_hash: String,
+    // Narrative from explain::explain_into, for HMIs and alerts.
+    pub explanation: Option<String>,
+    // d mu / d score per channel (HarmonyContext::sensitivity); empty when not computed.
+    pub sensitivity: Vec<f64>,
+    // Per channel, how its score was acquired (SourceSet::sample_traced); empty when not traced.
+    pub provenance: Vec<Provenance>,
+}
+
+impl EvaluationReport {
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+
+    // to_json() into a buffer the caller clears and reuses each cycle; no per-cycle allocation
+    // once the buffer has grown to the report's size.
+    pub fn write_json(&self, out: &mut String) {
+        let _ = write!(out, "{{\"schema_version\":\"{}\",\"record\":", SCHEMA_VERSION);
+        self.record.write_json(out);
+        let _ = write!(out, ",\"threshold\":{},\"reasons\":[", JsonNum(self.threshold));
+        for (i, r) in self.reasons.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            r.write_json(out);
+        }
+        let _ = write!(out, "],\"config_hash\":\"{}\"", JsonStr(&self.config_hash));
+        if let Some(e) = &self.explanation {
+            let _ = write!(out, ",\"explanation\":\"{}\"", JsonStr(e));
+        }
+        if !self.sensitivity.is_empty() {
+            out.push_str(",\"sensitivity\":[");
+            for (i, d) in self.sensitivity.iter().enumerate() {
+                let _ = write!(out, "{}{}", if i > 0 { "," } else { "" }, JsonNum(*d));
+            }
+            out.push(']');
+        }
+        if !self.provenance.is_empty() {
+            out.push_str(",\"provenance\":[");
+            for (i, p) in self.provenance.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_provenance(p, out);
+            }
+            out.push(']');
+        }
+        out.push('}');
+    }
+}
+
+fn write_provenance(p: &Provenance, out: &mut String) {
+    let _ = write!(
+        out,
+        "{{\"channel\":\"{}\",\"endpoint\":\"{}\",\"raw\":{},\"score\":{},\"transform\":\"{}\",\"acquired_ms\":{},\"quality\":\"{}\"}}",
+        JsonStr(&p.channel),
+        JsonStr(&p.endpoint),
+        JsonNum(p.raw),
+        JsonNum(p.score),
+        JsonStr(&p.transform),
+        p.acquired_ms,
+        p.quality.as_str()
+    );
+}
@@ -0,0 +1,461 @@
+//! Report_Sink.rs - At-least-once delivery of EvaluationReports to file/SQLite/Kafka/HTTP (forbid unsafe)
+//!
+//! Each sink has a spool file, `<spool_dir>/<sink>.spool`, that every report is appended to
+//! before delivery is attempted; the spool is truncated only once the sink has accepted all of
+//! it. A sink outage therefore never loses a report and never blocks the other sinks, and a
+//! restart replays whatever was left in order. A crash between a delivery and the truncate can
+//! deliver a report twice, so consumers dedupe on the record's (node_id, seq, timestamp_ms).
+//! Each spool holds at most `max_pending` reports; past that the oldest are dropped and counted.
+//! Every sink delivers from its own thread, so publish() on the decision cycle only appends to
+//! the spools. lag() / metrics() give per-sink depth, age of the oldest pending report and drops.
+#![forbid(unsafe_code)]
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use crate::decision::{now_ms, JsonStr};
+use crate::report::EvaluationReport;
+
+pub trait ReportSink {
+    // Names the spool file, so must stay the same across restarts.
+    fn name(&self) -> &str;
+    // Ok only once the sink has durably accepted the report.
+    fn deliver(&mut self, seq: u64, payload: &str) -> Result<(), String>;
+}
+
+// Appends one JSON report per line; a log shipper can pick the file up from there.
+pub struct FileSink {
+    name: String,
+    file: File,
+}
+
+impl FileSink {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let file = OpenOptions::new().create(true).append(true).open(path).map_err(|e| format!("report file {}: {}", path, e))?;
+        Ok(FileSink { name: format!("file:{}", path), file })
+    }
+}
+
+impl ReportSink for FileSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn deliver(&mut self, _seq: u64, payload: &str) -> Result<(), String> {
+        writeln!(self.file, "{}", payload).and_then(|_| self.file.sync_data()).map_err(|e| e.to_string())
+    }
+}
+
+// POSTs each report; any non-2xx status is a failed delivery and is retried. Delivery runs on
+// the sink's own thread; the timeout bounds how long one attempt holds up the reports behind it.
+pub struct HttpSink {
+    url: String,
+    timeout: Duration,
+}
+
+impl HttpSink {
+    pub fn new(url: &str) -> Self {
+        HttpSink { url: url.to_string(), timeout: Duration::from_millis(250) }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl ReportSink for HttpSink {
+    fn name(&self) -> &str {
+        &self.url
+    }
+
+    fn deliver(&mut self, seq: u64, payload: &str) -> Result<(), String> {
+        ureq::post(&self.url)
+            .timeout(self.timeout)
+            .set("Content-Type", "application/json")
+            .set("X-Harmony-Seq", &seq.to_string())
+            .send_string(payload)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+// One row per report: (seq, payload, inserted_ms).
+#[cfg(feature = "sqlite")]
+pub struct SqliteSink {
+    name: String,
+    conn: rusqlite::Connection,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteSink {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let conn = rusqlite::Connection::open(path).map_err(|e| format!("report db {}: {}", path, e))?;
+        conn.execute_batch("CREATE TABLE IF NOT EXISTS reports (seq INTEGER NOT NULL, payload TEXT NOT NULL, inserted_ms INTEGER NOT NULL)")
+            .map_err(|e| format!("report db {}: {}", path, e))?;
+        Ok(SqliteSink { name: format!("sqlite:{}", path), conn })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl ReportSink for SqliteSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn deliver(&mut self, seq: u64, payload: &str) -> Result<(), String> {
+        self.conn
+            .execute("INSERT INTO reports (seq, payload, inserted_ms) VALUES (?1, ?2, ?3)", rusqlite::params![seq as i64, payload, now_ms() as i64])
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "kafka")]
+#[derive(Default)]
+struct KafkaAcks(std::sync::Mutex<Option<Result<(), String>>>);
+
+#[cfg(feature = "kafka")]
+impl rdkafka::ClientContext for KafkaAcks {}
+
+#[cfg(feature = "kafka")]
+impl rdkafka::producer::ProducerContext for KafkaAcks {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &rdkafka::producer::DeliveryResult<'_>, _: ()) {
+        let ack = result.as_ref().map(|_| ()).map_err(|(e, _)| e.to_string());
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(ack);
+    }
+}
+
+// Produces with acks=all and waits for the broker's acknowledgement of each report.
+#[cfg(feature = "kafka")]
+pub struct KafkaSink {
+    name: String,
+    topic: String,
+    producer: rdkafka::producer::BaseProducer<KafkaAcks>,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaSink {
+    pub fn new(brokers: &str, topic: &str) -> Result<Self, String> {
+        let producer = rdkafka::ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("acks", "all")
+            .set("enable.idempotence", "true")
+            .create_with_context(KafkaAcks::default())
+            .map_err(|e| format!("kafka {}: {}", brokers, e))?;
+        Ok(KafkaSink { name: format!("kafka:{}/{}", brokers, topic), topic: topic.to_string(), producer })
+    }
+}
+
+#[cfg(feature = "kafka")]
+impl ReportSink for KafkaSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn deliver(&mut self, seq: u64, payload: &str) -> Result<(), String> {
+        use rdkafka::producer::{BaseRecord, Producer};
+        let key = seq.to_string();
+        self.producer.send(BaseRecord::to(&self.topic).key(&key).payload(payload)).map_err(|(e, _)| e.to_string())?;
+        self.producer.flush(Duration::from_secs(5)).map_err(|e| e.to_string())?;
+        let ack = self.producer.context().0.lock().unwrap_or_else(|e| e.into_inner()).take();
+        ack.unwrap_or_else(|| Err("no delivery acknowledgement".into()))
+    }
+}
+
+// Builds a sink from `file:<path>`, `http://...` / `https://...`, `sqlite:<path>` or
+// `kafka:<brokers>/<topic>`, as listed in HARMONY_REPORT_SINKS.
+pub fn parse_sink(spec: &str) -> Result<Box<dyn ReportSink + Send>, String> {
+    if spec.starts_with("http://") || spec.starts_with("https://") {
+        return Ok(Box::new(HttpSink::new(spec)));
+    }
+    match spec.split_once(':') {
+        Some(("file", path)) => Ok(Box::new(FileSink::open(path)?)),
+        #[cfg(feature = "sqlite")]
+        Some(("sqlite", path)) => Ok(Box::new(SqliteSink::open(path)?)),
+        #[cfg(not(feature = "sqlite"))]
+        Some(("sqlite", _)) => Err(format!("report sink {}: built without the sqlite feature", spec)),
+        #[cfg(feature = "kafka")]
+        Some(("kafka", target)) => {
+            let (brokers, topic) = target.rsplit_once('/').ok_or_else(|| format!("report sink {}: expected kafka:<brokers>/<topic>", spec))?;
+            Ok(Box::new(KafkaSink::new(brokers, topic)?))
+        }
+        #[cfg(not(feature = "kafka"))]
+        Some(("kafka", _)) => Err(format!("report sink {}: built without the kafka feature", spec)),
+        _ => Err(format!("unknown report sink {}", spec)),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SinkLag {
+    pub sink: String,
+    pub pending: usize,
+    // Age of the oldest undelivered report; None when caught up.
+    pub oldest_ms: Option<u64>,
+    pub delivered: u64,
+    pub dropped: u64,
+    pub last_error: Option<String>,
+}
+
+struct Pending {
+    seq: u64,
+    queued_ms: u64,
+    payload: String,
+}
+
+struct Queue {
+    spool: File,
+    pending: VecDeque<Pending>,
+    delivered: u64,
+    dropped: u64,
+    last_error: Option<String>,
+    // The spool still holds reports dropped from `pending`; rewrite it before it is next trusted.
+    stale_spool: bool,
+}
+
+// The part of a lane both the publishing cycle and the lane's delivery thread touch.
+struct Shared {
+    queue: Mutex<Queue>,
+    wake: Condvar,
+}
+
+struct Lane {
+    name: String,
+    spool_path: PathBuf,
+    shared: Arc<Shared>,
+}
+
+pub struct ReportSinks {
+    spool_dir: PathBuf,
+    max_pending: usize,
+    retry_ms: u64,
+    lanes: Vec<Lane>,
+    json: String,
+}
+
+impl ReportSinks {
+    pub fn new(spool_dir: &Path, max_pending: usize) -> Self {
+        ReportSinks { spool_dir: spool_dir.to_path_buf(), max_pending: max_pending.max(1), retry_ms: 5_000, lanes: Vec::new(), json: String::new() }
+    }
+
+    // How long a failing sink is left alone before the next delivery attempt. Reports keep
+    // spooling meanwhile. Applies to sinks attached after the call.
+    pub fn retry_every(mut self, interval: Duration) -> Self {
+        self.retry_ms = interval.as_millis() as u64;
+        self
+    }
+
+    // Opens the sink's spool, queueing anything a previous run left undelivered, and hands the
+    // sink to its own delivery thread; the replay happens there, not on the caller's cycle.
+    pub fn attach(&mut self, sink: Box<dyn ReportSink + Send>) -> Result<&mut Self, String> {
+        let spool_path = self.spool_dir.join(format!("{}.spool", spool_name(sink.name())));
+        if self.lanes.iter().any(|l| l.spool_path == spool_path) {
+            return Err(format!("report sink {} shares a spool with an attached sink", sink.name()));
+        }
+        let io_err = |e: io::Error| format!("report spool {}: {}", spool_path.display(), e);
+        fs::create_dir_all(&self.spool_dir).map_err(io_err)?;
+        let (mut pending, mut dropped) = (VecDeque::new(), 0);
+        match fs::read_to_string(&spool_path) {
+            Ok(text) => {
+                for line in text.lines() {
+                    // A torn last line from a crash mid-append cannot be delivered; count it.
+                    match parse_spooled(line) {
+                        Some(p) => pending.push_back(p),
+                        None => dropped += 1,
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(io_err(e)),
+        }
+        while pending.len() > self.max_pending {
+            pending.pop_front();
+            dropped += 1;
+        }
+        let spool = OpenOptions::new().create(true).append(true).open(&spool_path).map_err(io_err)?;
+        if !pending.is_empty() {
+            eprintln!("Reports: {} replaying {} spooled reports", sink.name(), pending.len());
+        }
+        let queue = Queue { spool, pending, delivered: 0, dropped, last_error: None, stale_spool: dropped > 0 };
+        let shared = Arc::new(Shared { queue: Mutex::new(queue), wake: Condvar::new() });
+        let lane = Lane { name: sink.name().to_string(), spool_path: spool_path.clone(), shared: shared.clone() };
+        let retry = Duration::from_millis(self.retry_ms);
+        thread::Builder::new()
+            .name(format!("report-sink-{}", self.lanes.len()))
+            .spawn(move || deliver_loop(sink, &spool_path, &shared, retry))
+            .map_err(|e| format!("report sink {}: {}", lane.name, e))?;
+        self.lanes.push(lane);
+        Ok(self)
+    }
+
+    // Serializes the report once and spools it for every sink; delivery happens on the sinks'
+    // own threads, so a slow or failing sink costs this cycle nothing beyond the spool append.
+    pub fn publish(&mut self, report: &EvaluationReport) {
+        self.json.clear();
+        report.write_json(&mut self.json);
+        let queued_ms = now_ms();
+        for lane in &self.lanes {
+            let mut q = lock(&lane.shared);
+            if q.pending.len() >= self.max_pending {
+                q.pending.pop_front();
+                q.dropped += 1;
+                q.stale_spool = true;
+            }
+            let entry = Pending { seq: report.record.seq, queued_ms, payload: self.json.clone() };
+            // Without the spool the report is still held in memory; only a restart would lose it.
+            if let Err(e) = append_spooled(&mut q.spool, &entry) {
+                q.last_error = Some(format!("spool {}: {}", lane.spool_path.display(), e));
+            }
+            q.pending.push_back(entry);
+            lane.shared.wake.notify_one();
+        }
+    }
+
+    pub fn lag(&self) -> Vec<SinkLag> {
+        let now = now_ms();
+        self.lanes
+            .iter()
+            .map(|l| {
+                let q = lock(&l.shared);
+                SinkLag {
+                    sink: l.name.clone(),
+                    pending: q.pending.len(),
+                    oldest_ms: q.pending.front().map(|p| now.saturating_sub(p.queued_ms)),
+                    delivered: q.delivered,
+                    dropped: q.dropped,
+                    last_error: q.last_error.clone(),
+                }
+            })
+            .collect()
+    }
+
+    // Age of the oldest undelivered report across all sinks; lag() without its per-sink copies,
+    // for the per-cycle audit-lag reading.
+    pub fn oldest_pending_ms(&self) -> Option<u64> {
+        let now = now_ms();
+        self.lanes.iter().filter_map(|l| lock(&l.shared).pending.front().map(|p| now.saturating_sub(p.queued_ms))).max()
+    }
+
+    // A handle on the sinks' counters for the diagnostics /metrics endpoint.
+    pub fn metrics(&self) -> ReportMetrics {
+        ReportMetrics { lanes: self.lanes.iter().map(|l| (l.name.clone(), l.shared.clone())).collect() }
+    }
+
+}
+
+pub struct ReportMetrics {
+    lanes: Vec<(String, Arc<Shared>)>,
+}
+
+impl ReportMetrics {
+    // Prometheus text exposition; every series carries a sink label.
+    pub fn metrics_text(&self) -> String {
+        let now = now_ms();
+        let mut out = String::new();
+        out.push_str("# TYPE harmony_report_pending gauge\n# TYPE harmony_report_oldest_pending_ms gauge\n");
+        out.push_str("# TYPE harmony_report_delivered_total counter\n# TYPE harmony_report_dropped_total counter\n");
+        for (name, shared) in &self.lanes {
+            let (pending, oldest_ms, delivered, dropped) = {
+                let q = lock(shared);
+                (q.pending.len(), q.pending.front().map_or(0, |p| now.saturating_sub(p.queued_ms)), q.delivered, q.dropped)
+            };
+            let label = format!("sink=\"{}\"", JsonStr(name));
+            let _ = writeln!(out, "harmony_report_pending{{{}}} {}", label, pending);
+            let _ = writeln!(out, "harmony_report_oldest_pending_ms{{{}}} {}", label, oldest_ms);
+            let _ = writeln!(out, "harmony_report_delivered_total{{{}}} {}", label, delivered);
+            let _ = writeln!(out, "harmony_report_dropped_total{{{}}} {}", label, dropped);
+        }
+        out
+    }
+}
+
+fn lock(shared: &Shared) -> MutexGuard<'_, Queue> {
+    shared.queue.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// One per sink: delivers the oldest pending report, outside the lock, and drops it from the
+// queue and the spool once the sink has accepted it. A failure backs off for `retry`.
+fn deliver_loop(mut sink: Box<dyn ReportSink + Send>, spool_path: &Path, shared: &Shared, retry: Duration) {
+    loop {
+        let (seq, queued_ms, payload) = {
+            let mut q = lock(shared);
+            while q.pending.is_empty() {
+                q = shared.wake.wait(q).unwrap_or_else(|e| e.into_inner());
+            }
+            let p = q.pending.front().expect("non-empty");
+            (p.seq, p.queued_ms, p.payload.clone())
+        };
+        let result = sink.deliver(seq, &payload);
+        let mut q = lock(shared);
+        if let Err(e) = result {
+            // Log the start of an outage, not every retry of it.
+            if q.last_error.is_none() {
+                eprintln!("Reports: {} delivery failed at seq {}, spooling: {}", sink.name(), seq, e);
+            }
+            q.last_error = Some(e);
+            drop(q);
+            thread::sleep(retry);
+            continue;
+        }
+        // The report may have been dropped for space while it was in flight.
+        if q.pending.front().is_some_and(|p| p.seq == seq && p.queued_ms == queued_ms) {
+            q.pending.pop_front();
+        }
+        q.delivered += 1;
+        if q.last_error.take().is_some() {
+            eprintln!("Reports: {} delivering again", sink.name());
+        }
+        let synced = if q.pending.is_empty() {
+            q.spool.set_len(0)
+        } else if q.stale_spool {
+            rewrite_spool(&mut q, spool_path)
+        } else {
+            continue;
+        };
+        match synced {
+            Ok(()) => q.stale_spool = false,
+            Err(e) => q.last_error = Some(format!("spool {}: {}", spool_path.display(), e)),
+        }
+    }
+}
+
+// Replaces the spool with exactly the pending reports (after drops), via a rename so a crash
+// leaves either the old spool or the new one.
+fn rewrite_spool(q: &mut Queue, spool_path: &Path) -> io::Result<()> {
+    let tmp = spool_path.with_extension("spool.tmp");
+    let mut file = File::create(&tmp)?;
+    for p in &q.pending {
+        writeln!(file, "{}\t{}\t{}", p.seq, p.queued_ms, p.payload)?;
+    }
+    file.sync_data()?;
+    fs::rename(&tmp, spool_path)?;
+    q.spool = OpenOptions::new().append(true).open(spool_path)?;
+    Ok(())
+}
+
+// One report per line: `seq \t queued_ms \t payload`. The JSON payload never contains a raw
+// newline or tab, since write_json escapes control characters. Synced before the report
+// counts as spooled, so a power loss cannot take it.
+fn append_spooled(file: &mut File, p: &Pending) -> io::Result<()> {
+    writeln!(file, "{}\t{}\t{}", p.seq, p.queued_ms, p.payload)?;
+    file.sync_data()
+}
+
+fn parse_spooled(line: &str) -> Option<Pending> {
+    let mut parts = line.splitn(3, '\t');
+    let seq = parts.next()?.parse().ok()?;
+    let queued_ms = parts.next()?.parse().ok()?;
+    let payload = parts.next()?;
+    (payload.starts_with('{') && payload.ends_with('}')).then(|| Pending { seq, queued_ms, payload: payload.to_string() })
+}
+
+fn spool_name(sink: &str) -> String {
+    sink.chars().map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' }).collect()
+}
@@ -1,66 +1,136 @@
 //! Resonance_Crypto.rs - CCSS Level-III Safety Crate (forbid unsafe)
 #![forbid(unsafe_code)]
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Mutex;
+use std::time::Duration;
 
-const HARMONY_THRESHOLD: f64 = 0.9995;
-const MIN_SCORE: f64 = 1e-12;
+use async_trait::async_trait;
 
-pub struct CryptoContext {
-    pub scores: Vec<f64>,
-    pub weights: Vec<f64>,
+mod clock_sync;
+mod core;
+mod decision;
+mod decision_kernel;
+mod plugin;
+mod rbac;
+mod robust_feeds;
+use crate::core::checks::{CheckRegistry, SyncCheck};
+use crate::core::harmony::{Conditions, Decision, Evaluation};
+use crate::core::monitor::HarmonyMonitor;
+use crate::core::source::{FnSource, Score, ScoreSource, SourceError, SourceSet};
+use plugin::Domain;
+use robust_feeds::RobustAggregator;
+
+
+fn ch_checks() -> CheckRegistry {
+    let mut checks = CheckRegistry::new();
+    checks
+        .register(Box::new(SyncCheck::new("cyber_alarm_clear", cyber_alarm_clear)))
+        .register(Box::new(SyncCheck::new("multisig_quorum_intact", multisig_quorum_intact)))
+        .register(Box::new(SyncCheck::new("chain_tip_confirmations_ge6", chain_tip_confirmations_ge6)))
+        .register(Box::new(SyncCheck::new("admin_override_off", admin_override_off)))
+        .register(Box::new(SyncCheck::new("smart_contract_audit_recent", smart_contract_audit_recent)));
+    checks
 }
 
-impl CryptoContext {
-    pub fn calculate_mu(&self) -> f64 {
-        let mut log_sum = 0.0;
-        for (w, s) in self.weights.iter().zip(self.scores.iter()) {
-            let s_clipped = s.clamp(MIN_SCORE, 1.0);
-            log_sum += w * s_clipped.ln();
+#[allow(non_camel_case_types)]
+pub enum TxDecision { TX_GO, TX_CAUTION, TX_HALT }
+
+pub fn evaluate_crypto_harmony(eval: &Evaluation) -> TxDecision {
+    match eval.decision {
+        Decision::GO => TxDecision::TX_GO,
+        Decision::CAUTION => TxDecision::TX_CAUTION,
+        Decision::HALT => {
+            log_harmony_fault(eval.mu, eval.ch);
+            TxDecision::TX_HALT
         }
-        log_sum.exp()
     }
 }
 
-pub fn check_ch() -> bool {
-    cyber_alarm_clear()
-        && multisig_quorum_intact()
-        && chain_tip_confirmations_ge6()
-        && admin_override_off()
-        && smart_contract_audit_recent()
+// Oracle feeds provisioned for this deployment; tolerating one lying oracle needs three.
+const ORACLE_FEEDS: usize = 4;
+
+// Tolerates one lying oracle out of the configured feeds.
+struct OracleSource {
+    oracles: Mutex<RobustAggregator>,
+}
+
+impl OracleSource {
+    fn new() -> Self {
+        let oracles = RobustAggregator::new(1, 3.0, 0.001);
+        oracles.check_feeds(ORACLE_FEEDS).expect("oracle feeds");
+        OracleSource { oracles: Mutex::new(oracles) }
+    }
 }
 
-pub enum TxDecision { TX_GO, TX_HALT }
+#[async_trait]
+impl ScoreSource for OracleSource {
+    fn name(&self) -> &str {
+        "oracle_consensus"
+    }
+
+    async fn sample(&self) -> Result<Score, SourceError> {
+        let feeds = query_oracle_feeds().await;
+        let consensus = self.oracles.lock().unwrap().aggregate(&feeds);
+        consensus.map(|o| o.value).ok_or_else(|| SourceError::Unavailable("no oracle consensus".into()))
+    }
+}
 
-pub fn evaluate_crypto_harmony(mu: f64, ch: bool) -> TxDecision {
-    if mu >= HARMONY_THRESHOLD && ch {
-        TxDecision::TX_GO
-    } else {
+// One source per weighted channel, in weight order; swap in real telemetry backends here.
+fn score_sources() -> SourceSet {
+    let mut sources = SourceSet::new(Duration::from_millis(200));
+    sources
+        .register(Box::new(FnSource::new("node_sync_health", query_node_sync_health)))
+        .register(Box::new(FnSource::new("mempool_fee_convergence", query_mempool_fee_convergence)))
+        .register(Box::new(FnSource::new("key_custody_integrity", query_key_custody_integrity)))
+        .register(Box::new(FnSource::new("smart_contract_audit_score", query_smart_contract_audit_score)))
+        .register(Box::new(OracleSource::new()));
+    sources
+}
+
+// Autoheal runs on every HALT cycle; the domain counts as safe until it decides GO again.
+struct Crypto {
+    sources: SourceSet,
+    checks: CheckRegistry,
+    healing: bool,
+}
+
+impl Domain for Crypto {
+    fn name(&self) -> &str { "crypto" }
+    fn tick(&self) -> Duration { Duration::from_millis(500) }
+    fn weights(&self) -> Vec<f64> { vec![0.30, 0.25, 0.20, 0.15, 0.10] }
+    fn sources(&self) -> &SourceSet { &self.sources }
+    fn checks(&self) -> &CheckRegistry { &self.checks }
+
+    fn safe_state(&mut self) {
         trigger_autoheal();
-        log_harmony_fault(mu, ch);
-        TxDecision::TX_HALT
+        self.healing = true;
+    }
+
+    fn in_safe_state(&self) -> bool {
+        self.healing
+    }
+
+    fn decide(&mut self, _scores: &[f64], _errors: &[(usize, SourceError)], _conditions: &Conditions, eval: Evaluation) -> Evaluation {
+        if eval.decision == Decision::GO {
+            self.healing = false;
+        }
+        eval
     }
 }
 
 #[tokio::main]
 async fn main() {
-    let ctx = CryptoContext {
-        scores: vec![0.98, 0.97, 1.0, 0.96, 0.99],
-        weights: vec![0.30, 0.25, 0.20, 0.15, 0.10],
-    };
-    loop {
-        let scores = vec![
-            query_node_sync_health().await,
-            query_mempool_fee_convergence().await,
-            query_key_custody_integrity().await,
-            query_smart_contract_audit_score().await,
-            query_oracle_stability().await,
-        ];
-        let mu = ctx.calculate_mu();
-        let ch = check_ch().await;
-        match evaluate_crypto_harmony(mu, ch) {
-            TxDecision::TX_GO => println!("Crypto: TX RESONANCE GO"),
-            TxDecision::TX_HALT => println!("Crypto: TX HALT – safe-state"),
-        }
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-    }
+    let mut monitor = HarmonyMonitor::new(Crypto { sources: score_sources(), checks: ch_checks(), healing: false }).expect("harmony context");
+    monitor
+        .run(|monitor, eval| {
+            for (i, e) in monitor.source_errors() {
+                eprintln!("Crypto: score source {} failed: {}", monitor.names()[*i], e);
+            }
+            println!("Crypto: dmu/ds {}", monitor.sensitivity());
+            match evaluate_crypto_harmony(eval) {
+                TxDecision::TX_GO => println!("Crypto: TX RESONANCE GO"),
+                TxDecision::TX_CAUTION => println!("Crypto: TX CAUTION – early warning"),
+                TxDecision::TX_HALT => println!("Crypto: TX HALT – safe-state [{}]", monitor.conditions().failure_summary()),
+            }
+        })
+        .await;
 }
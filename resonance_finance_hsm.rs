@@ -1,66 +1,345 @@
 //! Resonance_Finance_HSM.rs - Basel III / Fed-Line HSM Plug-in (forbid unsafe)
 #![forbid(unsafe_code)]
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-const HARMONY_THRESHOLD: f64 = 0.9995;
-const MIN_SCORE: f64 = 1e-12;
+mod audit_log;
+mod clock_sync;
+mod core;
+mod crypto_policy;
+mod decision;
+mod decision_kernel;
+mod key_provider;
+mod mtls;
+mod plugin;
+mod rbac;
+#[cfg(windows)]
+mod windows_host;
+use crate::core::baseline::{Baseline, Baselines, Period};
+use crate::core::checks::{CheckRegistry, SyncCheck};
+use crate::core::governance::WeightGovernance;
+use crate::core::harmony::{Conditions, Decision, Evaluation, HarmonyContext, Severity};
+use crate::core::monitor::HarmonyMonitor;
+use crate::core::profile::{hm, ProfileSchedule, Window, WEEKDAYS};
+use crate::core::source::{FnSource, SourceError, SourceSet};
+use crate::core::tuning::WeightTuner;
+use audit_log::{AuditLog, Pkcs11Signer, SegmentSigner};
+use clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+use decision::{now_ms, DecisionRecord};
+use key_provider::{CloudKms, KmsProvider, ProviderSigner, VaultProvider};
+use plugin::Domain;
 
-pub struct FinanceContext {
-    pub scores: Vec<f64>,
-    pub weights: Vec<f64>,
+
+fn ch_checks() -> CheckRegistry {
+    let mut checks = CheckRegistry::new();
+    checks
+        .register(Box::new(SyncCheck::new("aml_alert_clear", aml_alert_clear)))
+        .register(Box::new(SyncCheck::new("regulatory_capital_ok", regulatory_capital_ok)))
+        .register(Box::new(SyncCheck::new("dual_control_sign_off_ok", dual_control_sign_off_ok)))
+        .register(Box::new(SyncCheck::new("fed_line_status_ok", fed_line_status_ok)))
+        .register(Box::new(SyncCheck::new("cyber_threat_level_ok", cyber_threat_level_ok)));
+    checks
+}
+
+#[allow(non_camel_case_types)]
+pub enum TxDecision { TX_GO, TX_CAUTION, TX_HALT }
+
+pub async fn evaluate_finance_harmony(eval: &Evaluation) -> TxDecision {
+    match eval.decision {
+        Decision::GO => TxDecision::TX_GO,
+        Decision::CAUTION => TxDecision::TX_CAUTION,
+        Decision::HALT => {
+            log_harmony_fault(eval.mu, eval.ch);
+            TxDecision::TX_HALT
+        }
+    }
+}
+
+// One source per weighted channel, in weight order; swap in real telemetry backends here.
+fn score_sources() -> SourceSet {
+    let mut sources = SourceSet::new(Duration::from_millis(50));
+    sources
+        .register(Box::new(FnSource::new("liquidity_buffer", query_liquidity_buffer)))
+        .register(Box::new(FnSource::new("settlement_success_rate", query_settlement_success_rate)))
+        .register(Box::new(FnSource::new("fx_volatility_convergence", query_fx_volatility_convergence)))
+        .register(Box::new(FnSource::new("fraud_score_stability", query_fraud_score_stability)))
+        .register(Box::new(FnSource::new("fed_line_sync_health", query_fed_line_sync_health)));
+    sources
+}
+
+const FX_VOLATILITY: usize = 2; // fx_volatility_convergence channel
+// FX volatility has a weekly shape (the Sunday-evening open, the London/New York overlap);
+// scored against its learned 15-minute baseline, forgiving at most 0.001 of a slot's usual dip.
+const FX_MAX_ALLOWANCE: f64 = 0.001;
+
+fn score_baselines(channels: usize) -> Baselines {
+    let offset = std::env::var("HARMONY_MARKET_UTC_OFFSET_MIN").ok().and_then(|v| v.parse().ok()).unwrap_or(-300);
+    let fx = Baseline::new(Period::Weekly, Duration::from_secs(15 * 60), FX_MAX_ALLOWANCE).expect("fx baseline");
+    Baselines::new(channels).learn(FX_VOLATILITY, fx.utc_offset(offset))
+}
+
+// Outside NYSE hours (09:30-16:00 US Eastern, weekdays) flow is thin and mostly automated, so
+// the bar is stricter; market hours run the standard thresholds. Exchange holidays still count
+// as market hours. HARMONY_MARKET_UTC_OFFSET_MIN is -240 while US daylight time is in effect.
+const OFF_HOURS_THRESHOLD: f64 = 0.9998;
+const OFF_HOURS_CAUTION: f64 = 0.999;
+
+// Short-term channel behaviour for the desk, logged once a minute (600 cycles at 10 Hz).
+const STATS_WINDOWS: [Duration; 2] = [Duration::from_secs(60), Duration::from_secs(900)];
+const STATS_LOG_CYCLES: u64 = 600;
+
+const WEIGHTS: [f64; 5] = [0.30, 0.25, 0.20, 0.15, 0.10];
+
+// Operator-approved limits, enforced by every profile's context and by the tuner alike: no
+// channel above 0.40; liquidity and settlement never below 0.20 and 0.15, the rest never
+// below 0.05.
+fn weight_governance(names: &[&str]) -> WeightGovernance {
+    let floors = [0.20, 0.15, 0.05, 0.05, 0.05];
+    names.iter().zip(floors).enumerate().try_fold(WeightGovernance::new(0.40).expect("max weight"), |g, (i, (name, min))| g.floor(i, name, min)).expect("weight floors")
+}
+
+// A dip that precedes a HALT within 30 s counts as predicting it; weights move once a minute at
+// most, 0.005 at a time, so a noisy feed loses weight over hours rather than cycles. Each change
+// is appended to HARMONY_WEIGHT_AUDIT before it applies and refused if that fails.
+fn weight_tuner(governance: &WeightGovernance) -> WeightTuner {
+    let mut tuner = WeightTuner::new("finance", &WEIGHTS, governance).expect("weight governance").horizon(300).period(600).rate(0.05, 0.005);
+    let path = std::env::var("HARMONY_WEIGHT_AUDIT").unwrap_or_else(|_| "/var/log/harmony/finance_weights.log".into());
+    tuner.audit_changes(move |change| {
+        let mut f = OpenOptions::new().create(true).append(true).open(&path).map_err(|e| format!("{}: {}", path, e))?;
+        writeln!(f, "{} {}", change.at_ms, change).and_then(|_| f.sync_data()).map_err(|e| format!("{}: {}", path, e))
+    });
+    tuner
 }
 
-impl FinanceContext {
-    pub fn calculate_mu(&self) -> f64 {
-        let mut log_sum = 0.0;
-        for (w, s) in self.weights.iter().zip(self.scores.iter()) {
-            let s_clipped = s.clamp(MIN_SCORE, 1.0);
-            log_sum += w * s_clipped.ln();
+fn threshold_profiles(channels: usize, governance: &WeightGovernance) -> ProfileSchedule {
+    let weights = WEIGHTS.to_vec();
+    let market = HarmonyContext::builder().weights(weights.clone()).channels(channels).governance(governance.clone()).build().expect("harmony context");
+    let off_hours = HarmonyContext::builder()
+        .weights(weights)
+        .channels(channels)
+        .governance(governance.clone())
+        .threshold(OFF_HOURS_THRESHOLD)
+        .caution_threshold(OFF_HOURS_CAUTION)
+        .build()
+        .expect("off-hours harmony context");
+    let offset = std::env::var("HARMONY_MARKET_UTC_OFFSET_MIN").ok().and_then(|v| v.parse().ok()).unwrap_or(-300);
+    ProfileSchedule::new("off_hours", off_hours)
+        .utc_offset(offset)
+        .profile("market_hours", market, &[Window::new(WEEKDAYS, hm(9, 30), hm(16, 0)).expect("market window")])
+        .expect("threshold profiles")
+}
+
+// The gate pipe serves a decision for one 10 Hz cycle plus margin, then TX_HALT.
+const GATE_LEASE: Duration = Duration::from_millis(150);
+// chrony samples go stale after 10 s; refresh every 5 s at 10 Hz.
+const CLOCK_REFRESH_CYCLES: u64 = 50;
+
+// The decision history for Basel audits: every cycle's record, sealed a minute (600 cycles) at a
+// time. HARMONY_AUDIT_SIGNER picks the key's home:
+//   pkcs11 (default)  HSM key HARMONY_AUDIT_KEY_LABEL on the module HARMONY_PKCS11_MODULE (PIN in
+//                     HARMONY_PKCS11_PIN); HARMONY_PKCS11_VALIDATED lists, comma-separated, the
+//                     token identities with a CMVP certificate.
+//   vault             transit key HARMONY_AUDIT_KEY_REF at VAULT_ADDR (VAULT_TOKEN), mounted at
+//                     HARMONY_VAULT_TRANSIT (default transit).
+//   aws-kms, gcp-kms  KMS key HARMONY_AUDIT_KEY_REF, through the instance's CLI credentials.
+// Each run starts its own chain in a new file under HARMONY_AUDIT_DIR.
+const AUDIT_SEGMENT_LEN: usize = 600;
+
+fn audit_signer() -> Result<Box<dyn SegmentSigner>, String> {
+    let var = |k: &str| std::env::var(k).map_err(|_| format!("{} not set", k));
+    match std::env::var("HARMONY_AUDIT_SIGNER").as_deref().unwrap_or("pkcs11") {
+        "pkcs11" => {
+            let validated = std::env::var("HARMONY_PKCS11_VALIDATED").unwrap_or_default();
+            let validated: Vec<&str> = validated.split(',').map(str::trim).filter(|v| !v.is_empty()).collect();
+            let signer = Pkcs11Signer::open(&var("HARMONY_PKCS11_MODULE")?, &var("HARMONY_PKCS11_PIN")?, &var("HARMONY_AUDIT_KEY_LABEL")?, &validated)?;
+            Ok(Box::new(signer))
+        }
+        "vault" => {
+            let transit = std::env::var("HARMONY_VAULT_TRANSIT").unwrap_or_else(|_| "transit".into());
+            // Signing only: the PKI mount and role are never used on this path.
+            let provider = VaultProvider::new(&var("VAULT_ADDR")?, &var("VAULT_TOKEN")?, "pki", "harmony", &transit);
+            Ok(Box::new(ProviderSigner { provider, key_ref: var("HARMONY_AUDIT_KEY_REF")? }))
         }
-        log_sum.exp()
+        "aws-kms" => Ok(Box::new(ProviderSigner { provider: KmsProvider::new(CloudKms::Aws), key_ref: var("HARMONY_AUDIT_KEY_REF")? })),
+        "gcp-kms" => Ok(Box::new(ProviderSigner { provider: KmsProvider::new(CloudKms::Gcp), key_ref: var("HARMONY_AUDIT_KEY_REF")? })),
+        other => Err(format!("HARMONY_AUDIT_SIGNER {}: expected pkcs11, vault, aws-kms or gcp-kms", other)),
     }
 }
 
-pub async fn check_ch() -> bool {
-    aml_alert_clear()            &&
-    regulatory_capital_ok()      &&
-    dual_control_sign_off_ok()   &&
-    fed_line_status_ok()         &&
-    cyber_threat_level_ok()
+fn decision_audit(signer: Box<dyn SegmentSigner>) -> Result<AuditLog<File, Box<dyn SegmentSigner>>, String> {
+    let dir = std::env::var("HARMONY_AUDIT_DIR").unwrap_or_else(|_| "/var/log/harmony".into());
+    let path = Path::new(&dir).join(format!("finance-{}.audit", now_ms()));
+    let file = OpenOptions::new().create_new(true).append(true).open(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(AuditLog::new(file, signer, AUDIT_SEGMENT_LEN, 0, [0; 32]))
 }
 
-pub enum TxDecision { TX_GO, TX_HALT }
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
 
-pub async fn evaluate_finance_harmony(mu: f64, ch: bool) -> TxDecision {
-    if mu >= HARMONY_THRESHOLD && ch {
-        TxDecision::TX_GO
-    } else {
+// Autoheal runs on every HALT cycle; the domain counts as safe until it decides GO again.
+struct Finance {
+    sources: SourceSet,
+    checks: CheckRegistry,
+    baselines: Baselines,
+    audit: AuditLog<File, Box<dyn SegmentSigner>>,
+    healing: bool,
+}
+
+impl Finance {
+    // Conditions beyond the registry's checks. A key source that cannot sign leaves the history
+    // unsealed, so trading degrades to CAUTION until a seal signs again.
+    fn check_ch(&self, conditions: &mut Conditions) {
+        conditions.record("audit_key_source_ok", Severity::Major, self.audit.key_source_ok());
+    }
+}
+
+impl Domain for Finance {
+    fn name(&self) -> &str { "finance" }
+    fn tick(&self) -> Duration { Duration::from_millis(100) } // 10 Hz
+    fn weights(&self) -> Vec<f64> { WEIGHTS.to_vec() }
+    fn sources(&self) -> &SourceSet { &self.sources }
+    fn checks(&self) -> &CheckRegistry { &self.checks }
+
+    fn safe_state(&mut self) {
         trigger_autoheal();
-        log_harmony_fault(mu, ch);
-        TxDecision::TX_HALT
+        self.healing = true;
+    }
+
+    fn in_safe_state(&self) -> bool {
+        self.healing
+    }
+
+    fn refine(&mut self, scores: &mut [f64], errors: &mut Vec<(usize, SourceError)>, conditions: &mut Conditions) {
+        self.baselines.apply(scores, errors, unix_now());
+        self.check_ch(conditions);
+    }
+
+    fn decide(&mut self, _scores: &[f64], _errors: &[(usize, SourceError)], _conditions: &Conditions, eval: Evaluation) -> Evaluation {
+        if eval.decision == Decision::GO {
+            self.healing = false;
+        }
+        eval
     }
 }
 
-#[tokio::main]
-async fn main() {
-    let ctx = FinanceContext {
-        scores: vec![0.98, 0.97, 1.0, 0.96, 0.99],
-        weights: vec![0.30, 0.25, 0.20, 0.15, 0.10],
-    };
+async fn run_finance_harmony() {
+    let sources = score_sources();
+    let governance = weight_governance(&sources.names());
+    let mut profiles = threshold_profiles(sources.len(), &governance);
+    profiles.select(unix_now());
+    println!("Finance: threshold profile {}", profiles.active());
+    let latest = Arc::new(Mutex::new((String::from("TX_HALT"), Instant::now())));
+    #[cfg(windows)]
+    {
+        if let Err(e) = windows_host::init_event_log() {
+            eprintln!("Finance: event log unavailable: {}", e);
+        }
+        let gate = latest.clone();
+        tokio::spawn(async move {
+            if let Err(e) = windows_host::serve_gate_pipe(gate, GATE_LEASE).await {
+                eprintln!("Finance: gate pipe stopped: {}", e);
+            }
+        });
+    }
+    let baselines = score_baselines(sources.len());
+    // No signed decision history, no trading: refuse to start rather than run unaudited.
+    let audit = audit_signer().and_then(decision_audit).expect("decision audit log");
+    let mut monitor = HarmonyMonitor::new(Finance { sources, checks: ch_checks(), baselines, audit, healing: false }).expect("harmony context");
+    monitor.set_context(profiles.context().clone()).expect("threshold profile");
+    monitor.stats_windows(&STATS_WINDOWS);
+    let mut tuner = weight_tuner(&governance);
+    let mut clock = ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS);
+    let mut record = DecisionRecord::new("finance", 0, 0.0, false, Decision::HALT, clock.status());
+    let mut cycle = 0u64;
     loop {
-        let scores = vec![
-            query_liquidity_buffer().await,
-            query_settlement_success_rate().await,
-            query_fx_volatility_convergence().await,
-            query_fraud_score_stability().await,
-            query_fed_line_sync_health().await,
-        ];
-        let mu = ctx.calculate_mu();
-        let ch = check_ch().await;
-        match evaluate_finance_harmony(mu, ch).await {
-            TxDecision::TX_GO => println!("Finance: TX RESONANCE GO"),
-            TxDecision::TX_HALT => println!("Finance: TX HALT – safe-state"),
-        }
-        tokio::time::sleep(Duration::from_millis(100)).await; // 10 Hz
+        let start = Instant::now();
+        #[cfg(windows)]
+        if windows_host::stop_requested() {
+            if let Err(e) = monitor.domain_mut().audit.seal() {
+                eprintln!("Finance: audit log: final segment left unsealed: {}", e);
+            }
+            return;
+        }
+        if cycle.is_multiple_of(CLOCK_REFRESH_CYCLES) {
+            clock.refresh().await;
+        }
+        if let Some(t) = profiles.select(unix_now()) {
+            println!("Finance: threshold profile {}", t);
+            monitor.set_context(profiles.context().clone()).expect("threshold profile");
+        }
+        let eval = monitor.cycle().await;
+        for (i, e) in monitor.source_errors() {
+            eprintln!("Finance: score source {} failed: {}", monitor.names()[*i], e);
+        }
+        cycle += 1;
+        record.refresh(cycle, eval.mu, eval.ch, eval.decision, clock.status());
+        record.set_lease(GATE_LEASE);
+        if let Err(e) = monitor.domain_mut().audit.append_line(&record.to_json()) {
+            eprintln!("Finance: audit log: {}", e);
+        }
+        if cycle.is_multiple_of(STATS_LOG_CYCLES) {
+            for (i, name) in monitor.names().iter().enumerate() {
+                if let Some(s) = monitor.stats().stats(i, STATS_WINDOWS[0]) {
+                    println!("Finance: {} over {}s: {}", name, STATS_WINDOWS[0].as_secs(), s);
+                }
+            }
+        }
+        match tuner.observe(monitor.scores(), monitor.source_errors(), &eval) {
+            Ok(Some(weights)) => match profiles.set_weights(weights) {
+                Ok(()) => {
+                    println!("Finance: weights tuned to {:?}", weights);
+                    monitor.set_context(profiles.context().clone()).expect("threshold profile");
+                }
+                Err(e) => {
+                    eprintln!("Finance: audited weights refused, reverting tuner: {}", e);
+                    tuner.revert(profiles.context().weights());
+                }
+            },
+            Ok(None) => {}
+            Err(e) => eprintln!("Finance: {}", e),
+        }
+        println!("Finance: dmu/ds {}", monitor.sensitivity());
+        let (line, state) = match evaluate_finance_harmony(&eval).await {
+            TxDecision::TX_GO => ("Finance: TX RESONANCE GO", "TX_GO"),
+            TxDecision::TX_CAUTION => ("Finance: TX CAUTION – early warning", "TX_CAUTION"),
+            TxDecision::TX_HALT => ("Finance: TX HALT – safe-state", "TX_HALT"),
+        };
+        match monitor.conditions().failure_summary() {
+            failed if failed.is_empty() => println!("{}", line),
+            failed => println!("{} [{}]", line, failed),
+        }
+        #[cfg(windows)]
+        windows_host::log_decision(line, state == "TX_HALT");
+        *latest.lock().unwrap() = (format!("{} mu={}", state, eval.mu), Instant::now());
+        tokio::time::sleep(monitor.domain().tick().saturating_sub(start.elapsed())).await;
+    }
+}
+
+fn run_engine() {
+    tokio::runtime::Runtime::new().expect("tokio runtime").block_on(run_finance_harmony());
+}
+
+fn main() {
+    #[cfg(windows)]
+    match std::env::args().nth(1).as_deref() {
+        Some("install") => {
+            let exe = std::env::current_exe().expect("current exe");
+            windows_host::install_service(&exe).expect("install service");
+            return;
+        }
+        Some("uninstall") => {
+            windows_host::uninstall_service().expect("uninstall service");
+            return;
+        }
+        Some("service") => {
+            windows_host::run_service(run_engine).expect("service dispatcher");
+            return;
+        }
+        _ => {}
     }
+    run_engine();
 }
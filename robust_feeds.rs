@@ -0,0 +1,110 @@
+//! Robust_Feeds.rs - Byzantine-tolerant score aggregation from untrusted feeds (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::collections::BTreeMap;
+
+const REPUTATION_PENALTY: f64 = 0.2;
+const REPUTATION_RECOVERY: f64 = 0.01;
+const REPUTATION_FLOOR: f64 = 0.5;
+// Consistency constant so MAD estimates a standard deviation for normal data.
+const MAD_SCALE: f64 = 1.4826;
+
+pub struct FeedSample {
+    pub feed_id: String,
+    pub value: f64,
+}
+
+pub struct AggregateOutcome {
+    pub value: f64,
+    pub used: Vec<String>,
+    pub rejected: Vec<String>,
+}
+
+pub struct RobustAggregator {
+    max_faulty: usize,
+    outlier_k: f64,
+    min_spread: f64,
+    reputation: BTreeMap<String, f64>,
+}
+
+impl RobustAggregator {
+    // Tolerates `max_faulty` arbitrary feeds; needs 2f+1 feeds reporting to produce a value.
+    // Reputation only decides which of them the value is taken from: a feed that has lost
+    // trust cannot, by being distrusted, take the aggregate away from the others.
+    pub fn new(max_faulty: usize, outlier_k: f64, min_spread: f64) -> Self {
+        RobustAggregator { max_faulty, outlier_k, min_spread, reputation: BTreeMap::new() }
+    }
+
+    // 2f+1: with fewer feeds reporting, f liars can outvote the honest ones.
+    pub fn required_feeds(&self) -> usize {
+        2 * self.max_faulty + 1
+    }
+
+    // For the deployment's feed list: refuses one too short to ever produce a value.
+    pub fn check_feeds(&self, configured: usize) -> Result<(), String> {
+        if configured < self.required_feeds() {
+            return Err(format!("{} feeds configured; tolerating {} faulty needs at least {}", configured, self.max_faulty, self.required_feeds()));
+        }
+        Ok(())
+    }
+
+    pub fn reputation(&self, feed_id: &str) -> f64 {
+        *self.reputation.get(feed_id).unwrap_or(&1.0)
+    }
+
+    // Median of trusted feeds after MAD outlier rejection. None means "not enough
+    // independent evidence", which the caller must treat as a failed score. With fewer than
+    // 2f+1 trusted feeds the center and the value come from every reporting feed instead;
+    // the median of 2f+1 or more with at most f faulty still lies within the honest ones.
+    pub fn aggregate(&mut self, samples: &[FeedSample]) -> Option<AggregateOutcome> {
+        let reporting: Vec<f64> = samples.iter().filter(|s| s.value.is_finite()).map(|s| s.value).collect();
+        if reporting.len() < self.required_feeds() {
+            return None;
+        }
+        let trusted: Vec<f64> = samples
+            .iter()
+            .filter(|s| s.value.is_finite() && self.reputation(&s.feed_id) >= REPUTATION_FLOOR)
+            .map(|s| s.value)
+            .collect();
+        let enough_trusted = trusted.len() >= self.required_feeds();
+        let basis = if enough_trusted { &trusted } else { &reporting };
+        let center = median(basis);
+        let deviations: Vec<f64> = basis.iter().map(|v| (v - center).abs()).collect();
+        let spread = (MAD_SCALE * median(&deviations)).max(self.min_spread);
+
+        // Every feed is judged against the trusted center, so a distrusted feed that
+        // agrees again slowly earns its way back above the floor.
+        let mut used = Vec::new();
+        let mut rejected = Vec::new();
+        let mut kept = Vec::new();
+        for s in samples {
+            let agrees = s.value.is_finite() && (s.value - center).abs() <= self.outlier_k * spread;
+            let was_trusted = self.reputation(&s.feed_id) >= REPUTATION_FLOOR;
+            let r = self.reputation.entry(s.feed_id.clone()).or_insert(1.0);
+            if agrees {
+                *r = (*r + REPUTATION_RECOVERY).min(1.0);
+            } else {
+                *r = (*r - REPUTATION_PENALTY).max(0.0);
+            }
+            if agrees && (was_trusted || !enough_trusted) {
+                used.push(s.feed_id.clone());
+                kept.push(s.value);
+            } else {
+                rejected.push(s.feed_id.clone());
+            }
+        }
+        if kept.len() < self.max_faulty + 1 {
+            return None;
+        }
+        Some(AggregateOutcome { value: median(&kept), used, rejected })
+    }
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut v = values.to_vec();
+    v.sort_by(|a, b| a.total_cmp(b));
+    let n = v.len();
+    if n == 0 {
+        return f64::NAN;
+    }
+    if n % 2 == 1 { v[n / 2] } else { (v[n / 2 - 1] + v[n / 2]) / 2.0 }
+}
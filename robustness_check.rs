@@ -0,0 +1,78 @@
+//! Robustness_Check.rs - Perturbs weights/threshold within tolerance and ranks decision fragility (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::{env, fs, process};
+
+mod anchor;
+mod decision_kernel;
+use anchor::{parse_anchor, Frame};
+use decision_kernel::{HARMONY_THRESHOLD, MIN_SCORE};
+
+struct Tolerances {
+    weight_rel: f64,
+    threshold_abs: f64,
+}
+
+fn decisions(frames: &[Frame], weight_scale: Option<(usize, f64)>, threshold: f64) -> Vec<bool> {
+    frames
+        .iter()
+        .map(|f| {
+            let mut log_sum = 0.0;
+            for (i, (w, s)) in f.weights.iter().zip(f.scores.iter()).enumerate() {
+                let w = match weight_scale {
+                    Some((j, k)) if j == i => w * k,
+                    _ => *w,
+                };
+                log_sum += w * s.clamp(MIN_SCORE, 1.0).ln();
+            }
+            log_sum.exp() >= threshold && f.ch
+        })
+        .collect()
+}
+
+fn flips(a: &[bool], b: &[bool]) -> usize {
+    a.iter().zip(b.iter()).filter(|(x, y)| x != y).count()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some(path) = args.first() else {
+        eprintln!("usage: robustness_check <anchor> [--weight-tol REL] [--threshold-tol ABS]");
+        process::exit(2);
+    };
+    let mut tol = Tolerances { weight_rel: 0.05, threshold_abs: 0.0002 };
+    for pair in args[1..].chunks(2) {
+        let v: f64 = match pair.get(1).and_then(|v| v.parse().ok()) {
+            Some(v) => v,
+            None => { eprintln!("bad value for {}", pair[0]); process::exit(2); }
+        };
+        match pair[0].as_str() {
+            "--weight-tol" => tol.weight_rel = v,
+            "--threshold-tol" => tol.threshold_abs = v,
+            other => { eprintln!("unknown option {}", other); process::exit(2); }
+        }
+    }
+    let frames = match fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|t| parse_anchor(&t)) {
+        Ok(f) => f,
+        Err(e) => { eprintln!("{}: {}", path, e); process::exit(2); }
+    };
+    let channels = frames.iter().map(|f| f.weights.len()).max().unwrap_or(0);
+    let baseline = decisions(&frames, None, HARMONY_THRESHOLD);
+
+    // Each parameter is moved to both ends of its declared tolerance; the score is
+    // the worst-case number of cycles whose GO/HALT outcome changes.
+    let mut fragility: Vec<(String, usize)> = Vec::new();
+    for i in 0..channels {
+        let down = decisions(&frames, Some((i, 1.0 - tol.weight_rel)), HARMONY_THRESHOLD);
+        let up = decisions(&frames, Some((i, 1.0 + tol.weight_rel)), HARMONY_THRESHOLD);
+        fragility.push((format!("weight[{}] ±{}%", i, tol.weight_rel * 100.0), flips(&baseline, &down).max(flips(&baseline, &up))));
+    }
+    let lo = decisions(&frames, None, HARMONY_THRESHOLD - tol.threshold_abs);
+    let hi = decisions(&frames, None, HARMONY_THRESHOLD + tol.threshold_abs);
+    fragility.push((format!("threshold ±{}", tol.threshold_abs), flips(&baseline, &lo).max(flips(&baseline, &hi))));
+
+    fragility.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    println!("Robustness over {} cycles (baseline GO in {}):", frames.len(), baseline.iter().filter(|g| **g).count());
+    for (param, n) in &fragility {
+        println!("  {:<24} {:>6} outcome flips ({:.2}%)", param, n, 100.0 * *n as f64 / frames.len().max(1) as f64);
+    }
+}
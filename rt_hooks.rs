@@ -0,0 +1,202 @@
+//! RT_Hooks.rs - SCHED_FIFO, CPU pinning and mlockall for the evaluation thread, verified via procfs (forbid unsafe)
+//!
+//! Options come from the environment so unit files and container specs can set them:
+//!   HARMONY_RT_PRIORITY=1..99   SCHED_FIFO priority for the calling thread
+//!   HARMONY_RT_CPUS=2,3 or 2-3  pin the calling thread to these (isolated) CPUs
+//!   HARMONY_RT_MLOCK=1          mlockall(MCL_CURRENT | MCL_FUTURE)
+//! `verify` reads back what the kernel actually granted; a silently failed request shows up there.
+#![forbid(unsafe_code)]
+use std::fs;
+
+use nix::sched::{sched_setaffinity, CpuSet};
+use nix::sys::mman::{mlockall, MlockAllFlags};
+use nix::unistd::Pid;
+use thread_priority::unix::{
+    set_thread_priority_and_policy, thread_native_id, RealtimeThreadSchedulePolicy, ScheduleParams, ThreadSchedulePolicy,
+};
+use thread_priority::ThreadPriority;
+
+const SCHED_FIFO: u32 = 1;
+
+#[derive(Clone, Debug, Default)]
+pub struct RtOptions {
+    pub fifo_priority: Option<u8>,
+    pub cpus: Vec<usize>,
+    pub mlock: bool,
+}
+
+impl RtOptions {
+    pub fn from_env() -> Self {
+        Self::from_vars(|k| std::env::var(k).ok())
+    }
+
+    // from_env over any lookup, so the parsing is testable without touching the process env.
+    // HARMONY_RT_CPUS takes the kernel's list syntax, ranges included ("2-3,6").
+    fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |k: &str| lookup(k).filter(|v| !v.trim().is_empty());
+        RtOptions {
+            fifo_priority: var("HARMONY_RT_PRIORITY").and_then(|v| v.trim().parse().ok()).filter(|p| (1..=99).contains(p)),
+            cpus: var("HARMONY_RT_CPUS").map(|v| parse_cpu_list(&v)).unwrap_or_default(),
+            mlock: var("HARMONY_RT_MLOCK").is_some_and(|v| v.trim() == "1"),
+        }
+    }
+
+    pub fn requested(&self) -> bool {
+        self.fifo_priority.is_some() || !self.cpus.is_empty() || self.mlock
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RtStatus {
+    pub fifo_ok: bool,
+    pub affinity_ok: bool,
+    pub mlock_ok: bool,
+    pub errors: Vec<String>,
+}
+
+impl RtStatus {
+    pub fn guarantees_met(&self) -> bool {
+        self.fifo_ok && self.affinity_ok && self.mlock_ok
+    }
+}
+
+// Applies to the calling thread (priority, affinity) and the whole process (mlock).
+// Call from the thread that runs the evaluation loop, before the loop starts.
+pub fn apply(opts: &RtOptions) -> Vec<String> {
+    let mut errors = Vec::new();
+    if opts.mlock {
+        if let Err(e) = mlockall(MlockAllFlags::MCL_CURRENT | MlockAllFlags::MCL_FUTURE) {
+            errors.push(format!("mlockall: {}", e));
+        }
+    }
+    if !opts.cpus.is_empty() {
+        let mut set = CpuSet::new();
+        for &cpu in &opts.cpus {
+            if let Err(e) = set.set(cpu) {
+                errors.push(format!("cpu {}: {}", cpu, e));
+            }
+        }
+        if let Err(e) = sched_setaffinity(Pid::from_raw(0), &set) {
+            errors.push(format!("sched_setaffinity: {}", e));
+        }
+    }
+    if let Some(p) = opts.fifo_priority {
+        let priority = ThreadPriority::from_posix(ScheduleParams { sched_priority: p as i32 });
+        let policy = ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo);
+        if let Err(e) = set_thread_priority_and_policy(thread_native_id(), priority, policy) {
+            errors.push(format!("SCHED_FIFO {}: {:?}", p, e));
+        }
+    }
+    errors
+}
+
+// (policy, rt_priority) of the calling thread: fields 41 and 40 of /proc/thread-self/stat.
+fn sched_of_current_thread() -> Option<(u32, u32)> {
+    let stat = fs::read_to_string("/proc/thread-self/stat").ok()?;
+    // Field 2 (comm) may contain spaces; everything after the closing paren starts at field 3.
+    let rest: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    Some((rest.get(38)?.parse().ok()?, rest.get(37)?.parse().ok()?))
+}
+
+fn status_field(path: &str, key: &str) -> Option<String> {
+    fs::read_to_string(path).ok()?.lines().find_map(|l| l.strip_prefix(key)).map(|v| v.trim().to_string())
+}
+
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cpus = Vec::new();
+    for part in list.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((a, b)) => {
+                if let (Ok(a), Ok(b)) = (a.parse::<usize>(), b.parse::<usize>()) {
+                    cpus.extend(a..=b);
+                }
+            }
+            None => cpus.extend(part.parse::<usize>().ok()),
+        }
+    }
+    cpus
+}
+
+fn kib(v: Option<String>) -> u64 {
+    v.and_then(|s| s.trim_end_matches("kB").trim().parse().ok()).unwrap_or(0)
+}
+
+// Unrequested guarantees count as met so `guarantees_met` reflects only what was asked for.
+pub fn verify(opts: &RtOptions) -> RtStatus {
+    let mut st = RtStatus { fifo_ok: true, affinity_ok: true, mlock_ok: true, errors: Vec::new() };
+    if let Some(p) = opts.fifo_priority {
+        match sched_of_current_thread() {
+            Some((policy, prio)) if policy == SCHED_FIFO && prio == p as u32 => {}
+            Some((policy, prio)) => {
+                st.fifo_ok = false;
+                st.errors.push(format!("scheduler is policy {} priority {}, wanted SCHED_FIFO {}", policy, prio, p));
+            }
+            None => {
+                st.fifo_ok = false;
+                st.errors.push("cannot read /proc/thread-self/stat".into());
+            }
+        }
+    }
+    if !opts.cpus.is_empty() {
+        let mut allowed = status_field("/proc/thread-self/status", "Cpus_allowed_list:").map(|l| parse_cpu_list(&l)).unwrap_or_default();
+        let mut wanted = opts.cpus.clone();
+        allowed.sort_unstable();
+        wanted.sort_unstable();
+        wanted.dedup();
+        if allowed != wanted {
+            st.affinity_ok = false;
+            st.errors.push(format!("thread may run on CPUs {:?}, wanted {:?}", allowed, wanted));
+        }
+    }
+    if opts.mlock {
+        let locked = kib(status_field("/proc/self/status", "VmLck:"));
+        let rss = kib(status_field("/proc/self/status", "VmRSS:"));
+        if locked == 0 || locked < rss {
+            st.mlock_ok = false;
+            st.errors.push(format!("{} KiB locked of {} KiB resident", locked, rss));
+        }
+    }
+    st
+}
+
+// Applies the options and logs what was actually obtained; returns the verified status.
+pub fn setup(label: &str, opts: &RtOptions) -> RtStatus {
+    if !opts.requested() {
+        return RtStatus { fifo_ok: true, affinity_ok: true, mlock_ok: true, errors: Vec::new() };
+    }
+    for e in apply(opts) {
+        eprintln!("{}: RT setup: {}", label, e);
+    }
+    let st = verify(opts);
+    if st.guarantees_met() {
+        println!("{}: RT guarantees obtained ({:?})", label, opts);
+    } else {
+        for e in &st.errors {
+            eprintln!("{}: RT guarantee missing: {}", label, e);
+        }
+    }
+    st
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(vars: &[(&str, &str)]) -> RtOptions {
+        RtOptions::from_vars(|k| vars.iter().find(|(name, _)| *name == k).map(|(_, v)| v.to_string()))
+    }
+
+    #[test]
+    fn from_env_parses_every_option() {
+        let o = opts(&[("HARMONY_RT_PRIORITY", "80"), ("HARMONY_RT_CPUS", "2-3,6"), ("HARMONY_RT_MLOCK", "1")]);
+        assert_eq!((o.fifo_priority, o.cpus, o.mlock), (Some(80), vec![2, 3, 6], true));
+        assert!(!opts(&[]).requested());
+    }
+
+    #[test]
+    fn from_env_ignores_out_of_range_and_blank_values() {
+        let o = opts(&[("HARMONY_RT_PRIORITY", "100"), ("HARMONY_RT_CPUS", " "), ("HARMONY_RT_MLOCK", "yes")]);
+        assert!(!o.requested(), "{:?}", o);
+        assert_eq!(opts(&[("HARMONY_RT_PRIORITY", "0")]).fifo_priority, None);
+    }
+}
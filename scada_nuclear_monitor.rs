@@ -1,54 +1,443 @@
 //! SCADA_Nuclear_Monitor.rs - NRC / IEC 61513 Ground Safety Crate (forbid unsafe)
 #![forbid(unsafe_code)]
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant};
 
-const HARMONY_THRESHOLD: f64 = 0.9995;
-const MIN_SCORE: f64 = 1e-12;
+mod attestation;
+mod clock_sync;
+mod consistency_checker;
+mod core;
+mod crypto_policy;
+mod decision;
+mod decision_kernel;
+mod decision_stream;
+mod plugin;
+mod plausibility;
+mod rbac;
+mod rt_hooks;
+mod sealed_config;
+use crate::core::calibration::Calibrations;
+use crate::core::checks::{CheckOutcome, CheckRegistry, SyncCheck};
+use crate::core::filter::ScoreFilters;
+use crate::core::gate::GateSet;
+use crate::core::governance::WeightGovernance;
+use crate::core::harmony::{self, Aggregator, Conditions, ContextBuilder, Decision, Evaluation, HarmonyContext, InvalidScorePolicy, Severity};
+use crate::core::monitor::HarmonyMonitor;
+use crate::core::source::{FnSource, ScoreSource, SourceError, SourceSet, StalePolicy};
+use crate::core::trend::{MuTrend, Trends};
+use crate::core::vote::{VoteAlarm, VotedSource};
+use attestation::AttestationMonitor;
+use clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+use consistency_checker::ConsistencyChecker;
+use decision::DecisionRecord;
+use decision_stream::RecordVerifier;
+use plausibility::{MetricBounds, PlausibilityGuard};
+use plugin::Domain;
+use rt_hooks::RtOptions;
+use sealed_config::{EngineConfig, SealPolicy};
 
-pub struct NuclearContext {
-    pub scores: Vec<f64>,
-    pub weights: Vec<f64>,
+
+// Safety review: mu is the worst channel, so no healthy channel can offset a bad one and every
+// channel must clear the threshold on its own. The weights still select the channels.
+const AGGREGATOR: Aggregator = Aggregator::Minimum;
+// GO needs the worst channel to clear the threshold by two standard deviations of its estimate
+// (the Kalman primary_coolant_health channel; the others are taken as exact).
+const CONFIDENCE_Z: f64 = 2.0;
+// A NaN or infinite filter estimate means the filter itself has failed; halt rather than
+// reason about the channel.
+const INVALID_SCORES: InvalidScorePolicy = InvalidScorePolicy::Halt;
+
+// Safety review: no channel may carry more than 0.40 of mu, and the reactivity, coolant and
+// containment channels keep enough weight to matter. A sealed config may tighten these
+// limits (max_weight, min_weight.<channel>) but never relax them.
+fn weight_governance() -> WeightGovernance {
+    WeightGovernance::new(0.40)
+        .and_then(|g| g.floor(0, "neutron_flux_coherence", 0.20))
+        .and_then(|g| g.floor(PRIMARY_COOLANT, "primary_coolant_health", 0.15))
+        .and_then(|g| g.floor(CONTAINMENT_PRESSURE, "containment_pressure", 0.15))
+        .expect("weight governance")
+}
+
+fn ch_checks() -> CheckRegistry {
+    let mut checks = CheckRegistry::new();
+    checks
+        .register(Box::new(SyncCheck::new("telemetry_link_alive", telemetry_link_alive)))
+        .register(Box::new(SyncCheck::new("range_safety_clear", range_safety_clear)))
+        .register(Box::new(SyncCheck::new("reactor_pressure_ok", reactor_pressure_ok)))
+        .register(Box::new(SyncCheck::new("operator_alert_ok", operator_alert_ok)))
+        .register(Box::new(SyncCheck::new("no_scram_override", no_scram_override)));
+    checks
+}
+
+// Neutron flux comes from three independent detectors voted 2oo3: one failed or outlying
+// detector leaves the channel on the other two and fails neutron_flux_coherence_sensors_agree
+// (Major, CAUTION); losing a second fails the channel. The detectors are read together within
+// 60 ms, well inside the 250 ms deadline the whole source set shares.
+fn neutron_flux() -> VotedSource {
+    let detectors: Vec<Box<dyn ScoreSource>> = vec![
+        Box::new(FnSource::new("neutron_flux_detector_a", query_neutron_flux_detector_a)),
+        Box::new(FnSource::new("neutron_flux_detector_b", query_neutron_flux_detector_b)),
+        Box::new(FnSource::new("neutron_flux_detector_c", query_neutron_flux_detector_c)),
+    ];
+    VotedSource::new("neutron_flux_coherence", 2, detectors)
+        .expect("2oo3 neutron flux")
+        .tolerance(0.005)
+        .leg_timeout(Duration::from_millis(60))
+}
+
+// One source per weighted channel, in weight order; swap in real telemetry backends here.
+// No control decision rests on a reading older than two cycles: a stale channel scores
+// MIN_SCORE and data_fresh fails Critical.
+fn score_sources() -> (SourceSet, VoteAlarm) {
+    let flux = neutron_flux();
+    let flux_vote = flux.alarm();
+    let mut sources = SourceSet::new(Duration::from_millis(250));
+    sources
+        .max_age(Duration::from_secs(2), StalePolicy::Halt)
+        .register(Box::new(flux))
+        .register(Box::new(FnSource::new("primary_coolant_health", query_primary_coolant_health)))
+        .register(Box::new(FnSource::new("containment_pressure", query_containment_pressure)))
+        .register(Box::new(FnSource::new("cyber_i_c_health", query_cyber_i_c_health)))
+        .register(Box::new(FnSource::new("operator_alertness", query_operator_alertness)));
+    (sources, flux_vote)
+}
+
+const PRIMARY_COOLANT: usize = 1; // primary_coolant_health channel
+const CONTAINMENT_PRESSURE: usize = 2; // containment_pressure channel
+const INNOVATION_LOG_SIGMAS: f64 = 3.0;
+
+// Smooth the process channels against sensor noise; cyber and operator channels stay raw so a
+// real event there is seen on the cycle it happens. At 1 Hz a 3 s half-life delays a HALT on a
+// sustained drop by a few cycles. Coolant is Kalman-estimated (drift variance 1e-8 per cycle,
+// reading variance 1e-7).
+fn score_filters(channels: usize) -> ScoreFilters {
+    ScoreFilters::new(channels)
+        .ewma(0, Duration::from_secs(3))
+        .kalman(PRIMARY_COOLANT, 1e-8, 1e-7)
+        .ewma(2, Duration::from_secs(3))
+}
+
+// Containment pressure margin is gated on its trend as well as its level: losing 0.002/s
+// (about 0.1 a minute) is an early warning, 0.01/s holds the rod drive while it is still above
+// threshold. Fitted over 30 s of the smoothed score, so at 1 Hz a step needs ~15 s to show.
+fn score_trends(channels: usize) -> Trends {
+    Trends::new(channels, Duration::from_secs(30)).limit(CONTAINMENT_PRESSURE, 0.002, 0.01)
+}
+
+// The three process channels are analog and noisy: an exactly repeated value for two minutes is
+// a frozen transmitter or a spoofed feed, and a score cannot honestly move more than 0.2 in a
+// second. Cyber and operator scores are computed, may sit at 1.0 all shift and may step, so
+// only their range is checked. An implausible channel counts as failed until 30 s clean.
+fn plausibility_guard(channels: usize) -> PlausibilityGuard {
+    let bounds = (0..channels)
+        .map(|i| {
+            let process = i <= CONTAINMENT_PRESSURE;
+            MetricBounds {
+                min: 0.0,
+                max: 1.0,
+                max_rate_per_s: if process { 0.2 } else { f64::INFINITY },
+                max_flat: if process { Duration::from_secs(120) } else { Duration::MAX },
+                recovery: Duration::from_secs(30),
+            }
+        })
+        .collect();
+    PlausibilityGuard::new(bounds)
+}
+
+// The evaluation settings every context this monitor runs on shares, built-in or sealed.
+fn evaluation(builder: ContextBuilder) -> ContextBuilder {
+    builder.aggregator(AGGREGATOR).decide_on_lower_bound(CONFIDENCE_Z).on_invalid_score(INVALID_SCORES)
+}
+
+// Redundant monitor nodes decide on the same plant at 1 Hz from their own sensor trains; mu may
+// differ by filter noise, and a node may land a cycle behind. Three consecutive cycles apart
+// by more than this, or on different decisions, fail peers_consistent.
+const PEER_MU_TOLERANCE: f64 = 0.0005;
+const PEER_DISAGREEMENT_CYCLES: u32 = 3;
+const PEER_RECORD_MAX_AGE: Duration = Duration::from_secs(3);
+// chrony samples go stale after 10 s; refresh every 5 s at 1 Hz.
+const CLOCK_REFRESH_CYCLES: u64 = 5;
+
+// Peer decisions are verified against HARMONY_DECISION_PEERS (one `<node_id> <hex ed25519 public
+// key>` per line) before they are compared; without it no peer is heard, and the comparison
+// has only this node to go on.
+fn peer_verifier() -> RecordVerifier {
+    let peers_path = std::env::var("HARMONY_DECISION_PEERS").unwrap_or_else(|_| "/etc/harmony/decision_peers".into());
+    let trusted = match std::fs::read_to_string(&peers_path).map_err(|e| e.to_string()).and_then(|t| sealed_config::parse_trusted_keys(&t)) {
+        Ok(keys) => keys,
+        Err(e) => {
+            eprintln!("Nuclear: decision peers {}: {}", peers_path, e);
+            Vec::new()
+        }
+    };
+    RecordVerifier::new(trusted, PEER_RECORD_MAX_AGE)
+}
+
+// The rod drive is held from the first HALT cycle until the monitor is back at GO.
+struct Nuclear {
+    sources: SourceSet,
+    checks: CheckRegistry,
+    names: Vec<String>,
+    flux_vote: VoteAlarm,
+    // Only a validated sealed config may move the threshold, combine interlocks into gates or
+    // mark the config sealed.
+    threshold: f64,
+    gates: GateSet,
+    config_sealed: bool,
+    attestation: AttestationMonitor,
+    plausibility: PlausibilityGuard,
+    filters: ScoreFilters,
+    trends: Trends,
+    // Compared after each cycle, so peers_consistent reflects the previous cycle's decisions.
+    consistency: ConsistencyChecker,
+    rod_drive_held: bool,
+}
+
+impl Nuclear {
+    fn new() -> Self {
+        let (sources, flux_vote) = score_sources();
+        let names = sources.names().iter().map(|n| n.to_string()).collect();
+        let channels = sources.len();
+        Nuclear {
+            sources,
+            checks: ch_checks(),
+            names,
+            flux_vote,
+            threshold: harmony::HARMONY_THRESHOLD,
+            gates: GateSet::default(),
+            config_sealed: false,
+            attestation: AttestationMonitor::new(load_attestation_policy()),
+            plausibility: plausibility_guard(channels),
+            filters: score_filters(channels),
+            trends: score_trends(channels),
+            consistency: ConsistencyChecker::new(PEER_MU_TOLERANCE, PEER_DISAGREEMENT_CYCLES),
+            rod_drive_held: false,
+        }
+    }
+
+    // Conditions beyond the registry's checks.
+    fn check_ch(&self, conditions: &mut Conditions) {
+        let faults = self.attestation.faults();
+        let attested = match faults.is_empty() {
+            true => CheckOutcome::pass(),
+            false => CheckOutcome::fail(&format!("{:?}", faults)),
+        };
+        conditions.record("config_sealed", Severity::Critical, self.config_sealed).record_outcome("attested", Severity::Critical, attested);
+        conditions.record_with(
+            "scores_plausible",
+            Severity::Critical,
+            self.plausibility.all_plausible(),
+            (!self.plausibility.all_plausible()).then(|| format!("{:?}", self.plausibility.flags())),
+        );
+        self.gates.apply(conditions);
+        self.flux_vote.record(conditions);
+        self.trends.record(&self.names, conditions);
+        conditions.record("peers_consistent", Severity::Major, self.consistency.consistent());
+    }
 }
 
-impl NuclearContext {
-    pub fn calculate_mu(&self) -> f64 {
-        let mut log_sum = 0.0;
-        for (w, s) in self.weights.iter().zip(self.scores.iter()) {
-            let s_clipped = s.clamp(MIN_SCORE, 1.0);
-            log_sum += w * s_clipped.ln();
+impl Domain for Nuclear {
+    fn name(&self) -> &str { "nuclear" }
+    fn tick(&self) -> Duration { Duration::from_secs(1) }
+    fn weights(&self) -> Vec<f64> { vec![0.30, 0.25, 0.20, 0.15, 0.10] }
+    fn sources(&self) -> &SourceSet { &self.sources }
+    fn checks(&self) -> &CheckRegistry { &self.checks }
+    fn threshold(&self) -> f64 { self.threshold }
+
+    fn safe_state(&mut self) {
+        if !self.rod_drive_held {
+            println!("Nuclear: holding rod drive");
+        }
+        self.rod_drive_held = true;
+    }
+
+    fn in_safe_state(&self) -> bool {
+        self.rod_drive_held
+    }
+
+    // Plausibility before filtering, so a stuck or spoofed reading is judged raw; flagged
+    // channels then count as failed sources for the filters, trends and score faults alike.
+    fn refine(&mut self, scores: &mut [f64], errors: &mut Vec<(usize, SourceError)>, conditions: &mut Conditions) {
+        for (i, score) in scores.iter_mut().enumerate() {
+            if !errors.iter().any(|(e, _)| *e == i) {
+                *score = self.plausibility.admit(i, *score);
+            }
         }
-        log_sum.exp()
+        for (i, reason) in self.plausibility.flags() {
+            if !errors.iter().any(|(e, _)| *e == i) {
+                errors.push((i, SourceError::Malformed(format!("implausible: {:?}", reason))));
+            }
+        }
+        let now = Instant::now();
+        self.filters.apply(scores, errors, now);
+        self.trends.update(scores, errors, now);
+        self.check_ch(conditions);
+    }
+
+    fn stddev(&self, channel: usize) -> f64 {
+        self.filters.stddev(channel)
+    }
+
+    fn decide(&mut self, _scores: &[f64], _errors: &[(usize, SourceError)], _conditions: &Conditions, eval: Evaluation) -> Evaluation {
+        if eval.decision == Decision::GO {
+            self.rod_drive_held = false;
+        }
+        eval
     }
 }
 
-pub async fn check_ch() -> bool {
-    telemetry_link_alive() &&
-    range_safety_clear()   &&
-    reactor_pressure_ok()  &&
-    operator_alert_ok()    &&
-    no_scram_override()
+// A re-sealed config is picked up once a minute at 1 Hz.
+const CONFIG_RELOAD_CYCLES: u64 = 60;
+
+// Applies a re-sealed config's weights through HarmonyContext::set_weights, under the same
+// governance as at start. Anything else changed, or a config that no longer verifies, is
+// refused and the running config stays in force.
+fn reload_weights(config_path: &str, keys_path: &str, active: &mut EngineConfig, monitor: &mut HarmonyMonitor<Nuclear>, config_hash: &mut String) {
+    let sealed = match sealed_config::load(config_path, keys_path, SealPolicy::KeepCurrent) {
+        Ok(sealed) if sealed.hash == *config_hash => return,
+        other => other,
+    };
+    let applied = sealed.and_then(|sealed| match active.differs_beyond_weights(&sealed.config) {
+        Some(setting) => Err(format!("{} changed, which needs a restart", setting)),
+        None => monitor.set_weights(&sealed.config.weights).map(|_| sealed),
+    });
+    match applied {
+        Ok(sealed) => {
+            println!("Nuclear: config {} -> {}: weights {:?}", config_hash, sealed.hash, monitor.context().weights());
+            *config_hash = sealed.hash;
+            *active = sealed.config;
+        }
+        Err(e) => eprintln!("Nuclear: config reload rejected: {}; keeping current config {}", e, config_hash),
+    }
 }
 
 #[tokio::main]
 async fn main() {
-    let ctx = NuclearContext {
-        scores: vec![0.98, 0.97, 1.0, 0.96, 0.99],
-        weights: vec![0.30, 0.25, 0.20, 0.15, 0.10],
+    // The evaluation loop runs on this thread (block_on), so RT settings applied here cover it.
+    let rt = RtOptions::from_env();
+    rt_hooks::setup("Nuclear", &rt);
+    let mut cycle: u64 = 0;
+    let mut nuclear = Nuclear::new();
+    // An unsealed or tampered config never reaches GO: the monitor runs HALT-only.
+    let config_path = std::env::var("HARMONY_CONFIG").unwrap_or_else(|_| "/etc/harmony/nuclear.conf".into());
+    let keys_path = std::env::var("HARMONY_TRUSTED_KEYS").unwrap_or_else(|_| "/etc/harmony/trusted_engineers".into());
+    let mut active_config = None;
+    let mut sealed_ctx = None;
+    let mut config_hash = match sealed_config::load(&config_path, &keys_path, SealPolicy::HaltOnly) {
+        Ok(sealed) if sealed.sealed() => {
+            // A correctly signed config can still be wrong; validate it like the built-in one.
+            // Its threshold replaces the default and its conditions must match the registry.
+            // Gates may only combine registry interlocks, never config_sealed or attested.
+            // Calibration curves take effect only with the rest of the sealed config.
+            let (sources, checks) = (&nuclear.sources, &nuclear.checks);
+            let governance = WeightGovernance::parse(sealed.config.max_weight, &sealed.config.min_weights, &sources.names())
+                .and_then(|g| weight_governance().tighten(&g));
+            let ctx = governance.and_then(|governance| {
+                evaluation(HarmonyContext::builder())
+                    .weights(sealed.config.weights.clone())
+                    .threshold(sealed.config.threshold)
+                    .channels(sources.len())
+                    .governance(governance)
+                    .build()
+                    .map_err(|e| e.to_string())
+            });
+            let sealed_parts = ctx.and_then(|c| {
+                checks.require(&sealed.config.conditions)?;
+                let g = GateSet::parse(&sealed.config.gates, &checks.names())?;
+                Ok((c, g, Calibrations::parse(&sealed.config.calibrations, &sources.names())?))
+            });
+            match sealed_parts {
+                Ok((ctx, gates, calibrations)) => {
+                    println!("Nuclear: weight governance {}", ctx.governance());
+                    nuclear.sources.calibrate(&calibrations).expect("calibrations name registered sources");
+                    for c in calibrations.iter() {
+                        println!("Nuclear: {} calibrated {}", c.channel, c.curve);
+                    }
+                    nuclear.threshold = ctx.threshold;
+                    nuclear.gates = gates;
+                    nuclear.config_sealed = true;
+                    sealed_ctx = Some(ctx);
+                    active_config = Some(sealed.config);
+                    sealed.hash
+                }
+                Err(e) => {
+                    eprintln!("Nuclear: sealed config rejected: {}; running HALT-only", e);
+                    sealed.hash
+                }
+            }
+        }
+        Ok(sealed) => sealed.hash,
+        Err(e) => {
+            eprintln!("Nuclear: {}; running HALT-only", e);
+            "unavailable".to_string()
+        }
     };
+    let mut monitor = HarmonyMonitor::with_context(nuclear, |b| evaluation(b).governance(weight_governance())).expect("harmony context");
+    if let Some(ctx) = sealed_ctx {
+        monitor.set_context(ctx).expect("sealed context");
+    }
+    let node_id = std::env::var("HARMONY_NODE_ID").unwrap_or_else(|_| "nuclear".into());
+    let mut peer_records = peer_verifier();
+    let mut clock = ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS);
+    let mut record = DecisionRecord::new(&node_id, 0, 0.0, false, Decision::HALT, clock.status());
+    // mu over the same 30 s, extrapolated up to two minutes ahead.
+    let mut mu_trend = MuTrend::new(Duration::from_secs(30)).horizon(Duration::from_secs(120));
     loop {
-        let scores = vec![
-            query_neutron_flux_coherence().await,
-            query_primary_coolant_health().await,
-            query_containment_pressure().await,
-            query_cyber_i_c_health().await,
-            query_operator_alertness().await,
-        ];
-        let mu = ctx.calculate_mu();
-        let ch = check_ch().await;
-        match (mu >= HARMONY_THRESHOLD && ch) {
-            true  => println!("Nuclear: CONTROL GO"),
-            false => println!("Nuclear: CONTROL HALT – hold rod drive"),
-        }
-        tokio::time::sleep(Duration::from_secs(1)).await;
+        let start = Instant::now();
+        cycle += 1;
+        if rt.requested() && cycle.is_multiple_of(600) {
+            for e in rt_hooks::verify(&rt).errors {
+                eprintln!("Nuclear: RT guarantee lost: {}", e);
+            }
+        }
+        if cycle % 10 == 1 {
+            monitor.domain_mut().attestation.measure();
+        }
+        if let Some(active) = active_config.as_mut().filter(|_| cycle.is_multiple_of(CONFIG_RELOAD_CYCLES)) {
+            reload_weights(&config_path, &keys_path, active, &mut monitor, &mut config_hash);
+        }
+        if let Some(verdict) = poll_attestation_verdict().await {
+            monitor.domain_mut().attestation.accept_verdict(verdict);
+        }
+        if cycle % CLOCK_REFRESH_CYCLES == 1 {
+            clock.refresh().await;
+        }
+        let eval = monitor.cycle().await;
+        let decision = eval.decision;
+        record.refresh(cycle, eval.mu, eval.ch, decision, clock.status());
+        let consistency = &mut monitor.domain_mut().consistency;
+        consistency.observe(record.clone());
+        while let Some(peer) = recv_peer_decision().await {
+            match peer_records.verify(&peer) {
+                Ok(_) => consistency.observe(peer),
+                Err(e) => eprintln!("Nuclear: peer decision refused: {:?}", e),
+            }
+        }
+        if let Some(disagreement) = consistency.compare() {
+            eprintln!("Nuclear: redundant nodes disagree: {:?}", disagreement);
+        }
+        for (i, e) in monitor.source_errors() {
+            eprintln!("Nuclear: score source {} failed: {}", monitor.names()[*i], e);
+        }
+        let nuclear = monitor.domain();
+        if let Some(inn) = nuclear.filters.innovation(PRIMARY_COOLANT).filter(|i| i.sigmas() > INNOVATION_LOG_SIGMAS) {
+            eprintln!("Nuclear: primary_coolant_health reading {:+.6} off prediction ({:.1} sigma), estimate {:.6}", inn.innovation, inn.sigmas(), inn.estimate);
+        }
+        let interval = *monitor.interval().expect("nuclear decides on mu's lower bound");
+        println!("Nuclear: mu {} dmu/ds {}", interval, monitor.sensitivity());
+        println!("Nuclear: containment_pressure {:+.5}/s", nuclear.trends.slope(CONTAINMENT_PRESSURE));
+        mu_trend.update(interval.lower, Instant::now());
+        if let Some(eta) = mu_trend.eta(monitor.context().caution_threshold).filter(|_| decision != Decision::HALT) {
+            println!("Nuclear: eta_to_halt {:.0} s (mu {:+.6}/s)", eta.as_secs_f64(), mu_trend.slope());
+        }
+        match decision {
+            Decision::GO => println!("Nuclear: CONTROL GO [config {}]", config_hash),
+            Decision::CAUTION => println!("Nuclear: CONTROL CAUTION – early warning [config {}]", config_hash),
+            Decision::HALT => println!(
+                "Nuclear: CONTROL HALT – hold rod drive [{}] [config {}]",
+                monitor.conditions().failure_summary(),
+                config_hash
+            ),
+        }
+        tokio::time::sleep(monitor.domain().tick().saturating_sub(start.elapsed())).await;
     }
 }
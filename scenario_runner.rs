@@ -0,0 +1,46 @@
+//! Scenario_Runner.rs - Runs scripted scenarios and asserts decision sequences (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::{env, fs, process};
+
+mod clock_sync;
+mod core;
+mod decision;
+mod decision_kernel;
+mod plugin;
+mod rbac;
+mod simulation;
+use simulation::{parse_scenario, run_scenario};
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: scenario_runner <scenario.yaml>...");
+        process::exit(2);
+    }
+    let mut failed = 0;
+    for path in &paths {
+        let text = match fs::read_to_string(path) {
+            Ok(t) => t,
+            Err(e) => { eprintln!("{}: {}", path, e); failed += 1; continue; }
+        };
+        let scenario = match parse_scenario(&text) {
+            Ok(s) => s,
+            Err(e) => { eprintln!("{}: {}", path, e); failed += 1; continue; }
+        };
+        let (trace, mismatches) = match run_scenario(&scenario) {
+            Ok(run) => run,
+            Err(e) => { eprintln!("{}: {}", path, e); failed += 1; continue; }
+        };
+        for m in &mismatches {
+            eprintln!(
+                "{}: tick {} expected {:?} got {:?} (mu={:.6})",
+                scenario.name, m.tick, m.expected, m.actual, m.mu
+            );
+        }
+        match mismatches.is_empty() {
+            true  => println!("PASS {} ({} ticks)", scenario.name, trace.len()),
+            false => { println!("FAIL {}", scenario.name); failed += 1; }
+        }
+    }
+    process::exit(if failed == 0 { 0 } else { 1 });
+}
@@ -0,0 +1,49 @@
+//! Seal_Config.rs - Safety-engineer tool to sign and verify engine config seals (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::{env, fs, process};
+
+use ed25519_dalek::SigningKey;
+
+mod sealed_config;
+use sealed_config::{parse_config, parse_trusted_keys, sign_config, unhex, verify_seal};
+
+fn fail(msg: String) -> ! {
+    eprintln!("seal_config: {}", msg);
+    process::exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["sign", config, signer, key_file] => {
+            let bytes = fs::read(config).unwrap_or_else(|e| fail(format!("{}: {}", config, e)));
+            // Refuse to seal something the engine would not be able to load.
+            parse_config(&String::from_utf8_lossy(&bytes)).unwrap_or_else(|e| fail(format!("{}: {}", config, e)));
+            let secret: [u8; 32] = fs::read_to_string(key_file)
+                .ok()
+                .and_then(|s| unhex(&s))
+                .and_then(|b| b.try_into().ok())
+                .unwrap_or_else(|| fail(format!("{}: expected a 32-byte hex Ed25519 secret key", key_file)));
+            let seal = sign_config(&bytes, signer, &SigningKey::from_bytes(&secret));
+            let seal_path = format!("{}.seal", config);
+            fs::write(&seal_path, seal).unwrap_or_else(|e| fail(format!("{}: {}", seal_path, e)));
+            println!("sealed {} as {}", config, signer);
+        }
+        ["verify", config, trusted_keys] => {
+            let bytes = fs::read(config).unwrap_or_else(|e| fail(format!("{}: {}", config, e)));
+            let seal_path = format!("{}.seal", config);
+            let seal = fs::read_to_string(&seal_path).unwrap_or_else(|e| fail(format!("{}: {}", seal_path, e)));
+            let keys = fs::read_to_string(trusted_keys).unwrap_or_else(|e| fail(format!("{}: {}", trusted_keys, e)));
+            let trusted = parse_trusted_keys(&keys).unwrap_or_else(|e| fail(e));
+            match verify_seal(&bytes, &seal, &trusted) {
+                Ok(signer) => println!("{}: sealed by {}", config, signer),
+                Err(e) => fail(e),
+            }
+        }
+        _ => {
+            eprintln!("usage: seal_config sign <config> <signer-id> <secret-key-hex-file>");
+            eprintln!("       seal_config verify <config> <trusted-keys>");
+            process::exit(2);
+        }
+    }
+}
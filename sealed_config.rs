@@ -0,0 +1,299 @@
+//! Sealed_Config.rs - Safety-engineer-signed engine configuration (forbid unsafe)
+//!
+//! The approved config (weights, threshold, CH conditions) is hashed with SHA-256 and the digest
+//! signed with a safety engineer's Ed25519 key into `<config>.seal`:
+//!   sha256=<hex>
+//!   signer=<id>
+//!   sig=<hex>
+//! On startup the running config's hash must match the seal and the signature must verify under
+//! one of the trusted engineer keys; otherwise the engine refuses to start or runs HALT-only.
+#![forbid(unsafe_code)]
+use std::fs;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SealPolicy {
+    RefuseStart,
+    HaltOnly,
+    // For a reload: a seal that does not verify is an error, and the caller keeps the config
+    // it is already running.
+    KeepCurrent,
+}
+
+#[derive(Clone, Debug)]
+pub struct EngineConfig {
+    pub threshold: f64,
+    pub weights: Vec<f64>,
+    pub conditions: Vec<String>,
+    // Provider name per weight, in channel order; required by the hosted (tenant) service.
+    pub providers: Vec<String>,
+    // `gate.<name> = <severity>: <expr>` lines as (name, spec); parsed by core::gate.
+    pub gates: Vec<(String, String)>,
+    // `calibrate.<channel> = <curve>` lines as (channel, spec); parsed by core::calibration.
+    pub calibrations: Vec<(String, String)>,
+    // Weight governance (core::governance): `max_weight = <w>` and `min_weight.<channel> = <w>`.
+    pub max_weight: Option<f64>,
+    pub min_weights: Vec<(String, f64)>,
+}
+
+impl EngineConfig {
+    // The first setting, other than the weights, on which `other` differs: a running engine
+    // may take new weights from a reload, but anything else needs a restart.
+    pub fn differs_beyond_weights(&self, other: &EngineConfig) -> Option<&'static str> {
+        if self.threshold != other.threshold {
+            Some("threshold")
+        } else if self.conditions != other.conditions {
+            Some("conditions")
+        } else if self.providers != other.providers {
+            Some("providers")
+        } else if self.gates != other.gates {
+            Some("gates")
+        } else if self.calibrations != other.calibrations {
+            Some("calibrations")
+        } else if self.max_weight != other.max_weight || self.min_weights != other.min_weights {
+            Some("weight governance")
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SealedConfig {
+    pub config: EngineConfig,
+    pub hash: String,
+    // None when the seal failed and the engine is running HALT-only.
+    pub signer: Option<String>,
+}
+
+impl SealedConfig {
+    pub fn sealed(&self) -> bool {
+        self.signer.is_some()
+    }
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn unhex(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim();
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok()).collect()
+}
+
+pub fn config_digest(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+// `key = value` lines; `#` comments. weights, conditions and providers are comma-separated.
+pub fn parse_config(text: &str) -> Result<EngineConfig, String> {
+    let mut threshold = None;
+    let mut weights = None;
+    let mut conditions = Vec::new();
+    let mut providers = Vec::new();
+    let mut gates = Vec::new();
+    let mut calibrations = Vec::new();
+    let mut max_weight = None;
+    let mut min_weights = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (k, v) = line.split_once('=').ok_or_else(|| format!("line {}: expected key = value", i + 1))?;
+        let bad = || format!("line {}: bad value for {}", i + 1, k.trim());
+        match k.trim() {
+            "threshold" => threshold = Some(v.trim().parse::<f64>().map_err(|_| bad())?),
+            "weights" => {
+                weights = Some(v.split(',').map(|w| w.trim().parse::<f64>()).collect::<Result<Vec<_>, _>>().map_err(|_| bad())?)
+            }
+            "conditions" => conditions = v.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect(),
+            "providers" => providers = v.split(',').map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect(),
+            gate if gate.starts_with("gate.") && gate.len() > 5 => gates.push((gate[5..].to_string(), v.trim().to_string())),
+            cal if cal.starts_with("calibrate.") && cal.len() > 10 => calibrations.push((cal[10..].to_string(), v.trim().to_string())),
+            "max_weight" => max_weight = Some(v.trim().parse::<f64>().map_err(|_| bad())?),
+            min if min.starts_with("min_weight.") && min.len() > 11 => {
+                min_weights.push((min[11..].to_string(), v.trim().parse::<f64>().map_err(|_| bad())?))
+            }
+            other => return Err(format!("line {}: unknown key {}", i + 1, other)),
+        }
+    }
+    Ok(EngineConfig {
+        threshold: threshold.ok_or("threshold missing")?,
+        weights: weights.ok_or("weights missing")?,
+        conditions,
+        providers,
+        gates,
+        calibrations,
+        max_weight,
+        min_weights,
+    })
+}
+
+struct Seal {
+    sha256: String,
+    signer: String,
+    sig: Vec<u8>,
+}
+
+fn parse_seal(text: &str) -> Result<Seal, String> {
+    let field = |k: &str| {
+        text.lines()
+            .find_map(|l| l.trim().strip_prefix(k).and_then(|r| r.strip_prefix('=')))
+            .map(|v| v.trim().to_string())
+            .ok_or_else(|| format!("seal: {} missing", k))
+    };
+    Ok(Seal {
+        sha256: field("sha256")?.to_ascii_lowercase(),
+        signer: field("signer")?,
+        sig: unhex(&field("sig")?).ok_or("seal: sig is not hex")?,
+    })
+}
+
+// Checks `config_bytes` against `seal_text` under the trusted (id, public key) list.
+pub fn verify_seal(config_bytes: &[u8], seal_text: &str, trusted: &[(String, VerifyingKey)]) -> Result<String, String> {
+    let seal = parse_seal(seal_text)?;
+    let digest = config_digest(config_bytes);
+    if hex(&digest) != seal.sha256 {
+        return Err(format!("config hash {} does not match sealed hash {}", hex(&digest), seal.sha256));
+    }
+    let key = trusted
+        .iter()
+        .find(|(id, _)| *id == seal.signer)
+        .map(|(_, k)| k)
+        .ok_or_else(|| format!("signer {} is not a trusted safety engineer", seal.signer))?;
+    let sig_bytes: [u8; 64] = seal.sig.as_slice().try_into().map_err(|_| "seal: signature must be 64 bytes")?;
+    key.verify(&digest, &Signature::from_bytes(&sig_bytes)).map_err(|_| format!("seal signature by {} does not verify", seal.signer))?;
+    Ok(seal.signer)
+}
+
+pub fn sign_config(config_bytes: &[u8], signer: &str, key: &SigningKey) -> String {
+    let digest = config_digest(config_bytes);
+    format!("sha256={}\nsigner={}\nsig={}\n", hex(&digest), signer, hex(&key.sign(&digest).to_bytes()))
+}
+
+// Trusted keys file: one `<id> <hex ed25519 public key>` per line.
+pub fn parse_trusted_keys(text: &str) -> Result<Vec<(String, VerifyingKey)>, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| {
+            let (id, key) = l.split_once(char::is_whitespace).ok_or_else(|| format!("trusted keys: bad line {}", l))?;
+            let bytes: [u8; 32] = unhex(key)
+                .and_then(|b| b.try_into().ok())
+                .ok_or_else(|| format!("trusted keys: {} is not a 32-byte hex key", id))?;
+            let key = VerifyingKey::from_bytes(&bytes).map_err(|_| format!("trusted keys: {} is not a valid key", id))?;
+            Ok((id.to_string(), key))
+        })
+        .collect()
+}
+
+// Loads and verifies the sealed config. With HaltOnly, a seal failure still returns the
+// parsed config (so the engine can report), but with `signer: None`; callers must then hold
+// HALT. With RefuseStart the error is returned.
+pub fn load(config_path: &str, trusted_keys_path: &str, policy: SealPolicy) -> Result<SealedConfig, String> {
+    let bytes = fs::read(config_path).map_err(|e| format!("{}: {}", config_path, e))?;
+    let config = parse_config(&String::from_utf8_lossy(&bytes)).map_err(|e| format!("{}: {}", config_path, e))?;
+    let hash = hex(&config_digest(&bytes));
+    let seal_path = format!("{}.seal", config_path);
+    let verified = fs::read_to_string(&seal_path)
+        .map_err(|e| format!("{}: {}", seal_path, e))
+        .and_then(|seal| {
+            let trusted = fs::read_to_string(trusted_keys_path).map_err(|e| format!("{}: {}", trusted_keys_path, e))?;
+            verify_seal(&bytes, &seal, &parse_trusted_keys(&trusted)?)
+        });
+    match (verified, policy) {
+        (Ok(signer), _) => Ok(SealedConfig { config, hash, signer: Some(signer) }),
+        (Err(e), SealPolicy::RefuseStart | SealPolicy::KeepCurrent) => Err(e),
+        (Err(e), SealPolicy::HaltOnly) => {
+            eprintln!("SealedConfig: {}; running HALT-only", e);
+            Ok(SealedConfig { config, hash, signer: None })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const CONFIG: &str = "threshold = 0.9995\nweights = 0.5, 0.5\nconditions = reactor_pressure_ok\n";
+
+    fn engineer() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    fn trusted() -> Vec<(String, VerifyingKey)> {
+        vec![("se-alice".to_string(), engineer().verifying_key())]
+    }
+
+    // A config, its seal and the trusted keys in a fresh directory.
+    fn sealed_dir(test: &str, config: &str, seal: &str) -> (PathBuf, String, String) {
+        let dir = std::env::temp_dir().join(format!("sealed_config-{}-{}", test, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (config_path, keys_path) = (dir.join("harmony.conf"), dir.join("trusted_keys"));
+        fs::write(&config_path, config).unwrap();
+        fs::write(dir.join("harmony.conf.seal"), seal).unwrap();
+        fs::write(&keys_path, format!("se-alice {}\n", hex(engineer().verifying_key().as_bytes()))).unwrap();
+        (dir, config_path.to_string_lossy().into_owned(), keys_path.to_string_lossy().into_owned())
+    }
+
+    #[test]
+    fn seal_verifies_only_the_sealed_bytes_under_a_trusted_key() {
+        let seal = sign_config(CONFIG.as_bytes(), "se-alice", &engineer());
+        assert_eq!(verify_seal(CONFIG.as_bytes(), &seal, &trusted()), Ok("se-alice".to_string()));
+        let edited = CONFIG.replace("0.9995", "0.9");
+        assert!(verify_seal(edited.as_bytes(), &seal, &trusted()).unwrap_err().contains("does not match sealed hash"));
+
+        let impostor = sign_config(CONFIG.as_bytes(), "se-alice", &SigningKey::from_bytes(&[8; 32]));
+        assert_eq!(verify_seal(CONFIG.as_bytes(), &impostor, &trusted()), Err("seal signature by se-alice does not verify".into()));
+        let unknown = sign_config(CONFIG.as_bytes(), "se-mallory", &engineer());
+        assert!(verify_seal(CONFIG.as_bytes(), &unknown, &trusted()).unwrap_err().contains("not a trusted safety engineer"));
+        assert!(verify_seal(CONFIG.as_bytes(), "sha256=00\nsigner=se-alice\n", &trusted()).is_err());
+    }
+
+    #[test]
+    fn load_policy_decides_what_a_bad_seal_does() {
+        let seal = sign_config(CONFIG.as_bytes(), "se-alice", &engineer());
+        let (dir, config, keys) = sealed_dir("good", CONFIG, &seal);
+        let loaded = load(&config, &keys, SealPolicy::KeepCurrent).unwrap();
+        assert_eq!((loaded.signer.as_deref(), loaded.config.weights.as_slice()), (Some("se-alice"), &[0.5, 0.5][..]));
+        fs::remove_dir_all(dir).unwrap();
+
+        let (dir, config, keys) = sealed_dir("tampered", &CONFIG.replace("0.9995", "0.9"), &seal);
+        assert!(load(&config, &keys, SealPolicy::RefuseStart).is_err());
+        assert!(load(&config, &keys, SealPolicy::KeepCurrent).is_err());
+        let halt_only = load(&config, &keys, SealPolicy::HaltOnly).unwrap();
+        assert!(!halt_only.sealed());
+        assert_eq!(halt_only.config.threshold, 0.9);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_config_and_keys() {
+        for text in ["weights = 1.0", "threshold = 0.9", "threshold = x\nweights = 1", "threshold = 0.9\nweights = 1\nthreshhold = 0.5", "threshold 0.9"] {
+            assert!(parse_config(text).is_err(), "{:?}", text);
+        }
+        assert!(parse_trusted_keys("se-alice 00ff").is_err());
+        assert!(parse_trusted_keys("se-alice").is_err());
+        assert_eq!(parse_trusted_keys("# engineers\n\n").unwrap().len(), 0);
+        assert_eq!(unhex("0"), None);
+        assert_eq!(unhex("zz"), None);
+    }
+
+    #[test]
+    fn reload_may_change_only_the_weights() {
+        let running = parse_config(CONFIG).unwrap();
+        let reweighted = parse_config(&CONFIG.replace("0.5, 0.5", "0.4, 0.6")).unwrap();
+        assert_eq!(running.differs_beyond_weights(&reweighted), None);
+        let loosened = parse_config(&format!("{}max_weight = 0.6\n", CONFIG)).unwrap();
+        assert_eq!(running.differs_beyond_weights(&loosened), Some("weight governance"));
+        let retuned = parse_config(&CONFIG.replace("0.9995", "0.999")).unwrap();
+        assert_eq!(running.differs_beyond_weights(&retuned), Some("threshold"));
+    }
+}
@@ -0,0 +1,144 @@
+//! Self_IDS.rs - Anomaly detection on the engine's own cycle timing, memory and call patterns (forbid unsafe)
+//!
+//! During `learn_windows` the model records what normal looks like; the baseline is then frozen
+//! so a slow-moving compromise cannot retrain it. Afterwards each window is compared against it:
+//! cycle-time or RSS beyond `z_limit` standard deviations, a call kind never seen while learning,
+//! or a call-rate spike raises the cyber condition for `hold`.
+#![forbid(unsafe_code)]
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Default)]
+struct Welford {
+    n: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Welford {
+    fn push(&mut self, x: f64) {
+        self.n += 1;
+        let d = x - self.mean;
+        self.mean += d / self.n as f64;
+        self.m2 += d * (x - self.mean);
+    }
+
+    fn std(&self) -> f64 {
+        if self.n > 1 { (self.m2 / (self.n - 1) as f64).sqrt() } else { 0.0 }
+    }
+
+    // Distance in standard deviations; a floor on sigma keeps a perfectly steady baseline
+    // from turning every tiny wobble into an alarm.
+    fn z(&self, x: f64, sigma_floor: f64) -> f64 {
+        (x - self.mean).abs() / self.std().max(sigma_floor)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BehaviourAnomaly {
+    CycleTime { micros: f64, z: f64 },
+    Memory { rss_kib: f64, z: f64 },
+    UnknownCall(String),
+    CallRate { kind: String, count: u64, z: f64 },
+}
+
+pub struct SelfIds {
+    learn_windows: u32,
+    windows_seen: u32,
+    z_limit: f64,
+    hold: Duration,
+    cycle_us: Welford,
+    rss_kib: Welford,
+    call_rates: BTreeMap<String, Welford>,
+    window_calls: BTreeMap<String, u64>,
+    window_cycles: Vec<f64>,
+    last_anomaly: Option<(Vec<BehaviourAnomaly>, Instant)>,
+}
+
+fn rss_kib() -> f64 {
+    fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|s| s.lines().find_map(|l| l.strip_prefix("VmRSS:")).and_then(|v| v.trim().trim_end_matches("kB").trim().parse().ok()))
+        .unwrap_or(0.0)
+}
+
+impl SelfIds {
+    pub fn new(learn_windows: u32, z_limit: f64, hold: Duration) -> Self {
+        SelfIds {
+            learn_windows,
+            windows_seen: 0,
+            z_limit,
+            hold,
+            cycle_us: Welford::default(),
+            rss_kib: Welford::default(),
+            call_rates: BTreeMap::new(),
+            window_calls: BTreeMap::new(),
+            window_cycles: Vec::new(),
+            last_anomaly: None,
+        }
+    }
+
+    pub fn learning(&self) -> bool {
+        self.windows_seen < self.learn_windows
+    }
+
+    pub fn observe_cycle(&mut self, took: Duration) {
+        self.window_cycles.push(took.as_secs_f64() * 1e6);
+    }
+
+    // Every externally visible action counts: API requests by route, outbound connections,
+    // subprocesses spawned, files opened for write.
+    pub fn observe_call(&mut self, kind: &str) {
+        *self.window_calls.entry(kind.to_string()).or_insert(0) += 1;
+    }
+
+    // Closes the current window (call every N cycles); returns anomalies found in it.
+    pub fn end_window(&mut self) -> Vec<BehaviourAnomaly> {
+        let rss = rss_kib();
+        let cycles = std::mem::take(&mut self.window_cycles);
+        let calls = std::mem::take(&mut self.window_calls);
+        if self.learning() {
+            cycles.iter().for_each(|c| self.cycle_us.push(*c));
+            self.rss_kib.push(rss);
+            let kinds: Vec<String> = self.call_rates.keys().chain(calls.keys()).cloned().collect();
+            for kind in kinds {
+                let count = calls.get(&kind).copied().unwrap_or(0);
+                self.call_rates.entry(kind).or_default().push(count as f64);
+            }
+            self.windows_seen += 1;
+            return Vec::new();
+        }
+        let mut found = Vec::new();
+        if let Some(worst) = cycles.iter().cloned().fold(None, |m: Option<f64>, c| Some(m.map_or(c, |m| m.max(c)))) {
+            let z = self.cycle_us.z(worst, 1.0);
+            if z > self.z_limit {
+                found.push(BehaviourAnomaly::CycleTime { micros: worst, z });
+            }
+        }
+        let z = self.rss_kib.z(rss, 64.0);
+        if z > self.z_limit {
+            found.push(BehaviourAnomaly::Memory { rss_kib: rss, z });
+        }
+        for (kind, count) in &calls {
+            match self.call_rates.get(kind) {
+                None => found.push(BehaviourAnomaly::UnknownCall(kind.clone())),
+                Some(rate) => {
+                    let z = rate.z(*count as f64, 1.0);
+                    if z > self.z_limit {
+                        found.push(BehaviourAnomaly::CallRate { kind: kind.clone(), count: *count, z });
+                    }
+                }
+            }
+        }
+        if !found.is_empty() {
+            self.last_anomaly = Some((found.clone(), Instant::now()));
+        }
+        found
+    }
+
+    // Cyber check_ch condition; false while learning so an unbaselined engine never asserts it.
+    pub fn process_behaviour_ok(&self) -> bool {
+        !self.learning() && self.last_anomaly.as_ref().is_none_or(|(_, at)| at.elapsed() > self.hold)
+    }
+}
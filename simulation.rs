@@ -0,0 +1,203 @@
+//! Simulation.rs - Deterministic scripted-scenario runner over virtual time (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::fmt;
+
+use crate::core::harmony::{self, ContextError, HarmonyContext, MIN_SCORE};
+use crate::decision::Decision;
+
+#[derive(Clone, Debug, Default)]
+pub struct Step {
+    pub at: u64,
+    pub scores: Option<Vec<f64>>,
+    pub ch: Option<bool>,
+    // Named CH conditions failing from this tick on; an empty list clears them.
+    pub fail: Option<Vec<String>>,
+    pub expect: Option<Decision>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Scenario {
+    pub name: String,
+    pub threshold: f64,
+    // Defaults to the threshold: no CAUTION band unless the scenario asks for one.
+    pub caution_threshold: Option<f64>,
+    pub weights: Vec<f64>,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug)]
+pub struct ScenarioError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceEntry {
+    pub tick: u64,
+    pub decision: Decision,
+    pub mu: f64,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    pub tick: u64,
+    pub expected: Decision,
+    pub actual: Decision,
+    pub mu: f64,
+}
+
+impl Scenario {
+    // The engine's own context for the scenario, validated like a domain's.
+    pub fn context(&self) -> Result<HarmonyContext, ContextError> {
+        HarmonyContext::builder()
+            .weights(self.weights.clone())
+            .channels(self.weights.len())
+            .threshold(self.threshold)
+            .caution_threshold(self.caution_threshold.unwrap_or(self.threshold))
+            .build()
+    }
+}
+
+// Parses the scenario subset of YAML used under scenarios/:
+//   name: <str>, threshold: <f64>, caution_threshold: <f64> (optional), weights: [..],
+//   steps: list of {at, scores, ch, fail, expect}.
+// threshold and weights are required, every score list must have one score per weight, and
+// the weights and thresholds must build a valid HarmonyContext.
+pub fn parse_scenario(text: &str) -> Result<Scenario, ScenarioError> {
+    let mut sc = Scenario::default();
+    let mut threshold = None;
+    let mut in_steps = false;
+    for (i, raw) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let err = |m: &str| ScenarioError { line: line_no, message: m.to_string() };
+        let line = raw.split('#').next().unwrap_or("").trim_end();
+        if line.trim().is_empty() {
+            continue;
+        }
+        let trimmed = line.trim_start();
+        let (item_start, body) = match trimmed.strip_prefix("- ") {
+            Some(rest) => (true, rest),
+            None => (false, trimmed),
+        };
+        let (key, value) = body.split_once(':').ok_or_else(|| err("expected `key: value`"))?;
+        let (key, value) = (key.trim(), value.trim());
+        if in_steps && (item_start || raw.starts_with(' ')) {
+            if item_start {
+                sc.steps.push(Step::default());
+            }
+            let step = sc.steps.last_mut().ok_or_else(|| err("step field before `- at:`"))?;
+            match key {
+                "at" => step.at = value.parse().map_err(|_| err("bad tick"))?,
+                "scores" => {
+                    let scores = parse_list(value).ok_or_else(|| err("bad score list"))?;
+                    if scores.len() != sc.weights.len() {
+                        return Err(err(&format!("{} scores for {} weights", scores.len(), sc.weights.len())));
+                    }
+                    step.scores = Some(scores);
+                }
+                "ch" => step.ch = Some(value.parse().map_err(|_| err("ch must be true/false"))?),
+                "fail" => step.fail = Some(parse_names(value).ok_or_else(|| err("bad condition list"))?),
+                "expect" => step.expect = Some(parse_decision(value).ok_or_else(|| err("expect must be GO/CAUTION/HALT"))?),
+                _ => return Err(err("unknown step field")),
+            }
+            continue;
+        }
+        in_steps = false;
+        match key {
+            "name" => sc.name = value.to_string(),
+            "threshold" => threshold = Some(value.parse().map_err(|_| err("bad threshold"))?),
+            "caution_threshold" => sc.caution_threshold = Some(value.parse().map_err(|_| err("bad caution_threshold"))?),
+            "weights" if !sc.steps.is_empty() => return Err(err("weights must come before steps")),
+            "weights" => sc.weights = parse_list(value).ok_or_else(|| err("bad weight list"))?,
+            "steps" => in_steps = true,
+            _ => return Err(err("unknown key")),
+        }
+    }
+    let whole = |m: String| ScenarioError { line: 0, message: m };
+    sc.threshold = threshold.ok_or_else(|| whole("threshold missing".to_string()))?;
+    sc.context().map_err(|e| whole(e.to_string()))?;
+    if sc.steps.windows(2).any(|w| w[1].at < w[0].at) {
+        return Err(whole("steps must be in tick order".to_string()));
+    }
+    Ok(sc)
+}
+
+// Runs the scenario tick by tick through core::harmony::evaluate, GO/CAUTION/HALT as a monitor
+// would decide them. State set by a step persists until changed; no wall clock, RNG, or
+// hash-ordered container is involved, so runs are bit-for-bit repeatable.
+pub fn run_scenario(sc: &Scenario) -> Result<(Vec<TraceEntry>, Vec<Mismatch>), ContextError> {
+    let ctx = sc.context()?;
+    let mut scores = vec![MIN_SCORE; sc.weights.len()];
+    let mut ch = false;
+    let mut failing: Vec<String> = Vec::new();
+    let mut trace = Vec::new();
+    let mut mismatches = Vec::new();
+    let last = sc.steps.last().map_or(0, |s| s.at);
+    let mut next = 0;
+    for tick in 0..=last {
+        let mut expected = Vec::new();
+        while next < sc.steps.len() && sc.steps[next].at == tick {
+            let step = &sc.steps[next];
+            if let Some(s) = &step.scores {
+                scores = s.clone();
+            }
+            if let Some(c) = step.ch {
+                ch = c;
+            }
+            if let Some(f) = &step.fail {
+                failing = f.clone();
+            }
+            if let Some(e) = step.expect {
+                expected.push(e);
+            }
+            next += 1;
+        }
+        let ch_ok = ch && failing.is_empty();
+        let eval = harmony::evaluate(&ctx, &scores, ch_ok);
+        let (mu, decision) = (eval.mu, eval.decision);
+        let mut reasons = Vec::new();
+        if mu.is_nan() || mu < ctx.threshold {
+            reasons.push("MU_BELOW_THRESHOLD".to_string());
+        }
+        if !ch_ok {
+            reasons.push("CH_FAILED".to_string());
+            reasons.extend(failing.iter().map(|f| format!("CH_FAILED:{}", f)));
+        }
+        for e in expected {
+            if e != decision {
+                mismatches.push(Mismatch { tick, expected: e, actual: decision, mu });
+            }
+        }
+        trace.push(TraceEntry { tick, decision, mu, reasons });
+    }
+    Ok((trace, mismatches))
+}
+
+fn parse_list(v: &str) -> Option<Vec<f64>> {
+    let inner = v.strip_prefix('[')?.strip_suffix(']')?;
+    inner.split(',').map(|x| x.trim().parse().ok()).collect()
+}
+
+fn parse_names(v: &str) -> Option<Vec<String>> {
+    let inner = v.strip_prefix('[')?.strip_suffix(']')?.trim();
+    if inner.is_empty() {
+        return Some(Vec::new());
+    }
+    Some(inner.split(',').map(|x| x.trim().to_string()).collect())
+}
+
+fn parse_decision(v: &str) -> Option<Decision> {
+    match v {
+        "GO" => Some(Decision::GO),
+        "CAUTION" => Some(Decision::CAUTION),
+        "HALT" => Some(Decision::HALT),
+        _ => None,
+    }
+}
@@ -0,0 +1,123 @@
+//! Soak_Test.rs - Long-running cycle-latency soak with a p99.9 SLO assertion (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::time::{Duration, Instant};
+use std::{env, process, thread};
+
+mod clock_sync;
+mod decision;
+mod decision_kernel;
+use clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+use decision::{Decision, DecisionRecord};
+use decision_kernel::{HARMONY_THRESHOLD, MIN_SCORE};
+
+struct SoakConfig {
+    duration: Duration,
+    cadence: Duration,
+    channels: usize,
+    // Fraction of the cadence a single cycle may use at p99.9.
+    budget_fraction: f64,
+}
+
+fn parse_args() -> Result<SoakConfig, String> {
+    let mut cfg = SoakConfig {
+        duration: Duration::from_secs(3600),
+        cadence: Duration::from_millis(100),
+        channels: 5,
+        budget_fraction: 0.5,
+    };
+    let args: Vec<String> = env::args().skip(1).collect();
+    for pair in args.chunks(2) {
+        let value = pair.get(1).ok_or_else(|| format!("{} needs a value", pair[0]))?;
+        let num = |v: &str| v.parse::<f64>().map_err(|_| format!("bad value for {}: {}", pair[0], v));
+        match pair[0].as_str() {
+            "--hours" => cfg.duration = Duration::from_secs_f64(num(value)? * 3600.0),
+            "--seconds" => cfg.duration = Duration::from_secs_f64(num(value)?),
+            "--cadence-ms" => cfg.cadence = Duration::from_secs_f64(num(value)? / 1000.0),
+            "--channels" => cfg.channels = num(value)? as usize,
+            "--budget-fraction" => cfg.budget_fraction = num(value)?,
+            other => return Err(format!("unknown option {}", other)),
+        }
+    }
+    Ok(cfg)
+}
+
+// Deterministic synthetic provider: healthy values with occasional dips.
+struct SyntheticProvider {
+    state: u64,
+}
+
+impl SyntheticProvider {
+    fn sample(&mut self) -> f64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        let u = (self.state >> 11) as f64 / (1u64 << 53) as f64;
+        if u < 0.001 { 0.99 } else { 0.9995 + u * 0.0005 }
+    }
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let idx = ((sorted.len() as f64 - 1.0) * p).ceil() as usize;
+    sorted[idx.min(sorted.len() - 1)]
+}
+
+fn main() {
+    let cfg = match parse_args() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("soak_test: {}", e);
+            eprintln!("usage: soak_test [--hours H | --seconds S] [--cadence-ms MS] [--channels N] [--budget-fraction F]");
+            process::exit(2);
+        }
+    };
+    let weights = vec![1.0 / cfg.channels as f64; cfg.channels];
+    let mut providers: Vec<SyntheticProvider> =
+        (0..cfg.channels).map(|i| SyntheticProvider { state: 0x9E37_79B9_7F4A_7C15 ^ (i as u64 + 1) }).collect();
+    let clock = ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS);
+    let mut latencies = Vec::new();
+    let mut overruns = 0u64;
+    let start = Instant::now();
+    let mut deadline = start;
+    let mut seq = 0u64;
+    while start.elapsed() < cfg.duration {
+        let cycle_start = Instant::now();
+        let mut log_sum = 0.0;
+        for (w, p) in weights.iter().zip(providers.iter_mut()) {
+            log_sum += w * p.sample().clamp(MIN_SCORE, 1.0).ln();
+        }
+        let mu = log_sum.exp();
+        let decision = if mu >= HARMONY_THRESHOLD { Decision::GO } else { Decision::HALT };
+        seq += 1;
+        let record = DecisionRecord::new("soak", seq, mu, true, decision, clock.status());
+        std::hint::black_box(record.to_json());
+        let took = cycle_start.elapsed();
+        latencies.push(took);
+
+        deadline += cfg.cadence;
+        let now = Instant::now();
+        if now > deadline {
+            overruns += 1;
+            deadline = now;
+        } else {
+            thread::sleep(deadline - now);
+        }
+    }
+    latencies.sort();
+    let budget = cfg.cadence.mul_f64(cfg.budget_fraction);
+    let p50 = percentile(&latencies, 0.50);
+    let p99 = percentile(&latencies, 0.99);
+    let p999 = percentile(&latencies, 0.999);
+    let max = latencies.last().copied().unwrap_or_default();
+    println!(
+        "soak: {} cycles, p50={:?} p99={:?} p99.9={:?} max={:?} overruns={} budget={:?}",
+        latencies.len(), p50, p99, p999, max, overruns, budget
+    );
+    if p999 > budget {
+        eprintln!("soak: FAIL p99.9 {:?} exceeds cadence budget {:?}", p999, budget);
+        process::exit(1);
+    }
+    println!("soak: PASS");
+}
@@ -0,0 +1,125 @@
+//! Space_Weather.rs - NOAA SWPC space-weather provider shared by Space/Grid/Aviation (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::time::{Duration, Instant};
+
+const MAX_INDEX_AGE: Duration = Duration::from_secs(15 * 60);
+
+// Score per NOAA scale level 0..=5 (quiet .. extreme).
+const SCALE_SCORES: [f64; 6] = [1.0, 0.9995, 0.999, 0.99, 0.9, 0.5];
+
+pub struct SpaceWeatherIndices {
+    pub kp: f64,          // planetary K-index, 0..9
+    pub xray_flux: f64,   // GOES 0.1-0.8 nm, W/m^2
+    pub proton_flux: f64, // GOES >=10 MeV integral, pfu
+}
+
+// Highest tolerated NOAA scale level before the condition fails.
+pub struct SpaceWeatherLimits {
+    pub max_g: u8,
+    pub max_r: u8,
+    pub max_s: u8,
+}
+
+pub const GROUND_SEGMENT_LIMITS: SpaceWeatherLimits = SpaceWeatherLimits { max_g: 2, max_r: 1, max_s: 1 };
+pub const GRID_LIMITS: SpaceWeatherLimits = SpaceWeatherLimits { max_g: 3, max_r: 5, max_s: 5 };
+pub const AVIATION_LIMITS: SpaceWeatherLimits = SpaceWeatherLimits { max_g: 4, max_r: 2, max_s: 2 };
+
+pub struct SpaceWeatherProvider {
+    indices: Option<SpaceWeatherIndices>,
+    updated: Option<Instant>,
+}
+
+impl SpaceWeatherProvider {
+    pub fn new() -> Self {
+        SpaceWeatherProvider { indices: None, updated: None }
+    }
+
+    pub async fn refresh(&mut self) {
+        let indices = SpaceWeatherIndices {
+            kp: query_swpc_planetary_k_index().await,
+            xray_flux: query_goes_xray_flux().await,
+            proton_flux: query_goes_proton_flux().await,
+        };
+        self.ingest(indices);
+    }
+
+    pub fn ingest(&mut self, indices: SpaceWeatherIndices) {
+        self.indices = Some(indices);
+        self.updated = Some(Instant::now());
+    }
+
+    pub fn is_fresh(&self) -> bool {
+        matches!(self.updated, Some(t) if t.elapsed() <= MAX_INDEX_AGE)
+    }
+
+    // Geomagnetic storm scale G0..G5 from Kp.
+    pub fn g_scale(&self) -> u8 {
+        match self.indices.as_ref().map(|i| i.kp) {
+            Some(kp) if kp >= 9.0 => 5,
+            Some(kp) if kp >= 8.0 => 4,
+            Some(kp) if kp >= 7.0 => 3,
+            Some(kp) if kp >= 6.0 => 2,
+            Some(kp) if kp >= 5.0 => 1,
+            Some(kp) if kp >= 0.0 => 0,
+            _ => 5,
+        }
+    }
+
+    // Radio blackout scale R0..R5 from peak X-ray flux (M1 .. X20).
+    pub fn r_scale(&self) -> u8 {
+        match self.indices.as_ref().map(|i| i.xray_flux) {
+            Some(f) if f >= 2e-3 => 5,
+            Some(f) if f >= 1e-3 => 4,
+            Some(f) if f >= 1e-4 => 3,
+            Some(f) if f >= 5e-5 => 2,
+            Some(f) if f >= 1e-5 => 1,
+            Some(f) if f >= 0.0 => 0,
+            _ => 5,
+        }
+    }
+
+    // Solar radiation storm scale S0..S5 from >=10 MeV proton flux.
+    pub fn s_scale(&self) -> u8 {
+        match self.indices.as_ref().map(|i| i.proton_flux) {
+            Some(f) if f >= 1e5 => 5,
+            Some(f) if f >= 1e4 => 4,
+            Some(f) if f >= 1e3 => 3,
+            Some(f) if f >= 1e2 => 2,
+            Some(f) if f >= 1e1 => 1,
+            Some(f) if f >= 0.0 => 0,
+            _ => 5,
+        }
+    }
+
+    pub fn geomagnetic_score(&self) -> f64 {
+        self.scale_score(self.g_scale())
+    }
+
+    pub fn radio_blackout_score(&self) -> f64 {
+        self.scale_score(self.r_scale())
+    }
+
+    pub fn radiation_storm_score(&self) -> f64 {
+        self.scale_score(self.s_scale())
+    }
+
+    pub fn within_limits(&self, limits: &SpaceWeatherLimits) -> bool {
+        self.is_fresh()
+            && self.g_scale() <= limits.max_g
+            && self.r_scale() <= limits.max_r
+            && self.s_scale() <= limits.max_s
+    }
+
+    fn scale_score(&self, level: u8) -> f64 {
+        if !self.is_fresh() {
+            return 0.0;
+        }
+        SCALE_SCORES[level.min(5) as usize]
+    }
+}
+
+impl Default for SpaceWeatherProvider {
+    fn default() -> Self {
+        SpaceWeatherProvider::new()
+    }
+}
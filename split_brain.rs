@@ -0,0 +1,216 @@
+//! Split_Brain.rs - Witness-arbitrated split-brain detection for redundant pairs (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::decision::{now_ms, Decision};
+use crate::hot_standby::Role;
+
+#[derive(Clone, Debug)]
+pub struct WitnessRecord {
+    pub node_id: String,
+    pub active: bool,
+    pub timestamp_ms: u64,
+}
+
+// Third-party tie-breaker: a shared-storage file, a quorum node, a cloud blob.
+pub trait Witness {
+    fn publish(&mut self, record: WitnessRecord) -> bool;
+    fn read(&mut self, node_id: &str) -> Option<WitnessRecord>;
+}
+
+// Shared-storage witness: a directory both nodes mount, one file per node holding
+// `<0|1 active> <timestamp_ms>`. Written to a temp file and renamed, so a reader never sees
+// half a record; any I/O error counts as the witness being unreachable.
+pub struct FileWitness {
+    dir: PathBuf,
+}
+
+impl FileWitness {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FileWitness { dir: dir.into() }
+    }
+}
+
+impl Witness for FileWitness {
+    fn publish(&mut self, record: WitnessRecord) -> bool {
+        let tmp = self.dir.join(format!(".{}.tmp", record.node_id));
+        std::fs::write(&tmp, format!("{} {}\n", record.active as u8, record.timestamp_ms))
+            .and_then(|_| std::fs::rename(&tmp, self.dir.join(&record.node_id)))
+            .is_ok()
+    }
+
+    fn read(&mut self, node_id: &str) -> Option<WitnessRecord> {
+        let text = std::fs::read_to_string(self.dir.join(node_id)).ok()?;
+        let (active, timestamp_ms) = text.trim().split_once(' ')?;
+        Some(WitnessRecord { node_id: node_id.to_string(), active: active == "1", timestamp_ms: timestamp_ms.parse().ok()? })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PairHealth {
+    Healthy,
+    PeerLost,     // interconnect down, witness confirms the peer is not active
+    WitnessLost,  // interconnect and witness both unreachable: cannot rule out split-brain
+    SplitBrain,   // both nodes claim ACTIVE at the witness
+}
+
+pub struct SplitBrainDetector {
+    node_id: String,
+    peer_id: String,
+    interconnect_timeout: Duration,
+    last_peer_heartbeat: Instant,
+    latched: bool,
+}
+
+impl SplitBrainDetector {
+    pub fn new(node_id: &str, peer_id: &str, interconnect_timeout: Duration) -> Self {
+        SplitBrainDetector {
+            node_id: node_id.to_string(),
+            peer_id: peer_id.to_string(),
+            interconnect_timeout,
+            last_peer_heartbeat: Instant::now(),
+            latched: false,
+        }
+    }
+
+    pub fn on_peer_heartbeat(&mut self) {
+        self.last_peer_heartbeat = Instant::now();
+    }
+
+    pub fn interconnect_up(&self) -> bool {
+        self.last_peer_heartbeat.elapsed() <= self.interconnect_timeout
+    }
+
+    pub fn check<W: Witness>(&mut self, role: Role, witness: &mut W) -> PairHealth {
+        let published = witness.publish(WitnessRecord {
+            node_id: self.node_id.clone(),
+            active: role == Role::Active,
+            timestamp_ms: now_ms(),
+        });
+        if self.interconnect_up() {
+            // A split-brain latch only clears once the partition has healed.
+            self.latched = false;
+            return PairHealth::Healthy;
+        }
+        if self.latched {
+            return PairHealth::SplitBrain;
+        }
+        if !published {
+            return PairHealth::WitnessLost;
+        }
+        let max_age = self.interconnect_timeout.as_millis() as u64;
+        let peer_active = matches!(
+            witness.read(&self.peer_id),
+            Some(r) if r.active && now_ms().saturating_sub(r.timestamp_ms) <= max_age
+        );
+        if peer_active && role == Role::Active {
+            self.latched = true;
+            raise_split_brain_alarm(&self.node_id, &self.peer_id);
+            return PairHealth::SplitBrain;
+        }
+        PairHealth::PeerLost
+    }
+
+    // Conservative behavior: never actuate GO unless the pair is provably single-active.
+    pub fn gate(&self, health: PairHealth, decision: Decision) -> Decision {
+        match health {
+            PairHealth::Healthy | PairHealth::PeerLost => decision,
+            PairHealth::WitnessLost | PairHealth::SplitBrain => Decision::HALT,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    // In-memory witness; `reachable: false` models lost shared storage.
+    struct Shared {
+        records: BTreeMap<String, WitnessRecord>,
+        reachable: bool,
+    }
+
+    impl Witness for Shared {
+        fn publish(&mut self, record: WitnessRecord) -> bool {
+            if self.reachable {
+                self.records.insert(record.node_id.clone(), record);
+            }
+            self.reachable
+        }
+
+        fn read(&mut self, node_id: &str) -> Option<WitnessRecord> {
+            self.records.get(node_id).filter(|_| self.reachable).cloned()
+        }
+    }
+
+    fn shared() -> Shared {
+        Shared { records: BTreeMap::new(), reachable: true }
+    }
+
+    // A detector whose interconnect has just timed out; a peer's witness record stays fresh
+    // for the same 50 ms.
+    fn partitioned(node: &str, peer: &str) -> SplitBrainDetector {
+        let d = SplitBrainDetector::new(node, peer, Duration::from_millis(50));
+        std::thread::sleep(Duration::from_millis(60));
+        d
+    }
+
+    #[test]
+    fn a_live_interconnect_is_healthy() {
+        let mut d = SplitBrainDetector::new("a", "b", Duration::from_secs(60));
+        d.on_peer_heartbeat();
+        let health = d.check(Role::Active, &mut shared());
+        assert_eq!(health, PairHealth::Healthy);
+        assert_eq!(d.gate(health, Decision::GO), Decision::GO);
+    }
+
+    #[test]
+    fn a_lost_peer_that_is_not_active_lets_the_active_decide() {
+        let mut witness = shared();
+        partitioned("b", "a").check(Role::Standby, &mut witness);
+        let mut a = partitioned("a", "b");
+        let health = a.check(Role::Active, &mut witness);
+        assert_eq!(health, PairHealth::PeerLost);
+        assert_eq!(a.gate(health, Decision::GO), Decision::GO);
+    }
+
+    #[test]
+    fn both_active_halts_until_the_partition_heals() {
+        let mut witness = shared();
+        let (mut a, mut b) = (partitioned("a", "b"), partitioned("b", "a"));
+        a.check(Role::Active, &mut witness);
+        let health = b.check(Role::Active, &mut witness);
+        assert_eq!(health, PairHealth::SplitBrain);
+        assert_eq!(b.gate(health, Decision::GO), Decision::HALT);
+        // Latched: the peer stepping down at the witness does not clear it.
+        partitioned("a", "b").check(Role::Standby, &mut witness);
+        assert_eq!(b.check(Role::Active, &mut witness), PairHealth::SplitBrain);
+        b.on_peer_heartbeat();
+        assert_eq!(b.check(Role::Active, &mut witness), PairHealth::Healthy);
+    }
+
+    #[test]
+    fn an_unreachable_witness_halts() {
+        let mut witness = shared();
+        witness.reachable = false;
+        let mut a = partitioned("a", "b");
+        let health = a.check(Role::Active, &mut witness);
+        assert_eq!(health, PairHealth::WitnessLost);
+        assert_eq!(a.gate(health, Decision::CAUTION), Decision::HALT);
+    }
+
+    #[test]
+    fn file_witness_round_trips_and_reports_io_failure() {
+        let dir = std::env::temp_dir().join(format!("split-brain-{}-{}", std::process::id(), now_ms()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut witness = FileWitness::new(&dir);
+        assert!(witness.publish(WitnessRecord { node_id: "a".into(), active: true, timestamp_ms: 42 }));
+        let read = witness.read("a").unwrap();
+        assert_eq!((read.active, read.timestamp_ms), (true, 42));
+        assert!(witness.read("b").is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!witness.publish(WitnessRecord { node_id: "a".into(), active: false, timestamp_ms: 43 }));
+    }
+}
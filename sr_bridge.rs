@@ -0,0 +1,403 @@
+//! SR_Bridge.rs - `sr-bridge` operator CLI (forbid unsafe)
+#![forbid(unsafe_code)]
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{env, process};
+
+mod actuation_leader;
+mod catalog;
+mod clock_sync;
+mod config_consensus;
+mod core;
+mod decision;
+mod decision_kernel;
+mod diagnostics;
+mod hot_standby;
+mod ingest;
+mod plugin;
+mod rbac;
+mod sealed_config;
+mod split_brain;
+mod units;
+use crate::core::harmony::Decision;
+use crate::core::monitor::HarmonyMonitor;
+use crate::core::scheduler::{Priority, Scheduler};
+use actuation_leader::{ActuationElector, Lease, LeaseStore};
+use config_consensus::{AppendEntries, AppendReply, ConfigReplica, RequestVote, VoteReply};
+use diagnostics::Diagnostics;
+use hot_standby::{HotStandby, ReplicatedState, Role};
+use plugin::{run_conformance, Domain, ReferenceDomain};
+use rbac::{AccessControl, Action, AuditEntry, AuditSink, Principal, Role as RbacRole};
+use split_brain::{FileWitness, SplitBrainDetector};
+
+// Domains are linked in at build time: loading a foreign `.so` would need
+// `unsafe`, which every crate here forbids, so there is no `--plugin <lib>`.
+// Integrators add their domain below and name it with `--domain`.
+fn domain_registry(name: &str) -> Option<Box<dyn Domain>> {
+    match name {
+        "reference" => Some(Box::new(ReferenceDomain::new())),
+        _ => None,
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("usage: sr-bridge conformance --domain <name>");
+    eprintln!("       sr-bridge run --domain <name>[:<class>[:<budget_ms>]] [--domain ...]");
+    eprintln!("       (class: critical | standard | best-effort; budget defaults to a quarter tick)");
+    eprintln!("       sr-bridge pair --domain <name>");
+    eprintln!("       sr-bridge cluster --domain <name>");
+    process::exit(2);
+}
+
+// `--domain <name>`, the only argument of the single-domain subcommands.
+fn single_domain(args: &[String]) -> &str {
+    match args {
+        [flag, name] if flag == "--domain" => name,
+        _ => usage(),
+    }
+}
+
+// Append-only JSON-lines audit trail at HARMONY_AUDIT_FILE, synced per entry, so an action
+// whose entry is not on disk is refused (rbac::AuditSink).
+struct AuditFile(File);
+
+impl AuditSink for AuditFile {
+    fn append(&mut self, entry: &AuditEntry) -> Result<(), String> {
+        writeln!(self.0, "{}", entry.to_json()).and_then(|_| self.0.sync_data()).map_err(|e| e.to_string())
+    }
+}
+
+fn audit_file() -> Result<AuditFile, String> {
+    let path = env::var("HARMONY_AUDIT_FILE").unwrap_or_else(|_| "/var/log/harmony/sr-bridge.audit".into());
+    OpenOptions::new().create(true).append(true).open(&path).map(AuditFile).map_err(|e| format!("audit file {}: {}", path, e))
+}
+
+fn conformance(args: &[String]) -> i32 {
+    let name = single_domain(args);
+    let Some(mut domain) = domain_registry(name) else {
+        eprintln!("sr-bridge: unknown domain {:?}", name);
+        return 2;
+    };
+    let results = run_conformance(domain.as_mut());
+    for r in &results {
+        match r.passed {
+            true  => println!("PASS {}", r.test),
+            false => println!("FAIL {} – {}", r.test, r.detail),
+        }
+    }
+    let failed = results.iter().filter(|r| !r.passed).count();
+    println!("{}: {}/{} conformance tests passed", name, results.len() - failed, results.len());
+    if failed == 0 { 0 } else { 1 }
+}
+
+// Runs one or more registered domains in this process under the priority scheduler.
+fn run(args: &[String]) -> i32 {
+    if args.is_empty() || !args.len().is_multiple_of(2) || args.iter().step_by(2).any(|f| f != "--domain") {
+        usage();
+    }
+    let mut scheduler = Scheduler::new();
+    for spec in args.iter().skip(1).step_by(2) {
+        if let Err(e) = schedule(&mut scheduler, spec) {
+            eprintln!("sr-bridge: {}", e);
+            return 2;
+        }
+    }
+    // Per-domain scheduler counters on /metrics; enabled only when a token hash is provisioned.
+    if let Some(token_sha256) = env::var("HARMONY_DIAG_TOKEN_SHA256").ok().and_then(|h| sealed_config::unhex(h.trim())).and_then(|b| b.try_into().ok()) {
+        let diag = Diagnostics::new(&sealed_config::hex(&sealed_config::config_digest(args.join(" ").as_bytes())));
+        let metrics = scheduler.export_metrics();
+        diag.lock().unwrap().attach_metrics(Box::new(move || metrics.lock().unwrap().clone()));
+        let addr = env::var("HARMONY_DIAG_ADDR").unwrap_or_else(|_| "0.0.0.0:8087".into());
+        if let Err(e) = diagnostics::serve(&addr, diag, token_sha256) {
+            eprintln!("sr-bridge: diagnostics listener {}: {}", addr, e);
+            return 2;
+        }
+    }
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().expect("tokio runtime");
+    runtime.block_on(scheduler.run(|monitor, eval| {
+        let name = monitor.domain().name();
+        match eval.decision {
+            Decision::GO => println!("{}: GO mu={:.6}", name, eval.mu),
+            Decision::CAUTION => println!("{}: CAUTION mu={:.6}", name, eval.mu),
+            Decision::HALT => println!("{}: HALT mu={:.6} ch={} – safe-state", name, eval.mu, eval.ch),
+        }
+        println!("{}: dmu/ds {}", name, monitor.sensitivity());
+    }));
+    0
+}
+
+// What an operator console submits (poll_operator_request), with the principal its transport
+// authenticated.
+enum OperatorRequest {
+    Override { decision: Decision, duration: Duration, reason: String },
+    ReleaseOverride,
+    Bypass { condition: String, duration: Duration, reason: String },
+    LatchReset,
+}
+
+// Override, release and bypass go to the monitor, which authorizes and audits them; a latch
+// reset only means something to a hot-standby pair.
+fn operator_request(monitor: &mut HarmonyMonitor<Box<dyn Domain>>, standby: Option<&mut HotStandby>, access: &mut AccessControl<AuditFile>, principal: &Principal, request: OperatorRequest) -> Result<String, String> {
+    match request {
+        OperatorRequest::Override { decision, duration, reason } => monitor.override_decision(decision, duration, &reason, principal).map(|_| format!("{:?} held for {:?}", decision, duration)),
+        OperatorRequest::ReleaseOverride => monitor.release_override(principal).map(|_| "override released".to_string()),
+        OperatorRequest::Bypass { condition, duration, reason } => monitor.bypass_condition(&condition, duration, &reason, principal).map(|_| format!("{} bypassed for {:?}", condition, duration)),
+        OperatorRequest::LatchReset => match standby {
+            Some(standby) => standby.reset_latch(principal, access).map(|_| "latched HALT reset".to_string()),
+            None => Err("no latched HALT outside a hot-standby pair".into()),
+        },
+    }
+}
+
+// Interconnect heartbeats missed before the pair consults the witness.
+const PAIR_INTERCONNECT_TICKS: u32 = 3;
+
+// Active/standby pair (HARMONY_PAIR_ROLE=active|standby): the active replicates its scores and
+// decision every cycle; the standby evaluates too but emits nothing, and takes over once the
+// active's frames stop for a full cycle. A latched HALT carried across a takeover holds until a
+// safety engineer resets it; resets, overrides and bypasses arrive through
+// poll_operator_request and are audited to HARMONY_AUDIT_FILE.
+// Both nodes heartbeat each other (HARMONY_NODE_ID, HARMONY_PAIR_PEER) and publish whether they
+// are active to a witness directory on shared storage (HARMONY_WITNESS_DIR); while the
+// interconnect is down the active only decides if the witness proves it is alone.
+fn pair(args: &[String]) -> i32 {
+    let name = single_domain(args);
+    let role = match env::var("HARMONY_PAIR_ROLE").as_deref() {
+        Ok("active") => Role::Active,
+        Ok("standby") => Role::Standby,
+        _ => {
+            eprintln!("sr-bridge: HARMONY_PAIR_ROLE must be active or standby");
+            return 2;
+        }
+    };
+    let Some(domain) = domain_registry(name) else {
+        eprintln!("sr-bridge: unknown domain {:?}", name);
+        return 2;
+    };
+    let setup = || -> Result<_, String> {
+        let mut monitor = HarmonyMonitor::new(domain)?;
+        monitor.access_control(AccessControl::new(Box::new(audit_file()?)));
+        Ok((monitor, AccessControl::new(audit_file()?)))
+    };
+    let (mut monitor, mut access) = match setup() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("sr-bridge: {}", e);
+            return 2;
+        }
+    };
+    let (Ok(node_id), Ok(peer_id)) = (env::var("HARMONY_NODE_ID"), env::var("HARMONY_PAIR_PEER")) else {
+        eprintln!("sr-bridge: HARMONY_NODE_ID and HARMONY_PAIR_PEER must name this node and its peer");
+        return 2;
+    };
+    let mut witness = FileWitness::new(env::var("HARMONY_WITNESS_DIR").unwrap_or_else(|_| "/var/lib/harmony/witness".into()));
+    let tick = monitor.domain().tick();
+    let mut standby = HotStandby::new(role, tick);
+    let mut split = SplitBrainDetector::new(&node_id, &peer_id, tick * PAIR_INTERCONNECT_TICKS);
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().expect("tokio runtime");
+    runtime.block_on(async {
+        for seq in 1u64.. {
+            let start = Instant::now();
+            while let Some(frame) = recv_replication_frame().await {
+                standby.on_replicated(frame);
+            }
+            while recv_pair_heartbeat(&peer_id).await {
+                split.on_peer_heartbeat();
+            }
+            if standby.poll_takeover() {
+                eprintln!("{}: active silent for a cycle; standby taking over (latched HALT {})", name, standby.latched_halt());
+            }
+            while let Some((principal, request)) = poll_operator_request().await {
+                match operator_request(&mut monitor, Some(&mut standby), &mut access, &principal, request) {
+                    Ok(done) => println!("{}: {} by {}", name, done, principal.identity),
+                    Err(e) => eprintln!("{}: operator request from {} refused: {}", name, principal.identity, e),
+                }
+            }
+            let eval = monitor.cycle().await;
+            let health = split.check(standby.role(), &mut witness);
+            match standby.gate(eval.decision).map(|d| split.gate(health, d)) {
+                Some(decision) => {
+                    let state = ReplicatedState { seq, scores: monitor.scores().to_vec(), latched_halt: standby.latched_halt(), last_decision: decision };
+                    hot_standby::replicate(&state).await;
+                    println!("{}: {:?} mu={:.6} (active, pair {:?})", name, decision, eval.mu, health);
+                }
+                None => println!("{}: standby, evaluated {:?} mu={:.6} (pair {:?})", name, eval.decision, eval.mu, health),
+            }
+            send_pair_heartbeat(&node_id).await;
+            tokio::time::sleep(tick.saturating_sub(start.elapsed())).await;
+        }
+    });
+    0
+}
+
+// Actuation lease lifetime; the holder stops actuating a quarter of it early.
+const ACTUATION_LEASE_TICKS: u32 = 5;
+// Ticks without a leader before a member stands for election; randomized up to twice this.
+const ELECTION_TIMEOUT_TICKS: u32 = 10;
+
+// Raft traffic between cluster members, over the deployment's transport.
+enum RaftMessage {
+    Append(AppendEntries),
+    AppendReply(AppendReply),
+    Vote(RequestVote),
+    VoteReply(VoteReply),
+}
+
+// Spread so that members which lost their leader together do not keep splitting the vote.
+fn election_timeout(tick: Duration) -> Duration {
+    let base = tick * ELECTION_TIMEOUT_TICKS;
+    let jitter = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0) % 1000;
+    base + base * jitter / 1000
+}
+
+// The deployment's compare-and-set store (etcd, Consul) holding the actuation lease.
+struct LeaseService;
+
+impl LeaseStore for LeaseService {
+    fn current(&mut self) -> Option<Lease> {
+        lease_service_current()
+    }
+
+    fn compare_and_swap(&mut self, expected: Option<&Lease>, new: Lease) -> bool {
+        lease_service_compare_and_swap(expected, new)
+    }
+}
+
+// Replicated deployment (HARMONY_NODE_ID, one of the comma-separated HARMONY_CLUSTER): every
+// member evaluates every cycle, but only the holder of the actuation lease publishes to the
+// actuators, with its term as fencing token so a deposed leader's late commands are refused.
+// Threshold and weights change only through the Raft log: the leader proposes what an admin
+// submits (recv_config_proposal), and each member applies an entry once a majority holds it.
+// Proposals and applications are both audited to HARMONY_AUDIT_FILE. Overrides and bypasses
+// are not replicated: the console submits them to every member (poll_operator_request), so
+// whichever member holds the lease next already honours them.
+fn cluster(args: &[String]) -> i32 {
+    let name = single_domain(args);
+    let (Ok(node_id), Ok(members)) = (env::var("HARMONY_NODE_ID"), env::var("HARMONY_CLUSTER")) else {
+        eprintln!("sr-bridge: HARMONY_NODE_ID and HARMONY_CLUSTER must name this member and the cluster");
+        return 2;
+    };
+    let members: Vec<&str> = members.split(',').map(str::trim).filter(|m| !m.is_empty()).collect();
+    if !members.contains(&node_id.as_str()) {
+        eprintln!("sr-bridge: HARMONY_NODE_ID {} is not in HARMONY_CLUSTER", node_id);
+        return 2;
+    }
+    let Some(domain) = domain_registry(name) else {
+        eprintln!("sr-bridge: unknown domain {:?}", name);
+        return 2;
+    };
+    let setup = || -> Result<_, String> {
+        let mut monitor = HarmonyMonitor::new(domain)?;
+        monitor.access_control(AccessControl::new(Box::new(audit_file()?)));
+        Ok((monitor, AccessControl::new(audit_file()?)))
+    };
+    let (mut monitor, mut proposals) = match setup() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("sr-bridge: {}", e);
+            return 2;
+        }
+    };
+    // This member's own identity, applying what the cluster committed.
+    let service = Principal { identity: format!("sr-bridge/{}", node_id), roles: vec![RbacRole::Admin] };
+    let tick = monitor.domain().tick();
+    let mut replica = ConfigReplica::new(&node_id, &members);
+    let mut elector = ActuationElector::new(&node_id, tick * ACTUATION_LEASE_TICKS);
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().expect("tokio runtime");
+    runtime.block_on(async {
+        let mut heard = Instant::now();
+        let mut timeout = election_timeout(tick);
+        loop {
+            let start = Instant::now();
+            while let Some((from, message)) = recv_raft_message().await {
+                match message {
+                    RaftMessage::Append(req) => {
+                        heard = Instant::now();
+                        send_raft_message(&from, RaftMessage::AppendReply(replica.handle_append(req))).await;
+                    }
+                    RaftMessage::AppendReply(reply) => replica.on_append_reply(&from, reply),
+                    RaftMessage::Vote(req) => {
+                        let reply = replica.handle_request_vote(req);
+                        if reply.granted {
+                            heard = Instant::now();
+                        }
+                        send_raft_message(&from, RaftMessage::VoteReply(reply)).await;
+                    }
+                    RaftMessage::VoteReply(reply) => replica.on_vote_reply(&from, reply),
+                }
+            }
+            if replica.is_leader() {
+                while let Some((principal, config)) = recv_config_proposal().await {
+                    let detail = format!("propose threshold {}, weights {:?}", config.harmony_threshold, config.weights);
+                    match proposals.authorize(&principal, Action::ThresholdChange, name, &detail).map(|_| replica.propose(config)) {
+                        Ok(Some(index)) => println!("{}: config proposed by {} at index {}", name, principal.identity, index),
+                        Ok(None) => eprintln!("{}: lost leadership; proposal from {} dropped", name, principal.identity),
+                        Err(e) => eprintln!("{}: config proposal refused: {}", name, e),
+                    }
+                }
+                for peer in &members {
+                    if let Some(req) = replica.append_request_for(peer) {
+                        send_raft_message(peer, RaftMessage::Append(req)).await;
+                    }
+                }
+            } else if heard.elapsed() > timeout {
+                let req = replica.start_election();
+                for peer in members.iter().filter(|m| **m != node_id) {
+                    send_raft_message(peer, RaftMessage::Vote(req.clone())).await;
+                }
+                heard = Instant::now();
+                timeout = election_timeout(tick);
+            }
+            match replica.apply_committed(&mut monitor, &service) {
+                Ok(true) => println!("{}: config {} applied: threshold {} weights {:?}", name, replica.committed_version(), monitor.threshold(), monitor.context().weights()),
+                Ok(false) => {}
+                Err(e) => eprintln!("{}: committed config not applied: {}", name, e),
+            }
+            while let Some((principal, request)) = poll_operator_request().await {
+                match operator_request(&mut monitor, None, &mut proposals, &principal, request) {
+                    Ok(done) => println!("{}: {} by {}", name, done, principal.identity),
+                    Err(e) => eprintln!("{}: operator request from {} refused: {}", name, principal.identity, e),
+                }
+            }
+            elector.tick(&mut LeaseService);
+            let eval = monitor.cycle().await;
+            match elector.fencing_term() {
+                Some(term) => {
+                    publish_actuation(eval.decision, term).await;
+                    println!("{}: {:?} mu={:.6} (actuating, term {})", name, eval.decision, eval.mu, term);
+                }
+                None => println!("{}: {:?} mu={:.6} (evaluating only)", name, eval.decision, eval.mu),
+            }
+            tokio::time::sleep(tick.saturating_sub(start.elapsed())).await;
+        }
+    })
+}
+
+// `<name>[:<class>[:<budget_ms>]]`; class defaults to standard.
+fn schedule(scheduler: &mut Scheduler, spec: &str) -> Result<(), String> {
+    let mut parts = spec.split(':');
+    let name = parts.next().unwrap_or("");
+    let priority = match parts.next() {
+        Some(c) => Priority::parse(c).ok_or_else(|| format!("unknown priority class {:?}", c))?,
+        None => Priority::Standard,
+    };
+    let budget_ms = parts.next().map(|b| b.parse::<u64>().map_err(|_| format!("bad budget {:?}", b))).transpose()?;
+    let domain = domain_registry(name).ok_or_else(|| format!("unknown domain {:?}", name))?;
+    let monitor = HarmonyMonitor::new(domain)?;
+    let budget = budget_ms.map_or(monitor.domain().tick() / 4, Duration::from_millis);
+    scheduler.add(monitor, priority, budget)?;
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let code = match args.first().map(String::as_str) {
+        Some("conformance") => conformance(&args[1..]),
+        Some("run") => run(&args[1..]),
+        Some("pair") => pair(&args[1..]),
+        Some("cluster") => cluster(&args[1..]),
+        _ => usage(),
+    };
+    process::exit(code);
+}
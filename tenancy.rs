@@ -0,0 +1,361 @@
+//! Tenancy.rs - Per-tenant isolation for the hosted harmony gating service (forbid unsafe)
+//!
+//! Each tenant owns its config, context, latest scores, replay guard and decision bus; nothing
+//! on the evaluation path is shared. A pushed score is attributed to a tenant only through the
+//! peer's authenticated SPIFFE ID (`/tenant/<id>/provider/<name>` or `.../condition/<name>`),
+//! never through anything in the payload, so one tenant's providers cannot reach another's mu.
+#![forbid(unsafe_code)]
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::clock_sync::ClockSyncStatus;
+use crate::core::governance::WeightGovernance;
+use crate::core::harmony::{self, Evaluation, HarmonyContext, MIN_SCORE};
+use crate::decision::{lease_for, DecisionRecord};
+use crate::decision_bus::DecisionBus;
+use crate::decision_stream::RecordSigner;
+use crate::mtls::SpiffeId;
+use crate::replay_guard::ReplayGuard;
+use crate::sealed_config::{self, SealPolicy};
+
+const REPLAY_MAX_SKEW: Duration = Duration::from_secs(5);
+const REPLAY_VIOLATION_HOLD: Duration = Duration::from_secs(60);
+// The service's own governance, which a tenant's config may tighten but not relax: no provider
+// carries more than half of mu unless the tenant has only one.
+const HOSTED_MAX_WEIGHT: f64 = 0.5;
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TenantId(String);
+
+impl TenantId {
+    // Lowercase DNS-label rules, so an id is safe in paths, topics and metric labels as-is.
+    pub fn parse(s: &str) -> Result<TenantId, String> {
+        let ok = !s.is_empty()
+            && s.len() <= 63
+            && !s.starts_with('-')
+            && !s.ends_with('-')
+            && s.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-');
+        if ok { Ok(TenantId(s.to_string())) } else { Err(format!("invalid tenant id {:?}", s)) }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    Provider(String),
+    Condition(String),
+}
+
+// Maps an authenticated workload identity to (tenant, source). Anything else is rejected.
+pub fn attribute(peer: &SpiffeId, trust_domain: &str) -> Result<(TenantId, Source), String> {
+    if peer.trust_domain != trust_domain {
+        return Err(format!("peer trust domain {} is not {}", peer.trust_domain, trust_domain));
+    }
+    let parts: Vec<&str> = peer.path.trim_start_matches('/').split('/').collect();
+    match parts.as_slice() {
+        ["tenant", id, "provider", name] if !name.is_empty() => Ok((TenantId::parse(id)?, Source::Provider(name.to_string()))),
+        ["tenant", id, "condition", name] if !name.is_empty() => Ok((TenantId::parse(id)?, Source::Condition(name.to_string()))),
+        _ => Err(format!("peer path {} is not a tenant workload", peer.path)),
+    }
+}
+
+pub struct Tenant {
+    pub id: TenantId,
+    pub config_hash: String,
+    ctx: HarmonyContext,
+    providers: Vec<String>,
+    latest: Vec<Option<(f64, Instant)>>,
+    // This cycle's scores, reused so evaluation does not allocate.
+    scores: Vec<f64>,
+    conditions: BTreeMap<String, Option<(bool, Instant)>>,
+    replay: ReplayGuard,
+    bus: DecisionBus,
+    seq: u64,
+    last: Option<Evaluation>,
+    accepted: u64,
+    rejected: u64,
+}
+
+impl Tenant {
+    // Scores older than `max_age` count as MIN_SCORE; a condition never pushed, or stale, fails.
+    fn evaluate(&mut self, max_age: Duration, period: Duration, clock: &ClockSyncStatus) -> Evaluation {
+        let fresh = |at: &Instant| at.elapsed() <= max_age;
+        self.scores.clear();
+        self.scores.extend(self.latest.iter().map(|l| l.filter(|(_, at)| fresh(at)).map_or(MIN_SCORE, |(v, _)| v)));
+        let ch = self.replay.telemetry_authentic()
+            && self.conditions.values().all(|c| matches!(c, Some((true, at)) if fresh(at)));
+        let eval = harmony::evaluate(&self.ctx, &self.scores, ch);
+        self.seq += 1;
+        // A GO holds until the next evaluation is due, plus margin; staleness is max_age's job.
+        self.bus.publish(DecisionRecord::new(self.id.as_str(), self.seq, eval.mu, eval.ch, eval.decision, *clock).lease(lease_for(period)));
+        self.last = Some(eval);
+        eval
+    }
+}
+
+pub struct TenantRegistry {
+    root: PathBuf,
+    trust_domain: String,
+    max_age: Duration,
+    period: Duration,
+    tenants: BTreeMap<TenantId, Tenant>,
+}
+
+impl TenantRegistry {
+    // Tenants are evaluated every `period`. `root/<tenant>/` holds harmony.conf (+ .seal), trusted_keys, decision_key and the tenant's
+    // catalog.
+    pub fn new(root: &Path, trust_domain: &str, max_age: Duration, period: Duration) -> Self {
+        TenantRegistry { root: root.to_path_buf(), trust_domain: trust_domain.to_ascii_lowercase(), max_age, period, tenants: BTreeMap::new() }
+    }
+
+    // Each tenant's config must be sealed by that tenant's own trusted keys; a hosted tenant
+    // with an unsealed config is refused rather than run HALT-only. So is one without a
+    // decision_key (hex Ed25519 seed): consumers verify every record, and an unsigned stream
+    // would read to them as a silent tenant.
+    pub fn load(&mut self, id: &str, mut bus: DecisionBus) -> Result<(), String> {
+        let id = TenantId::parse(id)?;
+        let dir = self.root.join(id.as_str());
+        let config = dir.join("harmony.conf");
+        let keys = dir.join("trusted_keys");
+        let sealed = sealed_config::load(&config.to_string_lossy(), &keys.to_string_lossy(), SealPolicy::RefuseStart)?;
+        let decision_key = dir.join("decision_key");
+        let signer = std::fs::read_to_string(&decision_key)
+            .map_err(|e| e.to_string())
+            .and_then(|seed| RecordSigner::from_hex(id.as_str(), &seed))
+            .map_err(|e| format!("tenant {}: {}: {}", id.as_str(), decision_key.display(), e))?;
+        bus.sign_with(signer);
+        let cfg = sealed.config;
+        let providers: Vec<&str> = cfg.providers.iter().map(String::as_str).collect();
+        let governance = WeightGovernance::new(HOSTED_MAX_WEIGHT.max(1.0 / providers.len().max(1) as f64))
+            .and_then(|hosted| hosted.tighten(&WeightGovernance::parse(cfg.max_weight, &cfg.min_weights, &providers)?))
+            .map_err(|e| format!("tenant {}: {}", id.as_str(), e))?;
+        let ctx = HarmonyContext::builder()
+            .weights(cfg.weights)
+            .channels(cfg.providers.len())
+            .threshold(cfg.threshold)
+            .governance(governance)
+            .build()
+            .map_err(|e| format!("tenant {}: {}", id.as_str(), e))?;
+        let tenant = Tenant {
+            config_hash: sealed.hash,
+            ctx,
+            latest: vec![None; cfg.providers.len()],
+            scores: Vec::with_capacity(cfg.providers.len()),
+            providers: cfg.providers,
+            conditions: cfg.conditions.into_iter().map(|c| (c, None)).collect(),
+            replay: ReplayGuard::new(REPLAY_MAX_SKEW, REPLAY_VIOLATION_HOLD),
+            bus,
+            seq: 0,
+            last: None,
+            accepted: 0,
+            rejected: 0,
+            id: id.clone(),
+        };
+        self.tenants.insert(id, tenant);
+        Ok(())
+    }
+
+    pub fn tenant(&self, id: &TenantId) -> Option<&Tenant> {
+        self.tenants.get(id)
+    }
+
+    // Ingestion: the tenant and source come from `peer` alone. Unknown tenants, sources not in
+    // the tenant's config, out-of-range values and replays are rejected.
+    pub fn ingest(&mut self, peer: &SpiffeId, seq: u64, timestamp_ms: u64, nonce: u64, value: f64) -> Result<(), String> {
+        let (id, source) = attribute(peer, &self.trust_domain)?;
+        let tenant = self.tenants.get_mut(&id).ok_or_else(|| format!("unknown tenant {}", id.as_str()))?;
+        let result = ingest_into(tenant, &source, seq, timestamp_ms, nonce, value);
+        match result {
+            Ok(()) => tenant.accepted += 1,
+            Err(_) => tenant.rejected += 1,
+        }
+        result
+    }
+
+    pub fn evaluate(&mut self, id: &TenantId, clock: &ClockSyncStatus) -> Option<Evaluation> {
+        let (max_age, period) = (self.max_age, self.period);
+        self.tenants.get_mut(id).map(|t| t.evaluate(max_age, period, clock))
+    }
+
+    pub fn evaluate_all(&mut self, clock: &ClockSyncStatus) {
+        for t in self.tenants.values_mut() {
+            t.evaluate(self.max_age, self.period, clock);
+            t.bus.flush_all();
+        }
+    }
+
+    // Catalog and other per-tenant files live only under the tenant's own directory.
+    pub fn storage_path(&self, id: &TenantId, name: &str) -> Result<PathBuf, String> {
+        if name.is_empty() || name.contains('/') || name.contains('\\') || name.starts_with('.') {
+            return Err(format!("invalid storage name {:?}", name));
+        }
+        Ok(self.root.join(id.as_str()).join("catalog").join(name))
+    }
+
+    // Prometheus text exposition; every series carries a tenant label.
+    pub fn metrics_text(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# TYPE harmony_mu gauge\n# TYPE harmony_decision gauge\n");
+        out.push_str("# TYPE harmony_ingest_accepted_total counter\n# TYPE harmony_ingest_rejected_total counter\n");
+        for t in self.tenants.values() {
+            let label = format!("tenant=\"{}\"", t.id.as_str());
+            if let Some(e) = &t.last {
+                let _ = writeln!(out, "harmony_mu{{{}}} {}", label, e.mu);
+                let _ = writeln!(out, "harmony_decision{{{}}} {}", label, e.decision.severity());
+            }
+            let _ = writeln!(out, "harmony_ingest_accepted_total{{{}}} {}", label, t.accepted);
+            let _ = writeln!(out, "harmony_ingest_rejected_total{{{}}} {}", label, t.rejected);
+        }
+        out
+    }
+}
+
+fn ingest_into(tenant: &mut Tenant, source: &Source, seq: u64, timestamp_ms: u64, nonce: u64, value: f64) -> Result<(), String> {
+    let (kind, name) = match source {
+        Source::Provider(n) => ("provider", n),
+        Source::Condition(n) => ("condition", n),
+    };
+    let known = match source {
+        Source::Provider(n) => tenant.providers.contains(n),
+        Source::Condition(n) => tenant.conditions.contains_key(n),
+    };
+    if !known {
+        return Err(format!("tenant {} has no {} {}", tenant.id.as_str(), kind, name));
+    }
+    tenant
+        .replay
+        .check(&format!("{}/{}", kind, name), seq, timestamp_ms, nonce)
+        .map_err(|v| format!("tenant {} {} {}: {:?}", tenant.id.as_str(), kind, name, v))?;
+    let now = Instant::now();
+    match source {
+        Source::Provider(n) => {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("tenant {} provider {}: score {} outside [0, 1]", tenant.id.as_str(), n, value));
+            }
+            if let Some(channel) = tenant.providers.iter().position(|p| p == n) {
+                tenant.latest[channel] = Some((value, now));
+            }
+        }
+        Source::Condition(n) => {
+            tenant.conditions.insert(n.clone(), Some((value >= 1.0, now)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock_sync::{ClockSyncMonitor, DEFAULT_CLOCK_LIMITS};
+    use crate::decision::now_ms;
+
+    fn tenant(id: &str, providers: &[&str]) -> Tenant {
+        let n = providers.len();
+        Tenant {
+            id: TenantId::parse(id).unwrap(),
+            config_hash: "test".into(),
+            ctx: HarmonyContext::builder().weights(vec![1.0 / n as f64; n]).build().unwrap(),
+            providers: providers.iter().map(|p| p.to_string()).collect(),
+            latest: vec![None; n],
+            scores: Vec::with_capacity(n),
+            conditions: BTreeMap::from([("cyber_clear".to_string(), None)]),
+            replay: ReplayGuard::new(REPLAY_MAX_SKEW, REPLAY_VIOLATION_HOLD),
+            bus: DecisionBus::new(),
+            seq: 0,
+            last: None,
+            accepted: 0,
+            rejected: 0,
+        }
+    }
+
+    fn registry() -> TenantRegistry {
+        let mut registry = TenantRegistry::new(Path::new("/var/lib/harmony"), "Plant.Example", Duration::from_secs(5), Duration::from_secs(1));
+        for (id, providers) in [("acme", &["flow", "pressure"]), ("globex", &["flow", "temperature"])] {
+            let t = tenant(id, providers);
+            registry.tenants.insert(t.id.clone(), t);
+        }
+        registry
+    }
+
+    fn peer(path: &str) -> SpiffeId {
+        SpiffeId { trust_domain: "plant.example".into(), path: path.into() }
+    }
+
+    #[test]
+    fn tenant_ids_are_dns_labels() {
+        for bad in ["", "-acme", "acme-", "Acme", "acme/../globex", "a.b", &"x".repeat(64)] {
+            assert!(TenantId::parse(bad).is_err(), "{:?}", bad);
+        }
+        assert_eq!(TenantId::parse("acme-2").unwrap().as_str(), "acme-2");
+    }
+
+    #[test]
+    fn only_tenant_workloads_in_the_trust_domain_are_attributed() {
+        let foreign = SpiffeId { trust_domain: "evil.example".into(), path: "/tenant/acme/provider/flow".into() };
+        assert!(attribute(&foreign, "plant.example").is_err());
+        for path in ["/tenant/acme/provider/", "/tenant/acme/flow", "/tenant/../provider/flow", "/ops/acme/provider/flow", "/tenant/acme/provider/flow/x"] {
+            assert!(attribute(&peer(path), "plant.example").is_err(), "{}", path);
+        }
+        let (id, source) = attribute(&peer("/tenant/acme/condition/cyber_clear"), "plant.example").unwrap();
+        assert_eq!((id.as_str(), source), ("acme", Source::Condition("cyber_clear".into())));
+    }
+
+    #[test]
+    fn a_push_reaches_only_its_own_tenant() {
+        let mut registry = registry();
+        let (acme, globex) = (TenantId::parse("acme").unwrap(), TenantId::parse("globex").unwrap());
+        registry.ingest(&peer("/tenant/acme/provider/flow"), 1, now_ms(), 1, 0.2).unwrap();
+        assert!(registry.tenant(&acme).unwrap().latest[0].is_some());
+        assert!(registry.tenant(&globex).unwrap().latest.iter().all(Option::is_none));
+        // acme has no temperature provider, even though globex does.
+        assert!(registry.ingest(&peer("/tenant/acme/provider/temperature"), 2, now_ms(), 2, 0.9).is_err());
+        assert!(registry.ingest(&peer("/tenant/initech/provider/flow"), 1, now_ms(), 1, 0.9).unwrap_err().contains("unknown tenant"));
+        assert_eq!(registry.tenant(&acme).unwrap().rejected, 1);
+        assert_eq!(registry.tenant(&globex).unwrap().rejected, 0);
+    }
+
+    #[test]
+    fn rejects_replays_and_out_of_range_scores() {
+        let mut registry = registry();
+        let flow = peer("/tenant/acme/provider/flow");
+        registry.ingest(&flow, 5, now_ms(), 10, 0.99).unwrap();
+        assert!(registry.ingest(&flow, 5, now_ms(), 11, 0.99).is_err());
+        assert!(registry.ingest(&flow, 6, now_ms(), 10, 0.99).is_err());
+        assert!(registry.ingest(&flow, 7, now_ms() - 60_000, 12, 0.99).is_err());
+        assert!(registry.ingest(&flow, 8, now_ms(), 13, 1.5).unwrap_err().contains("outside [0, 1]"));
+        assert!(registry.ingest(&flow, 9, now_ms(), 14, f64::NAN).is_err());
+        let acme = registry.tenant(&TenantId::parse("acme").unwrap()).unwrap();
+        assert_eq!((acme.accepted, acme.rejected), (1, 5));
+    }
+
+    #[test]
+    fn missing_condition_or_stale_scores_never_go() {
+        let mut registry = registry();
+        let acme = TenantId::parse("acme").unwrap();
+        for (seq, provider) in [(1, "flow"), (2, "pressure")] {
+            registry.ingest(&peer(&format!("/tenant/acme/provider/{}", provider)), seq, now_ms(), seq, 1.0).unwrap();
+        }
+        let clock = ClockSyncMonitor::new(DEFAULT_CLOCK_LIMITS).status();
+        let eval = registry.evaluate(&acme, &clock).unwrap();
+        assert!(!eval.ch);
+        assert_ne!(eval.decision, harmony::Decision::GO);
+        registry.ingest(&peer("/tenant/acme/condition/cyber_clear"), 3, now_ms(), 3, 1.0).unwrap();
+        assert_eq!(registry.evaluate(&acme, &clock).unwrap().decision, harmony::Decision::GO);
+        assert_eq!(registry.evaluate(&TenantId::parse("globex").unwrap(), &clock).unwrap().decision, harmony::Decision::HALT);
+    }
+
+    #[test]
+    fn storage_stays_under_the_tenant_directory() {
+        let registry = registry();
+        let acme = TenantId::parse("acme").unwrap();
+        for name in ["", "../globex/catalog", "a/b", ".hidden", "..\\x"] {
+            assert!(registry.storage_path(&acme, name).is_err(), "{:?}", name);
+        }
+        assert_eq!(registry.storage_path(&acme, "2025.log").unwrap(), Path::new("/var/lib/harmony/acme/catalog/2025.log"));
+    }
+}
@@ -0,0 +1,129 @@
+//! Units.rs - Telemetry units, dimensions and conversion (forbid unsafe)
+//!
+//! A metric is defined in one unit (containment pressure in kPa, humidity as a ratio) and every
+//! source feeding it declares the unit it publishes in. Conversion::between checks the two share
+//! a dimension when the pipeline is configured, so a psi gauge bound to a kPa metric is scaled
+//! on the way in and a degC probe bound to a pressure metric is refused before it reaches mu.
+//! Conversions are affine (scale then offset) so temperatures convert exactly.
+#![forbid(unsafe_code)]
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dimension {
+    Pressure,
+    Temperature,
+    Fraction,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Pa,
+    KPa,
+    MPa,
+    Bar,
+    Psi,
+    Kelvin,
+    Celsius,
+    Fahrenheit,
+    Ratio,
+    Percent,
+    Ppm,
+}
+
+impl Unit {
+    pub fn parse(s: &str) -> Result<Unit, String> {
+        Ok(match s.trim() {
+            "Pa" => Unit::Pa,
+            "kPa" => Unit::KPa,
+            "MPa" => Unit::MPa,
+            "bar" => Unit::Bar,
+            "psi" => Unit::Psi,
+            "K" => Unit::Kelvin,
+            "degC" => Unit::Celsius,
+            "degF" => Unit::Fahrenheit,
+            "ratio" => Unit::Ratio,
+            "%" => Unit::Percent,
+            "ppm" => Unit::Ppm,
+            other => return Err(format!("unknown unit {:?}", other)),
+        })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Unit::Pa => "Pa",
+            Unit::KPa => "kPa",
+            Unit::MPa => "MPa",
+            Unit::Bar => "bar",
+            Unit::Psi => "psi",
+            Unit::Kelvin => "K",
+            Unit::Celsius => "degC",
+            Unit::Fahrenheit => "degF",
+            Unit::Ratio => "ratio",
+            Unit::Percent => "%",
+            Unit::Ppm => "ppm",
+        }
+    }
+
+    pub fn dimension(self) -> Dimension {
+        match self {
+            Unit::Pa | Unit::KPa | Unit::MPa | Unit::Bar | Unit::Psi => Dimension::Pressure,
+            Unit::Kelvin | Unit::Celsius | Unit::Fahrenheit => Dimension::Temperature,
+            Unit::Ratio | Unit::Percent | Unit::Ppm => Dimension::Fraction,
+        }
+    }
+
+    // (scale, offset) taking a value in this unit to the dimension's base unit (Pa, K, ratio).
+    fn to_base(self) -> (f64, f64) {
+        match self {
+            Unit::Pa => (1.0, 0.0),
+            Unit::KPa => (1e3, 0.0),
+            Unit::MPa => (1e6, 0.0),
+            Unit::Bar => (1e5, 0.0),
+            Unit::Psi => (6_894.757_293_168_361, 0.0),
+            Unit::Kelvin => (1.0, 0.0),
+            Unit::Celsius => (1.0, 273.15),
+            Unit::Fahrenheit => (5.0 / 9.0, 273.15 - 32.0 * 5.0 / 9.0),
+            Unit::Ratio => (1.0, 0.0),
+            Unit::Percent => (1e-2, 0.0),
+            Unit::Ppm => (1e-6, 0.0),
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Conversion {
+    scale: f64,
+    offset: f64,
+}
+
+impl Conversion {
+    pub const IDENTITY: Conversion = Conversion { scale: 1.0, offset: 0.0 };
+
+    // Refused across dimensions.
+    pub fn between(from: Unit, to: Unit) -> Result<Conversion, String> {
+        if from.dimension() != to.dimension() {
+            return Err(format!("cannot convert {} ({:?}) to {} ({:?})", from, from.dimension(), to, to.dimension()));
+        }
+        if from == to {
+            return Ok(Conversion::IDENTITY);
+        }
+        let (fs, fo) = from.to_base();
+        let (ts, to_off) = to.to_base();
+        Ok(Conversion { scale: fs / ts, offset: (fo - to_off) / ts })
+    }
+
+    pub fn apply(&self, value: f64) -> f64 {
+        value * self.scale + self.offset
+    }
+
+    // For deltas (counters): a difference of 1 degC is 1 K, not 274.15.
+    pub fn apply_delta(&self, delta: f64) -> f64 {
+        delta * self.scale
+    }
+}
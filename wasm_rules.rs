@@ -0,0 +1,162 @@
+//! Wasm_Rules.rs - Site-supplied score transforms and conditions as sandboxed WASM modules (forbid unsafe)
+//!
+//! Site engineers extend a monitor without forking the crate: a rule is a WebAssembly module run
+//! in wasmi, a pure-Rust interpreter, so `forbid(unsafe_code)` holds here too. A module sees only
+//! this cycle's scores, through two imports,
+//!   env.channels() -> i32                         env.score(channel: i32) -> f64
+//! and exports either or both of
+//!   transform(channel: i32, score: f64) -> f64    called for each channel in turn
+//!   condition() -> i32                            non-zero holds; sees the transformed scores
+//! Every call starts on a fresh fuel allowance and the instance's memory is capped, so a looping
+//! or allocating module traps instead of stalling the cycle. A rule may only tighten: a transform
+//! result is clamped to [0, the score it was given], so it can lower a channel but never raise
+//! one, and a condition only adds its own. A trap or a non-finite result fails the rule's
+//! condition at its severity and leaves that channel at MIN_SCORE for the cycle.
+//!
+//! HARMONY_WASM_RULES lists rules as `<name>=<severity>:<path>`, comma-separated.
+#![forbid(unsafe_code)]
+use std::fmt::Write;
+use std::fs;
+
+use sha2::{Digest, Sha256};
+use wasmi::{Caller, Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc, WasmParams, WasmResults};
+
+use crate::core::harmony::{Conditions, Severity, MIN_SCORE};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RuleLimits {
+    // Per call: each transform() and condition() invocation.
+    pub fuel: u64,
+    pub max_memory_bytes: usize,
+    pub max_module_bytes: usize,
+}
+
+pub const DEFAULT_RULE_LIMITS: RuleLimits = RuleLimits { fuel: 100_000, max_memory_bytes: 1 << 20, max_module_bytes: 1 << 20 };
+
+struct Host {
+    scores: Vec<f64>,
+    limits: StoreLimits,
+}
+
+pub struct WasmRule {
+    name: String,
+    severity: Severity,
+    sha256: String,
+    fuel: u64,
+    store: Store<Host>,
+    transform: Option<TypedFunc<(i32, f64), f64>>,
+    condition: Option<TypedFunc<(), i32>>,
+    held: bool,
+    // The first failure this cycle, if any.
+    fault: Option<String>,
+}
+
+impl WasmRule {
+    pub fn load(name: &str, severity: Severity, module: &[u8], limits: RuleLimits) -> Result<Self, String> {
+        if module.len() > limits.max_module_bytes {
+            return Err(format!("{}: module is {} bytes, limit {}", name, module.len(), limits.max_module_bytes));
+        }
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let compiled = Module::new(&engine, module).map_err(|e| format!("{}: {}", name, e))?;
+        let limiter = StoreLimitsBuilder::new().memory_size(limits.max_memory_bytes).instances(1).memories(1).tables(1).build();
+        let mut store = Store::new(&engine, Host { scores: Vec::new(), limits: limiter });
+        store.limiter(|host| &mut host.limits);
+        store.set_fuel(limits.fuel).map_err(|e| format!("{}: {}", name, e))?;
+        let mut linker = <Linker<Host>>::new(&engine);
+        linker
+            .func_wrap("env", "channels", |caller: Caller<'_, Host>| -> i32 { caller.data().scores.len() as i32 })
+            .map_err(|e| format!("{}: {}", name, e))?;
+        linker
+            .func_wrap("env", "score", |caller: Caller<'_, Host>, channel: i32| -> f64 {
+                usize::try_from(channel).ok().and_then(|c| caller.data().scores.get(c).copied()).unwrap_or(f64::NAN)
+            })
+            .map_err(|e| format!("{}: {}", name, e))?;
+        let pre = linker.instantiate(&mut store, &compiled).map_err(|e| format!("{}: {}", name, e))?;
+        let instance = pre.start(&mut store).map_err(|e| format!("{}: start: {}", name, e))?;
+        let transform = instance.get_typed_func::<(i32, f64), f64>(&store, "transform").ok();
+        let condition = instance.get_typed_func::<(), i32>(&store, "condition").ok();
+        if transform.is_none() && condition.is_none() {
+            return Err(format!("{}: exports neither transform(i32, f64) -> f64 nor condition() -> i32", name));
+        }
+        let sha256 = Sha256::digest(module).iter().fold(String::new(), |mut s, b| {
+            let _ = write!(s, "{:02x}", b);
+            s
+        });
+        Ok(WasmRule { name: name.to_string(), severity, sha256, fuel: limits.fuel, store, transform, condition, held: true, fault: None })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Of the module as loaded, for logs and audit.
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+
+    fn call<P: WasmParams, R: WasmResults>(&mut self, f: &TypedFunc<P, R>, params: P) -> Result<R, String> {
+        self.store.set_fuel(self.fuel).map_err(|e| e.to_string())?;
+        f.call(&mut self.store, params).map_err(|e| e.to_string())
+    }
+
+    // Runs the transform over `scores` in place, then the condition. Once per cycle, after the
+    // host's own filters and before evaluation.
+    pub fn apply(&mut self, scores: &mut [f64]) {
+        self.fault = None;
+        self.store.data_mut().scores.clear();
+        self.store.data_mut().scores.extend_from_slice(scores);
+        if let Some(f) = self.transform {
+            for (i, score) in scores.iter_mut().enumerate().filter(|(_, s)| s.is_finite()) {
+                match self.call(&f, (i as i32, *score)) {
+                    Ok(v) if v.is_finite() => *score = v.max(0.0).min(*score),
+                    result => {
+                        let why = result.map_or_else(|e| format!("transform trapped on channel {}: {}", i, e), |v| format!("transform returned {} for channel {}", v, i));
+                        self.fault.get_or_insert(why);
+                        *score = MIN_SCORE;
+                    }
+                }
+            }
+            self.store.data_mut().scores.clear();
+            self.store.data_mut().scores.extend_from_slice(scores);
+        }
+        self.held = match self.condition {
+            Some(f) => match self.call(&f, ()) {
+                Ok(r) => r != 0,
+                Err(e) => {
+                    self.fault.get_or_insert(format!("condition trapped: {}", e));
+                    false
+                }
+            },
+            None => true,
+        };
+    }
+
+    // Records `<name>` from the last apply().
+    pub fn record(&self, conditions: &mut Conditions) {
+        let detail = self.fault.clone().or_else(|| (!self.held).then(|| "condition does not hold".to_string()));
+        conditions.record_with(&self.name, self.severity, detail.is_none(), detail);
+    }
+}
+
+// `<name>=<severity>:<path>[,...]`, as in HARMONY_WASM_RULES.
+pub fn load_rules(specs: &str, limits: RuleLimits) -> Result<Vec<WasmRule>, String> {
+    specs
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|spec| {
+            let (name, rest) = spec.split_once('=').ok_or_else(|| format!("wasm rule {:?}: expected <name>=<severity>:<path>", spec))?;
+            let (sev, path) = rest.split_once(':').ok_or_else(|| format!("wasm rule {}: expected <severity>:<path>", name))?;
+            let severity = match sev.trim() {
+                "critical" => Severity::Critical,
+                "major" => Severity::Major,
+                "advisory" => Severity::Advisory,
+                other => return Err(format!("wasm rule {}: unknown severity {:?}", name, other)),
+            };
+            let module = fs::read(path.trim()).map_err(|e| format!("wasm rule {}: {}: {}", name, path, e))?;
+            WasmRule::load(name.trim(), severity, &module, limits)
+        })
+        .collect()
+}
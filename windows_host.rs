@@ -0,0 +1,124 @@
+//! Windows_Host.rs - Windows service registration, Event Log output and named-pipe gate (forbid unsafe)
+//!
+//! `<engine> install` registers the service (auto-start, runs `<engine> service`), `uninstall`
+//! removes it. While running, decisions go to the Application Event Log under SERVICE_NAME and
+//! the latest decision line is served to local clients on GATE_PIPE. Declared `#[cfg(windows)]`.
+#![forbid(unsafe_code)]
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use tokio::io::AsyncWriteExt;
+use tokio::net::windows::named_pipe::ServerOptions;
+use windows_service::service::{
+    ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode, ServiceInfo,
+    ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::service_dispatcher;
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use windows_service::define_windows_service;
+
+pub const SERVICE_NAME: &str = "HarmonyFinance";
+pub const GATE_PIPE: &str = r"\\.\pipe\harmony-finance-gate";
+
+static STOP: AtomicBool = AtomicBool::new(false);
+static ENGINE: OnceLock<fn()> = OnceLock::new();
+
+// Engine loops poll this and return once the SCM asks the service to stop.
+pub fn stop_requested() -> bool {
+    STOP.load(Ordering::SeqCst)
+}
+
+pub fn install_service(exe: &Path) -> windows_service::Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CREATE_SERVICE)?;
+    let info = ServiceInfo {
+        name: OsString::from(SERVICE_NAME),
+        display_name: OsString::from("Harmony Finance Gate"),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: exe.to_path_buf(),
+        launch_arguments: vec![OsString::from("service")],
+        dependencies: vec![],
+        account_name: None,
+        account_password: None,
+    };
+    manager.create_service(&info, ServiceAccess::QUERY_STATUS)?;
+    Ok(())
+}
+
+pub fn uninstall_service() -> windows_service::Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    manager.open_service(SERVICE_NAME, ServiceAccess::DELETE)?.delete()
+}
+
+pub fn init_event_log() -> Result<(), String> {
+    eventlog::init(SERVICE_NAME, log::Level::Info).map_err(|e| e.to_string())
+}
+
+// HALT is logged as a warning so desk operators can filter on it in Event Viewer.
+pub fn log_decision(line: &str, halt: bool) {
+    if halt {
+        log::warn!("{}", line);
+    } else {
+        log::info!("{}", line);
+    }
+}
+
+// Local gating API: each client connection receives the latest decision line, then is closed.
+// The engine stamps each line when it writes it; a line older than `lease` is served as
+// TX_HALT, so a stalled engine loop never leaves clients acting on its last GO.
+pub async fn serve_gate_pipe(latest: Arc<Mutex<(String, Instant)>>, lease: Duration) -> std::io::Result<()> {
+    let mut server = ServerOptions::new().first_pipe_instance(true).create(GATE_PIPE)?;
+    loop {
+        server.connect().await?;
+        let client = server;
+        server = ServerOptions::new().create(GATE_PIPE)?;
+        let line = match &*latest.lock().unwrap() {
+            (line, at) if at.elapsed() <= lease => line.clone(),
+            (_, at) => format!("TX_HALT stale={}ms", at.elapsed().as_millis()),
+        };
+        tokio::spawn(async move {
+            let mut client = client;
+            let _ = client.write_all(line.as_bytes()).await;
+            let _ = client.write_all(b"\n").await;
+        });
+    }
+}
+
+define_windows_service!(ffi_service_main, service_main);
+
+fn service_main(_args: Vec<OsString>) {
+    let handler = move |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            STOP.store(true, Ordering::SeqCst);
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    };
+    let Ok(status) = service_control_handler::register(SERVICE_NAME, handler) else { return };
+    let report = |state, accept| ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state: state,
+        controls_accepted: accept,
+        exit_code: ServiceExitCode::Win32(0),
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    };
+    let _ = status.set_service_status(report(ServiceState::Running, ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN));
+    if let Some(engine) = ENGINE.get() {
+        engine();
+    }
+    let _ = status.set_service_status(report(ServiceState::Stopped, ServiceControlAccept::empty()));
+}
+
+// Blocks until the service stops; must be called from the process main thread.
+pub fn run_service(engine: fn()) -> windows_service::Result<()> {
+    let _ = ENGINE.set(engine);
+    service_dispatcher::start(OsStr::new(SERVICE_NAME), ffi_service_main)
+}